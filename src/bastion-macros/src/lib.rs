@@ -0,0 +1,169 @@
+//! Bastion Macros
+//!
+//! Derive macros for Bastion, the highly-available, fault-tolerant, async
+//! communication oriented executor. They are re-exported by the `bastion`
+//! crate and shouldn't be depended on directly.
+//!
+
+#![doc(
+    html_logo_url = "https://raw.githubusercontent.com/bastion-rs/bastion/master/img/bastion-logo.png"
+)]
+// Force missing implementations
+#![warn(missing_docs)]
+#![warn(missing_debug_implementations)]
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, Ident};
+
+/// Derives the routing of an enum sent as a message to the methods
+/// of a handler trait.
+///
+/// For an enum named `Cache`, this generates a `CacheHandler` trait
+/// with an associated `Output` type and one `on_<variant>` method per
+/// variant (in snake case), taking the fields of the variant and the
+/// `MessageOrigin` of the message. It also generates a
+/// `Cache::handle(msg, &mut handler)` function which calls the
+/// method matching the variant of the received message, or gives
+/// the message back if it isn't a `Cache`.
+///
+/// As every method of the trait has to be implemented, adding a
+/// variant to the enum fails to compile until it is handled.
+#[proc_macro_derive(BastionMessage)]
+pub fn derive_bastion_message(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "BastionMessage can only be derived for enums",
+            ))
+        }
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "BastionMessage can't be derived for generic enums",
+        ));
+    }
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let handler = format_ident!("{}Handler", name);
+
+    let mut methods = Vec::new();
+    let mut arms = Vec::new();
+    for variant in &data.variants {
+        let variant_name = &variant.ident;
+        let method = Ident::new(
+            &format!("on_{}", snake_case(&variant_name.to_string())),
+            Span::call_site(),
+        );
+
+        let (params, pattern) = match &variant.fields {
+            Fields::Named(fields) => {
+                let params: Vec<_> = fields
+                    .named
+                    .iter()
+                    .map(|field| (field.ident.clone().unwrap(), &field.ty))
+                    .collect();
+                let idents = params.iter().map(|(ident, _)| ident);
+                let pattern = quote!(#name::#variant_name { #(#idents),* });
+                (params, pattern)
+            }
+            Fields::Unnamed(fields) => {
+                let params: Vec<_> = fields
+                    .unnamed
+                    .iter()
+                    .enumerate()
+                    .map(|(index, field)| (format_ident!("field{}", index), &field.ty))
+                    .collect();
+                let idents = params.iter().map(|(ident, _)| ident);
+                let pattern = quote!(#name::#variant_name(#(#idents),*));
+                (params, pattern)
+            }
+            Fields::Unit => (Vec::new(), quote!(#name::#variant_name)),
+        };
+
+        let idents: Vec<_> = params.iter().map(|(ident, _)| ident).collect();
+        let types = params.iter().map(|(_, ty)| ty);
+        let doc = format!("Handles a [`{}::{}`] message.", name, variant_name);
+        methods.push(quote! {
+            #[doc = #doc]
+            fn #method(
+                &mut self,
+                #(#idents: #types,)*
+                origin: ::bastion::message::MessageOrigin,
+            ) -> Self::Output;
+        });
+        arms.push(quote! {
+            #pattern => handler.#method(#(#idents,)* origin),
+        });
+    }
+
+    let handler_doc = format!(
+        "Handles the variants of [`{}`], which are routed to its methods by [`{}::handle`].",
+        name, name
+    );
+
+    Ok(quote! {
+        #[doc = #handler_doc]
+        #vis trait #handler {
+            /// The value returned by the methods handling the messages.
+            type Output;
+
+            #(#methods)*
+        }
+
+        impl #name {
+            /// Calls the method of `handler` matching the variant of
+            /// the given message, or gives the message back if it
+            /// isn't of this type.
+            #[allow(unused_variables)]
+            #vis fn handle<H: #handler>(
+                msg: ::bastion::envelope::SignedMessage,
+                handler: &mut H,
+            ) -> ::std::result::Result<H::Output, ::bastion::envelope::SignedMessage> {
+                let (msg, origin) = msg.into_routed::<Self>()?;
+                Ok(match msg {
+                    #(#arms)*
+                })
+            }
+        }
+    })
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (index, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if index > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snake_case() {
+        assert_eq!(snake_case("Get"), "get");
+        assert_eq!(snake_case("PutEntry"), "put_entry");
+    }
+}
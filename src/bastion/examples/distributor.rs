@@ -147,7 +147,7 @@ fn run() -> AnyResult<()> {
     let attendees = Distributor::named("attendees");
 
     // Enthusiast -> Ask one of the staff members "when is the conference going to happen ?"
-    let reply: Result<String, SendError> = run!(async {
+    let reply: Result<String, RequestError> = run!(async {
         staff
             .request("when is the next conference going to happen?")
             .await
@@ -33,7 +33,7 @@ fn main() {
     let say_hi = Distributor::named("say_hi");
 
     run!(async {
-        let answer: Result<&str, SendError> =
+        let answer: Result<&str, RequestError> =
             say_hi.request("hi!").await.expect("Couldn't send request");
 
         println!("{}", answer.expect("Couldn't receive answer"))
@@ -0,0 +1,116 @@
+//!
+//! Registry of the running children groups by identifier, allowing
+//! to resolve a [`ChildAddr`] into a [`ChildRef`] (see
+//! [`Bastion::resolve`]).
+//!
+//! Every children group registers a fresh [`ChildrenRef`] each time
+//! it handles a message, so that the resolved [`ChildRef`]s reference
+//! the current run of the restarted elements.
+//!
+//! [`ChildAddr`]: crate::child_ref::ChildAddr
+//! [`ChildRef`]: crate::child_ref::ChildRef
+//! [`ChildrenRef`]: crate::children_ref::ChildrenRef
+//! [`Bastion::resolve`]: crate::Bastion::resolve
+use crate::child_ref::{ChildAddr, ChildRef};
+use crate::children_ref::ChildrenRef;
+use crate::context::BastionId;
+use fxhash::FxHashMap;
+use once_cell::sync::Lazy;
+use std::sync::RwLock;
+use tracing::trace;
+
+pub(crate) static ADDRESSES: Lazy<AddressRegistry> = Lazy::new(AddressRegistry::default);
+
+#[derive(Debug, Default)]
+pub(crate) struct AddressRegistry {
+    groups: RwLock<FxHashMap<BastionId, ChildrenRef>>,
+}
+
+impl AddressRegistry {
+    pub(crate) fn register(&self, children: ChildrenRef) {
+        self.groups
+            .write()
+            .unwrap()
+            .insert(children.id().clone(), children);
+    }
+
+    pub(crate) fn remove(&self, id: &BastionId) {
+        trace!("AddressRegistry: Removing Children({}).", id);
+        self.groups.write().unwrap().remove(id);
+    }
+
+    pub(crate) fn get(&self, id: &BastionId) -> Option<ChildrenRef> {
+        self.groups.read().unwrap().get(id).cloned()
+    }
+
+    pub(crate) fn resolve(&self, addr: &ChildAddr) -> Option<ChildRef> {
+        let groups = self.groups.read().unwrap();
+        groups
+            .get(addr.children_id()?)?
+            .elems()
+            .iter()
+            .find(|child| child.id() == addr.id())
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::health::GroupHealth;
+    use crate::path::{BastionPath, BastionPathElement};
+    use crate::results::GroupResults;
+    use futures::channel::mpsc;
+    use std::sync::Arc;
+
+    fn children_ref(id: BastionId, elems: Vec<ChildRef>) -> ChildrenRef {
+        let (sender, _) = mpsc::unbounded();
+        let path = Arc::new(BastionPath::root());
+        let health = Arc::new(GroupHealth::default());
+        let results = Arc::new(GroupResults::default());
+        ChildrenRef::new(id, sender, path, elems, vec![], vec![], health, results)
+    }
+
+    fn child_ref(children_id: &BastionId) -> ChildRef {
+        let (sender, _) = mpsc::unbounded();
+        let path = BastionPath::root()
+            .append(BastionPathElement::Supervisor(BastionId::new()))
+            .unwrap()
+            .append(BastionPathElement::Children(children_id.clone()))
+            .unwrap()
+            .append(BastionPathElement::Child(BastionId::new()))
+            .unwrap();
+        let id = path.id().clone();
+        ChildRef::new(id, sender, "test_name".to_string(), Arc::new(path))
+    }
+
+    #[test]
+    fn test_address_registry_resolves_registered_children() {
+        let registry = AddressRegistry::default();
+        let children_id = BastionId::new();
+        let child = child_ref(&children_id);
+        let addr = child.child_addr();
+
+        assert!(registry.resolve(&addr).is_none());
+
+        registry.register(children_ref(children_id.clone(), vec![child.clone()]));
+        assert_eq!(
+            registry.resolve(&addr).map(|child| child.id().clone()),
+            Some(child.id().clone())
+        );
+
+        registry.remove(&children_id);
+        assert!(registry.resolve(&addr).is_none());
+    }
+
+    #[test]
+    fn test_child_addr_roundtrips_through_json() {
+        let child = child_ref(&BastionId::new());
+        let addr = child.child_addr();
+
+        let json = serde_json::to_string(&addr).unwrap();
+        let parsed: ChildAddr = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, addr);
+        assert_eq!(parsed.id(), child.id());
+    }
+}
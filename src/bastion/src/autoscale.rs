@@ -0,0 +1,156 @@
+//!
+//! Autoscaling of the children groups depending on the amount of
+//! messages waiting in the mailboxes of their elements, set using
+//! [`Children::with_autoscaling`].
+//!
+//! [`Children::with_autoscaling`]: crate::children::Children::with_autoscaling
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Defines when a children group spawns or retires elements, given
+/// to [`Children::with_autoscaling`].
+///
+/// Every check interval (one second by default), the group sums up
+/// the amount of messages waiting in the mailboxes of its elements
+/// (its backlog). It then spawns an element if the backlog is above
+/// the scale-up threshold, or retires one if it is at or below the
+/// scale-down threshold (zero by default), while keeping between
+/// `min` and `max` elements. Once the group scaled, it waits for the
+/// cooldown (30 seconds by default) before scaling again.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use std::time::Duration;
+/// #
+/// let policy = AutoscalePolicy::new(2, 20)
+///     .scale_up_when_backlog_above(1_000)
+///     .scale_down_when_backlog_below(10)
+///     .with_cooldown(Duration::from_secs(10));
+/// ```
+///
+/// [`Children::with_autoscaling`]: crate::children::Children::with_autoscaling
+pub struct AutoscalePolicy {
+    min: usize,
+    max: usize,
+    scale_up_backlog: usize,
+    scale_down_backlog: usize,
+    cooldown: Duration,
+    check_interval: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AutoscaleDecision {
+    ScaleUp,
+    ScaleDown,
+    Keep,
+}
+
+impl AutoscalePolicy {
+    /// Creates a policy keeping between `min` and `max` elements in
+    /// the children group, which scales up when its backlog is
+    /// above 100 messages.
+    ///
+    /// # Arguments
+    ///
+    /// * `min` - The minimum amount of elements of the group.
+    /// * `max` - The maximum amount of elements of the group, which
+    ///     should be greater than or equal to `min`.
+    pub fn new(min: usize, max: usize) -> Self {
+        AutoscalePolicy {
+            min,
+            max,
+            scale_up_backlog: 100,
+            scale_down_backlog: 0,
+            cooldown: Duration::from_secs(30),
+            check_interval: Duration::from_secs(1),
+        }
+    }
+
+    /// Sets the backlog above which an element is spawned.
+    pub fn scale_up_when_backlog_above(mut self, backlog: usize) -> Self {
+        self.scale_up_backlog = backlog;
+        self
+    }
+
+    /// Sets the backlog at or below which an element is retired.
+    pub fn scale_down_when_backlog_below(mut self, backlog: usize) -> Self {
+        self.scale_down_backlog = backlog;
+        self
+    }
+
+    /// Sets how long the group waits after scaling before scaling
+    /// again.
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Sets how often the backlog of the group is checked.
+    pub fn with_check_interval(mut self, check_interval: Duration) -> Self {
+        self.check_interval = check_interval;
+        self
+    }
+
+    /// Returns the minimum amount of elements of the group.
+    pub fn min(&self) -> usize {
+        self.min
+    }
+
+    /// Returns the maximum amount of elements of the group.
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Returns how long the group waits after scaling before
+    /// scaling again.
+    pub fn cooldown(&self) -> Duration {
+        self.cooldown
+    }
+
+    /// Returns how often the backlog of the group is checked.
+    pub fn check_interval(&self) -> Duration {
+        self.check_interval
+    }
+
+    pub(crate) fn decide(&self, elems: usize, backlog: usize) -> AutoscaleDecision {
+        if elems < self.min {
+            AutoscaleDecision::ScaleUp
+        } else if elems > self.max {
+            AutoscaleDecision::ScaleDown
+        } else if backlog > self.scale_up_backlog && elems < self.max {
+            AutoscaleDecision::ScaleUp
+        } else if backlog <= self.scale_down_backlog && elems > self.min {
+            AutoscaleDecision::ScaleDown
+        } else {
+            AutoscaleDecision::Keep
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_autoscale_policy_follows_backlog() {
+        let policy = AutoscalePolicy::new(2, 4)
+            .scale_up_when_backlog_above(100)
+            .scale_down_when_backlog_below(10);
+
+        assert_eq!(policy.decide(2, 500), AutoscaleDecision::ScaleUp);
+        assert_eq!(policy.decide(3, 50), AutoscaleDecision::Keep);
+        assert_eq!(policy.decide(3, 10), AutoscaleDecision::ScaleDown);
+    }
+
+    #[test]
+    fn test_autoscale_policy_keeps_bounds() {
+        let policy = AutoscalePolicy::new(2, 4);
+
+        assert_eq!(policy.decide(1, 0), AutoscaleDecision::ScaleUp);
+        assert_eq!(policy.decide(5, 1_000), AutoscaleDecision::ScaleDown);
+        assert_eq!(policy.decide(4, 1_000), AutoscaleDecision::Keep);
+        assert_eq!(policy.decide(2, 0), AutoscaleDecision::Keep);
+    }
+}
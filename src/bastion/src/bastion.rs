@@ -1,21 +1,49 @@
+use crate::addresses::ADDRESSES;
+use crate::blocking_pool::BlockingPool;
 use crate::broadcast::{Broadcast, Parent};
+use crate::child_ref::{ChildAddr, ChildRef};
 use crate::children::Children;
 use crate::children_ref::ChildrenRef;
+#[cfg(feature = "remote")]
+use crate::cluster::Cluster;
 use crate::config::Config;
 use crate::context::{BastionContext, BastionId};
+#[cfg(feature = "remote")]
+use crate::dead_letters::DeadLetters;
+use crate::distributor::Batch;
 use crate::envelope::Envelope;
+use crate::errors::SendError;
+use crate::events::SystemEvent;
+use crate::executor::{self, BlockingPoolConfig, BlockingPoolStats, Executor};
+use crate::health::{self, SystemHealth};
+use crate::logging::LogPolicy;
 use crate::message::{BastionMessage, Message};
+use crate::metrics::{self, RuntimeMetrics};
 use crate::path::BastionPathElement;
-use crate::supervisor::{Supervisor, SupervisorRef};
-use crate::system::SYSTEM;
+#[cfg(feature = "prometheus")]
+use crate::prometheus;
+#[cfg(feature = "remote")]
+use crate::remote::{self, RemoteConfig};
+use crate::supervisor::{FatalReport, Supervisor, SupervisorRef};
+use crate::system::{self, GlobalSystem, SYSTEM};
+use crate::testing::TestRuntime;
+use crate::tree::SupervisionTree;
 
 use core::future::Future;
-use tracing::{debug, trace};
+use futures::Stream;
+use tracing::{debug, trace, warn};
 
 use std::fmt::{self, Debug, Formatter};
+#[cfg(any(feature = "prometheus", feature = "remote"))]
+use std::io;
+#[cfg(any(feature = "prometheus", feature = "remote"))]
+use std::net::{SocketAddr, ToSocketAddrs};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
 distributed_api! {
-    use std::sync::Arc;
     use crate::distributed::*;
     use artillery_core::cluster::ap::*;
 }
@@ -24,6 +52,10 @@ distributed_api! {
 /// start, stop and kill it and to create new supervisors and top-level
 /// children groups.
 ///
+/// Its functions act on the default system of the process, apart
+/// from when they are called by an element or a task of a
+/// [`BastionSystem`], in which case they act on that system.
+///
 /// # Example
 ///
 /// ```rust
@@ -253,14 +285,243 @@ impl Bastion {
     /// ```
     pub fn init_with(config: Config) {
         debug!("Bastion: Initializing with config: {:?}", config);
-        if config.backtraces().is_hide() {
-            debug!("Bastion: Hiding backtraces.");
-            std::panic::set_hook(Box::new(|_| ()));
-        }
+        configure(&config);
 
         let _ = &SYSTEM;
     }
 
+    /// Initializes the system if it hasn't already been done, using
+    /// the default [`Config`] and running every task of the system
+    /// (the supervisors, children, `spawn!`ed and `blocking!` tasks)
+    /// on the specified executor instead of bastion's own.
+    ///
+    /// This has to be called before [`Bastion::init`],
+    /// [`Bastion::init_with`] or any of bastion's features are used,
+    /// otherwise the executor is ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - The runtime the tasks of the system will be
+    ///     run on.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// use futures::future::BoxFuture;
+    /// use std::time::Duration;
+    ///
+    /// #[derive(Debug)]
+    /// struct ThreadPerTask;
+    ///
+    /// impl Executor for ThreadPerTask {
+    ///     fn spawn(&self, task: BoxFuture<'static, ()>) {
+    ///         std::thread::spawn(move || futures::executor::block_on(task));
+    ///     }
+    ///
+    ///     fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>) {
+    ///         std::thread::spawn(task);
+    ///     }
+    ///
+    ///     fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+    ///         Box::pin(async move { futures_timer::Delay::new(duration).await })
+    ///     }
+    /// }
+    ///
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// Bastion::init_with_executor(ThreadPerTask);
+    ///
+    /// // You can now use bastion...
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn init_with_executor(executor: impl Executor) {
+        if !executor::set_executor(Arc::new(executor)) {
+            warn!("Bastion: An executor was already set, ignoring the new one.");
+        }
+
+        Bastion::init()
+    }
+
+    /// Initializes the system if it hasn't already been done, using
+    /// the default [`Config`] and a deterministic, single-threaded
+    /// runtime with virtual time, and returns a handle to this
+    /// runtime (see the [`testing`] module).
+    ///
+    /// This allows to test elements, retries and timeouts instantly
+    /// and reproducibly, the tasks only running when the runtime is
+    /// driven (e.g. using [`TestRuntime::advance`] or [`run!`]).
+    ///
+    /// This has to be called before any of bastion's features are
+    /// used, and can be called again (e.g. by every test of a test
+    /// binary) to get the same runtime.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// let runtime = Bastion::init_test();
+    ///
+    /// let children = Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| async move {
+    ///         // Times out after one virtual second...
+    ///         let timeout = ctx.recv_timeout(Duration::from_secs(1)).await;
+    ///         assert!(timeout.is_err());
+    ///         Ok(())
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    ///
+    /// Bastion::start();
+    /// // ...which elapses instantly.
+    /// runtime.advance(Duration::from_secs(1));
+    ///
+    /// Bastion::stop();
+    /// Bastion::block_until_stopped();
+    /// ```
+    ///
+    /// [`testing`]: crate::testing
+    /// [`TestRuntime::advance`]: crate::testing::TestRuntime::advance
+    /// [`run!`]: crate::run
+    pub fn init_test() -> TestRuntime {
+        let runtime = TestRuntime::install();
+        Bastion::init();
+        runtime
+    }
+
+    /// Starts the dedicated pool of threads running the blocking
+    /// tasks (see [`blocking!`], [`Children::with_exec_blocking`] and
+    /// [`BastionContext::spawn_blocking`]) with the specified size
+    /// limits.
+    ///
+    /// This has to be called before any blocking task is spawned,
+    /// and only once; the tasks are otherwise run on the default
+    /// blocking pool of the executor, which doesn't have size limits
+    /// nor [stats](Bastion::blocking_pool_stats).
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The size limits of the pool.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// Bastion::blocking_pool_config(BlockingPoolConfig {
+    ///     min: 2,
+    ///     max: 16,
+    ///     keep_alive: Duration::from_secs(30),
+    /// });
+    /// Bastion::init();
+    ///
+    /// let stats = Bastion::blocking_pool_stats().unwrap();
+    /// assert_eq!(stats.threads(), 2);
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`blocking!`]: crate::blocking
+    /// [`Children::with_exec_blocking`]: crate::children::Children::with_exec_blocking
+    /// [`BastionContext::spawn_blocking`]: crate::context::BastionContext::spawn_blocking
+    pub fn blocking_pool_config(config: BlockingPoolConfig) {
+        if !BlockingPool::init(config) {
+            warn!(
+                "Bastion: The blocking pool was already started, ignoring {:?}.",
+                config
+            );
+        }
+    }
+
+    /// Sets the closure called with the message of the panic (if
+    /// it was created with a string) of the tasks spawned using
+    /// [`spawn_handle!`] that panicked, replacing the previous one.
+    ///
+    /// # Arguments
+    ///
+    /// * `hook` - The closure called when a task panics.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::on_task_panic(|message: Option<&str>| {
+    ///     eprintln!("A task panicked: {:?}", message);
+    /// });
+    ///
+    /// let handle = spawn_handle! {
+    ///     panic!("oops");
+    /// };
+    /// assert!(run!(handle).is_err());
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`spawn_handle!`]: crate::spawn_handle
+    pub fn on_task_panic<F>(hook: F)
+    where
+        F: Fn(Option<&str>) + Send + Sync + 'static,
+    {
+        debug!("Bastion: Setting task panic hook.");
+        executor::set_task_panic_hook(Arc::new(hook));
+    }
+
+    /// Returns the amount of tasks queued and threads running in the
+    /// pool started using [`Bastion::blocking_pool_config`], or
+    /// `None` if it wasn't.
+    pub fn blocking_pool_stats() -> Option<BlockingPoolStats> {
+        BlockingPool::get().map(BlockingPool::stats)
+    }
+
     /// Creates a new [`Supervisor`], passes it through the specified
     /// `init` closure and then sends it to the system for it to
     /// start supervising children.
@@ -320,9 +581,10 @@ impl Bastion {
 
         debug!("Bastion: Deploying Supervisor({}).", supervisor.id());
         let msg = BastionMessage::deploy_supervisor(supervisor);
-        let envelope = Envelope::new(msg, SYSTEM.path().clone(), SYSTEM.sender().clone());
+        let global = system::current();
+        let envelope = Envelope::new(msg, global.path().clone(), global.sender().clone());
         trace!("Bastion: Sending envelope: {:?}", envelope);
-        SYSTEM.sender().unbounded_send(envelope).map_err(|_| ())?;
+        global.sender().unbounded_send(envelope).map_err(|_| ())?;
 
         Ok(supervisor_ref)
     }
@@ -388,7 +650,7 @@ impl Bastion {
         C: FnOnce(Children) -> Children,
     {
         debug!("Bastion: Creating children group.");
-        SYSTEM.supervisor().children(init)
+        system::current().supervisor().children(init)
     }
 
     /// Creates a new [`Children`] which will have the given closure
@@ -441,28 +703,21 @@ impl Bastion {
     {
         Bastion::children(|ch| ch.with_redundancy(1).with_exec(action))
     }
-    distributed_api! {
-        // FIXME!
-        #[allow(missing_docs)]
-        pub fn distributed<I, F>(cluster_config: &'static ArtilleryAPClusterConfig, action: I) -> Result<ChildrenRef, ()>
-        where
-            I: Fn(Arc<DistributedContext>) -> F + Send + Sync + 'static,
-            F: Future<Output = Result<(), ()>> + Send + 'static,
-        {
-            cluster_actor(cluster_config, action)
-        }
-    }
 
-    /// Sends a message to the system which will then send it to all
-    /// the root-level supervisors and their supervised children and
-    /// supervisors, etc.
+    /// Sends a batch of messages to the recipients of several
+    /// [`Distributor`]s, making sure that either all of them are
+    /// enqueued or none of them are.
     ///
-    /// This method returns `()` if it succeeded, or `Err(msg)`
-    /// otherwise.
+    /// Every recipient is resolved and checked before any message is
+    /// sent, so coordinated updates across multiple groups can't
+    /// partially apply when one of them is unavailable.
+    ///
+    /// This method returns `()` if all the messages were sent, or the
+    /// [`SendError`] explaining why no message was sent otherwise.
     ///
     /// # Arguments
     ///
-    /// * `msg` - The message to send.
+    /// * `fill` - The closure adding the messages to the [`Batch`].
     ///
     /// # Example
     ///
@@ -481,54 +736,47 @@ impl Bastion {
     /// # }
     /// #
     /// # fn run() {
-    ///     # Bastion::init();
-    ///     #
-    /// let msg = "A message containing data.";
-    /// Bastion::broadcast(msg).expect("Couldn't send the message.");
+    /// # Bastion::init();
+    /// #
+    /// let accounts = Distributor::named("accounts");
+    /// let ledger = Distributor::named("ledger");
     ///
-    ///     # Bastion::children(|children| {
-    ///         # children.with_exec(|ctx: BastionContext| {
-    ///             # async move {
-    /// // And then in every children groups's elements' future...
-    /// msg! { ctx.recv().await?,
-    ///     ref msg: &'static str => {
-    ///         assert_eq!(msg, &"A message containing data.");
-    ///     };
-    ///     // We are only broadcasting a `&'static str` in this
-    ///     // example, so we know that this won't happen...
-    ///     _: _ => ();
-    /// }
-    ///                 #
-    ///                 # Ok(())
-    ///             # }
-    ///         # })
-    ///     # }).unwrap();
-    ///     #
-    ///     # Bastion::start();
-    ///     # Bastion::stop();
-    ///     # Bastion::block_until_stopped();
-    /// # }    
+    /// let sent = Bastion::atomic_batch(|batch| {
+    ///     batch.tell(accounts, "debit 42");
+    ///     batch.tell(ledger, "record 42");
+    /// });
+    ///
+    /// // Nobody subscribed to the distributors, so nothing was sent.
+    /// assert!(sent.is_err());
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
     /// ```
-    pub fn broadcast<M: Message>(msg: M) -> Result<(), M> {
-        debug!("Bastion: Broadcasting message: {:?}", msg);
-        let msg = BastionMessage::broadcast(msg);
-        let envelope = Envelope::from_dead_letters(msg);
-        trace!("Bastion: Sending envelope: {:?}", envelope);
-        // FIXME: panics?
-        SYSTEM
-            .sender()
-            .unbounded_send(envelope)
-            .map_err(|err| err.into_inner().into_msg().unwrap())
+    ///
+    /// [`Distributor`]: crate::distributor::Distributor
+    pub fn atomic_batch<F>(fill: F) -> Result<(), SendError>
+    where
+        F: FnOnce(&mut Batch),
+    {
+        let mut batch = Batch::default();
+        fill(&mut batch);
+        debug!("Bastion: Sending a batch of {} messages.", batch.len());
+        batch.send()
     }
 
-    /// Sends a message to the system to tell it to start
-    /// handling messages and running children.
+    /// Returns the name of every [`Distributor`] that was
+    /// registered, sorted alphabetically.
+    ///
+    /// Use [`distributors_with_subscribers`] to also know how
+    /// many children are subscribed to each of them.
     ///
     /// # Example
     ///
     /// ```rust
-    /// use bastion::prelude::*;
-    ///
+    /// # use bastion::prelude::*;
+    /// #
     /// # #[cfg(feature = "tokio-runtime")]
     /// # #[tokio::main]
     /// # async fn main() {
@@ -541,36 +789,36 @@ impl Bastion {
     /// # }
     /// #
     /// # fn run() {
-    /// Bastion::init();
-    ///
-    /// // Use bastion, spawn children and supervisors...
-    ///
-    /// Bastion::start();
-    ///
-    /// // The system will soon start, messages will
-    /// // now be handled...
+    /// # Bastion::init();
+    /// #
+    /// for name in Bastion::distributors() {
+    ///     println!("found distributor {}", name);
+    /// }
     /// #
+    /// # Bastion::start();
     /// # Bastion::stop();
     /// # Bastion::block_until_stopped();
     /// # }
     /// ```
-    pub fn start() {
-        debug!("Bastion: Starting.");
-        let msg = BastionMessage::start();
-        let envelope = Envelope::from_dead_letters(msg);
-        trace!("Bastion: Sending envelope: {:?}", envelope);
-        // FIXME: Err(Error)
-        SYSTEM.sender().unbounded_send(envelope).ok();
+    ///
+    /// [`Distributor`]: crate::distributor::Distributor
+    /// [`distributors_with_subscribers`]: Self::distributors_with_subscribers
+    pub fn distributors() -> Vec<String> {
+        Bastion::distributors_with_subscribers()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
     }
 
-    /// Sends a message to the system to tell it to stop
-    /// every running children groups and supervisors.
+    /// Returns the name of every [`Distributor`] that was
+    /// registered along with the number of children subscribed to
+    /// it, sorted by name.
     ///
     /// # Example
     ///
     /// ```rust
-    /// use bastion::prelude::*;
-    ///
+    /// # use bastion::prelude::*;
+    /// #
     /// # #[cfg(feature = "tokio-runtime")]
     /// # #[tokio::main]
     /// # async fn main() {
@@ -583,27 +831,912 @@ impl Bastion {
     /// # }
     /// #
     /// # fn run() {
-    ///
-    /// Bastion::init();
-    ///
-    /// // Use bastion, spawn children and supervisors...
-    ///
-    /// Bastion::start();
-    ///
-    /// // Send messages to children and/or do some
-    /// // work until you decide to stop the system...
-    ///
-    /// Bastion::stop();
+    /// # Bastion::init();
+    /// #
+    /// for (name, subscribers) in Bastion::distributors_with_subscribers() {
+    ///     println!("{} has {} subscribers", name, subscribers);
+    /// }
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
     /// # Bastion::block_until_stopped();
     /// # }
     /// ```
-    pub fn stop() {
-        debug!("Bastion: Stopping.");
-        let msg = BastionMessage::stop();
-        let envelope = Envelope::from_dead_letters(msg);
-        trace!("Bastion: Sending envelope: {:?}", envelope);
+    ///
+    /// [`Distributor`]: crate::distributor::Distributor
+    pub fn distributors_with_subscribers() -> Vec<(String, usize)> {
+        trace!("Bastion: Listing distributors.");
+        system::current()
+            .dispatcher()
+            .distributors()
+            .unwrap_or_else(|error| {
+                warn!("Bastion: Couldn't list the distributors: {}", error);
+                Vec::new()
+            })
+    }
+
+    /// Returns a snapshot of the supervision tree: the supervisors,
+    /// their children groups (with their redundancy, the names of
+    /// their distributors and the restarts of their elements) and
+    /// the state of each of them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// # Bastion::start();
+    /// #
+    /// let tree = Bastion::tree();
+    /// for group in tree.children_groups() {
+    ///     println!(
+    ///         "{} ({:?}): {}/{} elements, {} restarts",
+    ///         group.name(),
+    ///         group.state(),
+    ///         group.elements().len(),
+    ///         group.redundancy(),
+    ///         group.restarts(),
+    ///     );
+    /// }
+    /// #
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn tree() -> SupervisionTree {
+        trace!("Bastion: Taking a snapshot of the supervision tree.");
+        system::current().tree().snapshot()
+    }
+
+    /// Returns a stream of the lifecycle events of the elements of
+    /// the system (see [`SystemEvent`]), starting from now: children
+    /// groups starting and stopping, children starting, failing and
+    /// restarting, supervisors escalating failures and children
+    /// subscribing to or unsubscribing from distributors.
+    ///
+    /// The events are buffered until the stream is polled, and the
+    /// stream ends once dropped by the system.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use bastion::prelude::*;
+    /// # use futures::StreamExt;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// # Bastion::start();
+    /// let mut events = Bastion::events();
+    ///
+    /// spawn!(async move {
+    ///     while let Some(event) = events.next().await {
+    ///         if let SystemEvent::ChildFailed { child, reason, .. } = event {
+    ///             println!("Child({}) failed: {:?}", child, reason);
+    ///         }
+    ///     }
+    /// });
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn events() -> impl Stream<Item = SystemEvent> {
+        trace!("Bastion: Subscribing to the system events.");
+        system::current().events().subscribe()
+    }
+
+    /// Sets which messages sent within the system have their
+    /// lifecycle logged (see [`LogPolicy`]): when they are sent,
+    /// received by an element and replied to, along with the name
+    /// of their type and their correlation id.
+    ///
+    /// The events are logged using `tracing`, at the `INFO` level
+    /// and with the `bastion::messages` target. Sampling the
+    /// messages (e.g. `LogPolicy::Sampled(0.01)` to log one message
+    /// out of a hundred) keeps the overhead of the logging bounded,
+    /// and all the events of a sampled message are logged.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - Which messages to log.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// Bastion::log_messages(LogPolicy::Sampled(0.01));
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn log_messages(policy: LogPolicy) {
+        debug!("Bastion: Logging messages: {:?}", policy);
+        system::current().log_messages(policy);
+    }
+
+    /// Returns the runtime metrics of the system: the amount of
+    /// tasks queued by the executor and of live tasks, the amount of
+    /// running children, how many messages they receive per second,
+    /// and the depth of the mailboxes of each children group (see
+    /// [`RuntimeMetrics`]).
+    ///
+    /// The throughput of messages is measured since the previous
+    /// call, so this should be called periodically (e.g. each time
+    /// a metrics endpoint is scraped). With the `metrics` feature,
+    /// the metrics are also recorded using the `metrics` crate
+    /// facade.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// # Bastion::start();
+    /// let metrics = Bastion::metrics();
+    /// for group in metrics.groups() {
+    ///     if group.mailbox_depth() > 1_000 {
+    ///         println!("{} is lagging behind.", group.name());
+    ///     }
+    /// }
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn metrics() -> RuntimeMetrics {
+        let metrics = metrics::collect(&system::current());
+        #[cfg(feature = "metrics")]
+        metrics.record();
+
+        metrics
+    }
+
+    /// Returns a snapshot of the health of the system (see
+    /// [`SystemHealth`]): the groups whose children are
+    /// crash-looping, the groups running fewer elements than their
+    /// redundancy, the distributors without subscribers and whether
+    /// the executor is saturated.
+    ///
+    /// [`SystemHealth::is_live`] and [`SystemHealth::is_ready`] can
+    /// back the liveness and readiness probes of the process.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// # Bastion::start();
+    /// let health = Bastion::health();
+    /// let status = if health.is_ready() { 200 } else { 503 };
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn health() -> SystemHealth {
+        health::check(&system::current())
+    }
+
+    #[cfg(feature = "prometheus")]
+    /// Starts a children group, named `bastion-prometheus` and
+    /// supervised by the system's root supervisor, serving the
+    /// runtime metrics of the system (see [`Bastion::metrics`]) in
+    /// the Prometheus text format on `/metrics` at the given
+    /// address.
+    ///
+    /// Along with the executor, task and message metrics, the
+    /// response contains the elements, mailbox depth and restarts of
+    /// each children group, and a histogram of how long the
+    /// questions asked within the system waited for their answer.
+    ///
+    /// This method returns the [`ChildrenRef`] referencing the
+    /// children group, or an error if the address couldn't be bound.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address to listen on.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// Bastion::serve_metrics("127.0.0.1:0").expect("Couldn't serve the metrics.");
+    ///
+    /// Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Bastion::metrics`]: crate::Bastion::metrics
+    pub fn serve_metrics(addr: impl ToSocketAddrs) -> io::Result<ChildrenRef> {
+        let addr = first_addr(addr)?;
+        debug!("Bastion: Serving metrics on {}.", addr);
+        prometheus::serve(addr)
+    }
+
+    #[cfg(feature = "remote")]
+    /// Sets how the connections between this node and the other
+    /// nodes are encrypted and authenticated (see [`RemoteConfig`]).
+    ///
+    /// The configuration applies to the connections opened or
+    /// accepted afterwards, so it should be set before calling
+    /// [`Bastion::listen`] or [`Bastion::connect`].
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration of the connections.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// Bastion::configure_remote(
+    ///     RemoteConfig::new().with_authenticator(TokenAuthenticator::new("secret")),
+    /// );
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`RemoteConfig`]: crate::remote::RemoteConfig
+    pub fn configure_remote(config: RemoteConfig) {
+        debug!("Bastion: Configuring remote connections.");
+        remote::configure(config);
+    }
+
+    #[cfg(feature = "remote")]
+    /// Starts a children group, named `bastion-remote` and
+    /// supervised by the system's root supervisor, accepting the
+    /// connections of other nodes at the given address (see
+    /// [`Bastion::connect`]).
+    ///
+    /// The messages the nodes send to a distributor over a
+    /// connection are sent to the recipients of the distributor with
+    /// the same name in this system, as [`WireMessage`]s, and the
+    /// answers to their questions are sent back over the connection.
+    ///
+    /// This method returns the [`ChildrenRef`] referencing the
+    /// children group, or an error if the address couldn't be bound.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address to listen on.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// Bastion::listen("127.0.0.1:0").expect("Couldn't listen.");
+    ///
+    /// Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`WireMessage`]: crate::wire::WireMessage
+    pub fn listen(addr: impl ToSocketAddrs) -> io::Result<ChildrenRef> {
+        let addr = first_addr(addr)?;
+        debug!("Bastion: Listening on {}.", addr);
+        remote::listen(addr)
+    }
+
+    #[cfg(feature = "remote")]
+    /// Connects to the node listening at the given address (see
+    /// [`Bastion::listen`]) and registers it under the given name,
+    /// which can then be given to [`Distributor::remote`] to send
+    /// messages to the node.
+    ///
+    /// If the connection is lost, it is opened again following the
+    /// [`ReconnectPolicy`] of the [`RemoteConfig`] (by default, the
+    /// next time a message is sent to the node). Connecting to
+    /// another node under the same name replaces the previous one.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The name to register the node under.
+    /// * `addr` - The address the node listens on.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// # Bastion::start();
+    /// Bastion::connect("node-b", "10.0.0.2:4222").expect("Couldn't connect to node-b.");
+    ///
+    /// Distributor::remote("node-b", "workers")
+    ///     .tell_one("hello".to_string())
+    ///     .expect("Couldn't send the message.");
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Distributor::remote`]: crate::distributor::Distributor::remote
+    /// [`ReconnectPolicy`]: crate::remote::ReconnectPolicy
+    /// [`RemoteConfig`]: crate::remote::RemoteConfig
+    pub fn connect(node: impl Into<String>, addr: impl ToSocketAddrs) -> io::Result<()> {
+        let addr = first_addr(addr)?;
+        remote::connect(node.into(), addr)
+    }
+
+    #[cfg(feature = "remote")]
+    /// Returns the cluster this node can join, to gossip its
+    /// membership with the other nodes, list the members and watch
+    /// the changes of the membership (see [`Cluster`]).
+    ///
+    /// Once this node joined the cluster, the members don't need to
+    /// be connected to using [`Bastion::connect`]: they are
+    /// registered under the name they joined with.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// # Bastion::start();
+    /// let config = ClusterConfig::new("node-a", "10.0.0.1:4222".parse().unwrap())
+    ///     .with_seed("10.0.0.2:4222".parse().unwrap());
+    /// Bastion::cluster().join(config).expect("Couldn't join the cluster.");
+    ///
+    /// // Reaches the workers of every member of the cluster.
+    /// Distributor::named("workers")
+    ///     .tell_everyone(WireMessage::pack(&"hello".to_string()).unwrap())
+    ///     .expect("Couldn't send the message.");
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Cluster`]: crate::cluster::Cluster
+    pub fn cluster() -> Cluster {
+        Cluster::get()
+    }
+
+    #[cfg(feature = "remote")]
+    /// Returns the messages told to other nodes (or received from
+    /// them) which couldn't be delivered, along with where they were
+    /// sent, so that they can be inspected and sent again (see
+    /// [`DeadLetters`]).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// # Bastion::start();
+    /// let workers = Distributor::remote("node-b", "workers");
+    /// if workers.tell_one("hello".to_string()).is_err() {
+    ///     let dead_letters = Bastion::dead_letters().drain();
+    ///     assert_eq!(dead_letters[0].node(), Some("node-b"));
+    /// }
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`DeadLetters`]: crate::dead_letters::DeadLetters
+    pub fn dead_letters() -> DeadLetters {
+        DeadLetters::get()
+    }
+
+    /// Returns a [`SupervisorRef`] referencing the supervisor
+    /// launched under the specified path, if there is one.
+    ///
+    /// The path of a supervisor is made of the names of its
+    /// supervisors and its own name, separated by `/` (e.g.
+    /// `"ingest/parsers"`), and only exists if the supervisor and
+    /// all its supervisors were named using
+    /// [`Supervisor::with_name`]. A supervisor is registered under
+    /// its path once it is launched and unregistered when it stops.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the supervisor.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// # Bastion::start();
+    /// #
+    /// if let Some(parsers) = Bastion::supervisor_ref("ingest/parsers") {
+    ///     parsers.stop().expect("Couldn't stop the supervisor");
+    /// }
+    /// #
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Supervisor::with_name`]: crate::supervisor::Supervisor::with_name
+    pub fn supervisor_ref(path: &str) -> Option<SupervisorRef> {
+        trace!("Bastion: Looking up Supervisor at path: {}", path);
+        system::current().names().supervisor(path)
+    }
+
+    /// Returns a [`ChildrenRef`] referencing the children group
+    /// launched under the specified path, if there is one.
+    ///
+    /// The path of a children group is the path of its supervisor
+    /// followed by its name (e.g. `"ingest/parsers/csv"`), or only
+    /// its name if it was created using [`Bastion::children`]. It
+    /// only exists if the group was named using
+    /// [`Children::with_name`] and its supervisor can be looked up
+    /// by path (see [`Bastion::supervisor_ref`]).
+    ///
+    /// Note that the elements of the returned [`ChildrenRef`] are
+    /// those the group had when it last handled a message.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the children group.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// # Bastion::start();
+    /// #
+    /// if let Some(csv) = Bastion::children_ref("ingest/parsers/csv") {
+    ///     csv.broadcast("flush").expect("Couldn't broadcast the message");
+    /// }
+    /// #
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Children::with_name`]: crate::children::Children::with_name
+    pub fn children_ref(path: &str) -> Option<ChildrenRef> {
+        trace!("Bastion: Looking up Children at path: {}", path);
+        system::current().names().children(path)
+    }
+
+    /// Resolves the address of an element of a children group (see
+    /// [`ChildRef::child_addr`]) into a [`ChildRef`] referencing it,
+    /// returning `None` if its children group isn't running or
+    /// doesn't contain it anymore.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address of the element, e.g. deserialized
+    ///     after being stored in a database.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// # Bastion::start();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children).unwrap();
+    /// # let json = serde_json::to_string(&children_ref.elems()[0].child_addr()).unwrap();
+    /// let addr: ChildAddr = serde_json::from_str(&json).expect("Couldn't parse the address");
+    /// if let Some(child_ref) = Bastion::resolve(&addr) {
+    ///     child_ref.tell_anonymously("hello").expect("Couldn't send the message");
+    /// }
+    /// #
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`ChildRef::child_addr`]: crate::child_ref::ChildRef::child_addr
+    pub fn resolve(addr: &ChildAddr) -> Option<ChildRef> {
+        trace!("Bastion: Resolving Child({}).", addr.id());
+        ADDRESSES.resolve(addr)
+    }
+
+    distributed_api! {
+        // FIXME!
+        #[allow(missing_docs)]
+        pub fn distributed<I, F>(cluster_config: &'static ArtilleryAPClusterConfig, action: I) -> Result<ChildrenRef, ()>
+        where
+            I: Fn(Arc<DistributedContext>) -> F + Send + Sync + 'static,
+            F: Future<Output = Result<(), ()>> + Send + 'static,
+        {
+            cluster_actor(cluster_config, action)
+        }
+    }
+
+    /// Sends a message to the system which will then send it to all
+    /// the root-level supervisors and their supervised children and
+    /// supervisors, etc.
+    ///
+    /// This method returns `()` if it succeeded, or `Err(msg)`
+    /// otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The message to send.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    ///     # Bastion::init();
+    ///     #
+    /// let msg = "A message containing data.";
+    /// Bastion::broadcast(msg).expect("Couldn't send the message.");
+    ///
+    ///     # Bastion::children(|children| {
+    ///         # children.with_exec(|ctx: BastionContext| {
+    ///             # async move {
+    /// // And then in every children groups's elements' future...
+    /// msg! { ctx.recv().await?,
+    ///     ref msg: &'static str => {
+    ///         assert_eq!(msg, &"A message containing data.");
+    ///     };
+    ///     // We are only broadcasting a `&'static str` in this
+    ///     // example, so we know that this won't happen...
+    ///     _: _ => ();
+    /// }
+    ///                 #
+    ///                 # Ok(())
+    ///             # }
+    ///         # })
+    ///     # }).unwrap();
+    ///     #
+    ///     # Bastion::start();
+    ///     # Bastion::stop();
+    ///     # Bastion::block_until_stopped();
+    /// # }    
+    /// ```
+    pub fn broadcast<M: Message>(msg: M) -> Result<(), M> {
+        debug!("Bastion: Broadcasting message: {:?}", msg);
+        let msg = BastionMessage::broadcast(msg);
+        let envelope = Envelope::from_dead_letters(msg);
+        trace!("Bastion: Sending envelope: {:?}", envelope);
+        // FIXME: panics?
+        system::current()
+            .sender()
+            .unbounded_send(envelope)
+            .map_err(|err| err.into_inner().into_msg().unwrap())
+    }
+
+    /// Sends a message to the system to tell it to start
+    /// handling messages and running children.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bastion::prelude::*;
+    ///
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// Bastion::init();
+    ///
+    /// // Use bastion, spawn children and supervisors...
+    ///
+    /// Bastion::start();
+    ///
+    /// // The system will soon start, messages will
+    /// // now be handled...
+    /// #
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn start() {
+        debug!("Bastion: Starting.");
+        let msg = BastionMessage::start();
+        let envelope = Envelope::from_dead_letters(msg);
+        trace!("Bastion: Sending envelope: {:?}", envelope);
+        // FIXME: Err(Error)
+        system::current().sender().unbounded_send(envelope).ok();
+    }
+
+    /// Sends a message to the system to tell it to stop
+    /// every running children groups and supervisors.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bastion::prelude::*;
+    ///
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    ///
+    /// Bastion::init();
+    ///
+    /// // Use bastion, spawn children and supervisors...
+    ///
+    /// Bastion::start();
+    ///
+    /// // Send messages to children and/or do some
+    /// // work until you decide to stop the system...
+    ///
+    /// Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn stop() {
+        debug!("Bastion: Stopping.");
+        let msg = BastionMessage::stop();
+        let envelope = Envelope::from_dead_letters(msg);
+        trace!("Bastion: Sending envelope: {:?}", envelope);
         // FIXME: Err(Error)
-        SYSTEM.sender().unbounded_send(envelope).ok();
+        system::current().sender().unbounded_send(envelope).ok();
+    }
+
+    /// Sets the handler called when a top-level supervisor gives up,
+    /// either because it exceeded its [`RestartLimit`] or because its
+    /// [`SupervisionDecider`] escalated a failure, as there is no
+    /// supervisor above it to escalate the failure to.
+    ///
+    /// The handler receives a [`FatalReport`] containing the whole
+    /// escalation chain, down to the failure that caused it, so that
+    /// it can flush state, emit a crash report or trigger a failover.
+    ///
+    /// The handler is only notified: the report is logged and the
+    /// system is stopped once the handler returned, or right away if
+    /// no handler was set.
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - The closure called with the report.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// Bastion::init();
+    ///
+    /// Bastion::on_fatal(|report: &FatalReport| {
+    ///     for failure in report.chain() {
+    ///         eprintln!("{}: {:?}", failure.id(), failure.reason());
+    ///     }
+    ///     eprintln!("caused by: {:?}", report.root_cause().message());
+    /// });
+    ///
+    /// Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`RestartLimit`]: crate::supervisor::RestartLimit
+    /// [`SupervisionDecider`]: crate::supervisor::SupervisionDecider
+    pub fn on_fatal<F>(handler: F)
+    where
+        F: Fn(&FatalReport) + Send + Sync + 'static,
+    {
+        debug!("Bastion: Setting fatal handler.");
+        system::current().set_fatal_handler(Arc::new(handler));
     }
 
     /// Sends a message to the system to tell it to kill every
@@ -644,16 +1777,17 @@ impl Bastion {
         let envelope = Envelope::from_dead_letters(msg);
         trace!("Bastion: Sending envelope: {:?}", envelope);
         // FIXME: Err(Error)
-        SYSTEM.sender().unbounded_send(envelope).ok();
+        let global = system::current();
+        global.sender().unbounded_send(envelope).ok();
 
-        let handle = SYSTEM.handle();
+        let handle = global.handle();
         let system = crate::executor::run(async { handle.lock().await.take() });
         if let Some(system) = system {
             debug!("Bastion: Cancelling system handle.");
             system.cancel();
         }
 
-        SYSTEM.notify_stopped();
+        global.notify_stopped();
     }
 
     /// Blocks the current thread until the system is stopped
@@ -693,7 +1827,458 @@ impl Bastion {
     /// ```
     pub fn block_until_stopped() {
         debug!("Bastion: Blocking until system is stopped.");
-        SYSTEM.wait_until_stopped();
+        let global = system::current();
+        match TestRuntime::installed() {
+            Some(runtime) => runtime.drive_until(|| !global.is_running()),
+            None => global.wait_until_stopped(),
+        }
+    }
+
+    /// Blocks the current thread until the system is stopped
+    /// (either by calling [`Bastion::stop`] or
+    /// [`Bastion::kill`]), or until the given timeout elapsed.
+    ///
+    /// Returns whether the system stopped before the timeout
+    /// elapsed. With the runtime installed by [`Bastion::init_test`],
+    /// the timeout is measured in virtual time.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long to wait for the system to stop.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// use bastion::prelude::*;
+    ///
+    /// Bastion::init();
+    /// Bastion::start();
+    ///
+    /// if !Bastion::block_until_stopped_timeout(Duration::from_millis(100)) {
+    ///     // The system is still running, stop it gracefully.
+    ///     Bastion::stop();
+    ///     Bastion::block_until_stopped();
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// [`Bastion::init_test`]: Self::init_test
+    pub fn block_until_stopped_timeout(timeout: Duration) -> bool {
+        debug!(
+            "Bastion: Blocking until system is stopped or {:?} elapsed.",
+            timeout
+        );
+        let global = system::current();
+        match TestRuntime::installed() {
+            Some(runtime) => runtime.drive_until_timeout(timeout, || !global.is_running()),
+            None => global.wait_until_stopped_timeout(timeout),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    /// Stops the system gracefully, using [`Bastion::stop`], when
+    /// the process receives `SIGINT`, `SIGTERM` or `SIGHUP` (or a
+    /// ctrl-c or the closing of its console on Windows), so that
+    /// the children can run their [`with_callbacks`] stop callbacks
+    /// and drain their mailboxes before the process exits (e.g. when
+    /// its container is stopped).
+    ///
+    /// If a second signal is received while the system is stopping,
+    /// it is killed using [`Bastion::kill`] instead.
+    ///
+    /// This is opt-in, as it replaces the handlers the application
+    /// might have set for these signals, and can only be called once
+    /// per process: it returns an error if a handler was already set.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// use bastion::prelude::*;
+    ///
+    /// Bastion::init();
+    /// Bastion::stop_on_signal().expect("Couldn't set the signal handler.");
+    ///
+    /// // Use bastion, spawn children and supervisors...
+    ///
+    /// Bastion::start();
+    /// # Bastion::stop();
+    /// Bastion::block_until_stopped();
+    /// // The process received a signal and the system is now
+    /// // stopped...
+    /// # }
+    /// ```
+    ///
+    /// [`with_callbacks`]: crate::children::Children::with_callbacks
+    pub fn stop_on_signal() -> Result<(), ()> {
+        debug!("Bastion: Stopping on signals.");
+        let stopping = AtomicBool::new(false);
+        ctrlc::set_handler(move || {
+            if stopping.swap(true, Ordering::SeqCst) {
+                warn!("Bastion: Received a signal while stopping, killing the system.");
+                Bastion::kill();
+            } else {
+                debug!("Bastion: Received a signal, stopping the system.");
+                Bastion::stop();
+            }
+        })
+        .map_err(|err| {
+            warn!("Bastion: Couldn't set the signal handler: {}", err);
+        })
+    }
+}
+
+/// An actor system independent from the default one used by
+/// [`Bastion`], with its own root supervisor, dispatcher (and thus
+/// its own distributors' recipients) and registry of named elements.
+///
+/// Its elements, and the elements and tasks they start, belong to
+/// it: the functions of [`Bastion`] they call act on it, as do the
+/// [`Distributor`]s they use. Other code can act on it using
+/// [`BastionSystem::enter`]. The names of the distributors are
+/// interned once for the whole process, so that a [`Distributor`]
+/// can be used with any system.
+///
+/// Dropping a `BastionSystem` kills it if it is still running,
+/// allowing to fully tear it down (e.g. at the end of a test).
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();    
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();    
+/// # }
+/// #
+/// # fn run() {
+/// use bastion::prelude::*;
+///
+/// let first = BastionSystem::new(Config::new());
+/// let second = BastionSystem::new(Config::new());
+///
+/// // Both systems can have an element named "db"...
+/// first
+///     .supervisor(|sp| sp.with_name("db"))
+///     .expect("Couldn't create the supervisor.");
+/// second
+///     .supervisor(|sp| sp.with_name("db"))
+///     .expect("Couldn't create the supervisor.");
+///
+/// first.start();
+/// second.start();
+///
+/// // ...and stopping one doesn't affect the other.
+/// first.stop();
+/// first.block_until_stopped();
+/// assert!(second.is_running());
+/// # second.stop();
+/// # second.block_until_stopped();
+/// # }
+/// ```
+///
+/// [`Distributor`]: crate::distributor::Distributor
+pub struct BastionSystem {
+    system: Arc<GlobalSystem>,
+}
+
+impl BastionSystem {
+    /// Initializes and launches a new system using the specified
+    /// [`Config`]. The system has to be started using
+    /// [`BastionSystem::start`] for its elements to start handling
+    /// messages.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration used to initialize the system.
+    pub fn new(config: Config) -> Self {
+        debug!("BastionSystem: Initializing with config: {:?}", config);
+        configure(&config);
+
+        BastionSystem {
+            system: GlobalSystem::init(),
+        }
+    }
+
+    /// Calls `f` with this system as the current one, so that the
+    /// functions of [`Bastion`] and the [`Distributor`]s act on it,
+    /// and that the tasks spawned by `f` belong to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The closure to call with this system as the current
+    ///     one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// let system = BastionSystem::new(Config::new());
+    /// # system.start();
+    ///
+    /// system
+    ///     .enter(|| Distributor::named("jobs").tell_one("A job."))
+    ///     .expect_err("The system has no recipient for jobs.");
+    /// # system.stop();
+    /// # system.block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Distributor`]: crate::distributor::Distributor
+    pub fn enter<R>(&self, f: impl FnOnce() -> R) -> R {
+        system::enter(&self.system, f)
+    }
+
+    /// Creates a new [`Supervisor`] supervised by the root
+    /// supervisor of this system (see [`Bastion::supervisor`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `init` - The closure taking the new [`Supervisor`] as an
+    ///     argument and returning it once configured.
+    pub fn supervisor<S>(&self, init: S) -> Result<SupervisorRef, ()>
+    where
+        S: FnOnce(Supervisor) -> Supervisor,
+    {
+        self.enter(|| Bastion::supervisor(init))
+    }
+
+    /// Creates a new [`Children`] group supervised by the root
+    /// supervisor of this system (see [`Bastion::children`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `init` - The closure taking the new [`Children`] as an
+    ///     argument and returning it once configured.
+    pub fn children<C>(&self, init: C) -> Result<ChildrenRef, ()>
+    where
+        C: FnOnce(Children) -> Children,
+    {
+        self.enter(|| Bastion::children(init))
+    }
+
+    /// Creates a new [`Children`] group with a single element
+    /// running the given closure, supervised by the root supervisor
+    /// of this system (see [`Bastion::spawn`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The closure returning the future the element
+    ///     will run.
+    pub fn spawn<I, F>(&self, action: I) -> Result<ChildrenRef, ()>
+    where
+        I: Fn(BastionContext) -> F + Send + 'static,
+        F: Future<Output = Result<(), ()>> + Send + 'static,
+    {
+        self.enter(|| Bastion::spawn(action))
+    }
+
+    /// Sends a message to every children group of this system (see
+    /// [`Bastion::broadcast`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The message to send.
+    pub fn broadcast<M: Message>(&self, msg: M) -> Result<(), M> {
+        self.enter(|| Bastion::broadcast(msg))
+    }
+
+    /// Returns a snapshot of the supervision tree of this system
+    /// (see [`Bastion::tree`]).
+    pub fn tree(&self) -> SupervisionTree {
+        self.enter(Bastion::tree)
+    }
+
+    /// Returns a stream of the lifecycle events of the elements of
+    /// this system (see [`Bastion::events`]).
+    pub fn events(&self) -> impl Stream<Item = SystemEvent> {
+        self.enter(Bastion::events)
+    }
+
+    /// Sets which messages sent within this system have their
+    /// lifecycle logged (see [`Bastion::log_messages`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - Which messages to log.
+    pub fn log_messages(&self, policy: LogPolicy) {
+        self.enter(|| Bastion::log_messages(policy))
+    }
+
+    /// Returns the runtime metrics of this system (see
+    /// [`Bastion::metrics`]).
+    pub fn metrics(&self) -> RuntimeMetrics {
+        self.enter(Bastion::metrics)
+    }
+
+    /// Returns a snapshot of the health of this system (see
+    /// [`Bastion::health`]).
+    pub fn health(&self) -> SystemHealth {
+        self.enter(Bastion::health)
+    }
+
+    #[cfg(feature = "prometheus")]
+    /// Starts a children group serving the runtime metrics of this
+    /// system in the Prometheus text format (see
+    /// [`Bastion::serve_metrics`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address to listen on.
+    pub fn serve_metrics(&self, addr: impl ToSocketAddrs) -> io::Result<ChildrenRef> {
+        self.enter(|| Bastion::serve_metrics(addr))
+    }
+
+    #[cfg(feature = "remote")]
+    /// Starts a children group accepting the connections of other
+    /// nodes, whose messages are sent to the distributors of this
+    /// system (see [`Bastion::listen`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address to listen on.
+    pub fn listen(&self, addr: impl ToSocketAddrs) -> io::Result<ChildrenRef> {
+        self.enter(|| Bastion::listen(addr))
+    }
+
+    #[cfg(feature = "remote")]
+    /// Connects to the node listening at the given address and
+    /// registers it under the given name (see [`Bastion::connect`]).
+    /// The questions the node asks over the connection are sent to
+    /// the distributors of this system.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The name to register the node under.
+    /// * `addr` - The address the node listens on.
+    pub fn connect(&self, node: impl Into<String>, addr: impl ToSocketAddrs) -> io::Result<()> {
+        self.enter(|| Bastion::connect(node, addr))
+    }
+
+    /// Returns a reference to the supervisor of this system with the
+    /// given path (see [`Bastion::supervisor_ref`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the supervisor.
+    pub fn supervisor_ref(&self, path: &str) -> Option<SupervisorRef> {
+        self.enter(|| Bastion::supervisor_ref(path))
+    }
+
+    /// Returns a reference to the children group of this system with
+    /// the given path (see [`Bastion::children_ref`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the children group.
+    pub fn children_ref(&self, path: &str) -> Option<ChildrenRef> {
+        self.enter(|| Bastion::children_ref(path))
+    }
+
+    /// Starts this system (see [`Bastion::start`]).
+    pub fn start(&self) {
+        self.enter(Bastion::start)
+    }
+
+    /// Stops this system gracefully (see [`Bastion::stop`]).
+    pub fn stop(&self) {
+        self.enter(Bastion::stop)
+    }
+
+    /// Kills this system (see [`Bastion::kill`]).
+    pub fn kill(&self) {
+        self.enter(Bastion::kill)
+    }
+
+    /// Returns whether this system is still running, i.e. whether
+    /// it wasn't stopped nor killed.
+    pub fn is_running(&self) -> bool {
+        self.system.is_running()
+    }
+
+    /// Blocks the current thread until this system is stopped (see
+    /// [`Bastion::block_until_stopped`]).
+    pub fn block_until_stopped(&self) {
+        self.enter(Bastion::block_until_stopped)
+    }
+
+    /// Blocks the current thread until this system is stopped, or
+    /// until the given timeout elapsed, returning whether it stopped
+    /// (see [`Bastion::block_until_stopped_timeout`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long to wait for the system to stop.
+    pub fn block_until_stopped_timeout(&self, timeout: Duration) -> bool {
+        self.enter(|| Bastion::block_until_stopped_timeout(timeout))
+    }
+}
+
+// Applies the process-wide parts of the configuration of a system.
+fn configure(config: &Config) {
+    if config.backtraces().is_hide() {
+        debug!("Bastion: Hiding backtraces.");
+        std::panic::set_hook(Box::new(|_| ()));
+    }
+}
+
+// Returns the first address the given one resolves to.
+#[cfg(any(feature = "prometheus", feature = "remote"))]
+fn first_addr(addr: impl ToSocketAddrs) -> io::Result<SocketAddr> {
+    addr.to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "No address to bind."))
+}
+
+impl Drop for BastionSystem {
+    fn drop(&mut self) {
+        if self.system.is_running() {
+            debug!("BastionSystem: Killing the system as it is dropped.");
+            self.kill();
+        }
     }
 }
 
@@ -702,3 +2287,11 @@ impl Debug for Bastion {
         fmt.debug_struct("Bastion").finish()
     }
 }
+
+impl Debug for BastionSystem {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("BastionSystem")
+            .field("running", &self.system.is_running())
+            .finish()
+    }
+}
@@ -0,0 +1,71 @@
+//!
+//! Behaviors allow the elements of a children group to be written
+//! as state machines, swapping the handler of their messages with
+//! [`BastionContext::become_`] and going back to the previous one
+//! with [`BastionContext::unbecome`].
+//!
+//! [`BastionContext::become_`]: crate::context::BastionContext::become_
+//! [`BastionContext::unbecome`]: crate::context::BastionContext::unbecome
+use crate::context::BastionContext;
+use crate::envelope::SignedMessage;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Arc;
+
+#[derive(Clone)]
+/// A handler of the messages received by an element of a children
+/// group, set as its active handler using
+/// [`BastionContext::become_`] and called by
+/// [`BastionContext::run_behaviors`].
+///
+/// A behavior returns `Err(())` to make the element fault, the same
+/// way its [`with_exec`] future would.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// #
+/// fn idle() -> Behavior {
+///     Behavior::new(|ctx: &BastionContext, msg: SignedMessage| {
+///         if msg.is::<&'static str>() {
+///             ctx.become_(busy());
+///         }
+///         Ok(())
+///     })
+/// }
+///
+/// fn busy() -> Behavior {
+///     Behavior::new(|ctx: &BastionContext, msg: SignedMessage| {
+///         // Handle the message and go back to being idle...
+///         ctx.unbecome();
+///         Ok(())
+///     })
+/// }
+/// ```
+///
+/// [`BastionContext::become_`]: crate::context::BastionContext::become_
+/// [`BastionContext::run_behaviors`]: crate::context::BastionContext::run_behaviors
+/// [`with_exec`]: crate::children::Children::with_exec
+pub struct Behavior(Arc<dyn Fn(&BastionContext, SignedMessage) -> Result<(), ()> + Send + Sync>);
+
+impl Behavior {
+    /// Creates a behavior calling the given closure with the context
+    /// of the element and each message it receives while the
+    /// behavior is active.
+    pub fn new<F>(handler: F) -> Self
+    where
+        F: Fn(&BastionContext, SignedMessage) -> Result<(), ()> + Send + Sync + 'static,
+    {
+        Behavior(Arc::new(handler))
+    }
+
+    pub(crate) fn handle(&self, ctx: &BastionContext, msg: SignedMessage) -> Result<(), ()> {
+        (self.0)(ctx, msg)
+    }
+}
+
+impl Debug for Behavior {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Behavior").finish()
+    }
+}
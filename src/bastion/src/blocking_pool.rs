@@ -0,0 +1,358 @@
+//!
+//! The dedicated pool of threads running the blocking tasks, set up
+//! using [`Bastion::blocking_pool_config`], and the pools of threads
+//! dedicated to a children group, set up using
+//! [`Children::with_dedicated_executor`] or
+//! [`Children::with_affinity`].
+//!
+//! [`Bastion::blocking_pool_config`]: crate::Bastion::blocking_pool_config
+//! [`Children::with_dedicated_executor`]: crate::children::Children::with_dedicated_executor
+//! [`Children::with_affinity`]: crate::children::Children::with_affinity
+use crate::executor::CoreSet;
+#[cfg(not(target_arch = "wasm32"))]
+use bastion_executor::placement::{self, CoreId};
+use once_cell::sync::OnceCell;
+use std::collections::VecDeque;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+use tracing::{debug, trace};
+
+static POOL: OnceCell<BlockingPool> = OnceCell::new();
+
+type Job = Box<dyn FnOnce() + Send>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The size limits of the pool of threads running the blocking
+/// tasks, given to [`Bastion::blocking_pool_config`].
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use std::time::Duration;
+/// #
+/// let config = BlockingPoolConfig {
+///     min: 2,
+///     max: 16,
+///     keep_alive: Duration::from_secs(30),
+/// };
+/// ```
+///
+/// [`Bastion::blocking_pool_config`]: crate::Bastion::blocking_pool_config
+pub struct BlockingPoolConfig {
+    /// The amount of threads started with the pool and kept alive
+    /// even when idle.
+    pub min: usize,
+    /// The maximum amount of threads of the pool. Tasks spawned
+    /// while every thread is busy wait in the pool's queue.
+    pub max: usize,
+    /// How long a thread above `min` stays idle before exiting.
+    pub keep_alive: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The state of the pool of threads running the blocking tasks,
+/// returned by [`Bastion::blocking_pool_stats`].
+///
+/// [`Bastion::blocking_pool_stats`]: crate::Bastion::blocking_pool_stats
+pub struct BlockingPoolStats {
+    queued: usize,
+    threads: usize,
+    active: usize,
+    completed: u64,
+}
+
+#[derive(Debug)]
+pub(crate) struct BlockingPool {
+    config: BlockingPoolConfig,
+    // The cores the threads of the pool are pinned to, if any.
+    affinity: Option<CoreSet>,
+    name: &'static str,
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    state: Mutex<PoolState>,
+    available: Condvar,
+    completed: AtomicU64,
+}
+
+#[derive(Default)]
+struct PoolState {
+    jobs: VecDeque<Job>,
+    threads: usize,
+    // The threads waiting for a job.
+    idle: usize,
+    // Whether the pool was dropped, in which case its threads exit
+    // once the queue is empty.
+    closed: bool,
+}
+
+impl Default for BlockingPoolConfig {
+    fn default() -> Self {
+        BlockingPoolConfig {
+            min: 2,
+            max: 512,
+            keep_alive: Duration::from_secs(10),
+        }
+    }
+}
+
+impl BlockingPoolStats {
+    /// Returns the amount of tasks waiting for a thread.
+    pub fn queued(&self) -> usize {
+        self.queued
+    }
+
+    /// Returns the amount of threads of the pool.
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Returns the amount of threads running a task.
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    /// Returns the amount of tasks the pool ran since it started.
+    pub fn completed(&self) -> u64 {
+        self.completed
+    }
+}
+
+impl BlockingPool {
+    // Starts the pool used by `executor::blocking`, returning
+    // `false` if it was already started.
+    pub(crate) fn init(config: BlockingPoolConfig) -> bool {
+        let mut initialized = false;
+        POOL.get_or_init(|| {
+            initialized = true;
+            BlockingPool::new(config, None, "bastion-blocking")
+        });
+
+        initialized
+    }
+
+    pub(crate) fn get() -> Option<&'static BlockingPool> {
+        POOL.get()
+    }
+
+    // Starts a pool of `threads` threads, pinned to the given cores
+    // if any, running the elements of a children group.
+    pub(crate) fn dedicated(threads: usize, affinity: Option<CoreSet>) -> Self {
+        let config = BlockingPoolConfig {
+            min: threads,
+            max: threads,
+            ..BlockingPoolConfig::default()
+        };
+
+        BlockingPool::new(config, affinity, "bastion-dedicated")
+    }
+
+    fn new(mut config: BlockingPoolConfig, affinity: Option<CoreSet>, name: &'static str) -> Self {
+        config.max = config.max.max(config.min).max(1);
+        debug!(
+            "BlockingPool: Starting {} with {:?} on cores {:?}.",
+            name, config, affinity
+        );
+
+        let pool = BlockingPool {
+            config,
+            affinity,
+            name,
+            shared: Arc::default(),
+        };
+        for index in 0..config.min {
+            pool.shared.state.lock().unwrap().threads += 1;
+            pool.start_thread(index);
+        }
+
+        pool
+    }
+
+    pub(crate) fn execute(&self, job: Job) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.jobs.push_back(job);
+        if state.jobs.len() > state.idle && state.threads < self.config.max {
+            state.threads += 1;
+            trace!(
+                "BlockingPool: Starting thread {}/{}.",
+                state.threads,
+                self.config.max
+            );
+            self.start_thread(state.threads - 1);
+        }
+
+        self.shared.available.notify_one();
+    }
+
+    pub(crate) fn max_threads(&self) -> usize {
+        self.config.max
+    }
+
+    pub(crate) fn stats(&self) -> BlockingPoolStats {
+        let state = self.shared.state.lock().unwrap();
+        BlockingPoolStats {
+            queued: state.jobs.len(),
+            threads: state.threads,
+            active: state.threads - state.idle,
+            completed: self.shared.completed.load(Ordering::Relaxed),
+        }
+    }
+
+    fn start_thread(&self, index: usize) {
+        let shared = self.shared.clone();
+        let BlockingPoolConfig {
+            min, keep_alive, ..
+        } = self.config;
+        let core = self
+            .affinity
+            .as_ref()
+            .and_then(|affinity| affinity.cores().get(index % affinity.cores().len()))
+            .copied();
+        thread::Builder::new()
+            .name(self.name.to_string())
+            .spawn(move || {
+                if let Some(id) = core {
+                    pin_to(id);
+                }
+
+                shared.run(min, keep_alive)
+            })
+            .expect("Couldn't start a thread of the blocking pool.");
+    }
+}
+
+impl Drop for BlockingPool {
+    fn drop(&mut self) {
+        debug!("BlockingPool: Stopping {}.", self.name);
+        self.shared.state.lock().unwrap().closed = true;
+        self.shared.available.notify_all();
+    }
+}
+
+impl Shared {
+    fn run(&self, min: usize, keep_alive: Duration) {
+        loop {
+            let job = {
+                let mut state = self.state.lock().unwrap();
+                loop {
+                    if let Some(job) = state.jobs.pop_front() {
+                        break job;
+                    } else if state.closed {
+                        state.threads -= 1;
+                        return;
+                    }
+
+                    state.idle += 1;
+                    let (guard, wait) = self.available.wait_timeout(state, keep_alive).unwrap();
+                    state = guard;
+                    state.idle -= 1;
+
+                    if wait.timed_out() && state.jobs.is_empty() && state.threads > min {
+                        state.threads -= 1;
+                        trace!("BlockingPool: Stopping idle thread.");
+                        return;
+                    }
+                }
+            };
+
+            job();
+            self.completed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Debug for PoolState {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("PoolState")
+            .field("jobs", &self.jobs.len())
+            .field("threads", &self.threads)
+            .field("idle", &self.idle)
+            .field("closed", &self.closed)
+            .finish()
+    }
+}
+
+// Pins the current thread to the core with the given identifier.
+#[cfg(not(target_arch = "wasm32"))]
+fn pin_to(id: usize) {
+    placement::set_for_current(CoreId { id });
+}
+
+// The threads of the browser can't be pinned to a core.
+#[cfg(target_arch = "wasm32")]
+fn pin_to(_: usize) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_blocking_pool_limits() {
+        let config = BlockingPoolConfig {
+            min: 1,
+            max: 2,
+            keep_alive: Duration::from_millis(50),
+        };
+        let pool = BlockingPool::new(config, None, "bastion-blocking");
+        assert_eq!(pool.stats().threads(), 1);
+
+        let (release, released) = mpsc::channel::<()>();
+        let released = Arc::new(Mutex::new(released));
+        for _ in 0..3 {
+            let released = released.clone();
+            pool.execute(Box::new(move || {
+                released.lock().unwrap().recv().ok();
+            }));
+        }
+        thread::sleep(Duration::from_millis(20));
+
+        let stats = pool.stats();
+        assert_eq!(stats.threads(), 2);
+        assert_eq!(
+            stats.queued() + stats.active(),
+            3 - stats.completed() as usize
+        );
+        assert!(stats.queued() >= 1);
+
+        for _ in 0..3 {
+            release.send(()).unwrap();
+        }
+        thread::sleep(Duration::from_millis(200));
+
+        let stats = pool.stats();
+        assert_eq!(stats.completed(), 3);
+        assert_eq!(stats.queued(), 0);
+        assert_eq!(stats.threads(), 1);
+    }
+
+    #[test]
+    fn test_dedicated_pool() {
+        let pool = BlockingPool::dedicated(2, Some(CoreSet::new(vec![0])));
+        let (sender, receiver) = mpsc::channel();
+        for _ in 0..4 {
+            let sender = sender.clone();
+            pool.execute(Box::new(move || {
+                let name = thread::current().name().map(str::to_string);
+                sender.send(name).unwrap();
+            }));
+        }
+
+        for _ in 0..4 {
+            let name = receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+            assert_eq!(name.as_deref(), Some("bastion-dedicated"));
+        }
+        assert_eq!(pool.stats().threads(), 2);
+
+        let shared = pool.shared.clone();
+        drop(pool);
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(shared.state.lock().unwrap().threads, 0);
+    }
+}
@@ -4,7 +4,7 @@ use crate::envelope::Envelope;
 use crate::message::BastionMessage;
 use crate::path::{BastionPath, BastionPathElement};
 use crate::supervisor::SupervisorRef;
-use crate::system::SYSTEM;
+use crate::system;
 use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
 use futures::prelude::*;
 use fxhash::FxHashMap;
@@ -230,7 +230,7 @@ impl Parent {
         match self {
             // FIXME
             Parent::None => unimplemented!(),
-            Parent::System => SYSTEM
+            Parent::System => system::current()
                 .sender()
                 .unbounded_send(env)
                 .map_err(|err| err.into_inner()),
@@ -1,10 +1,13 @@
+use crate::supervisor::ChildFailure;
+use crate::time;
 use std::fmt::{self, Debug, Formatter};
 use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub(crate) enum CallbackType {
-    AfterRestart,
+    AfterRestart(RestartContext),
     AfterStop,
     BeforeRestart,
     BeforeStart,
@@ -64,9 +67,59 @@ pub struct Callbacks {
     after_start: Option<Arc<dyn Fn() + Send + Sync>>,
     before_restart: Option<Arc<dyn Fn() + Send + Sync>>,
     after_restart: Option<Arc<dyn Fn() + Send + Sync>>,
+    after_restart_context: Option<Arc<dyn Fn(&RestartContext) + Send + Sync>>,
     after_stop: Option<Arc<dyn Fn() + Send + Sync>>,
 }
 
+#[derive(Debug, Clone)]
+/// Describes the restart of a [`Supervisor`] or of an element of a
+/// [`Children`], given to the callback defined using
+/// [`Callbacks::with_after_restart_context`].
+///
+/// [`Supervisor`]: crate::supervisor::Supervisor
+/// [`Children`]: crate::children::Children
+pub struct RestartContext {
+    restart_count: usize,
+    last_failure: Option<ChildFailure>,
+    downtime: Duration,
+}
+
+impl RestartContext {
+    pub(crate) fn new(restart_count: usize, last_failure: Option<ChildFailure>) -> Self {
+        let downtime = last_failure
+            .as_ref()
+            .map(|failure| time::since(failure.failed_at()))
+            .unwrap_or_default();
+
+        RestartContext {
+            restart_count,
+            last_failure,
+            downtime,
+        }
+    }
+
+    /// Returns how many times the restarted element was restarted,
+    /// including this restart.
+    pub fn restart_count(&self) -> usize {
+        self.restart_count
+    }
+
+    /// Returns the failure that made the supervisor restart the
+    /// element, if it is known. Depending on the supervision
+    /// strategy, it can be the failure of another element.
+    pub fn last_failure(&self) -> Option<&ChildFailure> {
+        self.last_failure.as_ref()
+    }
+
+    /// Returns how long passed between the failure that made the
+    /// supervisor restart the element and the restart (including
+    /// the restart strategy's delay), or zero if the failure isn't
+    /// known.
+    pub fn downtime(&self) -> Duration {
+        self.downtime
+    }
+}
+
 impl Callbacks {
     /// Creates a new instance of `Callbacks` for
     /// [`Supervisor::with_callbacks`] or [`Children::with_callbacks`].
@@ -409,6 +462,73 @@ impl Callbacks {
         self
     }
 
+    /// Sets the method that will get called instead of the one
+    /// defined using [`with_after_restart`], with a [`RestartContext`]
+    /// describing the restart (how many times the element was
+    /// restarted, the failure that caused the restart and how long
+    /// the element was down).
+    ///
+    /// This allows to initialize the restarted element differently
+    /// than on its first start (e.g. to recover from a snapshot).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # Bastion::supervisor(|supervisor| {
+    /// supervisor.children(|children| {
+    ///     let callbacks = Callbacks::new()
+    ///         .with_before_start(|| println!("Children group started."))
+    ///         .with_after_restart_context(|ctx: &RestartContext| {
+    ///             println!(
+    ///                 "Children group restarted {} times, after {:?} of downtime.",
+    ///                 ctx.restart_count(),
+    ///                 ctx.downtime()
+    ///             );
+    ///         });
+    ///
+    ///     children
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///         })
+    ///         .with_callbacks(callbacks)
+    /// })
+    /// # }).unwrap();
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`with_after_restart`]: Self::with_after_restart
+    pub fn with_after_restart_context<C>(mut self, after_restart: C) -> Self
+    where
+        C: Fn(&RestartContext) + Send + Sync + 'static,
+    {
+        let after_restart = Arc::new(after_restart);
+        self.after_restart_context = Some(after_restart);
+        self
+    }
+
     /// Sets the method that will get called after the [`Supervisor`]
     /// or [`Children`] is stopped or killed if:
     /// - the supervisor of the supervised element using this callback
@@ -521,7 +641,8 @@ impl Callbacks {
         self.before_restart.is_some()
     }
 
-    /// Returns whether a callback was defined using [`with_after_restart`].
+    /// Returns whether a callback was defined using [`with_after_restart`]
+    /// or [`with_after_restart_context`].
     ///
     /// # Example
     ///
@@ -535,8 +656,9 @@ impl Callbacks {
     /// ```
     ///
     /// [`with_after_restart`]: Self::with_after_restart
+    /// [`with_after_restart_context`]: Self::with_after_restart_context
     pub fn has_after_restart(&self) -> bool {
-        self.after_restart.is_some()
+        self.after_restart.is_some() || self.after_restart_context.is_some()
     }
 
     /// Returns whether a callback was defined using [`with_after_stop`].
@@ -577,8 +699,10 @@ impl Callbacks {
         }
     }
 
-    pub(crate) fn after_restart(&self) {
-        if let Some(after_restart) = &self.after_restart {
+    pub(crate) fn after_restart(&self, context: &RestartContext) {
+        if let Some(after_restart) = &self.after_restart_context {
+            after_restart(context)
+        } else if let Some(after_restart) = &self.after_restart {
             after_restart()
         } else {
             self.before_start()
@@ -598,6 +722,10 @@ impl Debug for Callbacks {
             .field("before_start", &self.before_start.is_some())
             .field("before_restart", &self.before_start.is_some())
             .field("after_restart", &self.before_start.is_some())
+            .field(
+                "after_restart_context",
+                &self.after_restart_context.is_some(),
+            )
             .field("after_stop", &self.before_start.is_some())
             .finish()
     }
@@ -1,27 +1,36 @@
 //!
 //! Child is a element of Children group executing user-defined computation
+use crate::blocking_pool::BlockingPool;
 use crate::broadcast::Broadcast;
 use crate::callbacks::{CallbackType, Callbacks};
 use crate::child_ref::ChildRef;
 use crate::context::{BastionContext, BastionId, ContextState};
 use crate::envelope::Envelope;
+use crate::events::SystemEvent;
+use crate::executor::{self, Deadline};
+use crate::health::FailureKind;
 use crate::message::BastionMessage;
+use crate::monitor::{DownReason, MONITORS};
 #[cfg(feature = "scaling")]
 use crate::resizer::ActorGroupStats;
-use crate::system::SYSTEM;
+use crate::supervisor::{ChildFailure, FailureReason};
+use crate::system;
+use crate::time::{self, Instant};
 use anyhow::Result as AnyResult;
 
-use bastion_executor::pool;
 use futures::pending;
 use futures::poll;
 use futures::prelude::*;
 use lightproc::prelude::*;
 use lightproc::proc_state::EmptyProcState;
+use std::any::Any;
 use std::fmt::{self, Debug, Formatter};
 use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
+use std::time::Duration;
 use tracing::{debug, error, trace, warn};
 
 pub(crate) struct Init(pub(crate) Box<dyn Fn(BastionContext) -> Exec + Send>);
@@ -46,6 +55,32 @@ pub(crate) struct Child {
     pre_start_msgs: Vec<Envelope>,
     // A shortcut for accessing to this actor by others.
     child_ref: ChildRef,
+    // The message of the panic that made the child's future
+    // fail, given to its supervisor.
+    panic_message: Arc<Mutex<Option<String>>>,
+    // How long the child's future can keep running to finish its
+    // in-flight work once the child is asked to stop.
+    stop_timeout: Option<Duration>,
+    // When the child will be killed if its future didn't finish,
+    // set once it is asked to stop.
+    stop_deadline: Option<Deadline>,
+    // How long the child's future can run before being considered
+    // failed.
+    exec_timeout: Option<Duration>,
+    // When the child's future will be considered failed, set once
+    // the child is started and unset while it is suspended.
+    exec_deadline: Option<Deadline>,
+    // When `exec_deadline` elapses.
+    exec_ends_at: Option<Instant>,
+    // How long the child's future can still run before being
+    // considered failed, while `exec_deadline` is paused.
+    exec_left: Option<Duration>,
+    // The threads dedicated to the child's group, running its future
+    // instead of the shared pool if set.
+    executor: Option<Arc<BlockingPool>>,
+    // Whether the child's future is paused. Messages sent to a
+    // suspended child stay in its mailbox until it is resumed.
+    suspended: bool,
     started: bool,
 }
 
@@ -77,6 +112,15 @@ impl Child {
         debug!("Child({}): Initializing.", bcast.id());
         let pre_start_msgs = Vec::new();
         let started = false;
+        let panic_message = Arc::new(Mutex::new(None));
+        let stop_timeout = None;
+        let stop_deadline = None;
+        let exec_timeout = None;
+        let exec_deadline = None;
+        let exec_ends_at = None;
+        let exec_left = None;
+        let executor = None;
+        let suspended = false;
 
         Child {
             bcast,
@@ -85,10 +129,50 @@ impl Child {
             state,
             pre_start_msgs,
             child_ref,
+            panic_message,
+            stop_timeout,
+            stop_deadline,
+            exec_timeout,
+            exec_deadline,
+            exec_ends_at,
+            exec_left,
+            executor,
+            suspended,
             started,
         }
     }
 
+    pub(crate) fn with_stop_timeout(mut self, stop_timeout: Option<Duration>) -> Self {
+        self.stop_timeout = stop_timeout;
+        self
+    }
+
+    pub(crate) fn with_exec_timeout(mut self, exec_timeout: Option<Duration>) -> Self {
+        self.exec_timeout = exec_timeout;
+        self
+    }
+
+    pub(crate) fn with_executor(mut self, executor: Option<Arc<BlockingPool>>) -> Self {
+        self.executor = executor;
+        self
+    }
+
+    // Stops the exec timeout from elapsing while the child is
+    // suspended.
+    fn pause_exec_deadline(&mut self) {
+        if let Some(ends_at) = self.exec_ends_at.take() {
+            self.exec_deadline = None;
+            self.exec_left = Some(ends_at.saturating_duration_since(time::now()));
+        }
+    }
+
+    fn resume_exec_deadline(&mut self) {
+        if let Some(left) = self.exec_left.take() {
+            self.exec_ends_at = Some(time::now() + left);
+            self.exec_deadline = Some(executor::deadline(left));
+        }
+    }
+
     fn stack(&self) -> ProcStack {
         trace!("Child({}): Creating ProcStack.", self.id());
         let id = self.bcast.id().clone();
@@ -99,22 +183,39 @@ impl Child {
 
         let parent_inner = self.bcast.parent().clone().into_children();
         let child_ref_inner = self.child_ref.clone();
+        let panic_message = self.panic_message.clone();
 
         // FIXME: with_pid
         ProcStack::default().with_after_panic(move |_state: &mut EmptyProcState| {
             warn!("Child({}): Panicked.", id);
+            parent.group_health().record_failure(FailureKind::Panicked);
+            system::current()
+                .events()
+                .publish(SystemEvent::ChildFailed {
+                    group: parent.id().clone(),
+                    child: id.clone(),
+                    reason: FailureKind::Panicked,
+                });
 
             if let Some(parent) = &parent_inner {
                 let used_dispatchers = parent.dispatchers();
-                let global_dispatcher = SYSTEM.dispatcher();
+                let global_dispatcher = system::current().dispatcher();
                 global_dispatcher.remove(used_dispatchers, &child_ref_inner);
             }
 
             let id = id.clone();
-            let msg = BastionMessage::restart_required(id, parent.id().clone());
+            let failure = ChildFailure::new(
+                id.clone(),
+                Some(child_ref_inner.clone()),
+                FailureReason::Child(FailureKind::Panicked),
+            )
+            .with_message(panic_message.lock().unwrap().take());
+            let msg = BastionMessage::restart_required(id, parent.id().clone(), failure);
             let env = Envelope::new(msg, path.clone(), sender.clone());
             // TODO: handle errors
             parent.send(env).ok();
+
+            MONITORS.notify(&child_ref_inner, DownReason::Failed(FailureKind::Panicked));
         })
     }
 
@@ -122,26 +223,65 @@ impl Child {
         self.bcast.id()
     }
 
-    fn stopped(&mut self) {
+    fn stopped(&mut self, reason: DownReason) {
         debug!("Child({}): Stopped.", self.id());
         self.remove_from_dispatchers();
         let _ = self.remove_from_distributors();
+        self.state.release_permit();
         self.bcast.stopped();
+        MONITORS.notify(&self.child_ref, reason);
+    }
+
+    // Stops the child without it being restarted, handing the value
+    // it exited with (if any) to its group.
+    fn completed(&mut self) {
+        let parent = self.bcast.parent().clone().into_children().unwrap();
+        parent
+            .group_results()
+            .complete(self.id().clone(), self.state.take_result());
+        self.stopped(DownReason::Stopped);
     }
 
-    fn faulted(&mut self) {
+    async fn finish_stopping(&mut self) {
+        self.stopped(DownReason::Stopped);
+
+        #[cfg(feature = "scaling")]
+        self.cleanup_actors_stats().await;
+
+        self.callbacks.after_stop();
+    }
+
+    fn faulted(&mut self, kind: FailureKind, message: Option<String>) {
         debug!("Child({}): Faulted.", self.id());
         self.remove_from_dispatchers();
         let _ = self.remove_from_distributors();
+        self.state.release_permit();
 
         let parent = self.bcast.parent().clone().into_children().unwrap();
         let path = self.bcast.path().clone();
         let sender = self.bcast.sender().clone();
 
-        let msg = BastionMessage::restart_required(self.id().clone(), parent.id().clone());
+        parent.group_health().record_failure(kind);
+        system::current()
+            .events()
+            .publish(SystemEvent::ChildFailed {
+                group: parent.id().clone(),
+                child: self.id().clone(),
+                reason: kind,
+            });
+
+        let failure = ChildFailure::new(
+            self.id().clone(),
+            Some(self.child_ref.clone()),
+            FailureReason::Child(kind),
+        )
+        .with_message(message);
+        let msg = BastionMessage::restart_required(self.id().clone(), parent.id().clone(), failure);
         let env = Envelope::new(msg, path, sender);
         // TODO: handle errors
         parent.send(env).ok();
+
+        MONITORS.notify(&self.child_ref, DownReason::Failed(kind));
     }
 
     async fn handle(&mut self, env: Envelope) -> Result<(), ()> {
@@ -154,19 +294,25 @@ impl Child {
                 msg: BastionMessage::Stop,
                 ..
             } => {
-                self.stopped();
-
-                #[cfg(feature = "scaling")]
-                self.cleanup_actors_stats().await;
+                if let Some(stop_timeout) = self.stop_timeout {
+                    if self.started && self.stop_deadline.is_none() {
+                        debug!("Child({}): Stopping within {:?}.", self.id(), stop_timeout);
+                        // A suspended child gets to finish its work too.
+                        self.suspended = false;
+                        self.state.stop();
+                        self.stop_deadline = Some(executor::deadline(stop_timeout));
+                        return Ok(());
+                    }
+                }
 
-                self.callbacks.after_stop();
+                self.finish_stopping().await;
                 return Err(());
             }
             Envelope {
                 msg: BastionMessage::Kill,
                 ..
             } => {
-                self.stopped();
+                self.stopped(DownReason::Killed);
 
                 #[cfg(feature = "scaling")]
                 self.cleanup_actors_stats().await;
@@ -245,6 +391,59 @@ impl Child {
                 msg: BastionMessage::Heartbeat,
                 ..
             } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::Suspend,
+                ..
+            } => {
+                debug!("Child({}): Suspending.", self.id());
+                self.suspended = true;
+                self.pause_exec_deadline();
+            }
+            Envelope {
+                msg: BastionMessage::Resume,
+                ..
+            } => {
+                debug!("Child({}): Resuming.", self.id());
+                self.suspended = false;
+                self.resume_exec_deadline();
+            }
+            Envelope {
+                msg: BastionMessage::RetireChild { .. },
+                ..
+            } => {
+                // The group stops the child once it shrunk.
+                debug!("Child({}): Asking the group to retire it.", self.id());
+                self.bcast.send_parent(env).ok();
+            }
+            Envelope {
+                msg: BastionMessage::LinkFailed { id },
+                ..
+            } => {
+                warn!("Child({}): Linked Child({}) failed.", self.id(), id);
+                let message = format!("linked Child({}) failed", id);
+                self.faulted(FailureKind::Linked, Some(message));
+                return Err(());
+            }
+            Envelope {
+                msg: BastionMessage::ReplaceExec(_),
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::RestartChild { .. },
+                ..
+            } => {
+                // The group stops the child and restarts it.
+                debug!("Child({}): Asking the group to restart it.", self.id());
+                self.bcast.send_parent(env).ok();
+            }
+            Envelope {
+                msg: BastionMessage::KillWithReason { reason },
+                ..
+            } => {
+                warn!("Child({}): Killed: {}", self.id(), reason);
+                self.faulted(FailureKind::Killed, Some(reason));
+                return Err(());
+            }
         }
 
         Ok(())
@@ -257,8 +456,16 @@ impl Child {
             BastionMessage::Start
         );
         debug!("Child({}): Starting.", self.id());
+        if self.recover().await.is_err() {
+            return Err(());
+        }
+
         self.callbacks.before_start();
         self.started = true;
+        self.exec_left = self.exec_timeout;
+        if !self.suspended {
+            self.resume_exec_deadline();
+        }
 
         let msgs = self.pre_start_msgs.drain(..).collect::<Vec<_>>();
         self.pre_start_msgs.shrink_to_fit();
@@ -277,11 +484,43 @@ impl Child {
         Ok(())
     }
 
+    // Replays the persisted events of the child to its state before
+    // it handles any message (see `Children::with_recovery`), the
+    // child being faulted if they couldn't be.
+    async fn recover(&mut self) -> Result<(), ()> {
+        if !self.state.needs_recovery() {
+            return Ok(());
+        }
+
+        debug!("Child({}): Recovering its state.", self.id());
+        let state = self.state.clone();
+        let error = match executor::blocking(async move { state.recover_local() }).await {
+            Some(Ok(sequence_nr)) => {
+                debug!(
+                    "Child({}): Recovered its state up to event {}.",
+                    self.id(),
+                    sequence_nr
+                );
+                return Ok(());
+            }
+            Some(Err(error)) => error.to_string(),
+            None => "the recovery handler panicked".to_string(),
+        };
+
+        warn!(
+            "Child({}): Couldn't recover its state: {}",
+            self.id(),
+            error
+        );
+        self.faulted(FailureKind::Errored, Some(error));
+        Err(())
+    }
+
     fn apply_callback(&mut self, callback_type: CallbackType) {
         match callback_type {
             CallbackType::BeforeStart => self.callbacks.before_start(),
             CallbackType::BeforeRestart => self.callbacks.before_restart(),
-            CallbackType::AfterRestart => self.callbacks.after_restart(),
+            CallbackType::AfterRestart(context) => self.callbacks.after_restart(&context),
             CallbackType::AfterStop => self.callbacks.after_stop(),
             CallbackType::AfterStart => self.callbacks.after_start(),
         }
@@ -314,6 +553,14 @@ impl Child {
         };
 
         self.callbacks.after_start();
+        if let Some(parent) = self.bcast.parent().clone().into_children() {
+            system::current()
+                .events()
+                .publish(SystemEvent::ChildStarted {
+                    group: parent.id().clone(),
+                    child: self.id().clone(),
+                });
+        }
 
         loop {
             #[cfg(feature = "scaling")]
@@ -365,34 +612,84 @@ impl Child {
             #[cfg(feature = "scaling")]
             self.update_stats().await;
 
-            if !self.started {
+            if !self.started || self.suspended {
                 pending!();
 
                 continue;
             }
 
-            match poll!(&mut self.exec) {
-                Poll::Ready(Ok(())) => {
+            self.state.tick();
+
+            self.state.start_polling();
+            #[cfg(feature = "tracing")]
+            self.state.message_span().enter(self.bcast.path());
+            let poll = poll!(AssertUnwindSafe(&mut self.exec).catch_unwind());
+            #[cfg(feature = "tracing")]
+            self.state.message_span().exit();
+            self.state.check_in();
+
+            match poll {
+                Poll::Ready(Err(payload)) => {
+                    // Keeps the panic's message for the supervisor and
+                    // lets the panic propagate to the process' handler.
+                    *self.panic_message.lock().unwrap() = panic_message(&*payload);
+                    panic::resume_unwind(payload);
+                }
+                Poll::Ready(Ok(_)) if self.stop_deadline.is_some() => {
+                    debug!("Child({}): The future finished before stopping.", self.id());
+                    return self.finish_stopping().await;
+                }
+                Poll::Ready(Ok(Ok(()))) => {
                     debug!(
                         "Child({}): The future finished executing successfully.",
                         self.id()
                     );
-                    return self.stopped();
+                    return self.completed();
                 }
-                Poll::Ready(Err(())) => {
+                Poll::Ready(Ok(Err(()))) => {
                     warn!("Child({}): The future returned an error.", self.id());
-                    return self.faulted();
+                    return self.faulted(FailureKind::Errored, None);
+                }
+                Poll::Pending if self.state.has_result() => {
+                    debug!("Child({}): The future stopped with a result.", self.id());
+                    return self.completed();
                 }
                 Poll::Pending => (),
             }
 
+            if let (Some(exec_deadline), None) = (&mut self.exec_deadline, &self.stop_deadline) {
+                if let Poll::Ready(()) = poll!(exec_deadline) {
+                    warn!(
+                        "Child({}): The future didn't finish within the exec timeout.",
+                        self.id()
+                    );
+                    let message = self
+                        .exec_timeout
+                        .map(|timeout| format!("didn't finish within {:?}", timeout));
+                    return self.faulted(FailureKind::TimedOut, message);
+                }
+            }
+
+            if let Some(stop_deadline) = &mut self.stop_deadline {
+                if let Poll::Ready(()) = poll!(stop_deadline) {
+                    warn!(
+                        "Child({}): The future didn't finish within the stop timeout.",
+                        self.id()
+                    );
+                    return self.finish_stopping().await;
+                }
+            }
+
             pending!();
         }
     }
 
     pub(crate) fn launch(self) -> RecoverableHandle<()> {
         let stack = self.stack();
-        pool::spawn(self.run(), stack)
+        match self.executor.clone() {
+            Some(pool) => executor::spawn_on(&pool, self.run(), stack),
+            None => executor::spawn_with(self.run(), stack),
+        }
     }
 
     /// Adds the actor into each registry declared in the parent node.
@@ -401,7 +698,7 @@ impl Child {
             let child_ref = self.child_ref.clone();
             let used_dispatchers = parent.dispatchers();
 
-            let global_dispatcher = SYSTEM.dispatcher();
+            let global_dispatcher = system::current().dispatcher();
             // FIXME: Pass the module name explicitly?
             let module_name = module_path!().to_string();
             global_dispatcher.register(used_dispatchers, &child_ref, module_name)?;
@@ -415,7 +712,7 @@ impl Child {
             let child_ref = self.child_ref.clone();
             let distributors = parent.distributors();
 
-            let global_dispatcher = SYSTEM.dispatcher();
+            let global_dispatcher = system::current().dispatcher();
             distributors
                 .iter()
                 .map(|&distributor| {
@@ -426,13 +723,19 @@ impl Child {
         Ok(())
     }
 
-    /// Cleanup the actor's record from each declared distributor.
+    /// Cleanup the actor's record from each declared distributor,
+    /// and from the topics it subscribed to.
     fn remove_from_distributors(&self) -> AnyResult<()> {
+        let global_dispatcher = system::current().dispatcher();
+        let topics = self.state.take_topics();
+        if !topics.is_empty() {
+            global_dispatcher.remove_recipient(&topics, self.child_ref.clone())?;
+        }
+
         if let Some(parent) = self.bcast.parent().clone().into_children() {
             let child_ref = self.child_ref.clone();
             let distributors = parent.distributors();
 
-            let global_dispatcher = SYSTEM.dispatcher();
             global_dispatcher.remove_recipient(distributors, child_ref)?;
         }
         Ok(())
@@ -444,7 +747,7 @@ impl Child {
             let child_ref = self.child_ref.clone();
             let used_dispatchers = parent.dispatchers();
 
-            let global_dispatcher = SYSTEM.dispatcher();
+            let global_dispatcher = system::current().dispatcher();
             global_dispatcher.remove(used_dispatchers, &child_ref);
         }
     }
@@ -458,6 +761,16 @@ impl Child {
     }
 }
 
+// Returns the message of a panic, if it was created with a
+// string (like with `panic!` or `unwrap`).
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> Option<String> {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        Some(message.to_string())
+    } else {
+        payload.downcast_ref::<String>().cloned()
+    }
+}
+
 impl Future for Exec {
     type Output = Result<(), ()>;
 
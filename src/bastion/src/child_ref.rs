@@ -2,27 +2,52 @@
 //! Allows users to communicate with Child through the mailboxes.
 use crate::context::BastionId;
 use crate::envelope::{Envelope, RefAddr};
+use crate::limits::{InflightLimit, InflightPermit, MailboxLimit, MailboxReservation};
 use crate::message::{Answer, BastionMessage, Message};
+use crate::monitor::{MonitorRef, MONITORS};
 use crate::path::BastionPath;
 use crate::{broadcast::Sender, prelude::SendError};
+use futures::future;
+use serde::{Deserialize, Serialize};
 use std::cmp::{Eq, PartialEq};
 use std::fmt::Debug;
 use std::hash::{Hash, Hasher};
 use std::sync::Arc;
+use std::task::Poll;
 use tracing::{debug, trace};
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// The address of an element of a children group, made of its
+/// identifier and the identifiers of the supervisors and children
+/// group it belongs to.
+///
+/// Contrary to a [`ChildRef`], a `ChildAddr` can be serialized (e.g.
+/// to be stored in a database or sent over the network) and then be
+/// resolved back into a [`ChildRef`] using [`Bastion::resolve`],
+/// for as long as the element's children group is running.
+///
+/// [`Bastion::resolve`]: crate::Bastion::resolve
+pub struct ChildAddr {
+    parent_chain: Vec<BastionId>,
+    id: BastionId,
+}
+
 #[derive(Debug, Clone)]
 /// A "reference" to an element of a children group, allowing to
 /// communicate with it.
 pub struct ChildRef {
     id: BastionId,
     sender: Sender,
-    name: String,
+    name: Arc<str>,
     path: Arc<BastionPath>,
     // True if the ChildRef references a child that will receive user defined messages.
     // use `ChildRef::new_internal` to set it to false, for internal use children,
     // such as the heartbeat children for example
     is_public: bool,
+    // The limit of asks waiting for an answer of the child's group.
+    inflight_limit: Option<Arc<InflightLimit>>,
+    // The depth and capacity of the child's mailbox.
+    mailbox: Option<Arc<MailboxLimit>>,
 }
 
 impl ChildRef {
@@ -35,9 +60,11 @@ impl ChildRef {
         ChildRef {
             id,
             sender,
-            name,
+            name: name.into(),
             path,
             is_public: false,
+            inflight_limit: None,
+            mailbox: None,
         }
     }
 
@@ -50,9 +77,64 @@ impl ChildRef {
         ChildRef {
             id,
             sender,
-            name,
+            name: name.into(),
             path,
             is_public: true,
+            inflight_limit: None,
+            mailbox: None,
+        }
+    }
+
+    pub(crate) fn with_inflight_limit(mut self, limit: Option<Arc<InflightLimit>>) -> Self {
+        self.inflight_limit = limit;
+        self
+    }
+
+    pub(crate) fn with_mailbox(mut self, mailbox: Option<Arc<MailboxLimit>>) -> Self {
+        self.mailbox = mailbox;
+        self
+    }
+
+    // Whether the child's mailbox is full and its group refuses the
+    // messages sent to it.
+    pub(crate) fn refuses_messages(&self) -> bool {
+        match &self.mailbox {
+            Some(mailbox) => mailbox.refuses_senders(),
+            None => false,
+        }
+    }
+
+    // Reserves room for the given amount of messages in the child's
+    // mailbox, failing if its group would refuse them.
+    pub(crate) fn reserve(&self, count: usize) -> Option<MailboxReservation> {
+        match &self.mailbox {
+            Some(mailbox) => mailbox.try_reserve(count),
+            None => Some(MailboxReservation::unbounded()),
+        }
+    }
+
+    // Reserves room in the child's mailbox for the message carried
+    // by the envelope until it is enqueued, if its group applies
+    // backpressure, failing if there is no room left.
+    fn reserve_room(&self, env: &mut Envelope) -> Result<(), ()> {
+        let (mailbox, msg) = match (&self.mailbox, &mut env.msg) {
+            (Some(mailbox), BastionMessage::Message(msg)) if mailbox.applies_backpressure() => {
+                (mailbox, msg)
+            }
+            _ => return Ok(()),
+        };
+
+        let reservation = mailbox.try_reserve(1).ok_or(())?;
+        msg.set_reservation(reservation);
+        Ok(())
+    }
+
+    // Counts an ask sent to the child, failing if its group reached
+    // its limit of asks waiting for an answer.
+    pub(crate) fn inflight_permit(&self) -> Result<Option<InflightPermit>, ()> {
+        match &self.inflight_limit {
+            Some(limit) => limit.try_acquire().map(Some).ok_or(()),
+            None => Ok(None),
         }
     }
 
@@ -145,6 +227,125 @@ impl ChildRef {
         self.is_public
     }
 
+    /// Returns the amount of messages waiting in the mailbox of the
+    /// child this `ChildRef` is referencing.
+    ///
+    /// Note that messages that were just sent to the child might not
+    /// be counted yet.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx| {
+    ///         async move {
+    ///             let backlog: usize = ctx.current().mailbox_len();
+    ///             # Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn mailbox_len(&self) -> usize {
+        match &self.mailbox {
+            Some(mailbox) => mailbox.len(),
+            None => 0,
+        }
+    }
+
+    /// Waits until the mailbox of the child this `ChildRef` is
+    /// referencing has room for a new message (counting the messages
+    /// sent to it that weren't enqueued yet when its group applies
+    /// backpressure), returning right away if its capacity isn't
+    /// limited (see [`Children::with_mailbox_capacity`]).
+    ///
+    /// This allows senders to slow down when the children group uses
+    /// [`OverflowPolicy::Backpressure`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| {
+    /// #     children
+    /// #         .with_mailbox_capacity(100)
+    /// #         .with_overflow_policy(OverflowPolicy::Backpressure)
+    /// # }).unwrap();
+    /// # let child_ref = children_ref.elems()[0].clone();
+    /// Bastion::children(|children| {
+    ///     children.with_exec(move |ctx| {
+    ///         let child_ref = child_ref.clone();
+    ///         async move {
+    ///             for i in 0..10_000 {
+    ///                 child_ref.wait_for_capacity().await;
+    ///                 child_ref.tell_anonymously(i).ok();
+    ///             }
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Children::with_mailbox_capacity`]: crate::children::Children::with_mailbox_capacity
+    /// [`OverflowPolicy::Backpressure`]: crate::children::OverflowPolicy::Backpressure
+    pub async fn wait_for_capacity(&self) {
+        let mailbox = match &self.mailbox {
+            Some(mailbox) => mailbox,
+            None => return,
+        };
+
+        future::poll_fn(|cx| {
+            if mailbox.has_room() {
+                return Poll::Ready(());
+            }
+
+            trace!("ChildRef({}): Waiting for room in the mailbox.", self.id());
+            mailbox.park(cx.waker());
+            Poll::Pending
+        })
+        .await
+    }
+
     /// Sends a message to the child this `ChildRef` is referencing.
     /// This message is intended to be used outside of Bastion context when
     /// there is no way for receiver to identify message sender
@@ -209,6 +410,10 @@ impl ChildRef {
     /// ```
     pub fn tell_anonymously<M: Message>(&self, msg: M) -> Result<(), M> {
         debug!("ChildRef({}): Telling message: {:?}", self.id(), msg);
+        if self.refuses_messages() {
+            debug!("ChildRef({}): Mailbox full.", self.id());
+            return Err(msg);
+        }
         let msg = BastionMessage::tell(msg);
         let env = Envelope::from_dead_letters(msg);
         // FIXME: panics?
@@ -373,12 +578,23 @@ impl ChildRef {
     /// ```
     pub fn ask_anonymously<M: Message>(&self, msg: M) -> Result<Answer, M> {
         debug!("ChildRef({}): Asking message: {:?}", self.id(), msg);
+        if self.refuses_messages() {
+            debug!("ChildRef({}): Mailbox full.", self.id());
+            return Err(msg);
+        }
+        let permit = match self.inflight_permit() {
+            Ok(permit) => permit,
+            Err(()) => {
+                debug!("ChildRef({}): Too many asks in flight.", self.id());
+                return Err(msg);
+            }
+        };
         let (msg, answer) = BastionMessage::ask(msg, self.addr());
         let env = Envelope::from_dead_letters(msg);
         // FIXME: panics?
         self.send(env).map_err(|env| env.into_msg().unwrap())?;
 
-        Ok(answer)
+        Ok(answer.with_permit(permit))
     }
 
     /// Try to send a message to the child this `ChildRef` is referencing,
@@ -472,7 +688,11 @@ impl ChildRef {
         debug!("ChildRef({}): Try Asking message: {:?}", self.id(), msg);
         let (msg, answer) = BastionMessage::ask(msg, self.addr());
         let env = Envelope::from_dead_letters(msg);
-        self.try_send(env).map(|_| answer)
+        let permit = match self.inflight_permit() {
+            Ok(permit) => permit,
+            Err(()) => return Err(SendError::inflight_limit(env)),
+        };
+        self.try_send(env).map(|_| answer.with_permit(permit))
     }
 
     /// Sends a message to the child this `ChildRef` is referencing
@@ -575,20 +795,359 @@ impl ChildRef {
         self.send(env).map_err(|_| ())
     }
 
+    /// Sends a message to the child this `ChildRef` is referencing
+    /// to tell it to suicide, reporting it to its supervisor as a
+    /// failure (with [`FailureKind::Killed`]) described by `reason`.
+    ///
+    /// Unlike with [`kill`], the supervisor handles the child as if
+    /// it had failed: the reason is available as the message of the
+    /// [`ChildFailure`] given to the callback set with
+    /// [`Supervisor::with_on_child_failure`] and through
+    /// [`RestartContext::last_failure`], and the child is restarted
+    /// following the supervisor's restart strategy.
+    ///
+    /// This method returns `()` if it succeeded, or `Err(())`
+    /// otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - Why the child is killed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children).unwrap();
+    /// # let child_ref = &children_ref.elems()[0];
+    /// child_ref
+    ///     .kill_with_reason("leaking file descriptors")
+    ///     .expect("Couldn't send the message.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`kill`]: Self::kill
+    /// [`FailureKind::Killed`]: crate::health::FailureKind::Killed
+    /// [`ChildFailure`]: crate::supervisor::ChildFailure
+    /// [`Supervisor::with_on_child_failure`]: crate::supervisor::Supervisor::with_on_child_failure
+    /// [`RestartContext::last_failure`]: crate::callbacks::RestartContext::last_failure
+    pub fn kill_with_reason(&self, reason: impl Into<String>) -> Result<(), ()> {
+        let reason = reason.into();
+        debug!("ChildRef({}): Killing: {}", self.id(), reason);
+        let msg = BastionMessage::kill_with_reason(reason);
+        let env = Envelope::from_dead_letters(msg);
+        self.send(env).map_err(|_| ())
+    }
+
+    /// Sends a message to the child this `ChildRef` is referencing
+    /// to tell its children group to stop it and restart it, without
+    /// restarting the other elements of the group.
+    ///
+    /// The child is given the group's stop timeout (if any) to
+    /// finish its in-flight work (see
+    /// [`Children::with_stop_timeout`]), and is then restarted with
+    /// the messages left in its mailbox and its state (see
+    /// [`Children::with_state`]). The restart isn't reported as a
+    /// failure and doesn't count towards the restart limits of the
+    /// supervisor.
+    ///
+    /// Note that the child keeps its identifier but the `ChildRef`s
+    /// referencing it need to be retrieved again (e.g. using
+    /// [`ChildrenRef::elems`]) once it is restarted.
+    ///
+    /// This method returns `()` if it succeeded, or `Err(())`
+    /// otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children).unwrap();
+    /// # let child_ref = &children_ref.elems()[0];
+    /// child_ref.restart().expect("Couldn't send the message.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Children::with_stop_timeout`]: crate::children::Children::with_stop_timeout
+    /// [`Children::with_state`]: crate::children::Children::with_state
+    /// [`ChildrenRef::elems`]: crate::children_ref::ChildrenRef::elems
+    pub fn restart(&self) -> Result<(), ()> {
+        debug!("ChildRef({}): Restarting.", self.id());
+        let msg = BastionMessage::restart_child(self.id.clone());
+        let env = Envelope::from_dead_letters(msg);
+        self.send(env).map_err(|_| ())
+    }
+
+    /// Sends a message to the child this `ChildRef` is referencing
+    /// to retire it from its children group: the group shrinks by
+    /// one element and stops the child, which is then removed from
+    /// the group's dispatchers and distributors and from its
+    /// supervisor without being restarted (even if the whole group
+    /// is restarted later on).
+    ///
+    /// Note that if the group is resized automatically, its resizer
+    /// can still launch new elements later on.
+    ///
+    /// This method returns `()` if it succeeded, or `Err(())`
+    /// otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children.with_redundancy(3)).unwrap();
+    /// # let child_ref = &children_ref.elems()[0];
+    /// // The group now has two elements.
+    /// child_ref.stop_permanently().expect("Couldn't send the message.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn stop_permanently(&self) -> Result<(), ()> {
+        debug!("ChildRef({}): Retiring.", self.id());
+        let msg = BastionMessage::retire_child(self.id.clone());
+        let env = Envelope::from_dead_letters(msg);
+        self.send(env).map_err(|_| ())
+    }
+
+    /// Makes the child this `ChildRef` is referencing monitor the
+    /// child referenced by `other`: once `other` terminates (it is
+    /// stopped, killed or fails), a [`Down`] message describing why
+    /// is sent to this child's mailbox.
+    ///
+    /// The monitor only lasts until `other` terminates, even if it
+    /// is then restarted, and can be cancelled using
+    /// [`MonitorRef::demonitor`]. Note that no message is sent if
+    /// `other` already terminated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children.with_redundancy(2)).unwrap();
+    /// # let watcher = &children_ref.elems()[0];
+    /// # let other = &children_ref.elems()[1];
+    /// let monitor = watcher.monitor(other);
+    /// // ...
+    /// monitor.demonitor();
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Down`]: crate::monitor::Down
+    /// [`MonitorRef::demonitor`]: crate::monitor::MonitorRef::demonitor
+    pub fn monitor(&self, other: &ChildRef) -> MonitorRef {
+        debug!("ChildRef({}): Monitoring Child({}).", self.id(), other.id());
+        MONITORS.monitor(self, other)
+    }
+
+    /// Links the child this `ChildRef` is referencing and the child
+    /// referenced by `other`, so that when one of them fails, the
+    /// other one fails too (with [`FailureKind::Linked`]) and is
+    /// then handled by its supervisor.
+    ///
+    /// Stopping or killing one of the children doesn't make the
+    /// other one fail. Like monitors, the link only lasts until one
+    /// of the children terminates.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children.with_redundancy(2)).unwrap();
+    /// # let watcher = &children_ref.elems()[0];
+    /// # let other = &children_ref.elems()[1];
+    /// watcher.link(other);
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`FailureKind::Linked`]: crate::health::FailureKind::Linked
+    pub fn link(&self, other: &ChildRef) {
+        debug!("ChildRef({}): Linking to Child({}).", self.id(), other.id());
+        MONITORS.link(self, other);
+    }
+
+    /// Removes the link between the child this `ChildRef` is
+    /// referencing and the child referenced by `other`, created
+    /// using [`link`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children.with_redundancy(2)).unwrap();
+    /// # let watcher = &children_ref.elems()[0];
+    /// # let other = &children_ref.elems()[1];
+    /// watcher.link(other);
+    /// // ...
+    /// watcher.unlink(other);
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`link`]: Self::link
+    pub fn unlink(&self, other: &ChildRef) {
+        debug!(
+            "ChildRef({}): Unlinking from Child({}).",
+            self.id(),
+            other.id()
+        );
+        MONITORS.unlink(self, other);
+    }
+
     /// Returns [`RefAddr`] for the child
     pub fn addr(&self) -> RefAddr {
-        RefAddr::new(self.path.clone(), self.sender.clone())
+        RefAddr::new(self.path.clone(), self.sender.clone()).with_group_name(self.name.clone())
     }
 
-    pub(crate) fn send(&self, env: Envelope) -> Result<(), Envelope> {
+    pub(crate) fn send(&self, mut env: Envelope) -> Result<(), Envelope> {
         trace!("ChildRef({}): Sending message: {:?}", self.id(), env);
+        if self.reserve_room(&mut env).is_err() {
+            debug!("ChildRef({}): Mailbox full.", self.id());
+            return Err(env);
+        }
         self.sender
             .unbounded_send(env)
             .map_err(|err| err.into_inner())
     }
 
-    pub(crate) fn try_send(&self, env: Envelope) -> Result<(), SendError> {
+    pub(crate) fn try_send(&self, mut env: Envelope) -> Result<(), SendError> {
         trace!("ChildRef({}): Sending message: {:?}", self.id(), env);
+        if self.refuses_messages() || self.reserve_room(&mut env).is_err() {
+            debug!("ChildRef({}): Mailbox full.", self.id());
+            return Err(SendError::mailbox_full(env));
+        }
+        self.sender.unbounded_send(env).map_err(Into::into)
+    }
+
+    // Sends a message which room was reserved for in the child's
+    // mailbox (see `reserve`), the message keeping its part of the
+    // reservation until it is enqueued if the group of the child
+    // applies backpressure.
+    pub(crate) fn send_reserved(
+        &self,
+        mut env: Envelope,
+        reservation: &mut MailboxReservation,
+    ) -> Result<(), SendError> {
+        trace!(
+            "ChildRef({}): Sending reserved message: {:?}",
+            self.id(),
+            env
+        );
+        if let (Some(mailbox), BastionMessage::Message(msg)) = (&self.mailbox, &mut env.msg) {
+            if mailbox.applies_backpressure() {
+                msg.set_reservation(reservation.take_one());
+            }
+        }
         self.sender.unbounded_send(env).map_err(Into::into)
     }
 
@@ -605,6 +1164,68 @@ impl ChildRef {
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    /// Returns the serializable address of the child this
+    /// `ChildRef` is referencing, which can be resolved back into a
+    /// `ChildRef` using [`Bastion::resolve`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children).unwrap();
+    /// # let child_ref = &children_ref.elems()[0];
+    /// let addr = child_ref.child_addr();
+    /// let json = serde_json::to_string(&addr).expect("Couldn't serialize the address");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Bastion::resolve`]: crate::Bastion::resolve
+    pub fn child_addr(&self) -> ChildAddr {
+        ChildAddr {
+            parent_chain: self.path.parent_chain().to_vec(),
+            id: self.id.clone(),
+        }
+    }
+}
+
+impl ChildAddr {
+    /// Returns the identifier of the child.
+    pub fn id(&self) -> &BastionId {
+        &self.id
+    }
+
+    /// Returns the identifier of the child's children group, or
+    /// `None` if the address doesn't reference an element of a
+    /// children group.
+    pub fn children_id(&self) -> Option<&BastionId> {
+        self.parent_chain.last()
+    }
+
+    /// Returns the identifiers of the supervisors and children group
+    /// the child belongs to, from the root.
+    pub fn parent_chain(&self) -> &[BastionId] {
+        &self.parent_chain
+    }
 }
 
 impl PartialEq for ChildRef {
@@ -1,31 +1,52 @@
 //!
 //! Children are a group of child supervised under a supervisor
-use crate::callbacks::{CallbackType, Callbacks};
+use crate::addresses::ADDRESSES;
+use crate::autoscale::{AutoscaleDecision, AutoscalePolicy};
+use crate::blocking_pool::BlockingPool;
+use crate::callbacks::{CallbackType, Callbacks, RestartContext};
 use crate::child::{Child, Init};
 use crate::child_ref::ChildRef;
 use crate::children_ref::ChildrenRef;
-use crate::context::{BastionContext, BastionId, ContextState};
+#[cfg(feature = "remote")]
+use crate::codec::WireCodec;
+use crate::context::{BastionContext, BastionId, ContextState, InitData, LocalStateInit};
+#[cfg(feature = "remote")]
+use crate::dead_letters::{self, DeadLetter, Destination};
 use crate::dispatcher::Dispatcher;
 use crate::envelope::Envelope;
-use crate::message::BastionMessage;
+use crate::events::SystemEvent;
+use crate::executor::{self, CoreSet};
+use crate::health::{FailureKind, GroupHealth, HealthPolicy, HealthStatus};
+use crate::limits::{ConcurrencyLimit, InflightLimit, MailboxLimit};
+use crate::local_executor::LocalExecutor;
+use crate::message::{BastionMessage, Message};
+use crate::monitor::{DownReason, MONITORS};
+use crate::names::NamedRef;
+use crate::observer::{MailboxEvent, MessageObserver};
 use crate::path::BastionPathElement;
+use crate::persistence::{EventCodec, Journal, Persistence, Recovery};
+use crate::profile::GroupProfile;
 #[cfg(feature = "scaling")]
 use crate::resizer::{ActorGroupStats, OptimalSizeExploringResizer, ScalingRule};
-use crate::system::SYSTEM;
+use crate::results::GroupResults;
+use crate::supervisor::{ChildFailure, FailureReason, RestartStrategy};
+use crate::system;
+use crate::time::{self, Instant};
+use crate::typed::TypedContext;
 use crate::{
     broadcast::{Broadcast, Parent, Sender},
     distributor::Distributor,
 };
 use anyhow::Result as AnyResult;
 
-use bastion_executor::pool;
 use futures::pending;
 use futures::poll;
 use futures::prelude::*;
-use futures::stream::FuturesOrdered;
-use futures_timer::Delay;
-use fxhash::FxHashMap;
+use futures::stream::{FuturesOrdered, FuturesUnordered};
+use fxhash::{FxHashMap, FxHashSet};
 use lightproc::prelude::*;
+use serde::de::DeserializeOwned;
+use std::collections::VecDeque;
 use std::fmt::Debug;
 use std::future::Future;
 use std::pin::Pin;
@@ -34,6 +55,108 @@ use std::task::Poll;
 use std::time::Duration;
 use tracing::{debug, trace, warn};
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Defines how a restarted element of a children group drains
+/// the messages it inherited from its previous run, so that a
+/// huge mailbox doesn't cause a latency spike for new traffic.
+///
+/// The default policy is `Unbounded`.
+///
+/// See [`Children::with_drain_policy`].
+pub enum DrainPolicy {
+    /// Inherited messages are received before any new message,
+    /// as fast as the element asks for them.
+    Unbounded,
+    /// At most the given number of inherited messages (at least
+    /// one) are received per tick of the element, while new messages
+    /// are received without limit. The element is woken up for its
+    /// next tick as long as inherited messages are left.
+    PerTick(usize),
+    /// Inherited messages and new messages are received in turn.
+    Interleave,
+    /// Inherited messages that have been waiting for longer than
+    /// the given duration are never received by the element and
+    /// are routed to the dead letters instead.
+    Ttl(Duration),
+}
+
+impl Default for DrainPolicy {
+    fn default() -> Self {
+        DrainPolicy::Unbounded
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Defines what happens to the messages sent to an element of a
+/// children group whose mailbox is full.
+///
+/// The default policy is `FailSender`.
+///
+/// See [`Children::with_mailbox_capacity`].
+pub enum OverflowPolicy {
+    /// The oldest message waiting in the mailbox is routed to the
+    /// dead letters to make room for the new one.
+    DropOldest,
+    /// The new message is routed to the dead letters.
+    DropNewest,
+    /// The new message is refused: [`ChildRef::tell_anonymously`]
+    /// and [`ChildRef::ask_anonymously`] return it back, while
+    /// [`ChildRef::try_tell_anonymously`],
+    /// [`ChildRef::try_ask_anonymously`] and the distributors return
+    /// [`SendError::Full`]. Messages sent by other means (e.g. using
+    /// [`BastionContext::tell`]) are routed to the dead letters.
+    ///
+    /// [`ChildRef::tell_anonymously`]: crate::child_ref::ChildRef::tell_anonymously
+    /// [`ChildRef::ask_anonymously`]: crate::child_ref::ChildRef::ask_anonymously
+    /// [`ChildRef::try_tell_anonymously`]: crate::child_ref::ChildRef::try_tell_anonymously
+    /// [`ChildRef::try_ask_anonymously`]: crate::child_ref::ChildRef::try_ask_anonymously
+    /// [`SendError::Full`]: crate::errors::SendError::Full
+    /// [`BastionContext::tell`]: crate::context::BastionContext::tell
+    FailSender,
+    /// Senders are expected to wait for room in the mailbox using
+    /// [`ChildRef::wait_for_capacity`] before sending a message.
+    /// Room is reserved for every message sent using a [`ChildRef`]
+    /// (or a distributor) from when it is sent until it is enqueued,
+    /// so that the mailbox never grows past its capacity: a message
+    /// sent while there is no room left is refused like with
+    /// `FailSender`, and messages sent by other means (e.g. using
+    /// [`ChildrenRef::broadcast`]) are routed to the dead letters if
+    /// the mailbox is full.
+    ///
+    /// [`ChildRef::wait_for_capacity`]: crate::child_ref::ChildRef::wait_for_capacity
+    /// [`ChildRef`]: crate::child_ref::ChildRef
+    /// [`ChildrenRef::broadcast`]: crate::children_ref::ChildrenRef::broadcast
+    Backpressure,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Defines what happens to the state of an element of a children
+/// group (see [`Children::with_state`]) when the element is
+/// restarted after a failure.
+///
+/// The default policy is `Reset`.
+///
+/// See [`Children::with_state_recovery`].
+pub enum StateRecovery {
+    /// The state is dropped and created again.
+    Reset,
+    /// The state is kept as it was when the element failed, which
+    /// might be in the middle of an update if it panicked.
+    Preserve,
+}
+
+impl Default for StateRecovery {
+    fn default() -> Self {
+        StateRecovery::Reset
+    }
+}
+
+impl Default for OverflowPolicy {
+    fn default() -> Self {
+        OverflowPolicy::FailSender
+    }
+}
+
 #[derive(Debug)]
 /// A children group that will contain a defined number of
 /// elements (set with [`with_redundancy`] or `1` by default)
@@ -112,6 +235,10 @@ pub struct Children {
     distributors: Vec<Distributor>,
     // The name of children
     name: Option<String>,
+    // The path under which the group is registered to be looked
+    // up with `Bastion::children_ref`, if it and all its
+    // supervisors are named.
+    named_path: Option<String>,
     #[cfg(feature = "scaling")]
     // Resizer for dynamic actor group scaling up/down.
     resizer: Box<OptimalSizeExploringResizer>,
@@ -123,6 +250,81 @@ pub struct Children {
     // Children instance. For example for heartsbeat checks, collecting
     // stats, etc.
     helper_actors: FxHashMap<BastionId, (Sender, RecoverableHandle<()>)>,
+    // Whether elements restarted after a failure inherit the messages
+    // left in their mailbox, and how they drain them.
+    preserve_mailbox: bool,
+    drain_policy: DrainPolicy,
+    // The failure telemetry shared by the elements of the group.
+    health: Arc<GroupHealth>,
+    // The values the elements of the group exited with.
+    results: Arc<GroupResults>,
+    // The restart strategy overriding the one of the supervisor.
+    restart_strategy: Option<RestartStrategy>,
+    // How long the elements can keep running to finish their
+    // in-flight work when the group is stopped.
+    stop_timeout: Option<Duration>,
+    // Whether the elements are suspended, in which case restarted
+    // or newly launched elements are suspended too.
+    suspended: bool,
+    // How long the future of an element can run before the element
+    // is considered failed.
+    exec_timeout: Option<Duration>,
+    // How long an element can poll its future without yielding
+    // before being considered hung and restarted.
+    heartbeat_timeout: Option<Duration>,
+    // How long an element can handle a single message before a
+    // warning is emitted.
+    handler_warn_threshold: Option<Duration>,
+    // The limit of elements that can be handling a message at once.
+    concurrency_limit: Option<Arc<ConcurrencyLimit>>,
+    // The limit of asks sent to the elements that can be waiting
+    // for an answer at once.
+    inflight_limit: Option<Arc<InflightLimit>>,
+    // The amount of messages the mailbox of each element can hold,
+    // and what happens to the messages sent to a full mailbox.
+    mailbox_capacity: Option<usize>,
+    overflow_policy: OverflowPolicy,
+    // Called when a message goes through the mailbox of an element.
+    message_observer: Option<MessageObserver>,
+    // The profile of the messages handled by the elements, if
+    // profiling is enabled.
+    profile: Option<Arc<GroupProfile>>,
+    // The closure creating the state of each element, and what
+    // happens to it when an element is restarted.
+    local_state: Option<LocalStateInit>,
+    state_recovery: StateRecovery,
+    // The persistence identifier of the elements and the journal
+    // they persist their events to, how the events are serialized
+    // and the handler replaying them to the state of the elements.
+    persistence: Option<Persistence>,
+    event_codec: EventCodec,
+    recovery: Option<Recovery>,
+    // The values handed to the elements depending on their index.
+    init_data: Option<InitData>,
+    // When to spawn or retire elements depending on the amount of
+    // messages waiting in their mailboxes.
+    autoscaling: Option<AutoscalePolicy>,
+    // When the group last spawned or retired an element because of
+    // `autoscaling`.
+    last_autoscale: Option<Instant>,
+    // The amount of threads dedicated to the group and the cores
+    // they are pinned to, and the pool of those threads once the
+    // first element is launched.
+    dedicated_threads: Option<usize>,
+    affinity: Option<CoreSet>,
+    executor: Option<Arc<BlockingPool>>,
+    // The states of the launched elements, used to check whether
+    // they are still responsive.
+    elem_states: FxHashMap<BastionId, Arc<Pin<Box<ContextState>>>>,
+    // The elements still running the previous exec closure after
+    // it was replaced, which are swapped one at a time.
+    exec_swaps: VecDeque<BastionId>,
+    // The element currently being stopped to run the new exec
+    // closure.
+    swapping: Option<BastionId>,
+    // The elements being stopped to be restarted, as asked using
+    // `ChildRef::restart`.
+    restarting: FxHashSet<BastionId>,
 }
 
 impl Children {
@@ -137,10 +339,42 @@ impl Children {
         let dispatchers = Vec::new();
         let distributors = Vec::new();
         let name = None;
+        let named_path = None;
         #[cfg(feature = "scaling")]
         let resizer = Box::new(OptimalSizeExploringResizer::default());
         let hearbeat_tick = Duration::from_secs(60);
         let helper_actors = FxHashMap::default();
+        let preserve_mailbox = false;
+        let drain_policy = DrainPolicy::default();
+        let health = Arc::new(GroupHealth::default());
+        let results = Arc::new(GroupResults::default());
+        let restart_strategy = None;
+        let stop_timeout = None;
+        let suspended = false;
+        let exec_timeout = None;
+        let heartbeat_timeout = None;
+        let handler_warn_threshold = None;
+        let concurrency_limit = None;
+        let inflight_limit = None;
+        let mailbox_capacity = None;
+        let overflow_policy = OverflowPolicy::default();
+        let message_observer = None;
+        let profile = None;
+        let local_state = None;
+        let state_recovery = StateRecovery::default();
+        let persistence = None;
+        let event_codec = EventCodec::default();
+        let recovery = None;
+        let init_data = None;
+        let autoscaling = None;
+        let last_autoscale = None;
+        let dedicated_threads = None;
+        let affinity = None;
+        let executor = None;
+        let elem_states = FxHashMap::default();
+        let exec_swaps = VecDeque::new();
+        let swapping = None;
+        let restarting = FxHashSet::default();
 
         Children {
             bcast,
@@ -153,10 +387,42 @@ impl Children {
             dispatchers,
             distributors,
             name,
+            named_path,
             #[cfg(feature = "scaling")]
             resizer,
             hearbeat_tick,
             helper_actors,
+            preserve_mailbox,
+            drain_policy,
+            health,
+            results,
+            restart_strategy,
+            stop_timeout,
+            suspended,
+            exec_timeout,
+            heartbeat_timeout,
+            handler_warn_threshold,
+            concurrency_limit,
+            inflight_limit,
+            mailbox_capacity,
+            overflow_policy,
+            message_observer,
+            profile,
+            local_state,
+            state_recovery,
+            persistence,
+            event_codec,
+            recovery,
+            init_data,
+            autoscaling,
+            last_autoscale,
+            dedicated_threads,
+            affinity,
+            executor,
+            elem_states,
+            exec_swaps,
+            swapping,
+            restarting,
         }
     }
 
@@ -213,6 +479,18 @@ impl Children {
         &self.callbacks
     }
 
+    pub(crate) fn restart_strategy(&self) -> Option<&RestartStrategy> {
+        self.restart_strategy.as_ref()
+    }
+
+    pub(crate) fn redundancy(&self) -> usize {
+        self.redundancy
+    }
+
+    pub(crate) fn distributors(&self) -> &[Distributor] {
+        &self.distributors
+    }
+
     pub(crate) fn name(&self) -> String {
         if let Some(name) = &self.name {
             name.clone()
@@ -221,6 +499,14 @@ impl Children {
         }
     }
 
+    pub(crate) fn given_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub(crate) fn set_named_path(&mut self, named_path: String) {
+        self.named_path = Some(named_path);
+    }
+
     pub(crate) fn as_ref(&self) -> ChildrenRef {
         trace!(
             "Children({}): Creating new ChildrenRef({}).",
@@ -236,7 +522,10 @@ impl Children {
         for (id, (sender, _)) in &self.launched {
             trace!("Children({}): Creating new ChildRef({}).", self.id(), id);
             // TODO: clone or ref?
-            let child = ChildRef::new(id.clone(), sender.clone(), self.name(), path.clone());
+            let mailbox = self.elem_states.get(id).map(|state| state.mailbox());
+            let child = ChildRef::new(id.clone(), sender.clone(), self.name(), path.clone())
+                .with_inflight_limit(self.inflight_limit.clone())
+                .with_mailbox(mailbox);
             children.push(child);
         }
 
@@ -248,10 +537,34 @@ impl Children {
 
         let distributors = self.distributors.clone();
 
-        ChildrenRef::new(id, sender, path, children, dispatchers, distributors)
+        let health = self.health.clone();
+        let results = self.results.clone();
+
+        ChildrenRef::new(
+            id,
+            sender,
+            path,
+            children,
+            dispatchers,
+            distributors,
+            health,
+            results,
+        )
+        .with_profile(self.profile.clone())
     }
 
     /// Sets the name of this children group.
+    ///
+    /// If the supervisor of the group can be looked up by path (see
+    /// [`Supervisor::with_name`]) or is the system supervisor (i.e.
+    /// the group was created with [`Bastion::children`]), the group
+    /// can be looked up using [`Bastion::children_ref`], with its
+    /// path being the path of its supervisor followed by its name
+    /// (e.g. `"ingest/parsers/csv"`).
+    ///
+    /// [`Supervisor::with_name`]: crate::supervisor::Supervisor::with_name
+    /// [`Bastion::children`]: crate::Bastion::children
+    /// [`Bastion::children_ref`]: crate::Bastion::children_ref
     pub fn with_name(mut self, name: impl Into<String>) -> Self {
         self.name = Some(name.into());
         self
@@ -321,16 +634,27 @@ impl Children {
         self
     }
 
-    /// Sets the number of elements this children group will
-    /// contain. Each element will call the closure passed in
-    /// [`with_exec`] and run the returned future until it stops,
-    /// panics or another element in the group stops or panics.
+    /// Sets the closure taking a [`BastionContext`] that will be
+    /// run on the blocking pool by every element of this children
+    /// group, instead of the future returned by the closure given
+    /// to [`with_exec`].
     ///
-    /// The default number of elements a children group contains is `1`.
+    /// This allows elements to do CPU-bound or blocking work (e.g.
+    /// synchronous I/O) without stalling the executor polling the
+    /// other elements. The closure can receive messages using
+    /// [`BastionContext::recv_blocking`] and should return
+    /// `Ok(())` or `Err(())` once it is done, like the futures
+    /// used by [`with_exec`].
+    ///
+    /// Note that stopping or killing an element doesn't interrupt
+    /// its closure: it should return once
+    /// [`BastionContext::recv_blocking`] returns `Err(())`, or check
+    /// [`BastionContext::is_stopping`] while it is busy.
     ///
     /// # Arguments
     ///
-    /// * `redundancy` - The number of elements this group will contain.
+    /// * `init` - The closure taking a [`BastionContext`] that will
+    ///     be run by every element of this children group.
     ///
     /// # Example
     ///
@@ -352,8 +676,19 @@ impl Children {
     /// # Bastion::init();
     /// #
     /// Bastion::children(|children| {
-    ///     // Note that "1" is the default number of elements.
-    ///     children.with_redundancy(1)
+    ///     children.with_exec_blocking(|ctx: BastionContext| {
+    ///         while let Ok(msg) = ctx.recv_blocking() {
+    ///             msg! { msg,
+    ///                 path: String => {
+    ///                     // Synchronously read the file...
+    ///                     let _ = std::fs::read(path);
+    ///                 };
+    ///                 _: _ => ();
+    ///             }
+    ///         }
+    ///
+    ///         Ok(())
+    ///     })
     /// }).expect("Couldn't create the children group.");
     /// #
     /// # Bastion::start();
@@ -363,38 +698,200 @@ impl Children {
     /// ```
     ///
     /// [`with_exec`]: Self::with_exec
-    pub fn with_redundancy(mut self, redundancy: usize) -> Self {
-        trace!(
-            "Children({}): Setting redundancy: {}",
-            self.id(),
-            redundancy
-        );
-        if redundancy == std::usize::MIN {
-            self.redundancy = redundancy.saturating_add(1);
-        } else {
-            self.redundancy = redundancy;
-        }
-        #[cfg(feature = "scaling")]
-        {
-            self.resizer.set_lower_bound(self.redundancy as u64);
-        }
+    pub fn with_exec_blocking<I>(self, init: I) -> Self
+    where
+        I: Fn(BastionContext) -> Result<(), ()> + Send + Sync + 'static,
+    {
+        trace!("Children({}): Setting blocking exec closure.", self.id());
+        let init = Arc::new(init);
+        self.with_exec(move |ctx: BastionContext| {
+            let init = init.clone();
+            async move {
+                let id = ctx.current().id().clone();
+                match executor::blocking(async move { init(ctx) }).await {
+                    Some(res) => res,
+                    None => {
+                        warn!("Child({}): Blocking exec closure panicked.", id);
+                        Err(())
+                    }
+                }
+            }
+        })
+    }
 
-        self
+    /// Sets the closure taking a [`BastionContext`] and returning a
+    /// [`Future`] that will be used by every element of this children
+    /// group, like [`with_exec`], except that the closure and the
+    /// futures it returns are run on a thread dedicated to the group.
+    ///
+    /// As every element of the group is confined to this thread,
+    /// the futures don't need to implement `Send` and can hold
+    /// values like `Rc`s, FFI handles or GPU contexts.
+    ///
+    /// A panic of the future of an element is handled as if it
+    /// returned `Err(())`.
+    ///
+    /// # Arguments
+    ///
+    /// * `init` - The closure taking a [`BastionContext`] and
+    ///     returning a [`Future`] that will be used by every element
+    ///     of this children group.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::rc::Rc;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec_local(|ctx: BastionContext| {
+    ///         // A value which can't be sent to another thread...
+    ///         let handle = Rc::new(());
+    ///         async move {
+    ///             while let Ok(_msg) = ctx.recv().await {
+    ///                 // ...but can be held across `.await` points.
+    ///                 let _handle = handle.clone();
+    ///             }
+    ///
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`with_exec`]: Self::with_exec
+    pub fn with_exec_local<I, F>(self, init: I) -> Self
+    where
+        I: Fn(BastionContext) -> F + Send + Sync + 'static,
+        F: Future<Output = Result<(), ()>> + 'static,
+    {
+        trace!("Children({}): Setting local exec closure.", self.id());
+        let executor = LocalExecutor::start(format!("bastion-local-{}", self.id()));
+        let init = Arc::new(init);
+        self.with_exec(move |ctx: BastionContext| {
+            let init = init.clone();
+            let id = ctx.current().id().clone();
+            let exec = executor.run(move || init(ctx));
+            async move {
+                match exec.await {
+                    Some(res) => res,
+                    None => {
+                        warn!("Child({}): Local exec future panicked.", id);
+                        Err(())
+                    }
+                }
+            }
+        })
     }
 
-    /// Appends each supervised element to the declared dispatcher.
+    /// Sets the closure taking a [`TypedContext`] that will be
+    /// used by every element of this children group, which then
+    /// only receives messages of type `M` (usually an enum), instead
+    /// of the closure given to [`with_exec`].
     ///
-    /// By default supervised elements aren't added to any of dispatcher.
+    /// The elements can be sent messages using [`TypedChildRef`]s,
+    /// and the messages of another type they receive are routed to
+    /// the dead letters.
     ///
     /// # Arguments
     ///
-    /// * `dispatcher` - An instance of struct that implements the
-    /// [`DispatcherHandler`] trait.
+    /// * `init` - The closure taking a [`TypedContext`] and
+    ///     returning a future that will be used by every element of
+    ///     this children group.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// #[derive(Debug, Clone)]
+    /// enum Query {
+    ///     Ping,
+    /// }
+    ///
+    /// Bastion::children(|children| {
+    ///     children.with_typed_exec(|ctx: TypedContext<Query>| {
+    ///         async move {
+    ///             loop {
+    ///                 match ctx.recv().await? {
+    ///                     Query::Ping => ctx.answer("pong").ok(),
+    ///                 };
+    ///             }
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`TypedContext`]: crate::typed::TypedContext
+    /// [`TypedChildRef`]: crate::typed::TypedChildRef
+    /// [`with_exec`]: Self::with_exec
+    pub fn with_typed_exec<M, I, F>(self, init: I) -> Self
+    where
+        M: Message + Clone,
+        I: Fn(TypedContext<M>) -> F + Send + 'static,
+        F: Future<Output = Result<(), ()>> + Send + 'static,
+    {
+        trace!("Children({}): Setting typed exec closure.", self.id());
+        self.with_exec(move |ctx: BastionContext| init(TypedContext::new(ctx)))
+    }
+
+    /// Sets the closure creating the state of each element of this
+    /// children group, which is then available through
+    /// [`BastionContext::state`].
+    ///
+    /// The state of an element is created when the element is
+    /// launched and dropped once it stops. When the element is
+    /// restarted after a failure, its state is either created again
+    /// or kept, depending on the group's [`StateRecovery`] (see
+    /// [`with_state_recovery`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `init` - The closure creating the state of an element.
     ///
     /// # Example
     ///
     /// ```rust
     /// # use bastion::prelude::*;
+    /// # use std::collections::HashMap;
     /// #
     /// # #[cfg(feature = "tokio-runtime")]
     /// # #[tokio::main]
@@ -412,9 +909,20 @@ impl Children {
     /// #
     /// Bastion::children(|children| {
     ///     children
-    ///         .with_dispatcher(
-    ///             Dispatcher::with_type(DispatcherType::Named("CustomGroup".to_string()))
-    ///         )
+    ///         .with_state(HashMap::<String, usize>::new)
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 loop {
+    ///                     msg! { ctx.recv().await?,
+    ///                         word: String => {
+    ///                             let mut counts = ctx.state::<HashMap<String, usize>>().unwrap();
+    ///                             *counts.entry(word).or_default() += 1;
+    ///                         };
+    ///                         _: _ => ();
+    ///                     }
+    ///                 }
+    ///             }
+    ///         })
     /// }).expect("Couldn't create the children group.");
     /// #
     /// # Bastion::start();
@@ -422,20 +930,30 @@ impl Children {
     /// # Bastion::block_until_stopped();
     /// # }
     /// ```
-    /// [`DispatcherHandler`]: crate::dispatcher::DispatcherHandler
-    pub fn with_dispatcher(mut self, dispatcher: Dispatcher) -> Self {
-        self.dispatchers.push(Arc::new(Box::new(dispatcher)));
+    ///
+    /// [`with_state_recovery`]: Self::with_state_recovery
+    pub fn with_state<S, F>(mut self, init: F) -> Self
+    where
+        S: Send + 'static,
+        F: Fn() -> S + Send + Sync + 'static,
+    {
+        trace!("Children({}): Setting state closure.", self.id());
+        self.local_state = Some(LocalStateInit::new(init));
         self
     }
 
-    /// Appends a distributor to the children.
+    /// Sets the values handed to the elements of this children
+    /// group, the element with index `i` (see
+    /// [`BastionContext::elem_index`]) getting the `i`-th value
+    /// through [`BastionContext::init_data`].
     ///
-    /// By default supervised elements aren't added to any distributor.
+    /// Elements whose index is greater than or equal to the amount
+    /// of values don't get any.
     ///
     /// # Arguments
     ///
-    /// * `distributor` - An instance of struct that implements the
-    /// [`RecipientHandler`] trait.
+    /// * `data` - The values handed to the elements, in the order of
+    ///     their index.
     ///
     /// # Example
     ///
@@ -456,9 +974,19 @@ impl Children {
     /// # fn run() {
     /// # Bastion::init();
     /// #
+    /// let partitions: Vec<Vec<u32>> = vec![vec![0, 2], vec![1, 3]];
+    ///
     /// Bastion::children(|children| {
     ///     children
-    ///         .with_distributor(Distributor::named("my distributor"))
+    ///         .with_redundancy(partitions.len())
+    ///         .with_init_data(partitions)
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 let partitions: &Vec<u32> = ctx.init_data().unwrap();
+    ///                 // Consume the assigned partitions...
+    ///                 Ok(())
+    ///             }
+    ///         })
     /// }).expect("Couldn't create the children group.");
     /// #
     /// # Bastion::start();
@@ -466,22 +994,1394 @@ impl Children {
     /// # Bastion::block_until_stopped();
     /// # }
     /// ```
-    /// [`RecipientHandler`]: crate::dispatcher::RecipientHandler
-    pub fn with_distributor(mut self, distributor: Distributor) -> Self {
-        // Try to register the distributor as soon as we're aware of it
-        let _ = SYSTEM.dispatcher().register_distributor(&distributor);
-        self.distributors.push(distributor);
+    pub fn with_init_data<T>(mut self, data: Vec<T>) -> Self
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        trace!(
+            "Children({}): Setting init data of {} elements.",
+            self.id(),
+            data.len()
+        );
+        self.init_data = Some(InitData::new(data));
+        self
+    }
+
+    /// Sets what happens to the state of an element of this children
+    /// group (see [`with_state`]) when the element is restarted after
+    /// a failure.
+    ///
+    /// The default policy is [`StateRecovery::Reset`].
+    ///
+    /// # Arguments
+    ///
+    /// * `recovery` - What happens to the state of a restarted
+    ///     element.
+    ///
+    /// [`with_state`]: Self::with_state
+    pub fn with_state_recovery(mut self, recovery: StateRecovery) -> Self {
+        trace!(
+            "Children({}): Setting state recovery: {:?}",
+            self.id(),
+            recovery
+        );
+        self.state_recovery = recovery;
+        self
+    }
+
+    /// Makes the elements of this children group persistent: the
+    /// events they persist using [`BastionContext::persist`] are
+    /// appended to the journal under the given persistence
+    /// identifier, and replayed through the handler given to
+    /// [`with_recovery`] when they start again (after a restart or
+    /// when the process starts again with a durable journal), so
+    /// that they rebuild their state.
+    ///
+    /// The first element of the group uses the persistence
+    /// identifier, and the others the identifier followed by `/` and
+    /// their index in the group.
+    ///
+    /// # Arguments
+    ///
+    /// * `persistence_id` - The persistence identifier of the
+    ///     elements.
+    /// * `journal` - The journal the events are appended to (e.g. a
+    ///     [`FileJournal`], or the one returned by
+    ///     [`persistence::journal`]).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use bastion::persistence;
+    /// # use serde::{Deserialize, Serialize};
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// #[derive(Debug, Serialize, Deserialize)]
+    /// struct Deposited(u64);
+    ///
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_persistence("account-1", persistence::journal())
+    ///         .with_state(|| 0u64)
+    ///         .with_recovery(|balance: &mut u64, Deposited(amount)| *balance += amount)
+    ///         .with_exec(|ctx: BastionContext| async move {
+    ///             loop {
+    ///                 let amount = MessageHandler::new(ctx.recv().await?)
+    ///                     .on_tell(|amount: u64, _| amount)
+    ///                     .on_fallback(|_, _| 0);
+    ///                 ctx.persist(&Deposited(amount)).await.map_err(|_| ())?;
+    ///                 *ctx.state::<u64>().unwrap() += amount;
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`BastionContext::persist`]: crate::context::BastionContext::persist
+    /// [`with_recovery`]: Self::with_recovery
+    /// [`FileJournal`]: crate::persistence::FileJournal
+    /// [`persistence::journal`]: crate::persistence::journal
+    pub fn with_persistence(
+        mut self,
+        persistence_id: impl Into<String>,
+        journal: Arc<dyn Journal>,
+    ) -> Self {
+        let id = persistence_id.into();
+        trace!("Children({}): Setting persistence id: {}", self.id(), id);
+        self.persistence = Some(Persistence {
+            id,
+            journal,
+            codec: EventCodec::default(),
+            recovery: None,
+        });
+        self
+    }
+
+    /// Sets the handler replaying the events persisted by an element
+    /// of this children group (see [`with_persistence`]) to its
+    /// state (see [`with_state`]) when it starts, before it handles
+    /// any message.
+    ///
+    /// The events are replayed when the element is launched, and
+    /// when it is restarted with a new state (see
+    /// [`with_state_recovery`]). The element fails if an event
+    /// couldn't be read or deserialized.
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - The closure applying an event to the state of
+    ///     the element.
+    ///
+    /// See [`with_persistence`] for an example.
+    ///
+    /// [`with_persistence`]: Self::with_persistence
+    /// [`with_state`]: Self::with_state
+    /// [`with_state_recovery`]: Self::with_state_recovery
+    pub fn with_recovery<S, E, F>(mut self, handler: F) -> Self
+    where
+        S: Send + 'static,
+        E: DeserializeOwned + Debug + 'static,
+        F: Fn(&mut S, E) + Send + Sync + 'static,
+    {
+        trace!("Children({}): Setting recovery handler.", self.id());
+        self.recovery = Some(Recovery::new(handler));
+        self
+    }
+
+    /// Sets the codec serializing the events persisted by the
+    /// elements of this children group (see [`with_persistence`]):
+    /// the events are packed in a [`WireMessage`] and stored in a
+    /// [`WireEnvelope`] encoded by the codec, instead of being
+    /// serialized as JSON.
+    ///
+    /// # Arguments
+    ///
+    /// * `codec` - The codec encoding the events.
+    ///
+    /// [`with_persistence`]: Self::with_persistence
+    /// [`WireMessage`]: crate::wire::WireMessage
+    /// [`WireEnvelope`]: crate::codec::WireEnvelope
+    #[cfg(feature = "remote")]
+    pub fn with_persistence_codec(mut self, codec: impl WireCodec) -> Self {
+        trace!(
+            "Children({}): Setting persistence codec: {}",
+            self.id(),
+            codec.name()
+        );
+        self.event_codec = EventCodec::new(codec);
+        self
+    }
+
+    pub(crate) fn with_init(mut self, init: Init) -> Self {
+        trace!("Children({}): Setting exec closure.", self.id());
+        self.init = init;
+        self
+    }
+
+    /// Sets the number of elements this children group will
+    /// contain. Each element will call the closure passed in
+    /// [`with_exec`] and run the returned future until it stops,
+    /// panics or another element in the group stops or panics.
+    ///
+    /// The default number of elements a children group contains is `1`.
+    ///
+    /// # Arguments
+    ///
+    /// * `redundancy` - The number of elements this group will contain.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     // Note that "1" is the default number of elements.
+    ///     children.with_redundancy(1)
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`with_exec`]: Self::with_exec
+    pub fn with_redundancy(mut self, redundancy: usize) -> Self {
+        trace!(
+            "Children({}): Setting redundancy: {}",
+            self.id(),
+            redundancy
+        );
+        if redundancy == std::usize::MIN {
+            self.redundancy = redundancy.saturating_add(1);
+        } else {
+            self.redundancy = redundancy;
+        }
+        #[cfg(feature = "scaling")]
+        {
+            self.resizer.set_lower_bound(self.redundancy as u64);
+        }
+
+        self
+    }
+
+    /// Appends each supervised element to the declared dispatcher.
+    ///
+    /// By default supervised elements aren't added to any of dispatcher.
+    ///
+    /// # Arguments
+    ///
+    /// * `dispatcher` - An instance of struct that implements the
+    /// [`DispatcherHandler`] trait.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_dispatcher(
+    ///             Dispatcher::with_type(DispatcherType::Named("CustomGroup".to_string()))
+    ///         )
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    /// [`DispatcherHandler`]: crate::dispatcher::DispatcherHandler
+    pub fn with_dispatcher(mut self, dispatcher: Dispatcher) -> Self {
+        self.dispatchers.push(Arc::new(Box::new(dispatcher)));
+        self
+    }
+
+    /// Appends a distributor to the children.
+    ///
+    /// By default supervised elements aren't added to any distributor.
+    ///
+    /// # Arguments
+    ///
+    /// * `distributor` - An instance of struct that implements the
+    /// [`RecipientHandler`] trait.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_distributor(Distributor::named("my distributor"))
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    /// [`RecipientHandler`]: crate::dispatcher::RecipientHandler
+    pub fn with_distributor(mut self, distributor: Distributor) -> Self {
+        // Try to register the distributor as soon as we're aware of it
+        let _ = system::current()
+            .dispatcher()
+            .register_distributor(&distributor);
+        self.distributors.push(distributor);
+        self
+    }
+
+    #[cfg(feature = "scaling")]
+    /// Sets a custom resizer for the Children.
+    ///
+    /// This method is available only with the `scaling` feature flag.
+    ///
+    /// # Arguments
+    ///
+    /// * `resizer` - An instance of the [`Resizer`] struct.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    ///     # Bastion::init();
+    ///     #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_redundancy(1)
+    ///         .with_resizer(
+    ///             OptimalSizeExploringResizer::default()
+    ///                 .with_lower_bound(10)
+    ///                 .with_upper_bound(UpperBound::Limit(100))
+    ///         )
+    /// }).expect("Couldn't create the children group.");
+    ///     #
+    ///     # Bastion::start();
+    ///     # Bastion::stop();
+    ///     # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_resizer(mut self, resizer: OptimalSizeExploringResizer) -> Self {
+        self.redundancy = resizer.lower_bound() as usize;
+        self.resizer = Box::new(resizer);
+        self
+    }
+
+    /// Sets the callbacks that will get called at this children group's
+    /// different lifecycle events.
+    ///
+    /// See [`Callbacks`]'s documentation for more information about the
+    /// different callbacks available.
+    ///
+    /// # Arguments
+    ///
+    /// * `callbacks` - The callbacks that will get called for this
+    ///     children group.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     let callbacks = Callbacks::new()
+    ///         .with_before_start(|| println!("Children group started."))
+    ///         .with_after_stop(|| println!("Children group stopped."));
+    ///
+    ///     children
+    ///         .with_callbacks(callbacks)
+    ///         .with_exec(|ctx| {
+    ///             // -- Children group started.
+    ///             async move {
+    ///                 // ...
+    ///                 # Ok(())
+    ///             }
+    ///             // -- Children group stopped.
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_callbacks(mut self, callbacks: Callbacks) -> Self {
+        trace!(
+            "Children({}): Setting callbacks: {:?}",
+            self.id(),
+            callbacks
+        );
+        self.callbacks = callbacks;
+        self
+    }
+
+    /// Overrides the default time interval for heartbeat onto
+    /// the user defined.
+    ///
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The value of the [`std::time::Duration`] type
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    /// children
+    ///     .with_heartbeat_tick(Duration::from_secs(5))
+    ///     .with_exec(|ctx| {
+    ///         // -- Children group started.
+    ///         async move {
+    ///             // ...
+    ///             # Ok(())
+    ///         }
+    ///         // -- Children group stopped.
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_heartbeat_tick(mut self, interval: Duration) -> Self {
+        trace!(
+            "Children({}): Set heartbeat tick to {:?}",
+            self.id(),
+            interval
+        );
+        self.hearbeat_tick = interval;
+        self
+    }
+
+    /// Sets how long the elements of this children group can keep
+    /// running to finish their in-flight work when the group is
+    /// stopped (e.g. by [`Bastion::stop`]), after which the elements
+    /// that are still running get killed.
+    ///
+    /// While an element is being stopped, [`BastionContext::recv`]
+    /// returns `Err(())` once its mailbox is empty and
+    /// [`BastionContext::is_stopping`] returns `true`. Returning an
+    /// error at that point doesn't make the element restart.
+    ///
+    /// By default, the elements are killed as soon as the group is
+    /// stopped.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long the elements can keep running once the
+    ///     group is stopped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    /// children
+    ///     .with_stop_timeout(Duration::from_secs(5))
+    ///     .with_exec(|ctx| {
+    ///         async move {
+    ///             // Returns `Err(())` once the group is stopping and
+    ///             // all the received messages were handled.
+    ///             while let Ok(msg) = ctx.recv().await {
+    ///                 // ...
+    ///             }
+    ///
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Bastion::stop`]: crate::Bastion::stop
+    /// [`BastionContext::recv`]: crate::context::BastionContext::recv
+    /// [`BastionContext::is_stopping`]: crate::context::BastionContext::is_stopping
+    pub fn with_stop_timeout(mut self, timeout: Duration) -> Self {
+        trace!(
+            "Children({}): Setting stop timeout: {:?}",
+            self.id(),
+            timeout
+        );
+        self.stop_timeout = Some(timeout);
+        self
+    }
+
+    /// Enables the liveness monitoring of the elements of this
+    /// children group: an element whose future keeps running
+    /// without yielding for longer than `timeout` (e.g. because it
+    /// is blocked on a blocking call or stuck in an infinite loop)
+    /// is considered hung, and is killed and restarted by its
+    /// supervisor like an element that failed.
+    ///
+    /// The elements check in each time their future yields, and the
+    /// group checks them at least every half `timeout` (see
+    /// [`with_heartbeat_tick`]). Their failures are recorded as
+    /// [`FailureKind::Unresponsive`].
+    ///
+    /// Note that the thread running a hung element stays blocked
+    /// until its future yields, even though the element is
+    /// replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long an element can run without yielding.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    /// children
+    ///     .with_heartbeat(Duration::from_secs(10))
+    ///     .with_exec(|ctx| {
+    ///         async move {
+    ///             // ...
+    ///             # Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`with_heartbeat_tick`]: Self::with_heartbeat_tick
+    /// [`FailureKind::Unresponsive`]: crate::health::FailureKind::Unresponsive
+    pub fn with_heartbeat(mut self, timeout: Duration) -> Self {
+        trace!(
+            "Children({}): Setting heartbeat timeout: {:?}",
+            self.id(),
+            timeout
+        );
+        self.heartbeat_timeout = Some(timeout);
+        self
+    }
+
+    /// Enables the detection of slow handlers in this children
+    /// group: when an element has been handling a single message
+    /// (from when it received it until it tries to receive another
+    /// one) for longer than `threshold`, a warning is logged and a
+    /// [`SystemEvent::SlowHandler`] is emitted (see
+    /// [`Bastion::events`]), with the type and correlation id of
+    /// the message and how long it had been handled.
+    ///
+    /// Unlike [`with_heartbeat`], the element isn't restarted, and
+    /// it is reported even if its future yields while handling the
+    /// message (e.g. while waiting for an answer). Each message is
+    /// reported once, and the elements are checked at least every
+    /// half `threshold`.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` - How long an element can handle a message
+    ///     before it is reported.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    /// children
+    ///     .with_handler_warn_threshold(Duration::from_secs(5))
+    ///     .with_exec(|ctx| {
+    ///         async move {
+    ///             // ...
+    ///             # Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`with_heartbeat`]: Self::with_heartbeat
+    /// [`SystemEvent::SlowHandler`]: crate::events::SystemEvent::SlowHandler
+    /// [`Bastion::events`]: crate::Bastion::events
+    pub fn with_handler_warn_threshold(mut self, threshold: Duration) -> Self {
+        trace!(
+            "Children({}): Setting handler warning threshold: {:?}",
+            self.id(),
+            threshold
+        );
+        self.handler_warn_threshold = Some(threshold);
+        self
+    }
+
+    /// Sets how long the future of each element of this children
+    /// group can run before the element is considered failed, in
+    /// which case it is killed and its supervisor applies its
+    /// restart strategy (the restarted element gets a new deadline).
+    /// The time spent suspended (see [`SupervisorRef::suspend`])
+    /// doesn't count.
+    ///
+    /// This is meant for task-style elements whose future is
+    /// expected to finish (e.g. batch jobs), rather than for
+    /// elements looping over their messages. Their failures are
+    /// recorded as [`FailureKind::TimedOut`].
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long the future of an element can run.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    /// children
+    ///     .with_exec_timeout(Duration::from_secs(60))
+    ///     .with_exec(|ctx| {
+    ///         async move {
+    ///             // Run the batch job...
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`FailureKind::TimedOut`]: crate::health::FailureKind::TimedOut
+    /// [`SupervisorRef::suspend`]: crate::supervisor::SupervisorRef::suspend
+    pub fn with_exec_timeout(mut self, timeout: Duration) -> Self {
+        trace!(
+            "Children({}): Setting exec timeout: {:?}",
+            self.id(),
+            timeout
+        );
+        self.exec_timeout = Some(timeout);
+        self
+    }
+
+    /// Runs the elements of this children group on their own pool
+    /// of `threads` threads instead of the pool shared by the rest
+    /// of the system, isolating them from the groups doing heavy
+    /// work.
+    ///
+    /// The threads are started when the first element is launched
+    /// and stopped once the group is stopped.
+    ///
+    /// # Arguments
+    ///
+    /// * `threads` - The amount of threads dedicated to the group.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_redundancy(4)
+    ///         .with_dedicated_executor(2)
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // Handle latency-sensitive messages...
+    ///                 Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_dedicated_executor(mut self, threads: usize) -> Self {
+        trace!(
+            "Children({}): Setting dedicated executor threads: {}",
+            self.id(),
+            threads
+        );
+        self.dedicated_threads = Some(threads.max(1));
+        self
+    }
+
+    /// Runs the elements of this children group on their own pool
+    /// of threads (see [`with_dedicated_executor`]), pinned to the
+    /// specified cores.
+    ///
+    /// The pool has one thread per core of the set, unless its size
+    /// is set using [`with_dedicated_executor`], in which case the
+    /// threads are spread over the cores. An empty set leaves the
+    /// threads unpinned.
+    ///
+    /// # Arguments
+    ///
+    /// * `cores` - The cores the threads of the group are pinned to.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_affinity(CoreSet::new(vec![0]))
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // Handle latency-sensitive messages...
+    ///                 Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`with_dedicated_executor`]: Self::with_dedicated_executor
+    pub fn with_affinity(mut self, cores: CoreSet) -> Self {
+        trace!("Children({}): Setting affinity: {:?}", self.id(), cores);
+        self.affinity = Some(cores);
+        self
+    }
+
+    /// Sets the maximum amount of elements of this children group
+    /// that can be handling a message at once, e.g. to protect a
+    /// database accessed by a group with a high redundancy.
+    ///
+    /// An element starts handling a message when it receives it
+    /// (using [`BastionContext::recv`] or [`BastionContext::try_recv`])
+    /// and stops when it asks for the next one. While the limit is
+    /// reached, the other elements are parked: they keep receiving
+    /// messages in their mailbox but don't get them until another
+    /// element is done with its message. Elements being stopped
+    /// ignore the limit to finish their in-flight work.
+    ///
+    /// By default, the amount of elements handling a message at once
+    /// isn't limited.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The maximum amount of elements handling a message
+    ///     at once, which should be greater than zero.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    /// children
+    ///     .with_redundancy(200)
+    ///     // At most 20 queries at once...
+    ///     .with_concurrency_limit(20)
+    ///     .with_exec(|ctx| {
+    ///         async move {
+    ///             loop {
+    ///                 let msg = ctx.recv().await?;
+    ///                 // Query the database...
+    ///             }
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`BastionContext::recv`]: crate::context::BastionContext::recv
+    /// [`BastionContext::try_recv`]: crate::context::BastionContext::try_recv
+    pub fn with_concurrency_limit(mut self, limit: usize) -> Self {
+        trace!(
+            "Children({}): Setting concurrency limit: {}",
+            self.id(),
+            limit
+        );
+        self.concurrency_limit = Some(Arc::new(ConcurrencyLimit::new(limit)));
+        self
+    }
+
+    /// Sets the maximum amount of messages "asked" to the elements
+    /// of this children group (e.g. using
+    /// [`ChildRef::ask_anonymously`] or a [`Distributor`]) that can
+    /// be waiting for an answer at once.
+    ///
+    /// An ask is waiting for an answer until its [`Answer`] is
+    /// dropped (e.g. once it was awaited). While the limit is
+    /// reached, asking another message fails and gives the message
+    /// back.
+    ///
+    /// By default, the amount of asks waiting for an answer isn't
+    /// limited.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The maximum amount of asks waiting for an answer
+    ///     at once.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    /// children
+    ///     .with_redundancy(200)
+    ///     .with_inflight_limit(1_000)
+    ///     .with_exec(|ctx| {
+    ///         async move {
+    ///             // Answer the queries...
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`ChildRef::ask_anonymously`]: crate::child_ref::ChildRef::ask_anonymously
+    /// [`Distributor`]: crate::distributor::Distributor
+    /// [`Answer`]: crate::message::Answer
+    pub fn with_inflight_limit(mut self, limit: usize) -> Self {
+        trace!(
+            "Children({}): Setting in-flight asks limit: {}",
+            self.id(),
+            limit
+        );
+        self.inflight_limit = Some(Arc::new(InflightLimit::new(limit)));
+        self
+    }
+
+    /// Sets the amount of messages the mailbox of each element of
+    /// this children group can hold, so that a slow element doesn't
+    /// grow its mailbox without limit.
+    ///
+    /// What happens to the messages sent to an element whose mailbox
+    /// is full depends on the group's [`OverflowPolicy`] (see
+    /// [`with_overflow_policy`]). The amount of messages waiting in
+    /// the mailbox of an element is available through
+    /// [`ChildRef::mailbox_len`].
+    ///
+    /// By default, mailboxes are unbounded.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The maximum amount of messages waiting in the
+    ///     mailbox of each element.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    /// children
+    ///     .with_mailbox_capacity(1_000)
+    ///     .with_overflow_policy(OverflowPolicy::DropOldest)
+    ///     .with_exec(|ctx| {
+    ///         async move {
+    ///             // Handle the most recent messages...
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`with_overflow_policy`]: Self::with_overflow_policy
+    /// [`ChildRef::mailbox_len`]: crate::child_ref::ChildRef::mailbox_len
+    pub fn with_mailbox_capacity(mut self, capacity: usize) -> Self {
+        trace!(
+            "Children({}): Setting mailbox capacity: {}",
+            self.id(),
+            capacity
+        );
+        self.mailbox_capacity = Some(capacity);
+        self
+    }
+
+    /// Sets what happens to the messages sent to an element of this
+    /// children group whose mailbox is full (see [`OverflowPolicy`]
+    /// and [`with_mailbox_capacity`]).
+    ///
+    /// The default policy is [`OverflowPolicy::FailSender`].
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The policy applied to the messages sent to a full
+    ///     mailbox.
+    ///
+    /// [`with_mailbox_capacity`]: Self::with_mailbox_capacity
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        trace!(
+            "Children({}): Setting overflow policy: {:?}",
+            self.id(),
+            policy
+        );
+        self.overflow_policy = policy;
+        self
+    }
+
+    /// Sets a closure called whenever a message is added to the
+    /// mailbox of an element of this children group, received by
+    /// it, or dropped without being received (see [`MailboxEvent`]),
+    /// e.g. to measure how long the messages wait in the mailboxes.
+    ///
+    /// The closure is called by the senders and the elements
+    /// themselves, so it should return quickly.
+    ///
+    /// # Arguments
+    ///
+    /// * `observer` - The closure called with the events of the
+    ///     elements' mailboxes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_message_observer(|event: MailboxEvent| {
+    ///             if event.kind() == MailboxEventKind::Dequeued {
+    ///                 // Record `event.queued_for()` for `event.child_id()`...
+    ///             }
+    ///         })
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // ...
+    ///                 Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_message_observer<F>(mut self, observer: F) -> Self
+    where
+        F: Fn(MailboxEvent) + Send + Sync + 'static,
+    {
+        trace!("Children({}): Setting message observer.", self.id());
+        self.message_observer = Some(MessageObserver::new(observer));
+        self
+    }
+
+    /// Enables the profiling of this children group: the time its
+    /// elements spend handling each type of message is recorded,
+    /// and returned by [`ChildrenRef::profile_report`] (see
+    /// [`ProfileReport`]).
+    ///
+    /// An element handles a message from when it receives it until
+    /// it tries to receive another one. The profile is kept when
+    /// the elements are restarted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_profiling()
+    ///         .with_exec(|ctx| {
+    ///             async move {
+    ///                 // ...
+    ///                 Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`ChildrenRef::profile_report`]: crate::children_ref::ChildrenRef::profile_report
+    /// [`ProfileReport`]: crate::profile::ProfileReport
+    pub fn with_profiling(mut self) -> Self {
+        trace!("Children({}): Enabling profiling.", self.id());
+        self.profile = Some(Arc::default());
+        self
+    }
+
+    /// Sets the policy used to spawn or retire elements of this
+    /// children group at runtime, depending on the amount of messages
+    /// waiting in their mailboxes (see [`AutoscalePolicy`]).
+    ///
+    /// The spawned elements subscribe to the distributors of the
+    /// group like the others, and the retired elements unsubscribe
+    /// from them once they stopped. A retired element is stopped
+    /// like when calling [`ChildRef::stop_permanently`], so it only
+    /// gets to handle the messages left in its mailbox if the group
+    /// has a stop timeout (see [`with_stop_timeout`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The [`AutoscalePolicy`] deciding when to spawn
+    ///     or retire elements.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    /// children
+    ///     .with_redundancy(2)
+    ///     .with_autoscaling(
+    ///         AutoscalePolicy::new(2, 20)
+    ///             .scale_up_when_backlog_above(1_000)
+    ///             .with_cooldown(Duration::from_secs(10)),
+    ///     )
+    ///     .with_distributor(Distributor::named("jobs"))
+    ///     .with_exec(|ctx| {
+    ///         async move {
+    ///             loop {
+    ///                 let msg = ctx.recv().await?;
+    ///                 // Handle the job...
+    ///             }
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`AutoscalePolicy`]: crate::autoscale::AutoscalePolicy
+    /// [`ChildRef::stop_permanently`]: crate::child_ref::ChildRef::stop_permanently
+    /// [`with_stop_timeout`]: Self::with_stop_timeout
+    pub fn with_autoscaling(mut self, policy: AutoscalePolicy) -> Self {
+        trace!(
+            "Children({}): Setting autoscaling policy: {:?}",
+            self.id(),
+            policy
+        );
+        self.autoscaling = Some(policy);
+        self
+    }
+
+    /// Sets whether the elements of this children group that are
+    /// restarted after a failure inherit the messages that were left
+    /// in their mailbox, which they then drain following the group's
+    /// [`DrainPolicy`] (see [`with_drain_policy`]).
+    ///
+    /// When disabled, those messages are routed to the dead letters
+    /// instead. In both cases, the message the element was handling
+    /// when it failed (e.g. the one that made it panic) has already
+    /// been received and isn't handed to the restarted element. With
+    /// the `remote` feature, it is kept in [`Bastion::dead_letters`]
+    /// if it is a [`WireMessage`] (e.g. one received from another
+    /// node).
+    ///
+    /// Mailboxes aren't preserved by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `preserve` - Whether restarted elements inherit their
+    ///     mailbox.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         // Pending requests are still handled once an element
+    ///         // restarted...
+    ///         .with_mailbox_preservation(true)
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 // ...
+    ///                 Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`with_drain_policy`]: Self::with_drain_policy
+    /// [`Bastion::dead_letters`]: crate::Bastion::dead_letters
+    /// [`WireMessage`]: crate::wire::WireMessage
+    pub fn with_mailbox_preservation(mut self, preserve: bool) -> Self {
+        trace!(
+            "Children({}): Setting mailbox preservation: {}",
+            self.id(),
+            preserve
+        );
+        self.preserve_mailbox = preserve;
         self
     }
 
-    #[cfg(feature = "scaling")]
-    /// Sets a custom resizer for the Children.
-    ///
-    /// This method is available only with the `scaling` feature flag.
+    /// Sets the policy used by the restarted elements of this children
+    /// group to drain the messages they inherited from their previous
+    /// run (see [`DrainPolicy`]).
     ///
     /// # Arguments
     ///
-    /// * `resizer` - An instance of the [`Resizer`] struct.
+    /// * `policy` - The [`DrainPolicy`] to apply after a restart.
     ///
     /// # Example
     ///
@@ -500,44 +2400,57 @@ impl Children {
     /// # }
     /// #
     /// # fn run() {
-    ///     # Bastion::init();
-    ///     #
+    /// # Bastion::init();
+    /// #
     /// Bastion::children(|children| {
-    ///     children
-    ///         .with_redundancy(1)
-    ///         .with_resizer(
-    ///             OptimalSizeExploringResizer::default()
-    ///                 .with_lower_bound(10)
-    ///                 .with_upper_bound(UpperBound::Limit(100))
-    ///         )
+    /// children
+    ///     .with_mailbox_preservation(true)
+    ///     // After a restart, handle at most 100 old messages per tick.
+    ///     .with_drain_policy(DrainPolicy::PerTick(100))
+    ///     .with_exec(|ctx| {
+    ///         async move {
+    ///             // ...
+    ///             # Ok(())
+    ///         }
+    ///     })
     /// }).expect("Couldn't create the children group.");
-    ///     #
-    ///     # Bastion::start();
-    ///     # Bastion::stop();
-    ///     # Bastion::block_until_stopped();
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
     /// # }
     /// ```
-    pub fn with_resizer(mut self, resizer: OptimalSizeExploringResizer) -> Self {
-        self.redundancy = resizer.lower_bound() as usize;
-        self.resizer = Box::new(resizer);
+    pub fn with_drain_policy(mut self, policy: DrainPolicy) -> Self {
+        trace!(
+            "Children({}): Setting drain policy: {:?}",
+            self.id(),
+            policy
+        );
+        self.drain_policy = match policy {
+            // The inherited messages would never be received.
+            DrainPolicy::PerTick(limit) => DrainPolicy::PerTick(limit.max(1)),
+            policy => policy,
+        };
         self
     }
 
-    /// Sets the callbacks that will get called at this children group's
-    /// different lifecycle events.
+    /// Sets the policy used to decide whether this children group is
+    /// healthy from the failure rate of its elements (see
+    /// [`HealthPolicy`]).
     ///
-    /// See [`Callbacks`]'s documentation for more information about the
-    /// different callbacks available.
+    /// The resulting health is available through
+    /// [`ChildrenRef::health`], and a failing group isn't scaled up
+    /// by its resizer.
     ///
     /// # Arguments
     ///
-    /// * `callbacks` - The callbacks that will get called for this
-    ///     children group.
+    /// * `policy` - The [`HealthPolicy`] of the group.
     ///
     /// # Example
     ///
     /// ```rust
     /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
     /// #
     /// # #[cfg(feature = "tokio-runtime")]
     /// # #[tokio::main]
@@ -553,45 +2466,48 @@ impl Children {
     /// # fn run() {
     /// # Bastion::init();
     /// #
-    /// Bastion::children(|children| {
-    ///     let callbacks = Callbacks::new()
-    ///         .with_before_start(|| println!("Children group started."))
-    ///         .with_after_stop(|| println!("Children group stopped."));
-    ///
-    ///     children
-    ///         .with_callbacks(callbacks)
-    ///         .with_exec(|ctx| {
-    ///             // -- Children group started.
-    ///             async move {
-    ///                 // ...
-    ///                 # Ok(())
-    ///             }
-    ///             // -- Children group stopped.
-    ///         })
+    /// let children_ref = Bastion::children(|children| {
+    /// children
+    ///     // More than one failure per second means the group is failing.
+    ///     .with_health_policy(
+    ///         HealthPolicy::default()
+    ///             .with_window(Duration::from_secs(30))
+    ///             .with_failing_threshold(1.0)
+    ///     )
+    ///     .with_exec(|ctx| {
+    ///         async move {
+    ///             // ...
+    ///             # Ok(())
+    ///         }
+    ///     })
     /// }).expect("Couldn't create the children group.");
+    ///
+    /// let report = children_ref.health();
     /// #
     /// # Bastion::start();
     /// # Bastion::stop();
     /// # Bastion::block_until_stopped();
     /// # }
     /// ```
-    pub fn with_callbacks(mut self, callbacks: Callbacks) -> Self {
+    ///
+    /// [`ChildrenRef::health`]: crate::children_ref::ChildrenRef::health
+    pub fn with_health_policy(mut self, policy: HealthPolicy) -> Self {
         trace!(
-            "Children({}): Setting callbacks: {:?}",
+            "Children({}): Setting health policy: {:?}",
             self.id(),
-            callbacks
+            policy
         );
-        self.callbacks = callbacks;
+        self.health = Arc::new(GroupHealth::new(policy));
         self
     }
 
-    /// Overrides the default time interval for heartbeat onto
-    /// the user defined.
-    ///
+    /// Sets the strategy the supervisor of this children group
+    /// should use to restart its failed elements, instead of its
+    /// own (see [`Supervisor::with_restart_strategy`]).
     ///
     /// # Arguments
     ///
-    /// * `interval` - The value of the [`std::time::Duration`] type
+    /// * `restart_strategy` - The [`RestartStrategy`] of the group.
     ///
     /// # Example
     ///
@@ -615,14 +2531,17 @@ impl Children {
     /// #
     /// Bastion::children(|children| {
     /// children
-    ///     .with_heartbeat_tick(Duration::from_secs(5))
+    ///     // Don't hammer the database while it is down.
+    ///     .with_restart_strategy(
+    ///         RestartStrategy::exponential(Duration::from_millis(100), 2.0)
+    ///             .with_jitter()
+    ///             .with_max_delay(Duration::from_secs(30))
+    ///     )
     ///     .with_exec(|ctx| {
-    ///         // -- Children group started.
     ///         async move {
     ///             // ...
     ///             # Ok(())
     ///         }
-    ///         // -- Children group stopped.
     ///     })
     /// }).expect("Couldn't create the children group.");
     /// #
@@ -631,26 +2550,37 @@ impl Children {
     /// # Bastion::block_until_stopped();
     /// # }
     /// ```
-    pub fn with_heartbeat_tick(mut self, interval: Duration) -> Self {
+    ///
+    /// [`Supervisor::with_restart_strategy`]: crate::supervisor::Supervisor::with_restart_strategy
+    pub fn with_restart_strategy(mut self, restart_strategy: RestartStrategy) -> Self {
         trace!(
-            "Children({}): Set heartbeat tick to {:?}",
+            "Children({}): Setting restart strategy: {:?}",
             self.id(),
-            interval
+            restart_strategy
         );
-        self.hearbeat_tick = interval;
+        self.restart_strategy = Some(restart_strategy);
         self
     }
 
     /// Returns executable code for the actor that will trigger heartbeat
     fn get_heartbeat_fut(&self) -> Init {
-        let interval = self.hearbeat_tick;
+        let mut interval = match self.heartbeat_timeout {
+            Some(timeout) => self.hearbeat_tick.min(timeout / 2),
+            None => self.hearbeat_tick,
+        };
+        if let Some(threshold) = self.handler_warn_threshold {
+            interval = interval.min(threshold / 2);
+        }
+        if let Some(autoscaling) = &self.autoscaling {
+            interval = interval.min(autoscaling.check_interval());
+        }
 
         let exec_fut = move |ctx: BastionContext| async move {
             let self_path = ctx.current().path();
             let self_sender = ctx.current().sender();
 
             loop {
-                Delay::new(interval).await;
+                executor::sleep(interval).await;
 
                 let msg = BastionMessage::heartbeat();
                 let env = Envelope::new(msg, self_path.clone(), self_sender.clone());
@@ -696,6 +2626,45 @@ impl Children {
             .await;
     }
 
+    // Stops the elements, giving them `stop_timeout` to finish before
+    // killing them.
+    async fn stop(&mut self, stop_timeout: Duration) {
+        debug!(
+            "Children({}): Stopping within {:?}.",
+            self.id(),
+            stop_timeout
+        );
+        self.bcast.stop_children();
+
+        let mut children = FuturesUnordered::new();
+        for (_, (_, launched)) in self.launched.drain() {
+            children.push(launched);
+        }
+
+        // The elements kill themselves once their stop timeout elapsed,
+        // so this only catches those that never yield.
+        let mut deadline = executor::sleep(stop_timeout * 2);
+        let finished = loop {
+            match future::select(children.next(), &mut deadline).await {
+                future::Either::Left((Some(_), _)) => continue,
+                future::Either::Left((None, _)) => break true,
+                future::Either::Right(_) => break false,
+            }
+        };
+
+        if !finished {
+            warn!(
+                "Children({}): {} elements didn't stop in time, killing them.",
+                self.id(),
+                children.len()
+            );
+            for launched in children.iter() {
+                launched.cancel();
+            }
+            children.for_each(|_| async {}).await;
+        }
+    }
+
     fn stopped(&mut self) {
         debug!("Children({}): Stopped.", self.id());
         if let Err(e) = self.remove_dispatchers() {
@@ -705,6 +2674,12 @@ impl Children {
             warn!("couldn't remove all distributors from the registry: {}", e);
         };
         self.bcast.stopped();
+        system::current()
+            .events()
+            .publish(SystemEvent::GroupStopped {
+                group: self.id().clone(),
+                name: self.name(),
+            });
     }
 
     fn faulted(&mut self) {
@@ -727,7 +2702,10 @@ impl Children {
 
     async fn stop_children(&mut self) -> Result<(), ()> {
         self.disable_helper_actors().await;
-        self.kill().await;
+        match self.stop_timeout {
+            Some(stop_timeout) => self.stop(stop_timeout).await,
+            None => self.kill().await,
+        }
         self.stopped();
         Err(())
     }
@@ -759,27 +2737,265 @@ impl Children {
         Ok(())
     }
 
-    fn request_restarting_child(&mut self, id: &BastionId, parent_id: &BastionId) {
+    fn request_restarting_child(
+        &mut self,
+        id: &BastionId,
+        parent_id: &BastionId,
+        failure: ChildFailure,
+    ) {
         if parent_id == self.bcast.id() && self.launched.contains_key(id) {
             let parent_id = self.bcast.id().clone();
-            let msg = BastionMessage::restart_required(id.clone(), parent_id);
+            let msg = BastionMessage::restart_required(id.clone(), parent_id, failure);
             let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
             self.bcast.send_parent(env).ok();
         }
     }
 
-    fn restart_child(&mut self, old_id: &BastionId, old_state: Arc<Pin<Box<ContextState>>>) {
+    fn check_slow_handlers(&self) {
+        let threshold = match self.handler_warn_threshold {
+            Some(threshold) => threshold,
+            None => return,
+        };
+
+        for (id, state) in &self.elem_states {
+            if let Some((message, correlation_id, elapsed)) = state.slow_handling(threshold) {
+                warn!(
+                    "Children({}): Child({}) has been handling {} ({}) for {:?}.",
+                    self.id(),
+                    id,
+                    message,
+                    correlation_id,
+                    elapsed
+                );
+                system::current()
+                    .events()
+                    .publish(SystemEvent::SlowHandler {
+                        group: self.id().clone(),
+                        child: id.clone(),
+                        message,
+                        correlation_id,
+                        elapsed,
+                    });
+            }
+        }
+    }
+
+    fn check_heartbeats(&mut self) {
+        let timeout = match self.heartbeat_timeout {
+            Some(timeout) => timeout,
+            None => return,
+        };
+
+        let hung: Vec<_> = self
+            .elem_states
+            .iter()
+            .filter_map(|(id, state)| match state.unresponsive_for() {
+                Some(unresponsive_for) if unresponsive_for > timeout => {
+                    Some((id.clone(), unresponsive_for))
+                }
+                _ => None,
+            })
+            .collect();
+
+        for (id, unresponsive_for) in hung {
+            let sender = match self.launched.get(&id) {
+                Some((sender, launched)) => {
+                    launched.cancel();
+                    sender.clone()
+                }
+                None => continue,
+            };
+            warn!(
+                "Children({}): Child({}) didn't check in for {:?}.",
+                self.id(),
+                id,
+                unresponsive_for
+            );
+            // It is launched again once restarted.
+            if let Some(state) = self.elem_states.remove(&id) {
+                state.release_permit();
+            }
+            self.health.record_failure(FailureKind::Unresponsive);
+            system::current()
+                .events()
+                .publish(SystemEvent::ChildFailed {
+                    group: self.id().clone(),
+                    child: id.clone(),
+                    reason: FailureKind::Unresponsive,
+                });
+
+            let path = self.bcast.path().clone();
+            let child_ref = ChildRef::new(id.clone(), sender, self.name(), path);
+            // The element can't clean up after itself while it's hung.
+            let dispatchers: Vec<_> = self
+                .dispatchers
+                .iter()
+                .map(|dispatcher| dispatcher.dispatcher_type())
+                .collect();
+            let global_dispatcher = system::current().dispatcher();
+            global_dispatcher.remove(&dispatchers, &child_ref);
+            global_dispatcher
+                .remove_recipient(&self.distributors, child_ref.clone())
+                .ok();
+
+            MONITORS.notify(&child_ref, DownReason::Failed(FailureKind::Unresponsive));
+
+            let failure = ChildFailure::new(
+                id.clone(),
+                Some(child_ref),
+                FailureReason::Child(FailureKind::Unresponsive),
+            )
+            .with_message(Some(format!("didn't check in for {:?}", unresponsive_for)));
+            let parent_id = self.bcast.id().clone();
+            self.request_restarting_child(&id, &parent_id, failure);
+        }
+    }
+
+    fn autoscale(&mut self) {
+        let policy = match &self.autoscaling {
+            Some(policy) => policy,
+            None => return,
+        };
+        // The backlog of suspended elements can only grow.
+        if self.suspended {
+            return;
+        }
+        if let Some(last_autoscale) = self.last_autoscale {
+            if time::since(last_autoscale) < policy.cooldown() {
+                return;
+            }
+        }
+
+        let elems = self.launched.len();
+        let backlog = self
+            .elem_states
+            .values()
+            .map(|state| state.mailbox_len())
+            .sum();
+        match policy.decide(elems, backlog) {
+            // Adding elements to a crashing group would only add more crashes.
+            AutoscaleDecision::ScaleUp if self.health.status() == HealthStatus::Failing => {
+                debug!(
+                    "Children({}): Not scaling up: the group is failing.",
+                    self.id()
+                );
+            }
+            AutoscaleDecision::ScaleUp => {
+                debug!(
+                    "Children({}): Scaling up from {} elements (backlog: {}).",
+                    self.id(),
+                    elems,
+                    backlog
+                );
+                self.redundancy += 1;
+                self.launch_child();
+                self.last_autoscale = Some(time::now());
+            }
+            AutoscaleDecision::ScaleDown => {
+                // Retires the element with the fewest waiting messages.
+                let id = self
+                    .launched
+                    .keys()
+                    .min_by_key(|id| {
+                        self.elem_states
+                            .get(*id)
+                            .map(|state| state.mailbox_len())
+                            .unwrap_or_default()
+                    })
+                    .cloned();
+                if let Some(id) = id {
+                    debug!(
+                        "Children({}): Scaling down from {} elements (backlog: {}).",
+                        self.id(),
+                        elems,
+                        backlog
+                    );
+                    self.retire_child(&id);
+                    self.last_autoscale = Some(time::now());
+                }
+            }
+            AutoscaleDecision::Keep => (),
+        }
+    }
+
+    fn suspend_elems(&mut self, suspended: bool) {
+        if suspended {
+            debug!("Children({}): Suspending elements.", self.id());
+        } else {
+            debug!("Children({}): Resuming elements.", self.id());
+        }
+        self.suspended = suspended;
+
+        // The helper actors (e.g. the heartbeat) keep running.
+        for id in self.launched.keys() {
+            let msg = if suspended {
+                BastionMessage::suspend()
+            } else {
+                BastionMessage::resume()
+            };
+            let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
+            self.bcast.send_child(id, env);
+        }
+    }
+
+    // Restarts an element with the current exec closure, giving it
+    // the mailbox of its previous run. The context is `None` when the
+    // element is restarted to swap its exec closure or because it was
+    // asked to (see `request_restart`) rather than because of a
+    // failure.
+    fn restart_child(
+        &mut self,
+        old_id: &BastionId,
+        old_state: Arc<Pin<Box<ContextState>>>,
+        context: Option<RestartContext>,
+    ) {
         let parent = Parent::children(self.as_ref());
         let bcast = Broadcast::new(parent, BastionPathElement::Child(old_id.clone()));
 
         let id = bcast.id().clone();
         let sender = bcast.sender().clone();
         let path = bcast.path().clone();
-        let child_ref = ChildRef::new(id.clone(), sender.clone(), self.name(), path);
+        let child_ref = ChildRef::new(id.clone(), sender.clone(), self.name(), path)
+            .with_inflight_limit(self.inflight_limit.clone())
+            .with_mailbox(Some(old_state.mailbox()));
 
         let children = self.as_ref();
         let supervisor = self.bcast.parent().clone().into_supervisor();
 
+        #[cfg(feature = "remote")]
+        {
+            if context.is_some() {
+                self.record_poison_message(old_id, &old_state);
+            }
+        }
+        if context.is_some() && !self.preserve_mailbox {
+            old_state.discard_mailbox();
+        }
+        old_state.start_draining(self.drain_policy.clone());
+        old_state.check_in();
+        // The element might have panicked while handling a message.
+        old_state.release_permit();
+        // The new run schedules its own messages.
+        old_state.cancel_schedules();
+        if let Some(local_state) = &self.local_state {
+            // The state is kept when the element isn't restarted
+            // because of a failure.
+            let reset = context.is_some() && self.state_recovery == StateRecovery::Reset;
+            if reset || !old_state.has_local() {
+                old_state.set_local(local_state.create());
+            }
+        }
+        self.elem_states.insert(id.clone(), old_state.clone());
+        if context.is_some() {
+            self.health.record_restart();
+        }
+        system::current()
+            .events()
+            .publish(SystemEvent::ChildRestarted {
+                group: self.id().clone(),
+                child: id.clone(),
+            });
+
         let ctx = BastionContext::new(
             id.clone(),
             child_ref.clone(),
@@ -795,9 +3011,17 @@ impl Children {
         let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
         self.bcast.send_child(&id, env);
 
-        let msg = BastionMessage::apply_callback(CallbackType::AfterRestart);
-        let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
-        self.bcast.send_child(&id, env);
+        if let Some(context) = context {
+            let msg = BastionMessage::apply_callback(CallbackType::AfterRestart(context));
+            let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
+            self.bcast.send_child(&id, env);
+        }
+
+        if self.suspended {
+            let msg = BastionMessage::suspend();
+            let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
+            self.bcast.send_child(&id, env);
+        }
 
         let msg = BastionMessage::start();
         let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
@@ -806,7 +3030,10 @@ impl Children {
         debug!("Children({}): Restarting Child({}).", self.id(), bcast.id());
         let callbacks = self.callbacks.clone();
         let state = Arc::new(Box::pin(ContextState::new()));
-        let child = Child::new(exec, callbacks, bcast, state, child_ref);
+        let child = Child::new(exec, callbacks, bcast, state, child_ref)
+            .with_stop_timeout(self.stop_timeout)
+            .with_exec_timeout(self.exec_timeout)
+            .with_executor(self.dedicated_executor());
         debug!(
             "Children({}): Launching faulted Child({}).",
             self.id(),
@@ -817,6 +3044,102 @@ impl Children {
         self.launched.insert(id, (sender, launched));
     }
 
+    #[cfg(feature = "remote")]
+    // Keeps the message the failed element was handling in the dead
+    // letters, as it would likely make the restarted element fail
+    // again.
+    fn record_poison_message(&self, id: &BastionId, state: &ContextState) {
+        if let Some(message) = state.take_poison_message() {
+            debug!(
+                "Children({}): Child({}) failed while handling {:?}.",
+                self.id(),
+                id,
+                message
+            );
+            dead_letters::record(DeadLetter::poisoned(
+                Destination::Child(id.clone()),
+                message,
+                "the element failed while handling the message",
+            ));
+        }
+    }
+
+    fn replace_exec(&mut self, init: Init) {
+        debug!("Children({}): Replacing exec closure.", self.id());
+        self.init = init;
+
+        // The element being swapped (if any) is already going to
+        // run the new exec closure.
+        self.exec_swaps = self
+            .launched
+            .keys()
+            .filter(|id| self.swapping.as_ref() != Some(id))
+            .cloned()
+            .collect();
+        if self.swapping.is_none() {
+            self.swap_next_exec();
+        }
+    }
+
+    // Stops the next element still running the previous exec
+    // closure, which is then restarted with the new one (see
+    // `handle_swapped_child`).
+    fn swap_next_exec(&mut self) {
+        self.swapping = None;
+        while let Some(id) = self.exec_swaps.pop_front() {
+            if self.launched.contains_key(&id) {
+                debug!("Children({}): Swapping exec of Child({}).", self.id(), id);
+                // The element is given its stop timeout (if any) to
+                // finish its in-flight work.
+                self.bcast.stop_child(&id);
+                self.swapping = Some(id);
+                return;
+            }
+        }
+    }
+
+    fn handle_swapped_child(&mut self, id: &BastionId) {
+        self.respawn_child(id);
+        self.swap_next_exec();
+    }
+
+    // Stops the given element, which is then restarted with its
+    // state (see `handle_restarted_child`).
+    fn request_restart(&mut self, id: &BastionId) {
+        if self.launched.contains_key(id) && self.restarting.insert(id.clone()) {
+            debug!("Children({}): Restarting Child({}).", self.id(), id);
+            // The element is given its stop timeout (if any) to
+            // finish its in-flight work.
+            self.bcast.stop_child(id);
+        }
+    }
+
+    fn handle_restarted_child(&mut self, id: &BastionId) {
+        self.restarting.remove(id);
+        self.respawn_child(id);
+    }
+
+    // Restarts a stopped element with the state of its previous
+    // run, without counting it as a failure.
+    fn respawn_child(&mut self, id: &BastionId) {
+        let state = self
+            .elem_states
+            .get(id)
+            .cloned()
+            .unwrap_or_else(|| Arc::new(Box::pin(ContextState::new())));
+        self.restart_child(id, state, None);
+    }
+
+    fn retire_child(&mut self, id: &BastionId) {
+        if self.launched.contains_key(id) {
+            debug!("Children({}): Retiring Child({}).", self.id(), id);
+            self.redundancy = self.redundancy.saturating_sub(1);
+            // The child is then dropped once it stopped, without
+            // being restarted.
+            self.bcast.stop_child(id);
+        }
+    }
+
     fn drop_child(&mut self, id: &BastionId) {
         debug!(
             "Children({}): Dropping Child({:?}): reached restart limits.",
@@ -824,6 +3147,11 @@ impl Children {
             id,
         );
         self.launched.remove_entry(id);
+        if let Some(state) = self.elem_states.remove(id) {
+            state.release_permit();
+            state.clear_local();
+            state.cancel_schedules();
+        }
 
         #[cfg(feature = "scaling")]
         self.update_actors_count_stats();
@@ -878,9 +3206,14 @@ impl Children {
                 self.bcast.send_children(envelope);
             }
             Envelope {
-                msg: BastionMessage::RestartRequired { id, parent_id },
+                msg:
+                    BastionMessage::RestartRequired {
+                        id,
+                        parent_id,
+                        failure,
+                    },
                 ..
-            } => self.request_restarting_child(&id, &parent_id),
+            } => self.request_restarting_child(&id, &parent_id, failure),
             Envelope {
                 msg: BastionMessage::FinishedChild { .. },
                 ..
@@ -890,9 +3223,18 @@ impl Children {
                 ..
             } => unreachable!(),
             Envelope {
-                msg: BastionMessage::RestoreChild { id, state },
+                msg: BastionMessage::RestoreChild { id, state, context },
                 ..
-            } => self.restart_child(&id, state),
+            } => {
+                // The element failed while it was being restarted.
+                self.restarting.remove(&id);
+                self.restart_child(&id, state, Some(context));
+                // The element failed while its exec was being swapped,
+                // and was restarted with the new one.
+                if self.swapping.as_ref() == Some(&id) {
+                    self.swap_next_exec();
+                }
+            }
             Envelope {
                 msg: BastionMessage::DropChild { id },
                 ..
@@ -901,6 +3243,14 @@ impl Children {
                 msg: BastionMessage::SetState { .. },
                 ..
             } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::Stopped { id },
+                ..
+            } if self.swapping.as_ref() == Some(&id) => self.handle_swapped_child(&id),
+            Envelope {
+                msg: BastionMessage::Stopped { id },
+                ..
+            } if self.restarting.contains(&id) => self.handle_restarted_child(&id),
             Envelope {
                 msg: BastionMessage::Stopped { id },
                 ..
@@ -912,7 +3262,39 @@ impl Children {
             Envelope {
                 msg: BastionMessage::Heartbeat,
                 ..
-            } => {}
+            } => {
+                self.check_heartbeats();
+                self.check_slow_handlers();
+                self.autoscale();
+            }
+            Envelope {
+                msg: BastionMessage::Suspend,
+                ..
+            } => self.suspend_elems(true),
+            Envelope {
+                msg: BastionMessage::Resume,
+                ..
+            } => self.suspend_elems(false),
+            Envelope {
+                msg: BastionMessage::RetireChild { id },
+                ..
+            } => self.retire_child(&id),
+            Envelope {
+                msg: BastionMessage::RestartChild { id },
+                ..
+            } => self.request_restart(&id),
+            Envelope {
+                msg: BastionMessage::KillWithReason { .. },
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::LinkFailed { .. },
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::ReplaceExec(init),
+                ..
+            } => self.replace_exec(init),
         }
 
         Ok(())
@@ -958,6 +3340,13 @@ impl Children {
     #[cfg(feature = "scaling")]
     async fn autoresize_group(&mut self) {
         match self.resizer.scale(&self.launched).await {
+            // Adding elements to a crashing group would only add more crashes.
+            ScalingRule::Upscale(_) if self.health.status() == HealthStatus::Failing => {
+                debug!(
+                    "Children({}): Not upscaling: the group is failing.",
+                    self.id()
+                );
+            }
             ScalingRule::Upscale(count) => {
                 for _ in 0..count {
                     self.launch_child();
@@ -980,8 +3369,40 @@ impl Children {
         state.set_actor_stats(self.resizer.actor_stats());
     }
 
+    // Registers the group under its path, if it has one, to be
+    // looked up with `Bastion::children_ref`. This is done again
+    // each time the group handles a message so that the elements
+    // of the registered `ChildrenRef` stay up to date.
+    // Registers the group by name (if it has a path) and by
+    // identifier, so that it can be looked up with
+    // `Bastion::children_ref` and its elements resolved with
+    // `Bastion::resolve`.
+    fn publish(&self) {
+        let children_ref = self.as_ref();
+        if let Some(named_path) = &self.named_path {
+            system::current()
+                .names()
+                .register(named_path, NamedRef::Children(children_ref.clone()));
+        }
+        ADDRESSES.register(children_ref);
+    }
+
+    fn unpublish(&self) {
+        if let Some(named_path) = &self.named_path {
+            system::current().names().remove(named_path, self.id());
+        }
+        ADDRESSES.remove(self.id());
+    }
+
     async fn run(mut self) -> Self {
         debug!("Children({}): Launched.", self.id());
+        self.publish();
+        system::current()
+            .events()
+            .publish(SystemEvent::GroupStarted {
+                group: self.id().clone(),
+                name: self.name(),
+            });
 
         #[cfg(feature = "scaling")]
         self.update_actors_count_stats();
@@ -1001,8 +3422,10 @@ impl Children {
                     ..
                 })) => {
                     if self.initialize().await.is_err() {
+                        self.unpublish();
                         return self;
                     }
+                    self.publish();
                 }
                 Poll::Ready(Some(msg)) if !self.started => {
                     trace!(
@@ -1019,8 +3442,10 @@ impl Children {
                         msg
                     );
                     if self.handle(msg).await.is_err() {
+                        self.unpublish();
                         return self;
                     }
+                    self.publish();
                 }
                 // NOTE: because `Broadcast` always holds both a `Sender` and
                 //      `Receiver` of the same channel, this would only be
@@ -1043,17 +3468,47 @@ impl Children {
         let id = bcast.id().clone();
         let sender = bcast.sender().clone();
         let path = bcast.path().clone();
-        let child_ref = ChildRef::new(id.clone(), sender.clone(), name, path);
+        let mut state = ContextState::new();
+        let index = self.free_index();
+        state.set_index(index);
+        if let Some(init_data) = &self.init_data {
+            state.set_init_data(init_data.get(index));
+        }
+        if let Some(persistence) = &self.persistence {
+            let mut persistence = persistence.for_element(index);
+            persistence.codec = self.event_codec.clone();
+            persistence.recovery = self.recovery.clone();
+            state.set_persistence(persistence);
+        }
+        if let Some(concurrency_limit) = &self.concurrency_limit {
+            state.set_concurrency_limit(concurrency_limit.clone());
+        }
+        if let Some(capacity) = self.mailbox_capacity {
+            let mailbox = MailboxLimit::new(capacity, self.overflow_policy);
+            state.set_mailbox_limit(Arc::new(mailbox));
+        }
+        if let Some(observer) = &self.message_observer {
+            state.set_observer(id.clone(), observer.clone());
+        }
+        if let Some(profile) = &self.profile {
+            state.set_profile(profile.clone());
+        }
+        if let Some(local_state) = &self.local_state {
+            state.set_local(local_state.create());
+        }
+
+        let child_ref = ChildRef::new(id.clone(), sender.clone(), name, path)
+            .with_inflight_limit(self.inflight_limit.clone())
+            .with_mailbox(Some(state.mailbox()));
 
         let children = self.as_ref();
         let supervisor = self.bcast.parent().clone().into_supervisor();
 
-        #[allow(unused_mut)]
-        let mut state = ContextState::new();
         #[cfg(feature = "scaling")]
         self.init_data_for_scaling(&mut state);
 
         let state = Arc::new(Box::pin(state));
+        self.elem_states.insert(id.clone(), state.clone());
 
         let ctx = BastionContext::new(
             id.clone(),
@@ -1071,6 +3526,12 @@ impl Children {
 
         self.bcast.register(&bcast);
 
+        if self.suspended {
+            let msg = BastionMessage::suspend();
+            let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
+            self.bcast.send_child(&id, env);
+        }
+
         let msg = BastionMessage::start();
         let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
         self.bcast.send_child(&id, env);
@@ -1081,13 +3542,47 @@ impl Children {
             bcast.id()
         );
         let callbacks = self.callbacks.clone();
-        let child = Child::new(exec, callbacks, bcast, state, child_ref);
+        let child = Child::new(exec, callbacks, bcast, state, child_ref)
+            .with_stop_timeout(self.stop_timeout)
+            .with_exec_timeout(self.exec_timeout)
+            .with_executor(self.dedicated_executor());
         debug!("Children({}): Launching Child({}).", self.id(), child.id());
         let id = child.id().clone();
         let launched = child.launch();
         self.launched.insert(id, (sender, launched));
     }
 
+    // Returns the threads dedicated to the group, starting them if
+    // they weren't yet, or `None` if the group runs on the shared
+    // pool.
+    fn dedicated_executor(&mut self) -> Option<Arc<BlockingPool>> {
+        if self.executor.is_none() {
+            let affinity = self.affinity.clone().filter(|cores| !cores.is_empty());
+            let threads = self
+                .dedicated_threads
+                .or_else(|| affinity.as_ref().map(CoreSet::len))?;
+            debug!(
+                "Children({}): Starting {} dedicated threads.",
+                self.id(),
+                threads
+            );
+            self.executor = Some(Arc::new(BlockingPool::dedicated(threads, affinity)));
+        }
+
+        self.executor.clone()
+    }
+
+    // Returns the smallest index that isn't used by a launched
+    // element.
+    fn free_index(&self) -> usize {
+        let used: FxHashSet<_> = self
+            .elem_states
+            .values()
+            .map(|state| state.index())
+            .collect();
+        (0..).find(|index| !used.contains(index)).unwrap()
+    }
+
     pub(crate) fn launch_heartbeat(&mut self) {
         let name = self.name();
         let parent = Parent::children(self.as_ref());
@@ -1138,12 +3633,12 @@ impl Children {
     pub(crate) fn launch(self) -> RecoverableHandle<Self> {
         debug!("Children({}): Launching.", self.id());
         let stack = self.stack();
-        pool::spawn(self.run(), stack)
+        executor::spawn_with(self.run(), stack)
     }
 
     /// Registers all declared local dispatchers in the global dispatcher.
     pub(crate) fn register_dispatchers(&self) -> AnyResult<()> {
-        let global_dispatcher = SYSTEM.dispatcher();
+        let global_dispatcher = system::current().dispatcher();
 
         for dispatcher in self.dispatchers.iter() {
             global_dispatcher.register_dispatcher(dispatcher)?;
@@ -1153,7 +3648,7 @@ impl Children {
 
     /// Removes all declared local dispatchers from the global dispatcher.
     pub(crate) fn remove_dispatchers(&self) -> AnyResult<()> {
-        let global_dispatcher = SYSTEM.dispatcher();
+        let global_dispatcher = system::current().dispatcher();
 
         for dispatcher in self.dispatchers.iter() {
             global_dispatcher.remove_dispatcher(dispatcher)?;
@@ -1163,7 +3658,7 @@ impl Children {
 
     /// Registers all declared local distributors in the global dispatcher.
     pub(crate) fn register_distributors(&self) -> AnyResult<()> {
-        let global_dispatcher = SYSTEM.dispatcher();
+        let global_dispatcher = system::current().dispatcher();
 
         for distributor in self.distributors.iter() {
             global_dispatcher.register_distributor(distributor)?;
@@ -1173,7 +3668,7 @@ impl Children {
 
     /// Removes all declared local distributors from the global dispatcher.
     pub(crate) fn remove_distributors(&self) -> AnyResult<()> {
-        let global_dispatcher = SYSTEM.dispatcher();
+        let global_dispatcher = system::current().dispatcher();
 
         for distributor in self.distributors.iter() {
             global_dispatcher.remove_distributor(distributor)?;
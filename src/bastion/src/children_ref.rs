@@ -1,17 +1,28 @@
 //!
 //! Allows users to communicate with children through the mailboxes.
 use crate::broadcast::Sender;
-use crate::context::BastionId;
+use crate::child::Init;
+use crate::context::{BastionContext, BastionId};
 use crate::dispatcher::DispatcherType;
 use crate::envelope::Envelope;
-use crate::message::{BastionMessage, Message};
+use crate::errors::SendError;
+use crate::executor;
+use crate::health::{GroupHealth, HealthReport};
+use crate::message::{Answer, BastionMessage, Message};
 use crate::path::BastionPath;
-use crate::system::SYSTEM;
+use crate::profile::{GroupProfile, ProfileReport};
+use crate::results::GroupResults;
+use crate::system;
+use crate::time;
 use crate::{child_ref::ChildRef, distributor::Distributor};
+use futures::future;
 use std::cmp::{Eq, PartialEq};
 use std::fmt::Debug;
+use std::future::Future;
 use std::sync::Arc;
-use tracing::{debug, trace};
+use std::task::Poll;
+use std::time::Duration;
+use tracing::{debug, trace, warn};
 
 #[derive(Debug, Clone)]
 /// A "reference" to a children group, allowing to communicate
@@ -23,6 +34,39 @@ pub struct ChildrenRef {
     children: Vec<ChildRef>,
     dispatchers: Vec<DispatcherType>,
     distributors: Vec<Distributor>,
+    health: Arc<GroupHealth>,
+    results: Arc<GroupResults>,
+    profile: Option<Arc<GroupProfile>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The outcome of [`ChildrenRef::drain`].
+pub struct DrainReport {
+    remaining: usize,
+    elapsed: Duration,
+}
+
+// How often `ChildrenRef::drain` checks whether the mailboxes of the
+// group's elements are empty.
+const DRAIN_CHECK_INTERVAL: Duration = Duration::from_millis(10);
+
+impl DrainReport {
+    /// Returns `true` if the mailboxes of the group's elements were
+    /// emptied before the timeout.
+    pub fn is_complete(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Returns the amount of messages that were still waiting in the
+    /// mailboxes of the group's elements when it was stopped.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Returns how long the group was drained for.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
 }
 
 impl ChildrenRef {
@@ -33,6 +77,8 @@ impl ChildrenRef {
         children: Vec<ChildRef>,
         dispatchers: Vec<DispatcherType>,
         distributors: Vec<Distributor>,
+        health: Arc<GroupHealth>,
+        results: Arc<GroupResults>,
     ) -> Self {
         ChildrenRef {
             id,
@@ -41,9 +87,17 @@ impl ChildrenRef {
             children,
             dispatchers,
             distributors,
+            health,
+            results,
+            profile: None,
         }
     }
 
+    pub(crate) fn with_profile(mut self, profile: Option<Arc<GroupProfile>>) -> Self {
+        self.profile = profile;
+        self
+    }
+
     /// Returns the identifier of the children group this `ChildrenRef`
     /// is referencing.
     ///
@@ -153,6 +207,134 @@ impl ChildrenRef {
         &self.distributors
     }
 
+    /// Returns a snapshot of the health of the children group, built
+    /// from the failures and restarts of its elements (see
+    /// [`HealthPolicy`]).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children).unwrap();
+    /// let report = children_ref.health();
+    /// if report.is_failing() {
+    ///     // stop sending work to the group...
+    /// }
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`HealthPolicy`]: crate::health::HealthPolicy
+    pub fn health(&self) -> HealthReport {
+        self.health.report()
+    }
+
+    pub(crate) fn group_health(&self) -> &Arc<GroupHealth> {
+        &self.health
+    }
+
+    /// Returns the profile of the messages handled by the elements
+    /// of the children group (see [`ProfileReport`]), or `None` if
+    /// profiling wasn't enabled using [`Children::with_profiling`].
+    ///
+    /// [`ProfileReport`]: crate::profile::ProfileReport
+    /// [`Children::with_profiling`]: crate::children::Children::with_profiling
+    pub fn profile_report(&self) -> Option<ProfileReport> {
+        self.profile.as_ref().map(|profile| profile.report())
+    }
+
+    pub(crate) fn group_results(&self) -> &Arc<GroupResults> {
+        &self.results
+    }
+
+    /// Waits for all the elements of the children group this
+    /// `ChildrenRef` is referencing to complete, i.e. for their
+    /// future to return `Ok(())` or for them to call
+    /// [`BastionContext::stop_with`], and returns the values they
+    /// exited with, in the same order as [`elems`].
+    ///
+    /// The value of an element is `None` if it completed without
+    /// calling [`BastionContext::stop_with`], or with a value of
+    /// another type than `R`. Elements that fail are restarted
+    /// following their supervisor's restart strategy and are waited
+    /// for until they complete.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// let children_ref = Bastion::children(|children| {
+    ///     children.with_redundancy(4).with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             let sum: u64 = (0..ctx.elem_index() as u64 * 1_000).sum();
+    ///             ctx.stop_with(sum)
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    ///
+    /// # Bastion::start();
+    /// let sums: Vec<Option<u64>> = run!(children_ref.join_all());
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`BastionContext::stop_with`]: crate::context::BastionContext::stop_with
+    /// [`elems`]: Self::elems
+    pub async fn join_all<R>(&self) -> Vec<Option<R>>
+    where
+        R: Clone + Send + Sync + 'static,
+    {
+        debug!(
+            "ChildrenRef({}): Waiting for the elements to complete.",
+            self.id()
+        );
+        let ids: Vec<_> = self.elems().iter().map(ChildRef::id).collect();
+        future::poll_fn(|cx| {
+            // Parking first so that an element completing meanwhile
+            // wakes the future up.
+            self.results.park(cx.waker());
+            match self.results.collect(&ids) {
+                Some(results) => Poll::Ready(results),
+                None => Poll::Pending,
+            }
+        })
+        .await
+    }
+
     /// Returns a list of [`ChildRef`] referencing the elements
     /// of the children group this `ChildrenRef` is referencing.
     ///
@@ -262,6 +444,91 @@ impl ChildrenRef {
         self.send(env).map_err(|err| err.into_msg().unwrap())
     }
 
+    /// "Asks" a clone of a message to each of the elements of the
+    /// children group this `ChildrenRef` is referencing, without
+    /// going through a distributor.
+    ///
+    /// The elements are those returned by [`elems`], and the
+    /// returned [`Answer`]s are in the same order.
+    ///
+    /// This method returns the [`Answer`]s if it succeeded, or a
+    /// [`SendError`] if the group has no elements or if the message
+    /// couldn't be asked to one of them (e.g. because the group
+    /// reached its limit of asks waiting for an answer, see
+    /// [`Children::with_inflight_limit`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The message to ask.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children).unwrap();
+    /// # Bastion::children(|children| {
+    /// #     children.with_exec(move |ctx: BastionContext| {
+    /// #         let children_ref = children_ref.clone();
+    /// #         async move {
+    /// let answers = children_ref
+    ///     .ask_all("How many jobs are you running?")
+    ///     .expect("Couldn't ask the message.");
+    ///
+    /// for answer in answers {
+    ///     msg! { answer.await?,
+    ///         jobs: usize => {
+    ///             println!("{} jobs", jobs);
+    ///         };
+    ///         _: _ => ();
+    ///     }
+    /// }
+    /// #
+    /// #             Ok(())
+    /// #         }
+    /// #     })
+    /// # }).unwrap();
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`elems`]: Self::elems
+    /// [`Answer`]: crate::message::Answer
+    /// [`SendError`]: crate::errors::SendError
+    /// [`Children::with_inflight_limit`]: crate::children::Children::with_inflight_limit
+    pub fn ask_all<M: Message + Clone>(&self, msg: M) -> Result<Vec<Answer>, SendError> {
+        debug!(
+            "ChildrenRef({}): Asking message to all elements: {:?}",
+            self.id(),
+            msg
+        );
+        if self.children.is_empty() {
+            return Err(SendError::EmptyRecipient);
+        }
+
+        self.children
+            .iter()
+            .map(|child| child.try_ask_anonymously(msg.clone()))
+            .collect()
+    }
+
     /// Sends a message to the children group this `ChildrenRef`
     /// is referencing to tell it to stop all of its running
     /// elements.
@@ -303,6 +570,112 @@ impl ChildrenRef {
         self.send(env).map_err(|_| ())
     }
 
+    /// Drains the children group this `ChildrenRef` is referencing
+    /// before stopping it, e.g. to replace it during a rolling
+    /// deployment.
+    ///
+    /// The elements of the group are first removed from all the
+    /// [`Distributor`]s the group subscribed to, so that they stop
+    /// receiving new work. The returned future then waits until the
+    /// mailboxes of the elements are empty or until `timeout`
+    /// passed, and stops the group (giving its elements their stop
+    /// timeout to finish their in-flight work, see
+    /// [`Children::with_stop_timeout`]).
+    ///
+    /// Note that only the elements referenced by this `ChildrenRef`
+    /// (see [`elems`]) are waited for, and that messages sent
+    /// directly to them keep being received while draining.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long to wait for the mailboxes to be emptied
+    ///     before stopping the group anyway.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| {
+    /// #     children.with_distributor(Distributor::named("parsers"))
+    /// # }).unwrap();
+    /// # Bastion::start();
+    /// let report: DrainReport = run!(children_ref.drain(Duration::from_secs(30)));
+    /// if !report.is_complete() {
+    ///     // Some messages were left unhandled...
+    /// }
+    /// #
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Distributor`]: crate::distributor::Distributor
+    /// [`Children::with_stop_timeout`]: crate::children::Children::with_stop_timeout
+    /// [`elems`]: Self::elems
+    pub async fn drain(&self, timeout: Duration) -> DrainReport {
+        debug!("ChildrenRef({}): Draining within {:?}.", self.id(), timeout);
+        let started_at = time::now();
+
+        let global_dispatcher = system::current().dispatcher();
+        for child in self.elems() {
+            if let Err(error) =
+                global_dispatcher.remove_recipient(&self.distributors, child.clone())
+            {
+                warn!(
+                    "ChildrenRef({}): Couldn't unsubscribe Child({}): {}",
+                    self.id(),
+                    child.id(),
+                    error
+                );
+            }
+        }
+
+        let remaining = loop {
+            let remaining: usize = self.elems().iter().map(ChildRef::mailbox_len).sum();
+            if remaining == 0 || time::since(started_at) >= timeout {
+                break remaining;
+            }
+
+            trace!(
+                "ChildrenRef({}): {} messages left to drain.",
+                self.id(),
+                remaining
+            );
+            executor::sleep(DRAIN_CHECK_INTERVAL).await;
+        };
+
+        if remaining > 0 {
+            warn!(
+                "ChildrenRef({}): Stopping with {} messages left after {:?}.",
+                self.id(),
+                remaining,
+                timeout
+            );
+        }
+        self.stop().ok();
+
+        DrainReport {
+            remaining,
+            elapsed: time::since(started_at),
+        }
+    }
+
     /// Sends a message to the children group this `ChildrenRef`
     /// is referencing to tell it to kill all of its running
     /// elements.
@@ -344,10 +717,85 @@ impl ChildrenRef {
         self.send(env).map_err(|_| ())
     }
 
+    /// Sends a message to the children group this `ChildrenRef`
+    /// is referencing to tell it to replace the closure used by
+    /// its elements (see [`Children::with_exec`]), without
+    /// downtime.
+    ///
+    /// The elements are swapped one at a time: each of them is
+    /// stopped (being given the group's stop timeout, if any, to
+    /// finish its in-flight work), then restarted with the new
+    /// closure and the messages that were still in its mailbox
+    /// (drained following the group's [`DrainPolicy`]), before the
+    /// next one is swapped. The elements restarted by their
+    /// supervisor in the meantime use the new closure too.
+    ///
+    /// This method returns `()` if it succeeded, or `Err(())`
+    /// otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `init` - The closure taking a [`BastionContext`] and
+    ///     returning a [`Future`] that will be used by every element
+    ///     of the children group from now on.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children).unwrap();
+    /// children_ref
+    ///     .replace_exec(|ctx: BastionContext| async move {
+    ///         // The new behavior of the elements...
+    ///         loop {
+    ///             msg! { ctx.recv().await?,
+    ///                 msg: &'static str => {
+    ///                     println!("v2 received: {}", msg);
+    ///                 };
+    ///                 _: _ => ();
+    ///             }
+    ///         }
+    ///     })
+    ///     .expect("Couldn't send the message.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Children::with_exec`]: crate::children::Children::with_exec
+    /// [`DrainPolicy`]: crate::children::DrainPolicy
+    pub fn replace_exec<I, F>(&self, init: I) -> Result<(), ()>
+    where
+        I: Fn(BastionContext) -> F + Send + 'static,
+        F: Future<Output = Result<(), ()>> + Send + 'static,
+    {
+        debug!("ChildrenRef({}): Replacing exec closure.", self.id());
+        let msg = BastionMessage::replace_exec(Init::new(init));
+        let env = Envelope::from_dead_letters(msg);
+        self.send(env).map_err(|_| ())
+    }
+
     pub(crate) fn send(&self, env: Envelope) -> Result<(), Envelope> {
         trace!("ChildrenRef({}): Sending message: {:?}", self.id(), env);
         self.sender.unbounded_send(env).or_else(|err| {
-            SYSTEM
+            system::current()
                 .dead_letters()
                 .sender
                 .unbounded_send(err.into_inner())
@@ -0,0 +1,962 @@
+//!
+//! The membership of the cluster formed by the nodes connected using
+//! the remote transport, returned by [`Bastion::cluster`].
+//!
+//! Once a node joined the cluster using [`Cluster::join`], it
+//! periodically gossips its heartbeat, along with the heartbeats of
+//! the members it knows about, to every member. A member whose
+//! heartbeat didn't increase for longer than the failure timeout is
+//! unreachable, and is removed from the cluster if it doesn't come
+//! back within the removal timeout.
+//!
+//! The members also gossip the distributors which have recipients
+//! on them, so that [`Distributor::tell_everyone`] reaches the
+//! recipients of all the members, and that [`Distributor::ask_one`]
+//! asks the question to the member answering the fastest when there
+//! is no recipient on this node.
+//!
+//! Every member elects the same leader for a group (see
+//! [`Cluster::leadership`]) without coordination: the reachable
+//! member with the highest rendezvous hashing score for the name of
+//! the group. Since the membership is gossiped, the members may
+//! briefly disagree on the leader while it changes, and each side of
+//! a network partition elects its own leader.
+//!
+//! This module is only available with the `remote` feature.
+//!
+//! [`Bastion::cluster`]: crate::Bastion::cluster
+//! [`Distributor::tell_everyone`]: crate::distributor::Distributor::tell_everyone
+//! [`Distributor::ask_one`]: crate::distributor::Distributor::ask_one
+use crate::children_ref::ChildrenRef;
+use crate::context::BastionContext;
+use crate::dead_letters::{self, DeadLetter, Destination};
+use crate::distributor::Distributor;
+use crate::executor;
+use crate::message::Answer;
+use crate::remote::{self, Frame};
+use crate::system::{self, STRING_INTERNER};
+use crate::time::{self, Instant};
+use crate::wire::WireMessage;
+use crate::Bastion;
+use futures::channel::mpsc;
+use futures::{future, stream, Stream, StreamExt};
+use fxhash::FxHashMap;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::io::{self, ErrorKind};
+use std::net::SocketAddr;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+use tracing::{debug, info, trace};
+
+static CLUSTER: Lazy<ClusterState> = Lazy::new(ClusterState::default);
+
+#[derive(Debug, Clone, Copy)]
+/// The cluster formed by this node and the other nodes it gossips
+/// with, returned by [`Bastion::cluster`].
+///
+/// # Example
+///
+/// ```no_run
+/// # use bastion::prelude::*;
+/// # use futures::StreamExt;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// # Bastion::start();
+/// let cluster = Bastion::cluster();
+/// let config = ClusterConfig::new("node-a", "10.0.0.1:4222".parse().unwrap())
+///     .with_seed("10.0.0.2:4222".parse().unwrap());
+/// cluster.join(config).expect("Couldn't join the cluster.");
+///
+/// let mut events = cluster.events();
+/// spawn!(async move {
+///     while let Some(event) = events.next().await {
+///         println!("{:?}", event);
+///     }
+/// });
+///
+/// for member in cluster.members() {
+///     println!("{} ({}): {:?}", member.name(), member.addr(), member.status());
+/// }
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+///
+/// [`Bastion::cluster`]: crate::Bastion::cluster
+pub struct Cluster {
+    state: &'static ClusterState,
+}
+
+#[derive(Debug, Clone)]
+/// The configuration of this node in the cluster, given to
+/// [`Cluster::join`].
+pub struct ClusterConfig {
+    name: String,
+    addr: SocketAddr,
+    seeds: Vec<SocketAddr>,
+    gossip_interval: Duration,
+    failure_timeout: Duration,
+    removal_timeout: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Whether a member of the cluster is reachable.
+pub enum MemberStatus {
+    /// The member's heartbeat increased within the failure timeout.
+    Up,
+    /// The member's heartbeat didn't increase for longer than the
+    /// failure timeout.
+    Unreachable,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A member of the cluster, returned by [`Cluster::members`].
+pub struct Member {
+    name: String,
+    addr: SocketAddr,
+    status: MemberStatus,
+    distributors: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+/// A change of the membership of the cluster, emitted by the stream
+/// returned by [`Cluster::events`].
+pub enum ClusterEvent {
+    /// A node joined the cluster.
+    MemberJoined(Member),
+    /// A member became unreachable.
+    MemberUnreachable(Member),
+    /// An unreachable member became reachable again.
+    MemberReachable(Member),
+    /// A member left the cluster using [`Cluster::leave`].
+    MemberLeft(Member),
+    /// A member was unreachable for longer than the removal timeout
+    /// and was removed from the cluster.
+    MemberRemoved(Member),
+}
+
+#[derive(Debug, Clone)]
+/// The leadership of a group by the members of the cluster,
+/// returned by [`Cluster::leadership`].
+///
+/// # Example
+///
+/// ```no_run
+/// # use bastion::prelude::*;
+/// # use futures::StreamExt;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// # Bastion::start();
+/// let mut roles = Bastion::cluster().leadership("cron").watch();
+/// spawn!(async move {
+///     while let Some(role) = roles.next().await {
+///         match role {
+///             Role::Leader => println!("Running the scheduled jobs."),
+///             Role::Follower => println!("Leaving the scheduled jobs to the leader."),
+///         }
+///     }
+/// });
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct Leadership {
+    group: String,
+    state: &'static ClusterState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The role of this node in a group, emitted by the stream returned
+/// by [`Leadership::watch`].
+pub enum Role {
+    /// This node leads the group.
+    Leader,
+    /// Another member leads the group.
+    Follower,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+// The state of a member, as gossiped by the nodes.
+pub(crate) struct Gossip {
+    name: String,
+    addr: SocketAddr,
+    heartbeat: u64,
+    distributors: Vec<String>,
+    leaving: bool,
+}
+
+#[derive(Debug, Default)]
+struct ClusterState {
+    local: Mutex<Option<Local>>,
+    members: RwLock<FxHashMap<String, MemberState>>,
+    // The heartbeats of the removed members, whose older gossip is
+    // ignored.
+    removed: Mutex<FxHashMap<String, u64>>,
+    watchers: Mutex<Vec<mpsc::UnboundedSender<ClusterEvent>>>,
+}
+
+#[derive(Debug)]
+// This node, once it joined the cluster.
+struct Local {
+    config: ClusterConfig,
+    heartbeat: u64,
+    // The groups gossiping with the other members and accepting
+    // their connections.
+    groups: Vec<ChildrenRef>,
+}
+
+#[derive(Debug)]
+struct MemberState {
+    gossip: Gossip,
+    // When the member's heartbeat last increased.
+    seen_at: Instant,
+    status: MemberStatus,
+}
+
+impl ClusterConfig {
+    /// Creates the configuration of a node named `name`, listening
+    /// for the connections of the other members at `addr`, which is
+    /// also the address gossiped to them.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the node, unique within the cluster.
+    /// * `addr` - The address the node listens on.
+    pub fn new(name: impl Into<String>, addr: SocketAddr) -> Self {
+        ClusterConfig {
+            name: name.into(),
+            addr,
+            seeds: Vec::new(),
+            gossip_interval: Duration::from_secs(1),
+            failure_timeout: Duration::from_secs(5),
+            removal_timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// Adds the address of a node of the cluster, to which the node
+    /// gossips until it knows about other members.
+    pub fn with_seed(mut self, addr: SocketAddr) -> Self {
+        self.seeds.push(addr);
+        self
+    }
+
+    /// Sets how often the node gossips with the other members.
+    /// Defaults to 1 second.
+    pub fn with_gossip_interval(mut self, interval: Duration) -> Self {
+        self.gossip_interval = interval;
+        self
+    }
+
+    /// Sets how long the heartbeat of a member can stay the same
+    /// before it is unreachable. Defaults to 5 seconds.
+    pub fn with_failure_timeout(mut self, timeout: Duration) -> Self {
+        self.failure_timeout = timeout;
+        self
+    }
+
+    /// Sets how long the heartbeat of a member can stay the same
+    /// before it is removed from the cluster. Defaults to 30
+    /// seconds.
+    pub fn with_removal_timeout(mut self, timeout: Duration) -> Self {
+        self.removal_timeout = timeout;
+        self
+    }
+
+    /// Returns the name of the node.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the address the node listens on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Member {
+    /// Returns the name of the member, which can be given to
+    /// [`Distributor::remote`].
+    ///
+    /// [`Distributor::remote`]: crate::distributor::Distributor::remote
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the address the member listens on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns whether the member is reachable.
+    pub fn status(&self) -> MemberStatus {
+        self.status
+    }
+
+    /// Returns the names of the distributors which have recipients
+    /// on the member.
+    pub fn distributors(&self) -> &[String] {
+        &self.distributors
+    }
+}
+
+impl Cluster {
+    pub(crate) fn get() -> Self {
+        Cluster { state: &CLUSTER }
+    }
+
+    /// Makes this node join the cluster: starts accepting the
+    /// connections of the other members at the configured address
+    /// (see [`Bastion::listen`]) and gossiping with them, starting
+    /// with the seeds.
+    ///
+    /// This method returns an error if the node already joined the
+    /// cluster or if the address couldn't be bound.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration of the node.
+    ///
+    /// [`Bastion::listen`]: crate::Bastion::listen
+    pub fn join(&self, config: ClusterConfig) -> io::Result<()> {
+        // FIXME: panics
+        let mut local = self.state.local.lock().unwrap();
+        if local.is_some() {
+            return Err(io::Error::new(
+                ErrorKind::AlreadyExists,
+                "The node already joined the cluster.",
+            ));
+        }
+
+        info!("Cluster: Joining as {} ({}).", config.name, config.addr);
+        let listener = remote::listen(config.addr)?;
+        let interval = config.gossip_interval;
+        let gossip = Bastion::children(|children| {
+            children.with_name("bastion-cluster").with_exec(
+                move |_ctx: BastionContext| async move {
+                    loop {
+                        executor::sleep(interval).await;
+                        CLUSTER.tick();
+                    }
+                },
+            )
+        })
+        .map_err(|_| io::Error::new(ErrorKind::Other, "Couldn't create the children group."))?;
+
+        *local = Some(Local {
+            config,
+            heartbeat: 0,
+            groups: vec![gossip, listener],
+        });
+        Ok(())
+    }
+
+    /// Makes this node leave the cluster, telling the other members
+    /// about it, and stops gossiping and accepting connections.
+    pub fn leave(&self) {
+        // FIXME: panics
+        let local = match self.state.local.lock().unwrap().take() {
+            Some(local) => local,
+            None => return,
+        };
+
+        info!("Cluster: Leaving.");
+        let mut leaving = self.state.local_gossip(&local.config, local.heartbeat + 1);
+        leaving.leaving = true;
+        let frame = Frame::Gossip {
+            members: vec![leaving],
+        };
+        for name in self.state.member_names() {
+            if let Err(err) = remote::send(&name, &frame) {
+                debug!("Cluster: Couldn't tell {} about leaving: {}", name, err);
+            }
+        }
+
+        for group in local.groups {
+            group.stop().ok();
+        }
+        // FIXME: panics
+        self.state.members.write().unwrap().clear();
+    }
+
+    /// Returns the name of this node, if it joined the cluster.
+    pub fn node(&self) -> Option<String> {
+        // FIXME: panics
+        let local = self.state.local.lock().unwrap();
+        local.as_ref().map(|local| local.config.name.clone())
+    }
+
+    /// Returns the members of the cluster known by this node, this
+    /// node included, sorted by name. Nothing is returned if this
+    /// node didn't join the cluster.
+    pub fn members(&self) -> Vec<Member> {
+        // FIXME: panics
+        let local = match &*self.state.local.lock().unwrap() {
+            Some(local) => self.state.local_gossip(&local.config, local.heartbeat),
+            None => return Vec::new(),
+        };
+
+        let mut members: Vec<_> = self
+            .state
+            .members
+            .read()
+            .unwrap()
+            .values()
+            .map(MemberState::member)
+            .collect();
+        members.push(Member {
+            name: local.name,
+            addr: local.addr,
+            status: MemberStatus::Up,
+            distributors: local.distributors,
+        });
+        members.sort_by(|a, b| a.name.cmp(&b.name));
+        members
+    }
+
+    /// Returns the leadership of the group with the given name, whose
+    /// leader is elected among the reachable members.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The name of the group, shared by the members.
+    pub fn leadership(&self, group: impl Into<String>) -> Leadership {
+        Leadership {
+            group: group.into(),
+            state: self.state,
+        }
+    }
+
+    /// Returns a stream of the changes of the membership of the
+    /// cluster (see [`ClusterEvent`]), starting from now.
+    pub fn events(&self) -> impl Stream<Item = ClusterEvent> {
+        let (sender, receiver) = mpsc::unbounded();
+        // FIXME: panics
+        self.state.watchers.lock().unwrap().push(sender);
+        receiver
+    }
+}
+
+impl Leadership {
+    /// Returns the name of the group.
+    pub fn group(&self) -> &str {
+        &self.group
+    }
+
+    /// Returns the name of the member leading the group, or `None`
+    /// if this node didn't join the cluster.
+    pub fn leader(&self) -> Option<String> {
+        self.state.leader(&self.group)
+    }
+
+    /// Returns the role of this node in the group. A node which
+    /// didn't join the cluster leads every group.
+    pub fn role(&self) -> Role {
+        // FIXME: panics
+        let local = self.state.local.lock().unwrap();
+        let name = match &*local {
+            Some(local) => local.config.name.clone(),
+            None => return Role::Leader,
+        };
+        // The lock is released before electing the leader.
+        drop(local);
+        if self.leader().as_ref() == Some(&name) {
+            Role::Leader
+        } else {
+            Role::Follower
+        }
+    }
+
+    /// Returns a stream emitting the current role of this node in
+    /// the group, and then its new role every time it changes.
+    pub fn watch(&self) -> impl Stream<Item = Role> {
+        let (sender, receiver) = mpsc::unbounded();
+        // FIXME: panics
+        self.state.watchers.lock().unwrap().push(sender);
+
+        let leadership = self.clone();
+        let mut current = self.role();
+        stream::once(future::ready(current)).chain(receiver.filter_map(move |_| {
+            let role = leadership.role();
+            let changed = role != current;
+            current = role;
+            future::ready(if changed { Some(role) } else { None })
+        }))
+    }
+}
+
+impl MemberState {
+    fn member(&self) -> Member {
+        Member {
+            name: self.gossip.name.clone(),
+            addr: self.gossip.addr,
+            status: self.status,
+            distributors: self.gossip.distributors.clone(),
+        }
+    }
+}
+
+impl ClusterState {
+    // Returns the state of this node.
+    fn local_gossip(&self, config: &ClusterConfig, heartbeat: u64) -> Gossip {
+        let distributors = system::current()
+            .dispatcher()
+            .distributors()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, recipients)| *recipients > 0)
+            .map(|(name, _)| name)
+            .collect();
+
+        Gossip {
+            name: config.name.clone(),
+            addr: config.addr,
+            heartbeat,
+            distributors,
+            leaving: false,
+        }
+    }
+
+    // Returns the reachable member (this node included) with the
+    // highest rendezvous hashing score for the group, so that every
+    // member elects the same leader without coordination.
+    fn leader(&self, group: &str) -> Option<String> {
+        // FIXME: panics
+        let local = self.local.lock().unwrap().as_ref()?.config.name.clone();
+        // FIXME: panics
+        let members = self.members.read().unwrap();
+        members
+            .values()
+            .filter(|member| member.status == MemberStatus::Up)
+            .map(|member| member.gossip.name.clone())
+            .chain(Some(local))
+            .max_by_key(|member| fxhash::hash64(&(member, group)))
+    }
+
+    fn member_names(&self) -> Vec<String> {
+        // FIXME: panics
+        self.members.read().unwrap().keys().cloned().collect()
+    }
+
+    // Increments the heartbeat of this node, detects the failures of
+    // the members and gossips with them.
+    fn tick(&self) {
+        let (gossip, config) = {
+            // FIXME: panics
+            let mut local = self.local.lock().unwrap();
+            let local = match &mut *local {
+                Some(local) => local,
+                None => return,
+            };
+            local.heartbeat += 1;
+            (
+                self.local_gossip(&local.config, local.heartbeat),
+                local.config.clone(),
+            )
+        };
+
+        self.detect_failures(&config);
+
+        let mut members = vec![gossip];
+        let mut targets = Vec::new();
+        {
+            // FIXME: panics
+            let known = self.members.read().unwrap();
+            for (name, member) in known.iter() {
+                if member.status == MemberStatus::Up {
+                    members.push(member.gossip.clone());
+                }
+                // The unreachable members are still gossiped with, in
+                // case they come back.
+                targets.push(name.clone());
+            }
+        }
+        if targets.is_empty() {
+            for seed in &config.seeds {
+                let name = seed.to_string();
+                remote::register(&name, *seed);
+                targets.push(name);
+            }
+        }
+
+        trace!("Cluster: Gossiping with {:?}.", targets);
+        let frame = Frame::Gossip { members };
+        for name in targets {
+            if let Err(err) = remote::send(&name, &frame) {
+                debug!("Cluster: Couldn't gossip with {}: {}", name, err);
+            }
+        }
+    }
+
+    fn detect_failures(&self, config: &ClusterConfig) {
+        let mut events = Vec::new();
+        {
+            // FIXME: panics
+            let mut members = self.members.write().unwrap();
+            let mut removed = self.removed.lock().unwrap();
+            members.retain(|name, member| {
+                let elapsed = time::since(member.seen_at);
+                if elapsed > config.removal_timeout {
+                    info!("Cluster: Removing {}.", name);
+                    removed.insert(name.clone(), member.gossip.heartbeat);
+                    events.push(ClusterEvent::MemberRemoved(member.member()));
+                    return false;
+                }
+
+                if elapsed > config.failure_timeout && member.status == MemberStatus::Up {
+                    info!("Cluster: {} is unreachable.", name);
+                    member.status = MemberStatus::Unreachable;
+                    events.push(ClusterEvent::MemberUnreachable(member.member()));
+                }
+                true
+            });
+        }
+
+        self.publish(events);
+    }
+
+    // Merges the state of the members gossiped by another node.
+    fn receive(&self, gossips: Vec<Gossip>) {
+        let local = match &*self.local.lock().unwrap() {
+            Some(local) => local.config.name.clone(),
+            // Only the members of the cluster gossip.
+            None => return,
+        };
+
+        let mut events = Vec::new();
+        {
+            // FIXME: panics
+            let mut members = self.members.write().unwrap();
+            let mut removed = self.removed.lock().unwrap();
+            for gossip in gossips {
+                if gossip.name == local {
+                    continue;
+                }
+
+                if gossip.leaving {
+                    if let Some(member) = members.remove(&gossip.name) {
+                        info!("Cluster: {} left.", gossip.name);
+                        removed.insert(gossip.name.clone(), gossip.heartbeat);
+                        events.push(ClusterEvent::MemberLeft(member.member()));
+                    }
+                    continue;
+                }
+
+                match members.get_mut(&gossip.name) {
+                    Some(member) if gossip.heartbeat > member.gossip.heartbeat => {
+                        member.gossip = gossip;
+                        member.seen_at = time::now();
+                        if member.status == MemberStatus::Unreachable {
+                            info!("Cluster: {} is reachable again.", member.gossip.name);
+                            member.status = MemberStatus::Up;
+                            events.push(ClusterEvent::MemberReachable(member.member()));
+                        }
+                    }
+                    Some(_) => (),
+                    None => {
+                        // Ignores the outdated gossip about a member
+                        // which was removed.
+                        match removed.get(&gossip.name) {
+                            Some(heartbeat) if gossip.heartbeat <= *heartbeat => continue,
+                            _ => removed.remove(&gossip.name),
+                        };
+
+                        info!("Cluster: {} ({}) joined.", gossip.name, gossip.addr);
+                        remote::register(&gossip.name, gossip.addr);
+                        let member = MemberState {
+                            gossip,
+                            seen_at: time::now(),
+                            status: MemberStatus::Up,
+                        };
+                        events.push(ClusterEvent::MemberJoined(member.member()));
+                        members.insert(member.gossip.name.clone(), member);
+                    }
+                }
+            }
+        }
+
+        self.publish(events);
+    }
+
+    fn publish(&self, events: Vec<ClusterEvent>) {
+        if events.is_empty() {
+            return;
+        }
+
+        // FIXME: panics
+        let mut watchers = self.watchers.lock().unwrap();
+        for event in events {
+            trace!("Cluster: Publishing {:?}", event);
+            // Forget about the watchers whose stream was dropped.
+            watchers.retain(|sender| sender.unbounded_send(event.clone()).is_ok());
+        }
+    }
+
+    // Returns the reachable members which have recipients for the
+    // distributor.
+    fn subscribers(&self, distributor: &str) -> Vec<String> {
+        // FIXME: panics
+        self.members
+            .read()
+            .unwrap()
+            .values()
+            .filter(|member| member.status == MemberStatus::Up)
+            .filter(|member| member.gossip.distributors.iter().any(|d| d == distributor))
+            .map(|member| member.gossip.name.clone())
+            .collect()
+    }
+}
+
+// Merges the state of the members gossiped by another node.
+pub(crate) fn receive(gossips: Vec<Gossip>) {
+    CLUSTER.receive(gossips);
+}
+
+// Sends the message to every recipient of the distributor on the
+// other members, if it is a `WireMessage`, returning how many members
+// it was sent to. The message is kept in the dead letters for the
+// members it couldn't be sent to.
+pub(crate) fn tell_everyone(distributor: Distributor, message: &dyn Any) -> usize {
+    let message = match message.downcast_ref::<WireMessage>() {
+        Some(message) => message,
+        None => return 0,
+    };
+
+    let distributor = STRING_INTERNER.resolve(distributor.interned()).to_string();
+    let destination = Destination::Everyone(distributor.clone());
+    let frame = destination.frame(message.clone());
+    CLUSTER
+        .subscribers(&distributor)
+        .into_iter()
+        .filter(|name| match remote::send(name, &frame) {
+            Ok(()) => true,
+            Err(err) => {
+                debug!("Cluster: Couldn't send message to {}: {}", name, err);
+                let dead_letter = DeadLetter::sent(name, destination.clone(), message.clone(), err);
+                dead_letters::record(dead_letter);
+                false
+            }
+        })
+        .count()
+}
+
+// Asks the question to a recipient of the distributor on the
+// reachable member which answered the previous questions the
+// fastest, returning `None` if no member could be asked.
+pub(crate) fn ask_one(distributor: Distributor, question: WireMessage) -> Option<Answer> {
+    let distributor = STRING_INTERNER.resolve(distributor.interned()).to_string();
+    let members = by_latency(CLUSTER.subscribers(&distributor), remote::latency);
+    for name in members {
+        match remote::ask(&name, &distributor, question.clone()) {
+            Ok(answer) => return Some(answer),
+            Err(err) => debug!("Cluster: Couldn't ask {}: {}", name, err),
+        }
+    }
+
+    None
+}
+
+// Sorts the members from the one which answered the fastest to the
+// slowest one, starting with the members which weren't asked yet so
+// that their latency gets known.
+fn by_latency(mut members: Vec<String>, latency: impl Fn(&str) -> Option<Duration>) -> Vec<String> {
+    members.sort_by_cached_key(|name| latency(name).unwrap_or_default());
+    members
+}
+
+#[cfg(test)]
+impl Cluster {
+    // A cluster separate from the one of this node, joined as `local`
+    // and knowing about the given members.
+    pub(crate) fn detached(local: &str, members: &[&str]) -> Self {
+        let state: &'static ClusterState = Box::leak(Box::default());
+        *state.local.lock().unwrap() = Some(Local {
+            config: ClusterConfig::new(local, "127.0.0.1:4221".parse().unwrap()),
+            heartbeat: 0,
+            groups: Vec::new(),
+        });
+        let gossips = members
+            .iter()
+            .map(|name| Gossip {
+                name: name.to_string(),
+                addr: "127.0.0.1:4222".parse().unwrap(),
+                heartbeat: 1,
+                distributors: Vec::new(),
+                leaving: false,
+            })
+            .collect();
+        state.receive(gossips);
+        Cluster { state }
+    }
+
+    // Makes the member leave the cluster, as if it gossiped about it.
+    pub(crate) fn leave_as(&self, member: &str) {
+        self.state.receive(vec![Gossip {
+            name: member.to_string(),
+            addr: "127.0.0.1:4222".parse().unwrap(),
+            heartbeat: 2,
+            distributors: Vec::new(),
+            leaving: true,
+        }]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gossip(name: &str, heartbeat: u64) -> Gossip {
+        Gossip {
+            name: name.to_string(),
+            addr: "127.0.0.1:4222".parse().unwrap(),
+            heartbeat,
+            distributors: vec!["workers".to_string()],
+            leaving: false,
+        }
+    }
+
+    fn joined(state: &ClusterState) {
+        *state.local.lock().unwrap() = Some(Local {
+            config: ClusterConfig::new("local", "127.0.0.1:4221".parse().unwrap()),
+            heartbeat: 0,
+            groups: Vec::new(),
+        });
+    }
+
+    #[test]
+    fn test_gossip_is_merged() {
+        let state = ClusterState::default();
+        let (sender, mut events) = mpsc::unbounded();
+        state.watchers.lock().unwrap().push(sender);
+
+        // Nodes which didn't join ignore the gossip.
+        state.receive(vec![gossip("a", 1)]);
+        assert!(state.member_names().is_empty());
+
+        joined(&state);
+        state.receive(vec![gossip("local", 3), gossip("a", 1), gossip("b", 1)]);
+        let mut names = state.member_names();
+        names.sort();
+        assert_eq!(names, vec!["a", "b"]);
+        assert_eq!(state.subscribers("workers").len(), 2);
+        assert!(matches!(
+            events.try_next(),
+            Ok(Some(ClusterEvent::MemberJoined(_)))
+        ));
+
+        let mut leaving = gossip("a", 2);
+        leaving.leaving = true;
+        state.receive(vec![leaving]);
+        assert_eq!(state.member_names(), vec!["b"]);
+
+        // Outdated gossip doesn't bring the member back.
+        state.receive(vec![gossip("a", 1)]);
+        assert_eq!(state.member_names(), vec!["b"]);
+    }
+
+    #[test]
+    fn test_leaders_are_elected() {
+        let state = ClusterState::default();
+        assert_eq!(state.leader("scheduler"), None);
+
+        joined(&state);
+        assert_eq!(state.leader("scheduler").as_deref(), Some("local"));
+
+        state.receive(vec![gossip("a", 1), gossip("b", 1)]);
+        let leader = state.leader("scheduler").unwrap();
+        // Another member is elected when the leader leaves.
+        if leader != "local" {
+            let mut leaving = gossip(&leader, 2);
+            leaving.leaving = true;
+            state.receive(vec![leaving]);
+            let next = state.leader("scheduler").unwrap();
+            assert_ne!(next, leader);
+        }
+        // The groups are spread across the members.
+        let leaders: std::collections::HashSet<_> = (0..30)
+            .filter_map(|group| state.leader(&group.to_string()))
+            .collect();
+        assert!(leaders.len() > 1);
+    }
+
+    #[test]
+    fn test_leadership_roles() {
+        let state: &'static ClusterState = Box::leak(Box::default());
+        let leadership = Leadership {
+            group: "cron".to_string(),
+            state,
+        };
+        // Nodes which didn't join lead every group.
+        assert_eq!(leadership.role(), Role::Leader);
+
+        joined(state);
+        state.receive(vec![gossip("a", 1), gossip("b", 1)]);
+        let expected = if leadership.leader().as_deref() == Some("local") {
+            Role::Leader
+        } else {
+            Role::Follower
+        };
+        assert_eq!(leadership.role(), expected);
+    }
+
+    #[test]
+    fn test_members_are_sorted_by_latency() {
+        let members = vec!["slow".to_string(), "fast".to_string(), "new".to_string()];
+        let sorted = by_latency(members, |name| match name {
+            "slow" => Some(Duration::from_millis(20)),
+            "fast" => Some(Duration::from_millis(2)),
+            _ => None,
+        });
+        assert_eq!(sorted, vec!["new", "fast", "slow"]);
+    }
+
+    #[test]
+    fn test_failures_are_detected() {
+        let state = ClusterState::default();
+        joined(&state);
+        state.receive(vec![gossip("a", 1)]);
+
+        let config = ClusterConfig::new("local", "127.0.0.1:4221".parse().unwrap())
+            .with_failure_timeout(Duration::from_millis(10))
+            .with_removal_timeout(Duration::from_millis(100));
+        std::thread::sleep(Duration::from_millis(20));
+        state.detect_failures(&config);
+        assert_eq!(
+            state.members.read().unwrap()["a"].status,
+            MemberStatus::Unreachable
+        );
+        assert!(state.subscribers("workers").is_empty());
+
+        state.receive(vec![gossip("a", 2)]);
+        assert_eq!(state.members.read().unwrap()["a"].status, MemberStatus::Up);
+
+        std::thread::sleep(Duration::from_millis(120));
+        state.detect_failures(&config);
+        assert!(state.member_names().is_empty());
+    }
+}
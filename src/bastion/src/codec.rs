@@ -0,0 +1,289 @@
+//!
+//! The formats the frames exchanged by the nodes are encoded in.
+//!
+//! Each frame sent over a connection between two nodes (a message, a
+//! question, an answer, the gossip of the cluster...) is a
+//! [`WireEnvelope`], encoded by the [`WireCodec`] the nodes agreed on
+//! when the connection was opened: the node opening it picks the
+//! codec (see [`RemoteConfig::with_codec`]), which the other node
+//! must know of.
+//!
+//! The [`JsonCodec`] (the default) and the [`BincodeCodec`] are
+//! always available. The [`MessagePackCodec`] and the
+//! [`ProtobufCodec`] are respectively available with the `msgpack`
+//! and `protobuf` features, and allow services written in other
+//! languages to talk to the nodes.
+//!
+//! The messages themselves are packed in [`WireMessage`]s, whose
+//! payload the codecs don't look into.
+//!
+//! This module is only available with the `remote` feature.
+//!
+//! [`RemoteConfig::with_codec`]: crate::remote::RemoteConfig::with_codec
+//! [`WireMessage`]: crate::wire::WireMessage
+use crate::wire::WireMessage;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::io::{self, ErrorKind};
+
+/// Encodes the [`WireEnvelope`]s sent over the connections between
+/// the nodes, and decodes the ones received.
+///
+/// The name of the codec is sent by the node opening a connection,
+/// and must be the same in every node using the codec.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use std::io;
+/// #
+/// #[derive(Debug)]
+/// struct PrettyJson;
+///
+/// impl WireCodec for PrettyJson {
+///     fn name(&self) -> &str {
+///         "pretty-json"
+///     }
+///
+///     fn encode(&self, envelope: &WireEnvelope) -> io::Result<Vec<u8>> {
+///         Ok(serde_json::to_vec_pretty(envelope)?)
+///     }
+///
+///     fn decode(&self, bytes: &[u8]) -> io::Result<WireEnvelope> {
+///         Ok(serde_json::from_slice(bytes)?)
+///     }
+/// }
+///
+/// let config = RemoteConfig::new().with_codec(PrettyJson);
+/// ```
+pub trait WireCodec: Send + Sync + Debug + 'static {
+    /// Returns the name identifying the codec.
+    fn name(&self) -> &str;
+
+    /// Encodes the envelope.
+    ///
+    /// # Arguments
+    ///
+    /// * `envelope` - The envelope to encode.
+    fn encode(&self, envelope: &WireEnvelope) -> io::Result<Vec<u8>>;
+
+    /// Decodes an envelope encoded by the same codec.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The encoded envelope.
+    fn decode(&self, bytes: &[u8]) -> io::Result<WireEnvelope>;
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A frame sent over a connection between two nodes, as encoded by
+/// a [`WireCodec`].
+///
+/// The meaning of the fields depends on the kind of the frame, and
+/// the fields a kind doesn't use are left to their default value.
+pub struct WireEnvelope {
+    /// The kind of the frame (e.g. `tell`, `ask` or `reply`).
+    pub kind: String,
+    /// The identifier of the question the frame asks or answers.
+    pub id: u64,
+    /// The name of the distributor (or sharding) the frame is sent
+    /// to.
+    pub target: String,
+    /// The identifier of the entity the frame is sent to.
+    pub entity: String,
+    /// The message carried by the frame.
+    pub message: Option<WireMessage>,
+    /// A text, such as the error answering a question.
+    pub text: String,
+    /// Additional data, such as the credentials of a node.
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+/// The [`WireCodec`] encoding the envelopes as JSON, used by
+/// default.
+pub struct JsonCodec;
+
+#[derive(Debug, Default, Clone, Copy)]
+/// The [`WireCodec`] encoding the envelopes using `bincode`, which
+/// is more compact and faster than JSON but only meant to be
+/// decoded by other Rust processes.
+pub struct BincodeCodec;
+
+#[cfg(feature = "msgpack")]
+#[derive(Debug, Default, Clone, Copy)]
+/// The [`WireCodec`] encoding the envelopes as MessagePack maps.
+///
+/// This codec is only available with the `msgpack` feature.
+pub struct MessagePackCodec;
+
+#[cfg(feature = "protobuf")]
+#[derive(Debug, Default, Clone, Copy)]
+/// The [`WireCodec`] encoding the envelopes as Protocol Buffers
+/// messages (using `prost`), following this schema:
+///
+/// ```protobuf
+/// message WireEnvelope {
+///   string kind = 1;
+///   uint64 id = 2;
+///   string target = 3;
+///   string entity = 4;
+///   bool has_message = 5;
+///   string tag = 6;
+///   uint32 version = 7;
+///   bytes payload = 8;
+///   string text = 9;
+///   bytes data = 10;
+/// }
+/// ```
+///
+/// This codec is only available with the `protobuf` feature.
+pub struct ProtobufCodec;
+
+#[cfg(feature = "protobuf")]
+#[derive(Clone, PartialEq, prost::Message)]
+struct ProtoEnvelope {
+    #[prost(string, tag = "1")]
+    kind: String,
+    #[prost(uint64, tag = "2")]
+    id: u64,
+    #[prost(string, tag = "3")]
+    target: String,
+    #[prost(string, tag = "4")]
+    entity: String,
+    #[prost(bool, tag = "5")]
+    has_message: bool,
+    #[prost(string, tag = "6")]
+    tag: String,
+    #[prost(uint32, tag = "7")]
+    version: u32,
+    #[prost(bytes = "vec", tag = "8")]
+    payload: Vec<u8>,
+    #[prost(string, tag = "9")]
+    text: String,
+    #[prost(bytes = "vec", tag = "10")]
+    data: Vec<u8>,
+}
+
+impl WireCodec for JsonCodec {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn encode(&self, envelope: &WireEnvelope) -> io::Result<Vec<u8>> {
+        Ok(serde_json::to_vec(envelope)?)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> io::Result<WireEnvelope> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+impl WireCodec for BincodeCodec {
+    fn name(&self) -> &str {
+        "bincode"
+    }
+
+    fn encode(&self, envelope: &WireEnvelope) -> io::Result<Vec<u8>> {
+        bincode::serialize(envelope).map_err(invalid)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> io::Result<WireEnvelope> {
+        bincode::deserialize(bytes).map_err(invalid)
+    }
+}
+
+#[cfg(feature = "msgpack")]
+impl WireCodec for MessagePackCodec {
+    fn name(&self) -> &str {
+        "msgpack"
+    }
+
+    fn encode(&self, envelope: &WireEnvelope) -> io::Result<Vec<u8>> {
+        rmp_serde::to_vec_named(envelope).map_err(invalid)
+    }
+
+    fn decode(&self, bytes: &[u8]) -> io::Result<WireEnvelope> {
+        rmp_serde::from_slice(bytes).map_err(invalid)
+    }
+}
+
+#[cfg(feature = "protobuf")]
+impl WireCodec for ProtobufCodec {
+    fn name(&self) -> &str {
+        "protobuf"
+    }
+
+    fn encode(&self, envelope: &WireEnvelope) -> io::Result<Vec<u8>> {
+        let message = envelope.message.as_ref();
+        let proto = ProtoEnvelope {
+            kind: envelope.kind.clone(),
+            id: envelope.id,
+            target: envelope.target.clone(),
+            entity: envelope.entity.clone(),
+            has_message: message.is_some(),
+            tag: message.map(|m| m.tag().to_string()).unwrap_or_default(),
+            version: message.map(WireMessage::version).unwrap_or_default(),
+            payload: message.map(|m| m.payload().to_vec()).unwrap_or_default(),
+            text: envelope.text.clone(),
+            data: envelope.data.clone(),
+        };
+        Ok(prost::Message::encode_to_vec(&proto))
+    }
+
+    fn decode(&self, bytes: &[u8]) -> io::Result<WireEnvelope> {
+        let proto: ProtoEnvelope = prost::Message::decode(bytes).map_err(invalid)?;
+        let message = if proto.has_message {
+            Some(WireMessage::from_parts(
+                proto.tag,
+                proto.version,
+                proto.payload,
+            ))
+        } else {
+            None
+        };
+        Ok(WireEnvelope {
+            kind: proto.kind,
+            id: proto.id,
+            target: proto.target,
+            entity: proto.entity,
+            message,
+            text: proto.text,
+            data: proto.data,
+        })
+    }
+}
+
+fn invalid(err: impl std::error::Error + Send + Sync + 'static) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(codec: &dyn WireCodec) {
+        let envelope = WireEnvelope {
+            kind: "ask".to_string(),
+            id: 7,
+            target: "workers".to_string(),
+            message: Some(WireMessage::pack(&"ping".to_string()).unwrap()),
+            data: vec![1, 2, 3],
+            ..WireEnvelope::default()
+        };
+        let bytes = codec.encode(&envelope).unwrap();
+        assert_eq!(codec.decode(&bytes).unwrap(), envelope);
+        assert!(codec.decode(&bytes[..bytes.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn test_codecs_round_trip() {
+        round_trip(&JsonCodec);
+        round_trip(&BincodeCodec);
+        #[cfg(feature = "msgpack")]
+        round_trip(&MessagePackCodec);
+        #[cfg(feature = "protobuf")]
+        round_trip(&ProtobufCodec);
+    }
+}
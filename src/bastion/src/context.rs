@@ -2,32 +2,67 @@
 //! A context allows a child's future to access its received
 //! messages, parent and supervisor.
 
+use crate::behavior::Behavior;
 use crate::child_ref::ChildRef;
+use crate::children::{DrainPolicy, OverflowPolicy};
 use crate::children_ref::ChildrenRef;
 use crate::dispatcher::{BroadcastTarget, DispatcherType, NotificationType};
-use crate::envelope::{Envelope, RefAddr, SignedMessage};
-use crate::message::{Answer, BastionMessage, Message, Msg};
+use crate::distributor::Distributor;
+use crate::envelope::{Envelope, MessageTarget, RefAddr, SignedMessage};
+use crate::executor;
+use crate::limits::{ConcurrencyLimit, MailboxLimit};
+use crate::message::{
+    AckSender, Answer, BastionMessage, CorrelationId, Message, Msg, Request, TypedAnswer,
+};
+use crate::observer::{MailboxEventKind, MessageObserver};
+use crate::persistence::Persistence;
+use crate::profile::GroupProfile;
+use crate::results::ChildResult;
+#[cfg(feature = "tracing")]
+use crate::spans::MessageSpan;
 use crate::supervisor::SupervisorRef;
-use crate::{prelude::ReceiveError, system::SYSTEM};
+use crate::time::{self, Instant};
+use crate::topic::Topic;
+#[cfg(feature = "remote")]
+use crate::wire::WireMessage;
+use crate::{prelude::ReceiveError, system};
 
+use anyhow::{anyhow, Result as AnyResult};
 use crossbeam_queue::SegQueue;
+use futures::future;
 use futures::pending;
-use futures::FutureExt;
+use futures::{FutureExt, Stream, StreamExt};
 use futures_timer::Delay;
 #[cfg(feature = "scaling")]
 use lever::table::lotable::LOTable;
-use std::fmt::{self, Display, Formatter};
+use lightproc::recoverable_handle::RecoverableHandle;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::collections::VecDeque;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
 use std::pin::Pin;
-#[cfg(feature = "scaling")]
-use std::sync::atomic::AtomicU64;
-use std::{sync::Arc, time::Duration};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::task::{Context, Poll, Waker};
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    thread::{self, Thread},
+    time::Duration,
+};
 use tracing::{debug, trace};
 use uuid::Uuid;
 
 /// Identifier for a root supervisor and dead-letters children.
 pub const NIL_ID: BastionId = BastionId(Uuid::nil());
 
-#[derive(Hash, Eq, PartialEq, Debug, Clone)]
+// How long `recv_blocking` parks its thread before checking the
+// mailbox again if it wasn't unparked.
+const RECV_BLOCKING_PARK_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Hash, Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
 /// An identifier used by supervisors, children groups and
 /// their elements to identify themselves, using a v4 UUID.
 ///
@@ -138,17 +173,167 @@ pub struct BastionContext {
     children: ChildrenRef,
     supervisor: Option<SupervisorRef>,
     state: Arc<Pin<Box<ContextState>>>,
+    // The stack of behaviors set using `become_`, the last one
+    // being the active one.
+    behaviors: Mutex<Vec<Behavior>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The message received by an element of a children group once a
+/// task it spawned using [`BastionContext::spawn_child`] completed.
+pub struct ChildCompleted {
+    id: BastionId,
+}
+
+#[derive(Debug, Clone)]
+/// A handle to the messages scheduled using
+/// [`BastionContext::schedule`] or
+/// [`BastionContext::schedule_repeated`], or to a stream attached
+/// using [`BastionContext::attach_stream`], allowing to cancel them.
+///
+/// The scheduled messages and attached streams are cancelled
+/// automatically when the element that scheduled or attached them
+/// is stopped or restarted.
+pub struct ScheduleHandle {
+    cancelled: Arc<AtomicBool>,
 }
 
+/// A handle to a task spawned using
+/// [`BastionContext::spawn_blocking`], resolving to the value it
+/// returned.
+///
+/// It resolves to `Err(())` if the task panicked or was cancelled,
+/// which happens if it didn't start running yet when the handle was
+/// cancelled or the element that spawned it was stopped or
+/// restarted.
+pub struct JoinHandle<R> {
+    handle: RecoverableHandle<Option<R>>,
+    cancel: ScheduleHandle,
+}
+
+/// A guard giving access to the state of an element of a children
+/// group, returned by [`BastionContext::state`].
+///
+/// The state is locked until the guard is dropped, which means
+/// that the guard can't be held across `.await` points.
+pub struct LocalState<'a, S> {
+    guard: MutexGuard<'a, Option<Box<dyn Any + Send>>>,
+    _state: PhantomData<S>,
+}
+
+#[derive(Clone)]
+// The closure creating the state of each element of a children
+// group (see `Children::with_state`).
+pub(crate) struct LocalStateInit(Arc<dyn Fn() -> Box<dyn Any + Send> + Send + Sync>);
+
+#[derive(Clone)]
+// The values handed to the elements of a children group depending
+// on their index (see `Children::with_init_data`).
+pub(crate) struct InitData(Arc<dyn Fn(usize) -> Option<Box<dyn Any + Send + Sync>> + Send + Sync>);
+
 #[derive(Debug)]
 pub(crate) struct ContextState {
-    messages: SegQueue<SignedMessage>,
+    messages: SegQueue<MailboxEntry>,
+    // Messages skipped by a selective receive (see `recv_where`),
+    // that are received before the ones still in `messages`.
+    skipped: Mutex<VecDeque<SignedMessage>>,
+    // Messages set aside by the child until it calls `unstash_all`.
+    stash: Mutex<Vec<SignedMessage>>,
+    // The amount of messages retrieved by the child, and when it
+    // retrieved the last one.
+    processed: AtomicU64,
+    last_message_at: Mutex<Option<Instant>>,
+    // The message the child is handling, until it tries to receive
+    // another one. Used to detect slow handlers and to profile the
+    // handling of the messages of the group, if `profile` is set.
+    handling: Mutex<Option<Handling>>,
+    profile: Option<Arc<GroupProfile>>,
+    // Messages inherited from a previous run of the child, that
+    // are drained according to `drain`.
+    backlog: SegQueue<MailboxEntry>,
+    drain: Mutex<Drain>,
+    ack: Mutex<Option<AckSender>>,
+    // Whether the child is being stopped and is given some time
+    // to finish its in-flight work.
+    stopping: AtomicBool,
+    // The wakers of the futures returned by `on_shutdown`.
+    stop_wakers: Mutex<Vec<Waker>>,
+    // The messages scheduled by the child, cancelled when it stops.
+    schedules: Mutex<Vec<ScheduleHandle>>,
+    // The distributors of the topics the child subscribed to, which
+    // it is unsubscribed from when it stops.
+    topics: Mutex<Vec<Distributor>>,
+    // The value the child exited with using `stop_with`.
+    result: Mutex<Option<ChildResult>>,
+    // The observer of the child's mailbox, and the child's id.
+    observer: Option<(BastionId, MessageObserver)>,
+    // When the child started polling its future, if it didn't
+    // yield since then. Used to detect hung children.
+    polling_since: Mutex<Option<Instant>>,
+    // The limit of elements of the child's group that can be
+    // handling a message at once, and whether the child holds one
+    // of its permits.
+    concurrency_limit: Option<Arc<ConcurrencyLimit>>,
+    holds_permit: AtomicBool,
+    // The depth of the child's mailbox, shared with its `ChildRef`s,
+    // and the capacity it is bounded to.
+    mailbox: Arc<MailboxLimit>,
+    // The thread running the child's blocking closure, if it is
+    // waiting for a message in `recv_blocking`.
+    blocked_on: Mutex<Option<Thread>>,
+    // The span of the message the child is handling.
+    #[cfg(feature = "tracing")]
+    span: MessageSpan,
+    // The state of the child, set using `Children::with_state`, and
+    // whether the events of the child still have to be replayed to
+    // it before the child starts (see `Children::with_recovery`).
+    local: Mutex<Option<Box<dyn Any + Send>>>,
+    recovering: AtomicBool,
+    // The index of the child in its group, and the value it was
+    // handed using `Children::with_init_data`.
+    index: usize,
+    init_data: Option<Box<dyn Any + Send + Sync>>,
+    // The persistence identifier of the child and its journal, set
+    // using `Children::with_persistence`.
+    persistence: Option<Persistence>,
     #[cfg(feature = "scaling")]
     stats: Arc<AtomicU64>,
     #[cfg(feature = "scaling")]
     actor_stats: Arc<LOTable<BastionId, u32>>,
 }
 
+#[derive(Debug)]
+struct Handling {
+    type_name: &'static str,
+    correlation_id: CorrelationId,
+    since: Instant,
+    // How long the child polled its future while handling the
+    // message, up to its last check in.
+    busy: Duration,
+    // Whether the handling was already reported as slow.
+    reported: bool,
+    // A copy of the message, kept in the dead letters if the child
+    // fails while handling it. Only the messages received from other
+    // nodes can be copied.
+    #[cfg(feature = "remote")]
+    message: Option<WireMessage>,
+}
+
+#[derive(Debug)]
+struct MailboxEntry {
+    msg: SignedMessage,
+    received_at: Instant,
+}
+
+#[derive(Debug, Default)]
+struct Drain {
+    policy: DrainPolicy,
+    // The number of inherited messages drained during the current tick.
+    drained: usize,
+    // Whether the last message was taken from the backlog.
+    from_backlog: bool,
+}
+
 impl BastionId {
     pub(crate) fn new() -> Self {
         let uuid = Uuid::new_v4();
@@ -172,6 +357,7 @@ impl BastionContext {
             children,
             supervisor,
             state,
+            behaviors: Mutex::new(Vec::new()),
         }
     }
 
@@ -218,8 +404,9 @@ impl BastionContext {
         &self.child
     }
 
-    /// Returns a [`ChildrenRef`] referencing the children group
-    /// of the element that is linked to this `BastionContext`.
+    /// Returns `true` if the element linked to this `BastionContext`
+    /// is being stopped and should finish its in-flight work before
+    /// its stop timeout elapses (see [`Children::with_stop_timeout`]).
     ///
     /// # Example
     ///
@@ -243,9 +430,10 @@ impl BastionContext {
     /// Bastion::children(|children| {
     ///     children.with_exec(|ctx: BastionContext| {
     ///         async move {
-    ///             let parent: &ChildrenRef = ctx.parent();
-    ///             // Get the other elements of the group, broadcast message,
-    ///             // or stop or kill the children group...
+    ///             while !ctx.is_stopping() {
+    ///                 // Do some work...
+    ///                 # break;
+    ///             }
     ///
     ///             Ok(())
     ///         }
@@ -257,20 +445,37 @@ impl BastionContext {
     /// # Bastion::block_until_stopped();
     /// # }
     /// ```
-    pub fn parent(&self) -> &ChildrenRef {
-        &self.children
+    ///
+    /// [`Children::with_stop_timeout`]: crate::children::Children::with_stop_timeout
+    pub fn is_stopping(&self) -> bool {
+        self.state.is_stopping()
     }
 
-    /// Returns a [`SupervisorRef`] referencing the supervisor
-    /// that supervises the element that is linked to this
-    /// `BastionContext` if it isn't the system supervisor
-    /// (ie. if the children group wasn't created using
-    /// [`Bastion::children`]).
+    /// Returns `true` if the supervisor of the element linked to this
+    /// `BastionContext` asked it to stop (e.g. because [`Bastion::stop`]
+    /// was called), which allows long computations to be cancelled
+    /// cooperatively.
+    ///
+    /// Note that an element is only given time to stop once asked
+    /// to if its children group has a stop timeout (see
+    /// [`Children::with_stop_timeout`]), otherwise it is stopped
+    /// right away.
+    ///
+    /// [`Bastion::stop`]: crate::Bastion::stop
+    /// [`Children::with_stop_timeout`]: crate::children::Children::with_stop_timeout
+    pub fn shutdown_requested(&self) -> bool {
+        self.state.is_stopping()
+    }
+
+    /// Returns a future resolving once the supervisor of the element
+    /// linked to this `BastionContext` asked it to stop (see
+    /// [`shutdown_requested`]).
     ///
     /// # Example
     ///
     /// ```rust
     /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
     /// #
     /// # #[cfg(feature = "tokio-runtime")]
     /// # #[tokio::main]
@@ -286,35 +491,25 @@ impl BastionContext {
     /// # fn run() {
     /// # Bastion::init();
     /// #
-    /// // When calling the method from a children group supervised
-    /// // by a supervisor created by the user...
-    /// Bastion::supervisor(|sp| {
-    ///     sp.children(|children| {
-    ///         children.with_exec(|ctx: BastionContext| {
+    /// # async fn compute() {}
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_stop_timeout(Duration::from_secs(5))
+    ///         .with_exec(|ctx: BastionContext| {
     ///             async move {
-    ///                 // ...the method will return a SupervisorRef referencing the
-    ///                 // user-created supervisor...
-    ///                 let supervisor: Option<&SupervisorRef> = ctx.supervisor(); // Some
-    ///                 assert!(supervisor.is_some());
+    ///                 futures::select! {
+    ///                     _ = futures::FutureExt::fuse(compute()) => {
+    ///                         // Send the result...
+    ///                     },
+    ///                     _ = futures::FutureExt::fuse(ctx.on_shutdown()) => {
+    ///                         // Save the progress...
+    ///                     },
+    ///                 }
     ///
     ///                 Ok(())
     ///             }
     ///         })
-    ///     })
-    /// }).expect("Couldn't create the supervisor.");
-    ///
-    /// // When calling the method from a children group supervised
-    /// // by the system's supervisor...
-    /// Bastion::children(|children| {
-    ///     children.with_exec(|ctx: BastionContext| {
-    ///         async move {
-    ///             // ...the method won't return a SupervisorRef...
-    ///             let supervisor: Option<&SupervisorRef> = ctx.supervisor(); // None
-    ///             assert!(supervisor.is_none());
-    ///
-    ///             Ok(())
-    ///         }
-    ///     })
     /// }).expect("Couldn't create the children group.");
     /// #
     /// # Bastion::start();
@@ -323,22 +518,26 @@ impl BastionContext {
     /// # }
     /// ```
     ///
-    /// [`Bastion::children`]: struct.Bastion.html#method.children
-    pub fn supervisor(&self) -> Option<&SupervisorRef> {
-        self.supervisor.as_ref()
+    /// [`shutdown_requested`]: Self::shutdown_requested
+    pub fn on_shutdown(&self) -> impl Future<Output = ()> + '_ {
+        future::poll_fn(move |cx| {
+            if self.state.is_stopping() {
+                return Poll::Ready(());
+            }
+
+            self.state.wake_on_stop(cx.waker());
+            // The element might have been asked to stop before the
+            // waker was registered.
+            if self.state.is_stopping() {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
     }
 
-    /// Tries to retrieve asynchronously a message received by
-    /// the element this `BastionContext` is linked to.
-    ///
-    /// If you need to wait (always asynchronously) until at
-    /// least one message can be retrieved, use [`recv`] instead.
-    ///    
-    /// If you want to wait for a certain amount of time before bailing out
-    /// use [`try_recv_timeout`] instead.
-    ///
-    /// This method returns [`SignedMessage`] if a message was available, or
-    /// `None` otherwise.
+    /// Returns a [`ChildrenRef`] referencing the children group
+    /// of the element that is linked to this `BastionContext`.
     ///
     /// # Example
     ///
@@ -362,9 +561,9 @@ impl BastionContext {
     /// Bastion::children(|children| {
     ///     children.with_exec(|ctx: BastionContext| {
     ///         async move {
-    ///             let opt_msg: Option<SignedMessage> = ctx.try_recv().await;
-    ///             // If a message was received by the element, `opt_msg` will
-    ///             // be `Some(Msg)`, otherwise it will be `None`.
+    ///             let parent: &ChildrenRef = ctx.parent();
+    ///             // Get the other elements of the group, broadcast message,
+    ///             // or stop or kill the children group...
     ///
     ///             Ok(())
     ///         }
@@ -376,37 +575,15 @@ impl BastionContext {
     /// # Bastion::block_until_stopped();
     /// # }
     /// ```
-    ///
-    /// [`recv`]: Self::method.recv
-    /// [`try_recv_timeout`]: Self::method.try_recv_timeout
-    pub async fn try_recv(&self) -> Option<SignedMessage> {
-        // We want to let a tick pass
-        // otherwise guard will never contain anything.
-        Delay::new(Duration::from_millis(0)).await;
-
-        trace!("BastionContext({}): Trying to receive message.", self.id);
-
-        if let Some(msg) = self.state.pop_message() {
-            trace!("BastionContext({}): Received message: {:?}", self.id, msg);
-            Some(msg)
-        } else {
-            trace!("BastionContext({}): Received no message.", self.id);
-            None
-        }
+    pub fn parent(&self) -> &ChildrenRef {
+        &self.children
     }
 
-    /// Retrieves asynchronously a message received by the element
-    /// this `BastionContext` is linked to and waits (always
-    /// asynchronously) for one if none has been received yet.
-    ///
-    /// If you don't need to wait until at least one message
-    /// can be retrieved, use [`try_recv`] instead.
-    ///    
-    /// If you want to wait for a certain amount of time before bailing out
-    /// use [`try_recv_timeout`] instead.
+    /// Returns a [`ChildrenRef`] referencing the children group of
+    /// the element that is linked to this `BastionContext`, allowing
+    /// it to message its siblings.
     ///
-    /// This method returns [`SignedMessage`] if it succeeded, or `Err(())`
-    /// otherwise.
+    /// This is the same as [`parent`].
     ///
     /// # Example
     ///
@@ -428,10 +605,13 @@ impl BastionContext {
     /// # Bastion::init();
     /// #
     /// Bastion::children(|children| {
-    ///     children.with_exec(|ctx: BastionContext| {
+    ///     children.with_redundancy(3).with_exec(|ctx: BastionContext| {
     ///         async move {
-    ///             // This will block until a message has been received...
-    ///             let msg: SignedMessage = ctx.recv().await?;
+    ///             for sibling in ctx.group().elems() {
+    ///                 if sibling.id() != ctx.current().id() {
+    ///                     ctx.tell(sibling, "hello").ok();
+    ///                 }
+    ///             }
     ///
     ///             Ok(())
     ///         }
@@ -444,37 +624,20 @@ impl BastionContext {
     /// # }
     /// ```
     ///
-    /// [`try_recv`]: Self::try_recv
-    /// [`try_recv_timeout`]: Self::try_recv_timeout
-    pub async fn recv(&self) -> Result<SignedMessage, ()> {
-        debug!("BastionContext({}): Waiting to receive message.", self.id);
-        loop {
-            if let Some(msg) = self.state.pop_message() {
-                trace!("BastionContext({}): Received message: {:?}", self.id, msg);
-                return Ok(msg);
-            }
-            pending!();
-        }
+    /// [`parent`]: Self::parent
+    pub fn group(&self) -> &ChildrenRef {
+        &self.children
     }
 
-    /// Retrieves asynchronously a message received by the element
-    /// this `BastionContext` is linked to and waits until `timeout` (always
-    /// asynchronously) for one if none has been received yet.
-    ///
-    /// If you want to wait for ever until at least one message
-    /// can be retrieved, use [`recv`] instead.
-    ///
-    /// If you don't need to wait until at least one message
-    /// can be retrieved, use [`try_recv`] instead.
-    ///
-    /// This method returns [`SignedMessage`] if it succeeded, or `Err(TimeoutError)`
-    /// otherwise.
+    /// Returns the name of the children group of the element that
+    /// is linked to this `BastionContext`, as set using
+    /// [`Children::with_name`] (or `"__Anonymous__"` if it wasn't
+    /// named).
     ///
     /// # Example
     ///
     /// ```rust
     /// # use bastion::prelude::*;
-    /// # use std::time::Duration;
     /// #
     /// # #[cfg(feature = "tokio-runtime")]
     /// # #[tokio::main]
@@ -491,15 +654,9 @@ impl BastionContext {
     /// # Bastion::init();
     /// #
     /// Bastion::children(|children| {
-    ///     children.with_exec(|ctx: BastionContext| {
+    ///     children.with_name("parsers").with_exec(|ctx: BastionContext| {
     ///         async move {
-    ///             // This will block until a message has been received...
-    ///             let timeout = Duration::from_millis(10);
-    ///             let msg: SignedMessage = ctx.try_recv_timeout(timeout).await.map_err(|e| {
-    ///                if let ReceiveError::Timeout(duration) = e {
-    ///                    // Timeout happened      
-    ///                }
-    ///             })?;
+    ///             assert_eq!(ctx.group_name(), "parsers");
     ///
     ///             Ok(())
     ///         }
@@ -512,26 +669,23 @@ impl BastionContext {
     /// # }
     /// ```
     ///
-    /// [`recv`]: Self::recv
-    /// [`try_recv`]: Self::try_recv
-    /// [`SignedMessage`]: .crate::enveloppe::SignedMessage
-    pub async fn try_recv_timeout(&self, timeout: Duration) -> Result<SignedMessage, ReceiveError> {
-        debug!(
-            "BastionContext({}): Waiting to receive message within {} milliseconds.",
-            self.id,
-            timeout.as_millis()
-        );
-        futures::select! {
-            message = self.recv().fuse() => {
-                message.map_err(|_| ReceiveError::Other)
-            },
-            _duration = Delay::new(timeout).fuse() => {
-                Err(ReceiveError::Timeout(timeout))
-            }
-        }
+    /// [`Children::with_name`]: crate::children::Children::with_name
+    pub fn group_name(&self) -> &str {
+        self.child.name()
     }
 
-    /// Returns [`RefAddr`] of the current `BastionContext`
+    /// Makes the element this `BastionContext` is linked to exit
+    /// with the given value, which is delivered to the futures
+    /// returned by [`ChildrenRef::join_all`].
+    ///
+    /// The element is considered completed rather than failed, and
+    /// thus isn't restarted. Its future isn't polled anymore once it
+    /// yields, so this method returns `Ok(())` to be returned right
+    /// away by the future.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - The value the element exits with.
     ///
     /// # Example
     ///
@@ -552,38 +706,38 @@ impl BastionContext {
     /// # fn run() {
     /// # Bastion::init();
     /// #
-    ///
-    /// Bastion::children(|children| {
+    /// let children_ref = Bastion::children(|children| {
     ///     children.with_exec(|ctx: BastionContext| {
     ///         async move {
-    ///             ctx.tell(&ctx.signature(), "Hello to myself");
+    ///             let mut processed = 0;
+    ///             while let Some(msg) = ctx.try_recv().await {
+    ///                 // Process the message...
+    ///                 processed += 1;
+    ///             }
     ///
-    ///             # Bastion::stop();
-    ///             Ok(())
+    ///             ctx.stop_with(processed)
     ///         }
     ///     })
     /// }).expect("Couldn't create the children group.");
     /// #
     /// # Bastion::start();
+    /// # Bastion::stop();
     /// # Bastion::block_until_stopped();
     /// # }
     /// ```
     ///
-    // TODO(scrabsha): should we link to Answer or to RefAddr?
-    // [`RefAddr`]: /prelude/struct.Answer.html
-    pub fn signature(&self) -> RefAddr {
-        RefAddr::new(
-            self.current().path().clone(),
-            self.current().sender().clone(),
-        )
+    /// [`ChildrenRef::join_all`]: crate::children_ref::ChildrenRef::join_all
+    pub fn stop_with<R: Send + Sync + 'static>(&self, result: R) -> Result<(), ()> {
+        debug!("BastionContext({}): Stopping with a result.", self.id);
+        self.state.set_result(Arc::new(result));
+        Ok(())
     }
 
-    /// Sends a message to the specified [`RefAddr`]
-    ///
-    /// # Arguments
-    ///
-    /// * `to` – the [`RefAddr`] to send the message to
-    /// * `msg` – The actual message to send
+    /// Returns a [`SupervisorRef`] referencing the supervisor
+    /// that supervises the element that is linked to this
+    /// `BastionContext` if it isn't the system supervisor
+    /// (ie. if the children group wasn't created using
+    /// [`Bastion::children`]).
     ///
     /// # Example
     ///
@@ -604,15 +758,32 @@ impl BastionContext {
     /// # fn run() {
     /// # Bastion::init();
     /// #
+    /// // When calling the method from a children group supervised
+    /// // by a supervisor created by the user...
+    /// Bastion::supervisor(|sp| {
+    ///     sp.children(|children| {
+    ///         children.with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 // ...the method will return a SupervisorRef referencing the
+    ///                 // user-created supervisor...
+    ///                 let supervisor: Option<&SupervisorRef> = ctx.supervisor(); // Some
+    ///                 assert!(supervisor.is_some());
+    ///
+    ///                 Ok(())
+    ///             }
+    ///         })
+    ///     })
+    /// }).expect("Couldn't create the supervisor.");
+    ///
+    /// // When calling the method from a children group supervised
+    /// // by the system's supervisor...
     /// Bastion::children(|children| {
     ///     children.with_exec(|ctx: BastionContext| {
     ///         async move {
-    ///             // Wait for a message to be received...
-    ///             let smsg: SignedMessage = ctx.recv().await?;
-    ///             // Obtain address of this message sender...
-    ///             let sender_addr = smsg.signature();
-    ///             // And send something back
-    ///             ctx.tell(&sender_addr, "Ack").expect("Unable to acknowledge");
+    ///             // ...the method won't return a SupervisorRef...
+    ///             let supervisor: Option<&SupervisorRef> = ctx.supervisor(); // None
+    ///             assert!(supervisor.is_none());
+    ///
     ///             Ok(())
     ///         }
     ///     })
@@ -623,34 +794,35 @@ impl BastionContext {
     /// # Bastion::block_until_stopped();
     /// # }
     /// ```
-    pub fn tell<M: Message>(&self, to: &RefAddr, msg: M) -> Result<(), M> {
-        debug!(
-            "{:?}: Telling message: {:?} to: {:?}",
-            self.current().path(),
-            msg,
-            to.path()
-        );
-        let msg = BastionMessage::tell(msg);
-        let env = Envelope::new_with_sign(msg, self.signature());
-        // FIXME: panics?
-        to.sender()
-            .unbounded_send(env)
-            .map_err(|err| err.into_inner().into_msg().unwrap())
+    ///
+    /// [`Bastion::children`]: struct.Bastion.html#method.children
+    pub fn supervisor(&self) -> Option<&SupervisorRef> {
+        self.supervisor.as_ref()
     }
 
-    /// Sends a message from behalf of current context to the addr,
-    /// allowing to addr owner answer.
+    /// Spawns a short-lived task supervised by the supervisor of
+    /// the element this `BastionContext` is linked to, instead of
+    /// an unsupervised task spawned using [`spawn!`].
     ///
-    /// This method returns [`Answer`] if it succeeded, or `Err(msg)`
-    /// otherwise.
+    /// The task runs as the single element of a new children group
+    /// of the supervisor, and thus is restarted following the
+    /// supervisor's restart strategy if it returns `Err(())` or
+    /// panics. Once its future returns `Ok(())`, the element this
+    /// `BastionContext` is linked to receives a [`ChildCompleted`]
+    /// message and the group is stopped.
     ///
-    /// # Argument
+    /// This method returns a [`ChildRef`] referencing the element
+    /// running the task if it succeeded, or `Err(())` otherwise.
     ///
-    /// * `msg` - The message to send.
+    /// # Arguments
+    ///
+    /// * `init` - The closure taking a [`BastionContext`] and
+    ///     returning the future run by the task, called again if the
+    ///     task is restarted.
     ///
     /// # Example
     ///
-    /// ```
+    /// ```rust
     /// # use bastion::prelude::*;
     /// #
     /// # #[cfg(feature = "tokio-runtime")]
@@ -665,24 +837,1748 @@ impl BastionContext {
     /// # }
     /// #
     /// # fn run() {
-    ///     # Bastion::init();
-    /// // The message that will be "asked"...
-    /// const ASK_MSG: &'static str = "A message containing data (ask).";
-    /// // The message the will be "answered"...
-    /// const ANSWER_MSG: &'static str = "A message containing data (answer).";
-    ///
-    ///     # let children_ref =
-    /// // Create a new child...
+    /// # Bastion::init();
+    /// #
     /// Bastion::children(|children| {
     ///     children.with_exec(|ctx: BastionContext| {
     ///         async move {
-    ///             // ...which will receive the message asked...
-    ///             msg! { ctx.recv().await?,
-    ///                 msg: &'static str =!> {
-    ///                     assert_eq!(msg, ASK_MSG);
-    ///                     // Handle the message...
+    ///             let task: ChildRef = ctx.spawn_child(|ctx: BastionContext| {
+    ///                 async move {
+    ///                     // Fetch something, possibly failing and being restarted...
+    ///                     Ok(())
+    ///                 }
+    ///             })?;
     ///
-    ///                     // ...and eventually answer to it...
+    ///             msg! { ctx.recv().await?,
+    ///                 ref completed: ChildCompleted => {
+    ///                     assert_eq!(completed.id(), task.id());
+    ///                 };
+    ///                 _: _ => ();
+    ///             }
+    ///
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`spawn!`]: crate::spawn
+    pub fn spawn_child<I, F>(&self, init: I) -> Result<ChildRef, ()>
+    where
+        I: Fn(BastionContext) -> F + Send + 'static,
+        F: Future<Output = Result<(), ()>> + Send + 'static,
+    {
+        debug!("BastionContext({}): Spawning child task.", self.id);
+        let spawner = self.current().addr();
+        let exec = move |ctx: BastionContext| {
+            let id = ctx.current().id().clone();
+            let group = ctx.parent().clone();
+            let spawner = spawner.clone();
+            let task = init(ctx);
+            async move {
+                task.await?;
+
+                debug!("BastionContext({}): Child task completed.", id);
+                spawner.tell(ChildCompleted { id }).ok();
+                group.stop().ok();
+                Ok(())
+            }
+        };
+
+        let children_ref = match &self.supervisor {
+            Some(supervisor) => supervisor.children(|children| children.with_exec(exec))?,
+            None => system::current()
+                .supervisor()
+                .children(|children| children.with_exec(exec))?,
+        };
+        children_ref.elems().first().cloned().ok_or(())
+    }
+
+    /// Runs the given closure on the blocking pool (see
+    /// [`Bastion::blocking_pool_config`]), returning a
+    /// [`JoinHandle`] resolving to the value it returned.
+    ///
+    /// Unlike with [`blocking!`], the closure is tied to the
+    /// element: it is cancelled if it didn't start running yet when
+    /// the element is stopped or restarted, and awaiting the handle
+    /// with `?` makes the element fail (and be restarted by its
+    /// supervisor) if the closure panicked.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The closure to run on the blocking pool.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             let checksum: JoinHandle<u64> = ctx.spawn_blocking(|| {
+    ///                 // Hash a file synchronously...
+    ///                 42
+    ///             });
+    ///             assert_eq!(checksum.await?, 42);
+    ///
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Bastion::blocking_pool_config`]: crate::Bastion::blocking_pool_config
+    /// [`blocking!`]: crate::blocking
+    pub fn spawn_blocking<F, R>(&self, f: F) -> JoinHandle<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        debug!("BastionContext({}): Spawning blocking task.", self.id);
+        let cancel = ScheduleHandle::new();
+        self.state.add_schedule(cancel.clone());
+
+        let cancelled = cancel.clone();
+        let handle = executor::blocking(async move {
+            if cancelled.is_cancelled() {
+                return None;
+            }
+
+            let result = f();
+            // Removes the task from the element's schedules.
+            cancelled.cancel();
+            Some(result)
+        });
+
+        JoinHandle { handle, cancel }
+    }
+
+    /// Tries to retrieve asynchronously a message received by
+    /// the element this `BastionContext` is linked to.
+    ///
+    /// If you need to wait (always asynchronously) until at
+    /// least one message can be retrieved, use [`recv`] instead.
+    ///    
+    /// If you want to wait for a certain amount of time before bailing out
+    /// use [`recv_timeout`] instead.
+    ///
+    /// This method returns [`SignedMessage`] if a message was available, or
+    /// `None` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             let opt_msg: Option<SignedMessage> = ctx.try_recv().await;
+    ///             // If a message was received by the element, `opt_msg` will
+    ///             // be `Some(Msg)`, otherwise it will be `None`.
+    ///
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`recv`]: Self::recv
+    /// [`recv_timeout`]: Self::recv_timeout
+    pub async fn try_recv(&self) -> Option<SignedMessage> {
+        // We want to let a tick pass
+        // otherwise guard will never contain anything.
+        Delay::new(Duration::from_millis(0)).await;
+
+        trace!("BastionContext({}): Trying to receive message.", self.id);
+
+        if let Some(msg) = self.state.pop_message() {
+            trace!("BastionContext({}): Received message: {:?}", self.id, msg);
+            Some(msg)
+        } else {
+            trace!("BastionContext({}): Received no message.", self.id);
+            None
+        }
+    }
+
+    /// Retrieves asynchronously a message received by the element
+    /// this `BastionContext` is linked to and waits (always
+    /// asynchronously) for one if none has been received yet.
+    ///
+    /// If you don't need to wait until at least one message
+    /// can be retrieved, use [`try_recv`] instead.
+    ///    
+    /// If you want to wait for a certain amount of time before bailing out
+    /// use [`recv_timeout`] instead.
+    ///
+    /// This method returns [`SignedMessage`] if it succeeded, or `Err(())`
+    /// otherwise (which happens when the element is being stopped with
+    /// a stop timeout and its mailbox is empty, see
+    /// [`Children::with_stop_timeout`]).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             // This will block until a message has been received...
+    ///             let msg: SignedMessage = ctx.recv().await?;
+    ///
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`try_recv`]: Self::try_recv
+    /// [`recv_timeout`]: Self::recv_timeout
+    /// [`Children::with_stop_timeout`]: crate::children::Children::with_stop_timeout
+    pub async fn recv(&self) -> Result<SignedMessage, ()> {
+        debug!("BastionContext({}): Waiting to receive message.", self.id);
+        loop {
+            if let Some(msg) = self.state.pop_message() {
+                trace!("BastionContext({}): Received message: {:?}", self.id, msg);
+                return Ok(msg);
+            }
+            if self.state.is_stopping() {
+                debug!("BastionContext({}): Stopping, no message left.", self.id);
+                return Err(());
+            }
+            // The child might be waiting for another element of its
+            // group to finish handling a message.
+            future::poll_fn(|cx| {
+                self.state.park(cx.waker());
+                Poll::Ready(())
+            })
+            .await;
+            pending!();
+        }
+    }
+
+    /// Retrieves asynchronously the first message received by the
+    /// element this `BastionContext` is linked to for which
+    /// `predicate` returns `true`, and waits (always asynchronously)
+    /// for one if none has been received yet.
+    ///
+    /// Contrary to [`recv`], the messages that don't match are kept
+    /// in the mailbox, in order, and will be retrieved by the next
+    /// calls to [`recv`] (or to this method), similarly to Erlang's
+    /// selective receive. This allows to wait for the response of a
+    /// request without losing the unrelated messages received in the
+    /// meantime.
+    ///
+    /// Note that the mailbox is locked while `predicate` is called,
+    /// which means that it shouldn't use this `BastionContext`.
+    ///
+    /// This method returns [`SignedMessage`] if it succeeded, or `Err(())`
+    /// otherwise (which happens when the element is being stopped
+    /// and no message of its mailbox matches, see [`recv`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `predicate` - The closure selecting the message to retrieve.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             let msg: SignedMessage = ctx.recv().await?;
+    ///             let requester = msg.signature().clone();
+    ///             // Waiting for the next message sent by the same
+    ///             // element, the others being kept for later...
+    ///             let next: SignedMessage = ctx
+    ///                 .recv_where(|msg| msg.signature().path() == requester.path())
+    ///                 .await?;
+    ///
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`recv`]: Self::recv
+    /// [`SignedMessage`]: crate::envelope::SignedMessage
+    pub async fn recv_where<P>(&self, predicate: P) -> Result<SignedMessage, ()>
+    where
+        P: Fn(&SignedMessage) -> bool,
+    {
+        debug!(
+            "BastionContext({}): Waiting to receive a matching message.",
+            self.id
+        );
+        loop {
+            if let Some(msg) = self.state.pop_message_where(&predicate) {
+                trace!("BastionContext({}): Received message: {:?}", self.id, msg);
+                return Ok(msg);
+            }
+            if self.state.is_stopping() {
+                debug!(
+                    "BastionContext({}): Stopping, no matching message left.",
+                    self.id
+                );
+                return Err(());
+            }
+            future::poll_fn(|cx| {
+                self.state.park(cx.waker());
+                Poll::Ready(())
+            })
+            .await;
+            pending!();
+        }
+    }
+
+    /// Retrieves asynchronously the first message of type `M`
+    /// received by the element this `BastionContext` is linked to,
+    /// keeping the other messages in its mailbox (see
+    /// [`recv_where`]).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             // Waiting for the configuration before handling
+    ///             // anything else...
+    ///             msg! { ctx.recv_match::<String>().await?,
+    ///                 config: String => {
+    ///                     // ...
+    ///                 };
+    ///                 _: _ => ();
+    ///             }
+    ///
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`recv_where`]: Self::recv_where
+    pub async fn recv_match<M: Message>(&self) -> Result<SignedMessage, ()> {
+        self.recv_where(|msg| msg.is::<M>()).await
+    }
+
+    /// Returns the index of the element this `BastionContext` is
+    /// linked to in its children group, between `0` and the group's
+    /// redundancy (excluded).
+    ///
+    /// The index of an element is kept when it is restarted, and the
+    /// index of a stopped element is reused by the next element
+    /// launched by the group (e.g. when scaling up), which allows to
+    /// assign each element its own partition of the work.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_redundancy(4)
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 let partition = ctx.elem_index();
+    ///                 assert!(partition < 4);
+    ///                 // Consume the partition...
+    ///                 Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn elem_index(&self) -> usize {
+        self.state.index()
+    }
+
+    /// Returns the value handed to the element this
+    /// `BastionContext` is linked to, which is the value at the
+    /// element's index (see [`elem_index`]) in the list given to
+    /// [`Children::with_init_data`], or `None` if there is no such
+    /// value or its type isn't `T`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_redundancy(2)
+    ///         .with_init_data(vec!["orders-0".to_string(), "orders-1".to_string()])
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 let topic: &String = ctx.init_data().expect("No topic assigned.");
+    ///                 // Consume the topic...
+    ///                 Ok(())
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`elem_index`]: Self::elem_index
+    /// [`Children::with_init_data`]: crate::children::Children::with_init_data
+    pub fn init_data<T: 'static>(&self) -> Option<&T> {
+        self.state.init_data.as_ref()?.downcast_ref()
+    }
+
+    /// Returns the state of the element this `BastionContext` is
+    /// linked to, created using the closure given to
+    /// [`Children::with_state`], or `None` if the group doesn't
+    /// have any or its type isn't `S`.
+    ///
+    /// The state lives as long as the element: it survives across
+    /// the iterations of the element's loop, is dropped once the
+    /// element stops, and is created again or kept when the element
+    /// is restarted depending on the group's [`StateRecovery`].
+    ///
+    /// The returned guard locks the state until it is dropped, and
+    /// can't be held across `.await` points.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_state(|| 0usize)
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 loop {
+    ///                     let msg = ctx.recv().await?;
+    ///                     let mut handled = ctx.state::<usize>().unwrap();
+    ///                     *handled += 1;
+    ///                 }
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Children::with_state`]: crate::children::Children::with_state
+    /// [`StateRecovery`]: crate::children::StateRecovery
+    pub fn state<S: Send + 'static>(&self) -> Option<LocalState<'_, S>> {
+        let guard = self.state.local.lock().unwrap();
+        match &*guard {
+            Some(local) if local.is::<S>() => Some(LocalState {
+                guard,
+                _state: PhantomData,
+            }),
+            _ => {
+                debug!("BastionContext({}): No state of the given type.", self.id);
+                None
+            }
+        }
+    }
+
+    /// Retrieves a message received by the element this
+    /// `BastionContext` is linked to, blocking the current thread
+    /// until one is received.
+    ///
+    /// This method is meant to be called from the closure given
+    /// to [`Children::with_exec_blocking`], which runs on a thread
+    /// of the blocking pool; calling it from an asynchronous
+    /// closure would block the thread of the executor running it,
+    /// so use [`recv`] there instead.
+    ///
+    /// This method returns [`SignedMessage`] if it succeeded, or
+    /// `Err(())` if the element is being stopped (see
+    /// [`Children::with_stop_timeout`]) and its mailbox is empty.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec_blocking(|ctx: BastionContext| {
+    ///         // This will block the thread until a message has been received...
+    ///         let msg: SignedMessage = ctx.recv_blocking()?;
+    ///
+    ///         Ok(())
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`recv`]: Self::recv
+    /// [`Children::with_exec_blocking`]: crate::children::Children::with_exec_blocking
+    /// [`Children::with_stop_timeout`]: crate::children::Children::with_stop_timeout
+    pub fn recv_blocking(&self) -> Result<SignedMessage, ()> {
+        debug!(
+            "BastionContext({}): Blocking until a message is received.",
+            self.id
+        );
+        loop {
+            if let Some(msg) = self.state.pop_message() {
+                trace!("BastionContext({}): Received message: {:?}", self.id, msg);
+                return Ok(msg);
+            }
+            if self.state.is_stopping() {
+                debug!("BastionContext({}): Stopping, no message left.", self.id);
+                return Err(());
+            }
+
+            self.state.block_on_mailbox();
+            // A message might have been received before the thread
+            // was registered.
+            if self.state.mailbox_len() == 0 && !self.state.is_stopping() {
+                // The child might also be waiting for another element
+                // of its group to finish handling a message, which
+                // doesn't unpark it.
+                thread::park_timeout(RECV_BLOCKING_PARK_TIMEOUT);
+            }
+            self.state.unblock_mailbox();
+        }
+    }
+
+    /// Retrieves asynchronously a message received by the element
+    /// this `BastionContext` is linked to and waits until `timeout`
+    /// (always asynchronously) for one if none has been received yet,
+    /// allowing the element to do some housekeeping (e.g. flushing
+    /// a buffer) even when it doesn't receive any message.
+    ///
+    /// If you want to wait for ever until at least one message
+    /// can be retrieved, use [`recv`] instead.
+    ///
+    /// If you don't need to wait until at least one message
+    /// can be retrieved, use [`try_recv`] instead.
+    ///
+    /// This method returns [`SignedMessage`] if it succeeded,
+    /// `Err(ReceiveError::Timeout(timeout))` if no message was
+    /// received in time, or `Err(ReceiveError::Stopped)` if the element
+    /// is being stopped and its mailbox is empty (see [`recv`]).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             let mut buffer = Vec::new();
+    ///             loop {
+    ///                 match ctx.recv_timeout(Duration::from_secs(1)).await {
+    ///                     Ok(msg) => buffer.push(msg),
+    ///                     // Flushing the buffer when no message was
+    ///                     // received for a second...
+    ///                     Err(ReceiveError::Timeout(_)) => buffer.clear(),
+    ///                     Err(_) => return Ok(()),
+    ///                 }
+    ///             }
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`recv`]: Self::recv
+    /// [`try_recv`]: Self::try_recv
+    /// [`SignedMessage`]: crate::envelope::SignedMessage
+    pub async fn recv_timeout(&self, timeout: Duration) -> Result<SignedMessage, ReceiveError> {
+        debug!(
+            "BastionContext({}): Waiting to receive message within {} milliseconds.",
+            self.id,
+            timeout.as_millis()
+        );
+        futures::select! {
+            message = self.recv().fuse() => {
+                message.map_err(|_| ReceiveError::Stopped)
+            },
+            _duration = executor::sleep(timeout).fuse() => {
+                Err(ReceiveError::Timeout(timeout))
+            }
+        }
+    }
+
+    /// Retrieves asynchronously a message received by the element
+    /// this `BastionContext` is linked to and waits until `timeout` (always
+    /// asynchronously) for one if none has been received yet.
+    ///
+    /// This method is the same as [`recv_timeout`].
+    ///
+    /// If you want to wait for ever until at least one message
+    /// can be retrieved, use [`recv`] instead.
+    ///
+    /// If you don't need to wait until at least one message
+    /// can be retrieved, use [`try_recv`] instead.
+    ///
+    /// This method returns [`SignedMessage`] if it succeeded, or `Err(TimeoutError)`
+    /// otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             // This will block until a message has been received...
+    ///             let timeout = Duration::from_millis(10);
+    ///             let msg: SignedMessage = ctx.try_recv_timeout(timeout).await.map_err(|e| {
+    ///                if let ReceiveError::Timeout(duration) = e {
+    ///                    // Timeout happened      
+    ///                }
+    ///             })?;
+    ///
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`recv`]: Self::recv
+    /// [`try_recv`]: Self::try_recv
+    /// [`recv_timeout`]: Self::recv_timeout
+    /// [`SignedMessage`]: .crate::enveloppe::SignedMessage
+    pub async fn try_recv_timeout(&self, timeout: Duration) -> Result<SignedMessage, ReceiveError> {
+        self.recv_timeout(timeout).await
+    }
+
+    /// Acknowledges the processing of the last message received
+    /// by the element this `BastionContext` is linked to, if its
+    /// sender asked for an acknowledgment (e.g. using
+    /// [`Distributor::tell_everyone_acked`]).
+    ///
+    /// This method returns `true` if an acknowledgment was sent,
+    /// or `false` if the last message didn't require one or if
+    /// it was already acknowledged.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             loop {
+    ///                 let msg: SignedMessage = ctx.recv().await?;
+    ///                 // Handle the message...
+    ///
+    ///                 // ...and let its sender know it was processed.
+    ///                 ctx.ack();
+    ///             }
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Distributor::tell_everyone_acked`]: crate::distributor::Distributor::tell_everyone_acked
+    pub fn ack(&self) -> bool {
+        debug!("BastionContext({}): Acknowledging last message.", self.id);
+        match self.state.take_ack() {
+            Some(ack) => ack.send(()).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Sets aside a message received by the element this
+    /// `BastionContext` is linked to, e.g. because it can't handle it
+    /// yet, until [`unstash_all`] is called.
+    ///
+    /// The message keeps its signature, and stashing the last
+    /// received message delays its acknowledgment (see [`ack`])
+    /// until it is received again.
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The message to set aside.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             // Setting aside the messages received while
+    ///             // initializing...
+    ///             loop {
+    ///                 let msg: SignedMessage = ctx.recv().await?;
+    ///                 if msg.is::<&'static str>() {
+    ///                     break;
+    ///                 }
+    ///                 ctx.stash(msg);
+    ///             }
+    ///             // ...and handling them once ready.
+    ///             ctx.unstash_all();
+    ///
+    ///             loop {
+    ///                 let msg: SignedMessage = ctx.recv().await?;
+    ///                 // Handle the message...
+    ///             }
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`unstash_all`]: Self::unstash_all
+    /// [`ack`]: Self::ack
+    pub fn stash(&self, msg: SignedMessage) {
+        trace!("BastionContext({}): Stashing message: {:?}", self.id, msg);
+        self.state.stash(msg);
+    }
+
+    /// Makes the given behavior the one handling the next messages
+    /// received by [`run_behaviors`], on top of the stack of
+    /// behaviors of the element this `BastionContext` is linked to.
+    ///
+    /// The previous behavior can be restored using [`unbecome`].
+    ///
+    /// # Arguments
+    ///
+    /// * `behavior` - The behavior handling the next messages.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// fn locked() -> Behavior {
+    ///     Behavior::new(|ctx: &BastionContext, msg: SignedMessage| {
+    ///         if msg.is::<&'static str>() {
+    ///             // The door was unlocked...
+    ///             ctx.become_(unlocked());
+    ///         }
+    ///         Ok(())
+    ///     })
+    /// }
+    ///
+    /// fn unlocked() -> Behavior {
+    ///     Behavior::new(|ctx: &BastionContext, msg: SignedMessage| {
+    ///         // The door was opened and locked again...
+    ///         ctx.unbecome();
+    ///         Ok(())
+    ///     })
+    /// }
+    ///
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             ctx.become_(locked());
+    ///             ctx.run_behaviors().await
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`run_behaviors`]: Self::run_behaviors
+    /// [`unbecome`]: Self::unbecome
+    pub fn become_(&self, behavior: Behavior) {
+        trace!("BastionContext({}): Becoming a new behavior.", self.id);
+        self.behaviors.lock().unwrap().push(behavior);
+    }
+
+    /// Drops the active behavior of the element this
+    /// `BastionContext` is linked to (see [`become_`]), the previous
+    /// one handling the next messages.
+    ///
+    /// This method returns `false` if there was no behavior to drop.
+    ///
+    /// [`become_`]: Self::become_
+    pub fn unbecome(&self) -> bool {
+        trace!("BastionContext({}): Unbecoming a behavior.", self.id);
+        self.behaviors.lock().unwrap().pop().is_some()
+    }
+
+    /// Receives the messages of the element this `BastionContext`
+    /// is linked to and has them handled by its active behavior (see
+    /// [`become_`]), until there is no behavior left in its stack.
+    ///
+    /// This method returns `Ok(())` once all the behaviors were
+    /// dropped using [`unbecome`], or `Err(())` if a behavior
+    /// returned an error or if the element is being stopped (see
+    /// [`recv`]).
+    ///
+    /// [`become_`]: Self::become_
+    /// [`unbecome`]: Self::unbecome
+    /// [`recv`]: Self::recv
+    pub async fn run_behaviors(&self) -> Result<(), ()> {
+        debug!("BastionContext({}): Running behaviors.", self.id);
+        loop {
+            // The behavior can become another one while handling
+            // the message.
+            let behavior = match self.behaviors.lock().unwrap().last() {
+                Some(behavior) => behavior.clone(),
+                None => {
+                    debug!("BastionContext({}): No behavior left.", self.id);
+                    return Ok(());
+                }
+            };
+
+            let msg = self.recv().await?;
+            behavior.handle(self, msg)?;
+        }
+    }
+
+    /// Subscribes the element this `BastionContext` is linked to to
+    /// the topic, so that it receives every message published on it
+    /// until it unsubscribes from it or stops.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic to subscribe to.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| async move {
+    ///         let events = Topic::named("events");
+    ///         ctx.subscribe(&events).expect("Couldn't subscribe.");
+    ///         // Receive the events...
+    ///         ctx.unsubscribe(&events).expect("Couldn't unsubscribe.");
+    ///         Ok(())
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn subscribe(&self, topic: &Topic) -> AnyResult<()> {
+        debug!(
+            "BastionContext({}): Subscribing to {}.",
+            self.id,
+            topic.name()
+        );
+        topic.distributor().subscribe(self.child.clone())?;
+        self.state.add_topic(topic.distributor());
+        Ok(())
+    }
+
+    /// Unsubscribes the element this `BastionContext` is linked to
+    /// from the topic (see [`subscribe`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic to unsubscribe from.
+    ///
+    /// [`subscribe`]: Self::subscribe
+    pub fn unsubscribe(&self, topic: &Topic) -> AnyResult<()> {
+        debug!(
+            "BastionContext({}): Unsubscribing from {}.",
+            self.id,
+            topic.name()
+        );
+        self.state.remove_topic(topic.distributor());
+        topic.distributor().unsubscribe(self.child.clone())
+    }
+
+    /// Returns the persistence identifier of the element this
+    /// `BastionContext` is linked to, if its children group was made
+    /// persistent using [`Children::with_persistence`].
+    ///
+    /// [`Children::with_persistence`]: crate::children::Children::with_persistence
+    pub fn persistence_id(&self) -> Option<&str> {
+        self.state
+            .persistence
+            .as_ref()
+            .map(|persistence| persistence.id.as_str())
+    }
+
+    /// Serializes the event and appends it to the journal of the
+    /// element this `BastionContext` is linked to, returning its
+    /// sequence number once the journal stored it.
+    ///
+    /// The event is given back to the recovery handler of the
+    /// children group (see [`Children::with_recovery`]) when the
+    /// element starts again, so it should be persisted before the
+    /// state of the element is updated with it.
+    ///
+    /// This method returns an error if the children group of the
+    /// element wasn't made persistent using
+    /// [`Children::with_persistence`], or if the journal failed.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The event to persist.
+    ///
+    /// See [`Children::with_persistence`] for an example.
+    ///
+    /// [`Children::with_recovery`]: crate::children::Children::with_recovery
+    /// [`Children::with_persistence`]: crate::children::Children::with_persistence
+    pub async fn persist<E: Serialize + Debug + 'static>(&self, event: &E) -> AnyResult<u64> {
+        let persistence = self.persistence()?;
+        trace!("BastionContext({}): Persisting event: {:?}", self.id, event);
+        let payload = persistence.codec.encode(event)?;
+        self.spawn_blocking(move || persistence.journal.append(&persistence.id, payload))
+            .await
+            .map_err(|_| anyhow!("The event couldn't be appended to the journal."))?
+    }
+
+    /// Replays the events persisted by the element this
+    /// `BastionContext` is linked to (see [`persist`]), from the
+    /// oldest to the most recent one, through the given handler,
+    /// and returns the sequence number of the last one (or `0` if
+    /// there was none).
+    ///
+    /// The events are already replayed to the state of the element
+    /// before it starts if its children group was given a recovery
+    /// handler using [`Children::with_recovery`]; this method is
+    /// meant for elements that keep their state in their future
+    /// instead, and should then be called when they start.
+    ///
+    /// This method returns an error if the children group of the
+    /// element wasn't made persistent using
+    /// [`Children::with_persistence`], if the journal failed or if
+    /// an event couldn't be deserialized.
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - The closure applying each event to the state of
+    ///     the element.
+    ///
+    /// See [`Children::with_persistence`] for an example.
+    ///
+    /// [`persist`]: Self::persist
+    /// [`Children::with_recovery`]: crate::children::Children::with_recovery
+    /// [`Children::with_persistence`]: crate::children::Children::with_persistence
+    pub async fn recover<E, F>(&self, mut handler: F) -> AnyResult<u64>
+    where
+        E: DeserializeOwned + Debug + 'static,
+        F: FnMut(E),
+    {
+        let persistence = self.persistence()?;
+        let codec = persistence.codec.clone();
+        debug!(
+            "BastionContext({}): Recovering the events of {}.",
+            self.id, persistence.id
+        );
+        let entries = self
+            .spawn_blocking(move || persistence.journal.read(&persistence.id, 1, u64::MAX))
+            .await
+            .map_err(|_| anyhow!("The events couldn't be read from the journal."))??;
+
+        let mut sequence_nr = 0;
+        for entry in entries {
+            let event: E = codec.decode(&entry)?;
+            trace!(
+                "BastionContext({}): Recovering event {}: {:?}",
+                self.id,
+                entry.sequence_nr,
+                event
+            );
+            handler(event);
+            sequence_nr = entry.sequence_nr;
+        }
+        Ok(sequence_nr)
+    }
+
+    fn persistence(&self) -> AnyResult<Persistence> {
+        self.state
+            .persistence
+            .clone()
+            .ok_or_else(|| anyhow!("The children group of the element wasn't made persistent."))
+    }
+
+    /// Sends a message to the element this `BastionContext` is
+    /// linked to once `delay` elapsed, unless it was cancelled using
+    /// the returned [`ScheduleHandle`] or the element was stopped or
+    /// restarted in the meantime.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay` - How long to wait before sending the message.
+    /// * `msg` - The message to send.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             let timeout = ctx.schedule(Duration::from_secs(30), "timeout");
+    ///             loop {
+    ///                 msg! { ctx.recv().await?,
+    ///                     msg: &'static str => {
+    ///                         // The request timed out...
+    ///                     };
+    ///                     _: _ => {
+    ///                         // The request completed in time...
+    ///                         timeout.cancel();
+    ///                     };
+    ///                 }
+    ///             }
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn schedule<M: Message>(&self, delay: Duration, msg: M) -> ScheduleHandle {
+        debug!(
+            "BastionContext({}): Scheduling message in {:?}: {:?}",
+            self.id, delay, msg
+        );
+        let handle = ScheduleHandle::new();
+        self.state.add_schedule(handle.clone());
+
+        let cancelled = handle.clone();
+        let child = self.current().clone();
+        let sign = self.signature();
+        spawn!(async move {
+            executor::sleep(delay).await;
+            if !cancelled.is_cancelled() {
+                child.tell_signed(msg, sign).ok();
+                cancelled.cancel();
+            }
+        });
+
+        handle
+    }
+
+    /// Sends a message to the element this `BastionContext` is
+    /// linked to every `interval`, until it is cancelled using the
+    /// returned [`ScheduleHandle`] or the element is stopped or
+    /// restarted.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - How long to wait between two messages.
+    /// * `msg` - The message to send, cloned every time.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             ctx.schedule_repeated(Duration::from_secs(1), "flush");
+    ///             loop {
+    ///                 msg! { ctx.recv().await?,
+    ///                     msg: &'static str => {
+    ///                         // Flush the buffered messages...
+    ///                     };
+    ///                     _: _ => {
+    ///                         // Buffer the message...
+    ///                     };
+    ///                 }
+    ///             }
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn schedule_repeated<M: Message + Clone>(
+        &self,
+        interval: Duration,
+        msg: M,
+    ) -> ScheduleHandle {
+        debug!(
+            "BastionContext({}): Scheduling message every {:?}: {:?}",
+            self.id, interval, msg
+        );
+        let handle = ScheduleHandle::new();
+        self.state.add_schedule(handle.clone());
+
+        let cancelled = handle.clone();
+        let child = self.current().clone();
+        let sign = self.signature();
+        spawn!(async move {
+            loop {
+                executor::sleep(interval).await;
+                // The child might have been dropped without being
+                // stopped.
+                if cancelled.is_cancelled() || child.sender().is_closed() {
+                    break;
+                }
+                child.tell_signed(msg.clone(), sign.clone()).ok();
+            }
+        });
+
+        handle
+    }
+
+    /// Sends the items of the given stream to the element this
+    /// `BastionContext` is linked to as messages, until the stream
+    /// ends, the returned [`ScheduleHandle`] is cancelled or the
+    /// element is stopped or restarted.
+    ///
+    /// The next item is only polled once the element's mailbox has
+    /// room for it (see [`Children::with_mailbox_capacity`]), so that
+    /// a fast stream doesn't overflow it. Once cancelled, the stream
+    /// is dropped when it yields its next item.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - The stream whose items are sent to the element.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use futures::stream;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children
+    ///         .with_mailbox_capacity(100)
+    ///         .with_overflow_policy(OverflowPolicy::Backpressure)
+    ///         .with_exec(|ctx: BastionContext| {
+    ///             async move {
+    ///                 ctx.attach_stream(stream::iter(0..10_000_u32));
+    ///                 loop {
+    ///                     msg! { ctx.recv().await?,
+    ///                         item: u32 => {
+    ///                             // Handle the item...
+    ///                         };
+    ///                         _: _ => ();
+    ///                     }
+    ///                 }
+    ///             }
+    ///         })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Children::with_mailbox_capacity`]: crate::children::Children::with_mailbox_capacity
+    pub fn attach_stream<S>(&self, stream: S) -> ScheduleHandle
+    where
+        S: Stream + Send + 'static,
+        S::Item: Message,
+    {
+        debug!("BastionContext({}): Attaching stream.", self.id);
+        let handle = ScheduleHandle::new();
+        self.state.add_schedule(handle.clone());
+
+        let cancelled = handle.clone();
+        let child = self.current().clone();
+        let sign = self.signature();
+        let id = self.id.clone();
+        spawn!(async move {
+            let mut stream = Box::pin(stream);
+            'items: loop {
+                child.wait_for_capacity().await;
+                let mut item = match stream.next().await {
+                    Some(item) => item,
+                    None => break,
+                };
+                loop {
+                    if cancelled.is_cancelled() {
+                        break 'items;
+                    }
+
+                    match child.tell_signed(item, sign.clone()) {
+                        Ok(()) => break,
+                        // Another sender took the room left in the
+                        // mailbox in the meantime.
+                        Err(refused) if !child.sender().is_closed() => {
+                            item = refused;
+                            child.wait_for_capacity().await;
+                        }
+                        Err(_) => break 'items,
+                    }
+                }
+            }
+
+            trace!("BastionContext({}): Detaching stream.", id);
+            cancelled.cancel();
+        });
+
+        handle
+    }
+
+    /// Returns the amount of messages waiting in the mailbox of the
+    /// element this `BastionContext` is linked to, e.g. to reply
+    /// that it is busy instead of handling a message when it is
+    /// overloaded.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             loop {
+    ///                 msg! { ctx.recv().await?,
+    ///                     query: &'static str =!> {
+    ///                         if ctx.mailbox_len() > 1_000 {
+    ///                             answer!(ctx, "busy").expect("Couldn't send the answer.");
+    ///                         } else {
+    ///                             // Handle the query...
+    ///                         }
+    ///                     };
+    ///                     _: _ => ();
+    ///                 }
+    ///             }
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn mailbox_len(&self) -> usize {
+        self.state.mailbox_len()
+    }
+
+    /// Returns the amount of messages retrieved by the element this
+    /// `BastionContext` is linked to (e.g. using [`recv`]) since it
+    /// was first launched, including the runs before its restarts.
+    ///
+    /// [`recv`]: Self::recv
+    pub fn messages_processed(&self) -> u64 {
+        self.state.messages_processed()
+    }
+
+    /// Returns when the element this `BastionContext` is linked to
+    /// last retrieved a message, or `None` if it didn't retrieve
+    /// any message yet.
+    pub fn last_message_at(&self) -> Option<Instant> {
+        self.state.last_message_at()
+    }
+
+    /// Puts back the messages set aside using [`stash`] at the front
+    /// of the mailbox of the element this `BastionContext` is linked
+    /// to, in the order they were stashed, so that they are
+    /// retrieved before any other message.
+    ///
+    /// This method returns the amount of messages that were
+    /// unstashed.
+    ///
+    /// [`stash`]: Self::stash
+    pub fn unstash_all(&self) -> usize {
+        let unstashed = self.state.unstash_all();
+        debug!(
+            "BastionContext({}): Unstashed {} messages.",
+            self.id, unstashed
+        );
+        unstashed
+    }
+
+    /// Returns [`RefAddr`] of the current `BastionContext`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    ///
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             ctx.tell(&ctx.signature(), "Hello to myself");
+    ///
+    ///             # Bastion::stop();
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    // TODO(scrabsha): should we link to Answer or to RefAddr?
+    // [`RefAddr`]: /prelude/struct.Answer.html
+    pub fn signature(&self) -> RefAddr {
+        self.current().addr()
+    }
+
+    /// Sends a message to the specified target, signed with the
+    /// signature of the element this `BastionContext` is linked to.
+    ///
+    /// The target can be a [`RefAddr`], a [`ChildRef`], a
+    /// [`Distributor`] or a [`SupervisorRef`] (see [`MessageTarget`]).
+    ///
+    /// This method returns `()` if it succeeded, or `Err(msg)`
+    /// otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` – the target to send the message to
+    /// * `msg` – The actual message to send
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             // Wait for a message to be received...
+    ///             let smsg: SignedMessage = ctx.recv().await?;
+    ///             // Obtain address of this message sender...
+    ///             let sender_addr = smsg.signature();
+    ///             // And send something back...
+    ///             ctx.tell(sender_addr, "Ack").expect("Unable to acknowledge");
+    ///             // ...and to the group's distributor
+    ///             ctx.tell(&Distributor::named("audit"), "Acked").ok();
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`ChildRef`]: crate::child_ref::ChildRef
+    /// [`Distributor`]: crate::distributor::Distributor
+    /// [`SupervisorRef`]: crate::supervisor::SupervisorRef
+    pub fn tell<M: Message>(&self, to: &impl MessageTarget, msg: M) -> Result<(), M> {
+        debug!("{:?}: Telling message: {:?}", self.current().path(), msg,);
+        to.tell_signed(msg, self.signature())
+    }
+
+    /// Sends a message from behalf of current context to the
+    /// specified target, allowing it to answer.
+    ///
+    /// The target can be a [`RefAddr`], a [`ChildRef`] or a
+    /// [`Distributor`] (see [`MessageTarget`]). Supervisors can't
+    /// answer messages, thus asking a [`SupervisorRef`] fails.
+    ///
+    /// This method returns [`Answer`] if it succeeded, or `Err(msg)`
+    /// otherwise.
+    ///
+    /// # Argument
+    ///
+    /// * `msg` - The message to send.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    ///     # Bastion::init();
+    /// // The message that will be "asked"...
+    /// const ASK_MSG: &'static str = "A message containing data (ask).";
+    /// // The message the will be "answered"...
+    /// const ANSWER_MSG: &'static str = "A message containing data (answer).";
+    ///
+    ///     # let children_ref =
+    /// // Create a new child...
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             // ...which will receive the message asked...
+    ///             msg! { ctx.recv().await?,
+    ///                 msg: &'static str =!> {
+    ///                     assert_eq!(msg, ASK_MSG);
+    ///                     // Handle the message...
+    ///
+    ///                     // ...and eventually answer to it...
     ///                     answer!(ctx, ANSWER_MSG);
     ///                 };
     ///                 // This won't happen because this example
@@ -700,7 +2596,7 @@ impl BastionContext {
     ///             # let child_ref = children_ref.elems()[0].clone();
     ///             # async move {
     /// // Later, the message is "asked" to the child...
-    /// let answer: Answer = ctx.ask(&child_ref.addr(), ASK_MSG).expect("Couldn't send the message.");
+    /// let answer: Answer = ctx.ask(&child_ref, ASK_MSG).expect("Couldn't send the message.");
     ///
     /// // ...and the child's answer is received...
     /// msg! { answer.await.expect("Couldn't receive the answer."),
@@ -723,21 +2619,260 @@ impl BastionContext {
     ///     # Bastion::block_until_stopped();
     /// # }
     /// ```
-    pub fn ask<M: Message>(&self, to: &RefAddr, msg: M) -> Result<Answer, M> {
-        debug!(
-            "{:?}: Asking message: {:?} to: {:?}",
-            self.current().path(),
-            msg,
-            to
-        );
-        let (msg, answer) = BastionMessage::ask(msg, self.signature());
-        let env = Envelope::new_with_sign(msg, self.signature());
-        // FIXME: panics?
-        to.sender()
-            .unbounded_send(env)
-            .map_err(|err| err.into_inner().into_msg().unwrap())?;
-
-        Ok(answer)
+    ///
+    /// [`ChildRef`]: crate::child_ref::ChildRef
+    /// [`Distributor`]: crate::distributor::Distributor
+    /// [`SupervisorRef`]: crate::supervisor::SupervisorRef
+    pub fn ask<M: Message>(&self, to: &impl MessageTarget, msg: M) -> Result<Answer, M> {
+        debug!("{:?}: Asking message: {:?}", self.current().path(), msg,);
+        to.ask_signed(msg, self.signature())
+    }
+
+    /// Sends a message to the specified target like [`tell`], with
+    /// the given correlation id rather than a generated one.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` – the target to send the message to
+    /// * `msg` – The actual message to send
+    /// * `correlation_id` – The correlation id of the message
+    ///
+    /// [`tell`]: Self::tell
+    pub fn tell_correlated<M: Message>(
+        &self,
+        to: &impl MessageTarget,
+        msg: M,
+        correlation_id: CorrelationId,
+    ) -> Result<(), M> {
+        debug!(
+            "{:?}: Telling message {}: {:?}",
+            self.current().path(),
+            correlation_id,
+            msg
+        );
+        to.tell_signed(msg, self.signature().with_correlation_id(correlation_id))
+    }
+
+    /// Sends a question to the specified target like [`ask`], with
+    /// the given correlation id rather than a generated one. The
+    /// answer to the question has the same correlation id.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` – the target to send the message to
+    /// * `msg` – The actual message to send
+    /// * `correlation_id` – The correlation id of the message
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let responder = Bastion::children(|children| children).unwrap();
+    /// Bastion::children(move |children| {
+    ///     children.with_exec(move |ctx: BastionContext| {
+    ///         let responder = responder.elems()[0].clone();
+    ///         async move {
+    ///             // The id of the request being served...
+    ///             let correlation_id = CorrelationId::from(42_u128);
+    ///             let answer = ctx
+    ///                 .ask_correlated(&responder, "question", correlation_id)
+    ///                 .expect("Couldn't send the message.");
+    ///             let answer = answer.await?;
+    ///             assert_eq!(answer.correlation_id(), correlation_id);
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`ask`]: Self::ask
+    pub fn ask_correlated<M: Message>(
+        &self,
+        to: &impl MessageTarget,
+        msg: M,
+        correlation_id: CorrelationId,
+    ) -> Result<Answer, M> {
+        debug!(
+            "{:?}: Asking message {}: {:?}",
+            self.current().path(),
+            correlation_id,
+            msg
+        );
+        to.ask_signed(msg, self.signature().with_correlation_id(correlation_id))
+    }
+
+    /// Sends a question to the given target, like [`ask`], whose
+    /// answer must be of type `R`.
+    ///
+    /// The question can only be matched by the recipient using
+    /// [`MessageHandler::on_request`] with the same answer type,
+    /// which gives it a [`QuestionSender`] only accepting answers of
+    /// type `R`. The returned [`TypedAnswer`] then resolves to the
+    /// answer itself.
+    ///
+    /// This method returns a [`TypedAnswer`] if it succeeded, or
+    /// `Err(msg)` otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `to` - The recipient of the question.
+    /// * `msg` - The question to send.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use bastion::message::MessageHandler;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// let counter = Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             loop {
+    ///                 MessageHandler::new(ctx.recv().await?)
+    ///                     .on_request(|_: &'static str, sender: QuestionSender<u64>| {
+    ///                         sender.reply(42).ok();
+    ///                     })
+    ///                     .on_fallback(|_, _| ());
+    ///             }
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    ///
+    /// Bastion::children(|children| {
+    ///     children.with_exec(move |ctx: BastionContext| {
+    ///         let counter = counter.elems()[0].clone();
+    ///         async move {
+    ///             let count: u64 = ctx.request(&counter, "count").unwrap().await?;
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`ask`]: Self::ask
+    /// [`MessageHandler::on_request`]: crate::message::MessageHandler::on_request
+    /// [`QuestionSender`]: crate::message::QuestionSender
+    pub fn request<R: Message, M: Message>(
+        &self,
+        to: &impl MessageTarget,
+        msg: M,
+    ) -> Result<TypedAnswer<R>, M> {
+        debug!("{:?}: Requesting message: {:?}", self.current().path(), msg);
+        to.ask_signed(Request::<M, R>::new(msg), self.signature())
+            .map(TypedAnswer::new)
+            .map_err(Request::into_inner)
+    }
+
+    /// Forwards a message received by the element this
+    /// `BastionContext` is linked to to the specified target (see
+    /// [`MessageTarget`]), keeping the address of its original
+    /// sender as the address replies should be sent to (see
+    /// [`SignedMessage::reply_to`]).
+    ///
+    /// If the message was asked, the recipient answers directly to
+    /// the original sender, which allows an element to route the
+    /// questions it receives to other elements.
+    ///
+    /// This method returns `()` if it succeeded, or `Err(msg)`
+    /// otherwise. Note that forwarding a message that isn't a
+    /// broadcast to a [`SupervisorRef`] always fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The message to forward.
+    /// * `to` - The target to forward the message to.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             let workers = Distributor::named("workers");
+    ///             loop {
+    ///                 // The workers will answer the questions
+    ///                 // directly...
+    ///                 let msg: SignedMessage = ctx.recv().await?;
+    ///                 if ctx.forward(msg, &workers).is_err() {
+    ///                     // No worker is available...
+    ///                 }
+    ///             }
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`SignedMessage::reply_to`]: crate::envelope::SignedMessage::reply_to
+    /// [`SupervisorRef`]: crate::supervisor::SupervisorRef
+    pub fn forward(
+        &self,
+        msg: SignedMessage,
+        to: &impl MessageTarget,
+    ) -> Result<(), SignedMessage> {
+        debug!("{:?}: Forwarding message: {:?}", self.current().path(), msg);
+        let (mut msg, sign) = msg.split();
+        msg.set_reply_to(sign);
+        to.forward_signed(SignedMessage::new(msg, self.signature()))
     }
 
     /// Sends the notification to each declared dispatcher of the actor.
@@ -749,7 +2884,7 @@ impl BastionContext {
     /// * `notification_type` - The type of the notification to send.
     ///
     pub fn notify(&self, dispatchers: &[DispatcherType], notification_type: NotificationType) {
-        let global_dispatcher = SYSTEM.dispatcher();
+        let global_dispatcher = system::current().dispatcher();
         let from_actor = self.current();
         global_dispatcher.notify(from_actor, dispatchers, notification_type);
     }
@@ -768,15 +2903,115 @@ impl BastionContext {
             sign: self.signature(),
         });
 
-        let global_dispatcher = SYSTEM.dispatcher();
+        let global_dispatcher = system::current().dispatcher();
         global_dispatcher.broadcast_message(target, &msg);
     }
 }
 
+impl ChildCompleted {
+    /// Returns the identifier of the element that ran the completed
+    /// task, which is the one of the [`ChildRef`] returned by
+    /// [`BastionContext::spawn_child`].
+    pub fn id(&self) -> &BastionId {
+        &self.id
+    }
+}
+
+impl<'a, S: 'static> Deref for LocalState<'a, S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        // The type of the state was checked when the guard was created.
+        self.guard.as_ref().unwrap().downcast_ref().unwrap()
+    }
+}
+
+impl<'a, S: 'static> DerefMut for LocalState<'a, S> {
+    fn deref_mut(&mut self) -> &mut S {
+        self.guard.as_mut().unwrap().downcast_mut().unwrap()
+    }
+}
+
+impl<'a, S: Debug + 'static> Debug for LocalState<'a, S> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_tuple("LocalState").field(&**self).finish()
+    }
+}
+
+impl LocalStateInit {
+    pub(crate) fn new<S, F>(init: F) -> Self
+    where
+        S: Send + 'static,
+        F: Fn() -> S + Send + Sync + 'static,
+    {
+        LocalStateInit(Arc::new(move || Box::new(init()) as Box<dyn Any + Send>))
+    }
+
+    pub(crate) fn create(&self) -> Box<dyn Any + Send> {
+        (self.0)()
+    }
+}
+
+impl Debug for LocalStateInit {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("LocalStateInit").finish()
+    }
+}
+
+impl InitData {
+    pub(crate) fn new<T>(data: Vec<T>) -> Self
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        InitData(Arc::new(move |index| {
+            data.get(index)
+                .cloned()
+                .map(|value| Box::new(value) as Box<dyn Any + Send + Sync>)
+        }))
+    }
+
+    pub(crate) fn get(&self, index: usize) -> Option<Box<dyn Any + Send + Sync>> {
+        (self.0)(index)
+    }
+}
+
+impl Debug for InitData {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("InitData").finish()
+    }
+}
+
 impl ContextState {
     pub(crate) fn new() -> Self {
         ContextState {
             messages: SegQueue::new(),
+            skipped: Mutex::new(VecDeque::new()),
+            stash: Mutex::new(Vec::new()),
+            processed: AtomicU64::new(0),
+            last_message_at: Mutex::new(None),
+            handling: Mutex::new(None),
+            profile: None,
+            backlog: SegQueue::new(),
+            drain: Mutex::new(Drain::default()),
+            ack: Mutex::new(None),
+            stopping: AtomicBool::new(false),
+            stop_wakers: Mutex::new(Vec::new()),
+            schedules: Mutex::new(Vec::new()),
+            topics: Mutex::new(Vec::new()),
+            result: Mutex::new(None),
+            observer: None,
+            polling_since: Mutex::new(None),
+            concurrency_limit: None,
+            holds_permit: AtomicBool::new(false),
+            mailbox: Arc::new(MailboxLimit::unbounded()),
+            blocked_on: Mutex::new(None),
+            #[cfg(feature = "tracing")]
+            span: MessageSpan::default(),
+            local: Mutex::new(None),
+            recovering: AtomicBool::new(false),
+            index: 0,
+            init_data: None,
+            persistence: None,
             #[cfg(feature = "scaling")]
             stats: Arc::new(AtomicU64::new(0)),
             #[cfg(feature = "scaling")]
@@ -784,37 +3019,656 @@ impl ContextState {
         }
     }
 
-    #[cfg(feature = "scaling")]
-    pub(crate) fn set_stats(&mut self, stats: Arc<AtomicU64>) {
-        self.stats = stats;
+    #[cfg(feature = "scaling")]
+    pub(crate) fn set_stats(&mut self, stats: Arc<AtomicU64>) {
+        self.stats = stats;
+    }
+
+    #[cfg(feature = "scaling")]
+    pub(crate) fn set_actor_stats(&mut self, actor_stats: Arc<LOTable<BastionId, u32>>) {
+        self.actor_stats = actor_stats;
+    }
+
+    #[cfg(feature = "scaling")]
+    pub(crate) fn stats(&self) -> Arc<AtomicU64> {
+        self.stats.clone()
+    }
+
+    #[cfg(feature = "scaling")]
+    pub(crate) fn actor_stats(&self) -> Arc<LOTable<BastionId, u32>> {
+        self.actor_stats.clone()
+    }
+
+    pub(crate) fn stop(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        self.unblock();
+
+        let stop_wakers: Vec<_> = self.stop_wakers.lock().unwrap().drain(..).collect();
+        for waker in stop_wakers {
+            waker.wake();
+        }
+        self.cancel_schedules();
+    }
+
+    pub(crate) fn add_schedule(&self, handle: ScheduleHandle) {
+        let mut schedules = self.schedules.lock().unwrap();
+        schedules.retain(|handle| !handle.is_cancelled());
+        schedules.push(handle);
+    }
+
+    pub(crate) fn add_topic(&self, topic: Distributor) {
+        let mut topics = self.topics.lock().unwrap();
+        if !topics.contains(&topic) {
+            topics.push(topic);
+        }
+    }
+
+    pub(crate) fn remove_topic(&self, topic: Distributor) {
+        self.topics
+            .lock()
+            .unwrap()
+            .retain(|subscribed| *subscribed != topic);
+    }
+
+    /// Returns the distributors of the topics the child subscribed
+    /// to, forgetting them.
+    pub(crate) fn take_topics(&self) -> Vec<Distributor> {
+        self.topics.lock().unwrap().drain(..).collect()
+    }
+
+    /// Cancels the messages scheduled by the child.
+    pub(crate) fn cancel_schedules(&self) {
+        for handle in self.schedules.lock().unwrap().drain(..) {
+            handle.cancel();
+        }
+    }
+
+    /// Wakes the given waker up once the child is stopped.
+    pub(crate) fn wake_on_stop(&self, waker: &Waker) {
+        let mut stop_wakers = self.stop_wakers.lock().unwrap();
+        if !stop_wakers
+            .iter()
+            .any(|stop_waker| stop_waker.will_wake(waker))
+        {
+            stop_wakers.push(waker.clone());
+        }
+    }
+
+    pub(crate) fn is_stopping(&self) -> bool {
+        self.stopping.load(Ordering::SeqCst)
+    }
+
+    /// Marks the start of a poll of the child's future.
+    pub(crate) fn start_polling(&self) {
+        *self.polling_since.lock().unwrap() = Some(time::now());
+    }
+
+    /// Marks the child as responsive, its future having yielded
+    /// back to it.
+    pub(crate) fn check_in(&self) {
+        let polling_since = self.polling_since.lock().unwrap().take();
+        if let (Some(polling_since), Some(handling)) =
+            (polling_since, &mut *self.handling.lock().unwrap())
+        {
+            handling.busy += time::since(handling.since.max(polling_since));
+        }
+    }
+
+    /// Returns for how long the child has been polling its future
+    /// without yielding, if it is currently polling it.
+    pub(crate) fn unresponsive_for(&self) -> Option<Duration> {
+        self.polling_since.lock().unwrap().map(time::since)
+    }
+
+    pub(crate) fn push_message(&self, mut msg: Msg, sign: RefAddr) {
+        let received_at = time::now();
+        msg.set_enqueued_at(received_at);
+        // The room reserved for the message is released once it is
+        // counted in the length of the mailbox.
+        let _reservation = msg.take_reservation();
+        let msg = SignedMessage::new(msg, sign);
+        if self.mailbox.is_full() {
+            match self.mailbox.policy() {
+                OverflowPolicy::DropOldest => {
+                    let oldest = self.skipped.lock().unwrap().pop_front().or_else(|| {
+                        self.backlog
+                            .pop()
+                            .or_else(|| self.messages.pop())
+                            .map(|entry| entry.msg)
+                    });
+                    if let Some(oldest) = oldest {
+                        trace!("ContextState: Mailbox full, dropping oldest message.");
+                        self.drop_message(oldest);
+                    }
+                }
+                OverflowPolicy::DropNewest
+                | OverflowPolicy::FailSender
+                | OverflowPolicy::Backpressure => {
+                    trace!("ContextState: Mailbox full, dropping message: {:?}", msg);
+                    self.drop_message(msg);
+                    return;
+                }
+            }
+        }
+
+        self.observe(MailboxEventKind::Enqueued, &msg);
+        self.messages.push(MailboxEntry { msg, received_at });
+        self.mailbox.set_len(self.mailbox_len());
+        self.unblock();
+    }
+
+    pub(crate) fn stash(&self, mut msg: SignedMessage) {
+        if let Some(ack) = self.take_ack() {
+            msg.msg.set_ack(ack);
+        }
+        self.stash.lock().unwrap().push(msg);
+    }
+
+    /// Moves the stashed messages to the front of the mailbox.
+    pub(crate) fn unstash_all(&self) -> usize {
+        let stashed: Vec<_> = self.stash.lock().unwrap().drain(..).collect();
+        let unstashed = stashed.len();
+        {
+            let mut skipped = self.skipped.lock().unwrap();
+            for msg in stashed.into_iter().rev() {
+                skipped.push_front(msg);
+            }
+        }
+
+        self.mailbox.set_len(self.mailbox_len());
+        unstashed
+    }
+
+    pub(crate) fn set_index(&mut self, index: usize) {
+        self.index = index;
+    }
+
+    pub(crate) fn index(&self) -> usize {
+        self.index
+    }
+
+    pub(crate) fn set_init_data(&mut self, init_data: Option<Box<dyn Any + Send + Sync>>) {
+        self.init_data = init_data;
+    }
+
+    pub(crate) fn set_persistence(&mut self, persistence: Persistence) {
+        self.persistence = Some(persistence);
+    }
+
+    pub(crate) fn set_local(&self, local: Box<dyn Any + Send>) {
+        *self.local.lock().unwrap() = Some(local);
+        self.recovering.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns whether the events of the child have to be replayed
+    /// to its state before it starts.
+    pub(crate) fn needs_recovery(&self) -> bool {
+        self.recovering.load(Ordering::SeqCst)
+            && self
+                .persistence
+                .as_ref()
+                .map_or(false, |persistence| persistence.recovery.is_some())
+    }
+
+    /// Replays the events of the child to its state through the
+    /// recovery handler of its group, if it wasn't done since the
+    /// state was created, returning the sequence number of the last
+    /// one.
+    pub(crate) fn recover_local(&self) -> AnyResult<u64> {
+        if !self.recovering.swap(false, Ordering::SeqCst) {
+            return Ok(0);
+        }
+
+        let persistence = match &self.persistence {
+            Some(persistence) => persistence,
+            None => return Ok(0),
+        };
+        // FIXME: panics?
+        match &mut *self.local.lock().unwrap() {
+            Some(local) => persistence.recover(&mut **local),
+            None => Ok(0),
+        }
+    }
+
+    pub(crate) fn has_local(&self) -> bool {
+        self.local.lock().unwrap().is_some()
+    }
+
+    /// Drops the state of the child, if any.
+    pub(crate) fn clear_local(&self) {
+        self.local.lock().unwrap().take();
+    }
+
+    pub(crate) fn set_mailbox_limit(&mut self, mailbox: Arc<MailboxLimit>) {
+        self.mailbox = mailbox;
+    }
+
+    pub(crate) fn mailbox(&self) -> Arc<MailboxLimit> {
+        self.mailbox.clone()
+    }
+
+    /// Registers the current thread to be unparked once a message
+    /// is pushed to the child's mailbox or the child is stopped.
+    pub(crate) fn block_on_mailbox(&self) {
+        *self.blocked_on.lock().unwrap() = Some(thread::current());
+    }
+
+    /// Unregisters the thread registered by `block_on_mailbox`.
+    pub(crate) fn unblock_mailbox(&self) {
+        self.blocked_on.lock().unwrap().take();
+    }
+
+    fn unblock(&self) {
+        if let Some(thread) = &*self.blocked_on.lock().unwrap() {
+            thread.unpark();
+        }
+    }
+
+    /// Returns the amount of messages waiting in the child's mailbox.
+    pub(crate) fn mailbox_len(&self) -> usize {
+        self.messages.len() + self.backlog.len() + self.skipped.lock().unwrap().len()
+    }
+
+    fn has_messages(&self) -> bool {
+        !self.messages.is_empty()
+            || !self.backlog.is_empty()
+            || !self.skipped.lock().unwrap().is_empty()
+    }
+
+    pub(crate) fn set_observer(&mut self, id: BastionId, observer: MessageObserver) {
+        self.observer = Some((id, observer));
+    }
+
+    pub(crate) fn set_profile(&mut self, profile: Arc<GroupProfile>) {
+        self.profile = Some(profile);
+    }
+
+    pub(crate) fn set_concurrency_limit(&mut self, limit: Arc<ConcurrencyLimit>) {
+        self.concurrency_limit = Some(limit);
+    }
+
+    pub(crate) fn pop_message(&self) -> Option<SignedMessage> {
+        self.pop_message_where(&|_| true)
+    }
+
+    /// Pops the first message of the mailbox matching the given
+    /// predicate, keeping the messages before it in the mailbox.
+    pub(crate) fn pop_message_where(
+        &self,
+        matches: &dyn Fn(&SignedMessage) -> bool,
+    ) -> Option<SignedMessage> {
+        // The child is done with the message it received before.
+        self.finish_handling();
+        let msg = self.pop_limited(matches);
+        self.mailbox.set_len(self.mailbox_len());
+        msg
+    }
+
+    fn pop_limited(&self, matches: &dyn Fn(&SignedMessage) -> bool) -> Option<SignedMessage> {
+        let limit = match &self.concurrency_limit {
+            Some(limit) => limit,
+            None => return self.pop_next(matches),
+        };
+
+        // The child is done with the message it received before.
+        self.release_permit();
+        if !self.has_messages() {
+            return None;
+        }
+
+        // A child being stopped can finish its in-flight work
+        // whatever the limit.
+        if self.is_stopping() {
+            return self.pop_next(matches);
+        }
+
+        if !limit.try_acquire() {
+            trace!("ContextState: Waiting for a permit to handle a message.");
+            return None;
+        }
+        match self.pop_next(matches) {
+            Some(msg) => {
+                self.holds_permit.store(true, Ordering::SeqCst);
+                Some(msg)
+            }
+            None => {
+                limit.release();
+                None
+            }
+        }
+    }
+
+    /// Gives back the permit of the group's concurrency limit held
+    /// by the child, if any.
+    pub(crate) fn release_permit(&self) {
+        if let Some(limit) = &self.concurrency_limit {
+            if self.holds_permit.swap(false, Ordering::SeqCst) {
+                limit.release();
+            }
+        }
+    }
+
+    /// Wakes the child up once a permit of the group's concurrency
+    /// limit is released, if it is waiting for one to handle the
+    /// messages in its mailbox.
+    pub(crate) fn park(&self, waker: &Waker) {
+        // The inherited messages left once the drain budget of this
+        // tick is spent are received during the next tick, which
+        // would otherwise wait for a new message.
+        if self.drain_budget_spent() {
+            waker.wake_by_ref();
+        }
+        if let Some(limit) = &self.concurrency_limit {
+            if self.has_messages() {
+                limit.park(waker);
+            }
+        }
+    }
+
+    fn pop_next(&self, matches: &dyn Fn(&SignedMessage) -> bool) -> Option<SignedMessage> {
+        let mut skipped = self.skipped.lock().unwrap();
+        let mut index = 0;
+        while index < skipped.len() {
+            if skipped[index].msg.is_expired() {
+                let msg = skipped.remove(index).unwrap();
+                trace!("ContextState: Dropping expired message: {:?}", msg);
+                self.drop_message(msg);
+            } else if matches(&skipped[index]) {
+                return skipped.remove(index).map(|msg| self.received(msg));
+            } else {
+                index += 1;
+            }
+        }
+
+        loop {
+            let msg = if self.backlog.is_empty() {
+                self.messages.pop()?.msg
+            } else {
+                self.pop_draining()?
+            };
+
+            if msg.msg.is_expired() {
+                trace!("ContextState: Dropping expired message: {:?}", msg);
+                self.drop_message(msg);
+                continue;
+            }
+
+            if !matches(&msg) {
+                trace!("ContextState: Skipping message: {:?}", msg);
+                skipped.push_back(msg);
+                continue;
+            }
+
+            return Some(self.received(msg));
+        }
+    }
+
+    fn received(&self, mut msg: SignedMessage) -> SignedMessage {
+        self.observe(MailboxEventKind::Dequeued, &msg);
+        // Only the last received message can be acknowledged.
+        *self.ack.lock().unwrap() = msg.msg.take_ack();
+        self.processed.fetch_add(1, Ordering::SeqCst);
+        system::message_received();
+        system::log_message("received", msg.msg.type_name(), msg.correlation_id());
+        #[cfg(feature = "tracing")]
+        self.span.received(&msg);
+        let now = time::now();
+        *self.last_message_at.lock().unwrap() = Some(now);
+        *self.handling.lock().unwrap() = Some(Handling {
+            type_name: msg.msg.type_name(),
+            correlation_id: msg.correlation_id(),
+            since: now,
+            busy: Duration::default(),
+            reported: false,
+            #[cfg(feature = "remote")]
+            message: msg.msg.peek::<WireMessage>().cloned(),
+        });
+        msg
+    }
+
+    #[cfg(feature = "remote")]
+    /// Returns the message the child was handling when it failed, if
+    /// it is a [`WireMessage`].
+    pub(crate) fn take_poison_message(&self) -> Option<WireMessage> {
+        self.handling.lock().unwrap().take()?.message
+    }
+
+    fn finish_handling(&self) {
+        let handling = match self.handling.lock().unwrap().take() {
+            Some(handling) => handling,
+            None => return,
+        };
+
+        if let Some(profile) = &self.profile {
+            let mut busy = handling.busy;
+            // The child is polling its future since its last check in.
+            if let Some(polling_since) = *self.polling_since.lock().unwrap() {
+                busy += time::since(handling.since.max(polling_since));
+            }
+            profile.record(handling.type_name, time::since(handling.since), busy);
+        }
+    }
+
+    /// Returns the type name and correlation id of the message the
+    /// child has been handling for longer than `threshold`, and for
+    /// how long, unless it was already returned by a previous call.
+    pub(crate) fn slow_handling(
+        &self,
+        threshold: Duration,
+    ) -> Option<(&'static str, CorrelationId, Duration)> {
+        let mut handling = self.handling.lock().unwrap();
+        let handling = handling.as_mut()?;
+        let elapsed = time::since(handling.since);
+        if handling.reported || elapsed <= threshold {
+            return None;
+        }
+
+        handling.reported = true;
+        Some((handling.type_name, handling.correlation_id, elapsed))
+    }
+
+    pub(crate) fn messages_processed(&self) -> u64 {
+        self.processed.load(Ordering::SeqCst)
+    }
+
+    #[cfg(feature = "tracing")]
+    pub(crate) fn message_span(&self) -> &MessageSpan {
+        &self.span
+    }
+
+    pub(crate) fn last_message_at(&self) -> Option<Instant> {
+        *self.last_message_at.lock().unwrap()
+    }
+
+    // Sends a message that won't be received to the dead letters.
+    fn drop_message(&self, msg: SignedMessage) {
+        self.observe(MailboxEventKind::Dropped, &msg);
+        Self::send_to_dead_letters(msg);
+    }
+
+    fn observe(&self, kind: MailboxEventKind, msg: &SignedMessage) {
+        if let Some((id, observer)) = &self.observer {
+            observer.notify(kind, id, msg);
+        }
+    }
+
+    pub(crate) fn send_to_dead_letters(msg: SignedMessage) {
+        let (mut msg, sign) = msg.split();
+        // The dead letters must receive it, whatever its age.
+        msg.clear_ttl();
+        let env = Envelope::new_with_sign(BastionMessage::Message(msg), sign);
+        system::current().dead_letters().send(env).ok();
+    }
+
+    /// Routes the messages that are currently in the mailbox to the
+    /// dead letters.
+    pub(crate) fn discard_mailbox(&self) {
+        let mut discarded = 0;
+        let stashed: Vec<_> = self.stash.lock().unwrap().drain(..).collect();
+        for msg in stashed {
+            self.drop_message(msg);
+            discarded += 1;
+        }
+        for msg in self.skipped.lock().unwrap().drain(..) {
+            self.drop_message(msg);
+            discarded += 1;
+        }
+        while let Some(entry) = self.backlog.pop().or_else(|| self.messages.pop()) {
+            self.drop_message(entry.msg);
+            discarded += 1;
+        }
+
+        debug!("ContextState: Discarded {} inherited messages.", discarded);
+        self.mailbox.set_len(self.mailbox_len());
+    }
+
+    /// Moves the messages that are currently in the mailbox to the
+    /// backlog, which will then be drained following the given policy
+    /// while new messages keep being received.
+    pub(crate) fn start_draining(&self, policy: DrainPolicy) {
+        if let DrainPolicy::Unbounded = policy {
+            return;
+        }
+
+        while let Some(entry) = self.messages.pop() {
+            self.backlog.push(entry);
+        }
+
+        debug!(
+            "ContextState: Draining {} inherited messages with policy {:?}.",
+            self.backlog.len(),
+            policy
+        );
+        *self.drain.lock().unwrap() = Drain {
+            policy,
+            ..Drain::default()
+        };
+    }
+
+    /// Marks the start of a new tick of the child owning this state.
+    pub(crate) fn tick(&self) {
+        if !self.backlog.is_empty() {
+            self.drain.lock().unwrap().drained = 0;
+        }
+    }
+
+    // Whether inherited messages are left while the drain policy
+    // doesn't allow receiving more of them during this tick.
+    fn drain_budget_spent(&self) -> bool {
+        if self.backlog.is_empty() {
+            return false;
+        }
+
+        let drain = self.drain.lock().unwrap();
+        matches!(drain.policy, DrainPolicy::PerTick(limit) if drain.drained >= limit)
+    }
+
+    fn pop_draining(&self) -> Option<SignedMessage> {
+        let mut drain = self.drain.lock().unwrap();
+        let entry = match drain.policy {
+            DrainPolicy::Unbounded => self.backlog.pop().or_else(|| self.messages.pop()),
+            DrainPolicy::PerTick(limit) => {
+                if drain.drained < limit {
+                    drain.drained += 1;
+                    self.backlog.pop()
+                } else {
+                    // New traffic can still be handled during this tick.
+                    self.messages.pop()
+                }
+            }
+            DrainPolicy::Interleave => {
+                drain.from_backlog = !drain.from_backlog;
+                if drain.from_backlog {
+                    self.backlog.pop().or_else(|| self.messages.pop())
+                } else {
+                    self.messages.pop().or_else(|| self.backlog.pop())
+                }
+            }
+            DrainPolicy::Ttl(ttl) => {
+                let mut fresh = None;
+                while let Some(entry) = self.backlog.pop() {
+                    if time::since(entry.received_at) <= ttl {
+                        fresh = Some(entry);
+                        break;
+                    }
+
+                    trace!(
+                        "ContextState: Dropping stale inherited message: {:?}",
+                        entry.msg
+                    );
+                    self.drop_message(entry.msg);
+                }
+                fresh.or_else(|| self.messages.pop())
+            }
+        };
+
+        entry.map(|entry| entry.msg)
+    }
+
+    pub(crate) fn take_ack(&self) -> Option<AckSender> {
+        self.ack.lock().unwrap().take()
+    }
+
+    pub(crate) fn set_result(&self, result: ChildResult) {
+        *self.result.lock().unwrap() = Some(result);
     }
 
-    #[cfg(feature = "scaling")]
-    pub(crate) fn set_actor_stats(&mut self, actor_stats: Arc<LOTable<BastionId, u32>>) {
-        self.actor_stats = actor_stats;
+    pub(crate) fn has_result(&self) -> bool {
+        self.result.lock().unwrap().is_some()
     }
 
-    #[cfg(feature = "scaling")]
-    pub(crate) fn stats(&self) -> Arc<AtomicU64> {
-        self.stats.clone()
+    pub(crate) fn take_result(&self) -> Option<ChildResult> {
+        self.result.lock().unwrap().take()
     }
 
     #[cfg(feature = "scaling")]
-    pub(crate) fn actor_stats(&self) -> Arc<LOTable<BastionId, u32>> {
-        self.actor_stats.clone()
+    pub(crate) fn mailbox_size(&self) -> u32 {
+        self.mailbox_len() as _
     }
+}
 
-    pub(crate) fn push_message(&self, msg: Msg, sign: RefAddr) {
-        self.messages.push(SignedMessage::new(msg, sign))
+impl ScheduleHandle {
+    fn new() -> Self {
+        ScheduleHandle {
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
     }
 
-    pub(crate) fn pop_message(&self) -> Option<SignedMessage> {
-        self.messages.pop()
+    /// Cancels the scheduled messages that weren't delivered yet.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
     }
 
-    #[cfg(feature = "scaling")]
-    pub(crate) fn mailbox_size(&self) -> u32 {
-        self.messages.len() as _
+    /// Returns whether the scheduled messages were cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+impl<R> JoinHandle<R> {
+    /// Cancels the task if it didn't start running yet.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+}
+
+impl<R> Future for JoinHandle<R> {
+    type Output = Result<R, ()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        match Pin::new(&mut self.handle).poll(cx) {
+            Poll::Ready(Some(Some(result))) => Poll::Ready(Ok(result)),
+            Poll::Ready(_) => Poll::Ready(Err(())),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<R> Debug for JoinHandle<R> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("JoinHandle")
+            .field("cancel", &self.cancel)
+            .finish()
     }
 }
 
@@ -856,6 +3710,22 @@ mod context_tests {
         test_try_recv_fail();
         test_try_recv_timeout();
         test_try_recv_timeout_fail();
+        test_recv_timeout_housekeeping();
+        test_recv_where();
+        test_stash();
+        test_mailbox_stats();
+        test_behaviors();
+        test_typed_context();
+        test_on_shutdown();
+        test_schedule();
+        test_group_metadata();
+        test_stop_with();
+        test_attach_stream();
+        test_request();
+        test_sender_identity();
+        test_spawn_blocking();
+        test_subscribe();
+        test_persistence();
     }
 
     fn test_recv() {
@@ -948,4 +3818,625 @@ mod context_tests {
         // The child panicked, but we should still be able to send things to it
         children.broadcast("test recv timeout").unwrap();
     }
+
+    fn test_recv_where() {
+        let children = Bastion::children(|children| {
+            children.with_exec(|ctx: BastionContext| async move {
+                msg! { ctx.recv_match::<u32>().await?,
+                    ref msg: u32 => {
+                        assert_eq!(*msg, 42);
+                    };
+                    _: _ => { panic!("didn't receive the expected message");};
+                }
+                // The skipped message is still in the mailbox.
+                msg! { ctx.recv().await?,
+                    ref msg: &'static str => {
+                        assert_eq!(msg, &"test recv where");
+                    };
+                    _: _ => { panic!("didn't receive the skipped message");};
+                }
+                Ok(())
+            })
+        })
+        .expect("Couldn't create the children group.");
+
+        children
+            .broadcast("test recv where")
+            .expect("couldn't send message");
+        children.broadcast(42u32).expect("couldn't send message");
+    }
+
+    fn test_stash() {
+        let children = Bastion::children(|children| {
+            children.with_exec(|ctx: BastionContext| async move {
+                let msg = ctx.recv().await?;
+                assert!(msg.is::<u32>());
+                ctx.stash(msg);
+
+                msg! { ctx.recv().await?,
+                    ref msg: &'static str => {
+                        assert_eq!(msg, &"test stash");
+                    };
+                    _: _ => { panic!("didn't receive the expected message");};
+                }
+
+                assert_eq!(ctx.unstash_all(), 1);
+                msg! { ctx.recv().await?,
+                    ref msg: u32 => {
+                        assert_eq!(*msg, 42);
+                    };
+                    _: _ => { panic!("didn't receive the stashed message");};
+                }
+                Ok(())
+            })
+        })
+        .expect("Couldn't create the children group.");
+
+        children.broadcast(42u32).expect("couldn't send message");
+        children
+            .broadcast("test stash")
+            .expect("couldn't send message");
+    }
+
+    fn test_mailbox_stats() {
+        let children = Bastion::children(|children| {
+            children.with_exec(|ctx: BastionContext| async move {
+                assert_eq!(ctx.messages_processed(), 0);
+                assert!(ctx.last_message_at().is_none());
+
+                ctx.recv().await?;
+                assert_eq!(ctx.messages_processed(), 1);
+                assert!(ctx.last_message_at().is_some());
+                Ok(())
+            })
+        })
+        .expect("Couldn't create the children group.");
+
+        children
+            .broadcast("test mailbox stats")
+            .expect("couldn't send message");
+    }
+
+    fn test_behaviors() {
+        fn counting(count: Arc<AtomicU64>) -> Behavior {
+            Behavior::new(move |ctx, msg| {
+                if msg.is::<&'static str>() {
+                    ctx.unbecome();
+                } else {
+                    count.fetch_add(1, Ordering::SeqCst);
+                }
+                Ok(())
+            })
+        }
+
+        let counted = Arc::new(AtomicU64::new(0));
+        let children = Bastion::children(|children| {
+            let counted = counted.clone();
+            children.with_exec(move |ctx: BastionContext| {
+                let counted = counted.clone();
+                async move {
+                    ctx.become_(Behavior::new(|_, _| Err(())));
+                    ctx.become_(counting(counted.clone()));
+                    // Handled by `counting`, and then by the behavior
+                    // faulting.
+                    assert!(ctx.run_behaviors().await.is_err());
+                    assert_eq!(counted.load(Ordering::SeqCst), 1);
+
+                    assert!(ctx.unbecome());
+                    assert!(!ctx.unbecome());
+                    ctx.run_behaviors().await
+                }
+            })
+        })
+        .expect("Couldn't create the children group.");
+
+        children.broadcast(42u32).expect("couldn't send message");
+        children
+            .broadcast("test behaviors")
+            .expect("couldn't send message");
+        children.broadcast(42u32).expect("couldn't send message");
+    }
+
+    fn test_typed_context() {
+        let children = Bastion::children(|children| {
+            children.with_typed_exec(|ctx: TypedContext<u32>| async move {
+                // The message of another type is skipped.
+                assert_eq!(ctx.recv().await?, 42);
+                Ok(())
+            })
+        })
+        .expect("Couldn't create the children group.");
+
+        let child = TypedChildRef::<u32>::new(children.elems()[0].clone());
+        children
+            .broadcast("test typed context")
+            .expect("couldn't send message");
+        child.tell(42).expect("couldn't send message");
+    }
+
+    fn test_on_shutdown() {
+        let children = Bastion::children(|children| {
+            children
+                .with_stop_timeout(Duration::from_secs(1))
+                .with_exec(|ctx: BastionContext| async move {
+                    assert!(!ctx.shutdown_requested());
+                    ctx.on_shutdown().await;
+                    assert!(ctx.shutdown_requested());
+                    Ok(())
+                })
+        })
+        .expect("Couldn't create the children group.");
+
+        children.stop().expect("couldn't stop the children group");
+    }
+
+    fn test_schedule() {
+        Bastion::children(|children| {
+            children.with_exec(|ctx: BastionContext| async move {
+                let cancelled = ctx.schedule(Duration::from_millis(1), 0_u32);
+                cancelled.cancel();
+                let handle = ctx.schedule_repeated(Duration::from_millis(1), 42_u32);
+
+                for _ in 0..2 {
+                    msg! { ctx.recv().await?,
+                        msg: u32 => {
+                            assert_eq!(msg, 42);
+                        };
+                        _: _ => { panic!("didn't receive the scheduled message");};
+                    }
+                }
+                handle.cancel();
+                Ok(())
+            })
+        })
+        .expect("Couldn't create the children group.");
+    }
+
+    fn test_subscribe() {
+        Bastion::children(|children| {
+            children.with_exec(|ctx: BastionContext| async move {
+                let topic = Topic::named("test-subscribe");
+                ctx.subscribe(&topic).unwrap();
+                topic.publish("published").unwrap();
+                msg! { ctx.recv().await?,
+                    msg: &'static str => {
+                        assert_eq!(msg, "published");
+                    };
+                    _: _ => { panic!("didn't receive the published message");};
+                }
+                ctx.unsubscribe(&topic).unwrap();
+                Ok(())
+            })
+        })
+        .expect("Couldn't create the children group.");
+    }
+
+    fn test_persistence() {
+        use std::sync::atomic::AtomicUsize;
+        use std::sync::mpsc;
+
+        let journal = Arc::new(InMemoryJournal::default());
+        let (sender, received) = mpsc::channel();
+        let sender = Mutex::new(sender);
+        let runs = Arc::new(AtomicUsize::new(0));
+        Bastion::children(move |children| {
+            children
+                .with_persistence("test-persistence", journal)
+                .with_state(|| 0u64)
+                .with_recovery(|total: &mut u64, n: u64| *total += n)
+                .with_exec(move |ctx: BastionContext| {
+                    let run = runs.fetch_add(1, Ordering::SeqCst);
+                    let sender = sender.lock().unwrap().clone();
+                    async move {
+                        let id = ctx.persistence_id().map(ToString::to_string);
+                        let total = *ctx.state::<u64>().unwrap();
+                        let last = ctx.recover(|_: u64| ()).await.map_err(|_| ())?;
+                        sender.send((run, id, total, last)).ok();
+                        if run > 0 {
+                            return Ok(());
+                        }
+
+                        let first = ctx.persist(&1u64).await.map_err(|_| ())?;
+                        let second = ctx.persist(&2u64).await.map_err(|_| ())?;
+                        sender.send((run, None, first, second)).ok();
+                        // The element is restarted with a new state.
+                        Err(())
+                    }
+                })
+        })
+        .expect("Couldn't create the children group.");
+
+        let recv = || {
+            received
+                .recv_timeout(Duration::from_secs(5))
+                .expect("The element didn't run.")
+        };
+        let id = Some("test-persistence".to_string());
+        assert_eq!(recv(), (0, id.clone(), 0, 0));
+        assert_eq!(recv(), (0, None, 1, 2));
+        // The events were replayed to the new state before the
+        // element started again.
+        assert_eq!(recv(), (1, id, 3, 2));
+    }
+
+    fn test_group_metadata() {
+        Bastion::supervisor(|sp| {
+            sp.with_name("metadata").children(|children| {
+                children.with_name("siblings").with_redundancy(2).with_exec(
+                    |ctx: BastionContext| async move {
+                        assert_eq!(ctx.group_name(), "siblings");
+                        assert_eq!(ctx.group().id(), ctx.parent().id());
+                        assert_eq!(ctx.group().elems().len(), 2);
+                        assert!(ctx.supervisor().is_some());
+                        Ok(())
+                    },
+                )
+            })
+        })
+        .expect("Couldn't create the supervisor.");
+    }
+
+    fn test_stop_with() {
+        let children = Bastion::children(|children| {
+            children
+                .with_redundancy(3)
+                .with_exec(|ctx: BastionContext| async move {
+                    if ctx.elem_index() == 0 {
+                        return Ok(());
+                    }
+                    ctx.stop_with(ctx.elem_index())?;
+                    // The future isn't polled anymore once it yields.
+                    future::pending::<()>().await;
+                    unreachable!()
+                })
+        })
+        .expect("Couldn't create the children group.");
+
+        let mut results = run!(children.join_all::<usize>());
+        results.sort();
+        assert_eq!(results, vec![None, Some(1), Some(2)]);
+    }
+
+    fn test_attach_stream() {
+        Bastion::children(|children| {
+            children
+                .with_mailbox_capacity(1)
+                .with_overflow_policy(OverflowPolicy::Backpressure)
+                .with_exec(|ctx: BastionContext| async move {
+                    ctx.attach_stream(futures::stream::iter(0..3_u32));
+
+                    for expected in 0..3 {
+                        msg! { ctx.recv().await?,
+                            item: u32 => {
+                                assert_eq!(item, expected);
+                            };
+                            _: _ => { panic!("didn't receive the stream's item");};
+                        }
+                    }
+                    Ok(())
+                })
+        })
+        .expect("Couldn't create the children group.");
+    }
+
+    fn test_request() {
+        let responder = Bastion::children(|children| {
+            children.with_exec(|ctx: BastionContext| async move {
+                loop {
+                    MessageHandler::new(ctx.recv().await?)
+                        .on_request(|name: &'static str, sender: QuestionSender<usize>| {
+                            sender.reply(name.len()).unwrap();
+                        })
+                        .on_fallback(|_, _| panic!("didn't receive the request"));
+                }
+            })
+        })
+        .expect("Couldn't create the children group.");
+
+        Bastion::children(|children| {
+            children.with_exec(move |ctx: BastionContext| {
+                let responder = responder.elems()[0].clone();
+                async move {
+                    let len: usize = ctx.request(&responder, "request").unwrap().await?;
+                    assert_eq!(len, 7);
+                    Ok(())
+                }
+            })
+        })
+        .expect("Couldn't create the children group.");
+    }
+
+    fn test_sender_identity() {
+        let responder = Bastion::children(|children| {
+            children.with_exec(|ctx: BastionContext| async move {
+                loop {
+                    MessageHandler::new(ctx.recv().await?)
+                        .on_question(|_: &'static str, sender| {
+                            let asker = sender.asker();
+                            let identity = (
+                                asker.child_id().cloned(),
+                                asker.group_id().cloned(),
+                                asker.group_name().map(str::to_string),
+                            );
+                            sender.reply(identity).unwrap();
+                        })
+                        .on_fallback(|_, _| panic!("didn't receive the question"));
+                }
+            })
+        })
+        .expect("Couldn't create the children group.");
+
+        Bastion::children(|children| {
+            children
+                .with_name("asker")
+                .with_exec(move |ctx: BastionContext| {
+                    let responder = responder.elems()[0].clone();
+                    async move {
+                        let (child_id, group_id, group_name) = ctx
+                            .ask(&responder, "who am I?")
+                            .unwrap()
+                            .into_typed::<(Option<BastionId>, Option<BastionId>, Option<String>)>()
+                            .await
+                            .unwrap();
+                        assert_eq!(child_id.as_ref(), Some(ctx.current().id()));
+                        assert_eq!(group_id.as_ref(), Some(ctx.parent().id()));
+                        assert_eq!(group_name.as_deref(), Some("asker"));
+                        Ok(())
+                    }
+                })
+        })
+        .expect("Couldn't create the children group.");
+    }
+
+    fn test_spawn_blocking() {
+        Bastion::children(|children| {
+            children.with_exec(|ctx: BastionContext| async move {
+                assert_eq!(ctx.spawn_blocking(|| 42).await, Ok(42));
+                let panicked = ctx.spawn_blocking(|| -> u32 { panic!("blocking task failed") });
+                assert_eq!(panicked.await, Err(()));
+                Ok(())
+            })
+        })
+        .expect("Couldn't create the children group.");
+    }
+
+    fn test_recv_timeout_housekeeping() {
+        let children = Bastion::children(|children| {
+            children.with_exec(|ctx: BastionContext| async move {
+                let timeout = std::time::Duration::from_millis(1);
+                assert!(matches!(
+                    ctx.recv_timeout(timeout).await,
+                    Err(ReceiveError::Timeout(_))
+                ));
+                // The element keeps receiving messages after a timeout.
+                msg! { ctx.recv().await?,
+                    ref msg: &'static str => {
+                        assert_eq!(msg, &"test recv timeout housekeeping");
+                    };
+                    _: _ => { panic!("didn't receive the expected message");};
+                }
+                Ok(())
+            })
+        })
+        .expect("Couldn't create the children group.");
+
+        // Triggering the timeout
+        run!(async { Delay::new(std::time::Duration::from_millis(2)).await });
+
+        children
+            .broadcast("test recv timeout housekeeping")
+            .expect("couldn't send message");
+    }
+
+    fn test_addr() -> RefAddr {
+        let (sender, _) = futures::channel::mpsc::unbounded();
+        RefAddr::new(Arc::new(BastionPath::root()), sender)
+    }
+
+    fn pop_number(state: &ContextState) -> Option<usize> {
+        state
+            .pop_message()
+            .map(|msg| msg.msg.downcast::<usize>().unwrap())
+    }
+
+    #[test]
+    fn test_drain_per_tick() {
+        let state = ContextState::new();
+        for i in 0..3_usize {
+            state.push_message(Msg::tell(i), test_addr());
+        }
+        state.start_draining(DrainPolicy::PerTick(2));
+        state.push_message(Msg::tell(42_usize), test_addr());
+
+        assert_eq!(pop_number(&state), Some(0));
+        assert_eq!(pop_number(&state), Some(1));
+        // The limit is reached, only new messages can be received.
+        assert_eq!(pop_number(&state), Some(42));
+        assert_eq!(pop_number(&state), None);
+
+        state.tick();
+        assert_eq!(pop_number(&state), Some(2));
+        assert_eq!(pop_number(&state), None);
+    }
+
+    #[test]
+    fn test_drain_per_tick_wakes_up() {
+        use futures::task::{self, ArcWake};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct Wakes(AtomicUsize);
+
+        impl ArcWake for Wakes {
+            fn wake_by_ref(arc_self: &Arc<Self>) {
+                arc_self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let wakes = Arc::new(Wakes(AtomicUsize::new(0)));
+        let waker = task::waker(wakes.clone());
+
+        let state = ContextState::new();
+        for i in 0..2_usize {
+            state.push_message(Msg::tell(i), test_addr());
+        }
+        state.start_draining(DrainPolicy::PerTick(1));
+
+        assert_eq!(pop_number(&state), Some(0));
+        assert_eq!(pop_number(&state), None);
+        // No new message will come, so the element has to wake
+        // itself up to receive the inherited message left.
+        state.park(&waker);
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 1);
+
+        state.tick();
+        assert_eq!(pop_number(&state), Some(1));
+        state.park(&waker);
+        assert_eq!(wakes.0.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_drain_interleave() {
+        let state = ContextState::new();
+        for i in 0..3_usize {
+            state.push_message(Msg::tell(i), test_addr());
+        }
+        state.start_draining(DrainPolicy::Interleave);
+        state.push_message(Msg::tell(42_usize), test_addr());
+
+        assert_eq!(pop_number(&state), Some(0));
+        assert_eq!(pop_number(&state), Some(42));
+        assert_eq!(pop_number(&state), Some(1));
+        assert_eq!(pop_number(&state), Some(2));
+        assert_eq!(pop_number(&state), None);
+    }
+
+    #[test]
+    fn test_expired_messages_are_skipped() {
+        let state = ContextState::new();
+        let expired = Msg::tell(0_usize).with_ttl(Duration::from_millis(0));
+        state.push_message(expired, test_addr());
+        let fresh = Msg::tell(1_usize).with_ttl(Duration::from_secs(60));
+        state.push_message(fresh, test_addr());
+
+        assert_eq!(pop_number(&state), Some(1));
+        assert_eq!(pop_number(&state), None);
+    }
+
+    #[test]
+    fn test_slow_handling() {
+        let state = ContextState::new();
+        state.push_message(Msg::tell(0_usize), test_addr());
+        state.push_message(Msg::tell(1_usize), test_addr());
+        assert_eq!(pop_number(&state), Some(0));
+        assert!(state.slow_handling(Duration::from_secs(60)).is_none());
+
+        std::thread::sleep(Duration::from_millis(5));
+        let (message, _, elapsed) = state.slow_handling(Duration::from_millis(1)).unwrap();
+        assert_eq!(message, std::any::type_name::<usize>());
+        assert!(elapsed >= Duration::from_millis(5));
+        // Each message is only reported once.
+        assert!(state.slow_handling(Duration::from_millis(1)).is_none());
+
+        // Trying to receive another message ends the handling.
+        assert_eq!(pop_number(&state), Some(1));
+        assert_eq!(pop_number(&state), None);
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(state.slow_handling(Duration::from_millis(1)).is_none());
+    }
+
+    #[test]
+    fn test_profiling() {
+        let mut state = ContextState::new();
+        let profile = Arc::new(GroupProfile::default());
+        state.set_profile(profile.clone());
+        state.push_message(Msg::tell(0_usize), test_addr());
+
+        state.start_polling();
+        assert_eq!(pop_number(&state), Some(0));
+        std::thread::sleep(Duration::from_millis(5));
+        state.check_in();
+        // The time spent waiting isn't counted as busy.
+        std::thread::sleep(Duration::from_millis(5));
+        state.start_polling();
+        assert_eq!(pop_number(&state), None);
+        state.check_in();
+
+        let report = profile.report();
+        let message = report.message::<usize>().unwrap();
+        assert_eq!(message.count(), 1);
+        assert!(message.busy() >= Duration::from_millis(5));
+        assert!(message.handling().sum() >= Duration::from_millis(10));
+        assert!(message.busy() < message.handling().sum());
+    }
+
+    #[test]
+    fn test_message_observer() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut state = ContextState::new();
+        let observed = events.clone();
+        let observer = MessageObserver::new(move |event: MailboxEvent| {
+            observed
+                .lock()
+                .unwrap()
+                .push((event.kind(), event.type_name()));
+        });
+        state.set_observer(BastionId::new(), observer);
+
+        let expired = Msg::tell(0_usize).with_ttl(Duration::from_millis(0));
+        state.push_message(expired, test_addr());
+        state.push_message(Msg::tell(1_usize), test_addr());
+        assert_eq!(pop_number(&state), Some(1));
+
+        let usize_name = std::any::type_name::<usize>();
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                (MailboxEventKind::Enqueued, usize_name),
+                (MailboxEventKind::Enqueued, usize_name),
+                (MailboxEventKind::Dropped, usize_name),
+                (MailboxEventKind::Dequeued, usize_name),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_message_targets() {
+        let (sender, mut receiver) = futures::channel::mpsc::unbounded();
+        let path = Arc::new(BastionPath::root());
+        let child = ChildRef::new(BastionId::new(), sender, "test".to_string(), path);
+
+        assert!(child.tell_signed(42_usize, test_addr()).is_ok());
+        let env = receiver.try_next().unwrap().unwrap();
+        assert_eq!(env.into_msg::<usize>(), Some(42));
+
+        let (sender, _) = futures::channel::mpsc::unbounded();
+        let path = Arc::new(BastionPath::root());
+        let supervisor = SupervisorRef::new(BastionId::new(), sender, path);
+        assert_eq!(supervisor.ask_signed(42_usize, test_addr()).err(), Some(42));
+    }
+
+    #[test]
+    fn test_forwarded_messages_keep_reply_to() {
+        let (sender, mut receiver) = futures::channel::mpsc::unbounded();
+        let path = Arc::new(BastionPath::root());
+        let worker = ChildRef::new(BastionId::new(), sender, "worker".to_string(), path);
+
+        let (original, _) = futures::channel::mpsc::unbounded();
+        let original = RefAddr::new(Arc::new(BastionPath::root()), original);
+        let mut msg = Msg::tell(42_usize);
+        msg.set_reply_to(original.clone());
+        let forwarder = test_addr();
+        assert!(worker
+            .forward_signed(SignedMessage::new(msg, forwarder.clone()))
+            .is_ok());
+
+        let msg = receiver
+            .try_next()
+            .unwrap()
+            .unwrap()
+            .into_signed_message()
+            .unwrap();
+        assert!(msg.reply_to().sender().same_receiver(original.sender()));
+        assert!(msg.signature().sender().same_receiver(forwarder.sender()));
+    }
 }
@@ -0,0 +1,355 @@
+//!
+//! The messages sent to or received from other nodes which couldn't
+//! be delivered, returned by [`Bastion::dead_letters`].
+//!
+//! A message told to another node (using a [`RemoteDistributor`],
+//! [`Distributor::tell_everyone`] across the cluster or a
+//! [`Sharding`]) becomes a [`DeadLetter`] on this node when the node
+//! is unknown or can't be reached, and on the node receiving it when
+//! it has no recipient for it. The dead letters keep the serialized
+//! message along with where it was sent, so that they can be
+//! inspected and sent again (see [`DeadLetter::retry`]).
+//!
+//! A message received from another node also becomes a dead letter
+//! when the element handling it fails, and is then not handed to the
+//! element once it is restarted (see
+//! [`Children::with_mailbox_preservation`]).
+//!
+//! Only the last [`DeadLetters::CAPACITY`] dead letters are kept.
+//! Questions aren't dead letters: asking them fails instead.
+//!
+//! This module is only available with the `remote` feature.
+//!
+//! [`Bastion::dead_letters`]: crate::Bastion::dead_letters
+//! [`RemoteDistributor`]: crate::remote::RemoteDistributor
+//! [`Distributor::tell_everyone`]: crate::distributor::Distributor::tell_everyone
+//! [`Sharding`]: crate::sharding::Sharding
+//! [`Children::with_mailbox_preservation`]: crate::children::Children::with_mailbox_preservation
+use crate::context::BastionId;
+use crate::distributor::Distributor;
+use crate::errors::SendError;
+use crate::remote::{self, Frame};
+use crate::sharding;
+use crate::system;
+use crate::wire::WireMessage;
+use once_cell::sync::Lazy;
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tracing::{debug, trace};
+
+static DEAD_LETTERS: Lazy<Mutex<VecDeque<DeadLetter>>> = Lazy::new(Default::default);
+
+#[derive(Debug, Clone, Copy)]
+/// The messages which couldn't be delivered to or by this node,
+/// returned by [`Bastion::dead_letters`].
+///
+/// # Example
+///
+/// ```no_run
+/// # use bastion::prelude::*;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// # Bastion::start();
+/// for dead_letter in Bastion::dead_letters().drain() {
+///     println!(
+///         "{:?} to {:?}: {}",
+///         dead_letter.destination(),
+///         dead_letter.node(),
+///         dead_letter.reason()
+///     );
+///     // Sent again, and back in the dead letters if it fails again.
+///     dead_letter.retry().ok();
+/// }
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+///
+/// [`Bastion::dead_letters`]: crate::Bastion::dead_letters
+pub struct DeadLetters {
+    store: &'static Mutex<VecDeque<DeadLetter>>,
+}
+
+#[derive(Debug, Clone)]
+/// A message which couldn't be delivered, along with where it was
+/// sent.
+pub struct DeadLetter {
+    node: Option<String>,
+    from: Option<SocketAddr>,
+    destination: Destination,
+    message: WireMessage,
+    reason: String,
+    at: SystemTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Where a [`DeadLetter`] was sent, on its node.
+pub enum Destination {
+    /// A recipient of the distributor with the given name (see
+    /// [`RemoteDistributor::tell_one`]).
+    ///
+    /// [`RemoteDistributor::tell_one`]: crate::remote::RemoteDistributor::tell_one
+    Distributor(String),
+    /// Every recipient of the distributor with the given name (see
+    /// [`Distributor::tell_everyone`]).
+    ///
+    /// [`Distributor::tell_everyone`]: crate::distributor::Distributor::tell_everyone
+    Everyone(String),
+    /// An entity of a sharding (see [`Sharding::tell`]).
+    ///
+    /// [`Sharding::tell`]: crate::sharding::Sharding::tell
+    Entity {
+        /// The name of the sharding.
+        sharding: String,
+        /// The identifier of the entity.
+        entity: String,
+    },
+    /// The element of a children group with the given identifier,
+    /// which failed while handling the message. The message isn't
+    /// sent again by [`DeadLetter::retry`], as it would likely make
+    /// the element fail again.
+    Child(BastionId),
+}
+
+impl DeadLetters {
+    /// The maximum number of dead letters kept, after which the
+    /// oldest ones are dropped.
+    pub const CAPACITY: usize = 10_000;
+
+    pub(crate) fn get() -> Self {
+        DeadLetters {
+            store: &DEAD_LETTERS,
+        }
+    }
+
+    /// Returns the number of dead letters kept.
+    pub fn len(&self) -> usize {
+        // FIXME: panics
+        self.store.lock().unwrap().len()
+    }
+
+    /// Returns whether no dead letter is kept.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes and returns the dead letters kept, from the oldest to
+    /// the most recent one.
+    pub fn drain(&self) -> Vec<DeadLetter> {
+        // FIXME: panics
+        self.store.lock().unwrap().drain(..).collect()
+    }
+
+    fn push(&self, dead_letter: DeadLetter) {
+        // FIXME: panics
+        let mut store = self.store.lock().unwrap();
+        if store.len() == Self::CAPACITY {
+            store.pop_front();
+        }
+        store.push_back(dead_letter);
+    }
+}
+
+impl DeadLetter {
+    // A message which couldn't be sent to the node.
+    pub(crate) fn sent(
+        node: impl Into<String>,
+        destination: Destination,
+        message: WireMessage,
+        reason: impl Display,
+    ) -> Self {
+        DeadLetter {
+            node: Some(node.into()),
+            from: None,
+            destination,
+            message,
+            reason: reason.to_string(),
+            at: SystemTime::now(),
+        }
+    }
+
+    // A message received from the node which couldn't be delivered
+    // by this node.
+    pub(crate) fn received(
+        from: SocketAddr,
+        destination: Destination,
+        message: WireMessage,
+        reason: impl Display,
+    ) -> Self {
+        DeadLetter {
+            node: None,
+            from: Some(from),
+            destination,
+            message,
+            reason: reason.to_string(),
+            at: SystemTime::now(),
+        }
+    }
+
+    // A message received from another node, which an element of this
+    // node failed to handle.
+    pub(crate) fn poisoned(
+        destination: Destination,
+        message: WireMessage,
+        reason: impl Display,
+    ) -> Self {
+        DeadLetter {
+            node: None,
+            from: None,
+            destination,
+            message,
+            reason: reason.to_string(),
+            at: SystemTime::now(),
+        }
+    }
+
+    /// Returns the name of the node the message was sent to, or
+    /// `None` if it was sent to this node.
+    pub fn node(&self) -> Option<&str> {
+        self.node.as_deref()
+    }
+
+    /// Returns the address of the node which sent the message, if
+    /// it was sent to this node.
+    pub fn from(&self) -> Option<SocketAddr> {
+        self.from
+    }
+
+    /// Returns where the message was sent, on its node.
+    pub fn destination(&self) -> &Destination {
+        &self.destination
+    }
+
+    /// Returns the message.
+    pub fn message(&self) -> &WireMessage {
+        &self.message
+    }
+
+    /// Returns why the message couldn't be delivered.
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    /// Returns when the message couldn't be delivered.
+    pub fn at(&self) -> SystemTime {
+        self.at
+    }
+
+    /// Sends the message again to its destination, and makes it a
+    /// dead letter again if it couldn't be delivered.
+    pub fn retry(self) -> Result<(), SendError> {
+        trace!("DeadLetters: Retrying {:?}.", self);
+        let sent = match &self.node {
+            Some(node) => remote::send(node, &self.destination.frame(self.message.clone()))
+                .map_err(|err| SendError::Other(err.into())),
+            None => self.destination.deliver(self.message.clone()),
+        };
+
+        sent.map_err(|err| {
+            let mut dead_letter = self;
+            dead_letter.reason = err.to_string();
+            dead_letter.at = SystemTime::now();
+            record(dead_letter);
+            err
+        })
+    }
+}
+
+impl Destination {
+    // Returns the frame sending the message to the destination on
+    // another node.
+    pub(crate) fn frame(&self, message: WireMessage) -> Frame {
+        match self {
+            Destination::Distributor(distributor) => Frame::Tell {
+                distributor: distributor.clone(),
+                message,
+            },
+            Destination::Everyone(distributor) => Frame::TellEveryone {
+                distributor: distributor.clone(),
+                message,
+            },
+            Destination::Entity { sharding, entity } => Frame::Shard {
+                sharding: sharding.clone(),
+                entity: entity.clone(),
+                message,
+            },
+            // The elements are only known by their own node.
+            Destination::Child(_) => unreachable!(),
+        }
+    }
+
+    // Delivers the message to the destination on this node.
+    pub(crate) fn deliver(&self, message: WireMessage) -> Result<(), SendError> {
+        match self {
+            Destination::Distributor(distributor) => {
+                Distributor::named(distributor).tell_one(message)
+            }
+            // Only the local recipients receive the message, which
+            // would otherwise be sent back to the other nodes.
+            Destination::Everyone(distributor) => system::current()
+                .dispatcher()
+                .tell_everyone(Distributor::named(distributor), message)
+                .map(|_| ()),
+            Destination::Entity { sharding, entity } => {
+                sharding::deliver(sharding, entity, message)
+            }
+            Destination::Child(id) => Err(SendError::Other(anyhow::anyhow!(
+                "Child({}) failed while handling the message",
+                id
+            ))),
+        }
+    }
+}
+
+// Keeps the message which couldn't be delivered.
+pub(crate) fn record(dead_letter: DeadLetter) {
+    debug!(
+        "DeadLetters: Couldn't deliver message to {:?} on {:?}: {}",
+        dead_letter.destination, dead_letter.node, dead_letter.reason
+    );
+    DeadLetters::get().push(dead_letter);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oldest_dead_letters_are_dropped() {
+        let store: &'static Mutex<VecDeque<DeadLetter>> = Box::leak(Box::default());
+        let dead_letters = DeadLetters { store };
+        assert!(dead_letters.is_empty());
+
+        for id in 0..DeadLetters::CAPACITY + 2 {
+            dead_letters.push(DeadLetter::sent(
+                "node",
+                Destination::Distributor("workers".to_string()),
+                WireMessage::pack(&id).unwrap(),
+                "Unknown node.",
+            ));
+        }
+        assert_eq!(dead_letters.len(), DeadLetters::CAPACITY);
+
+        let drained = dead_letters.drain();
+        assert!(dead_letters.is_empty());
+        assert_eq!(drained.len(), DeadLetters::CAPACITY);
+        assert_eq!(drained[0].message().unpack::<usize>().unwrap().unwrap(), 2);
+        assert_eq!(drained[0].node(), Some("node"));
+        assert_eq!(drained[0].reason(), "Unknown node.");
+    }
+}
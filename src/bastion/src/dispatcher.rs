@@ -4,11 +4,18 @@
 //! actors grouped together.
 use crate::{
     child_ref::ChildRef,
-    message::{Answer, Message},
+    context::BastionId,
+    message::{Answer, BastionMessage, Message},
     prelude::SendError,
 };
-use crate::{distributor::Distributor, envelope::SignedMessage};
+use crate::{
+    distributor::{Distributor, MembershipEvent},
+    envelope::{Envelope, RefAddr, SignedMessage},
+    events::SystemEvent,
+    system::{self, STRING_INTERNER},
+};
 use anyhow::Result as AnyResult;
+use futures::channel::{mpsc, oneshot};
 use lever::prelude::*;
 use std::hash::{Hash, Hasher};
 use std::sync::RwLock;
@@ -16,6 +23,7 @@ use std::sync::{
     atomic::{AtomicUsize, Ordering},
     Arc,
 };
+use std::time::Duration;
 use std::{
     collections::HashMap,
     fmt::{self, Debug},
@@ -62,6 +70,10 @@ pub trait Recipient {
     fn next(&self) -> Option<ChildRef>;
     /// Return all recipients that will receive a broadcast message
     fn all(&self) -> Vec<ChildRef>;
+    /// Return whether this actor will receive broadcast messages
+    fn contains(&self, actor: &ChildRef) -> bool {
+        self.all().contains(actor)
+    }
     /// Add this actor to your list of recipients
     fn register(&self, actor: ChildRef);
     /// Remove this actor from your list of recipients
@@ -76,6 +88,39 @@ impl RecipientHandler for RoundRobinHandler {}
 /// The default handler, which does round-robin.
 pub type DefaultRecipientHandler = RoundRobinHandler;
 
+/// A `RecipientSelector` decides which of the recipients of a
+/// [`Distributor`] will receive a message sent with `tell_one` or
+/// `ask_one`.
+///
+/// It can be registered per distributor with
+/// [`Distributor::set_selector`], which allows implementing custom
+/// routing (e.g. shard-aware routing based on the message content)
+/// instead of the default round-robin.
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// #[derive(Debug)]
+/// struct FirstRecipient;
+///
+/// impl RecipientSelector for FirstRecipient {
+///     fn select<'a>(&self, recipients: &'a [ChildRef], _envelope: &Envelope) -> Option<&'a ChildRef> {
+///         recipients.first()
+///     }
+/// }
+///
+/// # Bastion::init();
+/// Distributor::named("my distributor")
+///     .set_selector(FirstRecipient)
+///     .expect("couldn't set the selector");
+/// ```
+///
+/// [`Distributor::set_selector`]: crate::distributor::Distributor::set_selector
+pub trait RecipientSelector: Send + Sync + Debug {
+    /// Returns the recipient that will receive the given envelope,
+    /// or `None` if none of them should.
+    fn select<'a>(&self, recipients: &'a [ChildRef], envelope: &Envelope) -> Option<&'a ChildRef>;
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 /// Defines the type of the dispatcher.
 ///
@@ -135,6 +180,10 @@ impl Recipient for RoundRobinHandler {
         self.public_recipients()
     }
 
+    fn contains(&self, actor: &ChildRef) -> bool {
+        actor.is_public() && self.recipients.contains_key(actor)
+    }
+
     fn register(&self, actor: ChildRef) {
         let _ = self.recipients.insert(actor, ());
     }
@@ -186,6 +235,85 @@ impl DispatcherHandler for RoundRobinHandler {
 }
 /// Generic trait which any custom dispatcher handler must implement for
 /// the further usage by the `Dispatcher` instances.
+///
+/// A handler is notified with [`NotificationType::Register`] when an
+/// element of a group using its dispatcher starts (including after a
+/// restart), and with [`NotificationType::Remove`] when it stops, is
+/// killed or faults. Handlers only get a shared reference to
+/// themselves, so their state must use interior mutability. Because
+/// a handler can be given to [`Dispatcher::with_handler`] wrapped in
+/// an [`Arc`], its state can also be read from outside of the
+/// dispatcher.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use std::sync::atomic::{AtomicUsize, Ordering};
+/// # use std::sync::Arc;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();    
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();    
+/// # }
+/// #
+/// // Counts the running elements of the groups using its dispatcher.
+/// #[derive(Debug, Default)]
+/// struct RunningCounter {
+///     running: AtomicUsize,
+/// }
+///
+/// impl DispatcherHandler for RunningCounter {
+///     fn notify(
+///         &self,
+///         _from_child: &ChildRef,
+///         _entries: &DispatcherMap,
+///         notification_type: NotificationType,
+///     ) {
+///         match notification_type {
+///             NotificationType::Register => self.running.fetch_add(1, Ordering::SeqCst),
+///             NotificationType::Remove => self.running.fetch_sub(1, Ordering::SeqCst),
+///         };
+///     }
+///
+///     fn broadcast_message(&self, entries: &DispatcherMap, message: &Arc<SignedMessage>) {
+///         // Send the message to every element of the group.
+///         for (child, _) in entries.iter() {
+///             child.tell_anonymously(message.clone()).ok();
+///         }
+///     }
+/// }
+///
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let counter = Arc::new(RunningCounter::default());
+///
+/// Bastion::children(|children| {
+///     children
+///         .with_dispatcher(
+///             Dispatcher::with_type(DispatcherType::Named("counted".to_string()))
+///                 .with_handler(counter.clone()),
+///         )
+///         .with_exec(|ctx| async move {
+///             // ...
+/// #           Ok(())
+///         })
+/// }).expect("Couldn't create the children group.");
+///
+/// let running = counter.running.load(Ordering::SeqCst);
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
 pub trait DispatcherHandler {
     /// Sends the notification of the certain type to each actor in group.
     fn notify(
@@ -198,6 +326,36 @@ pub trait DispatcherHandler {
     fn broadcast_message(&self, entries: &DispatcherMap, message: &Arc<SignedMessage>);
 }
 
+impl<T: DispatcherHandler + ?Sized> DispatcherHandler for Box<T> {
+    fn notify(
+        &self,
+        from_child: &ChildRef,
+        entries: &DispatcherMap,
+        notification_type: NotificationType,
+    ) {
+        (**self).notify(from_child, entries, notification_type)
+    }
+
+    fn broadcast_message(&self, entries: &DispatcherMap, message: &Arc<SignedMessage>) {
+        (**self).broadcast_message(entries, message)
+    }
+}
+
+impl<T: DispatcherHandler + ?Sized> DispatcherHandler for Arc<T> {
+    fn notify(
+        &self,
+        from_child: &ChildRef,
+        entries: &DispatcherMap,
+        notification_type: NotificationType,
+    ) {
+        (**self).notify(from_child, entries, notification_type)
+    }
+
+    fn broadcast_message(&self, entries: &DispatcherMap, message: &Arc<SignedMessage>) {
+        (**self).broadcast_message(entries, message)
+    }
+}
+
 /// A generic implementation of the Bastion dispatcher
 ///
 /// The main idea of the dispatcher is to provide an alternative way to
@@ -247,15 +405,19 @@ impl Dispatcher {
     }
 
     /// Sets the handler for the dispatcher.
-    pub fn with_handler(
-        mut self,
-        handler: Box<dyn DispatcherHandler + Send + Sync + 'static>,
-    ) -> Self {
+    ///
+    /// The handler can be wrapped in an [`Arc`] to keep access to
+    /// its state once it was given to the dispatcher (see
+    /// [`DispatcherHandler`]).
+    pub fn with_handler<H>(mut self, handler: H) -> Self
+    where
+        H: DispatcherHandler + Send + Sync + 'static,
+    {
         trace!(
             "Setting handler for the {:?} dispatcher.",
             self.dispatcher_type
         );
-        self.handler = handler;
+        self.handler = Box::new(handler);
         self
     }
 
@@ -352,6 +514,10 @@ pub(crate) struct GlobalDispatcher {
     pub dispatchers: LOTable<DispatcherType, Arc<Box<Dispatcher>>>,
     // TODO: switch to LOTable once lever implements write optimized granularity
     pub distributors: Arc<RwLock<HashMap<Distributor, Box<(dyn RecipientHandler)>>>>,
+    /// Custom recipient selectors registered per distributor.
+    pub selectors: Arc<RwLock<HashMap<Distributor, Box<dyn RecipientSelector>>>>,
+    /// Streams notified when a distributor gains or loses a recipient.
+    pub watchers: Arc<RwLock<HashMap<Distributor, Vec<mpsc::UnboundedSender<MembershipEvent>>>>>,
 }
 
 impl GlobalDispatcher {
@@ -359,7 +525,9 @@ impl GlobalDispatcher {
     pub(crate) fn new() -> Self {
         GlobalDispatcher {
             dispatchers: LOTable::new(),
-            distributors: Arc::new(RwLock::new(HashMap::new()))
+            distributors: Arc::new(RwLock::new(HashMap::new())),
+            selectors: Arc::new(RwLock::new(HashMap::new())),
+            watchers: Arc::new(RwLock::new(HashMap::new())),
             // TODO: switch to LOTable once lever implements write optimized granularity
             // distributors: LOTableBuilder::new()
                 //.with_concurrency(TransactionConcurrency::Optimistic)
@@ -454,16 +622,48 @@ impl GlobalDispatcher {
     where
         M: Message,
     {
-        let child = self.next(distributor)?.ok_or(SendError::EmptyRecipient)?;
-        child.try_tell_anonymously(message).map(Into::into)
+        let env = Envelope::from_dead_letters(BastionMessage::tell(message));
+        self.tell_envelope(distributor, env)
+    }
+
+    pub(crate) fn tell_with_ttl<M>(
+        &self,
+        distributor: Distributor,
+        message: M,
+        ttl: Duration,
+    ) -> Result<(), SendError>
+    where
+        M: Message,
+    {
+        let env = Envelope::from_dead_letters(BastionMessage::tell_with_ttl(message, ttl));
+        self.tell_envelope(distributor, env)
+    }
+
+    fn tell_envelope(&self, distributor: Distributor, env: Envelope) -> Result<(), SendError> {
+        let child = self
+            .select(distributor, &env)?
+            .ok_or(SendError::EmptyRecipient)?;
+        child.try_send(env)
     }
 
     pub(crate) fn ask<M>(&self, distributor: Distributor, message: M) -> Result<Answer, SendError>
     where
         M: Message,
     {
-        let child = self.next(distributor)?.ok_or(SendError::EmptyRecipient)?;
-        child.try_ask_anonymously(message).map(Into::into)
+        let (msg, answer) = BastionMessage::ask(message, RefAddr::dead_letters());
+        let mut env = Envelope::from_dead_letters(msg);
+        let child = self
+            .select(distributor, &env)?
+            .ok_or(SendError::EmptyRecipient)?;
+        // The answer is signed by the recipient, which is only known now.
+        if let BastionMessage::Message(msg) = &mut env.msg {
+            msg.set_answer_signature(child.addr());
+        }
+        let permit = match child.inflight_permit() {
+            Ok(permit) => permit,
+            Err(()) => return Err(SendError::inflight_limit(env)),
+        };
+        child.try_send(env).map(|_| answer.with_permit(permit))
     }
 
     pub(crate) fn ask_everyone<M>(
@@ -504,6 +704,131 @@ impl GlobalDispatcher {
         }
     }
 
+    /// Sends the message to every recipient of the distributor,
+    /// returning for each of them either the receiver of its
+    /// acknowledgement or the reason it couldn't be sent.
+    pub(crate) fn tell_everyone_acked<M>(
+        &self,
+        distributor: Distributor,
+        message: M,
+    ) -> Result<Vec<(ChildRef, Result<oneshot::Receiver<()>, SendError>)>, SendError>
+    where
+        M: Message + Clone,
+    {
+        let all_children = self.all(distributor)?;
+        if all_children.is_empty() {
+            Err(SendError::EmptyRecipient)
+        } else {
+            Ok(all_children
+                .into_iter()
+                .map(|child| {
+                    let (msg, acked) = BastionMessage::tell_acked(message.clone());
+                    let env = Envelope::from_dead_letters(msg);
+                    let sent = child.try_send(env).map(|_| acked);
+                    if let Err(error) = &sent {
+                        debug!(
+                            "The message can't be delivered to {}: {}",
+                            child.path(),
+                            error
+                        );
+                    }
+                    (child, sent)
+                })
+                .collect())
+        }
+    }
+
+    /// Sends every message of the batch, or none of them if one of
+    /// them can't be delivered.
+    pub(crate) fn tell_batch(&self, batch: Vec<(Distributor, Envelope)>) -> Result<(), SendError> {
+        // Resolve and validate every recipient before sending anything.
+        let recipients = batch
+            .iter()
+            .map(|(distributor, env)| {
+                let child = self
+                    .select(*distributor, env)?
+                    .ok_or(SendError::EmptyRecipient)?;
+                if child.sender().is_closed() {
+                    return Err(SendError::Other(anyhow::anyhow!(
+                        "the recipient {} of {:?} is unavailable",
+                        child.path(),
+                        distributor
+                    )));
+                }
+                Ok(child)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Reserve room in the mailboxes of the recipients for all the
+        // messages of the batch they receive, so that none of them is
+        // refused once the first ones were sent.
+        let mut counts: HashMap<&BastionId, (&ChildRef, usize)> = HashMap::new();
+        for child in &recipients {
+            counts.entry(child.id()).or_insert((child, 0)).1 += 1;
+        }
+        let mut reservations = counts
+            .iter()
+            .map(|(id, (child, count))| {
+                let reservation = child.reserve(*count).ok_or_else(|| {
+                    SendError::Other(anyhow::anyhow!(
+                        "the mailbox of the recipient {} has no room for {} messages",
+                        child.path(),
+                        count
+                    ))
+                })?;
+                Ok((*id, reservation))
+            })
+            .collect::<Result<HashMap<_, _>, SendError>>()?;
+
+        recipients
+            .iter()
+            .zip(batch)
+            .try_for_each(|(child, (_, env))| {
+                // FIXME: panics?
+                let reservation = reservations.get_mut(child.id()).unwrap();
+                child.send_reserved(env, reservation)
+            })
+    }
+
+    /// Returns whether the child is still a recipient of the distributor.
+    pub(crate) fn has_recipient(
+        &self,
+        distributor: Distributor,
+        child: &ChildRef,
+    ) -> Result<bool, SendError> {
+        self.distributors
+            .read()
+            .map_err(|error| {
+                SendError::Other(anyhow::anyhow!(
+                    "couldn't get read lock on distributors {:?}",
+                    error
+                ))
+            })?
+            .get(&distributor)
+            .map(|recipient| recipient.contains(child))
+            .ok_or_else(|| SendError::from(distributor))
+    }
+
+    pub(crate) fn select(
+        &self,
+        distributor: Distributor,
+        envelope: &Envelope,
+    ) -> Result<Option<ChildRef>, SendError> {
+        let selectors = self.selectors.read().map_err(|error| {
+            SendError::Other(anyhow::anyhow!(
+                "couldn't get read lock on selectors {:?}",
+                error
+            ))
+        })?;
+        match selectors.get(&distributor) {
+            Some(selector) => {
+                let recipients = self.all(distributor)?;
+                Ok(selector.select(&recipients, envelope).cloned())
+            }
+            None => self.next(distributor),
+        }
+    }
+
     fn next(&self, distributor: Distributor) -> Result<Option<ChildRef>, SendError> {
         self.distributors
             .read()
@@ -566,15 +891,22 @@ impl GlobalDispatcher {
             anyhow::anyhow!("couldn't get read lock on distributors {:?}", error)
         })?;
         if let Some(recipients) = distributors.get(&distributor) {
-            recipients.register(child_ref);
+            recipients.register(child_ref.clone());
         } else {
             let recipients = DefaultRecipientHandler::default();
-            recipients.register(child_ref);
+            recipients.register(child_ref.clone());
             distributors.insert(
                 distributor.clone(),
                 Box::new(recipients) as Box<(dyn RecipientHandler)>,
             );
         };
+        self.notify_watchers(distributor, MembershipEvent::Subscribed(child_ref.clone()));
+        system::current()
+            .events()
+            .publish(SystemEvent::DistributorSubscribed {
+                distributor: *distributor,
+                child: child_ref,
+            });
         Ok(())
     }
 
@@ -587,13 +919,56 @@ impl GlobalDispatcher {
             anyhow::anyhow!("couldn't get read lock on distributors {:?}", error)
         })?;
         distributor_list.iter().for_each(|distributor| {
-            distributors
-                .get(&distributor)
-                .map(|recipients| recipients.remove(&child_ref));
+            if let Some(recipients) = distributors.get(&distributor) {
+                let was_recipient = recipients.all().contains(&child_ref);
+                recipients.remove(&child_ref);
+                if was_recipient {
+                    self.notify_watchers(
+                        distributor,
+                        MembershipEvent::Unsubscribed(child_ref.clone()),
+                    );
+                    system::current()
+                        .events()
+                        .publish(SystemEvent::DistributorUnsubscribed {
+                            distributor: *distributor,
+                            child: child_ref.clone(),
+                        });
+                }
+            }
         });
         Ok(())
     }
 
+    /// Returns a stream of the recipients joining or leaving the
+    /// distributor.
+    pub(crate) fn watch(
+        &self,
+        distributor: Distributor,
+    ) -> mpsc::UnboundedReceiver<MembershipEvent> {
+        let (sender, receiver) = mpsc::unbounded();
+        match self.watchers.write() {
+            Ok(mut watchers) => watchers.entry(distributor).or_default().push(sender),
+            // The sender is dropped, so the stream ends right away.
+            Err(error) => debug!("couldn't get write lock on watchers {:?}", error),
+        }
+        receiver
+    }
+
+    fn notify_watchers(&self, distributor: &Distributor, event: MembershipEvent) {
+        let mut watchers = match self.watchers.write() {
+            Ok(watchers) => watchers,
+            Err(error) => {
+                debug!("couldn't get write lock on watchers {:?}", error);
+                return;
+            }
+        };
+        if let Some(senders) = watchers.get_mut(distributor) {
+            trace!("Notifying watchers of {:?}: {:?}", distributor, event);
+            // Forget about the watchers whose stream was dropped.
+            senders.retain(|sender| sender.unbounded_send(event.clone()).is_ok());
+        }
+    }
+
     /// Adds distributor to the global registry.
     pub(crate) fn register_distributor(&self, distributor: &Distributor) -> AnyResult<()> {
         let mut distributors = self.distributors.write().map_err(|error| {
@@ -613,6 +988,49 @@ impl GlobalDispatcher {
         Ok(())
     }
 
+    /// Registers the selector used to pick the recipient of the
+    /// messages sent through the distributor.
+    pub(crate) fn register_selector(
+        &self,
+        distributor: &Distributor,
+        selector: Box<dyn RecipientSelector>,
+    ) -> AnyResult<()> {
+        let mut selectors = self
+            .selectors
+            .write()
+            .map_err(|error| anyhow::anyhow!("couldn't get write lock on selectors {:?}", error))?;
+        selectors.insert(*distributor, selector);
+        Ok(())
+    }
+
+    /// Removes the selector of the distributor, which will then fall
+    /// back to its recipient handler.
+    pub(crate) fn remove_selector(&self, distributor: &Distributor) -> AnyResult<()> {
+        let mut selectors = self
+            .selectors
+            .write()
+            .map_err(|error| anyhow::anyhow!("couldn't get write lock on selectors {:?}", error))?;
+        selectors.remove(distributor);
+        Ok(())
+    }
+
+    /// Returns the name of every registered distributor along with
+    /// its number of recipients, sorted by name.
+    pub(crate) fn distributors(&self) -> AnyResult<Vec<(String, usize)>> {
+        let distributors = self.distributors.read().map_err(|error| {
+            anyhow::anyhow!("couldn't get read lock on distributors {:?}", error)
+        })?;
+        let mut names = distributors
+            .iter()
+            .map(|(distributor, recipients)| {
+                let name = STRING_INTERNER.resolve(distributor.interned()).to_string();
+                (name, recipients.all().len())
+            })
+            .collect::<Vec<_>>();
+        names.sort();
+        Ok(names)
+    }
+
     /// Removes distributor from the global registry if it has no remaining recipients.
     pub(crate) fn remove_distributor(&self, distributor: &Distributor) -> AnyResult<()> {
         let mut distributors = self.distributors.write().map_err(|error| {
@@ -632,12 +1050,15 @@ impl GlobalDispatcher {
 #[cfg(test)]
 mod tests {
     use crate::child_ref::ChildRef;
+    use crate::children::OverflowPolicy;
     use crate::context::BastionId;
     use crate::dispatcher::*;
     use crate::envelope::{RefAddr, SignedMessage};
+    use crate::limits::MailboxLimit;
     use crate::message::Msg;
     use crate::path::BastionPath;
     use futures::channel::mpsc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::{Arc, Mutex};
 
     #[derive(Clone)]
@@ -680,6 +1101,45 @@ mod tests {
         }
     }
 
+    #[derive(Default)]
+    struct CountingHandler {
+        registered: AtomicUsize,
+        removed: AtomicUsize,
+    }
+
+    impl DispatcherHandler for CountingHandler {
+        fn notify(
+            &self,
+            _from_child: &ChildRef,
+            _entries: &DispatcherMap,
+            notification_type: NotificationType,
+        ) {
+            match notification_type {
+                NotificationType::Register => self.registered.fetch_add(1, Ordering::SeqCst),
+                NotificationType::Remove => self.removed.fetch_add(1, Ordering::SeqCst),
+            };
+        }
+
+        fn broadcast_message(&self, _entries: &DispatcherMap, _message: &Arc<SignedMessage>) {}
+    }
+
+    #[test]
+    fn test_dispatcher_shares_state_with_arc_handler() {
+        let handler = Arc::new(CountingHandler::default());
+        let instance = Dispatcher::default().with_handler(handler.clone());
+        let (sender, _) = mpsc::unbounded();
+        let path = Arc::new(BastionPath::root());
+        let child_ref = ChildRef::new(BastionId::new(), sender, "test_name".to_string(), path);
+
+        instance
+            .register(&child_ref, "my::test::module".to_string())
+            .unwrap();
+        instance.remove(&child_ref);
+
+        assert_eq!(handler.registered.load(Ordering::SeqCst), 1);
+        assert_eq!(handler.removed.load(Ordering::SeqCst), 1);
+    }
+
     #[test]
     fn test_get_dispatcher_type_as_anonymous() {
         let instance = Dispatcher::default();
@@ -965,4 +1425,238 @@ mod tests {
         // Distributor is now removed because it has no remaining recipients.
         assert!(global_dispatcher.distributors.read().unwrap().is_empty());
     }
+
+    #[test]
+    fn test_global_dispatcher_notifies_watchers() {
+        let (sender, _) = mpsc::unbounded();
+        let path = Arc::new(BastionPath::root());
+        let child_ref = ChildRef::new(BastionId::new(), sender, "test_name".to_string(), path);
+
+        let global_dispatcher = GlobalDispatcher::new();
+        let distributor = Distributor::named("watched-distributor");
+        let mut events = global_dispatcher.watch(distributor);
+
+        global_dispatcher
+            .register_recipient(&distributor, child_ref.clone())
+            .unwrap();
+        global_dispatcher
+            .remove_recipient(&[distributor], child_ref.clone())
+            .unwrap();
+        // Not a recipient anymore, so nothing is sent.
+        global_dispatcher
+            .remove_recipient(&[distributor], child_ref.clone())
+            .unwrap();
+
+        assert_eq!(
+            events.try_next().unwrap(),
+            Some(MembershipEvent::Subscribed(child_ref.clone()))
+        );
+        assert_eq!(
+            events.try_next().unwrap(),
+            Some(MembershipEvent::Unsubscribed(child_ref))
+        );
+        assert!(events.try_next().is_err());
+    }
+
+    #[test]
+    fn test_global_dispatcher_lists_distributors() {
+        let (sender, _) = mpsc::unbounded();
+        let path = Arc::new(BastionPath::root());
+        let child_ref = ChildRef::new(BastionId::new(), sender, "test_name".to_string(), path);
+
+        let global_dispatcher = GlobalDispatcher::new();
+        let empty = Distributor::named("list-b");
+        let subscribed = Distributor::named("list-a");
+        global_dispatcher.register_distributor(&empty).unwrap();
+        global_dispatcher
+            .register_recipient(&subscribed, child_ref)
+            .unwrap();
+
+        assert_eq!(
+            global_dispatcher.distributors().unwrap(),
+            vec![("list-a".to_string(), 1), ("list-b".to_string(), 0)]
+        );
+    }
+
+    #[derive(Debug)]
+    struct NamedSelector(&'static str);
+
+    impl RecipientSelector for NamedSelector {
+        fn select<'a>(
+            &self,
+            recipients: &'a [ChildRef],
+            _envelope: &Envelope,
+        ) -> Option<&'a ChildRef> {
+            recipients.iter().find(|child| child.name() == self.0)
+        }
+    }
+
+    #[test]
+    fn test_global_dispatcher_uses_registered_selector() {
+        let (first_sender, mut first_receiver) = mpsc::unbounded();
+        let (second_sender, mut second_receiver) = mpsc::unbounded();
+        let path = Arc::new(BastionPath::root());
+        let first = ChildRef::new(
+            BastionId::new(),
+            first_sender,
+            "first".to_string(),
+            path.clone(),
+        );
+        let second = ChildRef::new(BastionId::new(), second_sender, "second".to_string(), path);
+
+        let global_dispatcher = GlobalDispatcher::new();
+        let distributor = Distributor::named("test-selector-distributor");
+        global_dispatcher
+            .register_recipient(&distributor, first)
+            .unwrap();
+        global_dispatcher
+            .register_recipient(&distributor, second)
+            .unwrap();
+        global_dispatcher
+            .register_selector(&distributor, Box::new(NamedSelector("second")))
+            .unwrap();
+
+        global_dispatcher
+            .tell(distributor, "first message")
+            .unwrap();
+        global_dispatcher
+            .tell(distributor, "second message")
+            .unwrap();
+
+        assert!(first_receiver.try_next().is_err());
+        assert!(second_receiver.try_next().unwrap().is_some());
+        assert!(second_receiver.try_next().unwrap().is_some());
+
+        global_dispatcher.remove_selector(&distributor).unwrap();
+        global_dispatcher
+            .tell(distributor, "third message")
+            .unwrap();
+        global_dispatcher
+            .tell(distributor, "fourth message")
+            .unwrap();
+
+        assert!(first_receiver.try_next().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_global_dispatcher_tell_batch_is_all_or_nothing() {
+        let (sender, mut receiver) = mpsc::unbounded();
+        let path = Arc::new(BastionPath::root());
+        let child_ref = ChildRef::new(BastionId::new(), sender, "test_name".to_string(), path);
+
+        let global_dispatcher = GlobalDispatcher::new();
+        let available = Distributor::named("test-batch-available");
+        let missing = Distributor::named("test-batch-missing");
+        global_dispatcher
+            .register_recipient(&available, child_ref)
+            .unwrap();
+
+        let batch = vec![
+            (
+                available,
+                Envelope::from_dead_letters(BastionMessage::tell("first")),
+            ),
+            (
+                missing,
+                Envelope::from_dead_letters(BastionMessage::tell("second")),
+            ),
+        ];
+        assert!(global_dispatcher.tell_batch(batch).is_err());
+        // Nothing was sent because one of the distributors is missing.
+        assert!(receiver.try_next().is_err());
+
+        let batch = vec![
+            (
+                available,
+                Envelope::from_dead_letters(BastionMessage::tell("first")),
+            ),
+            (
+                available,
+                Envelope::from_dead_letters(BastionMessage::tell("second")),
+            ),
+        ];
+        global_dispatcher.tell_batch(batch).unwrap();
+        assert!(receiver.try_next().unwrap().is_some());
+        assert!(receiver.try_next().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_global_dispatcher_tell_batch_reserves_mailbox_room() {
+        let (sender, mut receiver) = mpsc::unbounded();
+        let path = Arc::new(BastionPath::root());
+        // The mailbox has room for one more message.
+        let mailbox = Arc::new(MailboxLimit::new(2, OverflowPolicy::FailSender));
+        mailbox.set_len(1);
+        let child_ref = ChildRef::new(BastionId::new(), sender, "test_name".to_string(), path)
+            .with_mailbox(Some(mailbox.clone()));
+
+        let global_dispatcher = GlobalDispatcher::new();
+        let bounded = Distributor::named("test-batch-bounded");
+        global_dispatcher
+            .register_recipient(&bounded, child_ref)
+            .unwrap();
+
+        let batch = vec![
+            (
+                bounded,
+                Envelope::from_dead_letters(BastionMessage::tell("first")),
+            ),
+            (
+                bounded,
+                Envelope::from_dead_letters(BastionMessage::tell("second")),
+            ),
+        ];
+        assert!(global_dispatcher.tell_batch(batch).is_err());
+        // Nothing was sent because the mailbox has no room for both.
+        assert!(receiver.try_next().is_err());
+        assert!(!mailbox.refuses_senders());
+
+        let batch = vec![(
+            bounded,
+            Envelope::from_dead_letters(BastionMessage::tell("first")),
+        )];
+        global_dispatcher.tell_batch(batch).unwrap();
+        assert!(receiver.try_next().unwrap().is_some());
+        // The reservation was released once the batch was sent.
+        assert!(!mailbox.refuses_senders());
+    }
+
+    #[test]
+    fn test_global_dispatcher_tell_everyone_acked_reports_failures() {
+        let (sender, mut receiver) = mpsc::unbounded();
+        let (closed_sender, _) = mpsc::unbounded();
+        let path = Arc::new(BastionPath::root());
+        let child_ref = ChildRef::new(
+            BastionId::new(),
+            sender,
+            "test_name".to_string(),
+            path.clone(),
+        );
+        let closed = ChildRef::new(BastionId::new(), closed_sender, "closed".to_string(), path);
+
+        let global_dispatcher = GlobalDispatcher::new();
+        let distributor = Distributor::named("test-acked-failures");
+        global_dispatcher
+            .register_recipient(&distributor, closed.clone())
+            .unwrap();
+        global_dispatcher
+            .register_recipient(&distributor, child_ref.clone())
+            .unwrap();
+
+        // A recipient that can't be sent the message doesn't prevent
+        // sending it to the others.
+        let sent = global_dispatcher
+            .tell_everyone_acked(distributor, "acked")
+            .unwrap();
+        assert_eq!(sent.len(), 2);
+        for (child, acked) in sent {
+            if child == closed {
+                assert!(acked.is_err());
+            } else {
+                assert_eq!(child, child_ref);
+                assert!(acked.is_ok());
+            }
+        }
+        assert!(receiver.try_next().unwrap().is_some());
+    }
 }
@@ -1,19 +1,220 @@
 //! `Distributor` is a mechanism that allows you to send messages to children.
 
+#[cfg(feature = "remote")]
+use crate::remote::RemoteDistributor;
+#[cfg(feature = "remote")]
+use crate::wire::WireMessage;
 use crate::{
-    message::{Answer, Message, MessageHandler},
+    dispatcher::RecipientSelector,
+    envelope::{Envelope, SignedMessage},
+    errors::RequestError,
+    executor,
+    message::{Answer, BastionMessage, Bytes, ErrorReply, Message, MessageHandler},
     prelude::{ChildRef, SendError},
-    system::{STRING_INTERNER, SYSTEM},
+    system::{self, STRING_INTERNER},
 };
 use anyhow::Result as AnyResult;
-use futures::{channel::oneshot, FutureExt};
-use futures_timer::Delay;
+use futures::{
+    channel::oneshot,
+    future::{self, Either},
+    Future, FutureExt, Stream,
+};
 use lasso::Spur;
+#[cfg(any(feature = "remote", feature = "nats"))]
+use std::any::Any;
 use std::{
-    fmt::Debug,
-    sync::mpsc::{channel, Receiver},
+    fmt::{self, Debug, Formatter},
+    sync::{
+        mpsc::{channel, Receiver},
+        Mutex,
+    },
     time::Duration,
 };
+use tracing::debug;
+
+#[derive(Debug, Clone, Default)]
+/// The report returned by [`Distributor::tell_everyone_acked`], listing
+/// the recipients that acknowledged the message (using
+/// [`BastionContext::ack`]) within the timeout, the ones that did not
+/// and the ones the message couldn't be sent to.
+///
+/// [`BastionContext::ack`]: crate::context::BastionContext::ack
+pub struct AckReport {
+    acked: Vec<ChildRef>,
+    not_acked: Vec<ChildRef>,
+    failed: Vec<ChildRef>,
+}
+
+impl AckReport {
+    /// Returns the recipients that acknowledged the message.
+    pub fn acked(&self) -> &[ChildRef] {
+        &self.acked
+    }
+
+    /// Returns the recipients that didn't acknowledge the message
+    /// within the timeout.
+    pub fn not_acked(&self) -> &[ChildRef] {
+        &self.not_acked
+    }
+
+    /// Returns the recipients the message couldn't be sent to,
+    /// because their mailbox was full or they were stopped.
+    pub fn failed(&self) -> &[ChildRef] {
+        &self.failed
+    }
+
+    /// Returns `true` if every recipient acknowledged the message.
+    pub fn is_complete(&self) -> bool {
+        self.not_acked.is_empty() && self.failed.is_empty()
+    }
+}
+
+#[derive(Debug, Default)]
+/// A batch of messages that are either all sent or not sent at
+/// all, created by [`Bastion::atomic_batch`].
+///
+/// [`Bastion::atomic_batch`]: crate::Bastion::atomic_batch
+pub struct Batch {
+    messages: Vec<(Distributor, Envelope)>,
+}
+
+impl Batch {
+    /// Adds a message to the batch, that will be told to one of
+    /// the recipients of `distributor`.
+    pub fn tell(&mut self, distributor: Distributor, message: impl Message) -> &mut Self {
+        let env = Envelope::from_dead_letters(BastionMessage::tell(message));
+        self.messages.push((distributor, env));
+        self
+    }
+
+    /// Returns the number of messages in the batch.
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Returns `true` if the batch doesn't contain any message.
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    pub(crate) fn send(self) -> Result<(), SendError> {
+        system::current().dispatcher().tell_batch(self.messages)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A change in the recipients of a [`Distributor`], emitted by the
+/// stream returned by [`Distributor::watch`].
+pub enum MembershipEvent {
+    /// The child subscribed to the distributor.
+    Subscribed(ChildRef),
+    /// The child unsubscribed from the distributor, or stopped.
+    Unsubscribed(ChildRef),
+}
+
+type RebindCallback = Box<dyn Fn(&ChildRef, &ChildRef) + Send + Sync>;
+
+/// A [`Distributor`] pinned to one of its recipients for the duration
+/// of a session, created by [`Distributor::bind`].
+///
+/// Every message sent through a `BoundDistributor` goes to the same
+/// recipient, which allows stateful protocols (websockets, multi-step
+/// transactions...) to always talk to the child holding their state.
+/// If the recipient dies or leaves the distributor, another one is
+/// picked and the callback set with [`on_rebind`] is called.
+///
+/// [`on_rebind`]: Self::on_rebind
+pub struct BoundDistributor {
+    distributor: Distributor,
+    session_id: String,
+    child: Mutex<ChildRef>,
+    on_rebind: Option<RebindCallback>,
+}
+
+impl BoundDistributor {
+    /// Sets the callback called with the previous and the new recipient
+    /// when the session has to be bound to another recipient, because
+    /// the previous one died or left the distributor.
+    pub fn on_rebind<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&ChildRef, &ChildRef) + Send + Sync + 'static,
+    {
+        self.on_rebind = Some(Box::new(callback));
+        self
+    }
+
+    /// Sends a message to the recipient the session is bound to.
+    pub fn tell(&self, message: impl Message) -> Result<(), SendError> {
+        self.child()?.try_tell_anonymously(message)
+    }
+
+    /// Asks a question to the recipient the session is bound to.
+    pub fn ask(&self, question: impl Message) -> Result<Answer, SendError> {
+        self.child()?.try_ask_anonymously(question)
+    }
+
+    /// Returns the recipient the session is bound to, binding it
+    /// to another recipient first if the current one isn't available
+    /// anymore.
+    pub fn child(&self) -> Result<ChildRef, SendError> {
+        let (previous, new_child) = {
+            let mut child = self.child.lock().map_err(|error| {
+                SendError::Other(anyhow::anyhow!(
+                    "couldn't get lock on bound child {:?}",
+                    error
+                ))
+            })?;
+
+            if !child.sender().is_closed()
+                && system::current()
+                    .dispatcher()
+                    .has_recipient(self.distributor, &child)?
+            {
+                return Ok(child.clone());
+            }
+
+            let new_child = bound_recipient(self.distributor, &self.session_id)?;
+            debug!(
+                "BoundDistributor({}): Rebinding from {} to {}.",
+                self.session_id,
+                child.path(),
+                new_child.path()
+            );
+            let previous = std::mem::replace(&mut *child, new_child.clone());
+            (previous, new_child)
+        };
+
+        // The lock is released so that the callback can use the
+        // session.
+        if let Some(callback) = &self.on_rebind {
+            callback(&previous, &new_child);
+        }
+
+        Ok(new_child)
+    }
+
+    /// Returns the identifier of the session.
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    /// Ends the session, returning the [`Distributor`] it was
+    /// bound through.
+    pub fn unbind(self) -> Distributor {
+        debug!("BoundDistributor({}): Unbinding.", self.session_id);
+        self.distributor
+    }
+}
+
+impl Debug for BoundDistributor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BoundDistributor")
+            .field("distributor", &self.distributor)
+            .field("session_id", &self.session_id)
+            .field("child", &self.child)
+            .finish()
+    }
+}
 
 // Copy is fine here because we're working
 // with interned strings here
@@ -41,11 +242,47 @@ impl Distributor {
         Self(STRING_INTERNER.get_or_intern(name.as_ref()))
     }
 
+    /// Returns the distributor with the given name on another node,
+    /// connected to using [`Bastion::connect`], to which messages
+    /// implementing [`Serialize`] can be sent (see
+    /// [`RemoteDistributor`]).
+    ///
+    /// This method is only available with the `remote` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The name the node was connected to with.
+    /// * `name` - The name of the distributor on the node.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// let workers = Distributor::remote("node-b", "workers");
+    /// assert_eq!(workers.node(), "node-b");
+    /// ```
+    ///
+    /// [`Bastion::connect`]: crate::Bastion::connect
+    /// [`Serialize`]: serde::Serialize
+    #[cfg(feature = "remote")]
+    pub fn remote(node: impl Into<String>, name: impl Into<String>) -> RemoteDistributor {
+        RemoteDistributor::new(node, name)
+    }
+
     /// Ask a question to a recipient attached to the `Distributor`
     /// and wait for a reply.
     ///
     /// This can be achieved manually using a `MessageHandler` and `ask_one`.
     /// Ask a question to a recipient attached to the `Distributor`
+    ///
+    /// If the recipient replies with an error using
+    /// [`AnswerSender::reply_err`], the request fails with
+    /// [`RequestError::Remote`], while failing to send the question
+    /// or to receive the reply results in [`RequestError::Send`].
+    ///
+    /// [`AnswerSender::reply_err`]: crate::message::AnswerSender::reply_err
+    ///
     /// # Example
     ///
     /// ```no_run
@@ -93,7 +330,7 @@ impl Distributor {
     ///
     /// let distributor = Distributor::named("my distributor");
     ///
-    /// let reply: Result<String, SendError> = distributor
+    /// let reply: Result<String, RequestError> = distributor
     ///     .request("is it raining today?")
     ///     .await
     ///     .expect("couldn't receive reply");
@@ -105,31 +342,26 @@ impl Distributor {
     pub fn request<R: Message>(
         &self,
         question: impl Message,
-    ) -> oneshot::Receiver<Result<R, SendError>> {
+    ) -> oneshot::Receiver<Result<R, RequestError>> {
         let (sender, receiver) = oneshot::channel();
         let s = *self;
         spawn!(async move {
-            match SYSTEM.dispatcher().ask(s, question) {
+            match system::current().dispatcher().ask(s, question) {
                 Ok(response) => match response.await {
                     Ok(message) => {
-                        let message_to_send = MessageHandler::new(message)
-                            .on_tell(|reply: R, _| Ok(reply))
-                            .on_fallback(|_, _| {
-                                Err(SendError::Other(anyhow::anyhow!(
-                                    "received a message with the wrong type"
-                                )))
-                            });
+                        let message_to_send = into_reply(message);
                         let _ = sender.send(message_to_send);
                     }
                     Err(e) => {
                         let _ = sender.send(Err(SendError::Other(anyhow::anyhow!(
                             "couldn't receive reply: {:?}",
                             e
-                        ))));
+                        ))
+                        .into()));
                     }
                 },
                 Err(error) => {
-                    let _ = sender.send(Err(error));
+                    let _ = sender.send(Err(error.into()));
                 }
             };
         });
@@ -188,7 +420,7 @@ impl Distributor {
     ///
     /// let distributor = Distributor::named("my distributor");
     ///
-    /// let reply: Result<bool, SendError> = distributor
+    /// let reply: Result<bool, RequestError> = distributor
     ///    .request_sync("is it raining today?")
     ///    .recv()
     ///    .expect("couldn't receive reply"); // Ok(true)
@@ -200,29 +432,24 @@ impl Distributor {
     pub fn request_sync<R: Message>(
         &self,
         question: impl Message,
-    ) -> Receiver<Result<R, SendError>> {
+    ) -> Receiver<Result<R, RequestError>> {
         let (sender, receiver) = channel();
         let s = *self;
         spawn!(async move {
-            match SYSTEM.dispatcher().ask(s, question) {
+            match system::current().dispatcher().ask(s, question) {
                 Ok(response) => {
                     if let Ok(message) = response.await {
-                        let message_to_send = MessageHandler::new(message)
-                            .on_tell(|reply: R, _| Ok(reply))
-                            .on_fallback(|_, _| {
-                                Err(SendError::Other(anyhow::anyhow!(
-                                    "received a message with the wrong type"
-                                )))
-                            });
+                        let message_to_send = into_reply(message);
                         let _ = sender.send(message_to_send);
                     } else {
                         let _ = sender.send(Err(SendError::Other(anyhow::anyhow!(
                             "couldn't receive reply"
-                        ))));
+                        ))
+                        .into()));
                     }
                 }
                 Err(error) => {
-                    let _ = sender.send(Err(error));
+                    let _ = sender.send(Err(error.into()));
                 }
             };
         });
@@ -284,7 +511,7 @@ impl Distributor {
     /// let distributor = Distributor::named("my distributor");
     ///
     /// let timeout = Duration::from_millis(10);
-    /// let reply: Result<String, SendError> = distributor
+    /// let reply: Result<String, RequestError> = distributor
     ///     .request_timeout("is it raining today?", timeout)
     ///     .await
     ///     .expect("couldn't receive reply");
@@ -297,42 +524,36 @@ impl Distributor {
         &self,
         question: impl Message,
         timeout: Duration,
-    ) -> oneshot::Receiver<Result<R, SendError>> {
+    ) -> oneshot::Receiver<Result<R, RequestError>> {
         let (sender, receiver) = oneshot::channel();
         let s = *self;
         spawn!(async move {
-            match SYSTEM.dispatcher().ask(s, question) {
+            match system::current().dispatcher().ask(s, question) {
                 Ok(response) => {
                     futures::select! {
                         response_awaited = response.fuse() => {
                             match response_awaited {
                                 Ok(message) => {
-                                    let message_to_send = MessageHandler::new(message)
-                                        .on_tell(|reply: R, _| Ok(reply))
-                                        .on_fallback(|_, _| {
-                                            Err(SendError::Other(anyhow::anyhow!(
-                                                "received a message with the wrong type"
-                                            )))
-                                        });
+                                    let message_to_send = into_reply(message);
                                     let _ = sender.send(message_to_send);
                                 }
                                 Err(e) => {
                                     let _ = sender.send(Err(SendError::Other(anyhow::anyhow!(
                                         "couldn't receive reply: {:?}",
                                         e
-                                    ))));
+                                    )).into()));
                                 }
                             }
                         },
-                        _duration = Delay::new(timeout).fuse() => {
+                        _duration = executor::sleep(timeout).fuse() => {
                             let _ = sender.send(Err(SendError::Other(anyhow::anyhow!(
                                 "operation timed out before finish"
-                            ))));
+                            )).into()));
                         }
                     }
                 }
                 Err(error) => {
-                    let _ = sender.send(Err(error));
+                    let _ = sender.send(Err(error.into()));
                 }
             }
         });
@@ -342,6 +563,18 @@ impl Distributor {
 
     /// Ask a question to a recipient attached to the `Distributor`
     ///
+    /// With the `remote` feature, if the question is a
+    /// [`WireMessage`] and no recipient is attached to the
+    /// distributor on this node, it is asked to a recipient of the
+    /// distributor on another member of the cluster (see
+    /// [`Cluster::join`]), picking the member which answered the
+    /// previous questions the fastest.
+    ///
+    /// With the `nats` feature, if the question is a [`WireMessage`]
+    /// and the distributor is bridged to a NATS subject (see
+    /// [`NatsBridge::bridge`]), it is sent as a request on the
+    /// subject instead.
+    ///
     /// # Example
     ///
     /// ```no_run
@@ -386,8 +619,37 @@ impl Distributor {
     /// # Bastion::block_until_stopped();
     /// # }
     /// ```
+    ///
+    /// [`WireMessage`]: crate::wire::WireMessage
+    /// [`Cluster::join`]: crate::cluster::Cluster::join
+    /// [`NatsBridge::bridge`]: crate::nats::NatsBridge::bridge
     pub fn ask_one(&self, question: impl Message) -> Result<Answer, SendError> {
-        SYSTEM.dispatcher().ask(*self, question)
+        #[cfg(feature = "nats")]
+        {
+            if let Some(asked) = crate::nats::ask(*self, &question) {
+                return asked;
+            }
+        }
+        #[cfg(feature = "remote")]
+        let remote = (&question as &dyn Any)
+            .downcast_ref::<WireMessage>()
+            .cloned();
+        let asked = system::current().dispatcher().ask(*self, question);
+        #[cfg(feature = "remote")]
+        {
+            // The members of the cluster are only asked when there is
+            // no recipient on this node.
+            let unavailable = matches!(
+                asked,
+                Err(SendError::EmptyRecipient) | Err(SendError::NoDistributor(_))
+            );
+            if let (true, Some(question)) = (unavailable, remote) {
+                if let Some(answer) = crate::cluster::ask_one(*self, question) {
+                    return Ok(answer);
+                }
+            }
+        }
+        asked
     }
 
     /// Ask a question to all recipients attached to the `Distributor`
@@ -438,11 +700,16 @@ impl Distributor {
     /// # }
     /// ```
     pub fn ask_everyone(&self, question: impl Message + Clone) -> Result<Vec<Answer>, SendError> {
-        SYSTEM.dispatcher().ask_everyone(*self, question)
+        system::current().dispatcher().ask_everyone(*self, question)
     }
 
     /// Send a Message to a recipient attached to the `Distributor`
     ///
+    /// With the `nats` feature, if the message is a [`WireMessage`]
+    /// and the distributor is bridged to a NATS subject (see
+    /// [`NatsBridge::bridge`]), it is published on the subject
+    /// instead.
+    ///
     /// # Example
     ///
     /// ```no_run
@@ -487,13 +754,87 @@ impl Distributor {
     /// # Bastion::block_until_stopped();
     /// # }
     /// ```
+    ///
+    /// [`WireMessage`]: crate::wire::WireMessage
+    /// [`NatsBridge::bridge`]: crate::nats::NatsBridge::bridge
     pub fn tell_one(&self, message: impl Message) -> Result<(), SendError> {
-        SYSTEM.dispatcher().tell(*self, message)
+        #[cfg(feature = "nats")]
+        {
+            if let Some(published) = crate::nats::tell(*self, &message) {
+                return published;
+            }
+        }
+        system::current().dispatcher().tell(*self, message)
+    }
+
+    /// Send a Message to a recipient attached to the `Distributor`,
+    /// that will only be received if it didn't wait for longer than
+    /// `ttl` in the recipient's mailbox.
+    ///
+    /// Expired messages are never seen by the recipient and are routed
+    /// to the dead letters instead.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// # Bastion::supervisor(|supervisor| {
+    /// #    supervisor.children(|children| {
+    /// #    children
+    /// #        .with_redundancy(1)
+    /// #        .with_distributor(Distributor::named("my distributor"))
+    /// #        .with_exec(|ctx: BastionContext| {
+    /// #           async move {
+    /// #               loop {
+    /// #                   let _: Option<SignedMessage> = ctx.try_recv().await;
+    /// #               }
+    /// #               Ok(())
+    /// #           }
+    /// #        })
+    /// #    })
+    /// # });
+    /// #
+    /// # Bastion::start();
+    ///
+    /// let distributor = Distributor::named("my distributor");
+    ///
+    /// // The work is wasted if it doesn't start within a second.
+    /// distributor
+    ///     .tell_one_with_ttl("compute this", Duration::from_secs(1))
+    ///     .expect("couldn't send message");
+    ///
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn tell_one_with_ttl(&self, message: impl Message, ttl: Duration) -> Result<(), SendError> {
+        system::current()
+            .dispatcher()
+            .tell_with_ttl(*self, message, ttl)
     }
 
     /// Send a Message to each recipient attached to the `Distributor`
     ///
     /// Requires a `Message` that implements `Clone`. (it will be cloned and passed to each recipient)
+    ///
+    /// With the `remote` feature, if the message is a [`WireMessage`],
+    /// it is also sent to the recipients of the distributor on the
+    /// other members of the cluster (see [`Cluster::join`]).
     /// # Example
     ///
     /// ```no_run
@@ -538,8 +879,181 @@ impl Distributor {
     /// # Bastion::block_until_stopped();
     /// # }
     /// ```
+    ///
+    /// [`WireMessage`]: crate::wire::WireMessage
+    /// [`Cluster::join`]: crate::cluster::Cluster::join
     pub fn tell_everyone(&self, message: impl Message + Clone) -> Result<Vec<()>, SendError> {
-        SYSTEM.dispatcher().tell_everyone(*self, message)
+        #[cfg(feature = "remote")]
+        let members = crate::cluster::tell_everyone(*self, &message);
+        let sent = system::current().dispatcher().tell_everyone(*self, message);
+        #[cfg(feature = "remote")]
+        {
+            // The message reached recipients on other members.
+            if members > 0 {
+                if let Err(SendError::EmptyRecipient) | Err(SendError::NoDistributor(_)) = sent {
+                    return Ok(Vec::new());
+                }
+            }
+        }
+        sent
+    }
+
+    /// Tell a buffer to every recipient attached to the `Distributor`,
+    /// without copying it: every recipient receives a [`Bytes`]
+    /// referencing the same memory, which can be matched using
+    /// [`MessageHandler::on_bytes`].
+    ///
+    /// Converting a `Vec<u8>` or a `Box<[u8]>` into [`Bytes`] doesn't
+    /// copy it either.
+    ///
+    /// ```no_run
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// # Bastion::children(|children| {
+    /// # children
+    ///     .with_redundancy(10)
+    ///     .with_distributor(Distributor::named("workers"))
+    ///     .with_exec(|ctx: BastionContext| {
+    ///        async move {
+    ///            loop {
+    ///                MessageHandler::new(ctx.recv().await?)
+    ///                    .on_bytes(|chunk: Bytes, _| {
+    ///                        // Process the chunk...
+    ///                    })
+    ///                    .on_fallback(|_, _| ());
+    ///            }
+    ///        }
+    ///     })
+    /// #    }).unwrap();
+    /// #
+    /// # Bastion::start();
+    ///
+    /// let chunk: Vec<u8> = vec![0; 16 * 1024 * 1024];
+    /// Distributor::named("workers")
+    ///     .tell_bytes(chunk)
+    ///     .expect("couldn't send the chunk");
+    /// #
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`MessageHandler::on_bytes`]: crate::message::MessageHandler::on_bytes
+    pub fn tell_bytes(&self, bytes: impl Into<Bytes>) -> Result<Vec<()>, SendError> {
+        system::current()
+            .dispatcher()
+            .tell_everyone(*self, bytes.into())
+    }
+
+    /// Tell a message to every recipient attached to the `Distributor`,
+    /// and track which of them acknowledge it using [`BastionContext::ack`].
+    ///
+    /// The returned future resolves to an [`AckReport`] once every recipient
+    /// acknowledged the message, or once `timeout` expired. The recipients
+    /// the message couldn't be sent to are listed in [`AckReport::failed`].
+    ///
+    /// ```no_run
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// // attach a named distributor to the children
+    /// # Bastion::children(|children| {
+    /// # children
+    ///     .with_redundancy(10)
+    ///     .with_distributor(Distributor::named("configuration"))
+    ///     .with_exec(|ctx: BastionContext| {
+    ///        async move {
+    ///            loop {
+    ///                let _config: SignedMessage = ctx.recv().await?;
+    ///                // Apply the new configuration...
+    ///
+    ///                // ...and let the sender know it was applied.
+    ///                ctx.ack();
+    ///            }
+    ///        }
+    ///     })
+    /// #    }).unwrap();
+    /// #
+    /// # Bastion::start();
+    ///
+    /// let distributor = Distributor::named("configuration");
+    ///
+    /// let report: AckReport = run!(distributor
+    ///     .tell_everyone_acked("reload", Duration::from_secs(1))
+    ///     .expect("couldn't send the message"));
+    ///
+    /// for child in report.not_acked() {
+    ///     println!("{} didn't apply the configuration", child.path());
+    /// }
+    /// #
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`BastionContext::ack`]: crate::context::BastionContext::ack
+    pub fn tell_everyone_acked(
+        &self,
+        message: impl Message + Clone,
+        timeout: Duration,
+    ) -> Result<impl Future<Output = AckReport>, SendError> {
+        let acks = system::current()
+            .dispatcher()
+            .tell_everyone_acked(*self, message)?
+            .into_iter()
+            .map(|(child, sent)| {
+                // The timer starts now, not when the report is polled.
+                let sent = sent.map(|acked| (acked, executor::sleep(timeout)));
+                async move {
+                    let acked = match sent {
+                        Ok((acked, deadline)) => Some(matches!(
+                            future::select(acked, deadline).await,
+                            Either::Left((Ok(()), _))
+                        )),
+                        Err(_) => None,
+                    };
+                    (child, acked)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Ok(future::join_all(acks).map(|acks| {
+            acks.into_iter()
+                .fold(AckReport::default(), |mut report, (child, acked)| {
+                    match acked {
+                        Some(true) => report.acked.push(child),
+                        Some(false) => report.not_acked.push(child),
+                        None => report.failed.push(child),
+                    }
+                    report
+                })
+        }))
     }
 
     /// subscribe a `ChildRef` to the named `Distributor`
@@ -589,7 +1103,9 @@ impl Distributor {
     /// # }
     /// ```
     pub fn subscribe(&self, child_ref: ChildRef) -> AnyResult<()> {
-        SYSTEM.dispatcher().register_recipient(self, child_ref)
+        system::current()
+            .dispatcher()
+            .register_recipient(self, child_ref)
     }
 
     /// unsubscribe a `ChildRef` to the named `Distributor`
@@ -639,15 +1155,181 @@ impl Distributor {
     /// # }
     /// ```
     pub fn unsubscribe(&self, child_ref: ChildRef) -> AnyResult<()> {
-        let global_dispatcher = SYSTEM.dispatcher();
+        let global_dispatcher = system::current().dispatcher();
         global_dispatcher.remove_recipient(&vec![*self], child_ref)
     }
 
+    /// Sets the [`RecipientSelector`] that will pick which recipient
+    /// of the `Distributor` receives the messages sent with
+    /// [`tell_one`] and [`ask_one`], replacing the default round-robin.
+    ///
+    /// ```no_run
+    /// # use bastion::prelude::*;
+    /// #
+    /// #[derive(Debug)]
+    /// struct LastRecipient;
+    ///
+    /// impl RecipientSelector for LastRecipient {
+    ///     fn select<'a>(&self, recipients: &'a [ChildRef], _envelope: &Envelope) -> Option<&'a ChildRef> {
+    ///         recipients.last()
+    ///     }
+    /// }
+    ///
+    /// # Bastion::init();
+    /// let distributor = Distributor::named("my distributor");
+    ///
+    /// // messages sent through the distributor will now go to its last recipient
+    /// distributor.set_selector(LastRecipient).expect("couldn't set the selector");
+    /// ```
+    ///
+    /// [`tell_one`]: Self::tell_one
+    /// [`ask_one`]: Self::ask_one
+    pub fn set_selector(&self, selector: impl RecipientSelector + 'static) -> AnyResult<()> {
+        system::current()
+            .dispatcher()
+            .register_selector(self, Box::new(selector))
+    }
+
+    /// Removes the [`RecipientSelector`] of the `Distributor`, which will
+    /// go back to distributing messages in a round-robin fashion.
+    ///
+    /// ```no_run
+    /// # use bastion::prelude::*;
+    /// #
+    /// # Bastion::init();
+    /// let distributor = Distributor::named("my distributor");
+    ///
+    /// distributor.remove_selector().expect("couldn't remove the selector");
+    /// ```
+    pub fn remove_selector(&self) -> AnyResult<()> {
+        system::current().dispatcher().remove_selector(self)
+    }
+
+    /// Returns a stream of the children subscribing to or
+    /// unsubscribing from the `Distributor`, starting from now.
+    ///
+    /// ```no_run
+    /// # use bastion::prelude::*;
+    /// # use futures::StreamExt;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// # Bastion::start();
+    /// let mut events = Distributor::named("workers").watch();
+    ///
+    /// spawn!(async move {
+    ///     while let Some(event) = events.next().await {
+    ///         match event {
+    ///             MembershipEvent::Subscribed(child) => println!("{} joined", child.path()),
+    ///             MembershipEvent::Unsubscribed(child) => println!("{} left", child.path()),
+    ///         }
+    ///     }
+    /// });
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn watch(&self) -> impl Stream<Item = MembershipEvent> {
+        system::current().dispatcher().watch(*self)
+    }
+
+    /// Binds a session to one of the recipients of the `Distributor`,
+    /// returning a [`BoundDistributor`] that will send all the messages
+    /// of the session to this recipient until it is unbound or the
+    /// recipient dies.
+    ///
+    /// If the `Distributor` has a [`RecipientSelector`], it picks the
+    /// recipient from an envelope holding the session id (as a
+    /// `String`).
+    ///
+    /// ```no_run
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// # Bastion::start();
+    /// let distributor = Distributor::named("websockets");
+    ///
+    /// let session = distributor
+    ///     .bind("client-42")
+    ///     .expect("couldn't bind the session")
+    ///     .on_rebind(|previous, current| {
+    ///         println!("{} took over the session from {}", current.path(), previous.path());
+    ///     });
+    ///
+    /// session.tell("hello").expect("couldn't send message");
+    /// session.tell("how are you?").expect("couldn't send message");
+    ///
+    /// session.unbind();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn bind(&self, session_id: impl Into<String>) -> Result<BoundDistributor, SendError> {
+        let session_id = session_id.into();
+        let child = bound_recipient(*self, &session_id)?;
+        debug!(
+            "BoundDistributor({}): Bound to {}.",
+            session_id,
+            child.path()
+        );
+
+        Ok(BoundDistributor {
+            distributor: *self,
+            session_id,
+            child: Mutex::new(child),
+            on_rebind: None,
+        })
+    }
+
     pub(crate) fn interned(&self) -> &Spur {
         &self.0
     }
 }
 
+// Returns the recipient to bind a session to, letting the selector of
+// the distributor pick it from the session id.
+fn bound_recipient(distributor: Distributor, session_id: &str) -> Result<ChildRef, SendError> {
+    let env = Envelope::from_dead_letters(BastionMessage::tell(session_id.to_string()));
+    system::current()
+        .dispatcher()
+        .select(distributor, &env)?
+        .ok_or(SendError::EmptyRecipient)
+}
+
+// Returns the answer to a request, or the error the recipient replied
+// with.
+fn into_reply<R: Message>(message: SignedMessage) -> Result<R, RequestError> {
+    MessageHandler::new(message)
+        .on_tell(|reply: R, _| Ok(reply))
+        .on_tell(|ErrorReply(error): ErrorReply, _| Err(RequestError::Remote(error)))
+        .on_fallback(|_, _| {
+            Err(SendError::Other(anyhow::anyhow!("received a message with the wrong type")).into())
+        })
+}
+
 #[cfg(test)]
 mod distributor_tests {
     use crate::prelude::*;
@@ -657,6 +1339,9 @@ mod distributor_tests {
     use std::{thread, time::Duration};
 
     const TEST_DISTRIBUTOR: &str = "test distributor";
+
+    #[derive(Debug, Clone)]
+    struct PleaseAck;
     const SUBSCRIBE_TEST_DISTRIBUTOR: &str = "subscribe test";
 
     #[cfg(feature = "tokio-runtime")]
@@ -680,6 +1365,72 @@ mod distributor_tests {
         test_ask();
         test_request();
         test_subscribe();
+        test_tell_everyone_acked();
+        test_bind();
+        test_bind_with_selector();
+    }
+
+    fn test_bind() {
+        let session = Distributor::named(TEST_DISTRIBUTOR)
+            .bind("test session")
+            .unwrap();
+        let bound = session.child().unwrap();
+
+        for _ in 0..3_usize {
+            let answer = session.ask(()).unwrap();
+            let child_ref: ChildRef = run!(async {
+                MessageHandler::new(answer.await.unwrap())
+                    .on_tell(|child_ref: ChildRef, _| child_ref)
+                    .on_fallback(|unknown, _sender_addr| {
+                        panic!("unknown message\n {:?}", unknown);
+                    })
+            });
+            assert_eq!(bound, child_ref, "the session should stay on its child");
+        }
+
+        session.unbind();
+    }
+
+    #[derive(Debug)]
+    struct FirstRecipient;
+
+    impl RecipientSelector for FirstRecipient {
+        fn select<'a>(
+            &self,
+            recipients: &'a [ChildRef],
+            _envelope: &Envelope,
+        ) -> Option<&'a ChildRef> {
+            recipients.first()
+        }
+    }
+
+    fn test_bind_with_selector() {
+        let distributor = Distributor::named(TEST_DISTRIBUTOR);
+        distributor.set_selector(FirstRecipient).unwrap();
+
+        // Round robin would bind the sessions to different children.
+        let first = distributor.bind("first session").unwrap();
+        let second = distributor.bind("second session").unwrap();
+        assert_eq!(first.child().unwrap(), second.child().unwrap());
+
+        distributor.remove_selector().unwrap();
+    }
+
+    fn test_tell_everyone_acked() {
+        let test_distributor = Distributor::named(TEST_DISTRIBUTOR);
+
+        let report = run!(test_distributor
+            .tell_everyone_acked(PleaseAck, Duration::from_secs(1))
+            .unwrap());
+        assert_eq!(5, report.acked().len(), "all 5 children should ack");
+        assert!(report.is_complete());
+
+        // The children don't acknowledge `&str` messages
+        let report = run!(test_distributor
+            .tell_everyone_acked("no ack for you", Duration::from_millis(100))
+            .unwrap());
+        assert_eq!(5, report.not_acked().len(), "no child should ack");
+        assert!(!report.is_complete());
     }
 
     fn test_subscribe() {
@@ -808,13 +1559,24 @@ mod distributor_tests {
 
         run!(async {
             let timeout = Duration::from_nanos(1);
-            let answer_timeout: Result<u8, SendError> = test_distributor
+            let answer_timeout: Result<u8, RequestError> = test_distributor
                 .request_timeout(question.clone(), timeout)
                 .await
                 .unwrap();
 
-            let err_msg: SendError = answer_timeout.unwrap_err();
-            assert!(matches!(err_msg, SendError::Other { .. }));
+            let err_msg: RequestError = answer_timeout.unwrap_err();
+            assert!(matches!(
+                err_msg,
+                RequestError::Send(SendError::Other { .. })
+            ));
+        });
+
+        run!(async {
+            let answer: Result<u8, RequestError> = test_distributor.request(true).await.unwrap();
+            assert!(matches!(
+                answer,
+                Err(RequestError::Remote(error)) if error == "can't answer a bool"
+            ));
         });
     }
 
@@ -850,6 +1612,12 @@ mod distributor_tests {
                                     // send your child ref
                                     .on_question(|_: (), sender| {
                                         let _ = sender.reply(child_ref);
+                                    })
+                                    .on_question(|_: bool, sender| {
+                                        let _ = sender.reply_err("can't answer a bool");
+                                    })
+                                    .on_tell(|_: PleaseAck, _| {
+                                        ctx.ack();
                                     });
                             }
                         })
@@ -3,13 +3,25 @@
 //! and instruct Bastion how to send messages back to them
 
 use crate::broadcast::Sender;
-use crate::message::{BastionMessage, Message, Msg};
+use crate::child_ref::ChildRef;
+use crate::context::BastionId;
+use crate::distributor::Distributor;
+use crate::message::{Answer, BastionMessage, CorrelationId, Message, MessageOrigin, Msg};
 use crate::path::BastionPath;
-use crate::system::SYSTEM;
+use crate::supervisor::SupervisorRef;
+use crate::system;
 use std::sync::Arc;
+use tracing::{debug, trace};
 
 #[derive(Debug)]
-pub(crate) struct Envelope {
+/// The internal wrapper used to carry a message and its sender signature
+/// through the mailboxes.
+///
+/// It is exposed read-only so that routing code such as a
+/// [`RecipientSelector`] can inspect what is about to be delivered.
+///
+/// [`RecipientSelector`]: crate::dispatcher::RecipientSelector
+pub struct Envelope {
     pub(crate) msg: BastionMessage,
     pub(crate) sign: RefAddr,
 }
@@ -63,6 +75,69 @@ impl SignedMessage {
 
     #[doc(hidden)]
     pub fn extract(self) -> (Msg, RefAddr) {
+        self.split()
+    }
+
+    /// Returns the payload of the message along with its origin if
+    /// it is of type `M`, or gives the message back otherwise. Used
+    /// by the code generated by `#[derive(BastionMessage)]`.
+    #[doc(hidden)]
+    pub fn into_routed<M: Message>(self) -> Result<(M, MessageOrigin), Self> {
+        if !self.msg.is::<M>() {
+            return Err(self);
+        }
+
+        let (mut msg, sign) = self.split();
+        let sender = msg.take_sender();
+        match msg.downcast::<M>() {
+            Ok(msg) => Ok((msg, MessageOrigin::new(sign, sender))),
+            // A broadcast still referenced by other recipients can't
+            // be taken (and can't be replied to).
+            Err(msg) => Err(SignedMessage::new(msg, sign)),
+        }
+    }
+
+    /// Consumes the message and returns its payload along with the
+    /// signature of its sender, which can be used to reply to it or
+    /// to forward other messages to it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             let (msg, sender): (Msg, RefAddr) = ctx.recv().await?.split();
+    ///             if let Ok(msg) = msg.downcast::<&'static str>() {
+    ///                 sender.tell(msg).expect("Couldn't send the message.");
+    ///             }
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn split(self) -> (Msg, RefAddr) {
         (self.msg, self.sign)
     }
 
@@ -105,6 +180,69 @@ impl SignedMessage {
     pub fn signature(&self) -> &RefAddr {
         &self.sign
     }
+
+    /// Returns the correlation id of the message, which is also the
+    /// one of the answer to a question (see [`CorrelationId`]).
+    pub fn correlation_id(&self) -> CorrelationId {
+        self.msg.correlation_id()
+    }
+
+    /// Returns the address replies to the message should be sent
+    /// to, which is the signature of its original sender if it was
+    /// forwarded (see [`BastionContext::forward`]), or its
+    /// [`signature`] otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             let msg: SignedMessage = ctx.recv().await?;
+    ///             // Replying to the original sender, even if the
+    ///             // message was forwarded by another element...
+    ///             ctx.tell(msg.reply_to(), "reply").expect("Unable to reply");
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`BastionContext::forward`]: crate::context::BastionContext::forward
+    /// [`signature`]: Self::signature
+    pub fn reply_to(&self) -> &RefAddr {
+        self.msg.reply_to().unwrap_or(&self.sign)
+    }
+
+    /// Returns whether the message's payload is of type `M`, e.g. to
+    /// select the messages to receive with
+    /// [`BastionContext::recv_where`].
+    ///
+    /// [`BastionContext::recv_where`]: crate::context::BastionContext::recv_where
+    pub fn is<M: Message>(&self) -> bool {
+        self.msg.is::<M>()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -149,17 +287,60 @@ impl SignedMessage {
 pub struct RefAddr {
     path: Arc<BastionPath>,
     sender: Sender,
+    // The name of the children group of the element, if the address
+    // is the one of an element.
+    group_name: Option<Arc<str>>,
+    // The correlation id given to the message signed with this
+    // signature, which is taken when creating its envelope.
+    correlation_id: Option<CorrelationId>,
+}
+
+/// The recipients of the messages sent using
+/// [`BastionContext::tell`] and [`BastionContext::ask`], which are
+/// signed with the sending element's signature.
+///
+/// This trait is implemented for [`RefAddr`], [`ChildRef`],
+/// [`Distributor`] (sending the message to one of its recipients)
+/// and [`SupervisorRef`] (broadcasting the message to the elements it
+/// supervises, which can't answer it).
+///
+/// [`BastionContext::tell`]: crate::context::BastionContext::tell
+/// [`BastionContext::ask`]: crate::context::BastionContext::ask
+pub trait MessageTarget {
+    #[doc(hidden)]
+    fn tell_signed<M: Message>(&self, msg: M, sign: RefAddr) -> Result<(), M>;
+
+    #[doc(hidden)]
+    fn ask_signed<M: Message>(&self, msg: M, sign: RefAddr) -> Result<Answer, M>;
+
+    #[doc(hidden)]
+    fn forward_signed(&self, msg: SignedMessage) -> Result<(), SignedMessage>;
 }
 
 impl RefAddr {
     pub(crate) fn new(path: Arc<BastionPath>, sender: Sender) -> Self {
-        RefAddr { path, sender }
+        RefAddr {
+            path,
+            sender,
+            group_name: None,
+            correlation_id: None,
+        }
+    }
+
+    pub(crate) fn with_group_name(mut self, group_name: Arc<str>) -> Self {
+        self.group_name = Some(group_name);
+        self
+    }
+
+    pub(crate) fn with_correlation_id(mut self, correlation_id: CorrelationId) -> Self {
+        self.correlation_id = Some(correlation_id);
+        self
     }
 
     pub(crate) fn dead_letters() -> Self {
         Self::new(
-            SYSTEM.dead_letters().path().clone(),
-            SYSTEM.dead_letters().sender().clone(),
+            system::current().dead_letters().path().clone(),
+            system::current().dead_letters().sender().clone(),
         )
     }
 
@@ -269,24 +450,374 @@ impl RefAddr {
         &self.path
     }
 
+    /// Returns the identifier of the element of a children group
+    /// this address belongs to, or `None` if it doesn't belong to
+    /// such an element (e.g. if the message was sent from outside
+    /// of Bastion).
+    ///
+    /// Together with [`group_id`] and [`group_name`], this allows
+    /// handlers to make decisions depending on the sender of a
+    /// message (e.g. to rate limit the senders separately).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             loop {
+    ///                 MessageHandler::new(ctx.recv().await?)
+    ///                     .on_tell(|msg: &str, sender: RefAddr| {
+    ///                         if sender.group_name() == Some("admins") {
+    ///                             // Handle the message...
+    ///                         } else if let Some(id) = sender.child_id() {
+    ///                             // Count the message against the limit of `id`...
+    ///                         }
+    ///                     })
+    ///                     .on_fallback(|_, _| ());
+    ///             }
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`group_id`]: Self::group_id
+    /// [`group_name`]: Self::group_name
+    pub fn child_id(&self) -> Option<&BastionId> {
+        match self.path.elem() {
+            Some(elem) if elem.is_child() => Some(self.path.id()),
+            _ => None,
+        }
+    }
+
+    /// Returns the identifier of the children group of the element
+    /// this address belongs to, or `None` if it doesn't belong to
+    /// an element of a children group.
+    pub fn group_id(&self) -> Option<&BastionId> {
+        self.child_id()?;
+        self.path.parent_chain().last()
+    }
+
+    /// Returns the name of the children group of the element this
+    /// address belongs to (see [`Children::with_name`]), or `None`
+    /// if it doesn't belong to an element of a children group.
+    ///
+    /// [`Children::with_name`]: crate::children::Children::with_name
+    pub fn group_name(&self) -> Option<&str> {
+        self.group_name.as_deref()
+    }
+
+    /// Sends a message to the owner of this address. The message will
+    /// be signed with the dead letters address, the same way as
+    /// [`ChildRef::tell_anonymously`] does.
+    ///
+    /// This method returns `()` if it succeeded, or `Err(msg)`
+    /// otherwise.
+    ///
+    /// # Argument
+    ///
+    /// * `msg` - The message to send.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children).unwrap();
+    /// let addr: RefAddr = children_ref.elems()[0].addr();
+    /// addr.tell("A message containing data.").expect("Couldn't send the message.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`ChildRef::tell_anonymously`]: crate::child_ref::ChildRef::tell_anonymously
+    pub fn tell<M: Message>(&self, msg: M) -> Result<(), M> {
+        debug!("{:?}: Telling message: {:?}", self.path(), msg);
+        let msg = BastionMessage::tell(msg);
+        let env = Envelope::from_dead_letters(msg);
+        // FIXME: panics?
+        self.sender
+            .unbounded_send(env)
+            .map_err(|err| err.into_inner().into_msg().unwrap())
+    }
+
+    /// Sends a message to the owner of this address, allowing it to
+    /// answer. The message will be signed with the dead letters
+    /// address, the same way as [`ChildRef::ask_anonymously`] does.
+    ///
+    /// This method returns [`Answer`] if it succeeded, or `Err(msg)`
+    /// otherwise.
+    ///
+    /// # Argument
+    ///
+    /// * `msg` - The message to send.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children).unwrap();
+    /// let addr: RefAddr = children_ref.elems()[0].addr();
+    /// let answer: Answer = addr.ask("A question.").expect("Couldn't send the message.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`ChildRef::ask_anonymously`]: crate::child_ref::ChildRef::ask_anonymously
+    pub fn ask<M: Message>(&self, msg: M) -> Result<Answer, M> {
+        debug!("{:?}: Asking message: {:?}", self.path(), msg);
+        let (msg, answer) = BastionMessage::ask(msg, self.clone());
+        let env = Envelope::from_dead_letters(msg);
+        // FIXME: panics?
+        self.sender
+            .unbounded_send(env)
+            .map_err(|err| err.into_inner().into_msg().unwrap())?;
+
+        Ok(answer)
+    }
+
     pub(crate) fn sender(&self) -> &Sender {
         &self.sender
     }
 }
 
+impl MessageTarget for RefAddr {
+    fn tell_signed<M: Message>(&self, msg: M, sign: RefAddr) -> Result<(), M> {
+        let env = Envelope::new_with_sign(BastionMessage::tell(msg), sign);
+        self.sender
+            .unbounded_send(env)
+            .map_err(|err| err.into_inner().into_msg().unwrap())
+    }
+
+    fn ask_signed<M: Message>(&self, msg: M, sign: RefAddr) -> Result<Answer, M> {
+        let (msg, answer) = BastionMessage::ask(msg, sign.clone());
+        let env = Envelope::new_with_sign(msg, sign);
+        self.sender
+            .unbounded_send(env)
+            .map_err(|err| err.into_inner().into_msg().unwrap())?;
+
+        Ok(answer)
+    }
+
+    fn forward_signed(&self, msg: SignedMessage) -> Result<(), SignedMessage> {
+        self.sender
+            .unbounded_send(Envelope::from(msg))
+            .map_err(|err| err.into_inner().into_signed_message().unwrap())
+    }
+}
+
+impl MessageTarget for ChildRef {
+    fn tell_signed<M: Message>(&self, msg: M, sign: RefAddr) -> Result<(), M> {
+        if self.refuses_messages() {
+            debug!("ChildRef({}): Mailbox full.", self.id());
+            return Err(msg);
+        }
+
+        let env = Envelope::new_with_sign(BastionMessage::tell(msg), sign);
+        self.send(env).map_err(|env| env.into_msg().unwrap())
+    }
+
+    fn ask_signed<M: Message>(&self, msg: M, sign: RefAddr) -> Result<Answer, M> {
+        if self.refuses_messages() {
+            debug!("ChildRef({}): Mailbox full.", self.id());
+            return Err(msg);
+        }
+        let permit = match self.inflight_permit() {
+            Ok(permit) => permit,
+            Err(()) => {
+                debug!("ChildRef({}): Too many asks in flight.", self.id());
+                return Err(msg);
+            }
+        };
+
+        // The answer is signed by the child.
+        let (msg, answer) = BastionMessage::ask(msg, self.addr());
+        let env = Envelope::new_with_sign(msg, sign);
+        self.send(env).map_err(|env| env.into_msg().unwrap())?;
+
+        Ok(answer.with_permit(permit))
+    }
+
+    fn forward_signed(&self, mut msg: SignedMessage) -> Result<(), SignedMessage> {
+        if self.refuses_messages() {
+            debug!("ChildRef({}): Mailbox full.", self.id());
+            return Err(msg);
+        }
+
+        // The answer is signed by the child.
+        msg.msg.set_answer_signature(self.addr());
+        self.send(Envelope::from(msg))
+            .map_err(|env| env.into_signed_message().unwrap())
+    }
+}
+
+impl MessageTarget for Distributor {
+    fn tell_signed<M: Message>(&self, msg: M, sign: RefAddr) -> Result<(), M> {
+        let env = Envelope::new_with_sign(BastionMessage::tell(msg), sign);
+        match system::current().dispatcher().select(*self, &env) {
+            Ok(Some(child)) => child.send(env).map_err(|env| env.into_msg().unwrap()),
+            _ => {
+                debug!("{:?}: No recipient to tell the message to.", self);
+                Err(env.into_msg().unwrap())
+            }
+        }
+    }
+
+    fn ask_signed<M: Message>(&self, msg: M, sign: RefAddr) -> Result<Answer, M> {
+        let env = Envelope::new_with_sign(BastionMessage::tell(msg), sign.clone());
+        match system::current().dispatcher().select(*self, &env) {
+            Ok(Some(child)) => child.ask_signed(env.into_msg().unwrap(), sign),
+            _ => {
+                debug!("{:?}: No recipient to ask the message to.", self);
+                Err(env.into_msg().unwrap())
+            }
+        }
+    }
+
+    fn forward_signed(&self, msg: SignedMessage) -> Result<(), SignedMessage> {
+        let env = Envelope::from(msg);
+        match system::current().dispatcher().select(*self, &env) {
+            Ok(Some(child)) => child.forward_signed(env.into_signed_message().unwrap()),
+            _ => {
+                debug!("{:?}: No recipient to forward the message to.", self);
+                Err(env.into_signed_message().unwrap())
+            }
+        }
+    }
+}
+
+impl MessageTarget for SupervisorRef {
+    fn tell_signed<M: Message>(&self, msg: M, sign: RefAddr) -> Result<(), M> {
+        // The supervisor broadcasts the message to its elements.
+        let env = Envelope::new_with_sign(BastionMessage::broadcast(msg), sign);
+        self.send(env).map_err(|env| env.into_msg().unwrap())
+    }
+
+    fn ask_signed<M: Message>(&self, msg: M, _sign: RefAddr) -> Result<Answer, M> {
+        trace!("SupervisorRef({}): Can't be asked a message.", self.id());
+        Err(msg)
+    }
+
+    fn forward_signed(&self, msg: SignedMessage) -> Result<(), SignedMessage> {
+        // Only broadcasts can be sent to all the supervised elements.
+        if !msg.msg.is_broadcast() {
+            trace!(
+                "SupervisorRef({}): Can't be forwarded a message.",
+                self.id()
+            );
+            return Err(msg);
+        }
+
+        self.send(Envelope::from(msg))
+            .map_err(|env| env.into_signed_message().unwrap())
+    }
+}
+
+impl From<SignedMessage> for Envelope {
+    fn from(msg: SignedMessage) -> Self {
+        Envelope::new_with_sign(BastionMessage::Message(msg.msg), msg.sign)
+    }
+}
+
 impl Envelope {
-    pub(crate) fn new(msg: BastionMessage, path: Arc<BastionPath>, sender: Sender) -> Self {
+    /// Returns the user message carried by this envelope, or `None`
+    /// if it is carrying an internal system message.
+    pub fn message(&self) -> Option<&Msg> {
+        match &self.msg {
+            BastionMessage::Message(msg) => Some(msg),
+            _ => None,
+        }
+    }
+
+    /// Returns the signature of the sender of this envelope.
+    pub fn signature(&self) -> &RefAddr {
+        &self.sign
+    }
+
+    pub(crate) fn new(mut msg: BastionMessage, path: Arc<BastionPath>, sender: Sender) -> Self {
+        if let BastionMessage::Message(msg) = &mut msg {
+            msg.log_sent();
+        }
+
         Envelope {
             msg,
             sign: RefAddr::new(path, sender),
         }
     }
 
-    pub(crate) fn new_with_sign(msg: BastionMessage, sign: RefAddr) -> Self {
+    pub(crate) fn new_with_sign(mut msg: BastionMessage, mut sign: RefAddr) -> Self {
+        if let BastionMessage::Message(msg) = &mut msg {
+            if let Some(correlation_id) = sign.correlation_id.take() {
+                msg.set_correlation_id(correlation_id);
+            }
+            // Logged once its correlation id is known.
+            msg.log_sent();
+        }
+
         Envelope { msg, sign }
     }
 
-    pub(crate) fn from_dead_letters(msg: BastionMessage) -> Self {
+    pub(crate) fn from_dead_letters(mut msg: BastionMessage) -> Self {
+        if let BastionMessage::Message(msg) = &mut msg {
+            msg.log_sent();
+        }
+
         Envelope {
             msg,
             sign: RefAddr::dead_letters(),
@@ -303,4 +834,11 @@ impl Envelope {
     pub(crate) fn into_msg<M: Message>(self) -> Option<M> {
         self.msg.into_msg()
     }
+
+    pub(crate) fn into_signed_message(self) -> Option<SignedMessage> {
+        match self.msg {
+            BastionMessage::Message(msg) => Some(SignedMessage::new(msg, self.sign)),
+            _ => None,
+        }
+    }
 }
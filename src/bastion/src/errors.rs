@@ -1,7 +1,7 @@
 //!
 //! Describes the error types that may happen within bastion.
 //! Given Bastion has a let it crash strategy, most error aren't noticeable.
-//! A ReceiveError may however be raised when calling try_recv() or try_recv_timeout()
+//! A ReceiveError may however be raised when calling recv_timeout() or try_recv_timeout()
 //! More errors may happen in the future.
 
 use crate::envelope::Envelope;
@@ -15,13 +15,15 @@ use thiserror::Error;
 
 #[derive(Debug)]
 /// These errors happen
-/// when [`try_recv`] or [`try_recv_timeout`] are invoked
+/// when [`recv_timeout`] or [`try_recv_timeout`] are invoked
 ///
-/// [`try_recv`]: crate::context::BastionContext::try_recv
+/// [`recv_timeout`]: crate::context::BastionContext::recv_timeout
 /// [`try_recv_timeout`]: crate::context::BastionContext::try_recv_timeout
 pub enum ReceiveError {
     /// We didn't receive a message on time
     Timeout(Duration),
+    /// The element is being stopped and its mailbox is empty
+    Stopped,
     /// Generic error. Not used yet
     Other,
 }
@@ -45,6 +47,123 @@ pub enum SendError {
     #[error("Distributor has 0 Recipients")]
     /// The distributor we're trying to dispatch messages to has no recipients
     EmptyRecipient,
+    #[error("couldn't ask message. Too many asks are waiting for an answer.")]
+    /// The children group of the recipient reached its limit of asks
+    /// waiting for an answer (see [`Children::with_inflight_limit`])
+    ///
+    /// [`Children::with_inflight_limit`]: crate::children::Children::with_inflight_limit
+    InflightLimit(Msg),
+    #[error("the question was dropped without being answered.")]
+    /// The recipient of a question dropped it without answering it
+    NoAnswer,
+    #[error("the answer wasn't received within {0:?}.")]
+    /// The answer wasn't received within the duration given to
+    /// [`Answer::timeout`]
+    ///
+    /// [`Answer::timeout`]: crate::message::Answer::timeout
+    Timeout(Duration),
+    #[error("received an answer of an unexpected type.")]
+    /// The answer isn't of the type given to [`Answer::into_typed`]
+    ///
+    /// [`Answer::into_typed`]: crate::message::Answer::into_typed
+    UnexpectedAnswer(Msg),
+    #[error("No known node named {0}")]
+    /// The node the message is sent to wasn't connected to using
+    /// [`Bastion::connect`]
+    ///
+    /// [`Bastion::connect`]: crate::Bastion::connect
+    UnknownNode(String),
+}
+
+#[derive(Error, Debug)]
+/// `RequestError`s occur when a request (e.g. using
+/// [`Distributor::request`]) didn't get a successful reply
+///
+/// [`Distributor::request`]: crate::distributor::Distributor::request
+pub enum RequestError {
+    #[error("the recipient replied with an error: {0}")]
+    /// The recipient replied with an error using
+    /// [`AnswerSender::reply_err`]
+    ///
+    /// [`AnswerSender::reply_err`]: crate::message::AnswerSender::reply_err
+    Remote(String),
+    #[error(transparent)]
+    /// The question couldn't be sent or its reply couldn't be
+    /// received
+    Send(#[from] SendError),
+}
+
+#[cfg(feature = "wire")]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+/// `RegistryError`s occur when registering a message type in the
+/// [`MessageRegistry`] conflicts with the types already registered
+///
+/// [`MessageRegistry`]: crate::wire::MessageRegistry
+pub enum RegistryError {
+    #[error("{type_name} is already registered as {name} v{version}.")]
+    /// The type is already registered with another name or version
+    AlreadyRegistered {
+        /// The name of the type (see [`std::any::type_name`])
+        type_name: &'static str,
+        /// The name the type is registered with
+        name: String,
+        /// The version the type is registered with
+        version: u32,
+    },
+    #[error("the name {0} is already registered for another type.")]
+    /// Another type is already registered with the same name
+    NameTaken(String),
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+/// `DispatchError`s occur when building a [`Dispatch`] whose arms
+/// don't handle distinct messages
+///
+/// [`Dispatch`]: crate::message::Dispatch
+pub enum DispatchError {
+    #[error("two arms match the {kind} messages of type {type_name}.")]
+    /// Two arms were registered for the same kind of messages (e.g.
+    /// tells) of the same type
+    OverlappingArms {
+        /// The kind of messages both arms match ("broadcast", "tell"
+        /// or "question")
+        kind: &'static str,
+        /// The name of the type of messages both arms match
+        type_name: &'static str,
+    },
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+/// `TaskError`s occur when a task spawned using [`spawn_handle!`]
+/// didn't complete
+///
+/// [`spawn_handle!`]: crate::spawn_handle
+pub enum TaskError {
+    #[error("the task panicked: {0:?}")]
+    /// The task panicked, with the given message if the panic was
+    /// created with a string (like with `panic!` or `unwrap`)
+    Panicked(Option<String>),
+    #[error("the task was cancelled.")]
+    /// The task was cancelled using [`TaskHandle::cancel`]
+    ///
+    /// [`TaskHandle::cancel`]: crate::executor::TaskHandle::cancel
+    Cancelled,
+}
+
+impl SendError {
+    pub(crate) fn inflight_limit(env: Envelope) -> Self {
+        match env.msg {
+            BastionMessage::Message(msg) => Self::InflightLimit(msg),
+            other => Self::Other(anyhow::anyhow!("{:?}", other)),
+        }
+    }
+
+    pub(crate) fn mailbox_full(env: Envelope) -> Self {
+        match env.msg {
+            BastionMessage::Message(msg) => Self::Full(msg),
+            other => Self::Other(anyhow::anyhow!("{:?}", other)),
+        }
+    }
 }
 
 impl From<TrySendError<Envelope>> for SendError {
@@ -0,0 +1,208 @@
+//!
+//! The lifecycle events of the elements of a system (children
+//! starting, failing and restarting, groups stopping, supervisors
+//! escalating failures, children subscribing to distributors...),
+//! streamed by [`Bastion::events`].
+//!
+//! [`Bastion::events`]: crate::Bastion::events
+use crate::child_ref::ChildRef;
+use crate::context::BastionId;
+use crate::distributor::Distributor;
+use crate::health::FailureKind;
+use crate::message::CorrelationId;
+use crate::supervisor::ChildFailure;
+use futures::channel::mpsc;
+#[cfg(feature = "remote")]
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::trace;
+
+#[derive(Debug, Clone)]
+/// An event in the lifecycle of the elements of a system, emitted
+/// by the stream returned by [`Bastion::events`].
+///
+/// [`Bastion::events`]: crate::Bastion::events
+pub enum SystemEvent {
+    /// A children group was launched.
+    GroupStarted {
+        /// The identifier of the children group.
+        group: BastionId,
+        /// The name of the children group.
+        name: String,
+    },
+    /// A children group stopped, along with its elements.
+    GroupStopped {
+        /// The identifier of the children group.
+        group: BastionId,
+        /// The name of the children group.
+        name: String,
+    },
+    /// An element of a children group started (or was restarted).
+    ChildStarted {
+        /// The identifier of the children group of the element.
+        group: BastionId,
+        /// The identifier of the element.
+        child: BastionId,
+    },
+    /// An element of a children group failed. Its supervisor will
+    /// handle the failure (e.g. restart it).
+    ChildFailed {
+        /// The identifier of the children group of the element.
+        group: BastionId,
+        /// The identifier of the element.
+        child: BastionId,
+        /// Why the element failed.
+        reason: FailureKind,
+    },
+    /// An element of a children group which failed (or was asked to
+    /// restart) was restarted.
+    ChildRestarted {
+        /// The identifier of the children group of the element.
+        group: BastionId,
+        /// The identifier of the restarted element.
+        child: BastionId,
+    },
+    /// An element of a children group has been handling a message
+    /// for longer than the group's handler warning threshold (see
+    /// [`Children::with_handler_warn_threshold`]). It is emitted
+    /// once per message.
+    ///
+    /// [`Children::with_handler_warn_threshold`]: crate::children::Children::with_handler_warn_threshold
+    SlowHandler {
+        /// The identifier of the children group of the element.
+        group: BastionId,
+        /// The identifier of the element.
+        child: BastionId,
+        /// The name of the type of the message (see
+        /// [`std::any::type_name`]).
+        message: &'static str,
+        /// The correlation id of the message.
+        correlation_id: CorrelationId,
+        /// How long the element had been handling the message.
+        elapsed: Duration,
+    },
+    /// A supervisor gave up and escalated a failure to its parent
+    /// supervisor (or to the fatal handler of the system if it
+    /// hasn't any).
+    SupervisorEscalated {
+        /// The identifier of the supervisor.
+        supervisor: BastionId,
+        /// The escalated failure.
+        failure: ChildFailure,
+    },
+    /// A child subscribed to a distributor.
+    DistributorSubscribed {
+        /// The distributor.
+        distributor: Distributor,
+        /// The child which subscribed.
+        child: ChildRef,
+    },
+    /// A child unsubscribed from a distributor, or stopped.
+    DistributorUnsubscribed {
+        /// The distributor.
+        distributor: Distributor,
+        /// The child which unsubscribed.
+        child: ChildRef,
+    },
+    #[cfg(feature = "remote")]
+    /// The connection with another node was refused, because the
+    /// node wasn't authenticated or its TLS handshake failed (see
+    /// [`RemoteConfig`]).
+    ///
+    /// [`RemoteConfig`]: crate::remote::RemoteConfig
+    ConnectionRejected {
+        /// The address of the node.
+        peer: SocketAddr,
+        /// Why the connection was refused.
+        reason: String,
+    },
+    #[cfg(feature = "remote")]
+    /// A message sent by another node was dropped, because the node
+    /// isn't authorized to send messages to the distributor (see
+    /// [`Authenticator::authorize`]).
+    ///
+    /// [`Authenticator::authorize`]: crate::security::Authenticator::authorize
+    MessageRejected {
+        /// The address of the node.
+        peer: SocketAddr,
+        /// The name of the distributor.
+        distributor: String,
+    },
+    #[cfg(feature = "remote")]
+    /// The connection to a node connected using
+    /// [`Bastion::connect`] (or to a member of the cluster) was
+    /// opened.
+    ///
+    /// [`Bastion::connect`]: crate::Bastion::connect
+    NodeUp {
+        /// The name the node is registered under.
+        node: String,
+        /// The address of the node.
+        addr: SocketAddr,
+    },
+    #[cfg(feature = "remote")]
+    /// The connection to a node was lost. It is opened again
+    /// following the [`ReconnectPolicy`] of the system.
+    ///
+    /// [`ReconnectPolicy`]: crate::remote::ReconnectPolicy
+    NodeDown {
+        /// The name the node is registered under.
+        node: String,
+        /// The address of the node.
+        addr: SocketAddr,
+    },
+}
+
+#[derive(Debug, Default)]
+// The subscribers to the events of a system.
+pub(crate) struct EventBus {
+    subscribers: Mutex<Vec<mpsc::UnboundedSender<SystemEvent>>>,
+}
+
+impl EventBus {
+    pub(crate) fn subscribe(&self) -> mpsc::UnboundedReceiver<SystemEvent> {
+        let (sender, receiver) = mpsc::unbounded();
+        // FIXME: panics
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    pub(crate) fn publish(&self, event: SystemEvent) {
+        // FIXME: panics
+        let mut subscribers = self.subscribers.lock().unwrap();
+        if subscribers.is_empty() {
+            return;
+        }
+
+        trace!("EventBus: Publishing {:?}", event);
+        // Forget about the subscribers whose stream was dropped.
+        subscribers.retain(|sender| sender.unbounded_send(event.clone()).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    #[test]
+    fn test_event_bus_forgets_dropped_subscribers() {
+        let bus = EventBus::default();
+        let mut kept = bus.subscribe();
+        let dropped = bus.subscribe();
+        drop(dropped);
+
+        let id = BastionId::new();
+        bus.publish(SystemEvent::ChildStarted {
+            group: id.clone(),
+            child: id.clone(),
+        });
+        assert_eq!(bus.subscribers.lock().unwrap().len(), 1);
+
+        match futures::executor::block_on(kept.next()) {
+            Some(SystemEvent::ChildStarted { child, .. }) => assert_eq!(child, id),
+            event => panic!("Unexpected event: {:?}", event),
+        }
+    }
+}
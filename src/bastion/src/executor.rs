@@ -1,12 +1,398 @@
 //! A module that exposes the functions used under the hoods from `bastion`s macros: `spawn!`, `run!`
 //! and `blocking!`.
+//!
+//! The tasks are run by bastion's executor by default, but can be
+//! run by any other runtime implementing [`Executor`], given to
+//! [`Bastion::init_with_executor`].
+//!
+//! [`Bastion::init_with_executor`]: crate::Bastion::init_with_executor
+use crate::blocking_pool::BlockingPool;
+pub use crate::blocking_pool::{BlockingPoolConfig, BlockingPoolStats};
+use crate::child::panic_message;
+use crate::errors::TaskError;
+use crate::system;
+use crate::testing::TestRuntime;
+#[cfg(not(target_arch = "wasm32"))]
+use bastion_executor::load_balancer::{self, SmpStats};
+use futures::future::{self, AbortHandle, Aborted, BoxFuture};
+use futures::FutureExt;
+use futures_timer::Delay;
+use lightproc::lightproc::LightProc;
 pub use lightproc::proc_stack::ProcStack;
 use lightproc::recoverable_handle::RecoverableHandle;
+use once_cell::sync::{Lazy, OnceCell};
+use std::fmt::{self, Debug, Formatter};
 use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+static EXECUTOR: OnceCell<Arc<dyn Executor>> = OnceCell::new();
+
+type TaskPanicHook = Arc<dyn Fn(Option<&str>) + Send + Sync>;
+
+// The closure called when a task spawned using `spawn_handle`
+// panics (see `Bastion::on_task_panic`).
+static TASK_PANIC_HOOK: Lazy<RwLock<Option<TaskPanicHook>>> = Lazy::new(RwLock::default);
+
+// The amount of tasks spawned since the process started, and of the
+// ones which completed or were cancelled since then (see
+// `Bastion::metrics`).
+static TASKS_SPAWNED: AtomicU64 = AtomicU64::new(0);
+static TASKS_DROPPED: AtomicU64 = AtomicU64::new(0);
+
+/// A handle to a task spawned using [`spawn_handle!`], resolving to
+/// the value the task returned.
+///
+/// It resolves to [`TaskError::Panicked`] if the task panicked
+/// (the panic is also given to the hook set using
+/// [`Bastion::on_task_panic`]), or to [`TaskError::Cancelled`] if
+/// the task was cancelled using [`cancel`].
+///
+/// Dropping the handle doesn't cancel the task.
+///
+/// [`spawn_handle!`]: crate::spawn_handle
+/// [`Bastion::on_task_panic`]: crate::Bastion::on_task_panic
+/// [`cancel`]: Self::cancel
+pub struct TaskHandle<T> {
+    handle: RecoverableHandle<Result<Result<T, Option<String>>, Aborted>>,
+    abort: AbortHandle,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A set of CPU cores, given to [`Children::with_affinity`] to pin
+/// the threads running the elements of a children group to them.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// #
+/// // The first two cores of the machine.
+/// let cores = CoreSet::new(0..2);
+/// assert_eq!(cores.cores(), &[0, 1]);
+/// ```
+///
+/// [`Children::with_affinity`]: crate::children::Children::with_affinity
+pub struct CoreSet {
+    cores: Vec<usize>,
+}
+
+/// A runtime able to run the tasks of the system, given to
+/// [`Bastion::init_with_executor`] to run bastion on another runtime
+/// than its own executor (e.g. smol, glommio or a custom runtime).
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// use futures::future::BoxFuture;
+/// use std::time::Duration;
+///
+/// #[derive(Debug)]
+/// struct ThreadPerTask;
+///
+/// impl Executor for ThreadPerTask {
+///     fn spawn(&self, task: BoxFuture<'static, ()>) {
+///         std::thread::spawn(move || futures::executor::block_on(task));
+///     }
+///
+///     fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>) {
+///         std::thread::spawn(task);
+///     }
+///
+///     fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+///         Box::pin(async move { futures_timer::Delay::new(duration).await })
+///     }
+/// }
+///
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();    
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();    
+/// # }
+/// #
+/// # fn run() {
+/// Bastion::init_with_executor(ThreadPerTask);
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+///
+/// [`Bastion::init_with_executor`]: crate::Bastion::init_with_executor
+pub trait Executor: Send + Sync + std::fmt::Debug + 'static {
+    /// Runs the given task to completion, without blocking the
+    /// caller.
+    ///
+    /// The task is short-lived: it polls a future of the system once
+    /// and is given again to this method each time the future is
+    /// woken up.
+    fn spawn(&self, task: BoxFuture<'static, ()>);
+
+    /// Runs the given task, which may block the thread running it,
+    /// to completion without blocking the caller.
+    fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>);
+
+    /// Returns a future completing once the given duration elapsed.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+impl CoreSet {
+    /// Creates a set of the cores with the given identifiers.
+    ///
+    /// # Arguments
+    ///
+    /// * `cores` - The identifiers of the cores, from `0` to the
+    ///     amount of cores of the machine (excluded).
+    pub fn new(cores: impl IntoIterator<Item = usize>) -> Self {
+        let mut cores: Vec<_> = cores.into_iter().collect();
+        cores.sort_unstable();
+        cores.dedup();
+
+        CoreSet { cores }
+    }
+
+    /// Returns the set of every core of the machine, or `None` if
+    /// they couldn't be retrieved.
+    pub fn available() -> Option<Self> {
+        Some(CoreSet::new(core_ids()?))
+    }
+
+    /// Returns the identifiers of the cores of the set.
+    pub fn cores(&self) -> &[usize] {
+        &self.cores
+    }
+
+    /// Returns the amount of cores of the set.
+    pub fn len(&self) -> usize {
+        self.cores.len()
+    }
+
+    /// Returns whether the set doesn't contain any core.
+    pub fn is_empty(&self) -> bool {
+        self.cores.is_empty()
+    }
+}
+
+impl<T> TaskHandle<T> {
+    /// Cancels the task, which stops being polled. It is dropped
+    /// the next time it would have been polled.
+    pub fn cancel(&self) {
+        self.abort.abort();
+    }
+}
+
+impl<T> Future for TaskHandle<T> {
+    type Output = Result<T, TaskError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        match Pin::new(&mut self.handle).poll(cx) {
+            Poll::Ready(Some(Ok(Ok(output)))) => Poll::Ready(Ok(output)),
+            Poll::Ready(Some(Ok(Err(message)))) => Poll::Ready(Err(TaskError::Panicked(message))),
+            Poll::Ready(Some(Err(Aborted))) => Poll::Ready(Err(TaskError::Cancelled)),
+            Poll::Ready(None) => Poll::Ready(Err(TaskError::Panicked(None))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<T> Debug for TaskHandle<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("TaskHandle").finish()
+    }
+}
+
+// Sets the closure called when a task spawned using `spawn_handle`
+// panics, replacing the previous one.
+pub(crate) fn set_task_panic_hook(hook: TaskPanicHook) {
+    *TASK_PANIC_HOOK.write().unwrap() = Some(hook);
+}
+
+// Sets the executor running the tasks of the system, returning
+// `false` if one was already set.
+pub(crate) fn set_executor(executor: Arc<dyn Executor>) -> bool {
+    debug!("Executor: Using {:?}.", executor);
+    EXECUTOR.set(executor).is_ok()
+}
+
+// Returns the executor given to `Bastion::init_with_executor`, if
+// any.
+#[cfg(not(target_arch = "wasm32"))]
+fn executor() -> Option<&'static Arc<dyn Executor>> {
+    EXECUTOR.get()
+}
+
+// The browser has no threads to run bastion's executor on, so the
+// tasks are run on its event loop unless another executor was given
+// to `Bastion::init_with_executor` before.
+#[cfg(target_arch = "wasm32")]
+fn executor() -> Option<&'static Arc<dyn Executor>> {
+    Some(EXECUTOR.get_or_init(|| {
+        debug!("Executor: Using the event loop of the browser.");
+        Arc::new(crate::wasm::WasmExecutor) as Arc<dyn Executor>
+    }))
+}
+
+// Returns the identifiers of the cores of the machine.
+#[cfg(not(target_arch = "wasm32"))]
+fn core_ids() -> Option<Vec<usize>> {
+    let cores = bastion_executor::placement::get_core_ids()?;
+    Some(cores.into_iter().map(|core| core.id).collect())
+}
+
+// The cores aren't known in the browser.
+#[cfg(target_arch = "wasm32")]
+fn core_ids() -> Option<Vec<usize>> {
+    None
+}
+
+// Counts a task as live until it is dropped.
+struct LiveTask;
+
+// Wraps a future spawned by the system so that it is counted as a
+// live task until it is dropped, and polled with the system it was
+// spawned from as the current one.
+fn task<F: Future>(future: F) -> impl Future<Output = F::Output> {
+    let live = LiveTask::start();
+    system::scoped(async move {
+        let _live = live;
+        future.await
+    })
+}
+
+// Returns the amount of tasks spawned since the process started, and
+// the amount of them which are still live.
+pub(crate) fn task_counts() -> (u64, u64) {
+    let dropped = TASKS_DROPPED.load(Ordering::Relaxed);
+    let spawned = TASKS_SPAWNED.load(Ordering::Relaxed);
+    (spawned, spawned.saturating_sub(dropped))
+}
+
+// Returns the amount of tasks queued on each core by bastion's
+// executor, or nothing if the tasks are run by another executor.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn run_queues() -> Vec<usize> {
+    if executor().is_some() {
+        return Vec::new();
+    }
+
+    let mut loads = load_balancer::stats().get_sorted_load().to_vec();
+    loads.sort_by_key(|(core, _)| *core);
+    loads.into_iter().map(|(_, load)| load).collect()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn run_queues() -> Vec<usize> {
+    Vec::new()
+}
+
+// Spawns the future with the given stack, on the executor given to
+// `Bastion::init_with_executor` if any.
+pub(crate) fn spawn_with<F, T>(future: F, stack: ProcStack) -> RecoverableHandle<T>
+where
+    F: Future<Output = T> + Send + 'static,
+    T: Send + 'static,
+{
+    let future = task(future);
+    match executor() {
+        Some(executor) => {
+            let executor = executor.clone();
+            let schedule = move |proc: LightProc| executor.spawn(async move { proc.run() }.boxed());
+            let (proc, handle) = LightProc::recoverable(future, schedule, stack);
+            proc.schedule();
+            handle
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        None => bastion_executor::pool::spawn(future, stack),
+        #[cfg(target_arch = "wasm32")]
+        None => unreachable!(),
+    }
+}
+
+// Spawns the future with the given stack on the threads dedicated to
+// a children group (see `Children::with_dedicated_executor`).
+pub(crate) fn spawn_on<F, T>(
+    pool: &Arc<BlockingPool>,
+    future: F,
+    stack: ProcStack,
+) -> RecoverableHandle<T>
+where
+    F: Future<Output = T> + Send + 'static,
+    T: Send + 'static,
+{
+    let future = task(future);
+    let pool = pool.clone();
+    let schedule = move |proc: LightProc| pool.execute(Box::new(move || proc.run()));
+    let (proc, handle) = LightProc::recoverable(future, schedule, stack);
+    proc.schedule();
+    handle
+}
+
+/// Returns a future completing once the given duration elapsed,
+/// using the executor given to [`Bastion::init_with_executor`] if
+/// any.
+///
+/// [`Bastion::init_with_executor`]: crate::Bastion::init_with_executor
+pub fn sleep(duration: Duration) -> BoxFuture<'static, ()> {
+    match executor() {
+        Some(executor) => executor.sleep(duration),
+        None => Delay::new(duration).boxed(),
+    }
+}
+
+// A future completing once the given duration elapsed (see `sleep`),
+// which can be stored by the types implementing `Debug`.
+pub(crate) struct Deadline(BoxFuture<'static, ()>);
+
+pub(crate) fn deadline(duration: Duration) -> Deadline {
+    Deadline(sleep(duration))
+}
+
+impl Future for Deadline {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        self.0.as_mut().poll(cx)
+    }
+}
+
+impl Debug for Deadline {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Deadline").finish()
+    }
+}
+
+impl LiveTask {
+    fn start() -> Self {
+        TASKS_SPAWNED.fetch_add(1, Ordering::Relaxed);
+        LiveTask
+    }
+}
+
+impl Drop for LiveTask {
+    fn drop(&mut self) {
+        TASKS_DROPPED.fetch_add(1, Ordering::Relaxed);
+    }
+}
 
 /// Spawns a blocking task, which will run on the blocking thread pool,
 /// and returns the handle.
 ///
+/// The task runs on the executor given to
+/// [`Bastion::init_with_executor`] if any, or else on the pool
+/// configured using [`Bastion::blocking_pool_config`] if it was.
+///
 /// # Example
 /// ```
 /// # use std::{thread, time};
@@ -28,12 +414,37 @@ use std::future::Future;
 /// });
 /// # }
 /// ```
+///
+/// [`Bastion::blocking_pool_config`]: crate::Bastion::blocking_pool_config
+/// [`Bastion::init_with_executor`]: crate::Bastion::init_with_executor
 pub fn blocking<F, R>(future: F) -> RecoverableHandle<R>
 where
     F: Future<Output = R> + Send + 'static,
     R: Send + 'static,
 {
-    bastion_executor::blocking::spawn_blocking(future, lightproc::proc_stack::ProcStack::default())
+    let future = task(future);
+    match executor() {
+        Some(executor) => {
+            let executor = executor.clone();
+            let schedule =
+                move |proc: LightProc| executor.spawn_blocking(Box::new(move || proc.run()));
+            let (proc, handle) = LightProc::recoverable(future, schedule, ProcStack::default());
+            proc.schedule();
+            handle
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        None => match BlockingPool::get() {
+            Some(pool) => {
+                let schedule = move |proc: LightProc| pool.execute(Box::new(move || proc.run()));
+                let (proc, handle) = LightProc::recoverable(future, schedule, ProcStack::default());
+                proc.schedule();
+                handle
+            }
+            None => bastion_executor::blocking::spawn_blocking(future, ProcStack::default()),
+        },
+        #[cfg(target_arch = "wasm32")]
+        None => unreachable!(),
+    }
 }
 
 /// Block the current thread until passed
@@ -76,7 +487,14 @@ pub fn run<F, T>(future: F) -> T
 where
     F: Future<Output = T>,
 {
-    bastion_executor::run::run(future, lightproc::proc_stack::ProcStack::default())
+    match TestRuntime::installed() {
+        Some(runtime) => runtime.block_on(future),
+        #[cfg(not(target_arch = "wasm32"))]
+        None => bastion_executor::run::run(future, lightproc::proc_stack::ProcStack::default()),
+        // Blocks the event loop of the browser (see the `wasm` module).
+        #[cfg(target_arch = "wasm32")]
+        None => futures::executor::block_on(future),
+    }
 }
 
 /// Spawn a given future onto the executor from the global level.
@@ -108,5 +526,63 @@ where
     F: Future<Output = T> + Send + 'static,
     T: Send + 'static,
 {
-    bastion_executor::pool::spawn(future, lightproc::proc_stack::ProcStack::default())
+    spawn_with(future, ProcStack::default())
+}
+
+/// Spawns a given future onto the executor from the global level,
+/// returning a [`TaskHandle`] to await its output or cancel it.
+///
+/// Unlike with [`spawn`], a panic of the future is logged, given to
+/// the hook set using [`Bastion::on_task_panic`] and returned by the
+/// handle as a [`TaskError::Panicked`].
+///
+/// # Example
+/// ```
+/// # use bastion::prelude::*;
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();    
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();    
+/// # }
+/// #
+/// # fn run() {
+/// use bastion::executor::{run, spawn_handle};
+/// let handle = spawn_handle(async {
+///     panic!("test");
+/// });
+/// assert_eq!(
+///     run(handle),
+///     Err(TaskError::Panicked(Some("test".to_string())))
+/// );
+/// # }
+/// ```
+///
+/// [`Bastion::on_task_panic`]: crate::Bastion::on_task_panic
+pub fn spawn_handle<F, T>(future: F) -> TaskHandle<T>
+where
+    F: Future<Output = T> + Send + 'static,
+    T: Send + 'static,
+{
+    let task = AssertUnwindSafe(future).catch_unwind().map(|result| {
+        result.map_err(|payload| {
+            let message = panic_message(&*payload);
+            warn!("Executor: Task panicked: {:?}", message);
+            if let Some(hook) = TASK_PANIC_HOOK.read().unwrap().clone() {
+                hook(message.as_deref());
+            }
+
+            message
+        })
+    });
+    let (task, abort) = future::abortable(task);
+
+    TaskHandle {
+        handle: spawn(task),
+        abort,
+    }
 }
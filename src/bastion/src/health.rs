@@ -0,0 +1,641 @@
+//!
+//! Failure telemetry shared by all the elements of a children group.
+//!
+//! Every children group records the failures of its elements (by
+//! [`FailureKind`]) and the restarts that followed them in a single
+//! place. It is the single source of truth for the
+//! subsystems that need to know whether a group is crashing: the
+//! autoscaler doesn't scale up a [`HealthStatus::Failing`] group, and
+//! health checks can read a [`HealthReport`] using
+//! [`ChildrenRef::health`].
+//!
+//! The health of a group doesn't change how messages are routed to
+//! its elements: the dispatchers and distributors keep sending
+//! messages to the elements of a failing group, so senders that
+//! should back off have to check its report themselves.
+//!
+//! The health of the whole system, aggregating the one of its
+//! groups with the state of its distributors and executor, is
+//! returned by [`Bastion::health`] (see [`SystemHealth`]).
+//!
+//! [`ChildrenRef::health`]: crate::children_ref::ChildrenRef::health
+//! [`Bastion::health`]: crate::Bastion::health
+use crate::addresses::ADDRESSES;
+use crate::blocking_pool::{BlockingPool, BlockingPoolStats};
+use crate::context::BastionId;
+use crate::executor;
+use crate::system::GlobalSystem;
+use crate::time::{self, Instant};
+use crate::tree::ElementState;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{trace, warn};
+
+// The amount of tasks queued on each core above which the executor
+// is saturated.
+const SATURATED_RUN_QUEUE: usize = 1_024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The category of a failure of an element of a children group.
+pub enum FailureKind {
+    /// The element's future panicked.
+    Panicked,
+    /// The element's future returned an error.
+    Errored,
+    /// The element's future didn't yield for longer than the
+    /// group's heartbeat timeout (e.g. because it is blocked or
+    /// stuck in a loop), so it was killed.
+    Unresponsive,
+    /// The element's future didn't finish within the group's exec
+    /// timeout.
+    TimedOut,
+    /// An element linked to the element failed (see
+    /// [`ChildRef::link`]).
+    ///
+    /// [`ChildRef::link`]: crate::child_ref::ChildRef::link
+    Linked,
+    /// The element was killed using [`ChildRef::kill_with_reason`].
+    ///
+    /// [`ChildRef::kill_with_reason`]: crate::child_ref::ChildRef::kill_with_reason
+    Killed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The health of a children group, derived from its failure rate
+/// using its [`HealthPolicy`].
+pub enum HealthStatus {
+    /// The failure rate is below the degraded threshold.
+    Healthy,
+    /// The failure rate is above the degraded threshold, but below
+    /// the failing one.
+    Degraded,
+    /// The failure rate is above the failing threshold.
+    Failing,
+}
+
+#[derive(Debug, Clone)]
+/// Defines how the failure rate of a children group is computed
+/// and which rates make it degraded or failing.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use std::time::Duration;
+/// #
+/// let policy = HealthPolicy::default()
+///     .with_window(Duration::from_secs(30))
+///     .with_degraded_threshold(0.5)
+///     .with_failing_threshold(2.0);
+/// ```
+pub struct HealthPolicy {
+    window: Duration,
+    degraded_threshold: f64,
+    failing_threshold: f64,
+}
+
+impl HealthPolicy {
+    /// Sets the duration of the sliding window over which the
+    /// failure and restart rates are computed. Defaults to 10
+    /// seconds.
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets the number of failures per second above which the
+    /// group is [`HealthStatus::Degraded`]. Defaults to `0.1`.
+    pub fn with_degraded_threshold(mut self, failures_per_sec: f64) -> Self {
+        self.degraded_threshold = failures_per_sec;
+        self
+    }
+
+    /// Sets the number of failures per second above which the
+    /// group is [`HealthStatus::Failing`]. Defaults to `1.0`.
+    pub fn with_failing_threshold(mut self, failures_per_sec: f64) -> Self {
+        self.failing_threshold = failures_per_sec;
+        self
+    }
+
+    /// Returns the duration of the sliding window.
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+}
+
+impl Default for HealthPolicy {
+    fn default() -> Self {
+        HealthPolicy {
+            window: Duration::from_secs(10),
+            degraded_threshold: 0.1,
+            failing_threshold: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A snapshot of the health of a children group.
+pub struct HealthReport {
+    status: HealthStatus,
+    failures_per_sec: f64,
+    restarts_per_sec: f64,
+    panics: u64,
+    errors: u64,
+    unresponsive: u64,
+    timeouts: u64,
+    linked: u64,
+    killed: u64,
+    restarts: u64,
+}
+
+impl HealthReport {
+    /// Returns the status of the group.
+    pub fn status(&self) -> HealthStatus {
+        self.status
+    }
+
+    /// Returns the number of failures per second over the
+    /// policy's window.
+    pub fn failures_per_sec(&self) -> f64 {
+        self.failures_per_sec
+    }
+
+    /// Returns the number of restarts per second over the
+    /// policy's window.
+    pub fn restarts_per_sec(&self) -> f64 {
+        self.restarts_per_sec
+    }
+
+    /// Returns the total number of failures of the given kind
+    /// since the group was created.
+    pub fn failures(&self, kind: FailureKind) -> u64 {
+        match kind {
+            FailureKind::Panicked => self.panics,
+            FailureKind::Errored => self.errors,
+            FailureKind::Unresponsive => self.unresponsive,
+            FailureKind::TimedOut => self.timeouts,
+            FailureKind::Linked => self.linked,
+            FailureKind::Killed => self.killed,
+        }
+    }
+
+    /// Returns the total number of restarts since the group was
+    /// created.
+    pub fn restarts(&self) -> u64 {
+        self.restarts
+    }
+
+    /// Returns `true` if the group is [`HealthStatus::Failing`].
+    pub fn is_failing(&self) -> bool {
+        self.status == HealthStatus::Failing
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A snapshot of the health of a system, returned by
+/// [`Bastion::health`], which can back the liveness and readiness
+/// probes of the process (e.g. on Kubernetes).
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// # Bastion::start();
+/// let health = Bastion::health();
+/// if !health.is_ready() {
+///     for group in health.crash_looping() {
+///         println!("{} is crash-looping.", group.name());
+///     }
+///     for group in health.under_replicated() {
+///         println!(
+///             "{} runs {} of {} elements.",
+///             group.name(),
+///             group.elements(),
+///             group.redundancy(),
+///         );
+///     }
+///     for distributor in health.idle_distributors() {
+///         println!("{} has no subscriber.", distributor);
+///     }
+/// }
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+///
+/// [`Bastion::health`]: crate::Bastion::health
+pub struct SystemHealth {
+    groups: Vec<GroupHealthSummary>,
+    idle_distributors: Vec<String>,
+    run_queues: Vec<usize>,
+    blocking_pool: Option<BlockingPoolStats>,
+    executor_saturated: bool,
+}
+
+#[derive(Debug, Clone)]
+/// The health of a running children group, in a [`SystemHealth`].
+pub struct GroupHealthSummary {
+    id: BastionId,
+    name: String,
+    elements: usize,
+    redundancy: usize,
+    report: HealthReport,
+}
+
+impl SystemHealth {
+    /// Returns the health of every running children group.
+    pub fn groups(&self) -> &[GroupHealthSummary] {
+        &self.groups
+    }
+
+    /// Returns the groups whose children are crash-looping, i.e.
+    /// which are [`HealthStatus::Failing`] according to their
+    /// [`HealthPolicy`].
+    pub fn crash_looping(&self) -> Vec<&GroupHealthSummary> {
+        self.groups
+            .iter()
+            .filter(|group| group.report.is_failing())
+            .collect()
+    }
+
+    /// Returns the groups running fewer elements than their
+    /// redundancy (e.g. because some are restarting).
+    pub fn under_replicated(&self) -> Vec<&GroupHealthSummary> {
+        self.groups
+            .iter()
+            .filter(|group| group.elements < group.redundancy)
+            .collect()
+    }
+
+    /// Returns the names of the distributors which have no
+    /// subscriber, so that the messages sent to them fail.
+    pub fn idle_distributors(&self) -> &[String] {
+        &self.idle_distributors
+    }
+
+    /// Returns the amount of tasks queued on each core by the
+    /// executor, or nothing if the tasks are run by another
+    /// executor (see [`Bastion::init_with_executor`]).
+    ///
+    /// [`Bastion::init_with_executor`]: crate::Bastion::init_with_executor
+    pub fn run_queues(&self) -> &[usize] {
+        &self.run_queues
+    }
+
+    /// Returns the state of the pool of threads running the
+    /// blocking tasks, if it was started.
+    pub fn blocking_pool(&self) -> Option<BlockingPoolStats> {
+        self.blocking_pool
+    }
+
+    /// Returns `true` if the executor is saturated: every core has
+    /// more than 1024 tasks queued, or blocking tasks are
+    /// waiting while the blocking pool runs its maximum amount of
+    /// threads.
+    pub fn is_executor_saturated(&self) -> bool {
+        self.executor_saturated
+    }
+
+    /// Returns `true` if the system is live: no group is
+    /// crash-looping and the executor isn't saturated. A liveness
+    /// probe failing on this restarts the process.
+    pub fn is_live(&self) -> bool {
+        !self.executor_saturated && self.crash_looping().is_empty()
+    }
+
+    /// Returns `true` if the system is live and ready to handle
+    /// work: every group runs all its elements and every
+    /// distributor has subscribers. A readiness probe failing on
+    /// this stops routing traffic to the process.
+    pub fn is_ready(&self) -> bool {
+        self.is_live() && self.under_replicated().is_empty() && self.idle_distributors.is_empty()
+    }
+}
+
+impl GroupHealthSummary {
+    /// Returns the identifier of the group.
+    pub fn id(&self) -> &BastionId {
+        &self.id
+    }
+
+    /// Returns the name of the group.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the amount of running elements of the group.
+    pub fn elements(&self) -> usize {
+        self.elements
+    }
+
+    /// Returns the amount of elements the group should run.
+    pub fn redundancy(&self) -> usize {
+        self.redundancy
+    }
+
+    /// Returns the failure telemetry of the group (see
+    /// [`ChildrenRef::health`]).
+    ///
+    /// [`ChildrenRef::health`]: crate::children_ref::ChildrenRef::health
+    pub fn report(&self) -> &HealthReport {
+        &self.report
+    }
+}
+
+// Checks the health of the running groups, the distributors and
+// the executor of the system.
+pub(crate) fn check(system: &GlobalSystem) -> SystemHealth {
+    trace!("Health: Checking the system.");
+    let groups = system
+        .tree()
+        .snapshot()
+        .children_groups()
+        .into_iter()
+        .filter(|group| group.state() == ElementState::Running)
+        .filter_map(|group| {
+            // The group might have stopped since the snapshot.
+            let children = ADDRESSES.get(group.id())?;
+            Some(GroupHealthSummary {
+                id: group.id().clone(),
+                name: group.name().to_string(),
+                elements: group.elements().len(),
+                redundancy: group.redundancy(),
+                report: children.health(),
+            })
+        })
+        .collect();
+
+    let idle_distributors = system
+        .dispatcher()
+        .distributors()
+        .unwrap_or_else(|error| {
+            warn!("Health: Couldn't list the distributors: {}", error);
+            Vec::new()
+        })
+        .into_iter()
+        .filter(|(_, subscribers)| *subscribers == 0)
+        .map(|(name, _)| name)
+        .collect();
+
+    let run_queues = executor::run_queues();
+    let pool = BlockingPool::get();
+    let blocking_pool = pool.map(BlockingPool::stats);
+    let executor_saturated = saturated(
+        &run_queues,
+        blocking_pool,
+        pool.map(BlockingPool::max_threads),
+    );
+
+    SystemHealth {
+        groups,
+        idle_distributors,
+        run_queues,
+        blocking_pool,
+        executor_saturated,
+    }
+}
+
+fn saturated(
+    run_queues: &[usize],
+    blocking_pool: Option<BlockingPoolStats>,
+    max_threads: Option<usize>,
+) -> bool {
+    let cores_saturated =
+        !run_queues.is_empty() && run_queues.iter().all(|len| *len > SATURATED_RUN_QUEUE);
+    let pool_saturated = match (blocking_pool, max_threads) {
+        (Some(stats), Some(max)) => stats.queued() > 0 && stats.active() >= max,
+        _ => false,
+    };
+
+    cores_saturated || pool_saturated
+}
+
+#[derive(Debug, Default)]
+struct HealthState {
+    // The recent failures, within the policy's window.
+    failures: VecDeque<(Instant, FailureKind)>,
+    // The recent restarts, within the policy's window.
+    restarts: VecDeque<Instant>,
+    total_panics: u64,
+    total_errors: u64,
+    total_unresponsive: u64,
+    total_timeouts: u64,
+    total_linked: u64,
+    total_killed: u64,
+    total_restarts: u64,
+}
+
+impl HealthState {
+    // Forgets about the events that left the window, so that a group
+    // failing for a long time without being asked for its health
+    // doesn't keep all of them.
+    fn forget_before(&mut self, now: Instant, window: Duration) {
+        while let Some((at, _)) = self.failures.front() {
+            if now.duration_since(*at) <= window {
+                break;
+            }
+            self.failures.pop_front();
+        }
+        while let Some(at) = self.restarts.front() {
+            if now.duration_since(*at) <= window {
+                break;
+            }
+            self.restarts.pop_front();
+        }
+    }
+}
+
+#[derive(Debug)]
+/// The failure telemetry of a children group, shared by the group,
+/// its elements and its [`ChildrenRef`]s.
+///
+/// [`ChildrenRef`]: crate::children_ref::ChildrenRef
+pub(crate) struct GroupHealth {
+    policy: HealthPolicy,
+    state: Mutex<HealthState>,
+}
+
+impl GroupHealth {
+    pub(crate) fn new(policy: HealthPolicy) -> Self {
+        GroupHealth {
+            policy,
+            state: Mutex::new(HealthState::default()),
+        }
+    }
+
+    pub(crate) fn record_failure(&self, kind: FailureKind) {
+        trace!("GroupHealth: Recording failure: {:?}", kind);
+        let mut state = self.state.lock().unwrap();
+        match kind {
+            FailureKind::Panicked => state.total_panics += 1,
+            FailureKind::Errored => state.total_errors += 1,
+            FailureKind::Unresponsive => state.total_unresponsive += 1,
+            FailureKind::TimedOut => state.total_timeouts += 1,
+            FailureKind::Linked => state.total_linked += 1,
+            FailureKind::Killed => state.total_killed += 1,
+        }
+        let now = time::now();
+        state.forget_before(now, self.policy.window);
+        state.failures.push_back((now, kind));
+    }
+
+    pub(crate) fn record_restart(&self) {
+        trace!("GroupHealth: Recording restart.");
+        let mut state = self.state.lock().unwrap();
+        state.total_restarts += 1;
+        let now = time::now();
+        state.forget_before(now, self.policy.window);
+        state.restarts.push_back(now);
+    }
+
+    pub(crate) fn status(&self) -> HealthStatus {
+        self.report().status
+    }
+
+    pub(crate) fn report(&self) -> HealthReport {
+        let window = self.policy.window;
+        let mut state = self.state.lock().unwrap();
+        state.forget_before(time::now(), window);
+
+        let secs = window.as_secs_f64().max(f64::EPSILON);
+        let failures_per_sec = state.failures.len() as f64 / secs;
+        let restarts_per_sec = state.restarts.len() as f64 / secs;
+        let status = if failures_per_sec > self.policy.failing_threshold {
+            HealthStatus::Failing
+        } else if failures_per_sec > self.policy.degraded_threshold {
+            HealthStatus::Degraded
+        } else {
+            HealthStatus::Healthy
+        };
+
+        HealthReport {
+            status,
+            failures_per_sec,
+            restarts_per_sec,
+            panics: state.total_panics,
+            errors: state.total_errors,
+            unresponsive: state.total_unresponsive,
+            timeouts: state.total_timeouts,
+            linked: state.total_linked,
+            killed: state.total_killed,
+            restarts: state.total_restarts,
+        }
+    }
+}
+
+impl Default for GroupHealth {
+    fn default() -> Self {
+        GroupHealth::new(HealthPolicy::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_health_is_healthy_by_default() {
+        let health = GroupHealth::default();
+        let report = health.report();
+
+        assert_eq!(report.status(), HealthStatus::Healthy);
+        assert_eq!(report.restarts(), 0);
+        assert_eq!(report.failures(FailureKind::Panicked), 0);
+    }
+
+    #[test]
+    fn test_group_health_status_follows_failure_rate() {
+        let policy = HealthPolicy::default()
+            .with_window(Duration::from_secs(1))
+            .with_degraded_threshold(1.0)
+            .with_failing_threshold(3.0);
+        let health = GroupHealth::new(policy);
+
+        health.record_failure(FailureKind::Panicked);
+        health.record_failure(FailureKind::Errored);
+        health.record_restart();
+        let report = health.report();
+        assert_eq!(report.status(), HealthStatus::Degraded);
+        assert_eq!(report.failures(FailureKind::Panicked), 1);
+        assert_eq!(report.failures(FailureKind::Errored), 1);
+        assert_eq!(report.restarts(), 1);
+
+        health.record_failure(FailureKind::Errored);
+        health.record_failure(FailureKind::Errored);
+        assert!(health.report().is_failing());
+    }
+
+    #[test]
+    fn test_group_health_counts_failures_by_kind() {
+        let health = GroupHealth::default();
+
+        health.record_failure(FailureKind::Unresponsive);
+        health.record_failure(FailureKind::TimedOut);
+        health.record_failure(FailureKind::Killed);
+        let report = health.report();
+        assert_eq!(report.failures(FailureKind::Unresponsive), 1);
+        assert_eq!(report.failures(FailureKind::TimedOut), 1);
+        assert_eq!(report.failures(FailureKind::Killed), 1);
+        assert_eq!(report.failures(FailureKind::Panicked), 0);
+        assert_eq!(report.failures(FailureKind::Errored), 0);
+    }
+
+    #[test]
+    fn test_executor_saturation() {
+        assert!(!saturated(&[], None, None));
+        assert!(!saturated(&[2_000, 10], None, None));
+        assert!(saturated(&[2_000, 1_500], None, None));
+    }
+
+    #[test]
+    fn test_group_health_forgets_old_failures() {
+        let policy = HealthPolicy::default()
+            .with_window(Duration::from_millis(50))
+            .with_failing_threshold(1.0);
+        let health = GroupHealth::new(policy);
+
+        health.record_failure(FailureKind::Panicked);
+        assert_eq!(health.status(), HealthStatus::Failing);
+
+        std::thread::sleep(Duration::from_millis(100));
+        let report = health.report();
+        assert_eq!(report.status(), HealthStatus::Healthy);
+        assert_eq!(report.failures(FailureKind::Panicked), 1);
+    }
+
+    #[test]
+    fn test_group_health_prunes_old_events_when_recording() {
+        let policy = HealthPolicy::default().with_window(Duration::from_millis(50));
+        let health = GroupHealth::new(policy);
+
+        for _ in 0..10 {
+            health.record_failure(FailureKind::Errored);
+            health.record_restart();
+        }
+
+        std::thread::sleep(Duration::from_millis(100));
+        health.record_failure(FailureKind::Errored);
+        health.record_restart();
+
+        let state = health.state.lock().unwrap();
+        assert_eq!(state.failures.len(), 1);
+        assert_eq!(state.restarts.len(), 1);
+        assert_eq!(state.total_errors, 11);
+        assert_eq!(state.total_restarts, 11);
+    }
+}
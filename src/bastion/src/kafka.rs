@@ -0,0 +1,594 @@
+//!
+//! Children groups consuming Kafka topics and publishing to them.
+//!
+//! A [`KafkaSource`] is a children group consuming a topic as part
+//! of a consumer group, which asks each record to a recipient of a
+//! distributor: the offset of the record is only committed once the
+//! recipient answered it (e.g. with `()`), so that the records which
+//! weren't processed are consumed again.
+//!
+//! A [`KafkaSink`] is a children group subscribed to a distributor,
+//! which publishes the [`KafkaRecord`]s and [`WireMessage`]s sent to
+//! the distributor to a topic, and answers the questions once the
+//! record was acknowledged by the brokers.
+//!
+//! When a broker fails or the partitions are rebalanced in a way
+//! that makes consuming or publishing fail, the element of the group
+//! fails and is restarted by its supervisor, creating its consumer
+//! or producer again.
+//!
+//! This module is only available with the `kafka` feature.
+//!
+//! [`WireMessage`]: crate::wire::WireMessage
+use crate::children::Children;
+use crate::children_ref::ChildrenRef;
+use crate::context::BastionContext;
+use crate::distributor::Distributor;
+use crate::message::{AnswerSender, ErrorReply, MessageHandler};
+use crate::wire::WireMessage;
+use crate::Bastion;
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{BaseConsumer, CommitMode, Consumer};
+use rdkafka::error::KafkaResult;
+use rdkafka::producer::{BaseProducer, BaseRecord, Producer};
+use rdkafka::{Message as _, Offset, TopicPartitionList};
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, trace, warn};
+
+// How long polling the consumer blocks at most, so that stopping the
+// element isn't delayed for long.
+const POLL_TIMEOUT: Duration = Duration::from_millis(500);
+// How long the records wait to be answered by default.
+const ACK_TIMEOUT: Duration = Duration::from_secs(30);
+// How long the records wait to be acknowledged by the brokers.
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+/// The brokers and topic used by a [`KafkaSource`] or
+/// [`KafkaSink`], along with the configuration of the Kafka clients.
+///
+/// This structure is only available with the `kafka` feature.
+pub struct KafkaConfig {
+    brokers: String,
+    topic: String,
+    group: String,
+    properties: Vec<(String, String)>,
+    ack_timeout: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A record consumed by a [`KafkaSource`], or to be published by a
+/// [`KafkaSink`].
+///
+/// This structure is only available with the `kafka` feature.
+pub struct KafkaRecord {
+    topic: String,
+    partition: i32,
+    offset: i64,
+    key: Option<Vec<u8>>,
+    value: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+/// A children group consuming a topic and asking each record to a
+/// recipient of a distributor, committing its offset once the
+/// recipient answered it.
+///
+/// The recipients receive the records as [`KafkaRecord`]s, and
+/// acknowledge them by answering them with any message but an
+/// error: a record answered with an error (see
+/// [`AnswerSender::reply_err`]), dropped or not answered in time
+/// makes the element fail, and is consumed again once the element
+/// is restarted.
+///
+/// This structure is only available with the `kafka` feature.
+///
+/// # Example
+///
+/// ```no_run
+/// # use bastion::prelude::*;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// let orders = Distributor::named("orders");
+/// Bastion::children(|children| {
+///     children
+///         .with_distributor(orders)
+///         .with_exec(|ctx: BastionContext| async move {
+///             loop {
+///                 MessageHandler::new(ctx.recv().await?)
+///                     .on_question(|record: KafkaRecord, sender| {
+///                         println!("Order: {:?}", record.value());
+///                         sender.reply(()).ok();
+///                     })
+///                     .on_fallback(|_, _| ());
+///             }
+///         })
+/// })
+/// .expect("Couldn't create the children group.");
+///
+/// let config = KafkaConfig::new("127.0.0.1:9092", "orders").with_group("shop");
+/// KafkaSource::new(config, orders)
+///     .start()
+///     .expect("Couldn't start the source.");
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+///
+/// [`AnswerSender::reply_err`]: crate::message::AnswerSender::reply_err
+pub struct KafkaSource {
+    config: Arc<KafkaConfig>,
+    distributor: Distributor,
+}
+
+#[derive(Debug, Clone)]
+/// A children group subscribed to a distributor, publishing the
+/// messages sent to the distributor to a topic.
+///
+/// The [`KafkaRecord`]s are published with their key and value,
+/// and the [`WireMessage`]s with their payload as value. The
+/// questions are answered with `()` once the record was
+/// acknowledged by the brokers, or with an error if publishing it
+/// failed.
+///
+/// This structure is only available with the `kafka` feature.
+///
+/// # Example
+///
+/// ```no_run
+/// # use bastion::prelude::*;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// # Bastion::start();
+/// let shipments = Distributor::named("shipments");
+/// let config = KafkaConfig::new("127.0.0.1:9092", "shipments");
+/// KafkaSink::new(config, shipments)
+///     .start()
+///     .expect("Couldn't start the sink.");
+///
+/// let record = KafkaRecord::new(b"{\"order\":42}".to_vec()).with_key(b"42".to_vec());
+/// shipments.tell_one(record).expect("Couldn't send the record.");
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+///
+/// [`WireMessage`]: crate::wire::WireMessage
+pub struct KafkaSink {
+    config: Arc<KafkaConfig>,
+    distributor: Distributor,
+}
+
+impl KafkaConfig {
+    /// Creates the configuration of the clients connecting to the
+    /// given brokers, consuming or publishing to the given topic.
+    ///
+    /// # Arguments
+    ///
+    /// * `brokers` - The comma-separated addresses of the brokers.
+    /// * `topic` - The topic to consume or publish to.
+    pub fn new(brokers: impl Into<String>, topic: impl Into<String>) -> Self {
+        KafkaConfig {
+            brokers: brokers.into(),
+            topic: topic.into(),
+            group: "bastion".to_string(),
+            properties: Vec::new(),
+            ack_timeout: ACK_TIMEOUT,
+        }
+    }
+
+    /// Sets the consumer group of the sources, which is `bastion` by
+    /// default. The partitions of the topic are shared by the sources
+    /// of the same group.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The name of the consumer group.
+    pub fn with_group(mut self, group: impl Into<String>) -> Self {
+        self.group = group.into();
+        self
+    }
+
+    /// Sets a property of the clients (see the [configuration of
+    /// librdkafka]), such as `security.protocol`.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The name of the property.
+    /// * `value` - The value of the property.
+    ///
+    /// [configuration of librdkafka]: https://github.com/edenhill/librdkafka/blob/master/CONFIGURATION.md
+    pub fn with_property(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.properties.push((key.into(), value.into()));
+        self
+    }
+
+    /// Sets how long the sources wait for a record to be answered
+    /// before failing, which is 30 seconds by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long a record waits to be answered.
+    pub fn with_ack_timeout(mut self, timeout: Duration) -> Self {
+        self.ack_timeout = timeout;
+        self
+    }
+
+    /// Returns the comma-separated addresses of the brokers.
+    pub fn brokers(&self) -> &str {
+        &self.brokers
+    }
+
+    /// Returns the topic consumed or published to.
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// Returns the consumer group of the sources.
+    pub fn group(&self) -> &str {
+        &self.group
+    }
+
+    fn client(&self) -> ClientConfig {
+        let mut client = ClientConfig::new();
+        client.set("bootstrap.servers", &self.brokers);
+        for (key, value) in &self.properties {
+            client.set(key, value);
+        }
+        client
+    }
+
+    fn consumer(&self) -> KafkaResult<BaseConsumer> {
+        let consumer: BaseConsumer = self
+            .client()
+            .set("group.id", &self.group)
+            // The offsets are committed once the records are answered.
+            .set("enable.auto.commit", "false")
+            .create()?;
+        consumer.subscribe(&[&self.topic])?;
+        Ok(consumer)
+    }
+
+    fn producer(&self) -> KafkaResult<BaseProducer> {
+        self.client().create()
+    }
+}
+
+impl KafkaRecord {
+    /// Creates a record with the given value and without key, to be
+    /// published by a [`KafkaSink`].
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value of the record.
+    pub fn new(value: Vec<u8>) -> Self {
+        KafkaRecord {
+            topic: String::new(),
+            partition: -1,
+            offset: -1,
+            key: None,
+            value,
+        }
+    }
+
+    /// Sets the key of the record.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key of the record.
+    pub fn with_key(mut self, key: Vec<u8>) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    /// Returns the topic the record was consumed from (or an empty
+    /// string if it wasn't consumed).
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// Returns the partition the record was consumed from (or `-1`
+    /// if it wasn't consumed).
+    pub fn partition(&self) -> i32 {
+        self.partition
+    }
+
+    /// Returns the offset of the record in its partition (or `-1` if
+    /// it wasn't consumed).
+    pub fn offset(&self) -> i64 {
+        self.offset
+    }
+
+    /// Returns the key of the record, if it has one.
+    pub fn key(&self) -> Option<&[u8]> {
+        self.key.as_deref()
+    }
+
+    /// Returns the value of the record.
+    pub fn value(&self) -> &[u8] {
+        &self.value
+    }
+
+    /// Deserializes the value of the record from JSON.
+    pub fn unpack<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_slice(&self.value)
+    }
+}
+
+impl From<WireMessage> for KafkaRecord {
+    fn from(message: WireMessage) -> Self {
+        KafkaRecord::new(message.payload().to_vec())
+    }
+}
+
+impl KafkaSource {
+    /// Creates the source consuming the topic of the configuration
+    /// and asking its records to a recipient of the distributor.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration of the consumer.
+    /// * `distributor` - The distributor the records are asked to.
+    pub fn new(config: KafkaConfig, distributor: Distributor) -> Self {
+        KafkaSource {
+            config: Arc::new(config),
+            distributor,
+        }
+    }
+
+    /// Configures the children group to run the source, allowing to
+    /// start it under a supervisor (e.g. using
+    /// [`Supervisor::children`]).
+    ///
+    /// Each element of the group consumes the topic, so the group
+    /// contains one element unless its redundancy is set afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `children` - The children group to configure.
+    ///
+    /// [`Supervisor::children`]: crate::supervisor::Supervisor::children
+    pub fn configure(self, children: Children) -> Children {
+        let name = format!("kafka-source/{}", self.config.topic);
+        children
+            .with_name(name)
+            .with_exec(move |ctx: BastionContext| {
+                let source = self.clone();
+                async move { source.run(ctx).await }
+            })
+    }
+
+    /// Starts the source in a children group supervised by the
+    /// system supervisor.
+    pub fn start(self) -> Result<ChildrenRef, ()> {
+        Bastion::children(|children| self.configure(children))
+    }
+
+    async fn run(self, ctx: BastionContext) -> Result<(), ()> {
+        let config = self.config.clone();
+        // Each restart joins the consumer group again.
+        let consumer = config.consumer().map_err(|err| {
+            warn!("Kafka({}): Couldn't create consumer: {}", config.topic, err);
+        })?;
+        debug!("Kafka({}): Consuming as {}.", config.topic, config.group);
+        let consumer = Arc::new(consumer);
+
+        loop {
+            let polling = consumer.clone();
+            let polled = ctx.spawn_blocking(move || poll(&polling)).await?;
+            let record = match polled {
+                Ok(Some(record)) => record,
+                Ok(None) => continue,
+                Err(err) => {
+                    warn!("Kafka({}): Couldn't consume: {}", config.topic, err);
+                    return Err(());
+                }
+            };
+
+            trace!(
+                "Kafka({}): Asking record {} of partition {}.",
+                config.topic,
+                record.offset,
+                record.partition
+            );
+            let (partition, offset) = (record.partition, record.offset);
+            let acked = match self.distributor.ask_one(record) {
+                Ok(answer) => answer.timeout(config.ack_timeout).await,
+                Err(err) => {
+                    warn!("Kafka({}): Couldn't ask record: {}", config.topic, err);
+                    return Err(());
+                }
+            };
+            match acked {
+                Ok(answer) if !answer.msg.is::<ErrorReply>() => (),
+                _ => {
+                    warn!(
+                        "Kafka({}): Record {} of partition {} wasn't acknowledged.",
+                        config.topic, offset, partition
+                    );
+                    return Err(());
+                }
+            }
+
+            let committing = consumer.clone();
+            let topic = config.topic.clone();
+            let committed = ctx
+                .spawn_blocking(move || commit(&committing, &topic, partition, offset))
+                .await?;
+            if let Err(err) = committed {
+                warn!("Kafka({}): Couldn't commit offset: {}", config.topic, err);
+                return Err(());
+            }
+        }
+    }
+}
+
+impl KafkaSink {
+    /// Creates the sink subscribed to the distributor and publishing
+    /// the messages sent to it to the topic of the configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration of the producer.
+    /// * `distributor` - The distributor the sink is subscribed to.
+    pub fn new(config: KafkaConfig, distributor: Distributor) -> Self {
+        KafkaSink {
+            config: Arc::new(config),
+            distributor,
+        }
+    }
+
+    /// Configures the children group to run the sink, allowing to
+    /// start it under a supervisor (e.g. using
+    /// [`Supervisor::children`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `children` - The children group to configure.
+    ///
+    /// [`Supervisor::children`]: crate::supervisor::Supervisor::children
+    pub fn configure(self, children: Children) -> Children {
+        let name = format!("kafka-sink/{}", self.config.topic);
+        children
+            .with_name(name)
+            .with_distributor(self.distributor)
+            .with_exec(move |ctx: BastionContext| {
+                let sink = self.clone();
+                async move { sink.run(ctx).await }
+            })
+    }
+
+    /// Starts the sink in a children group supervised by the system
+    /// supervisor.
+    pub fn start(self) -> Result<ChildrenRef, ()> {
+        Bastion::children(|children| self.configure(children))
+    }
+
+    async fn run(self, ctx: BastionContext) -> Result<(), ()> {
+        let config = self.config.clone();
+        // Each restart connects to the brokers again.
+        let producer = config.producer().map_err(|err| {
+            warn!("Kafka({}): Couldn't create producer: {}", config.topic, err);
+        })?;
+        debug!("Kafka({}): Publishing.", config.topic);
+        let producer = Arc::new(producer);
+
+        loop {
+            let (record, sender): (Option<KafkaRecord>, Option<AnswerSender>) =
+                MessageHandler::new(ctx.recv().await?)
+                    .on_question(|record: KafkaRecord, sender| (Some(record), Some(sender)))
+                    .on_question(|message: WireMessage, sender| {
+                        (Some(message.into()), Some(sender))
+                    })
+                    .on_tell(|record: KafkaRecord, _| (Some(record), None))
+                    .on_tell(|message: WireMessage, _| (Some(message.into()), None))
+                    .on_unknown(|unknown, _| {
+                        debug!("Kafka({}): Ignoring {}.", config.topic, unknown.type_name());
+                        (None, None)
+                    });
+            let record = match record {
+                Some(record) => record,
+                None => continue,
+            };
+
+            let publishing = producer.clone();
+            let topic = config.topic.clone();
+            let published = ctx
+                .spawn_blocking(move || publish(&publishing, &topic, &record))
+                .await?;
+            match (published, sender) {
+                (Ok(()), Some(sender)) => {
+                    sender.reply(()).ok();
+                }
+                (Ok(()), None) => (),
+                (Err(err), sender) => {
+                    warn!("Kafka({}): Couldn't publish: {}", config.topic, err);
+                    if let Some(sender) = sender {
+                        sender.reply_err(err).ok();
+                    }
+                    return Err(());
+                }
+            }
+        }
+    }
+}
+
+// Polls the consumer for the next record, returning `None` if none
+// was received in time.
+fn poll(consumer: &BaseConsumer) -> KafkaResult<Option<KafkaRecord>> {
+    let message = match consumer.poll(POLL_TIMEOUT) {
+        Some(message) => message?,
+        None => return Ok(None),
+    };
+    Ok(Some(KafkaRecord {
+        topic: message.topic().to_string(),
+        partition: message.partition(),
+        offset: message.offset(),
+        key: message.key().map(<[u8]>::to_vec),
+        value: message.payload().map(<[u8]>::to_vec).unwrap_or_default(),
+    }))
+}
+
+// Commits the offset following the record's, so that the group
+// resumes consuming after it.
+fn commit(consumer: &BaseConsumer, topic: &str, partition: i32, offset: i64) -> KafkaResult<()> {
+    let mut offsets = TopicPartitionList::new();
+    offsets.add_partition_offset(topic, partition, Offset::Offset(offset + 1))?;
+    consumer.commit(&offsets, CommitMode::Sync)
+}
+
+// Publishes the record and waits for the brokers to acknowledge it.
+fn publish(producer: &BaseProducer, topic: &str, record: &KafkaRecord) -> KafkaResult<()> {
+    let mut sent = BaseRecord::<[u8], [u8]>::to(topic).payload(&record.value[..]);
+    if let Some(key) = &record.key {
+        sent = sent.key(&key[..]);
+    }
+    producer.send(sent).map_err(|(err, _)| err)?;
+    producer.flush(PUBLISH_TIMEOUT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records() {
+        let record = KafkaRecord::new(b"{\"id\":42}".to_vec()).with_key(b"42".to_vec());
+        assert_eq!(record.key(), Some(&b"42"[..]));
+        assert_eq!(record.offset(), -1);
+        let value: serde_json::Value = record.unpack().unwrap();
+        assert_eq!(value["id"], 42);
+
+        let message = WireMessage::pack(&42_u32).unwrap();
+        let record = KafkaRecord::from(message);
+        assert_eq!(record.key(), None);
+        assert_eq!(record.unpack::<u32>().unwrap(), 42);
+    }
+}
@@ -58,34 +58,87 @@
 // Doc generation experimental features
 #![cfg_attr(feature = "docs", feature(doc_cfg))]
 
-pub use self::bastion::Bastion;
-pub use self::callbacks::Callbacks;
+pub use self::bastion::{Bastion, BastionSystem};
+pub use self::callbacks::{Callbacks, RestartContext};
 pub use self::config::Config;
+pub use bastion_macros::BastionMessage;
+
+// The browser has no threads to run bastion's executor on.
+#[cfg(all(target_arch = "wasm32", not(feature = "wasm")))]
+compile_error!("The `wasm` feature is required to run bastion on `wasm32`.");
 
 #[macro_use]
 mod macros;
 
+mod addresses;
 mod bastion;
+mod blocking_pool;
 mod broadcast;
 mod callbacks;
 mod child;
 mod config;
+mod limits;
+mod local_executor;
+mod names;
+#[cfg(feature = "prometheus")]
+mod prometheus;
+mod results;
+#[cfg(feature = "tracing")]
+mod spans;
 mod system;
+mod time;
 
+pub mod autoscale;
+pub mod behavior;
 pub mod child_ref;
 pub mod children;
 pub mod children_ref;
+#[cfg(feature = "remote")]
+pub mod cluster;
+#[cfg(feature = "remote")]
+pub mod codec;
 pub mod context;
+#[cfg(feature = "remote")]
+pub mod dead_letters;
 pub mod dispatcher;
 pub mod envelope;
+pub mod events;
 pub mod executor;
-#[cfg(not(target_os = "windows"))]
+pub mod health;
+#[cfg(not(any(target_os = "windows", target_arch = "wasm32")))]
 pub mod io;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod logging;
 pub mod message;
+pub mod metrics;
+pub mod monitor;
+#[cfg(feature = "nats")]
+pub mod nats;
+pub mod observer;
 pub mod path;
+pub mod persistence;
+pub mod profile;
+#[cfg(feature = "remote")]
+pub mod remote;
 #[cfg(feature = "scaling")]
 pub mod resizer;
+#[cfg(feature = "remote")]
+pub mod security;
+#[cfg(feature = "remote")]
+pub mod sharding;
+#[cfg(feature = "remote")]
+pub mod singleton;
+pub mod spec;
 pub mod supervisor;
+pub mod testing;
+pub mod topic;
+pub mod tree;
+pub mod typed;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "wire")]
+pub mod wire;
 
 pub mod errors;
 
@@ -99,32 +152,234 @@ distributed_api! {
 ///
 /// Prelude of Bastion
 pub mod prelude {
-    pub use crate::bastion::Bastion;
-    pub use crate::callbacks::Callbacks;
-    pub use crate::child_ref::ChildRef;
-    pub use crate::children::Children;
-    pub use crate::children_ref::ChildrenRef;
+    pub use crate::autoscale::AutoscalePolicy;
+    pub use crate::bastion::{Bastion, BastionSystem};
+    pub use crate::behavior::Behavior;
+    pub use crate::callbacks::{Callbacks, RestartContext};
+    pub use crate::child_ref::{ChildAddr, ChildRef};
+    pub use crate::children::{Children, DrainPolicy, OverflowPolicy, StateRecovery};
+    pub use crate::children_ref::{ChildrenRef, DrainReport};
+    #[cfg(feature = "remote")]
+    pub use crate::cluster::{
+        Cluster, ClusterConfig, ClusterEvent, Leadership, Member, MemberStatus, Role,
+    };
+    #[cfg(feature = "remote")]
+    pub use crate::codec::{BincodeCodec, JsonCodec, WireCodec, WireEnvelope};
+    #[cfg(feature = "remote")]
+    pub use crate::dead_letters::{DeadLetter, DeadLetters, Destination};
+    #[cfg(feature = "msgpack")]
+    pub use crate::codec::MessagePackCodec;
+    #[cfg(feature = "protobuf")]
+    pub use crate::codec::ProtobufCodec;
     pub use crate::config::Config;
-    pub use crate::context::{BastionContext, BastionId, NIL_ID};
+    pub use crate::context::{
+        BastionContext, BastionId, ChildCompleted, JoinHandle, LocalState, ScheduleHandle, NIL_ID,
+    };
     pub use crate::dispatcher::{
         BroadcastTarget, DefaultDispatcherHandler, Dispatcher, DispatcherHandler, DispatcherMap,
-        DispatcherType, NotificationType,
+        DispatcherType, NotificationType, RecipientSelector,
+    };
+    pub use crate::distributor::{
+        AckReport, Batch, BoundDistributor, Distributor, MembershipEvent,
     };
-    pub use crate::distributor::Distributor;
-    pub use crate::envelope::{RefAddr, SignedMessage};
+    pub use crate::envelope::{Envelope, MessageTarget, RefAddr, SignedMessage};
     pub use crate::errors::*;
-    #[cfg(not(target_os = "windows"))]
+    pub use crate::events::SystemEvent;
+    pub use crate::executor::{
+        BlockingPoolConfig, BlockingPoolStats, CoreSet, Executor, TaskHandle,
+    };
+    pub use crate::health::{
+        FailureKind, GroupHealthSummary, HealthPolicy, HealthReport, HealthStatus, SystemHealth,
+    };
+    #[cfg(not(any(target_os = "windows", target_arch = "wasm32")))]
     pub use crate::io::*;
-    pub use crate::message::{Answer, AnswerSender, Message, MessageHandler, Msg};
+    #[cfg(feature = "kafka")]
+    pub use crate::kafka::{KafkaConfig, KafkaRecord, KafkaSink, KafkaSource};
+    pub use crate::logging::LogPolicy;
+    pub use crate::message::{
+        Answer, AnswerSender, CorrelationId, Dispatch, DispatchBuilder, Message, MessageHandler,
+        MessageOrigin, Msg, QuestionSender, TypedAnswer, UnknownMessage,
+    };
+    pub use crate::metrics::{GroupMetrics, LatencyHistogram, RuntimeMetrics};
+    pub use crate::monitor::{Down, DownReason, MonitorRef};
     pub use crate::msg;
+    #[cfg(feature = "nats")]
+    pub use crate::nats::NatsBridge;
+    pub use crate::observer::{MailboxEvent, MailboxEventKind};
     pub use crate::path::{BastionPath, BastionPathElement};
+    pub use crate::persistence::{EventSourced, FileJournal, InMemoryJournal, Journal, Replay};
+    #[cfg(feature = "sled")]
+    pub use crate::persistence::SledJournal;
+    pub use crate::profile::{MessageProfile, ProfileReport};
+    #[cfg(feature = "remote")]
+    pub use crate::remote::{ReconnectPolicy, RemoteConfig, RemoteDistributor};
     #[cfg(feature = "scaling")]
     pub use crate::resizer::{OptimalSizeExploringResizer, UpperBound, UpscaleStrategy};
+    #[cfg(feature = "remote")]
+    pub use crate::security::{Authenticator, Peer, TokenAuthenticator};
+    #[cfg(feature = "tls")]
+    pub use crate::security::TlsConfig;
+    #[cfg(feature = "remote")]
+    pub use crate::sharding::Sharding;
+    #[cfg(feature = "remote")]
+    pub use crate::singleton::ClusterSingleton;
+    pub use crate::spec::ChildrenSpec;
     pub use crate::supervisor::{
-        ActorRestartStrategy, RestartPolicy, RestartStrategy, SupervisionStrategy, Supervisor,
-        SupervisorRef,
+        ActorRestartStrategy, ChildFailure, FailureReason, FatalReport, RestartLimit,
+        RestartPolicy, RestartStrategy, StopOrder, SupervisionDecider, SupervisionDecision,
+        SupervisionStrategy, Supervisor, SupervisorRef,
     };
-    pub use crate::{answer, blocking, children, run, spawn, supervisor};
+    pub use crate::testing::TestRuntime;
+    pub use crate::topic::Topic;
+    pub use crate::tree::{ChildNode, ChildrenNode, ElementState, SupervisionTree, SupervisorNode};
+    pub use crate::typed::{TypedChildRef, TypedContext};
+    #[cfg(feature = "wasm")]
+    pub use crate::wasm::WasmExecutor;
+    #[cfg(feature = "wire")]
+    pub use crate::wire::{MessageRegistry, MessageSchema, WireMessage};
+    pub use crate::{answer, blocking, children, run, spawn, spawn_handle, supervisor};
+    pub use bastion_macros::BastionMessage;
+
+    /// Second version of the prelude, which can be used instead of
+    /// the prelude itself with `use bastion::prelude::v2::*;`.
+    ///
+    /// It only re-exports the items needed by most applications
+    /// and groups the rest in modules by concern (e.g.
+    /// `v2::routing::BoundDistributor`), without any glob
+    /// re-export, so that it doesn't bring dozens of names into the
+    /// scope of the modules importing it.
+    ///
+    /// It exports the same items as the prelude, except for the
+    /// IO subsystem, which is available in `bastion::io`.
+    pub mod v2 {
+        pub use crate::bastion::{Bastion, BastionSystem};
+        pub use crate::config::Config;
+        pub use crate::context::{
+            BastionContext, BastionId, ChildCompleted, JoinHandle, LocalState, ScheduleHandle,
+            NIL_ID,
+        };
+        pub use crate::distributor::Distributor;
+        pub use crate::errors::{ReceiveError, RequestError, SendError, TaskError};
+        pub use crate::executor::TaskHandle;
+        pub use crate::message::{Answer, Message, MessageHandler};
+        pub use crate::{answer, blocking, children, msg, run, spawn, spawn_handle, supervisor};
+
+        /// Builders and strategies of the supervision tree.
+        pub mod supervision {
+            pub use crate::callbacks::{Callbacks, RestartContext};
+            pub use crate::child_ref::{ChildAddr, ChildRef};
+            pub use crate::children::{Children, DrainPolicy, OverflowPolicy, StateRecovery};
+            pub use crate::children_ref::{ChildrenRef, DrainReport};
+            pub use crate::executor::CoreSet;
+            pub use crate::monitor::{Down, DownReason, MonitorRef};
+            pub use crate::spec::ChildrenSpec;
+            pub use crate::supervisor::{
+                ActorRestartStrategy, ChildFailure, FailureReason, FatalReport, RestartLimit,
+                RestartPolicy, RestartStrategy, StopOrder, SupervisionDecider, SupervisionDecision,
+                SupervisionStrategy, Supervisor, SupervisorRef,
+            };
+            pub use crate::tree::{
+                ChildNode, ChildrenNode, ElementState, SupervisionTree, SupervisorNode,
+            };
+        }
+
+        /// Routing of the messages to groups of children.
+        pub mod routing {
+            pub use crate::dispatcher::{
+                BroadcastTarget, DefaultDispatcherHandler, Dispatcher, DispatcherHandler,
+                DispatcherMap, DispatcherType, NotificationType, RecipientSelector,
+            };
+            pub use crate::distributor::{
+                AckReport, Batch, BoundDistributor, Distributor, MembershipEvent,
+            };
+            pub use crate::topic::Topic;
+            #[cfg(feature = "remote")]
+            pub use crate::cluster::{
+                Cluster, ClusterConfig, ClusterEvent, Leadership, Member, MemberStatus, Role,
+            };
+            #[cfg(feature = "remote")]
+            pub use crate::codec::{BincodeCodec, JsonCodec, WireCodec, WireEnvelope};
+            #[cfg(feature = "remote")]
+            pub use crate::dead_letters::{DeadLetter, DeadLetters, Destination};
+            #[cfg(feature = "msgpack")]
+            pub use crate::codec::MessagePackCodec;
+            #[cfg(feature = "protobuf")]
+            pub use crate::codec::ProtobufCodec;
+            #[cfg(feature = "kafka")]
+            pub use crate::kafka::{KafkaConfig, KafkaRecord, KafkaSink, KafkaSource};
+            #[cfg(feature = "nats")]
+            pub use crate::nats::NatsBridge;
+            #[cfg(feature = "remote")]
+            pub use crate::remote::{ReconnectPolicy, RemoteConfig, RemoteDistributor};
+            #[cfg(feature = "remote")]
+            pub use crate::security::{Authenticator, Peer, TokenAuthenticator};
+            #[cfg(feature = "tls")]
+            pub use crate::security::TlsConfig;
+            #[cfg(feature = "remote")]
+            pub use crate::sharding::Sharding;
+            #[cfg(feature = "remote")]
+            pub use crate::singleton::ClusterSingleton;
+        }
+
+        /// Messages and their envelopes.
+        pub mod messaging {
+            pub use crate::behavior::Behavior;
+            pub use crate::envelope::{Envelope, MessageTarget, RefAddr, SignedMessage};
+            pub use crate::errors::DispatchError;
+            #[cfg(feature = "wire")]
+            pub use crate::errors::RegistryError;
+            pub use crate::logging::LogPolicy;
+            pub use crate::message::{
+                Answer, AnswerSender, CorrelationId, Dispatch, DispatchBuilder, Message,
+                MessageHandler, MessageOrigin, Msg, QuestionSender, TypedAnswer, UnknownMessage,
+            };
+            pub use crate::observer::{MailboxEvent, MailboxEventKind};
+            pub use crate::path::{BastionPath, BastionPathElement};
+            pub use crate::typed::{TypedChildRef, TypedContext};
+            #[cfg(feature = "wire")]
+            pub use crate::wire::{MessageRegistry, MessageSchema, WireMessage};
+            pub use bastion_macros::BastionMessage;
+        }
+
+        /// Failure telemetry of the children groups, health checks,
+        /// runtime metrics and lifecycle events of the system.
+        pub mod health {
+            pub use crate::events::SystemEvent;
+            pub use crate::health::{
+                FailureKind, GroupHealthSummary, HealthPolicy, HealthReport, HealthStatus,
+                SystemHealth,
+            };
+            pub use crate::metrics::{GroupMetrics, LatencyHistogram, RuntimeMetrics};
+            pub use crate::profile::{MessageProfile, ProfileReport};
+        }
+
+        /// Journal of the event-sourced actors.
+        pub mod persistence {
+            pub use crate::persistence::{
+                EventSourced, FileJournal, InMemoryJournal, Journal, Replay,
+            };
+            #[cfg(feature = "sled")]
+            pub use crate::persistence::SledJournal;
+        }
+
+        /// Executors running the futures of the elements, and the
+        /// runtime of the tests.
+        pub mod runtime {
+            pub use crate::executor::{
+                BlockingPoolConfig, BlockingPoolStats, CoreSet, Executor, TaskHandle,
+            };
+            pub use crate::testing::TestRuntime;
+            #[cfg(feature = "wasm")]
+            pub use crate::wasm::WasmExecutor;
+        }
+
+        /// Resizers and autoscaling of the children groups.
+        pub mod scaling {
+            pub use crate::autoscale::AutoscalePolicy;
+            #[cfg(feature = "scaling")]
+            pub use crate::resizer::{OptimalSizeExploringResizer, UpperBound, UpscaleStrategy};
+        }
+    }
 
     distributed_api! {
         // pub use crate::dist_messages::*;
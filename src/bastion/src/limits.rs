@@ -0,0 +1,353 @@
+//!
+//! Limits shared by the elements of a children group, set using
+//! [`Children::with_concurrency_limit`] and
+//! [`Children::with_inflight_limit`], and the capacity of their
+//! mailboxes, set using [`Children::with_mailbox_capacity`].
+//!
+//! [`Children::with_concurrency_limit`]: crate::children::Children::with_concurrency_limit
+//! [`Children::with_inflight_limit`]: crate::children::Children::with_inflight_limit
+//! [`Children::with_mailbox_capacity`]: crate::children::Children::with_mailbox_capacity
+use crate::children::OverflowPolicy;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::Waker;
+use tracing::trace;
+
+#[derive(Debug)]
+// Caps how many elements of a children group can be handling a
+// message at once. An element takes a permit when it receives a
+// message and gives it back when it asks for the next one.
+pub(crate) struct ConcurrencyLimit {
+    limit: usize,
+    active: AtomicUsize,
+    // The wakers of the elements waiting for a permit.
+    parked: Mutex<Vec<Waker>>,
+}
+
+#[derive(Debug)]
+// Caps how many asks sent to the elements of a children group can
+// be waiting for an answer at once.
+pub(crate) struct InflightLimit {
+    limit: usize,
+    inflight: AtomicUsize,
+}
+
+#[derive(Debug)]
+// An ask counted by an `InflightLimit`, until it is dropped.
+pub(crate) struct InflightPermit(Arc<InflightLimit>);
+
+#[derive(Debug)]
+// The depth of an element's mailbox, shared with the `ChildRef`s
+// referencing it, and the capacity it is bounded to (if any).
+pub(crate) struct MailboxLimit {
+    capacity: Option<usize>,
+    policy: OverflowPolicy,
+    len: AtomicUsize,
+    // The room reserved for the messages being sent: the batches
+    // sent to a mailbox refusing the senders once full, and every
+    // message sent to a mailbox applying backpressure, until it is
+    // enqueued.
+    reserved: AtomicUsize,
+    // The wakers of the senders waiting for room in the mailbox.
+    waiting: Mutex<Vec<Waker>>,
+}
+
+#[derive(Debug)]
+// Room reserved in a mailbox refusing the senders once full or
+// applying backpressure, until it is dropped.
+pub(crate) struct MailboxReservation(Option<(Arc<MailboxLimit>, usize)>);
+
+impl ConcurrencyLimit {
+    pub(crate) fn new(limit: usize) -> Self {
+        ConcurrencyLimit {
+            limit,
+            active: AtomicUsize::new(0),
+            parked: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn try_acquire(&self) -> bool {
+        self.active
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |active| {
+                if active < self.limit {
+                    Some(active + 1)
+                } else {
+                    None
+                }
+            })
+            .is_ok()
+    }
+
+    pub(crate) fn release(&self) {
+        self.active.fetch_sub(1, Ordering::SeqCst);
+
+        let parked: Vec<_> = self.parked.lock().unwrap().drain(..).collect();
+        trace!("ConcurrencyLimit: Waking {} parked elements.", parked.len());
+        for waker in parked {
+            waker.wake();
+        }
+    }
+
+    // Wakes the element up once a permit is released.
+    pub(crate) fn park(&self, waker: &Waker) {
+        self.parked.lock().unwrap().push(waker.clone());
+        // A permit might have been released before the element was
+        // parked.
+        if self.active.load(Ordering::SeqCst) < self.limit {
+            waker.wake_by_ref();
+        }
+    }
+}
+
+impl InflightLimit {
+    pub(crate) fn new(limit: usize) -> Self {
+        InflightLimit {
+            limit,
+            inflight: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn try_acquire(self: &Arc<Self>) -> Option<InflightPermit> {
+        self.inflight
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |inflight| {
+                if inflight < self.limit {
+                    Some(inflight + 1)
+                } else {
+                    None
+                }
+            })
+            .ok()
+            .map(|_| InflightPermit(self.clone()))
+    }
+}
+
+impl MailboxLimit {
+    pub(crate) fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+        MailboxLimit {
+            capacity: Some(capacity),
+            policy,
+            len: AtomicUsize::new(0),
+            reserved: AtomicUsize::new(0),
+            waiting: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn unbounded() -> Self {
+        MailboxLimit {
+            capacity: None,
+            policy: OverflowPolicy::default(),
+            len: AtomicUsize::new(0),
+            reserved: AtomicUsize::new(0),
+            waiting: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn policy(&self) -> &OverflowPolicy {
+        &self.policy
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn is_full(&self) -> bool {
+        match self.capacity {
+            Some(capacity) => self.len() >= capacity,
+            None => false,
+        }
+    }
+
+    // Whether the mailbox has room for a new message, counting the
+    // room reserved for the messages being sent.
+    pub(crate) fn has_room(&self) -> bool {
+        match self.capacity {
+            Some(capacity) => self.len() + self.reserved.load(Ordering::SeqCst) < capacity,
+            None => true,
+        }
+    }
+
+    // Whether the mailbox applies backpressure: every message sent
+    // to it reserves room until it is enqueued.
+    pub(crate) fn applies_backpressure(&self) -> bool {
+        self.capacity.is_some() && self.policy == OverflowPolicy::Backpressure
+    }
+
+    // Returns the capacity of the mailbox if it refuses the senders
+    // once full.
+    fn refusing_capacity(&self) -> Option<usize> {
+        match self.policy {
+            OverflowPolicy::FailSender | OverflowPolicy::Backpressure => self.capacity,
+            OverflowPolicy::DropOldest | OverflowPolicy::DropNewest => None,
+        }
+    }
+
+    // Whether senders should be refused instead of sending a
+    // message that would overflow the mailbox (counting the room
+    // reserved for the messages being sent).
+    pub(crate) fn refuses_senders(&self) -> bool {
+        match self.refusing_capacity() {
+            Some(capacity) => self.len() + self.reserved.load(Ordering::SeqCst) >= capacity,
+            None => false,
+        }
+    }
+
+    // Reserves room for the given amount of messages if the mailbox
+    // refuses the senders once full, or returns `None` if it hasn't
+    // enough room left.
+    pub(crate) fn try_reserve(self: &Arc<Self>, count: usize) -> Option<MailboxReservation> {
+        let capacity = match self.refusing_capacity() {
+            Some(capacity) => capacity,
+            None => return Some(MailboxReservation(None)),
+        };
+
+        self.reserved
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |reserved| {
+                if self.len() + reserved + count <= capacity {
+                    Some(reserved + count)
+                } else {
+                    None
+                }
+            })
+            .ok()
+            .map(|_| MailboxReservation(Some((self.clone(), count))))
+    }
+
+    pub(crate) fn set_len(&self, len: usize) {
+        self.len.store(len, Ordering::SeqCst);
+        self.wake_waiting();
+    }
+
+    // Wakes the senders waiting for room up if there is some.
+    fn wake_waiting(&self) {
+        if !self.has_room() {
+            return;
+        }
+
+        let waiting: Vec<_> = self.waiting.lock().unwrap().drain(..).collect();
+        if !waiting.is_empty() {
+            trace!("MailboxLimit: Waking {} waiting senders.", waiting.len());
+        }
+        for waker in waiting {
+            waker.wake();
+        }
+    }
+
+    // Wakes the sender up once there is room in the mailbox.
+    pub(crate) fn park(&self, waker: &Waker) {
+        self.waiting.lock().unwrap().push(waker.clone());
+        // The mailbox might have been emptied before the sender was
+        // parked.
+        if self.has_room() {
+            waker.wake_by_ref();
+        }
+    }
+}
+
+impl MailboxReservation {
+    // The reservation of a mailbox which isn't bounded.
+    pub(crate) fn unbounded() -> Self {
+        MailboxReservation(None)
+    }
+
+    // Moves the room reserved for one message to a new reservation.
+    pub(crate) fn take_one(&mut self) -> Self {
+        match &mut self.0 {
+            Some((mailbox, count)) if *count > 0 => {
+                *count -= 1;
+                MailboxReservation(Some((mailbox.clone(), 1)))
+            }
+            _ => MailboxReservation(None),
+        }
+    }
+}
+
+impl Drop for InflightPermit {
+    fn drop(&mut self) {
+        self.0.inflight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Drop for MailboxReservation {
+    fn drop(&mut self) {
+        if let Some((mailbox, count)) = &self.0 {
+            mailbox.reserved.fetch_sub(*count, Ordering::SeqCst);
+            mailbox.wake_waiting();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concurrency_limit_caps_permits() {
+        let limit = ConcurrencyLimit::new(2);
+
+        assert!(limit.try_acquire());
+        assert!(limit.try_acquire());
+        assert!(!limit.try_acquire());
+
+        limit.release();
+        assert!(limit.try_acquire());
+    }
+
+    #[test]
+    fn test_inflight_limit_releases_dropped_permits() {
+        let limit = Arc::new(InflightLimit::new(1));
+
+        let permit = limit.try_acquire().unwrap();
+        assert!(limit.try_acquire().is_none());
+
+        drop(permit);
+        assert!(limit.try_acquire().is_some());
+    }
+
+    #[test]
+    fn test_mailbox_limit_refuses_senders_when_full() {
+        let limit = MailboxLimit::new(2, OverflowPolicy::FailSender);
+
+        limit.set_len(1);
+        assert!(!limit.refuses_senders());
+        limit.set_len(2);
+        assert!(limit.refuses_senders());
+
+        let limit = MailboxLimit::new(2, OverflowPolicy::DropOldest);
+        limit.set_len(2);
+        assert!(limit.is_full());
+        assert!(!limit.refuses_senders());
+
+        let limit = MailboxLimit::unbounded();
+        limit.set_len(1_000);
+        assert!(!limit.is_full());
+    }
+
+    #[test]
+    fn test_mailbox_limit_reserves_room_for_backpressure() {
+        let limit = Arc::new(MailboxLimit::new(2, OverflowPolicy::Backpressure));
+        assert!(limit.applies_backpressure());
+
+        // The room stays reserved until the messages are enqueued.
+        let first = limit.try_reserve(1).unwrap();
+        let mut batch = limit.try_reserve(1).unwrap();
+        assert!(!limit.has_room());
+        assert!(limit.refuses_senders());
+        assert!(limit.try_reserve(1).is_none());
+
+        let second = batch.take_one();
+        drop(batch);
+        assert!(limit.try_reserve(1).is_none());
+
+        limit.set_len(1);
+        drop(first);
+        assert!(!limit.has_room());
+        limit.set_len(2);
+        drop(second);
+        assert!(!limit.has_room());
+        assert!(limit.try_reserve(1).is_none());
+
+        limit.set_len(1);
+        assert!(limit.has_room());
+        assert!(limit.try_reserve(1).is_some());
+    }
+}
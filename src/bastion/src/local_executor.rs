@@ -0,0 +1,128 @@
+//!
+//! The threads running the futures of the children groups whose
+//! elements are confined to a single thread, set up using
+//! [`Children::with_exec_local`].
+//!
+//! [`Children::with_exec_local`]: crate::children::Children::with_exec_local
+use crate::system::{self, Scoped};
+use futures::channel::{mpsc, oneshot};
+use futures::executor::{LocalPool, LocalSpawner};
+use futures::future::{self, AbortHandle, Abortable};
+use futures::task::LocalSpawnExt;
+use futures::{FutureExt, StreamExt};
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::thread;
+use tracing::{debug, trace};
+
+type LocalJob = Box<dyn FnOnce(&LocalSpawner) + Send>;
+
+#[derive(Debug, Clone)]
+// A thread polling the futures given to it, which don't need to be
+// `Send`. The thread stops once every clone of the executor is
+// dropped.
+pub(crate) struct LocalExecutor {
+    sender: mpsc::UnboundedSender<LocalJob>,
+}
+
+// Cancels the future run by a `LocalExecutor` when the future
+// awaiting its output is dropped.
+struct AbortOnDrop(AbortHandle);
+
+impl LocalExecutor {
+    pub(crate) fn start(name: String) -> Self {
+        debug!("LocalExecutor: Starting {}.", name);
+        let (sender, receiver) = mpsc::unbounded::<LocalJob>();
+        thread::Builder::new()
+            .name(name)
+            .spawn(move || {
+                let mut pool = LocalPool::new();
+                let spawner = pool.spawner();
+                pool.run_until(receiver.for_each(move |job| {
+                    job(&spawner);
+                    future::ready(())
+                }));
+
+                debug!("LocalExecutor: Stopping.");
+            })
+            .expect("Couldn't start the thread of a local executor.");
+
+        LocalExecutor { sender }
+    }
+
+    // Creates a future using `init` on the executor's thread and
+    // polls it there, returning its output, or `None` if it panicked
+    // or the executor stopped.
+    //
+    // The future is dropped if the returned one is.
+    pub(crate) fn run<I, F>(&self, init: I) -> impl Future<Output = Option<F::Output>> + Send
+    where
+        I: FnOnce() -> F + Send + 'static,
+        F: Future + 'static,
+        F::Output: Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        let (abort, registration) = AbortHandle::new_pair();
+        let system = system::scope();
+        let job: LocalJob = Box::new(move |spawner: &LocalSpawner| {
+            trace!("LocalExecutor: Spawning future.");
+            let task = Scoped::new(system, async move { init().await });
+            let task = AssertUnwindSafe(task).catch_unwind();
+            let task = Abortable::new(task, registration);
+            let spawned = spawner.spawn_local(async move {
+                if let Ok(Ok(output)) = task.await {
+                    sender.send(output).ok();
+                }
+            });
+            if let Err(err) = spawned {
+                debug!("LocalExecutor: Couldn't spawn future: {}", err);
+            }
+        });
+        let sent = self.sender.unbounded_send(job).is_ok();
+
+        async move {
+            if !sent {
+                return None;
+            }
+
+            let _abort = AbortOnDrop(abort);
+            receiver.await.ok()
+        }
+    }
+}
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_local_executor_runs_non_send_futures() {
+        let executor = LocalExecutor::start("bastion-local-test".to_string());
+
+        let output = futures::executor::block_on(executor.run(|| {
+            let value = Rc::new(21);
+            async move {
+                future::ready(()).await;
+                *value * 2
+            }
+        }));
+        assert_eq!(output, Some(42));
+
+        let output = futures::executor::block_on(executor.run(|| async {
+            panic!("local future failed");
+        }));
+        assert_eq!(output, None::<()>);
+
+        let thread = futures::executor::block_on(
+            executor.run(|| async { thread::current().name().map(str::to_string) }),
+        );
+        assert_eq!(thread.unwrap().as_deref(), Some("bastion-local-test"));
+    }
+}
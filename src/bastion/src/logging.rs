@@ -0,0 +1,122 @@
+//!
+//! The logging of the lifecycle of the messages sent within a
+//! system, enabled using [`Bastion::log_messages`].
+//!
+//! When enabled, each message sent, received by an element, or
+//! replied to is logged along with the name of its type and its
+//! correlation id, at the `INFO` level and with the
+//! `bastion::messages` target (through `tracing`, which can forward
+//! its events to `log`).
+//!
+//! Whether a message is logged only depends on its correlation id,
+//! so that either all of its events are logged or none is.
+//!
+//! [`Bastion::log_messages`]: crate::Bastion::log_messages
+use crate::message::CorrelationId;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::info;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Which messages have their lifecycle logged, set using
+/// [`Bastion::log_messages`].
+///
+/// [`Bastion::log_messages`]: crate::Bastion::log_messages
+pub enum LogPolicy {
+    /// No message is logged (the default).
+    Off,
+    /// Every message is logged.
+    All,
+    /// Only the given share of the messages (between `0.0` and
+    /// `1.0`) is logged, keeping the overhead of the logging
+    /// bounded.
+    Sampled(f64),
+}
+
+#[derive(Debug, Default)]
+// Logs the lifecycle of the messages sampled by the policy of a
+// system.
+pub(crate) struct MessageLog {
+    // The messages whose mixed correlation id is below the threshold
+    // are logged (all of them if it is `u64::MAX`).
+    threshold: AtomicU64,
+}
+
+impl Default for LogPolicy {
+    fn default() -> Self {
+        LogPolicy::Off
+    }
+}
+
+impl MessageLog {
+    pub(crate) fn set_policy(&self, policy: LogPolicy) {
+        let threshold = match policy {
+            LogPolicy::Off => 0,
+            LogPolicy::All => u64::MAX,
+            // Saturates to `0` for NaN and negative rates.
+            LogPolicy::Sampled(rate) => (rate.min(1.0) * u64::MAX as f64) as u64,
+        };
+        self.threshold.store(threshold, Ordering::Relaxed);
+    }
+
+    fn samples(&self, correlation_id: CorrelationId) -> bool {
+        match self.threshold.load(Ordering::Relaxed) {
+            0 => false,
+            u64::MAX => true,
+            threshold => mix(correlation_id) < threshold,
+        }
+    }
+
+    pub(crate) fn log(
+        &self,
+        event: &'static str,
+        type_name: &'static str,
+        correlation_id: CorrelationId,
+    ) {
+        if self.samples(correlation_id) {
+            info!(
+                target: "bastion::messages",
+                event,
+                message_type = type_name,
+                %correlation_id,
+                "Message {} {}: {}",
+                correlation_id,
+                event,
+                type_name
+            );
+        }
+    }
+}
+
+// Spreads the correlation ids, whose low bits are a counter, over
+// the whole range of `u64` (using the finalizer of SplitMix64).
+fn mix(correlation_id: CorrelationId) -> u64 {
+    let id = correlation_id.as_u128();
+    let mut x = (id as u64) ^ ((id >> 64) as u64);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sampling() {
+        let ids: Vec<_> = (0..10_000).map(|_| CorrelationId::new()).collect();
+        let log = MessageLog::default();
+        assert!(!ids.iter().any(|id| log.samples(*id)));
+
+        log.set_policy(LogPolicy::All);
+        assert!(ids.iter().all(|id| log.samples(*id)));
+
+        log.set_policy(LogPolicy::Sampled(0.1));
+        let sampled = ids.iter().filter(|id| log.samples(**id)).count();
+        assert!((500..1_500).contains(&sampled), "{}", sampled);
+        // The same messages are sampled each time.
+        assert_eq!(ids.iter().filter(|id| log.samples(**id)).count(), sampled);
+
+        log.set_policy(LogPolicy::Sampled(-1.0));
+        assert!(!ids.iter().any(|id| log.samples(*id)));
+    }
+}
@@ -318,6 +318,42 @@ macro_rules! spawn {
     };
 }
 
+/// Spawns a given future onto the executor from the global level,
+/// returning a [`TaskHandle`] to await its output, cancel it, or
+/// get the panic it failed with.
+///
+/// # Example
+/// ```
+/// # use bastion::prelude::*;
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();    
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();    
+/// # }
+/// #
+/// # fn run() {
+/// let handle = spawn_handle!(async { 40 + 2 });
+/// assert_eq!(run!(handle), Ok(42));
+/// # }
+/// ```
+///
+/// [`TaskHandle`]: crate::executor::TaskHandle
+#[macro_export]
+macro_rules! spawn_handle {
+    ($action:expr) => {
+        $crate::executor::spawn_handle($action)
+    };
+
+    ($($tokens:tt)*) => {
+        $crate::executor::spawn_handle(async move {$($tokens)*})
+    };
+}
+
 ///
 /// Marker of distributed API.
 #[doc(hidden)]
@@ -6,20 +6,51 @@
 //! * All message communication relies on at-most-once delivery guarantee.
 //! * Messages are not guaranteed to be ordered, all message's order is causal.
 //!
-use crate::callbacks::CallbackType;
+use crate::callbacks::{CallbackType, RestartContext};
+use crate::child::Init;
 use crate::children::Children;
 use crate::context::{BastionId, ContextState};
 use crate::envelope::{RefAddr, SignedMessage};
-use crate::supervisor::{SupervisionStrategy, Supervisor};
+use crate::errors::{DispatchError, SendError};
+use crate::executor::{self, Deadline};
+use crate::limits::{InflightPermit, MailboxReservation};
+use crate::supervisor::{ChildFailure, SupervisionStrategy, Supervisor};
+use crate::system;
+use crate::time::{self, Instant};
+#[cfg(feature = "wire")]
+use crate::wire::{MessageRegistry, WireMessage};
 
 use futures::channel::oneshot::{self, Receiver};
-use std::any::{type_name, Any};
-use std::fmt::Debug;
+use futures::future;
+use fxhash::FxHashMap;
+use once_cell::sync::Lazy;
+#[cfg(feature = "wire")]
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::any::{type_name, Any, TypeId};
+use std::fmt::{self, Debug, Formatter};
 use std::future::Future;
+use std::marker::PhantomData;
+#[cfg(feature = "wire")]
+use std::ops::RangeBounds;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 use tracing::{debug, trace};
+use uuid::Uuid;
+
+/// The buffers handled by [`MessageHandler::on_bytes`] and sent by
+/// [`Distributor::tell_bytes`] without being copied.
+///
+/// [`Distributor::tell_bytes`]: crate::distributor::Distributor::tell_bytes
+pub use bytes::Bytes;
+
+// The high bits of the correlation ids generated by this process,
+// making them unlikely to collide with the ones of other processes.
+static CORRELATION_PREFIX: Lazy<u64> = Lazy::new(|| Uuid::new_v4().as_u128() as u64);
+static NEXT_CORRELATION: AtomicU64 = AtomicU64::new(0);
 
 /// A trait that any message sent needs to implement (it is
 /// already automatically implemented but forces message to
@@ -40,7 +71,27 @@ impl<T> Message for T where T: Any + Send + Sync + Debug {}
 ///
 /// [`respond`]: #method.respond
 #[derive(Debug)]
-pub struct AnswerSender(oneshot::Sender<SignedMessage>, RefAddr);
+pub struct AnswerSender(
+    oneshot::Sender<SignedMessage>,
+    RefAddr,
+    RefAddr,
+    CorrelationId,
+);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// An identifier attached to every message, and to the answer to a
+/// question, allowing to join the logs of the asker and of the
+/// element answering it.
+///
+/// A unique correlation id is generated for every message, unless
+/// one is given when sending it using
+/// [`BastionContext::tell_correlated`] or
+/// [`BastionContext::ask_correlated`] (e.g. to reuse the id of an
+/// incoming HTTP request for end-to-end tracing).
+///
+/// [`BastionContext::tell_correlated`]: crate::context::BastionContext::tell_correlated
+/// [`BastionContext::ask_correlated`]: crate::context::BastionContext::ask_correlated
+pub struct CorrelationId(u128);
 
 #[derive(Debug)]
 /// A [`Future`] returned when successfully "asking" a
@@ -127,7 +178,49 @@ pub struct AnswerSender(oneshot::Sender<SignedMessage>, RefAddr);
 ///
 /// [`Future`]: std::future::Future
 /// [`ChildRef::ask_anonymously`]: crate::child_ref::ChildRef::ask_anonymously
-pub struct Answer(Receiver<SignedMessage>);
+pub struct Answer(
+    Receiver<SignedMessage>,
+    Option<InflightPermit>,
+    // When the answer is considered lost, set using `timeout`.
+    Option<(Deadline, Duration)>,
+    // When the question was asked.
+    Instant,
+);
+
+/// A [`Future`] returned when successfully "requesting" a message
+/// using [`BastionContext::request`], which resolves to the answer
+/// of type `R`, or to `Err(())` if the question was dropped without
+/// being answered.
+///
+/// The answer can only be sent using the [`QuestionSender`] given
+/// to the closure passed to [`MessageHandler::on_request`], which
+/// only accepts messages of type `R`.
+///
+/// [`Future`]: std::future::Future
+/// [`BastionContext::request`]: crate::context::BastionContext::request
+#[derive(Debug)]
+pub struct TypedAnswer<R> {
+    answer: Answer,
+    _reply: PhantomData<fn() -> R>,
+}
+
+/// Allows to answer a question asked using
+/// [`BastionContext::request`], only accepting answers of the type
+/// the asker expects.
+///
+/// It is given to the closure passed to
+/// [`MessageHandler::on_request`].
+///
+/// [`BastionContext::request`]: crate::context::BastionContext::request
+#[derive(Debug)]
+pub struct QuestionSender<R> {
+    sender: AnswerSender,
+    _reply: PhantomData<fn(R)>,
+}
+
+// A question asked using `BastionContext::request`, which can only
+// be answered with a `R` (see `MessageHandler::on_request`).
+pub(crate) struct Request<M, R>(M, PhantomData<fn() -> R>);
 
 #[derive(Debug)]
 /// A message returned by [`BastionContext::recv`] or
@@ -198,7 +291,41 @@ pub struct Answer(Receiver<SignedMessage>);
 ///
 /// [`BastionContext::recv`]: crate::context::BastionContext::recv
 /// [`BastionContext::try_recv`]: crate::context::BastionContext::try_recv
-pub struct Msg(MsgInner);
+pub struct Msg(MsgInner, MsgMeta);
+
+#[derive(Debug)]
+// An error sent back instead of an answer to a question.
+pub(crate) struct ErrorReply(pub(crate) String);
+
+/// The sending side of an acknowledgment requested along with a message.
+pub(crate) type AckSender = oneshot::Sender<()>;
+
+#[derive(Debug, Default)]
+struct MsgMeta {
+    // Fired when the recipient acknowledges the message.
+    ack: Option<AckSender>,
+    // The instant after which the message must not be received anymore.
+    expires_at: Option<Instant>,
+    // The address of the original sender of a forwarded message.
+    reply_to: Option<RefAddr>,
+    // The name of the type of the message.
+    type_name: &'static str,
+    // When the message was added to the recipient's mailbox.
+    enqueued_at: Option<Instant>,
+    // Identifies the message and the answer to it.
+    correlation_id: CorrelationId,
+    // Whether the sending of the message was logged (see
+    // `Bastion::log_messages`).
+    logged: bool,
+    // The room reserved for the message in the mailbox of its
+    // recipient, until it is enqueued (see
+    // `OverflowPolicy::Backpressure`).
+    reservation: Option<MailboxReservation>,
+    // The span current when the message was sent, parent of the span
+    // of its handling.
+    #[cfg(feature = "tracing")]
+    span: Option<tracing::Span>,
+}
 
 #[derive(Debug)]
 enum MsgInner {
@@ -230,6 +357,7 @@ pub(crate) enum BastionMessage {
     RestartRequired {
         id: BastionId,
         parent_id: BastionId,
+        failure: ChildFailure,
     },
     FinishedChild {
         id: BastionId,
@@ -239,6 +367,7 @@ pub(crate) enum BastionMessage {
     RestoreChild {
         id: BastionId,
         state: Arc<Pin<Box<ContextState>>>,
+        context: RestartContext,
     },
     DropChild {
         id: BastionId,
@@ -253,6 +382,21 @@ pub(crate) enum BastionMessage {
         id: BastionId,
     },
     Heartbeat,
+    Suspend,
+    Resume,
+    RetireChild {
+        id: BastionId,
+    },
+    LinkFailed {
+        id: BastionId,
+    },
+    ReplaceExec(Init),
+    RestartChild {
+        id: BastionId,
+    },
+    KillWithReason {
+        reason: String,
+    },
 }
 
 #[derive(Debug)]
@@ -271,34 +415,208 @@ impl AnswerSender {
         let msg = Msg::tell(msg);
         trace!("{:?}: Sending message: {:?}", self, msg);
 
-        let AnswerSender(sender, sign) = self;
+        let AnswerSender(sender, sign, _, correlation_id) = self;
+        let mut msg = msg;
+        msg.set_correlation_id(correlation_id);
+        system::log_message("replied", msg.type_name(), correlation_id);
         sender
             .send(SignedMessage::new(msg, sign))
             .map_err(|smsg| smsg.msg.try_unwrap().unwrap())
     }
+
+    /// Sends an error back to the original sender, instead of an
+    /// answer. Requests (e.g. [`Distributor::request`]) then fail
+    /// with [`RequestError::Remote`], which carries the error's
+    /// message.
+    ///
+    /// Returns `Ok` if the error was sent successfully, otherwise
+    /// returns the error's message.
+    ///
+    /// [`Distributor::request`]: crate::distributor::Distributor::request
+    /// [`RequestError::Remote`]: crate::errors::RequestError::Remote
+    pub fn reply_err(self, error: impl fmt::Display) -> Result<(), String> {
+        self.reply(ErrorReply(error.to_string()))
+            .map_err(|ErrorReply(error)| error)
+    }
+
+    /// Returns the signature of the element which asked the
+    /// question, allowing to identify it (see
+    /// [`RefAddr::child_id`] and [`RefAddr::group_name`]).
+    pub fn asker(&self) -> &RefAddr {
+        &self.2
+    }
+}
+
+impl CorrelationId {
+    /// Generates a new correlation id, unique within this process.
+    pub fn new() -> Self {
+        let next = NEXT_CORRELATION.fetch_add(1, Ordering::Relaxed);
+        CorrelationId((*CORRELATION_PREFIX as u128) << 64 | next as u128)
+    }
+
+    /// Returns the correlation id as an integer.
+    pub fn as_u128(&self) -> u128 {
+        self.0
+    }
+}
+
+impl Default for CorrelationId {
+    fn default() -> Self {
+        CorrelationId::new()
+    }
+}
+
+impl From<u128> for CorrelationId {
+    fn from(id: u128) -> Self {
+        CorrelationId(id)
+    }
+}
+
+impl From<Uuid> for CorrelationId {
+    fn from(id: Uuid) -> Self {
+        CorrelationId(id.as_u128())
+    }
+}
+
+impl fmt::Display for CorrelationId {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        write!(fmt, "{:032x}", self.0)
+    }
+}
+
+impl MsgMeta {
+    fn of<M: Message>() -> Self {
+        MsgMeta {
+            type_name: type_name::<M>(),
+            #[cfg(feature = "tracing")]
+            span: crate::spans::sender_span(),
+            ..MsgMeta::default()
+        }
+    }
+}
+
+impl<R: Message> TypedAnswer<R> {
+    pub(crate) fn new(answer: Answer) -> Self {
+        TypedAnswer {
+            answer,
+            _reply: PhantomData,
+        }
+    }
+}
+
+impl<R: Message> QuestionSender<R> {
+    /// Sends the answer back to the asker.
+    ///
+    /// Returns `Ok` if the answer was sent successfully, otherwise
+    /// returns the answer.
+    pub fn reply(self, msg: R) -> Result<(), R> {
+        self.sender.reply(msg)
+    }
+
+    /// Sends an error back to the asker instead of an answer (see
+    /// [`AnswerSender::reply_err`]).
+    pub fn reply_err(self, error: impl fmt::Display) -> Result<(), String> {
+        self.sender.reply_err(error)
+    }
+
+    /// Returns the signature of the element which asked the
+    /// question (see [`AnswerSender::asker`]).
+    pub fn asker(&self) -> &RefAddr {
+        self.sender.asker()
+    }
+}
+
+impl<M, R> Request<M, R> {
+    pub(crate) fn new(msg: M) -> Self {
+        Request(msg, PhantomData)
+    }
+
+    pub(crate) fn into_inner(self) -> M {
+        self.0
+    }
+}
+
+impl<M: Debug, R> Debug for Request<M, R> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_tuple("Request").field(&self.0).finish()
+    }
+}
+
+#[derive(Debug)]
+/// The origin of a message routed by the code generated by
+/// `#[derive(BastionMessage)]`, allowing to identify its sender and
+/// to reply to it if it is a question.
+pub struct MessageOrigin {
+    sign: RefAddr,
+    sender: Option<AnswerSender>,
+}
+
+impl MessageOrigin {
+    pub(crate) fn new(sign: RefAddr, sender: Option<AnswerSender>) -> Self {
+        MessageOrigin { sign, sender }
+    }
+
+    /// Returns the signature of the sender of the message.
+    pub fn signature(&self) -> &RefAddr {
+        &self.sign
+    }
+
+    /// Returns whether the message is a question, which can be
+    /// replied to.
+    pub fn is_question(&self) -> bool {
+        self.sender.is_some()
+    }
+
+    /// Replies to the message if it is a question, returning
+    /// `Err(msg)` if it isn't or if the answer couldn't be sent.
+    pub fn reply<M: Message>(self, msg: M) -> Result<(), M> {
+        match self.sender {
+            Some(sender) => sender.reply(msg),
+            None => Err(msg),
+        }
+    }
+
+    /// Takes the sender allowing to reply to the message, if it is
+    /// a question.
+    pub fn take_sender(&mut self) -> Option<AnswerSender> {
+        self.sender.take()
+    }
 }
 
 impl Msg {
     pub(crate) fn broadcast<M: Message>(msg: M) -> Self {
         let inner = MsgInner::Broadcast(Arc::new(msg));
-        Msg(inner)
+        Msg(inner, MsgMeta::of::<M>())
     }
 
     pub(crate) fn tell<M: Message>(msg: M) -> Self {
         let inner = MsgInner::Tell(Box::new(msg));
-        Msg(inner)
+        Msg(inner, MsgMeta::of::<M>())
+    }
+
+    pub(crate) fn tell_acked<M: Message>(msg: M) -> (Self, Receiver<()>) {
+        let (ack, acked) = oneshot::channel();
+        let inner = MsgInner::Tell(Box::new(msg));
+
+        let meta = MsgMeta {
+            ack: Some(ack),
+            ..MsgMeta::of::<M>()
+        };
+
+        (Msg(inner, meta), acked)
     }
 
     pub(crate) fn ask<M: Message>(msg: M, sign: RefAddr) -> (Self, Answer) {
         let msg = Box::new(msg);
         let (sender, recver) = oneshot::channel();
-        let sender = AnswerSender(sender, sign);
-        let answer = Answer(recver);
+        let meta = MsgMeta::of::<M>();
+        let sender = AnswerSender(sender, sign.clone(), sign, meta.correlation_id);
+        let answer = Answer(recver, None, None, time::now());
 
         let sender = Some(sender);
         let inner = MsgInner::Ask { msg, sender };
 
-        (Msg(inner), answer)
+        (Msg(inner, meta), answer)
     }
 
     #[doc(hidden)]
@@ -326,6 +644,105 @@ impl Msg {
         }
     }
 
+    pub(crate) fn take_ack(&mut self) -> Option<AckSender> {
+        self.1.ack.take()
+    }
+
+    pub(crate) fn set_ack(&mut self, ack: AckSender) {
+        self.1.ack = Some(ack);
+    }
+
+    pub(crate) fn reply_to(&self) -> Option<&RefAddr> {
+        self.1.reply_to.as_ref()
+    }
+
+    /// Sets the address replies should be sent to, unless the
+    /// message was already forwarded.
+    pub(crate) fn set_reply_to(&mut self, sign: RefAddr) {
+        if self.1.reply_to.is_none() {
+            self.1.reply_to = Some(sign);
+        }
+    }
+
+    /// Returns the correlation id of the message (see
+    /// [`CorrelationId`]).
+    pub fn correlation_id(&self) -> CorrelationId {
+        self.1.correlation_id
+    }
+
+    // Sets the correlation id of the message and of the answer to
+    // it, if it is a question.
+    pub(crate) fn set_correlation_id(&mut self, correlation_id: CorrelationId) {
+        self.1.correlation_id = correlation_id;
+        if let MsgInner::Ask {
+            sender: Some(AnswerSender(_, _, _, answer_id)),
+            ..
+        } = &mut self.0
+        {
+            *answer_id = correlation_id;
+        }
+    }
+
+    pub(crate) fn type_name(&self) -> &'static str {
+        self.1.type_name
+    }
+
+    // Logs the sending of the message, unless it was already logged
+    // (e.g. when forwarding it).
+    pub(crate) fn log_sent(&mut self) {
+        if !self.1.logged {
+            self.1.logged = true;
+            system::log_message("sent", self.1.type_name, self.1.correlation_id);
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    pub(crate) fn span(&self) -> Option<&tracing::Span> {
+        self.1.span.as_ref()
+    }
+
+    pub(crate) fn enqueued_at(&self) -> Option<Instant> {
+        self.1.enqueued_at
+    }
+
+    pub(crate) fn set_enqueued_at(&mut self, enqueued_at: Instant) {
+        self.1.enqueued_at = Some(enqueued_at);
+    }
+
+    pub(crate) fn set_reservation(&mut self, reservation: MailboxReservation) {
+        self.1.reservation = Some(reservation);
+    }
+
+    pub(crate) fn take_reservation(&mut self) -> Option<MailboxReservation> {
+        self.1.reservation.take()
+    }
+
+    pub(crate) fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.1.expires_at = Some(time::now() + ttl);
+        self
+    }
+
+    pub(crate) fn is_expired(&self) -> bool {
+        self.1
+            .expires_at
+            .map(|expires_at| expires_at <= time::now())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn clear_ttl(&mut self) {
+        self.1.expires_at = None;
+    }
+
+    pub(crate) fn set_answer_signature(&mut self, sign: RefAddr) {
+        if let MsgInner::Ask {
+            sender: Some(AnswerSender(_, answer_sign, _, _)),
+            ..
+        } = &mut self.0
+        {
+            *answer_sign = sign;
+        }
+    }
+
     #[doc(hidden)]
     pub fn is<M: Message>(&self) -> bool {
         match &self.0 {
@@ -338,14 +755,15 @@ impl Msg {
     #[doc(hidden)]
     pub fn downcast<M: Message>(self) -> Result<M, Self> {
         trace!("{:?}: Downcasting to {}.", self, type_name::<M>());
-        match self.0 {
+        let Msg(inner, meta) = self;
+        match inner {
             MsgInner::Tell(msg) => {
                 if msg.is::<M>() {
                     let msg: Box<dyn Any + 'static> = msg;
                     Ok(*msg.downcast().unwrap())
                 } else {
                     let inner = MsgInner::Tell(msg);
-                    Err(Msg(inner))
+                    Err(Msg(inner, meta))
                 }
             }
             MsgInner::Ask { msg, sender } => {
@@ -354,10 +772,10 @@ impl Msg {
                     Ok(*msg.downcast().unwrap())
                 } else {
                     let inner = MsgInner::Ask { msg, sender };
-                    Err(Msg(inner))
+                    Err(Msg(inner, meta))
                 }
             }
-            _ => Err(self),
+            inner => Err(Msg(inner, meta)),
         }
     }
 
@@ -373,11 +791,44 @@ impl Msg {
         None
     }
 
+    #[cfg(feature = "wire")]
+    pub(crate) fn peek<M: Message>(&self) -> Option<&M> {
+        match &self.0 {
+            MsgInner::Tell(msg) => msg.downcast_ref(),
+            MsgInner::Ask { msg, .. } => msg.downcast_ref(),
+            MsgInner::Broadcast(msg) => msg.downcast_ref(),
+        }
+    }
+
+    pub(crate) fn type_id(&self) -> TypeId {
+        let msg: &dyn Any = self.as_ref();
+        msg.type_id()
+    }
+
+    // Returns the kind of the message and the id of its type, used
+    // to find the arm of a `Dispatch` handling it.
+    fn dispatch_key(&self) -> (ArmKind, TypeId) {
+        let kind = match &self.0 {
+            MsgInner::Broadcast(_) => ArmKind::Broadcast,
+            MsgInner::Tell(_) => ArmKind::Tell,
+            MsgInner::Ask { .. } => ArmKind::Question,
+        };
+        (kind, self.type_id())
+    }
+
     pub(crate) fn try_clone(&self) -> Option<Self> {
         trace!("{:?}: Trying to clone.", self);
         if let MsgInner::Broadcast(msg) = &self.0 {
             let inner = MsgInner::Broadcast(msg.clone());
-            Some(Msg(inner))
+            let meta = MsgMeta {
+                type_name: self.1.type_name,
+                correlation_id: self.1.correlation_id,
+                logged: self.1.logged,
+                #[cfg(feature = "tracing")]
+                span: self.1.span.clone(),
+                ..MsgMeta::default()
+            };
+            Some(Msg(inner, meta))
         } else {
             None
         }
@@ -385,18 +836,18 @@ impl Msg {
 
     pub(crate) fn try_unwrap<M: Message>(self) -> Result<M, Self> {
         debug!("{:?}: Trying to unwrap.", self);
-        if let MsgInner::Broadcast(msg) = self.0 {
+        if let Msg(MsgInner::Broadcast(msg), meta) = self {
             match msg.downcast() {
                 Ok(msg) => match Arc::try_unwrap(msg) {
                     Ok(msg) => Ok(msg),
                     Err(msg) => {
                         let inner = MsgInner::Broadcast(msg);
-                        Err(Msg(inner))
+                        Err(Msg(inner, meta))
                     }
                 },
                 Err(msg) => {
                     let inner = MsgInner::Broadcast(msg);
-                    Err(Msg(inner))
+                    Err(Msg(inner, meta))
                 }
             }
         } else {
@@ -479,8 +930,26 @@ impl BastionMessage {
         (BastionMessage::Message(msg), answer)
     }
 
-    pub(crate) fn restart_required(id: BastionId, parent_id: BastionId) -> Self {
-        BastionMessage::RestartRequired { id, parent_id }
+    pub(crate) fn tell_with_ttl<M: Message>(msg: M, ttl: Duration) -> Self {
+        let msg = Msg::tell(msg).with_ttl(ttl);
+        BastionMessage::Message(msg)
+    }
+
+    pub(crate) fn tell_acked<M: Message>(msg: M) -> (Self, Receiver<()>) {
+        let (msg, acked) = Msg::tell_acked(msg);
+        (BastionMessage::Message(msg), acked)
+    }
+
+    pub(crate) fn restart_required(
+        id: BastionId,
+        parent_id: BastionId,
+        failure: ChildFailure,
+    ) -> Self {
+        BastionMessage::RestartRequired {
+            id,
+            parent_id,
+            failure,
+        }
     }
 
     pub(crate) fn finished_child(id: BastionId, parent_id: BastionId) -> Self {
@@ -491,8 +960,12 @@ impl BastionMessage {
         BastionMessage::RestartSubtree
     }
 
-    pub(crate) fn restore_child(id: BastionId, state: Arc<Pin<Box<ContextState>>>) -> Self {
-        BastionMessage::RestoreChild { id, state }
+    pub(crate) fn restore_child(
+        id: BastionId,
+        state: Arc<Pin<Box<ContextState>>>,
+        context: RestartContext,
+    ) -> Self {
+        BastionMessage::RestoreChild { id, state, context }
     }
 
     pub(crate) fn drop_child(id: BastionId) -> Self {
@@ -515,6 +988,34 @@ impl BastionMessage {
         BastionMessage::Heartbeat
     }
 
+    pub(crate) fn suspend() -> Self {
+        BastionMessage::Suspend
+    }
+
+    pub(crate) fn resume() -> Self {
+        BastionMessage::Resume
+    }
+
+    pub(crate) fn retire_child(id: BastionId) -> Self {
+        BastionMessage::RetireChild { id }
+    }
+
+    pub(crate) fn link_failed(id: BastionId) -> Self {
+        BastionMessage::LinkFailed { id }
+    }
+
+    pub(crate) fn replace_exec(init: Init) -> Self {
+        BastionMessage::ReplaceExec(init)
+    }
+
+    pub(crate) fn restart_child(id: BastionId) -> Self {
+        BastionMessage::RestartChild { id }
+    }
+
+    pub(crate) fn kill_with_reason(reason: String) -> Self {
+        BastionMessage::KillWithReason { reason }
+    }
+
     pub(crate) fn try_clone(&self) -> Option<Self> {
         trace!("{:?}: Trying to clone.", self);
         let clone = match self {
@@ -540,21 +1041,32 @@ impl BastionMessage {
                 state.clone(),
             ),
             BastionMessage::Message(msg) => BastionMessage::Message(msg.try_clone()?),
-            BastionMessage::RestartRequired { id, parent_id } => {
-                BastionMessage::restart_required(id.clone(), parent_id.clone())
-            }
+            BastionMessage::RestartRequired {
+                id,
+                parent_id,
+                failure,
+            } => BastionMessage::restart_required(id.clone(), parent_id.clone(), failure.clone()),
             BastionMessage::FinishedChild { id, parent_id } => {
                 BastionMessage::finished_child(id.clone(), parent_id.clone())
             }
             BastionMessage::RestartSubtree => BastionMessage::restart_subtree(),
-            BastionMessage::RestoreChild { id, state } => {
-                BastionMessage::restore_child(id.clone(), state.clone())
+            BastionMessage::RestoreChild { id, state, context } => {
+                BastionMessage::restore_child(id.clone(), state.clone(), context.clone())
             }
             BastionMessage::DropChild { id } => BastionMessage::drop_child(id.clone()),
             BastionMessage::SetState { state } => BastionMessage::set_state(state.clone()),
             BastionMessage::Stopped { id } => BastionMessage::stopped(id.clone()),
             BastionMessage::Faulted { id } => BastionMessage::faulted(id.clone()),
             BastionMessage::Heartbeat => BastionMessage::heartbeat(),
+            BastionMessage::Suspend => BastionMessage::suspend(),
+            BastionMessage::Resume => BastionMessage::resume(),
+            BastionMessage::RetireChild { id } => BastionMessage::retire_child(id.clone()),
+            BastionMessage::LinkFailed { id } => BastionMessage::link_failed(id.clone()),
+            BastionMessage::ReplaceExec(_) => return None,
+            BastionMessage::RestartChild { id } => BastionMessage::restart_child(id.clone()),
+            BastionMessage::KillWithReason { reason } => {
+                BastionMessage::kill_with_reason(reason.clone())
+            }
         };
 
         Some(clone)
@@ -569,12 +1081,141 @@ impl BastionMessage {
     }
 }
 
+impl Answer {
+    // Returns an answer along with the sending side of its channel,
+    // for the answers which aren't sent using an `AnswerSender` (e.g.
+    // the ones received from another node).
+    #[cfg(feature = "remote")]
+    pub(crate) fn channel() -> (oneshot::Sender<SignedMessage>, Self) {
+        let (sender, recver) = oneshot::channel();
+        (sender, Answer(recver, None, None, time::now()))
+    }
+
+    // Counts the ask in the in-flight asks of the recipient's
+    // group until the answer is received or dropped.
+    pub(crate) fn with_permit(mut self, permit: Option<InflightPermit>) -> Self {
+        self.1 = permit;
+        self
+    }
+
+    /// Makes the answer fail with [`SendError::Timeout`] if it isn't
+    /// received within the given duration.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long to wait for the answer.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let children_ref = Bastion::children(|children| children).unwrap();
+    /// # let child_ref = children_ref.elems()[0].clone();
+    /// Bastion::children(|children| {
+    ///     children.with_exec(move |ctx: BastionContext| {
+    ///         let child_ref = child_ref.clone();
+    ///         async move {
+    ///             let count: Result<u64, SendError> = ctx
+    ///                 .ask(&child_ref, "count")
+    ///                 .unwrap()
+    ///                 .timeout(Duration::from_secs(1))
+    ///                 .into_typed()
+    ///                 .await;
+    ///             Ok(())
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`SendError::Timeout`]: crate::errors::SendError::Timeout
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.2 = Some((executor::deadline(timeout), timeout));
+        self
+    }
+
+    /// Waits for the answer and downcasts it to `R`, failing with
+    /// [`SendError::UnexpectedAnswer`] if it is of another type.
+    ///
+    /// [`SendError::UnexpectedAnswer`]: crate::errors::SendError::UnexpectedAnswer
+    pub async fn into_typed<R: Message>(self) -> Result<R, SendError> {
+        self.map(|answer| answer.msg.downcast::<R>())
+            .await?
+            .map_err(SendError::UnexpectedAnswer)
+    }
+
+    /// Waits for the answer and calls the given closure with it,
+    /// returning its result.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The closure called with the answer.
+    pub async fn map<T, F>(mut self, f: F) -> Result<T, SendError>
+    where
+        F: FnOnce(SignedMessage) -> T,
+    {
+        let answer = future::poll_fn(|ctx| self.poll_answer(ctx)).await?;
+        Ok(f(answer))
+    }
+
+    fn poll_answer(&mut self, ctx: &mut Context) -> Poll<Result<SignedMessage, SendError>> {
+        if let Poll::Ready(answer) = Pin::new(&mut self.0).poll(ctx) {
+            self.1.take();
+            if answer.is_ok() {
+                system::answer_received(time::since(self.3));
+            }
+            return Poll::Ready(answer.map_err(|_| SendError::NoAnswer));
+        }
+
+        if let Some((deadline, timeout)) = &mut self.2 {
+            if Pin::new(deadline).poll(ctx).is_ready() {
+                debug!("{:?}: Timed out.", self);
+                self.1.take();
+                return Poll::Ready(Err(SendError::Timeout(*timeout)));
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+impl<R: Message> Future for TypedAnswer<R> {
+    type Output = Result<R, ()>;
+
+    fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
+        let answer = &mut self.get_mut().answer;
+        Pin::new(answer)
+            .poll(ctx)
+            .map(|answer| answer.and_then(|answer| answer.msg.downcast::<R>().map_err(|_| ())))
+    }
+}
+
 impl Future for Answer {
     type Output = Result<SignedMessage, ()>;
 
     fn poll(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Self::Output> {
         debug!("{:?}: Polling.", self);
-        Pin::new(&mut self.get_mut().0).poll(ctx).map_err(|_| ())
+        self.get_mut().poll_answer(ctx).map_err(|_| ())
     }
 }
 
@@ -919,6 +1560,10 @@ impl<O> MessageHandlerState<O> {
 ///     [`on_tell`],
 ///   - fallback case, which matches everything, entitled [`on_fallback`].
 ///
+/// Questions and messages that can not be responded to can also be
+/// matched with [`on_question_async`] and [`on_tell_async`], whose
+/// closures return a future which is awaited by the handler.
+///
 /// The closure passed to the functions described previously must return the
 /// same type. This value is retrieved when [`on_fallback`] is invoked.
 ///
@@ -1000,6 +1645,8 @@ impl<O> MessageHandlerState<O> {
 /// [`on_question`]: Self::on_question
 /// [`on_tell`]: Self::on_tell
 /// [`on_fallback`]: Self::on_fallback
+/// [`on_question_async`]: Self::on_question_async
+/// [`on_tell_async`]: Self::on_tell_async
 /// [`reply`]: AnswerSender::reply
 #[derive(Debug)]
 pub struct MessageHandler<O> {
@@ -1031,10 +1678,163 @@ impl<O> MessageHandler<O> {
         }
     }
 
+    /// Matches on a question of a specific type like [`on_question`],
+    /// but calls a closure returning a future, which is awaited
+    /// before returning the handler (e.g. to reply once some async
+    /// I/O completed).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use bastion::message::MessageHandler;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             loop {
+    ///                 MessageHandler::new(ctx.recv().await?)
+    ///                     .on_question_async(|key: String, sender| async move {
+    ///                         // Fetch the value asynchronously...
+    ///                         # let value = key;
+    ///                         sender.reply(value).ok();
+    ///                     })
+    ///                     .await
+    ///                     .on_fallback(|_, _| ());
+    ///             }
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`on_question`]: Self::on_question
+    pub async fn on_question_async<T, F, Fut>(self, f: F) -> MessageHandler<O>
+    where
+        T: 'static,
+        F: FnOnce(T, AnswerSender) -> Fut,
+        Fut: Future<Output = O>,
+    {
+        match self.try_into_question::<T>() {
+            Ok((arg, sender)) => {
+                let val = f(arg, sender).await;
+                MessageHandler::matched(val)
+            }
+            Err(this) => this,
+        }
+    }
+
+    /// Matches on a question of a specific type asked using
+    /// [`BastionContext::request`], whose asker expects an answer of
+    /// type `R`.
+    ///
+    /// The closure is given a [`QuestionSender`] which only accepts
+    /// answers of type `R`. Questions whose asker expects another
+    /// type of answer aren't matched.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use bastion::message::MessageHandler;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             loop {
+    ///                 MessageHandler::new(ctx.recv().await?)
+    ///                     .on_request(|name: &'static str, sender: QuestionSender<usize>| {
+    ///                         sender.reply(name.len()).ok();
+    ///                     })
+    ///                     .on_fallback(|_, _| ());
+    ///             }
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`BastionContext::request`]: crate::context::BastionContext::request
+    pub fn on_request<T, R, F>(self, f: F) -> MessageHandler<O>
+    where
+        T: 'static,
+        R: 'static,
+        F: FnOnce(T, QuestionSender<R>) -> O,
+    {
+        match self.try_into_question::<Request<T, R>>() {
+            Ok((request, sender)) => {
+                let sender = QuestionSender {
+                    sender,
+                    _reply: PhantomData,
+                };
+                let val = f(request.into_inner(), sender);
+                MessageHandler::matched(val)
+            }
+            Err(this) => this,
+        }
+    }
+
+    /// Calls the arm of the given [`Dispatch`] handling the message,
+    /// if any.
+    ///
+    /// Unlike a chain of `on_*` calls, this finds the matching arm
+    /// using a single lookup, whatever the number of arms.
+    pub fn on_dispatch(self, dispatch: &mut Dispatch<O>) -> MessageHandler<O> {
+        match self.state.take_message() {
+            Ok(msg) => match dispatch.dispatch(msg) {
+                Ok(output) => MessageHandler::matched(output),
+                Err(msg) => MessageHandler::new(msg),
+            },
+            Err(output) => MessageHandler::matched(output),
+        }
+    }
+
     /// Calls a fallback function if the message has still not matched yet.
     ///
     /// This consumes the [`MessageHandler`], so that no matching can be
     /// performed anymore.
+    ///
+    /// See [`on_unknown`] to inspect the message (e.g. the name of
+    /// its type) before logging it.
+    ///
+    /// [`on_unknown`]: Self::on_unknown
     pub fn on_fallback<F>(self, f: F) -> O
     where
         F: FnOnce(&dyn Any, RefAddr) -> O,
@@ -1043,6 +1843,62 @@ impl<O> MessageHandler<O> {
             .output_or_else(|SignedMessage { msg, sign }| f(msg.as_ref(), sign))
     }
 
+    /// Calls a fallback function if the message has still not
+    /// matched yet, like [`on_fallback`], giving it the message as
+    /// an [`UnknownMessage`] which can be inspected.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             loop {
+    ///                 MessageHandler::new(ctx.recv().await?)
+    ///                     .on_tell(|msg: &str, _| println!("received {}", msg))
+    ///                     .on_unknown(|msg, sender| {
+    ///                         println!(
+    ///                             "unexpected message of type {} from {}",
+    ///                             msg.type_name(),
+    ///                             sender.path(),
+    ///                         );
+    ///                     });
+    ///             }
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`on_fallback`]: Self::on_fallback
+    pub fn on_unknown<F>(self, f: F) -> O
+    where
+        F: FnOnce(UnknownMessage, RefAddr) -> O,
+    {
+        self.state
+            .output_or_else(|SignedMessage { msg, sign }| f(UnknownMessage::from(msg), sign))
+    }
+
     /// Calls a function if the incoming message is a broadcast and has a
     /// specific type.
     pub fn on_broadcast<T, F>(self, f: F) -> MessageHandler<O>
@@ -1075,6 +1931,216 @@ impl<O> MessageHandler<O> {
         }
     }
 
+    /// Calls a function if the incoming message is a [`Bytes`]
+    /// buffer that was told or broadcasted (e.g. using
+    /// [`Distributor::tell_bytes`]).
+    ///
+    /// The buffer isn't copied: broadcasted buffers share the same
+    /// memory between all their recipients.
+    ///
+    /// [`Distributor::tell_bytes`]: crate::distributor::Distributor::tell_bytes
+    pub fn on_bytes<F>(self, f: F) -> MessageHandler<O>
+    where
+        F: FnOnce(Bytes, RefAddr) -> O,
+    {
+        let this = match self.try_into_tell::<Bytes>() {
+            Ok((bytes, addr)) => return MessageHandler::matched(f(bytes, addr)),
+            Err(this) => this,
+        };
+
+        match this.try_into_broadcast::<Bytes>() {
+            Ok((bytes, addr)) => MessageHandler::matched(f(Bytes::clone(&bytes), addr)),
+            Err(this) => this,
+        }
+    }
+
+    /// Calls a closure returning a future if the incoming message
+    /// can't be replied to and has a specific type, like
+    /// [`on_tell`], and awaits the future before returning the
+    /// handler.
+    ///
+    /// [`on_tell`]: Self::on_tell
+    pub async fn on_tell_async<T, F, Fut>(self, f: F) -> MessageHandler<O>
+    where
+        T: Debug + 'static,
+        F: FnOnce(T, RefAddr) -> Fut,
+        Fut: Future<Output = O>,
+    {
+        match self.try_into_tell::<T>() {
+            Ok((msg, addr)) => {
+                let val = f(msg, addr).await;
+                MessageHandler::matched(val)
+            }
+            Err(this) => this,
+        }
+    }
+
+    /// Calls a function if the incoming message is a [`WireMessage`]
+    /// that can't be replied to and whose tag is the name of the
+    /// type `T`, with the message deserialized as a `T`.
+    ///
+    /// A message that couldn't be deserialized isn't matched.
+    ///
+    /// This method is only available with the `wire` feature.
+    ///
+    /// [`WireMessage`]: crate::wire::WireMessage
+    #[cfg(feature = "wire")]
+    pub fn on_wire<T, F>(self, f: F) -> MessageHandler<O>
+    where
+        T: DeserializeOwned + 'static,
+        F: FnOnce(T, RefAddr) -> O,
+    {
+        debug!("on_wire with type {}", std::any::type_name::<T>());
+        let unpacked = match &self.state {
+            MessageHandlerState::Unmatched(signed) if !signed.msg.is_ask() => signed
+                .msg
+                .peek::<WireMessage>()
+                .and_then(WireMessage::unpack::<T>),
+            _ => None,
+        };
+
+        match (unpacked, self.state) {
+            (Some(Ok(msg)), MessageHandlerState::Unmatched(signed)) => {
+                MessageHandler::matched(f(msg, signed.sign))
+            }
+            (Some(Err(error)), state) => {
+                debug!("couldn't deserialize wire message: {}", error);
+                MessageHandler { state }
+            }
+            (_, state) => MessageHandler { state },
+        }
+    }
+
+    /// Calls a function if the incoming message is a [`WireMessage`]
+    /// that can't be replied to, tagged with the name the type `T`
+    /// was registered with in the [`MessageRegistry`] and with a
+    /// version within `versions`.
+    ///
+    /// Messages of the version `T` is registered with are
+    /// deserialized as a `T`, while the ones of other versions are
+    /// given to `upgrade`, which converts them to a `T` (or returns
+    /// `None` if it can't, leaving the message unmatched). This
+    /// allows elements running a newer version of `T` to handle the
+    /// messages of the elements still running an older one (e.g.
+    /// during rolling upgrades).
+    ///
+    /// Messages aren't matched if `T` isn't registered.
+    ///
+    /// This method is only available with the `wire` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use serde::{Deserialize, Serialize};
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// // The version 1 of `Deposit` didn't have a currency.
+    /// #[derive(Debug, Deserialize)]
+    /// struct DepositV1 {
+    ///     amount: u64,
+    /// }
+    ///
+    /// #[derive(Debug, Serialize, Deserialize)]
+    /// struct Deposit {
+    ///     amount: u64,
+    ///     currency: String,
+    /// }
+    ///
+    /// MessageRegistry::global()
+    ///     .register::<Deposit>("bank.deposit", 2)
+    ///     .expect("Couldn't register the message type.");
+    ///
+    /// Bastion::children(|children| {
+    ///     children.with_exec(|ctx: BastionContext| {
+    ///         async move {
+    ///             loop {
+    ///                 MessageHandler::new(ctx.recv().await?)
+    ///                     .on_versioned(
+    ///                         1..=2,
+    ///                         |wire| {
+    ///                             let old: DepositV1 = wire.unpack_tagged(wire.tag())?.ok()?;
+    ///                             Some(Deposit {
+    ///                                 amount: old.amount,
+    ///                                 currency: "EUR".to_string(),
+    ///                             })
+    ///                         },
+    ///                         |deposit: Deposit, _| {
+    ///                             // Handle the deposit...
+    ///                         },
+    ///                     )
+    ///                     .on_fallback(|_, _| ());
+    ///             }
+    ///         }
+    ///     })
+    /// }).expect("Couldn't create the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`WireMessage`]: crate::wire::WireMessage
+    /// [`MessageRegistry`]: crate::wire::MessageRegistry
+    #[cfg(feature = "wire")]
+    pub fn on_versioned<T, V, U, F>(self, versions: V, upgrade: U, f: F) -> MessageHandler<O>
+    where
+        T: DeserializeOwned + 'static,
+        V: RangeBounds<u32>,
+        U: FnOnce(&WireMessage) -> Option<T>,
+        F: FnOnce(T, RefAddr) -> O,
+    {
+        debug!("on_versioned with type {}", std::any::type_name::<T>());
+        let schema = match MessageRegistry::global().schema_of::<T>() {
+            Some(schema) => schema,
+            None => {
+                debug!(
+                    "{} isn't registered and can't be matched by version.",
+                    std::any::type_name::<T>()
+                );
+                return self;
+            }
+        };
+
+        let unpacked = match &self.state {
+            MessageHandlerState::Unmatched(signed) if !signed.msg.is_ask() => signed
+                .msg
+                .peek::<WireMessage>()
+                .filter(|wire| wire.tag() == schema.name() && versions.contains(&wire.version()))
+                .and_then(|wire| {
+                    if wire.version() == schema.version() {
+                        wire.unpack_tagged::<T>(schema.name())?
+                            .map_err(|error| debug!("couldn't deserialize wire message: {}", error))
+                            .ok()
+                    } else {
+                        upgrade(wire)
+                    }
+                }),
+            _ => None,
+        };
+
+        match (unpacked, self.state) {
+            (Some(msg), MessageHandlerState::Unmatched(signed)) => {
+                MessageHandler::matched(f(msg, signed.sign))
+            }
+            (_, state) => MessageHandler { state },
+        }
+    }
+
     fn matched(output: O) -> MessageHandler<O> {
         let state = MessageHandlerState::Matched(output);
         MessageHandler { state }
@@ -1085,10 +2151,13 @@ impl<O> MessageHandler<O> {
         match self.state.take_message() {
             Ok(SignedMessage {
                 msg:
-                    Msg(MsgInner::Ask {
-                        msg,
-                        sender: Some(sender),
-                    }),
+                    Msg(
+                        MsgInner::Ask {
+                            msg,
+                            sender: Some(sender),
+                        },
+                        _,
+                    ),
                 ..
             }) if msg.is::<T>() => {
                 let msg: Box<dyn Any> = msg;
@@ -1109,7 +2178,7 @@ impl<O> MessageHandler<O> {
         );
         match self.state.take_message() {
             Ok(SignedMessage {
-                msg: Msg(MsgInner::Broadcast(msg)),
+                msg: Msg(MsgInner::Broadcast(msg), _),
                 sign,
             }) if msg.is::<T>() => {
                 let msg: Arc<dyn Any + Send + Sync + 'static> = msg;
@@ -1125,7 +2194,7 @@ impl<O> MessageHandler<O> {
         debug!("try_into_tell with type {}", std::any::type_name::<T>());
         match self.state.take_message() {
             Ok(SignedMessage {
-                msg: Msg(MsgInner::Tell(msg)),
+                msg: Msg(MsgInner::Tell(msg), _),
                 sign,
             }) if msg.is::<T>() => {
                 let msg: Box<dyn Any> = msg;
@@ -1136,3 +2205,504 @@ impl<O> MessageHandler<O> {
         }
     }
 }
+
+#[derive(Debug)]
+/// A message which wasn't matched by any handler, given to
+/// [`MessageHandler::on_unknown`], which allows to inspect it to
+/// log actionable diagnostics.
+pub struct UnknownMessage(Msg);
+
+impl UnknownMessage {
+    /// Returns the name of the type of the message (see
+    /// [`std::any::type_name`]).
+    pub fn type_name(&self) -> &'static str {
+        self.0.type_name()
+    }
+
+    /// Returns the [`TypeId`] of the type of the message.
+    pub fn type_id(&self) -> TypeId {
+        self.0.type_id()
+    }
+
+    /// Returns whether the message was broadcasted.
+    pub fn is_broadcast(&self) -> bool {
+        self.0.is_broadcast()
+    }
+
+    /// Returns whether the message was told.
+    pub fn is_tell(&self) -> bool {
+        self.0.is_tell()
+    }
+
+    /// Returns whether the message was asked, and can thus be
+    /// replied to.
+    pub fn is_ask(&self) -> bool {
+        self.0.is_ask()
+    }
+
+    /// Returns the length of the serialized message if the message
+    /// is a [`WireMessage`], or `None` otherwise.
+    ///
+    /// This method is only available with the `wire` feature.
+    ///
+    /// [`WireMessage`]: crate::wire::WireMessage
+    #[cfg(feature = "wire")]
+    pub fn payload_len(&self) -> Option<usize> {
+        self.0
+            .peek::<WireMessage>()
+            .map(|wire| wire.payload().len())
+    }
+
+    /// Returns the message, e.g. to downcast it.
+    pub fn as_any(&self) -> &dyn Any {
+        self.0.as_ref()
+    }
+
+    /// Returns the message as a [`Msg`], e.g. to forward it.
+    pub fn into_msg(self) -> Msg {
+        self.0
+    }
+}
+
+impl From<Msg> for UnknownMessage {
+    fn from(msg: Msg) -> Self {
+        UnknownMessage(msg)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum ArmKind {
+    Broadcast,
+    Tell,
+    Question,
+}
+
+impl ArmKind {
+    fn name(self) -> &'static str {
+        match self {
+            ArmKind::Broadcast => "broadcast",
+            ArmKind::Tell => "tell",
+            ArmKind::Question => "question",
+        }
+    }
+}
+
+type Arm<O> = Box<dyn FnMut(SignedMessage) -> O + Send>;
+
+/// A set of handlers ("arms") for messages of distinct types, built
+/// once using a [`DispatchBuilder`] and then used to handle every
+/// received message with [`dispatch`] or
+/// [`MessageHandler::on_dispatch`].
+///
+/// Each received message is routed to its arm using a single
+/// lookup, which keeps actors handling many types of messages
+/// readable, and registering two arms for the same messages is
+/// reported as an error when building the dispatch.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();    
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();    
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// Bastion::children(|children| {
+///     children.with_exec(|ctx: BastionContext| {
+///         async move {
+///             let mut dispatch = Dispatch::builder()
+///                 .on_tell(|n: u32, _| println!("received {}", n))
+///                 .on_tell(|s: &'static str, _| println!("received {:?}", s))
+///                 .on_question(|question: &'static str, sender| {
+///                     sender.reply(question.len()).ok();
+///                 })
+///                 .build()
+///                 .expect("Two arms handle the same messages.");
+///
+///             loop {
+///                 MessageHandler::new(ctx.recv().await?)
+///                     .on_dispatch(&mut dispatch)
+///                     .on_fallback(|_, _| ());
+///             }
+///         }
+///     })
+/// }).expect("Couldn't create the children group.");
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+///
+/// [`dispatch`]: Self::dispatch
+pub struct Dispatch<O> {
+    arms: FxHashMap<(ArmKind, TypeId), (&'static str, Arm<O>)>,
+}
+
+/// The builder of a [`Dispatch`], created using
+/// [`Dispatch::builder`].
+pub struct DispatchBuilder<O> {
+    arms: FxHashMap<(ArmKind, TypeId), (&'static str, Arm<O>)>,
+    overlap: Option<DispatchError>,
+}
+
+impl<O> Dispatch<O> {
+    /// Creates a new [`DispatchBuilder`] without any arm.
+    pub fn builder() -> DispatchBuilder<O> {
+        DispatchBuilder {
+            arms: FxHashMap::default(),
+            overlap: None,
+        }
+    }
+
+    /// Calls the arm handling the given message, returning its
+    /// output, or gives the message back if no arm handles it.
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The message to handle.
+    pub fn dispatch(&mut self, msg: SignedMessage) -> Result<O, SignedMessage> {
+        match self.arms.get_mut(&msg.msg.dispatch_key()) {
+            Some((type_name, arm)) => {
+                trace!("Dispatch: Dispatching message of type {}.", type_name);
+                Ok(arm(msg))
+            }
+            None => Err(msg),
+        }
+    }
+}
+
+impl<O: 'static> DispatchBuilder<O> {
+    /// Adds an arm handling the broadcasts of type `T`.
+    pub fn on_broadcast<T, F>(self, mut f: F) -> Self
+    where
+        T: Send + Sync + 'static,
+        F: FnMut(&T, RefAddr) -> O + Send + 'static,
+    {
+        self.arm::<T>(ArmKind::Broadcast, move |msg| {
+            MessageHandler::new(msg)
+                .on_broadcast::<T, _>(&mut f)
+                .on_fallback(|_, _| unreachable!())
+        })
+    }
+
+    /// Adds an arm handling the messages of type `T` that can't be
+    /// replied to.
+    pub fn on_tell<T, F>(self, mut f: F) -> Self
+    where
+        T: Debug + Send + 'static,
+        F: FnMut(T, RefAddr) -> O + Send + 'static,
+    {
+        self.arm::<T>(ArmKind::Tell, move |msg| {
+            MessageHandler::new(msg)
+                .on_tell::<T, _>(&mut f)
+                .on_fallback(|_, _| unreachable!())
+        })
+    }
+
+    /// Adds an arm handling the questions of type `T`.
+    pub fn on_question<T, F>(self, mut f: F) -> Self
+    where
+        T: Send + 'static,
+        F: FnMut(T, AnswerSender) -> O + Send + 'static,
+    {
+        self.arm::<T>(ArmKind::Question, move |msg| {
+            MessageHandler::new(msg)
+                .on_question::<T, _>(&mut f)
+                .on_fallback(|_, _| unreachable!())
+        })
+    }
+
+    /// Adds an arm handling the questions of type `T` asked using
+    /// [`BastionContext::request`] whose asker expects an answer of
+    /// type `R` (see [`MessageHandler::on_request`]).
+    ///
+    /// [`BastionContext::request`]: crate::context::BastionContext::request
+    pub fn on_request<T, R, F>(self, mut f: F) -> Self
+    where
+        T: Send + 'static,
+        R: 'static,
+        F: FnMut(T, QuestionSender<R>) -> O + Send + 'static,
+    {
+        self.arm::<Request<T, R>>(ArmKind::Question, move |msg| {
+            MessageHandler::new(msg)
+                .on_request::<T, R, _>(&mut f)
+                .on_fallback(|_, _| unreachable!())
+        })
+    }
+
+    /// Builds the [`Dispatch`], or returns an error if two of its
+    /// arms handle the same messages.
+    pub fn build(self) -> Result<Dispatch<O>, DispatchError> {
+        match self.overlap {
+            Some(error) => Err(error),
+            None => Ok(Dispatch { arms: self.arms }),
+        }
+    }
+
+    fn arm<T: 'static>(
+        mut self,
+        kind: ArmKind,
+        arm: impl FnMut(SignedMessage) -> O + Send + 'static,
+    ) -> Self {
+        let type_name = type_name::<T>();
+        let key = (kind, TypeId::of::<T>());
+        if self.arms.contains_key(&key) {
+            debug!(
+                "DispatchBuilder: Overlapping arms for {} of type {}.",
+                kind.name(),
+                type_name
+            );
+            self.overlap.get_or_insert(DispatchError::OverlappingArms {
+                kind: kind.name(),
+                type_name,
+            });
+        } else {
+            self.arms.insert(key, (type_name, Box::new(arm)));
+        }
+
+        self
+    }
+}
+
+impl<O> Debug for Dispatch<O> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Dispatch")
+            .field(
+                "arms",
+                &self.arms.values().map(|(name, _)| name).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<O> Debug for DispatchBuilder<O> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("DispatchBuilder")
+            .field(
+                "arms",
+                &self.arms.values().map(|(name, _)| name).collect::<Vec<_>>(),
+            )
+            .field("overlap", &self.overlap)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::envelope::Envelope;
+    use crate::path::BastionPath;
+    use crate::run;
+
+    fn test_addr() -> RefAddr {
+        let (sender, _) = futures::channel::mpsc::unbounded();
+        RefAddr::new(Arc::new(BastionPath::root()), sender)
+    }
+
+    #[test]
+    fn test_async_handlers_are_awaited() {
+        let (msg, answer) = Msg::ask("question", test_addr());
+        let handled = run!(async {
+            MessageHandler::new(SignedMessage::new(Msg::tell(1_u32), test_addr()))
+                .on_question_async(|_: &str, _| async { 0 })
+                .await
+                .on_tell_async(|n: u32, _| async move { n + 1 })
+                .await
+                .on_fallback(|_, _| 0)
+        });
+        assert_eq!(handled, 2);
+
+        run!(async {
+            MessageHandler::new(SignedMessage::new(msg, test_addr()))
+                .on_question_async(|question: &str, sender| async move {
+                    futures_timer::Delay::new(Duration::from_millis(1)).await;
+                    sender.reply(question.len()).unwrap();
+                })
+                .await
+                .on_fallback(|_, _| unreachable!())
+        });
+        let reply = run!(answer).unwrap();
+        assert_eq!(reply.msg.downcast::<usize>().unwrap(), 8);
+    }
+
+    #[test]
+    fn test_answer_combinators() {
+        let (mut msg, answer) = Msg::ask("question", test_addr());
+        msg.take_sender().unwrap().reply(42_u64).unwrap();
+        assert_eq!(run!(answer.into_typed::<u64>()).unwrap(), 42);
+
+        let (mut msg, answer) = Msg::ask("question", test_addr());
+        msg.take_sender().unwrap().reply(42_u64).unwrap();
+        let unexpected = run!(answer.into_typed::<&str>());
+        assert!(matches!(unexpected, Err(SendError::UnexpectedAnswer(_))));
+
+        let (_msg, answer) = Msg::ask("question", test_addr());
+        let timed_out = run!(answer.timeout(Duration::from_millis(1)).map(|_| ()));
+        assert!(matches!(timed_out, Err(SendError::Timeout(_))));
+
+        let (msg, answer) = Msg::ask("question", test_addr());
+        drop(msg);
+        assert!(matches!(run!(answer.map(|_| ())), Err(SendError::NoAnswer)));
+    }
+
+    #[test]
+    #[cfg(feature = "wire")]
+    fn test_on_wire_matches_tagged_messages() {
+        let wire = |value: &u32| {
+            SignedMessage::new(Msg::tell(WireMessage::pack(value).unwrap()), test_addr())
+        };
+
+        let handled = MessageHandler::new(wire(&41))
+            .on_wire(|n: String, _| n.len() as u32)
+            .on_wire(|n: u32, _| n + 1)
+            .on_fallback(|_, _| 0);
+        assert_eq!(handled, 42);
+
+        let corrupted =
+            WireMessage::with_tag(std::any::type_name::<u32>(), &"not a number").unwrap();
+        let handled = MessageHandler::new(SignedMessage::new(Msg::tell(corrupted), test_addr()))
+            .on_wire(|n: u32, _| n)
+            .on_fallback(|_, _| 0);
+        assert_eq!(handled, 0);
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let mut dispatch = Dispatch::builder()
+            .on_tell(|n: u32, _| n + 1)
+            .on_tell(|s: &str, _| s.len() as u32)
+            .on_question(|n: u32, sender| {
+                sender.reply(n * 2).unwrap();
+                0
+            })
+            .build()
+            .unwrap();
+
+        let tell = |msg| SignedMessage::new(msg, test_addr());
+        assert_eq!(dispatch.dispatch(tell(Msg::tell(41_u32))).unwrap(), 42);
+        assert_eq!(dispatch.dispatch(tell(Msg::tell("four"))).unwrap(), 4);
+        assert!(dispatch.dispatch(tell(Msg::tell(1_u64))).is_err());
+        assert!(dispatch.dispatch(tell(Msg::broadcast(1_u32))).is_err());
+
+        let (msg, answer) = Msg::ask(21_u32, test_addr());
+        let handled = MessageHandler::new(tell(msg))
+            .on_dispatch(&mut dispatch)
+            .on_fallback(|_, _| 1);
+        assert_eq!(handled, 0);
+        assert_eq!(run!(answer.into_typed::<u32>()).unwrap(), 42);
+
+        let overlap = Dispatch::<()>::builder()
+            .on_tell(|_: u32, _| ())
+            .on_question(|_: u32, _| ())
+            .on_tell(|_: u32, _| ())
+            .build();
+        assert_eq!(
+            overlap.unwrap_err(),
+            DispatchError::OverlappingArms {
+                kind: "tell",
+                type_name: "u32",
+            }
+        );
+    }
+
+    #[test]
+    fn test_unknown_message_inspection() {
+        let unknown = MessageHandler::new(SignedMessage::new(Msg::tell(42_u64), test_addr()))
+            .on_tell(|_: u32, _| None)
+            .on_unknown(|msg, _| Some(msg));
+        let unknown = unknown.unwrap();
+
+        assert_eq!(unknown.type_name(), "u64");
+        assert_eq!(unknown.type_id(), TypeId::of::<u64>());
+        assert!(unknown.is_tell());
+        assert_eq!(unknown.as_any().downcast_ref::<u64>(), Some(&42));
+    }
+
+    #[test]
+    #[cfg(feature = "wire")]
+    fn test_unknown_wire_message_payload_len() {
+        let wire = WireMessage::pack(&42_u32).unwrap();
+        let unknown = UnknownMessage::from(Msg::tell(wire));
+        assert_eq!(unknown.payload_len(), Some(2));
+        assert_eq!(UnknownMessage::from(Msg::tell(42_u32)).payload_len(), None);
+    }
+
+    #[test]
+    fn test_correlation_ids() {
+        let first = Msg::tell(1_u32);
+        let second = Msg::tell(1_u32);
+        assert_ne!(first.correlation_id(), second.correlation_id());
+
+        let correlation_id = CorrelationId::from(42_u128);
+        let sign = test_addr().with_correlation_id(correlation_id);
+        let env = Envelope::new_with_sign(BastionMessage::tell(1_u32), sign);
+        let msg = env.into_signed_message().unwrap();
+        assert_eq!(msg.correlation_id(), correlation_id);
+
+        let (mut question, answer) = Msg::ask("question", test_addr());
+        question.set_correlation_id(correlation_id);
+        question.take_sender().unwrap().reply(42_u64).unwrap();
+        assert_eq!(run!(answer).unwrap().correlation_id(), correlation_id);
+    }
+
+    #[test]
+    #[cfg(feature = "wire")]
+    fn test_on_versioned_upgrades_older_versions() {
+        #[derive(Debug, serde::Serialize, serde::Deserialize)]
+        struct Counter(u64);
+
+        MessageRegistry::global()
+            .register::<Counter>("test.counter", 2)
+            .unwrap();
+        let versioned = |version| {
+            let wire = WireMessage::pack(&Counter(21))
+                .unwrap()
+                .with_version(version);
+            SignedMessage::new(Msg::tell(wire), test_addr())
+        };
+        let handle = |version| {
+            MessageHandler::new(versioned(version))
+                .on_versioned(
+                    1..=2,
+                    |wire| {
+                        let Counter(old): Counter = wire.unpack_tagged(wire.tag())?.ok()?;
+                        Some(Counter(old * 2))
+                    },
+                    |Counter(n): Counter, _| n,
+                )
+                .on_fallback(|_, _| 0)
+        };
+
+        assert_eq!(handle(2), 21);
+        assert_eq!(handle(1), 42);
+        assert_eq!(handle(3), 0);
+    }
+
+    #[test]
+    fn test_on_bytes_shares_the_buffer() {
+        let bytes = Bytes::from(vec![42; 1024]);
+        let handle = |msg| {
+            MessageHandler::new(SignedMessage::new(msg, test_addr()))
+                .on_bytes(|bytes, _| Some(bytes))
+                .on_fallback(|_, _| None)
+        };
+
+        let told = handle(Msg::tell(bytes.clone())).unwrap();
+        let broadcasted = handle(Msg::broadcast(bytes.clone())).unwrap();
+        assert_eq!(told.as_ptr(), bytes.as_ptr());
+        assert_eq!(broadcasted.as_ptr(), bytes.as_ptr());
+        assert!(handle(Msg::tell(vec![42_u8; 1024])).is_none());
+    }
+}
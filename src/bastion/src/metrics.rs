@@ -0,0 +1,345 @@
+//!
+//! Runtime metrics of a system, returned by [`Bastion::metrics`]:
+//! the load of the executor, the amount of live tasks and children,
+//! the throughput of messages, how long questions wait for their
+//! answer, and the depth of the mailboxes and restarts of each
+//! children group.
+//!
+//! With the `metrics` feature, the metrics are also recorded as
+//! gauges using the [`metrics`] crate facade each time they are
+//! collected, so that any exporter installed by the application
+//! (e.g. a Prometheus exporter) publishes them.
+//!
+//! [`Bastion::metrics`]: crate::Bastion::metrics
+//! [`metrics`]: https://docs.rs/metrics
+use crate::addresses::ADDRESSES;
+use crate::blocking_pool::{BlockingPool, BlockingPoolStats};
+use crate::context::BastionId;
+use crate::executor;
+use crate::system::GlobalSystem;
+use crate::tree::ElementState;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tracing::trace;
+
+// The upper bounds of the buckets of the latency histograms, in
+// microseconds (from 10µs to 10s).
+const LATENCY_BUCKETS: [u64; 13] = [
+    10, 100, 1_000, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 500_000, 1_000_000, 2_500_000,
+    10_000_000,
+];
+
+#[derive(Debug, Clone, PartialEq)]
+/// A snapshot of the metrics of a system, returned by
+/// [`Bastion::metrics`].
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// # Bastion::start();
+/// let metrics = Bastion::metrics();
+/// println!(
+///     "{} children, {} live tasks, {:.1} messages/s",
+///     metrics.children(),
+///     metrics.tasks_live(),
+///     metrics.messages_per_sec(),
+/// );
+///
+/// for group in metrics.groups() {
+///     println!("{}: {} queued messages", group.name(), group.mailbox_depth());
+/// }
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+///
+/// [`Bastion::metrics`]: crate::Bastion::metrics
+pub struct RuntimeMetrics {
+    run_queues: Vec<usize>,
+    blocking_pool: Option<BlockingPoolStats>,
+    tasks_spawned: u64,
+    tasks_live: u64,
+    children: usize,
+    messages: u64,
+    messages_per_sec: f64,
+    ask_latency: LatencyHistogram,
+    groups: Vec<GroupMetrics>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The metrics of a running children group, in a
+/// [`RuntimeMetrics`].
+pub struct GroupMetrics {
+    id: BastionId,
+    name: String,
+    elements: usize,
+    mailbox_depth: usize,
+    restarts: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A histogram of durations, e.g. of how long the questions asked
+/// within a system waited for their answer (see
+/// [`RuntimeMetrics::ask_latency`]).
+pub struct LatencyHistogram {
+    buckets: Vec<(Duration, u64)>,
+    count: u64,
+    sum: Duration,
+}
+
+#[derive(Debug, Default)]
+// Records durations in a `LatencyHistogram`.
+pub(crate) struct LatencyRecorder {
+    buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+    count: AtomicU64,
+    // The sum of the durations, in microseconds.
+    sum: AtomicU64,
+}
+
+impl RuntimeMetrics {
+    /// Returns the amount of tasks queued on each core by bastion's
+    /// executor, by core, or nothing if the tasks are run by the
+    /// executor given to [`Bastion::init_with_executor`].
+    ///
+    /// [`Bastion::init_with_executor`]: crate::Bastion::init_with_executor
+    pub fn run_queues(&self) -> &[usize] {
+        &self.run_queues
+    }
+
+    /// Returns the state of the pool running the blocking tasks, if
+    /// it was configured using [`Bastion::blocking_pool_config`].
+    ///
+    /// [`Bastion::blocking_pool_config`]: crate::Bastion::blocking_pool_config
+    pub fn blocking_pool(&self) -> Option<&BlockingPoolStats> {
+        self.blocking_pool.as_ref()
+    }
+
+    /// Returns the amount of tasks (supervisors, children, `spawn!`ed
+    /// and `blocking!` tasks) spawned since the process started.
+    pub fn tasks_spawned(&self) -> u64 {
+        self.tasks_spawned
+    }
+
+    /// Returns the amount of tasks which didn't complete yet.
+    pub fn tasks_live(&self) -> u64 {
+        self.tasks_live
+    }
+
+    /// Returns the amount of elements of the running children
+    /// groups of the system.
+    pub fn children(&self) -> usize {
+        self.children
+    }
+
+    /// Returns the amount of messages received by the elements of
+    /// the system since it was initialized.
+    pub fn messages(&self) -> u64 {
+        self.messages
+    }
+
+    /// Returns how many messages the elements of the system received
+    /// per second since the metrics were previously collected (or
+    /// since the system was initialized).
+    pub fn messages_per_sec(&self) -> f64 {
+        self.messages_per_sec
+    }
+
+    /// Returns how long the questions asked within the system
+    /// (e.g. using [`BastionContext::ask`]) waited for their answer.
+    /// The questions which weren't answered aren't counted.
+    ///
+    /// [`BastionContext::ask`]: crate::context::BastionContext::ask
+    pub fn ask_latency(&self) -> &LatencyHistogram {
+        &self.ask_latency
+    }
+
+    /// Returns the metrics of the running children groups of the
+    /// system.
+    pub fn groups(&self) -> &[GroupMetrics] {
+        &self.groups
+    }
+
+    #[cfg(feature = "metrics")]
+    /// Records the metrics as gauges using the [`metrics`] crate
+    /// facade, which is done each time they are collected using
+    /// [`Bastion::metrics`].
+    ///
+    /// [`metrics`]: https://docs.rs/metrics
+    /// [`Bastion::metrics`]: crate::Bastion::metrics
+    pub fn record(&self) {
+        for (core, len) in self.run_queues.iter().enumerate() {
+            ::metrics::gauge!("bastion_run_queue_length", *len as f64, "core" => core.to_string());
+        }
+        if let Some(pool) = &self.blocking_pool {
+            ::metrics::gauge!("bastion_blocking_queued", pool.queued() as f64);
+            ::metrics::gauge!("bastion_blocking_threads", pool.threads() as f64);
+            ::metrics::gauge!("bastion_blocking_active", pool.active() as f64);
+        }
+        ::metrics::gauge!("bastion_tasks_spawned", self.tasks_spawned as f64);
+        ::metrics::gauge!("bastion_tasks_live", self.tasks_live as f64);
+        ::metrics::gauge!("bastion_children", self.children as f64);
+        ::metrics::gauge!("bastion_messages", self.messages as f64);
+        ::metrics::gauge!("bastion_messages_per_sec", self.messages_per_sec);
+        for group in &self.groups {
+            ::metrics::gauge!(
+                "bastion_group_elements",
+                group.elements as f64,
+                "group" => group.name.clone(),
+                "id" => group.id.to_string()
+            );
+            ::metrics::gauge!(
+                "bastion_group_mailbox_depth",
+                group.mailbox_depth as f64,
+                "group" => group.name.clone(),
+                "id" => group.id.to_string()
+            );
+            ::metrics::gauge!(
+                "bastion_group_restarts",
+                group.restarts as f64,
+                "group" => group.name.clone(),
+                "id" => group.id.to_string()
+            );
+        }
+    }
+}
+
+impl GroupMetrics {
+    /// Returns the identifier of the children group.
+    pub fn id(&self) -> &BastionId {
+        &self.id
+    }
+
+    /// Returns the name of the children group.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the amount of elements of the children group.
+    pub fn elements(&self) -> usize {
+        self.elements
+    }
+
+    /// Returns the amount of messages waiting in the mailboxes of
+    /// the elements of the children group.
+    pub fn mailbox_depth(&self) -> usize {
+        self.mailbox_depth
+    }
+
+    /// Returns how many times the elements of the children group
+    /// were restarted.
+    pub fn restarts(&self) -> usize {
+        self.restarts
+    }
+}
+
+impl LatencyHistogram {
+    /// Returns the buckets of the histogram, as their upper bound
+    /// and the amount of durations lower than or equal to it, by
+    /// increasing bound.
+    pub fn buckets(&self) -> &[(Duration, u64)] {
+        &self.buckets
+    }
+
+    /// Returns the amount of recorded durations.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the sum of the recorded durations.
+    pub fn sum(&self) -> Duration {
+        self.sum
+    }
+}
+
+impl LatencyRecorder {
+    pub(crate) fn record(&self, latency: Duration) {
+        let micros = latency.as_micros() as u64;
+        for (bound, bucket) in LATENCY_BUCKETS.iter().zip(&self.buckets) {
+            if micros <= *bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum.fetch_add(micros, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> LatencyHistogram {
+        let buckets = LATENCY_BUCKETS
+            .iter()
+            .zip(&self.buckets)
+            .map(|(bound, bucket)| {
+                (
+                    Duration::from_micros(*bound),
+                    bucket.load(Ordering::Relaxed),
+                )
+            })
+            .collect();
+
+        LatencyHistogram {
+            buckets,
+            count: self.count.load(Ordering::Relaxed),
+            sum: Duration::from_micros(self.sum.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+// Collects the metrics of the given system.
+pub(crate) fn collect(system: &GlobalSystem) -> RuntimeMetrics {
+    trace!("Metrics: Collecting.");
+    let groups: Vec<_> = system
+        .tree()
+        .snapshot()
+        .children_groups()
+        .into_iter()
+        .filter(|group| group.state() == ElementState::Running)
+        .map(|group| {
+            let mailbox_depth = ADDRESSES
+                .get(group.id())
+                .map(|children| {
+                    children
+                        .elems()
+                        .iter()
+                        .map(|child| child.mailbox_len())
+                        .sum()
+                })
+                .unwrap_or(0);
+
+            GroupMetrics {
+                id: group.id().clone(),
+                name: group.name().to_string(),
+                elements: group.elements().len(),
+                mailbox_depth,
+                restarts: group.restarts(),
+            }
+        })
+        .collect();
+
+    let (tasks_spawned, tasks_live) = executor::task_counts();
+    let (messages, messages_per_sec) = system.messages_rate();
+    RuntimeMetrics {
+        run_queues: executor::run_queues(),
+        blocking_pool: BlockingPool::get().map(BlockingPool::stats),
+        tasks_spawned,
+        tasks_live,
+        children: groups.iter().map(|group| group.elements).sum(),
+        messages,
+        messages_per_sec,
+        ask_latency: system.ask_latency(),
+        groups,
+    }
+}
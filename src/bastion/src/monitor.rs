@@ -0,0 +1,295 @@
+//!
+//! Monitors and links between the elements of children groups.
+//!
+//! A child monitoring another one (see [`ChildRef::monitor`])
+//! receives a [`Down`] message in its mailbox when the monitored
+//! child terminates, whatever the reason. Two linked children (see
+//! [`ChildRef::link`]) fail together: when one of them fails, the
+//! other one fails too (with [`FailureKind::Linked`]) and is handled
+//! by its supervisor like any other failed element.
+//!
+//! Like in Erlang, monitors and links only last until the child
+//! they concern terminates, even if it is then restarted by its
+//! supervisor.
+//!
+//! [`ChildRef::monitor`]: crate::child_ref::ChildRef::monitor
+//! [`ChildRef::link`]: crate::child_ref::ChildRef::link
+use crate::child_ref::ChildRef;
+use crate::context::BastionId;
+use crate::envelope::Envelope;
+use crate::health::FailureKind;
+use crate::message::BastionMessage;
+use fxhash::FxHashMap;
+use once_cell::sync::Lazy;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tracing::{debug, trace};
+
+pub(crate) static MONITORS: Lazy<MonitorRegistry> = Lazy::new(MonitorRegistry::default);
+
+static NEXT_MONITOR_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A monitor set up using [`ChildRef::monitor`], which can be used
+/// to cancel it.
+///
+/// [`ChildRef::monitor`]: crate::child_ref::ChildRef::monitor
+pub struct MonitorRef {
+    id: u64,
+    monitored: BastionId,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Why a monitored child terminated.
+pub enum DownReason {
+    /// The child was stopped or its future returned `Ok(())`.
+    Stopped,
+    /// The child was killed.
+    Killed,
+    /// The child failed, and its supervisor will handle the failure
+    /// (e.g. restart it).
+    Failed(FailureKind),
+}
+
+#[derive(Debug, Clone)]
+/// The message received by a child monitoring another one when the
+/// monitored child terminates.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// #
+/// # async fn handle(ctx: BastionContext) -> Result<(), ()> {
+/// msg! { ctx.recv().await?,
+///     down: Down => {
+///         println!("{} terminated: {:?}", down.child().path(), down.reason());
+///     };
+///     _: _ => ();
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Down {
+    monitor: MonitorRef,
+    child: ChildRef,
+    reason: DownReason,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct MonitorRegistry {
+    // The monitors of each monitored child, along with the child
+    // that set them up.
+    monitors: Mutex<FxHashMap<BastionId, Vec<(MonitorRef, ChildRef)>>>,
+    // The children linked to each child.
+    links: Mutex<FxHashMap<BastionId, Vec<ChildRef>>>,
+}
+
+impl MonitorRef {
+    /// Returns the identifier of the monitored child.
+    pub fn monitored(&self) -> &BastionId {
+        &self.monitored
+    }
+
+    /// Cancels this monitor: no [`Down`] message will be sent for
+    /// it. Does nothing if the monitored child already terminated.
+    pub fn demonitor(&self) {
+        MONITORS.demonitor(self);
+    }
+}
+
+impl DownReason {
+    /// Returns `true` if the child failed (rather than being
+    /// stopped or killed).
+    pub fn is_failure(&self) -> bool {
+        matches!(self, DownReason::Failed(_))
+    }
+}
+
+impl Down {
+    /// Returns the monitor that triggered this message.
+    pub fn monitor(&self) -> &MonitorRef {
+        &self.monitor
+    }
+
+    /// Returns the child that terminated.
+    pub fn child(&self) -> &ChildRef {
+        &self.child
+    }
+
+    /// Returns why the child terminated.
+    pub fn reason(&self) -> DownReason {
+        self.reason
+    }
+}
+
+impl MonitorRegistry {
+    pub(crate) fn monitor(&self, watcher: &ChildRef, monitored: &ChildRef) -> MonitorRef {
+        let monitor = MonitorRef {
+            id: NEXT_MONITOR_ID.fetch_add(1, Ordering::SeqCst),
+            monitored: monitored.id().clone(),
+        };
+        trace!(
+            "MonitorRegistry: Child({}) is monitoring Child({}).",
+            watcher.id(),
+            monitored.id()
+        );
+        self.monitors
+            .lock()
+            .unwrap()
+            .entry(monitored.id().clone())
+            .or_default()
+            .push((monitor.clone(), watcher.clone()));
+
+        monitor
+    }
+
+    pub(crate) fn demonitor(&self, monitor: &MonitorRef) {
+        let mut monitors = self.monitors.lock().unwrap();
+        if let Some(watchers) = monitors.get_mut(&monitor.monitored) {
+            watchers.retain(|(other, _)| other != monitor);
+            if watchers.is_empty() {
+                monitors.remove(&monitor.monitored);
+            }
+        }
+    }
+
+    pub(crate) fn link(&self, child: &ChildRef, other: &ChildRef) {
+        if child == other {
+            return;
+        }
+
+        trace!(
+            "MonitorRegistry: Linking Child({}) and Child({}).",
+            child.id(),
+            other.id()
+        );
+        let mut links = self.links.lock().unwrap();
+        for (from, to) in [(child, other), (other, child)].iter() {
+            let linked = links.entry(from.id().clone()).or_default();
+            if !linked.contains(to) {
+                linked.push((*to).clone());
+            }
+        }
+    }
+
+    pub(crate) fn unlink(&self, child: &ChildRef, other: &ChildRef) {
+        let mut links = self.links.lock().unwrap();
+        for (from, to) in [(child, other), (other, child)].iter() {
+            if let Some(linked) = links.get_mut(from.id()) {
+                linked.retain(|linked| linked != *to);
+                if linked.is_empty() {
+                    links.remove(from.id());
+                }
+            }
+        }
+    }
+
+    /// Notifies the children monitoring or linked to the given
+    /// child that it terminated, and forgets about its monitors and
+    /// links.
+    pub(crate) fn notify(&self, child: &ChildRef, reason: DownReason) {
+        let watchers = self.monitors.lock().unwrap().remove(child.id());
+        for (monitor, watcher) in watchers.unwrap_or_default() {
+            debug!(
+                "MonitorRegistry: Notifying Child({}) that Child({}) terminated: {:?}",
+                watcher.id(),
+                child.id(),
+                reason
+            );
+            let down = Down {
+                monitor,
+                child: child.clone(),
+                reason,
+            };
+            watcher.tell_anonymously(down).ok();
+        }
+
+        let linked = {
+            let mut links = self.links.lock().unwrap();
+            let linked = links.remove(child.id()).unwrap_or_default();
+            for other in &linked {
+                if let Some(other_links) = links.get_mut(other.id()) {
+                    other_links.retain(|other_link| other_link != child);
+                    if other_links.is_empty() {
+                        links.remove(other.id());
+                    }
+                }
+            }
+
+            linked
+        };
+        if !reason.is_failure() {
+            return;
+        }
+
+        for other in linked {
+            debug!(
+                "MonitorRegistry: Propagating the failure of Child({}) to Child({}).",
+                child.id(),
+                other.id()
+            );
+            let msg = BastionMessage::link_failed(child.id().clone());
+            let env = Envelope::from_dead_letters(msg);
+            other.send(env).ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::BastionPath;
+    use futures::channel::mpsc;
+    use std::sync::Arc;
+
+    fn child_ref() -> ChildRef {
+        let (sender, _) = mpsc::unbounded();
+        let path = Arc::new(BastionPath::root());
+        ChildRef::new(BastionId::new(), sender, "test_name".to_string(), path)
+    }
+
+    #[test]
+    fn test_monitor_registry_demonitors() {
+        let registry = MonitorRegistry::default();
+        let watcher = child_ref();
+        let monitored = child_ref();
+
+        let first = registry.monitor(&watcher, &monitored);
+        let second = registry.monitor(&watcher, &monitored);
+        assert_ne!(first, second);
+        assert_eq!(first.monitored(), monitored.id());
+
+        registry.demonitor(&first);
+        let monitors = registry.monitors.lock().unwrap();
+        let watchers = monitors.get(monitored.id()).unwrap();
+        assert_eq!(watchers.len(), 1);
+        assert_eq!(watchers[0].0, second);
+    }
+
+    #[test]
+    fn test_monitor_registry_links_both_ways() {
+        let registry = MonitorRegistry::default();
+        let child = child_ref();
+        let other = child_ref();
+
+        registry.link(&child, &other);
+        registry.link(&child, &other);
+        registry.link(&child, &child);
+        {
+            let links = registry.links.lock().unwrap();
+            assert_eq!(links.get(child.id()).unwrap(), &vec![other.clone()]);
+            assert_eq!(links.get(other.id()).unwrap(), &vec![child.clone()]);
+        }
+
+        registry.unlink(&other, &child);
+        assert!(registry.links.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_down_reason_is_failure() {
+        assert!(DownReason::Failed(FailureKind::Panicked).is_failure());
+        assert!(!DownReason::Stopped.is_failure());
+        assert!(!DownReason::Killed.is_failure());
+    }
+}
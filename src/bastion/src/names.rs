@@ -0,0 +1,149 @@
+//!
+//! Registry of the named supervisors and children groups, allowing
+//! to look them up by path (see [`Bastion::supervisor_ref`] and
+//! [`Bastion::children_ref`]).
+//!
+//! The path of a supervisor or children group is made of its name
+//! and the names of its supervisors, separated by `/` (e.g.
+//! `"ingest/parsers"`). Only the elements whose supervisors (apart
+//! from the system supervisor) are all named get a path.
+//!
+//! Every system (see [`BastionSystem`]) has its own registry, so
+//! that the same path can be used in each of them.
+//!
+//! [`BastionSystem`]: crate::BastionSystem
+//! [`Bastion::supervisor_ref`]: crate::Bastion::supervisor_ref
+//! [`Bastion::children_ref`]: crate::Bastion::children_ref
+use crate::children_ref::ChildrenRef;
+use crate::context::BastionId;
+use crate::supervisor::SupervisorRef;
+use fxhash::FxHashMap;
+use std::sync::RwLock;
+use tracing::{trace, warn};
+
+#[derive(Debug, Clone)]
+pub(crate) enum NamedRef {
+    Supervisor(SupervisorRef),
+    Children(ChildrenRef),
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct NameRegistry {
+    refs: RwLock<FxHashMap<String, NamedRef>>,
+}
+
+/// Returns the path of an element named `name` supervised by the
+/// element with the given path (the empty path being the root).
+pub(crate) fn join(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        normalize(name).to_string()
+    } else {
+        format!("{}/{}", parent, normalize(name))
+    }
+}
+
+fn normalize(path: &str) -> &str {
+    path.trim_matches('/')
+}
+
+impl NamedRef {
+    fn id(&self) -> &BastionId {
+        match self {
+            NamedRef::Supervisor(supervisor) => supervisor.id(),
+            NamedRef::Children(children) => children.id(),
+        }
+    }
+}
+
+impl NameRegistry {
+    pub(crate) fn register(&self, path: &str, named: NamedRef) {
+        trace!("NameRegistry: Registering {}.", path);
+        let previous = self
+            .refs
+            .write()
+            .unwrap()
+            .insert(normalize(path).to_string(), named.clone());
+        if let Some(previous) = previous {
+            if previous.id() != named.id() {
+                warn!("NameRegistry: {} was registered twice.", path);
+            }
+        }
+    }
+
+    /// Removes the given path from the registry, unless it was
+    /// registered by another element since then.
+    pub(crate) fn remove(&self, path: &str, id: &BastionId) {
+        let mut refs = self.refs.write().unwrap();
+        let path = normalize(path);
+        if refs.get(path).map(NamedRef::id) == Some(id) {
+            trace!("NameRegistry: Removing {}.", path);
+            refs.remove(path);
+        }
+    }
+
+    pub(crate) fn supervisor(&self, path: &str) -> Option<SupervisorRef> {
+        match self.refs.read().unwrap().get(normalize(path)) {
+            Some(NamedRef::Supervisor(supervisor)) => Some(supervisor.clone()),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn children(&self, path: &str) -> Option<ChildrenRef> {
+        match self.refs.read().unwrap().get(normalize(path)) {
+            Some(NamedRef::Children(children)) => Some(children.clone()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::BastionPath;
+    use futures::channel::mpsc;
+    use std::sync::Arc;
+
+    fn supervisor_ref() -> SupervisorRef {
+        let (sender, _) = mpsc::unbounded();
+        let path = Arc::new(BastionPath::root());
+        SupervisorRef::new(BastionId::new(), sender, path)
+    }
+
+    #[test]
+    fn test_join_paths() {
+        assert_eq!(join("", "ingest"), "ingest");
+        assert_eq!(join("ingest", "parsers"), "ingest/parsers");
+        assert_eq!(join("ingest", "/parsers/"), "ingest/parsers");
+    }
+
+    #[test]
+    fn test_name_registry_looks_up_supervisors() {
+        let registry = NameRegistry::default();
+        let supervisor = supervisor_ref();
+
+        registry.register("ingest/parsers", NamedRef::Supervisor(supervisor.clone()));
+        assert_eq!(
+            registry
+                .supervisor("/ingest/parsers/")
+                .map(|sp| sp.id().clone()),
+            Some(supervisor.id().clone())
+        );
+        assert!(registry.children("ingest/parsers").is_none());
+        assert!(registry.supervisor("ingest").is_none());
+    }
+
+    #[test]
+    fn test_name_registry_keeps_newer_registrations() {
+        let registry = NameRegistry::default();
+        let old = supervisor_ref();
+        let new = supervisor_ref();
+
+        registry.register("ingest", NamedRef::Supervisor(old.clone()));
+        registry.register("ingest", NamedRef::Supervisor(new.clone()));
+        registry.remove("ingest", old.id());
+        assert!(registry.supervisor("ingest").is_some());
+
+        registry.remove("ingest", new.id());
+        assert!(registry.supervisor("ingest").is_none());
+    }
+}
@@ -0,0 +1,433 @@
+//!
+//! The bridge between distributors and NATS subjects.
+//!
+//! Bridging a [`Distributor`] to a subject (see [`NatsBridge::bridge`])
+//! publishes the [`WireMessage`]s told to the distributor on the
+//! subject instead of sending them to its recipients, and tells the
+//! messages published on the subject to the recipients of the
+//! distributor. The questions asked to the distributor are NATS
+//! requests, answered by the recipients of the distributor on the
+//! node which received them.
+//!
+//! Every node bridging the same distributor to the same subject
+//! joins the same queue group, so that each message is delivered to
+//! a single node. This allows the nodes to share the recipients of
+//! a distributor through the NATS server, without joining a cluster.
+//!
+//! The messages are published as JSON objects holding the tag and
+//! version of the message along with the message itself (e.g.
+//! `{"tag":"Order","version":1,"message":{"id":42}}`), and the
+//! errors replied to the requests as `{"error":"..."}`. The messages
+//! published in another format by other services are told to the
+//! recipients tagged with the subject they were published on (see
+//! [`WireMessage::unpack_tagged`]).
+//!
+//! This module is only available with the `nats` feature.
+//!
+//! [`Distributor`]: crate::distributor::Distributor
+//! [`WireMessage`]: crate::wire::WireMessage
+//! [`WireMessage::unpack_tagged`]: crate::wire::WireMessage::unpack_tagged
+use crate::distributor::Distributor;
+use crate::envelope::{RefAddr, SignedMessage};
+use crate::errors::SendError;
+use crate::executor;
+use crate::message::{Answer, ErrorReply, Msg};
+use crate::system;
+use crate::wire::WireMessage;
+use fxhash::FxHashMap;
+use nats_client::{Connection, Handler, Message};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::fmt::{self, Debug, Formatter};
+use std::io;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tracing::{debug, trace, warn};
+
+static ROUTES: Lazy<RwLock<FxHashMap<Distributor, Arc<Route>>>> = Lazy::new(Default::default);
+
+// How long the requests wait for a reply by default.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone)]
+/// A connection to a NATS server, over which distributors are
+/// bridged to subjects.
+///
+/// This structure is only available with the `nats` feature.
+///
+/// # Example
+///
+/// ```no_run
+/// # use bastion::prelude::*;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// # Bastion::start();
+/// let orders = Distributor::named("orders");
+/// NatsBridge::connect("nats://127.0.0.1:4222")
+///     .expect("Couldn't connect to NATS.")
+///     .bridge(orders, "shop.orders")
+///     .expect("Couldn't subscribe to the subject.");
+///
+/// // Published on `shop.orders`, and received by a recipient of
+/// // `orders` on one of the nodes bridging it.
+/// let order = WireMessage::pack(&42_u64).expect("Couldn't pack the order.");
+/// orders.tell_one(order).expect("Couldn't publish the order.");
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct NatsBridge {
+    connection: Connection,
+    request_timeout: Duration,
+}
+
+// A distributor bridged to a subject.
+struct Route {
+    subject: String,
+    connection: Connection,
+    request_timeout: Duration,
+    // The subscription to the subject, until the distributor is
+    // unbridged.
+    handler: Mutex<Option<Handler>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+// A message or error published on a subject.
+struct Payload {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tag: Option<String>,
+    #[serde(default)]
+    version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    message: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl NatsBridge {
+    /// Connects to the NATS server at the given URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the server (e.g. `nats://127.0.0.1:4222`).
+    pub fn connect(url: &str) -> io::Result<Self> {
+        debug!("Nats: Connecting to {}.", url);
+        Ok(NatsBridge::from_connection(nats_client::connect(url)?))
+    }
+
+    /// Uses the given connection to a NATS server, which allows to
+    /// configure it (e.g. its credentials or TLS) using
+    /// [`nats::Options`].
+    ///
+    /// # Arguments
+    ///
+    /// * `connection` - The connection to the server.
+    ///
+    /// [`nats::Options`]: https://docs.rs/nats/*/nats/struct.Options.html
+    pub fn from_connection(connection: Connection) -> Self {
+        NatsBridge {
+            connection,
+            request_timeout: REQUEST_TIMEOUT,
+        }
+    }
+
+    /// Sets how long the questions asked to the distributors bridged
+    /// from now on wait for a reply before failing, which is 5
+    /// seconds by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long the questions wait for a reply.
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Bridges the distributor to the subject, replacing the subject
+    /// it was bridged to if any.
+    ///
+    /// From now on, the [`WireMessage`]s told to the distributor (see
+    /// [`Distributor::tell_one`]) are published on the subject, and
+    /// the questions asked to it (see [`Distributor::ask_one`]) are
+    /// sent as requests. The other messages are still sent to the
+    /// recipients of the distributor on this node.
+    ///
+    /// # Arguments
+    ///
+    /// * `distributor` - The distributor to bridge.
+    /// * `subject` - The subject the messages are published on.
+    ///
+    /// [`WireMessage`]: crate::wire::WireMessage
+    /// [`Distributor::tell_one`]: crate::distributor::Distributor::tell_one
+    /// [`Distributor::ask_one`]: crate::distributor::Distributor::ask_one
+    pub fn bridge(&self, distributor: Distributor, subject: impl Into<String>) -> io::Result<()> {
+        let subject = subject.into();
+        // The nodes bridging the distributor share the messages.
+        let queue = format!("bastion.{}", subject);
+        let subscription = self.connection.queue_subscribe(&subject, &queue)?;
+        debug!("Nats: Bridging {:?} to {}.", distributor, subject);
+
+        let connection = self.connection.clone();
+        let handler = subscription.with_handler(move |message| {
+            receive(&connection, distributor, message);
+            Ok(())
+        });
+        let route = Route {
+            subject,
+            connection: self.connection.clone(),
+            request_timeout: self.request_timeout,
+            handler: Mutex::new(Some(handler)),
+        };
+
+        // FIXME: panics
+        let previous = ROUTES.write().unwrap().insert(distributor, Arc::new(route));
+        if let Some(previous) = previous {
+            previous.unsubscribe();
+        }
+        Ok(())
+    }
+
+    /// Stops bridging the distributor, returning whether it was
+    /// bridged to a subject.
+    ///
+    /// # Arguments
+    ///
+    /// * `distributor` - The distributor to stop bridging.
+    pub fn unbridge(distributor: Distributor) -> bool {
+        // FIXME: panics
+        match ROUTES.write().unwrap().remove(&distributor) {
+            Some(route) => {
+                debug!("Nats: Unbridging {:?} from {}.", distributor, route.subject);
+                route.unsubscribe();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Debug for NatsBridge {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("NatsBridge")
+            .field("request_timeout", &self.request_timeout)
+            .finish()
+    }
+}
+
+impl Route {
+    fn unsubscribe(&self) {
+        // FIXME: panics
+        if let Some(handler) = self.handler.lock().unwrap().take() {
+            handler.unsubscribe().ok();
+        }
+    }
+}
+
+impl Payload {
+    fn pack(message: &WireMessage) -> io::Result<Vec<u8>> {
+        let payload = Payload {
+            tag: Some(message.tag().to_string()),
+            version: message.version(),
+            message: Some(serde_json::from_slice(message.payload())?),
+            error: None,
+        };
+        Ok(serde_json::to_vec(&payload)?)
+    }
+
+    fn error(error: String) -> io::Result<Vec<u8>> {
+        let payload = Payload {
+            tag: None,
+            version: 0,
+            message: None,
+            error: Some(error),
+        };
+        Ok(serde_json::to_vec(&payload)?)
+    }
+
+    // Unpacks the message published on the subject, as a message
+    // tagged with the subject if it wasn't published by a bridge.
+    fn unpack(subject: &str, data: Vec<u8>) -> Msg {
+        match serde_json::from_slice::<Payload>(&data) {
+            Ok(Payload {
+                error: Some(error), ..
+            }) => Msg::tell(ErrorReply(error)),
+            Ok(Payload {
+                tag: Some(tag),
+                version,
+                message: Some(message),
+                ..
+            }) => match serde_json::to_vec(&message) {
+                Ok(payload) => Msg::tell(WireMessage::from_parts(tag, version, payload)),
+                Err(_) => Msg::tell(WireMessage::from_parts(subject, 0, data)),
+            },
+            _ => Msg::tell(WireMessage::from_parts(subject, 0, data)),
+        }
+    }
+}
+
+// Returns the route of the distributor, if it is bridged.
+fn route(distributor: Distributor) -> Option<Arc<Route>> {
+    // FIXME: panics
+    ROUTES.read().unwrap().get(&distributor).cloned()
+}
+
+// Publishes the message on the subject the distributor is bridged
+// to, or returns `None` if it isn't bridged or if the message isn't a
+// `WireMessage`.
+pub(crate) fn tell(distributor: Distributor, message: &dyn Any) -> Option<Result<(), SendError>> {
+    let message = message.downcast_ref::<WireMessage>()?;
+    let route = route(distributor)?;
+    trace!("Nats: Publishing {} on {}.", message.tag(), route.subject);
+    let published = Payload::pack(message)
+        .and_then(|payload| route.connection.publish(&route.subject, payload));
+    Some(published.map_err(|err| SendError::Other(err.into())))
+}
+
+// Sends the question as a request on the subject the distributor is
+// bridged to, or returns `None` if it isn't bridged or if the
+// question isn't a `WireMessage`.
+pub(crate) fn ask(
+    distributor: Distributor,
+    question: &dyn Any,
+) -> Option<Result<Answer, SendError>> {
+    let question = question.downcast_ref::<WireMessage>()?;
+    let route = route(distributor)?;
+    let payload = match Payload::pack(question) {
+        Ok(payload) => payload,
+        Err(err) => return Some(Err(SendError::Other(err.into()))),
+    };
+
+    trace!("Nats: Requesting {} on {}.", question.tag(), route.subject);
+    let (sender, answer) = Answer::channel();
+    // Dropping the sender when the request fails makes the answer
+    // fail.
+    executor::blocking(async move {
+        match route
+            .connection
+            .request_timeout(&route.subject, payload, route.request_timeout)
+        {
+            Ok(reply) => {
+                let msg = Payload::unpack(&reply.subject, reply.data);
+                sender
+                    .send(SignedMessage::new(msg, RefAddr::dead_letters()))
+                    .ok();
+            }
+            Err(err) => debug!("Nats: Request on {} failed: {}", route.subject, err),
+        }
+    });
+    Some(Ok(answer))
+}
+
+// Tells the message received on the subject to a recipient of the
+// distributor on this node, or asks it if it is a request.
+fn receive(connection: &Connection, distributor: Distributor, message: Message) {
+    trace!("Nats: Received message on {}.", message.subject);
+    let dispatcher = system::current().dispatcher();
+    let msg = Payload::unpack(&message.subject, message.data.clone());
+    let question = match msg.downcast::<WireMessage>() {
+        Ok(question) => question,
+        Err(_) => {
+            debug!("Nats: Ignoring error published on {}.", message.subject);
+            return;
+        }
+    };
+
+    // The message is sent to the local recipients, which would
+    // otherwise publish it again.
+    let reply_to = match &message.reply {
+        Some(reply_to) => reply_to.clone(),
+        None => {
+            if let Err(err) = dispatcher.tell(distributor, question) {
+                debug!(
+                    "Nats: Couldn't deliver message from {}: {}",
+                    message.subject, err
+                );
+            }
+            return;
+        }
+    };
+
+    let reply = match dispatcher.ask(distributor, question) {
+        Ok(answer) => answer,
+        Err(err) => {
+            respond(connection, &reply_to, Payload::error(err.to_string()));
+            return;
+        }
+    };
+    let connection = connection.clone();
+    executor::spawn(async move {
+        let reply = match reply.await {
+            Ok(reply) => reply_payload(reply.msg),
+            Err(()) => Payload::error("the question was dropped".to_string()),
+        };
+        respond(&connection, &reply_to, reply);
+    });
+}
+
+fn respond(connection: &Connection, reply_to: &str, reply: io::Result<Vec<u8>>) {
+    if let Err(err) = reply.and_then(|reply| connection.publish(reply_to, reply)) {
+        warn!("Nats: Couldn't reply to {}: {}", reply_to, err);
+    }
+}
+
+// Returns the payload replying the answer of a recipient.
+fn reply_payload(msg: Msg) -> io::Result<Vec<u8>> {
+    let msg = match msg.downcast::<WireMessage>() {
+        Ok(message) => return Payload::pack(&message),
+        Err(msg) => msg,
+    };
+
+    match msg.downcast::<ErrorReply>() {
+        Ok(ErrorReply(error)) => Payload::error(error),
+        Err(msg) => Payload::error(format!(
+            "the answer isn't a WireMessage but a {}.",
+            msg.type_name()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payloads_round_trip() {
+        let message = WireMessage::pack(&42_u32).unwrap();
+        let payload = Payload::pack(&message).unwrap();
+        match Payload::unpack("numbers", payload).downcast::<WireMessage>() {
+            Ok(unpacked) => assert_eq!(unpacked, message),
+            Err(msg) => panic!("Unexpected message: {:?}", msg),
+        }
+
+        let error = Payload::error("failed".to_string()).unwrap();
+        assert!(matches!(
+            Payload::unpack("numbers", error).downcast::<ErrorReply>(),
+            Ok(ErrorReply(error)) if error == "failed"
+        ));
+    }
+
+    #[test]
+    fn test_foreign_messages_are_tagged_with_their_subject() {
+        let data = b"{\"id\":42}".to_vec();
+        match Payload::unpack("shop.orders", data).downcast::<WireMessage>() {
+            Ok(unpacked) => {
+                assert_eq!(unpacked.tag(), "shop.orders");
+                assert_eq!(unpacked.payload(), b"{\"id\":42}");
+            }
+            Err(msg) => panic!("Unexpected message: {:?}", msg),
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//!
+//! Events describing the messages going through the mailboxes of
+//! the elements of a children group, given to the observer set
+//! using [`Children::with_message_observer`].
+//!
+//! [`Children::with_message_observer`]: crate::children::Children::with_message_observer
+use crate::context::BastionId;
+use crate::envelope::{RefAddr, SignedMessage};
+use crate::time::{self, Instant};
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::trace;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// What happened to the message described by a [`MailboxEvent`].
+pub enum MailboxEventKind {
+    /// The message was added to the mailbox of the element.
+    Enqueued,
+    /// The message was received by the element.
+    Dequeued,
+    /// The message was dropped without being received, e.g. because
+    /// it expired, the mailbox was full or the element was
+    /// restarted without preserving its mailbox. It was then sent
+    /// to the dead letters.
+    Dropped,
+}
+
+#[derive(Debug, Clone)]
+/// A message going through the mailbox of an element of a children
+/// group, given to the observer set using
+/// [`Children::with_message_observer`].
+///
+/// [`Children::with_message_observer`]: crate::children::Children::with_message_observer
+pub struct MailboxEvent {
+    kind: MailboxEventKind,
+    child_id: BastionId,
+    type_name: &'static str,
+    sender: RefAddr,
+    enqueued_at: Instant,
+    at: Instant,
+}
+
+#[derive(Clone)]
+// The observer of the mailboxes of a children group's elements.
+pub(crate) struct MessageObserver(Arc<dyn Fn(MailboxEvent) + Send + Sync>);
+
+impl MailboxEvent {
+    /// Returns what happened to the message.
+    pub fn kind(&self) -> MailboxEventKind {
+        self.kind
+    }
+
+    /// Returns the identifier of the element whose mailbox the
+    /// message went through.
+    pub fn child_id(&self) -> &BastionId {
+        &self.child_id
+    }
+
+    /// Returns the name of the type of the message (see
+    /// [`std::any::type_name`]).
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns the signature of the sender of the message.
+    pub fn sender(&self) -> &RefAddr {
+        &self.sender
+    }
+
+    /// Returns when the message was added to the mailbox.
+    pub fn enqueued_at(&self) -> Instant {
+        self.enqueued_at
+    }
+
+    /// Returns when the event happened.
+    pub fn at(&self) -> Instant {
+        self.at
+    }
+
+    /// Returns how long the message waited in the mailbox before
+    /// the event happened.
+    pub fn queued_for(&self) -> Duration {
+        self.at.saturating_duration_since(self.enqueued_at)
+    }
+}
+
+impl MessageObserver {
+    pub(crate) fn new<F>(observer: F) -> Self
+    where
+        F: Fn(MailboxEvent) + Send + Sync + 'static,
+    {
+        MessageObserver(Arc::new(observer))
+    }
+
+    pub(crate) fn notify(&self, kind: MailboxEventKind, child_id: &BastionId, msg: &SignedMessage) {
+        trace!(
+            "MessageObserver: Message {:?} by Child({}).",
+            kind,
+            child_id
+        );
+        let at = time::now();
+        let event = MailboxEvent {
+            kind,
+            child_id: child_id.clone(),
+            type_name: msg.msg.type_name(),
+            sender: msg.signature().clone(),
+            enqueued_at: msg.msg.enqueued_at().unwrap_or(at),
+            at,
+        };
+        (self.0)(event);
+    }
+}
+
+impl Debug for MessageObserver {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("MessageObserver").finish()
+    }
+}
@@ -69,6 +69,12 @@ impl BastionPath {
         }
     }
 
+    // The identifiers of the supervisors and children group the
+    // path's element is supervised by, from the root.
+    pub(crate) fn parent_chain(&self) -> &[BastionId] {
+        &self.parent_chain
+    }
+
     /// iterates over path elements
     pub(crate) fn iter(&self) -> impl Iterator<Item = &BastionId> {
         let parent_iter = self.parent_chain.iter();
@@ -0,0 +1,938 @@
+//!
+//! Journal of the events persisted by event-sourced actors, and
+//! tools to inspect it.
+//!
+//! Events are stored serialized in a [`Journal`], keyed by the
+//! persistence identifier of the actor that emitted them and by
+//! a sequence number starting at `1`. The journal used by the
+//! system can be replaced with [`set_journal`] (an
+//! [`InMemoryJournal`] is used by default). The events can also be
+//! kept in files using a [`FileJournal`], or in a `sled` database
+//! using a [`SledJournal`] (with the `sled` feature).
+//!
+//! The elements of a children group set up using
+//! [`Children::with_persistence`] persist their events using
+//! [`BastionContext::persist`]. When they start again, their events
+//! are replayed through the handler given to
+//! [`Children::with_recovery`] to rebuild their state before they
+//! handle any message (or they can replay them themselves using
+//! [`BastionContext::recover`]).
+//!
+//! The events are serialized as JSON, unless the group was given a
+//! [`WireCodec`] using [`Children::with_persistence_codec`] (with
+//! the `remote` feature), in which case they are packed in a
+//! [`WireMessage`] and stored in a [`WireEnvelope`] encoded by the
+//! codec.
+//!
+//! [`replay_actor`] allows to rebuild the state of a single actor
+//! from its journal without starting the system, which is useful to
+//! diagnose state corruption in event-sourced actors.
+//!
+//! [`SledJournal`]: crate::persistence::SledJournal
+//! [`Children::with_persistence`]: crate::children::Children::with_persistence
+//! [`Children::with_recovery`]: crate::children::Children::with_recovery
+//! [`Children::with_persistence_codec`]: crate::children::Children::with_persistence_codec
+//! [`WireCodec`]: crate::codec::WireCodec
+//! [`WireMessage`]: crate::wire::WireMessage
+//! [`WireEnvelope`]: crate::codec::WireEnvelope
+//! [`BastionContext::persist`]: crate::context::BastionContext::persist
+//! [`BastionContext::recover`]: crate::context::BastionContext::recover
+#[cfg(feature = "remote")]
+use crate::codec::{WireCodec, WireEnvelope};
+#[cfg(feature = "remote")]
+use crate::wire::WireMessage;
+use anyhow::{anyhow, Result as AnyResult};
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::any::{type_name, Any};
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{self, Debug, Formatter};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use tracing::{debug, trace};
+
+static JOURNAL: Lazy<RwLock<Arc<dyn Journal>>> =
+    Lazy::new(|| RwLock::new(Arc::new(InMemoryJournal::default())));
+
+/// An event stored in a [`Journal`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    /// The persistence identifier of the actor that emitted the event.
+    pub persistence_id: String,
+    /// The sequence number of the event, starting at `1`.
+    pub sequence_nr: u64,
+    /// The serialized event.
+    pub payload: Vec<u8>,
+}
+
+/// A storage for the events persisted by event-sourced actors.
+///
+/// Implementors must keep the events of each persistence
+/// identifier ordered by sequence number.
+pub trait Journal: Send + Sync + Debug {
+    /// Appends the serialized event to the journal of the given
+    /// persistence identifier and returns its sequence number.
+    fn append(&self, persistence_id: &str, payload: Vec<u8>) -> AnyResult<u64>;
+    /// Returns the events of the given persistence identifier whose
+    /// sequence numbers are between `from_seq` and `to_seq` (both
+    /// included).
+    fn read(
+        &self,
+        persistence_id: &str,
+        from_seq: u64,
+        to_seq: u64,
+    ) -> AnyResult<Vec<JournalEntry>>;
+    /// Returns the sequence number of the last event persisted for
+    /// the given persistence identifier, or `0` if there is none.
+    fn highest_sequence_nr(&self, persistence_id: &str) -> AnyResult<u64>;
+}
+
+/// A [`Journal`] keeping the events in memory, used by default.
+#[derive(Debug, Default)]
+pub struct InMemoryJournal {
+    entries: RwLock<HashMap<String, Vec<JournalEntry>>>,
+}
+
+impl Journal for InMemoryJournal {
+    fn append(&self, persistence_id: &str, payload: Vec<u8>) -> AnyResult<u64> {
+        let mut entries = self
+            .entries
+            .write()
+            .map_err(|error| anyhow!("couldn't get write lock on journal {:?}", error))?;
+        let events = entries.entry(persistence_id.to_string()).or_default();
+        let sequence_nr = events.len() as u64 + 1;
+        events.push(JournalEntry {
+            persistence_id: persistence_id.to_string(),
+            sequence_nr,
+            payload,
+        });
+        Ok(sequence_nr)
+    }
+
+    fn read(
+        &self,
+        persistence_id: &str,
+        from_seq: u64,
+        to_seq: u64,
+    ) -> AnyResult<Vec<JournalEntry>> {
+        let entries = self
+            .entries
+            .read()
+            .map_err(|error| anyhow!("couldn't get read lock on journal {:?}", error))?;
+        Ok(entries
+            .get(persistence_id)
+            .map(|events| {
+                events
+                    .iter()
+                    .filter(|entry| entry.sequence_nr >= from_seq && entry.sequence_nr <= to_seq)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    fn highest_sequence_nr(&self, persistence_id: &str) -> AnyResult<u64> {
+        let entries = self
+            .entries
+            .read()
+            .map_err(|error| anyhow!("couldn't get read lock on journal {:?}", error))?;
+        Ok(entries
+            .get(persistence_id)
+            .map(|events| events.len() as u64)
+            .unwrap_or_default())
+    }
+}
+
+/// A [`Journal`] keeping the events of each persistence identifier
+/// in a file of a directory, so that they survive the restarts of
+/// the process.
+///
+/// Each event is appended to the file as its length (a big-endian
+/// `u32`) followed by the serialized event, and synced to the disk
+/// before [`append`] returns. An event whose write was interrupted
+/// (e.g. because the process crashed) is discarded.
+///
+/// ```rust,no_run
+/// # use bastion::persistence::{self, FileJournal};
+/// let journal = FileJournal::open("/var/lib/app/journal").expect("Couldn't open the journal.");
+/// persistence::set_journal(journal);
+/// ```
+///
+/// [`append`]: Journal::append
+#[derive(Debug)]
+pub struct FileJournal {
+    dir: PathBuf,
+    // The number of events of the persistence identifiers whose
+    // file was read, along with the length of their file.
+    counts: Mutex<HashMap<String, (u64, u64)>>,
+}
+
+impl FileJournal {
+    /// Opens the journal keeping its files in the given directory,
+    /// creating it if needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory of the journal's files.
+    pub fn open(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        debug!("Persistence: Opening file journal in {:?}.", dir);
+        Ok(FileJournal {
+            dir,
+            counts: Mutex::new(HashMap::new()),
+        })
+    }
+
+    // The file of the persistence identifier, whose name is the
+    // identifier in hexadecimal so that any identifier can be used.
+    fn path(&self, persistence_id: &str) -> PathBuf {
+        let name: String = persistence_id
+            .bytes()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        self.dir.join(format!("{}.journal", name))
+    }
+
+    // Returns the events of the persistence identifier, discarding
+    // the one whose write was interrupted, if any.
+    fn entries(&self, persistence_id: &str) -> AnyResult<Vec<JournalEntry>> {
+        let mut bytes = Vec::new();
+        match File::open(self.path(persistence_id)) {
+            Ok(mut file) => file.read_to_end(&mut bytes)?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(error.into()),
+        };
+
+        let mut entries = Vec::new();
+        let mut offset = 0;
+        while bytes.len() >= offset + 4 {
+            let mut len = [0; 4];
+            len.copy_from_slice(&bytes[offset..offset + 4]);
+            let len = u32::from_be_bytes(len) as usize;
+            if bytes.len() < offset + 4 + len {
+                break;
+            }
+
+            entries.push(JournalEntry {
+                persistence_id: persistence_id.to_string(),
+                sequence_nr: entries.len() as u64 + 1,
+                payload: bytes[offset + 4..offset + 4 + len].to_vec(),
+            });
+            offset += 4 + len;
+        }
+
+        if offset < bytes.len() {
+            debug!(
+                "Persistence({}): Discarding interrupted event {}.",
+                persistence_id,
+                entries.len() + 1
+            );
+            OpenOptions::new()
+                .write(true)
+                .open(self.path(persistence_id))?
+                .set_len(offset as u64)?;
+        }
+        Ok(entries)
+    }
+}
+
+impl Journal for FileJournal {
+    fn append(&self, persistence_id: &str, payload: Vec<u8>) -> AnyResult<u64> {
+        let mut counts = self
+            .counts
+            .lock()
+            .map_err(|error| anyhow!("couldn't get lock on journal {:?}", error))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path(persistence_id))?;
+        let len = file.metadata()?.len();
+        let (count, len) = match counts.get(persistence_id) {
+            Some((count, known_len)) if *known_len == len => (*count, len),
+            // The file wasn't read yet, or ends with an interrupted
+            // event which is discarded before appending after it.
+            _ => {
+                let entries = self.entries(persistence_id)?;
+                let len = entries
+                    .iter()
+                    .map(|entry| 4 + entry.payload.len() as u64)
+                    .sum();
+                (entries.len() as u64, len)
+            }
+        };
+
+        let mut record = Vec::with_capacity(4 + payload.len());
+        record.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        record.extend_from_slice(&payload);
+        if let Err(error) = file.write_all(&record).and_then(|()| file.sync_data()) {
+            // The event might have been partially written: it is
+            // truncated away, or discarded by the next append if
+            // this fails too.
+            counts.remove(persistence_id);
+            file.set_len(len).ok();
+            return Err(error.into());
+        }
+
+        counts.insert(
+            persistence_id.to_string(),
+            (count + 1, len + record.len() as u64),
+        );
+        Ok(count + 1)
+    }
+
+    fn read(
+        &self,
+        persistence_id: &str,
+        from_seq: u64,
+        to_seq: u64,
+    ) -> AnyResult<Vec<JournalEntry>> {
+        // The events being appended aren't read half-written.
+        let _counts = self
+            .counts
+            .lock()
+            .map_err(|error| anyhow!("couldn't get lock on journal {:?}", error))?;
+        Ok(self
+            .entries(persistence_id)?
+            .into_iter()
+            .filter(|entry| entry.sequence_nr >= from_seq && entry.sequence_nr <= to_seq)
+            .collect())
+    }
+
+    fn highest_sequence_nr(&self, persistence_id: &str) -> AnyResult<u64> {
+        Ok(self.read(persistence_id, 1, u64::MAX)?.len() as u64)
+    }
+}
+
+#[cfg(feature = "sled")]
+/// A [`Journal`] keeping the events in a `sled` database, in a tree
+/// per persistence identifier whose keys are the sequence numbers.
+///
+/// This journal is only available with the `sled` feature.
+///
+/// ```rust,no_run
+/// # use bastion::persistence::{self, SledJournal};
+/// let db = sled::open("/var/lib/app/journal").expect("Couldn't open the database.");
+/// persistence::set_journal(SledJournal::new(db));
+/// ```
+#[derive(Debug)]
+pub struct SledJournal {
+    db: sled::Db,
+    // Serializes the appends, which read the last sequence number.
+    appending: Mutex<()>,
+}
+
+#[cfg(feature = "sled")]
+impl SledJournal {
+    /// Creates a journal keeping the events in the database.
+    ///
+    /// # Arguments
+    ///
+    /// * `db` - The database keeping the events.
+    pub fn new(db: sled::Db) -> Self {
+        SledJournal {
+            db,
+            appending: Mutex::new(()),
+        }
+    }
+
+    fn tree(&self, persistence_id: &str) -> AnyResult<sled::Tree> {
+        Ok(self.db.open_tree(format!("bastion/{}", persistence_id))?)
+    }
+}
+
+#[cfg(feature = "sled")]
+impl Journal for SledJournal {
+    fn append(&self, persistence_id: &str, payload: Vec<u8>) -> AnyResult<u64> {
+        let _appending = self
+            .appending
+            .lock()
+            .map_err(|error| anyhow!("couldn't get lock on journal {:?}", error))?;
+        let tree = self.tree(persistence_id)?;
+        let sequence_nr = self.highest_sequence_nr(persistence_id)? + 1;
+        tree.insert(sequence_nr.to_be_bytes(), payload)?;
+        tree.flush()?;
+        Ok(sequence_nr)
+    }
+
+    fn read(
+        &self,
+        persistence_id: &str,
+        from_seq: u64,
+        to_seq: u64,
+    ) -> AnyResult<Vec<JournalEntry>> {
+        let tree = self.tree(persistence_id)?;
+        let range = from_seq.to_be_bytes()..=to_seq.to_be_bytes();
+        tree.range(range)
+            .map(|entry| {
+                let (key, payload) = entry?;
+                let mut sequence_nr = [0; 8];
+                sequence_nr.copy_from_slice(&key);
+                Ok(JournalEntry {
+                    persistence_id: persistence_id.to_string(),
+                    sequence_nr: u64::from_be_bytes(sequence_nr),
+                    payload: payload.to_vec(),
+                })
+            })
+            .collect()
+    }
+
+    fn highest_sequence_nr(&self, persistence_id: &str) -> AnyResult<u64> {
+        let last = match self.tree(persistence_id)?.last()? {
+            Some((key, _)) => key,
+            None => return Ok(0),
+        };
+        let mut sequence_nr = [0; 8];
+        sequence_nr.copy_from_slice(&last);
+        Ok(u64::from_be_bytes(sequence_nr))
+    }
+}
+
+/// Replaces the [`Journal`] used by the system.
+///
+/// ```rust
+/// # use bastion::persistence::{self, InMemoryJournal};
+/// persistence::set_journal(InMemoryJournal::default());
+/// ```
+pub fn set_journal(journal: impl Journal + 'static) {
+    debug!("Persistence: Setting journal: {:?}", journal);
+    *JOURNAL.write().unwrap() = Arc::new(journal);
+}
+
+/// Returns the [`Journal`] used by the system.
+pub fn journal() -> Arc<dyn Journal> {
+    JOURNAL.read().unwrap().clone()
+}
+
+/// The state of an actor that is rebuilt by applying the events
+/// it persisted, one after the other.
+pub trait EventSourced: Default {
+    /// The type of the events persisted by the actor.
+    type Event: Serialize + DeserializeOwned + Debug + 'static;
+
+    /// Updates the state by applying an event to it.
+    fn apply(&mut self, event: &Self::Event);
+}
+
+/// Serializes the event and appends it to the journal of the given
+/// persistence identifier, returning its sequence number.
+///
+/// ```rust
+/// # use bastion::persistence;
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Debug, Serialize, Deserialize)]
+/// struct Deposited(u64);
+///
+/// let sequence_nr = persistence::persist("account-1", &Deposited(42)).unwrap();
+/// assert_eq!(sequence_nr, 1);
+/// ```
+pub fn persist<E: Serialize + Debug>(persistence_id: &str, event: &E) -> AnyResult<u64> {
+    trace!(
+        "Persistence({}): Persisting event: {:?}",
+        persistence_id,
+        event
+    );
+    let payload = EventCodec::default().encode(event)?;
+    journal().append(persistence_id, payload)
+}
+
+#[cfg(feature = "remote")]
+// The kind of the envelopes the events are stored in when the
+// journal uses a `WireCodec`.
+const EVENT_KIND: &str = "event";
+
+#[derive(Debug, Clone, Default)]
+// How the events are serialized in a journal: as JSON, or packed in
+// a `WireEnvelope` encoded by a `WireCodec` (see
+// `Children::with_persistence_codec`).
+pub(crate) struct EventCodec {
+    #[cfg(feature = "remote")]
+    codec: Option<Arc<dyn WireCodec>>,
+}
+
+impl EventCodec {
+    #[cfg(feature = "remote")]
+    pub(crate) fn new(codec: impl WireCodec) -> Self {
+        EventCodec {
+            codec: Some(Arc::new(codec)),
+        }
+    }
+
+    // Serializes the event.
+    pub(crate) fn encode<E: Serialize + 'static>(&self, event: &E) -> AnyResult<Vec<u8>> {
+        #[cfg(feature = "remote")]
+        {
+            if let Some(codec) = &self.codec {
+                let envelope = WireEnvelope {
+                    kind: EVENT_KIND.to_string(),
+                    message: Some(WireMessage::pack(event)?),
+                    ..WireEnvelope::default()
+                };
+                return Ok(codec.encode(&envelope)?);
+            }
+        }
+
+        Ok(serde_json::to_vec(event)?)
+    }
+
+    // Deserializes the event of the journal entry.
+    pub(crate) fn decode<E: DeserializeOwned + 'static>(
+        &self,
+        entry: &JournalEntry,
+    ) -> AnyResult<E> {
+        let error = |error: &dyn std::fmt::Display| {
+            anyhow!(
+                "couldn't deserialize event {} of {}: {}",
+                entry.sequence_nr,
+                entry.persistence_id,
+                error
+            )
+        };
+
+        #[cfg(feature = "remote")]
+        {
+            if let Some(codec) = &self.codec {
+                let envelope = codec.decode(&entry.payload).map_err(|e| error(&e))?;
+                let message = match envelope.message {
+                    Some(message) if envelope.kind == EVENT_KIND => message,
+                    _ => return Err(error(&"not an event")),
+                };
+                return match message.unpack::<E>() {
+                    Some(event) => event.map_err(|e| error(&e)),
+                    None => Err(error(&format!("not a {}", type_name::<E>()))),
+                };
+            }
+        }
+
+        serde_json::from_slice(&entry.payload).map_err(|e| error(&e))
+    }
+}
+
+#[derive(Clone)]
+// Applies an event of the journal of an element to its state (see
+// `Children::with_recovery`).
+pub(crate) struct Recovery(
+    Arc<dyn Fn(&mut (dyn Any + Send), &EventCodec, &JournalEntry) -> AnyResult<()> + Send + Sync>,
+);
+
+impl Recovery {
+    pub(crate) fn new<S, E, F>(handler: F) -> Self
+    where
+        S: Send + 'static,
+        E: DeserializeOwned + Debug + 'static,
+        F: Fn(&mut S, E) + Send + Sync + 'static,
+    {
+        Recovery(Arc::new(move |state, codec, entry| {
+            let state = state.downcast_mut::<S>().ok_or_else(|| {
+                anyhow!(
+                    "the state of {} isn't a {}",
+                    entry.persistence_id,
+                    type_name::<S>()
+                )
+            })?;
+            let event: E = codec.decode(entry)?;
+            trace!(
+                "Persistence({}): Recovering event {}: {:?}",
+                entry.persistence_id,
+                entry.sequence_nr,
+                event
+            );
+            handler(state, event);
+            Ok(())
+        }))
+    }
+}
+
+impl Debug for Recovery {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recovery").finish()
+    }
+}
+
+#[derive(Debug, Clone)]
+// The persistence identifier of an element, the journal its events
+// are appended to and how they are serialized (see
+// `Children::with_persistence`), and the handler replaying them when
+// it starts (see `Children::with_recovery`).
+pub(crate) struct Persistence {
+    pub(crate) id: String,
+    pub(crate) journal: Arc<dyn Journal>,
+    pub(crate) codec: EventCodec,
+    pub(crate) recovery: Option<Recovery>,
+}
+
+impl Persistence {
+    // Returns the persistence of the element with the given index in
+    // its group: the first element uses the identifier given to the
+    // group, and the others the identifier followed by their index.
+    pub(crate) fn for_element(&self, index: usize) -> Self {
+        let id = match index {
+            0 => self.id.clone(),
+            index => format!("{}/{}", self.id, index),
+        };
+        Persistence {
+            id,
+            journal: self.journal.clone(),
+            codec: self.codec.clone(),
+            recovery: self.recovery.clone(),
+        }
+    }
+
+    // Applies the events of the journal to the state of the element
+    // using the recovery handler, if any, and returns the sequence
+    // number of the last one (or `0` if there was none).
+    pub(crate) fn recover(&self, state: &mut (dyn Any + Send)) -> AnyResult<u64> {
+        let recovery = match &self.recovery {
+            Some(recovery) => recovery,
+            None => return Ok(0),
+        };
+
+        debug!("Persistence({}): Recovering the events.", self.id);
+        let mut sequence_nr = 0;
+        for entry in self.journal.read(&self.id, 1, u64::MAX)? {
+            (recovery.0)(state, &self.codec, &entry)?;
+            sequence_nr = entry.sequence_nr;
+        }
+        Ok(sequence_nr)
+    }
+}
+
+/// A step of a [`Replay`], passed to its inspection callbacks after
+/// the event has been applied.
+#[derive(Debug)]
+pub struct ReplayStep<'a, A: EventSourced> {
+    /// The sequence number of the applied event.
+    pub sequence_nr: u64,
+    /// The applied event.
+    pub event: &'a A::Event,
+    /// The state of the actor once the event was applied.
+    pub state: &'a A,
+}
+
+type Inspector<A> = Box<dyn FnMut(&ReplayStep<A>)>;
+
+/// The deterministic replay of the journal of a single actor, created
+/// by [`replay_actor`].
+///
+/// The events are applied one at a time with [`step`], or all at once
+/// with [`run`], and every applied event is passed to the inspection
+/// callbacks added with [`inspect`].
+///
+/// [`step`]: Self::step
+/// [`run`]: Self::run
+/// [`inspect`]: Self::inspect
+pub struct Replay<A: EventSourced> {
+    persistence_id: String,
+    state: A,
+    sequence_nr: u64,
+    events: VecDeque<JournalEntry>,
+    codec: EventCodec,
+    inspectors: Vec<Inspector<A>>,
+}
+
+impl<A: EventSourced> Replay<A> {
+    /// Adds a callback that will be called with every applied event
+    /// and the resulting state.
+    pub fn inspect<F>(mut self, inspector: F) -> Self
+    where
+        F: FnMut(&ReplayStep<A>) + 'static,
+    {
+        self.inspectors.push(Box::new(inspector));
+        self
+    }
+
+    /// Decodes the events using the given codec, for the journals
+    /// of the children groups set up using
+    /// [`Children::with_persistence_codec`].
+    ///
+    /// # Arguments
+    ///
+    /// * `codec` - The codec the events were persisted with.
+    ///
+    /// [`Children::with_persistence_codec`]: crate::children::Children::with_persistence_codec
+    #[cfg(feature = "remote")]
+    pub fn with_codec(mut self, codec: impl WireCodec) -> Self {
+        self.codec = EventCodec::new(codec);
+        self
+    }
+
+    /// Applies the next event and returns its sequence number, or
+    /// `None` if all the events were already applied.
+    pub fn step(&mut self) -> AnyResult<Option<u64>> {
+        let entry = match self.events.pop_front() {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        let event: A::Event = self.codec.decode(&entry)?;
+        trace!(
+            "Replay({}): Applying event {}: {:?}",
+            self.persistence_id,
+            entry.sequence_nr,
+            event
+        );
+        self.state.apply(&event);
+        self.sequence_nr = entry.sequence_nr;
+
+        let step = ReplayStep {
+            sequence_nr: entry.sequence_nr,
+            event: &event,
+            state: &self.state,
+        };
+        self.inspectors
+            .iter_mut()
+            .for_each(|inspector| inspector(&step));
+
+        Ok(Some(entry.sequence_nr))
+    }
+
+    /// Applies all the remaining events and returns the rebuilt state.
+    pub fn run(mut self) -> AnyResult<A> {
+        while self.step()?.is_some() {}
+        Ok(self.state)
+    }
+
+    /// Returns the state rebuilt so far.
+    pub fn state(&self) -> &A {
+        &self.state
+    }
+
+    /// Returns the sequence number of the last applied event, or `0`
+    /// if no event was applied yet.
+    pub fn sequence_nr(&self) -> u64 {
+        self.sequence_nr
+    }
+
+    /// Returns the number of events that remain to be applied.
+    pub fn remaining(&self) -> usize {
+        self.events.len()
+    }
+}
+
+impl<A: EventSourced> Debug for Replay<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Replay")
+            .field("persistence_id", &self.persistence_id)
+            .field("sequence_nr", &self.sequence_nr)
+            .field("remaining", &self.events.len())
+            .finish()
+    }
+}
+
+/// Creates a [`Replay`] of the events persisted by the actor with the
+/// given persistence identifier, up to the sequence number `up_to_seq`
+/// (included), starting from the default state of `A`.
+///
+/// This doesn't require the system to be started.
+///
+/// ```rust
+/// # use bastion::persistence::{self, EventSourced};
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Debug, Serialize, Deserialize)]
+/// enum Event {
+///     Deposited(u64),
+///     Withdrawn(u64),
+/// }
+///
+/// #[derive(Debug, Default)]
+/// struct Account {
+///     balance: u64,
+/// }
+///
+/// impl EventSourced for Account {
+///     type Event = Event;
+///
+///     fn apply(&mut self, event: &Event) {
+///         match event {
+///             Event::Deposited(amount) => self.balance += amount,
+///             Event::Withdrawn(amount) => self.balance -= amount,
+///         }
+///     }
+/// }
+///
+/// persistence::persist("account-2", &Event::Deposited(100)).unwrap();
+/// persistence::persist("account-2", &Event::Withdrawn(30)).unwrap();
+/// persistence::persist("account-2", &Event::Withdrawn(200)).unwrap();
+///
+/// let account: Account = persistence::replay_actor("account-2", 2)
+///     .unwrap()
+///     .inspect(|step| println!("{}: {:?} -> {:?}", step.sequence_nr, step.event, step.state))
+///     .run()
+///     .unwrap();
+///
+/// assert_eq!(account.balance, 70);
+/// ```
+pub fn replay_actor<A: EventSourced>(persistence_id: &str, up_to_seq: u64) -> AnyResult<Replay<A>> {
+    debug!(
+        "Persistence({}): Replaying events up to {}.",
+        persistence_id, up_to_seq
+    );
+    let events = journal().read(persistence_id, 1, up_to_seq)?;
+
+    Ok(Replay {
+        persistence_id: persistence_id.to_string(),
+        state: A::default(),
+        sequence_nr: 0,
+        events: events.into(),
+        codec: EventCodec::default(),
+        inspectors: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Added(i64);
+
+    #[derive(Debug, Default)]
+    struct Counter(i64);
+
+    impl EventSourced for Counter {
+        type Event = Added;
+
+        fn apply(&mut self, event: &Added) {
+            self.0 += event.0;
+        }
+    }
+
+    #[test]
+    fn test_in_memory_journal_sequence_numbers() {
+        let journal = InMemoryJournal::default();
+        assert_eq!(journal.highest_sequence_nr("test").unwrap(), 0);
+        assert_eq!(journal.append("test", vec![1]).unwrap(), 1);
+        assert_eq!(journal.append("test", vec![2]).unwrap(), 2);
+        assert_eq!(journal.append("other", vec![3]).unwrap(), 1);
+        assert_eq!(journal.highest_sequence_nr("test").unwrap(), 2);
+
+        let entries = journal.read("test", 2, 10).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].payload, vec![2]);
+    }
+
+    #[test]
+    fn test_file_journal_discards_interrupted_events() {
+        let dir = std::env::temp_dir().join(format!("bastion-journal-{}", std::process::id()));
+        let journal = FileJournal::open(&dir).unwrap();
+        assert_eq!(journal.append("test/1", vec![1, 2]).unwrap(), 1);
+        assert_eq!(journal.append("test/1", vec![3]).unwrap(), 2);
+
+        // The process crashed while appending an event.
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(journal.path("test/1"))
+            .unwrap();
+        file.write_all(&[0, 0, 0, 9, 4]).unwrap();
+
+        let journal = FileJournal::open(&dir).unwrap();
+        assert_eq!(journal.highest_sequence_nr("test/1").unwrap(), 2);
+        assert_eq!(journal.append("test/1", vec![5]).unwrap(), 3);
+        let entries = journal.read("test/1", 2, 3).unwrap();
+        assert_eq!(entries[0].payload, vec![3]);
+        assert_eq!(entries[1].payload, vec![5]);
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_file_journal_appends_after_interrupted_event() {
+        let dir =
+            std::env::temp_dir().join(format!("bastion-journal-append-{}", std::process::id()));
+        let journal = FileJournal::open(&dir).unwrap();
+        assert_eq!(journal.append("test/2", vec![1]).unwrap(), 1);
+
+        // An append failed while writing its event.
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(journal.path("test/2"))
+            .unwrap();
+        file.write_all(&[0, 0, 0, 9, 4]).unwrap();
+
+        assert_eq!(journal.append("test/2", vec![2]).unwrap(), 2);
+        assert_eq!(journal.append("test/2", vec![3]).unwrap(), 3);
+        let payloads: Vec<_> = journal
+            .read("test/2", 1, 3)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.payload)
+            .collect();
+        assert_eq!(payloads, vec![vec![1], vec![2], vec![3]]);
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_replay_actor_steps_up_to_sequence_nr() {
+        for value in &[1, 2, 3, 4] {
+            persist("test-replay-counter", &Added(*value)).unwrap();
+        }
+
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let inspected = seen.clone();
+        let mut replay = replay_actor::<Counter>("test-replay-counter", 3)
+            .unwrap()
+            .inspect(move |step| {
+                inspected
+                    .borrow_mut()
+                    .push((step.sequence_nr, step.state.0))
+            });
+
+        assert_eq!(replay.step().unwrap(), Some(1));
+        assert_eq!(replay.state().0, 1);
+        assert_eq!(replay.remaining(), 2);
+
+        let counter = replay.run().unwrap();
+        assert_eq!(counter.0, 6);
+        assert_eq!(*seen.borrow(), vec![(1, 1), (2, 3), (3, 6)]);
+    }
+
+    #[test]
+    fn test_recovery_applies_events_to_state() {
+        let journal = Arc::new(InMemoryJournal::default());
+        let persistence = Persistence {
+            id: "test-recovery".to_string(),
+            journal: journal.clone(),
+            codec: EventCodec::default(),
+            recovery: Some(Recovery::new(|counter: &mut Counter, event: Added| {
+                counter.apply(&event)
+            })),
+        };
+        for value in &[1, 2, 3] {
+            let payload = persistence.codec.encode(&Added(*value)).unwrap();
+            journal.append("test-recovery", payload).unwrap();
+        }
+
+        let mut counter = Counter::default();
+        assert_eq!(persistence.recover(&mut counter).unwrap(), 3);
+        assert_eq!(counter.0, 6);
+
+        // The state of the element must have the type the handler
+        // expects.
+        assert!(persistence.recover(&mut 0u8).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "remote")]
+    fn test_event_codec_packs_events_in_envelopes() {
+        let codec = EventCodec::new(crate::codec::BincodeCodec);
+        let payload = codec.encode(&Added(42)).unwrap();
+        let envelope = crate::codec::BincodeCodec.decode(&payload).unwrap();
+        assert_eq!(envelope.kind, EVENT_KIND);
+
+        let entry = JournalEntry {
+            persistence_id: "test-codec".to_string(),
+            sequence_nr: 1,
+            payload,
+        };
+        let event: Added = codec.decode(&entry).unwrap();
+        assert_eq!(event.0, 42);
+        assert!(EventCodec::default().decode::<Added>(&entry).is_err());
+    }
+}
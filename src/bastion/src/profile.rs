@@ -0,0 +1,188 @@
+//!
+//! The profile of the messages handled by the elements of a children
+//! group, recorded when profiling is enabled using
+//! [`Children::with_profiling`] and returned by
+//! [`ChildrenRef::profile_report`].
+//!
+//! An element handles a message from when it receives it until it
+//! tries to receive another one. The profile records, by type of
+//! message, how long the elements handled them and how much of that
+//! time they spent polling their future (as opposed to waiting,
+//! e.g. for an answer or a timer), which is the share of the
+//! executor's time they used.
+//!
+//! [`Children::with_profiling`]: crate::children::Children::with_profiling
+//! [`ChildrenRef::profile_report`]: crate::children_ref::ChildrenRef::profile_report
+use crate::metrics::{LatencyHistogram, LatencyRecorder};
+use fxhash::FxHashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The profile of the messages handled by the elements of a
+/// children group, returned by [`ChildrenRef::profile_report`].
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// let children_ref = Bastion::children(|children| {
+///     children
+///         .with_profiling()
+///         .with_exec(|ctx: BastionContext| async move {
+///             loop {
+///                 ctx.recv().await?;
+///             }
+///         })
+/// }).expect("Couldn't create the children group.");
+/// # Bastion::start();
+///
+/// // ...
+///
+/// let report = children_ref.profile_report().expect("Profiling is disabled.");
+/// for message in report.messages() {
+///     println!(
+///         "{}: {} handled, {:?} busy",
+///         message.type_name(),
+///         message.count(),
+///         message.busy(),
+///     );
+/// }
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+///
+/// [`ChildrenRef::profile_report`]: crate::children_ref::ChildrenRef::profile_report
+pub struct ProfileReport {
+    messages: Vec<MessageProfile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The profile of the messages of a type handled by the elements of
+/// a children group, in a [`ProfileReport`].
+pub struct MessageProfile {
+    type_name: &'static str,
+    handling: LatencyHistogram,
+    busy: Duration,
+}
+
+#[derive(Debug, Default)]
+// Records the profile of the messages handled by the elements of a
+// children group.
+pub(crate) struct GroupProfile {
+    messages: Mutex<FxHashMap<&'static str, MessageRecorder>>,
+}
+
+#[derive(Debug, Default)]
+struct MessageRecorder {
+    handling: LatencyRecorder,
+    busy: Duration,
+}
+
+impl ProfileReport {
+    /// Returns the profile of each type of message handled by the
+    /// elements of the group, from the one they spent the most time
+    /// polling their future for to the one they spent the least.
+    pub fn messages(&self) -> &[MessageProfile] {
+        &self.messages
+    }
+
+    /// Returns the profile of the messages of the given type, if
+    /// any was handled.
+    pub fn message<M: 'static>(&self) -> Option<&MessageProfile> {
+        let type_name = std::any::type_name::<M>();
+        self.messages
+            .iter()
+            .find(|message| message.type_name == type_name)
+    }
+}
+
+impl MessageProfile {
+    /// Returns the name of the type of the messages (see
+    /// [`std::any::type_name`]).
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns how many messages of this type were handled.
+    pub fn count(&self) -> u64 {
+        self.handling.count()
+    }
+
+    /// Returns how long the messages of this type were handled, from
+    /// when they were received until the elements tried to receive
+    /// another message.
+    pub fn handling(&self) -> &LatencyHistogram {
+        &self.handling
+    }
+
+    /// Returns how long the elements spent polling their future in
+    /// total while handling the messages of this type.
+    pub fn busy(&self) -> Duration {
+        self.busy
+    }
+}
+
+impl GroupProfile {
+    pub(crate) fn record(&self, type_name: &'static str, handling: Duration, busy: Duration) {
+        // FIXME: panics
+        let mut messages = self.messages.lock().unwrap();
+        let message = messages.entry(type_name).or_default();
+        message.handling.record(handling);
+        message.busy += busy;
+    }
+
+    pub(crate) fn report(&self) -> ProfileReport {
+        // FIXME: panics
+        let messages = self.messages.lock().unwrap();
+        let mut messages: Vec<_> = messages
+            .iter()
+            .map(|(type_name, message)| MessageProfile {
+                type_name,
+                handling: message.handling.snapshot(),
+                busy: message.busy,
+            })
+            .collect();
+        messages.sort_by(|a, b| b.busy.cmp(&a.busy));
+
+        ProfileReport { messages }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_profile_sorts_messages_by_busy_time() {
+        let profile = GroupProfile::default();
+        profile.record("a", Duration::from_millis(20), Duration::from_millis(1));
+        profile.record("b", Duration::from_millis(2), Duration::from_millis(2));
+        profile.record("b", Duration::from_millis(4), Duration::from_millis(3));
+
+        let report = profile.report();
+        let messages = report.messages();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].type_name(), "b");
+        assert_eq!(messages[0].count(), 2);
+        assert_eq!(messages[0].busy(), Duration::from_millis(5));
+        assert_eq!(messages[0].handling().sum(), Duration::from_millis(6));
+        assert_eq!(messages[1].type_name(), "a");
+        assert_eq!(messages[1].count(), 1);
+    }
+}
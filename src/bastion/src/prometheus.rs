@@ -0,0 +1,292 @@
+//!
+//! The children group serving the metrics of a system in the
+//! Prometheus text format, started using [`Bastion::serve_metrics`]
+//! when the `prometheus` feature is enabled.
+//!
+//! The group's element accepts the connections on a non-blocking
+//! listener and answers each scrape of `/metrics` with the metrics
+//! collected at that time (see [`RuntimeMetrics`]).
+//!
+//! [`Bastion::serve_metrics`]: crate::Bastion::serve_metrics
+//! [`RuntimeMetrics`]: crate::metrics::RuntimeMetrics
+use crate::children_ref::ChildrenRef;
+use crate::context::BastionContext;
+use crate::executor;
+use crate::metrics::{self, LatencyHistogram, RuntimeMetrics};
+use crate::system;
+use crate::Bastion;
+use std::fmt::Write as _;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, trace, warn};
+
+// How long the element waits before accepting connections again,
+// when none is pending.
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(50);
+// How long a connection has to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+// The maximum size of a request.
+const MAX_REQUEST: usize = 8 * 1024;
+
+// Binds the listener and starts the children group serving the
+// metrics of the current system on it.
+pub(crate) fn serve(addr: SocketAddr) -> io::Result<ChildrenRef> {
+    let listener = TcpListener::bind(addr)?;
+    listener.set_nonblocking(true)?;
+    debug!("Prometheus: Serving metrics on {}.", listener.local_addr()?);
+
+    // The listener is kept open across the restarts of the element.
+    let listener = Arc::new(listener);
+    Bastion::children(|children| {
+        children
+            .with_name("bastion-prometheus")
+            .with_exec(move |_ctx: BastionContext| {
+                let listener = listener.clone();
+                async move {
+                    loop {
+                        match listener.accept() {
+                            Ok((stream, peer)) => {
+                                trace!("Prometheus: Accepted connection from {}.", peer);
+                                // The request is read in a blocking task
+                                // so that a slow client doesn't block the
+                                // executor.
+                                executor::blocking(async move {
+                                    if let Err(err) = respond(stream) {
+                                        debug!("Prometheus: Couldn't answer {}: {}", peer, err);
+                                    }
+                                })
+                                .await;
+                            }
+                            Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                                executor::sleep(ACCEPT_INTERVAL).await;
+                            }
+                            Err(err) => {
+                                warn!("Prometheus: Couldn't accept connection: {}", err);
+                                return Err(());
+                            }
+                        }
+                    }
+                }
+            })
+    })
+    .map_err(|_| io::Error::new(ErrorKind::Other, "Couldn't create the children group."))
+}
+
+// Reads the request sent on the stream and answers it.
+fn respond(mut stream: TcpStream) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buf)?;
+        if read == 0 || request.len() + read > MAX_REQUEST {
+            break;
+        }
+
+        request.extend_from_slice(&buf[..read]);
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut line = request.lines().next().unwrap_or_default().split(' ');
+    let (status, body) = match (line.next(), line.next()) {
+        (Some("GET"), Some("/metrics")) => {
+            let metrics = metrics::collect(&system::current());
+            ("200 OK", render(&metrics))
+        }
+        (Some("GET"), Some(_)) => ("404 Not Found", String::new()),
+        _ => ("405 Method Not Allowed", String::new()),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+// Renders the metrics in the Prometheus text format.
+fn render(metrics: &RuntimeMetrics) -> String {
+    let mut out = String::new();
+    // Writing to a `String` never fails.
+    let _ = render_into(&mut out, metrics);
+    out
+}
+
+fn render_into(out: &mut String, metrics: &RuntimeMetrics) -> std::fmt::Result {
+    if !metrics.run_queues().is_empty() {
+        header(
+            out,
+            "bastion_run_queue_length",
+            "gauge",
+            "Tasks queued on each core.",
+        )?;
+        for (core, len) in metrics.run_queues().iter().enumerate() {
+            writeln!(out, "bastion_run_queue_length{{core=\"{}\"}} {}", core, len)?;
+        }
+    }
+    if let Some(pool) = metrics.blocking_pool() {
+        header(
+            out,
+            "bastion_blocking_queued",
+            "gauge",
+            "Blocking tasks waiting for a thread.",
+        )?;
+        writeln!(out, "bastion_blocking_queued {}", pool.queued())?;
+        header(
+            out,
+            "bastion_blocking_threads",
+            "gauge",
+            "Threads of the blocking pool.",
+        )?;
+        writeln!(out, "bastion_blocking_threads {}", pool.threads())?;
+    }
+
+    header(
+        out,
+        "bastion_tasks_spawned_total",
+        "counter",
+        "Tasks spawned since the process started.",
+    )?;
+    writeln!(
+        out,
+        "bastion_tasks_spawned_total {}",
+        metrics.tasks_spawned()
+    )?;
+    header(
+        out,
+        "bastion_tasks_live",
+        "gauge",
+        "Tasks which didn't complete yet.",
+    )?;
+    writeln!(out, "bastion_tasks_live {}", metrics.tasks_live())?;
+    header(
+        out,
+        "bastion_children",
+        "gauge",
+        "Elements of the running children groups.",
+    )?;
+    writeln!(out, "bastion_children {}", metrics.children())?;
+    header(
+        out,
+        "bastion_messages_total",
+        "counter",
+        "Messages received by the elements.",
+    )?;
+    writeln!(out, "bastion_messages_total {}", metrics.messages())?;
+
+    header(
+        out,
+        "bastion_ask_latency_seconds",
+        "histogram",
+        "Time questions waited for their answer.",
+    )?;
+    histogram(out, "bastion_ask_latency_seconds", metrics.ask_latency())?;
+
+    header(
+        out,
+        "bastion_group_elements",
+        "gauge",
+        "Elements of each children group.",
+    )?;
+    for group in metrics.groups() {
+        writeln!(
+            out,
+            "bastion_group_elements{{{}}} {}",
+            labels(group.name(), group.id()),
+            group.elements()
+        )?;
+    }
+    header(
+        out,
+        "bastion_group_mailbox_depth",
+        "gauge",
+        "Messages queued in the mailboxes of each children group.",
+    )?;
+    for group in metrics.groups() {
+        writeln!(
+            out,
+            "bastion_group_mailbox_depth{{{}}} {}",
+            labels(group.name(), group.id()),
+            group.mailbox_depth()
+        )?;
+    }
+    header(
+        out,
+        "bastion_group_restarts_total",
+        "counter",
+        "Restarts of the elements of each children group.",
+    )?;
+    for group in metrics.groups() {
+        writeln!(
+            out,
+            "bastion_group_restarts_total{{{}}} {}",
+            labels(group.name(), group.id()),
+            group.restarts()
+        )?;
+    }
+
+    Ok(())
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) -> std::fmt::Result {
+    writeln!(out, "# HELP {} {}", name, help)?;
+    writeln!(out, "# TYPE {} {}", name, kind)
+}
+
+fn histogram(out: &mut String, name: &str, histogram: &LatencyHistogram) -> std::fmt::Result {
+    for (bound, count) in histogram.buckets() {
+        writeln!(
+            out,
+            "{}_bucket{{le=\"{}\"}} {}",
+            name,
+            bound.as_secs_f64(),
+            count
+        )?;
+    }
+    writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, histogram.count())?;
+    writeln!(out, "{}_sum {}", name, histogram.sum().as_secs_f64())?;
+    writeln!(out, "{}_count {}", name, histogram.count())
+}
+
+fn labels(name: &str, id: impl std::fmt::Display) -> String {
+    let name = name
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("group=\"{}\",id=\"{}\"", name, id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::LatencyRecorder;
+
+    #[test]
+    fn test_render_histogram() {
+        let recorder = LatencyRecorder::default();
+        recorder.record(Duration::from_millis(3));
+        recorder.record(Duration::from_millis(30));
+        recorder.record(Duration::from_secs(60));
+
+        let mut out = String::new();
+        histogram(&mut out, "latency", &recorder.snapshot()).unwrap();
+        assert!(out.contains("latency_bucket{le=\"0.001\"} 0\n"));
+        assert!(out.contains("latency_bucket{le=\"0.005\"} 1\n"));
+        assert!(out.contains("latency_bucket{le=\"0.05\"} 2\n"));
+        assert!(out.contains("latency_bucket{le=\"10\"} 2\n"));
+        assert!(out.contains("latency_bucket{le=\"+Inf\"} 3\n"));
+        assert!(out.contains("latency_count 3\n"));
+    }
+
+    #[test]
+    fn test_labels_are_escaped() {
+        assert_eq!(labels("a\"b", 1), "group=\"a\\\"b\",id=\"1\"");
+    }
+}
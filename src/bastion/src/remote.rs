@@ -0,0 +1,1791 @@
+//!
+//! The transport of messages between the systems of different
+//! processes, over TCP.
+//!
+//! A process accepts the connections of other nodes using
+//! [`Bastion::listen`], and connects to another node under a name
+//! using [`Bastion::connect`]. Messages implementing [`Serialize`]
+//! can then be sent to the distributors of the node through a
+//! [`RemoteDistributor`] (see [`Distributor::remote`]): they are
+//! packed in a [`WireMessage`], shipped over the connection and sent
+//! to the distributor with the same name on the other side, whose
+//! recipients match them using [`MessageHandler::on_wire`]. The
+//! answers to the questions are routed back over the same connection.
+//!
+//! Each frame sent over a connection is made of its length, as a
+//! big-endian `u32`, followed by the frame encoded by the
+//! [`WireCodec`] picked by the node which opened the connection
+//! (JSON by default, see [`RemoteConfig::with_codec`]). The frames of
+//! the handshake are always encoded as JSON.
+//!
+//! During the handshake, each node answers a random challenge sent by
+//! the other one, bound to its side of the connection, so that the
+//! secrets of the nodes are never sent (see the [`security`] module).
+//!
+//! The connections can be authenticated and encrypted using the
+//! [`RemoteConfig`] set using [`Bastion::configure_remote`] (see the
+//! [`security`] module).
+//!
+//! This module is only available with the `remote` feature.
+//!
+//! [`Bastion::listen`]: crate::Bastion::listen
+//! [`Bastion::connect`]: crate::Bastion::connect
+//! [`Serialize`]: serde::Serialize
+//! [`Distributor::remote`]: crate::distributor::Distributor::remote
+//! [`WireMessage`]: crate::wire::WireMessage
+//! [`MessageHandler::on_wire`]: crate::message::MessageHandler::on_wire
+//! [`Bastion::configure_remote`]: crate::Bastion::configure_remote
+//! [`security`]: crate::security
+//! [`WireCodec`]: crate::codec::WireCodec
+use crate::children_ref::ChildrenRef;
+use crate::cluster::{self, Gossip};
+use crate::codec::{BincodeCodec, JsonCodec, WireCodec, WireEnvelope};
+use crate::context::BastionContext;
+use crate::dead_letters::{self, DeadLetter, Destination};
+use crate::distributor::Distributor;
+use crate::envelope::{RefAddr, SignedMessage};
+use crate::errors::SendError;
+use crate::events::SystemEvent;
+use crate::executor;
+use crate::message::{Answer, ErrorReply, Msg};
+#[cfg(feature = "tls")]
+use crate::security::TlsConfig;
+use crate::security::{Authenticator, Peer};
+use crate::system;
+use crate::time::{self, Instant};
+use crate::wire::WireMessage;
+use crate::Bastion;
+use futures::channel::oneshot;
+use fxhash::FxHashMap;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::fmt::{self, Debug, Formatter};
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::time::Duration;
+use tracing::{debug, info, trace, warn};
+use uuid::Uuid;
+
+static NODES: Lazy<NodeRegistry> = Lazy::new(NodeRegistry::default);
+static CONFIG: Lazy<RwLock<Arc<RemoteConfig>>> = Lazy::new(Default::default);
+
+// How long the element waits before accepting connections again,
+// when none is pending.
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(50);
+// How long connecting to a node can take, handshakes included.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+// The maximum size of a frame.
+const MAX_FRAME: usize = 16 * 1024 * 1024;
+// The sides of a connection the challenges answered by a node are
+// bound to, so that a node can't be sent back its own answer.
+const OPENING_NODE: &[u8] = b"opening";
+const ACCEPTING_NODE: &[u8] = b"accepting";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// A distributor of another node, returned by
+/// [`Distributor::remote`], sending the messages given to it to the
+/// recipients of the distributor with the same name on the node.
+///
+/// # Example
+///
+/// ```no_run
+/// # use bastion::prelude::*;
+/// # use serde::{Deserialize, Serialize};
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// #[derive(Debug, Serialize, Deserialize)]
+/// struct Resize {
+///     width: u32,
+///     height: u32,
+/// }
+///
+/// # fn run() {
+/// # Bastion::init();
+/// # Bastion::start();
+/// Bastion::connect("node-b", "10.0.0.2:4222").expect("Couldn't connect to node-b.");
+///
+/// let workers = Distributor::remote("node-b", "workers");
+/// workers
+///     .tell_one(Resize {
+///         width: 640,
+///         height: 480,
+///     })
+///     .expect("Couldn't send the message.");
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+///
+/// [`Distributor::remote`]: crate::distributor::Distributor::remote
+pub struct RemoteDistributor {
+    node: String,
+    name: String,
+}
+
+#[derive(Debug, Clone)]
+/// The configuration of the connections between this node and the
+/// other nodes, set using [`Bastion::configure_remote`].
+///
+/// By default, the connections aren't encrypted, every node is
+/// trusted and the frames are encoded as JSON.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// let config = RemoteConfig::new().with_authenticator(TokenAuthenticator::new("secret"));
+/// Bastion::configure_remote(config);
+///
+/// Bastion::listen("127.0.0.1:0").expect("Couldn't listen.");
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+///
+/// [`Bastion::configure_remote`]: crate::Bastion::configure_remote
+pub struct RemoteConfig {
+    #[cfg(feature = "tls")]
+    tls: Option<TlsConfig>,
+    authenticator: Option<Arc<dyn Authenticator>>,
+    reconnect: ReconnectPolicy,
+    // The codec of the connections opened by this node.
+    codec: Arc<dyn WireCodec>,
+    // The codecs of the connections opened to some nodes, by name.
+    node_codecs: FxHashMap<String, Arc<dyn WireCodec>>,
+    // The codecs the other nodes can pick, besides the built-in ones.
+    codecs: Vec<Arc<dyn WireCodec>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// How the connections to the nodes connected using
+/// [`Bastion::connect`] (or members of the cluster) are opened again
+/// once they are lost, set using [`RemoteConfig::reconnect`].
+///
+/// Either way, the questions waiting for an answer from the node
+/// fail as soon as the connection is lost, and the loss of the
+/// connection and its reopening are published on the event bus of
+/// the system as [`SystemEvent::NodeDown`] and
+/// [`SystemEvent::NodeUp`] (see [`Bastion::events`]).
+///
+/// The default policy is [`OnDemand`].
+///
+/// [`Bastion::connect`]: crate::Bastion::connect
+/// [`SystemEvent::NodeDown`]: crate::events::SystemEvent::NodeDown
+/// [`SystemEvent::NodeUp`]: crate::events::SystemEvent::NodeUp
+/// [`Bastion::events`]: crate::Bastion::events
+/// [`OnDemand`]: ReconnectPolicy::OnDemand
+pub enum ReconnectPolicy {
+    /// Open the connection again the next time a message is sent to
+    /// the node.
+    OnDemand,
+    /// Open the connection again in the background, waiting longer
+    /// after each failed attempt. Meanwhile, sending a message to the
+    /// node fails immediately.
+    Backoff {
+        /// How long to wait before the first attempt.
+        initial: Duration,
+        /// How much longer to wait after each failed attempt.
+        multiplier: f64,
+        /// The longest to wait between two attempts.
+        max: Duration,
+    },
+}
+
+#[derive(Debug)]
+// A frame sent over a connection between two nodes, which is sent as
+// a `WireEnvelope`.
+pub(crate) enum Frame {
+    // A message sent to a distributor of the node.
+    Tell {
+        distributor: String,
+        message: WireMessage,
+    },
+    // A message sent to every recipient of a distributor of the
+    // node (see `Distributor::tell_everyone`).
+    TellEveryone {
+        distributor: String,
+        message: WireMessage,
+    },
+    // A question asked to a distributor of the node, whose answer is
+    // sent back with the same id.
+    Ask {
+        id: u64,
+        distributor: String,
+        message: WireMessage,
+    },
+    // The answer to a question.
+    Reply {
+        id: u64,
+        message: WireMessage,
+    },
+    // The error replied to a question (see `AnswerSender::reply_err`).
+    Error {
+        id: u64,
+        error: String,
+    },
+    // A question dropped without being answered.
+    Dropped {
+        id: u64,
+    },
+    // The name of the codec of the connection and the challenge of
+    // the node which opened the connection, which is the first frame
+    // it sends.
+    Hello {
+        codec: String,
+        challenge: Vec<u8>,
+    },
+    // The challenge of the node which accepted the connection.
+    Challenge {
+        challenge: Vec<u8>,
+    },
+    // The credentials of a node answering the challenge of the other
+    // node, sent by the node which accepted the connection first.
+    Proof {
+        credentials: Vec<u8>,
+    },
+    // Sent by the node which accepted the connection once it
+    // authenticated the other node.
+    Welcome,
+    // Why a node refused the other node, before closing the
+    // connection.
+    Rejected {
+        reason: String,
+    },
+    // A message sent to an entity of a sharding hosted by the node
+    // (see `Sharding::tell`).
+    Shard {
+        sharding: String,
+        entity: String,
+        message: WireMessage,
+    },
+    // The state of the members of the cluster known by the node (see
+    // `Cluster::join`).
+    Gossip {
+        members: Vec<Gossip>,
+    },
+}
+
+#[derive(Debug, Default)]
+// The nodes connected using `Bastion::connect`, by name.
+struct NodeRegistry {
+    nodes: RwLock<FxHashMap<String, Arc<Node>>>,
+}
+
+#[derive(Debug)]
+struct Node {
+    name: String,
+    addr: SocketAddr,
+    // The connection to the node, opened again when it is closed.
+    connection: Mutex<Option<Arc<Connection>>>,
+    // Whether the last connection to the node was opened and isn't
+    // closed yet.
+    up: AtomicBool,
+    // Whether the connection is being opened again in the background
+    // (see `ReconnectPolicy::Backoff`).
+    reconnecting: AtomicBool,
+    // Whether the node was replaced by another one registered under
+    // the same name.
+    removed: AtomicBool,
+}
+
+// A connection to another node, accepted or opened by this one,
+// over which both nodes send messages and questions.
+pub(crate) struct Connection {
+    peer: SocketAddr,
+    // The node, once authenticated.
+    identity: Peer,
+    authenticator: Option<Arc<dyn Authenticator>>,
+    // The node this node opened the connection to.
+    node: Weak<Node>,
+    socket: TcpStream,
+    codec: Arc<dyn WireCodec>,
+    writer: Mutex<Box<dyn Write + Send>>,
+    // The senders of the answers to the questions asked over the
+    // connection and when they were asked, by id.
+    pending: Mutex<FxHashMap<u64, (oneshot::Sender<SignedMessage>, Instant)>>,
+    next_id: AtomicU64,
+    // The moving average of how long the questions took to be
+    // answered, in microseconds (`0` until one is).
+    latency: AtomicU64,
+    closed: AtomicBool,
+}
+
+// The socket of a connection, along with the halves of the stream
+// over it, encrypted using TLS if it is configured.
+struct Stream {
+    socket: TcpStream,
+    reader: Box<dyn Read + Send>,
+    writer: Box<dyn Write + Send>,
+    // The certificates presented by the node over TLS.
+    certificates: Vec<Vec<u8>>,
+}
+
+impl RemoteDistributor {
+    pub(crate) fn new(node: impl Into<String>, name: impl Into<String>) -> Self {
+        RemoteDistributor {
+            node: node.into(),
+            name: name.into(),
+        }
+    }
+
+    /// Returns the name of the node of the distributor.
+    pub fn node(&self) -> &str {
+        &self.node
+    }
+
+    /// Returns the name of the distributor.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Sends the message to a recipient of the distributor on the
+    /// node, which receives it as a [`WireMessage`] (see
+    /// [`MessageHandler::on_wire`]).
+    ///
+    /// This method returns an error if the node wasn't connected
+    /// using [`Bastion::connect`], if the message couldn't be
+    /// serialized or if the connection to the node failed, in which
+    /// case the message is kept in the [`DeadLetters`]. The message
+    /// being sent doesn't mean that the node found a recipient for
+    /// it.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to send.
+    ///
+    /// [`WireMessage`]: crate::wire::WireMessage
+    /// [`MessageHandler::on_wire`]: crate::message::MessageHandler::on_wire
+    /// [`Bastion::connect`]: crate::Bastion::connect
+    /// [`DeadLetters`]: crate::dead_letters::DeadLetters
+    pub fn tell_one<M: Serialize + 'static>(&self, message: M) -> Result<(), SendError> {
+        let message = WireMessage::pack(&message).map_err(|err| SendError::Other(err.into()))?;
+        self.send(|connection| connection.tell(&self.name, message.clone()))
+            .map_err(|err| {
+                let destination = Destination::Distributor(self.name.clone());
+                dead_letters::record(DeadLetter::sent(&self.node, destination, message, &err));
+                err
+            })
+    }
+
+    /// Asks the question to a recipient of the distributor on the
+    /// node, which receives it as a [`WireMessage`] and must answer
+    /// it with another [`WireMessage`] (or with an error using
+    /// [`AnswerSender::reply_err`]).
+    ///
+    /// The returned [`Answer`] resolves to the [`WireMessage`]
+    /// replied by the recipient, and fails with
+    /// [`SendError::NoAnswer`] if the question was dropped without
+    /// being answered or if the connection was lost.
+    ///
+    /// # Arguments
+    ///
+    /// * `question` - The question to ask.
+    ///
+    /// [`WireMessage`]: crate::wire::WireMessage
+    /// [`AnswerSender::reply_err`]: crate::message::AnswerSender::reply_err
+    pub fn ask_one<M: Serialize + 'static>(&self, question: M) -> Result<Answer, SendError> {
+        let question = WireMessage::pack(&question).map_err(|err| SendError::Other(err.into()))?;
+        self.send(|connection| connection.ask(&self.name, question))
+    }
+
+    fn send<T>(&self, f: impl FnOnce(&Connection) -> io::Result<T>) -> Result<T, SendError> {
+        let node = NODES
+            .get(&self.node)
+            .ok_or_else(|| SendError::UnknownNode(self.node.clone()))?;
+        let connection = node
+            .connection()
+            .map_err(|err| SendError::Other(err.into()))?;
+        f(&connection).map_err(|err| {
+            warn!(
+                "Remote: Couldn't send message to {} ({}): {}",
+                self.node, connection.peer, err
+            );
+            connection.close();
+            SendError::Other(err.into())
+        })
+    }
+}
+
+impl RemoteConfig {
+    /// Creates the default configuration, neither encrypting nor
+    /// authenticating the connections.
+    pub fn new() -> Self {
+        RemoteConfig::default()
+    }
+
+    #[cfg(feature = "tls")]
+    /// Encrypts the connections using mutual TLS: the nodes must
+    /// present a certificate signed by one of the certificate
+    /// authorities trusted by the configuration.
+    ///
+    /// This method is only available with the `tls` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `tls` - The certificates of this node and the trusted
+    ///     certificate authorities.
+    pub fn with_tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Authenticates the nodes using the given authenticator, which
+    /// also authorizes the messages they send to the distributors
+    /// of this node.
+    ///
+    /// Authenticating the nodes doesn't prevent the messages they
+    /// send from being read or altered on the network: a warning is
+    /// logged when the configuration is set if the connections aren't
+    /// also encrypted using TLS.
+    ///
+    /// # Arguments
+    ///
+    /// * `authenticator` - The authenticator of the nodes.
+    pub fn with_authenticator(mut self, authenticator: impl Authenticator) -> Self {
+        self.authenticator = Some(Arc::new(authenticator));
+        self
+    }
+
+    /// Sets how the connections to the nodes are opened again once
+    /// they are lost (see [`ReconnectPolicy`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The reconnection policy.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// let config = RemoteConfig::new().reconnect(ReconnectPolicy::Backoff {
+    ///     initial: Duration::from_millis(100),
+    ///     multiplier: 2.0,
+    ///     max: Duration::from_secs(10),
+    /// });
+    /// ```
+    pub fn reconnect(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect = policy;
+        self
+    }
+
+    /// Encodes the frames sent over the connections opened by this
+    /// node using the given codec, which the other nodes must accept
+    /// (see [`RemoteConfig::with_accepted_codec`]). The built-in
+    /// codecs are always accepted.
+    ///
+    /// # Arguments
+    ///
+    /// * `codec` - The codec of the connections.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// let config = RemoteConfig::new().with_codec(BincodeCodec);
+    /// ```
+    pub fn with_codec(mut self, codec: impl WireCodec) -> Self {
+        let codec: Arc<dyn WireCodec> = Arc::new(codec);
+        self.codecs.push(codec.clone());
+        self.codec = codec;
+        self
+    }
+
+    /// Encodes the frames sent over the connections opened to the
+    /// node connected under the given name (see [`Bastion::connect`])
+    /// using the given codec, instead of the one set using
+    /// [`RemoteConfig::with_codec`].
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The name of the node.
+    /// * `codec` - The codec of the connections to the node.
+    ///
+    /// [`Bastion::connect`]: crate::Bastion::connect
+    pub fn with_node_codec(mut self, node: impl Into<String>, codec: impl WireCodec) -> Self {
+        let codec: Arc<dyn WireCodec> = Arc::new(codec);
+        self.codecs.push(codec.clone());
+        self.node_codecs.insert(node.into(), codec);
+        self
+    }
+
+    /// Accepts the connections whose frames are encoded using the
+    /// given codec, besides the built-in ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `codec` - The accepted codec.
+    pub fn with_accepted_codec(mut self, codec: impl WireCodec) -> Self {
+        self.codecs.push(Arc::new(codec));
+        self
+    }
+
+    // Returns the codec of the connections opened to the node.
+    fn codec_for(&self, node: &str) -> Arc<dyn WireCodec> {
+        match self.node_codecs.get(node) {
+            Some(codec) => codec.clone(),
+            None => self.codec.clone(),
+        }
+    }
+
+    // Returns the accepted codec with the given name.
+    fn accepted_codec(&self, name: &str) -> Option<Arc<dyn WireCodec>> {
+        if let Some(codec) = self.codecs.iter().find(|codec| codec.name() == name) {
+            return Some(codec.clone());
+        }
+
+        let builtin: Arc<dyn WireCodec> = match name {
+            "json" => Arc::new(JsonCodec),
+            "bincode" => Arc::new(BincodeCodec),
+            #[cfg(feature = "msgpack")]
+            "msgpack" => Arc::new(crate::codec::MessagePackCodec),
+            #[cfg(feature = "protobuf")]
+            "protobuf" => Arc::new(crate::codec::ProtobufCodec),
+            _ => return None,
+        };
+        Some(builtin)
+    }
+
+    fn credentials(&self, challenge: &[u8]) -> Vec<u8> {
+        match &self.authenticator {
+            Some(authenticator) => authenticator.credentials(challenge),
+            None => Vec::new(),
+        }
+    }
+
+    fn authenticate(&self, peer: &Peer) -> Result<(), String> {
+        match &self.authenticator {
+            Some(authenticator) => authenticator.authenticate(peer),
+            None => Ok(()),
+        }
+    }
+
+    fn encrypted(&self) -> bool {
+        #[cfg(feature = "tls")]
+        let encrypted = self.tls.is_some();
+        #[cfg(not(feature = "tls"))]
+        let encrypted = false;
+        encrypted
+    }
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        RemoteConfig {
+            #[cfg(feature = "tls")]
+            tls: None,
+            authenticator: None,
+            reconnect: ReconnectPolicy::default(),
+            codec: Arc::new(JsonCodec),
+            node_codecs: FxHashMap::default(),
+            codecs: Vec::new(),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    // Returns how long to wait before the attempt following the given
+    // number of failed ones.
+    fn delay(&self, failed: u32) -> Option<Duration> {
+        match *self {
+            ReconnectPolicy::OnDemand => None,
+            ReconnectPolicy::Backoff {
+                initial,
+                multiplier,
+                max,
+            } => {
+                let factor = multiplier.max(1.0).powi(failed.min(64) as i32);
+                // Saturates instead of overflowing.
+                let delay = initial.as_secs_f64() * factor;
+                Some(Duration::from_secs_f64(delay.min(max.as_secs_f64())))
+            }
+        }
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy::OnDemand
+    }
+}
+
+impl NodeRegistry {
+    fn get(&self, name: &str) -> Option<Arc<Node>> {
+        // FIXME: panics
+        self.nodes.read().unwrap().get(name).cloned()
+    }
+
+    // Registers the node unless it is already registered with the
+    // same address, without connecting to it yet.
+    fn register(&self, name: &str, addr: SocketAddr) {
+        // FIXME: panics
+        let mut nodes = self.nodes.write().unwrap();
+        if nodes.get(name).map(|node| node.addr) == Some(addr) {
+            return;
+        }
+
+        let node = Arc::new(Node::new(name.to_string(), addr));
+        if let Some(previous) = nodes.insert(name.to_string(), node) {
+            previous.close();
+        }
+    }
+
+    fn insert(&self, node: Arc<Node>) {
+        // FIXME: panics
+        let previous = self.nodes.write().unwrap().insert(node.name.clone(), node);
+        if let Some(previous) = previous {
+            previous.close();
+        }
+    }
+}
+
+impl Node {
+    fn new(name: String, addr: SocketAddr) -> Self {
+        Node {
+            name,
+            addr,
+            connection: Mutex::new(None),
+            up: AtomicBool::new(false),
+            reconnecting: AtomicBool::new(false),
+            removed: AtomicBool::new(false),
+        }
+    }
+
+    // Returns the connection to the node, opening it again if it
+    // was closed (unless it is being opened in the background).
+    fn connection(self: &Arc<Self>) -> io::Result<Arc<Connection>> {
+        // FIXME: panics
+        let mut connection = self.connection.lock().unwrap();
+        match &*connection {
+            Some(opened) if !opened.is_closed() => Ok(opened.clone()),
+            _ if self.reconnecting.load(Ordering::SeqCst) => Err(io::Error::new(
+                ErrorKind::NotConnected,
+                "The node is being reconnected to.",
+            )),
+            _ => {
+                let opened = self.open(&mut connection)?;
+                self.mark_up();
+                Ok(opened)
+            }
+        }
+    }
+
+    fn open(
+        self: &Arc<Self>,
+        connection: &mut Option<Arc<Connection>>,
+    ) -> io::Result<Arc<Connection>> {
+        debug!("Remote: Connecting to {} ({}).", self.name, self.addr);
+        let config = config();
+        let codec = config.codec_for(&self.name);
+        let opened = Connection::open(self.addr, &config, codec, Arc::downgrade(self))?;
+        *connection = Some(opened.clone());
+        Ok(opened)
+    }
+
+    fn mark_up(&self) {
+        if !self.up.swap(true, Ordering::SeqCst) {
+            info!("Remote: {} ({}) is up.", self.name, self.addr);
+            system::current().events().publish(SystemEvent::NodeUp {
+                node: self.name.clone(),
+                addr: self.addr,
+            });
+        }
+    }
+
+    // Called when the connection to the node is lost.
+    fn disconnected(self: &Arc<Self>) {
+        if self.removed.load(Ordering::SeqCst) {
+            return;
+        }
+
+        if self.up.swap(false, Ordering::SeqCst) {
+            warn!("Remote: {} ({}) is down.", self.name, self.addr);
+            system::current().events().publish(SystemEvent::NodeDown {
+                node: self.name.clone(),
+                addr: self.addr,
+            });
+        }
+
+        let policy = config().reconnect.clone();
+        if policy.delay(0).is_some() && !self.reconnecting.swap(true, Ordering::SeqCst) {
+            let node = self.clone();
+            executor::blocking(async move { node.reconnect(policy).await });
+        }
+    }
+
+    // Opens the connection again, waiting longer after each failed
+    // attempt, until it is opened or the node is replaced.
+    async fn reconnect(self: Arc<Self>, policy: ReconnectPolicy) {
+        let mut failed = 0;
+        while let Some(delay) = policy.delay(failed) {
+            executor::sleep(delay).await;
+            if self.removed.load(Ordering::SeqCst) {
+                break;
+            }
+
+            // FIXME: panics
+            let mut connection = self.connection.lock().unwrap();
+            match self.open(&mut connection) {
+                Ok(_) => {
+                    // Reset first, so that losing the connection again
+                    // starts reconnecting again.
+                    self.reconnecting.store(false, Ordering::SeqCst);
+                    self.mark_up();
+                    return;
+                }
+                Err(err) => {
+                    failed += 1;
+                    debug!(
+                        "Remote: Couldn't reconnect to {} ({}), attempt {}: {}",
+                        self.name, self.addr, failed, err
+                    );
+                }
+            }
+        }
+
+        self.reconnecting.store(false, Ordering::SeqCst);
+    }
+
+    fn latency(&self) -> Option<Duration> {
+        // FIXME: panics
+        match &*self.connection.lock().unwrap() {
+            Some(connection) if !connection.is_closed() => connection.latency(),
+            _ => None,
+        }
+    }
+
+    // Closes the connection to the node, which was replaced.
+    fn close(&self) {
+        self.removed.store(true, Ordering::SeqCst);
+        // FIXME: panics
+        if let Some(connection) = self.connection.lock().unwrap().take() {
+            connection.close();
+        }
+    }
+}
+
+impl Stream {
+    // Secures the socket using TLS if it is configured. `server` is
+    // the address of the node if this node opened the connection.
+    #[cfg_attr(not(feature = "tls"), allow(unused_variables))]
+    fn new(
+        socket: TcpStream,
+        server: Option<SocketAddr>,
+        config: &RemoteConfig,
+    ) -> io::Result<Self> {
+        socket.set_nodelay(true)?;
+        #[cfg(feature = "tls")]
+        {
+            if let Some(tls) = &config.tls {
+                let (reader, writer, certificates) = tls.handshake(&socket, server)?;
+                return Ok(Stream {
+                    socket,
+                    reader: Box::new(reader),
+                    writer: Box::new(writer),
+                    certificates,
+                });
+            }
+        }
+
+        Ok(Stream {
+            reader: Box::new(socket.try_clone()?),
+            writer: Box::new(socket.try_clone()?),
+            socket,
+            certificates: Vec::new(),
+        })
+    }
+}
+
+impl Connection {
+    // Opens a connection to the node listening at the address, whose
+    // frames are encoded using the codec, failing if the nodes don't
+    // trust each other.
+    fn open(
+        addr: SocketAddr,
+        config: &RemoteConfig,
+        codec: Arc<dyn WireCodec>,
+        node: Weak<Node>,
+    ) -> io::Result<Arc<Self>> {
+        let socket = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)?;
+        socket.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+        let mut stream = Stream::new(socket, Some(addr), config)?;
+
+        let nonce = nonce();
+        let hello = Frame::Hello {
+            codec: codec.name().to_string(),
+            challenge: nonce.clone(),
+        };
+        write_frame(&mut stream.writer, &hello, &JsonCodec)?;
+        let challenge = match handshake(&mut stream, addr)? {
+            Frame::Challenge { challenge } => challenge,
+            frame => return Err(unexpected_handshake(&frame)),
+        };
+        // The node accepting the connection proves its identity
+        // first.
+        let peer = match handshake(&mut stream, addr)? {
+            Frame::Proof { credentials } => Peer::new(
+                addr,
+                stream.certificates.clone(),
+                signed_challenge(&nonce, ACCEPTING_NODE),
+                credentials,
+            ),
+            frame => return Err(unexpected_handshake(&frame)),
+        };
+        if let Err(reason) = config.authenticate(&peer) {
+            return Err(refuse(&mut stream, addr, reason));
+        }
+
+        let proof = Frame::Proof {
+            credentials: config.credentials(&signed_challenge(&challenge, OPENING_NODE)),
+        };
+        write_frame(&mut stream.writer, &proof, &JsonCodec)?;
+        match handshake(&mut stream, addr)? {
+            Frame::Welcome => (),
+            frame => return Err(unexpected_handshake(&frame)),
+        }
+
+        stream.socket.set_read_timeout(None)?;
+        Ok(Connection::start(stream, peer, config, codec, node))
+    }
+
+    // Secures the accepted connection and authenticates the node
+    // which opened it, before reading the frames it sends.
+    fn accept(socket: TcpStream, addr: SocketAddr, config: &RemoteConfig) -> io::Result<Arc<Self>> {
+        socket.set_nonblocking(false)?;
+        socket.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+        let mut stream = match Stream::new(socket, None, config) {
+            Ok(stream) => stream,
+            Err(err) => {
+                reject_connection(addr, &err.to_string());
+                return Err(err);
+            }
+        };
+
+        let (codec, challenge) = match read_frame(&mut stream.reader, &JsonCodec)? {
+            Some(Frame::Hello { codec, challenge }) => (codec, challenge),
+            // No message is accepted before the node is authenticated.
+            frame => {
+                let reason = format!("unexpected handshake {:?}", frame);
+                reject_connection(addr, &reason);
+                return Err(io::Error::new(ErrorKind::InvalidData, reason));
+            }
+        };
+        let codec = match config.accepted_codec(&codec) {
+            Some(codec) => codec,
+            None => {
+                return Err(refuse(
+                    &mut stream,
+                    addr,
+                    format!("unknown codec {}", codec),
+                ))
+            }
+        };
+
+        let nonce = nonce();
+        let sent = Frame::Challenge {
+            challenge: nonce.clone(),
+        };
+        let proof = Frame::Proof {
+            credentials: config.credentials(&signed_challenge(&challenge, ACCEPTING_NODE)),
+        };
+        write_frame(&mut stream.writer, &sent, &JsonCodec)?;
+        write_frame(&mut stream.writer, &proof, &JsonCodec)?;
+        let peer = match handshake(&mut stream, addr) {
+            Ok(Frame::Proof { credentials }) => Peer::new(
+                addr,
+                stream.certificates.clone(),
+                signed_challenge(&nonce, OPENING_NODE),
+                credentials,
+            ),
+            Ok(frame) => {
+                let err = unexpected_handshake(&frame);
+                reject_connection(addr, &err.to_string());
+                return Err(err);
+            }
+            Err(err) => return Err(err),
+        };
+        if let Err(reason) = config.authenticate(&peer) {
+            return Err(refuse(&mut stream, addr, reason));
+        }
+
+        write_frame(&mut stream.writer, &Frame::Welcome, &JsonCodec)?;
+        stream.socket.set_read_timeout(None)?;
+        Ok(Connection::start(stream, peer, config, codec, Weak::new()))
+    }
+
+    // Starts reading the frames sent by the authenticated node over
+    // the stream, and returns the connection allowing to send frames
+    // to it.
+    fn start(
+        stream: Stream,
+        peer: Peer,
+        config: &RemoteConfig,
+        codec: Arc<dyn WireCodec>,
+        node: Weak<Node>,
+    ) -> Arc<Self> {
+        debug!(
+            "Remote: Connected to {} using {}.",
+            peer.addr(),
+            codec.name()
+        );
+        let connection = Arc::new(Connection {
+            peer: peer.addr(),
+            identity: peer,
+            authenticator: config.authenticator.clone(),
+            node,
+            socket: stream.socket,
+            codec,
+            writer: Mutex::new(stream.writer),
+            pending: Mutex::new(FxHashMap::default()),
+            next_id: AtomicU64::new(0),
+            latency: AtomicU64::new(0),
+            closed: AtomicBool::new(false),
+        });
+
+        let reader = connection.clone();
+        let stream = stream.reader;
+        // The frames are read in a blocking task, which lives as long
+        // as the connection.
+        executor::blocking(async move { reader.read_frames(stream) });
+        connection
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    // Closes the connection, dropping the questions waiting for an
+    // answer.
+    pub(crate) fn close(&self) {
+        if self.closed.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        debug!("Remote: Closing connection to {}.", self.peer);
+        self.socket.shutdown(Shutdown::Both).ok();
+        // The questions fail right away.
+        // FIXME: panics
+        self.pending.lock().unwrap().clear();
+        if let Some(node) = self.node.upgrade() {
+            node.disconnected();
+        }
+    }
+
+    fn tell(&self, distributor: &str, message: WireMessage) -> io::Result<()> {
+        self.send(&Frame::Tell {
+            distributor: distributor.to_string(),
+            message,
+        })
+    }
+
+    fn ask(&self, distributor: &str, message: WireMessage) -> io::Result<Answer> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, answer) = Answer::channel();
+        // FIXME: panics
+        self.pending
+            .lock()
+            .unwrap()
+            .insert(id, (sender, time::now()));
+
+        let frame = Frame::Ask {
+            id,
+            distributor: distributor.to_string(),
+            message,
+        };
+        if let Err(err) = self.send(&frame) {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(err);
+        }
+
+        Ok(answer)
+    }
+
+    pub(crate) fn send(&self, frame: &Frame) -> io::Result<()> {
+        if self.is_closed() {
+            return Err(ErrorKind::NotConnected.into());
+        }
+
+        trace!("Remote: Sending {:?} to {}.", frame, self.peer);
+        // FIXME: panics
+        write_frame(&mut *self.writer.lock().unwrap(), frame, &*self.codec)
+    }
+
+    fn read_frames(self: Arc<Self>, mut stream: Box<dyn Read + Send>) {
+        loop {
+            match read_frame(&mut stream, &*self.codec) {
+                Ok(Some(frame)) => self.handle(frame),
+                Ok(None) => {
+                    debug!("Remote: {} closed the connection.", self.peer);
+                    break;
+                }
+                Err(err) => {
+                    if !self.is_closed() {
+                        warn!("Remote: Couldn't read from {}: {}", self.peer, err);
+                    }
+                    break;
+                }
+            }
+        }
+
+        self.close();
+    }
+
+    fn handle(self: &Arc<Self>, frame: Frame) {
+        trace!("Remote: Received {:?} from {}.", frame, self.peer);
+        match frame {
+            Frame::Tell {
+                distributor,
+                message,
+            } => {
+                if self.authorize(&distributor) {
+                    self.deliver(Destination::Distributor(distributor), message);
+                }
+            }
+            Frame::TellEveryone {
+                distributor,
+                message,
+            } => {
+                if self.authorize(&distributor) {
+                    self.deliver(Destination::Everyone(distributor), message);
+                }
+            }
+            Frame::Shard {
+                sharding,
+                entity,
+                message,
+            } => {
+                // The sharding is authorized like a distributor.
+                if self.authorize(&sharding) {
+                    self.deliver(Destination::Entity { sharding, entity }, message);
+                }
+            }
+            Frame::Gossip { members } => cluster::receive(members),
+            Frame::Ask {
+                id, distributor, ..
+            } if !self.authorize(&distributor) => {
+                let frame = Frame::Error {
+                    id,
+                    error: format!("unauthorized to ask {}", distributor),
+                };
+                self.send(&frame).ok();
+            }
+            // Only the local recipients are asked, so that the question
+            // isn't asked back to the other nodes.
+            Frame::Ask {
+                id,
+                distributor,
+                message,
+            } => match system::current()
+                .dispatcher()
+                .ask(Distributor::named(&distributor), message)
+            {
+                Ok(answer) => {
+                    let connection = self.clone();
+                    executor::spawn(async move {
+                        let frame = match answer.await {
+                            Ok(reply) => reply_frame(id, reply.msg),
+                            Err(()) => Frame::Dropped { id },
+                        };
+                        if let Err(err) = connection.send(&frame) {
+                            debug!("Remote: Couldn't answer {}: {}", connection.peer, err);
+                        }
+                    });
+                }
+                Err(err) => {
+                    let frame = Frame::Error {
+                        id,
+                        error: err.to_string(),
+                    };
+                    self.send(&frame).ok();
+                }
+            },
+            Frame::Reply { id, message } => self.answer(id, Msg::tell(message)),
+            Frame::Error { id, error } => self.answer(id, Msg::tell(ErrorReply(error))),
+            Frame::Dropped { id } => {
+                // Dropping the sender makes the answer fail.
+                self.pending.lock().unwrap().remove(&id);
+            }
+            Frame::Hello { .. }
+            | Frame::Challenge { .. }
+            | Frame::Proof { .. }
+            | Frame::Welcome
+            | Frame::Rejected { .. } => {
+                debug!("Remote: Unexpected handshake from {}.", self.peer);
+            }
+        }
+    }
+
+    // Delivers the message received from the node, keeping it in the
+    // dead letters if it couldn't be.
+    fn deliver(&self, destination: Destination, message: WireMessage) {
+        if let Err(err) = destination.deliver(message.clone()) {
+            debug!(
+                "Remote: Couldn't deliver message from {} to {:?}: {}",
+                self.peer, destination, err
+            );
+            dead_letters::record(DeadLetter::received(self.peer, destination, message, err));
+        }
+    }
+
+    // Returns whether the node is allowed to send messages to the
+    // distributor, publishing the rejection of the message if not.
+    fn authorize(&self, distributor: &str) -> bool {
+        let authorized = match &self.authenticator {
+            Some(authenticator) => authenticator.authorize(&self.identity, distributor),
+            None => true,
+        };
+        if !authorized {
+            warn!(
+                "Remote: Rejected message from {} to {}.",
+                self.peer, distributor
+            );
+            system::current()
+                .events()
+                .publish(SystemEvent::MessageRejected {
+                    peer: self.peer,
+                    distributor: distributor.to_string(),
+                });
+        }
+        authorized
+    }
+
+    fn answer(&self, id: u64, msg: Msg) {
+        // FIXME: panics
+        match self.pending.lock().unwrap().remove(&id) {
+            Some((sender, asked_at)) => {
+                self.record_latency(time::since(asked_at));
+                sender
+                    .send(SignedMessage::new(msg, RefAddr::dead_letters()))
+                    .ok();
+            }
+            None => debug!("Remote: Unexpected answer {} from {}.", id, self.peer),
+        }
+    }
+
+    fn record_latency(&self, elapsed: Duration) {
+        // Rounds up, so that `0` keeps meaning that nothing was
+        // recorded.
+        let elapsed = elapsed.as_micros().max(1) as u64;
+        let previous = self.latency.load(Ordering::Relaxed);
+        let latency = match previous {
+            0 => elapsed,
+            previous => (previous * 7 + elapsed) / 8,
+        };
+        self.latency.store(latency.max(1), Ordering::Relaxed);
+    }
+
+    fn latency(&self) -> Option<Duration> {
+        match self.latency.load(Ordering::Relaxed) {
+            0 => None,
+            latency => Some(Duration::from_micros(latency)),
+        }
+    }
+}
+
+impl Debug for Connection {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Connection")
+            .field("peer", &self.peer)
+            .field("codec", &self.codec.name())
+            .field("closed", &self.closed)
+            .finish()
+    }
+}
+
+// Returns a random challenge sent to a node during the handshake.
+fn nonce() -> Vec<u8> {
+    let mut nonce = Uuid::new_v4().as_bytes().to_vec();
+    nonce.extend_from_slice(Uuid::new_v4().as_bytes());
+    nonce
+}
+
+// Returns the challenge answered by the node on the given side of
+// the connection.
+fn signed_challenge(nonce: &[u8], side: &[u8]) -> Vec<u8> {
+    let mut challenge = nonce.to_vec();
+    challenge.extend_from_slice(side);
+    challenge
+}
+
+// Reads the next frame of the handshake, failing if the node refused
+// this node or closed the connection.
+fn handshake(stream: &mut Stream, peer: SocketAddr) -> io::Result<Frame> {
+    match read_frame(&mut stream.reader, &JsonCodec)? {
+        Some(Frame::Rejected { reason }) => {
+            warn!("Remote: {} refused the connection: {}", peer, reason);
+            Err(io::Error::new(ErrorKind::PermissionDenied, reason))
+        }
+        Some(frame) => Ok(frame),
+        None => Err(io::Error::new(
+            ErrorKind::UnexpectedEof,
+            "The connection was closed during the handshake.",
+        )),
+    }
+}
+
+fn unexpected_handshake(frame: &Frame) -> io::Error {
+    let reason = format!("unexpected handshake {:?}", frame);
+    io::Error::new(ErrorKind::InvalidData, reason)
+}
+
+// Tells the node why it was refused and closes the connection.
+fn refuse(stream: &mut Stream, peer: SocketAddr, reason: String) -> io::Error {
+    reject_connection(peer, &reason);
+    let rejected = Frame::Rejected {
+        reason: reason.clone(),
+    };
+    write_frame(&mut stream.writer, &rejected, &JsonCodec).ok();
+    stream.socket.shutdown(Shutdown::Both).ok();
+    io::Error::new(ErrorKind::PermissionDenied, reason)
+}
+
+// Publishes the rejection of the connection with the node.
+fn reject_connection(peer: SocketAddr, reason: &str) {
+    warn!("Remote: Rejected connection with {}: {}", peer, reason);
+    system::current()
+        .events()
+        .publish(SystemEvent::ConnectionRejected {
+            peer,
+            reason: reason.to_string(),
+        });
+}
+
+// Returns the frame answering the question with the given id.
+fn reply_frame(id: u64, msg: Msg) -> Frame {
+    let msg = match msg.downcast::<WireMessage>() {
+        Ok(message) => return Frame::Reply { id, message },
+        Err(msg) => msg,
+    };
+
+    match msg.downcast::<ErrorReply>() {
+        Ok(ErrorReply(error)) => Frame::Error { id, error },
+        Err(msg) => Frame::Error {
+            id,
+            error: format!("the answer isn't a WireMessage but a {}.", msg.type_name()),
+        },
+    }
+}
+
+impl Frame {
+    fn to_envelope(&self) -> io::Result<WireEnvelope> {
+        let envelope = |kind: &str| WireEnvelope {
+            kind: kind.to_string(),
+            ..WireEnvelope::default()
+        };
+        let envelope = match self {
+            Frame::Tell {
+                distributor,
+                message,
+            } => WireEnvelope {
+                target: distributor.clone(),
+                message: Some(message.clone()),
+                ..envelope("tell")
+            },
+            Frame::TellEveryone {
+                distributor,
+                message,
+            } => WireEnvelope {
+                target: distributor.clone(),
+                message: Some(message.clone()),
+                ..envelope("tell_everyone")
+            },
+            Frame::Ask {
+                id,
+                distributor,
+                message,
+            } => WireEnvelope {
+                id: *id,
+                target: distributor.clone(),
+                message: Some(message.clone()),
+                ..envelope("ask")
+            },
+            Frame::Reply { id, message } => WireEnvelope {
+                id: *id,
+                message: Some(message.clone()),
+                ..envelope("reply")
+            },
+            Frame::Error { id, error } => WireEnvelope {
+                id: *id,
+                text: error.clone(),
+                ..envelope("error")
+            },
+            Frame::Dropped { id } => WireEnvelope {
+                id: *id,
+                ..envelope("dropped")
+            },
+            Frame::Hello { codec, challenge } => WireEnvelope {
+                text: codec.clone(),
+                data: challenge.clone(),
+                ..envelope("hello")
+            },
+            Frame::Challenge { challenge } => WireEnvelope {
+                data: challenge.clone(),
+                ..envelope("challenge")
+            },
+            Frame::Proof { credentials } => WireEnvelope {
+                data: credentials.clone(),
+                ..envelope("proof")
+            },
+            Frame::Welcome => envelope("welcome"),
+            Frame::Rejected { reason } => WireEnvelope {
+                text: reason.clone(),
+                ..envelope("rejected")
+            },
+            Frame::Shard {
+                sharding,
+                entity,
+                message,
+            } => WireEnvelope {
+                target: sharding.clone(),
+                entity: entity.clone(),
+                message: Some(message.clone()),
+                ..envelope("shard")
+            },
+            // The state of the members is a Rust structure, which is
+            // always serialized as JSON.
+            Frame::Gossip { members } => WireEnvelope {
+                data: serde_json::to_vec(members)?,
+                ..envelope("gossip")
+            },
+        };
+        Ok(envelope)
+    }
+
+    fn from_envelope(envelope: WireEnvelope) -> io::Result<Self> {
+        let message = || {
+            envelope.message.clone().ok_or_else(|| {
+                let err = format!("{} frame without message", envelope.kind);
+                io::Error::new(ErrorKind::InvalidData, err)
+            })
+        };
+        let frame = match envelope.kind.as_str() {
+            "tell" => Frame::Tell {
+                message: message()?,
+                distributor: envelope.target,
+            },
+            "tell_everyone" => Frame::TellEveryone {
+                message: message()?,
+                distributor: envelope.target,
+            },
+            "ask" => Frame::Ask {
+                message: message()?,
+                id: envelope.id,
+                distributor: envelope.target,
+            },
+            "reply" => Frame::Reply {
+                message: message()?,
+                id: envelope.id,
+            },
+            "error" => Frame::Error {
+                id: envelope.id,
+                error: envelope.text,
+            },
+            "dropped" => Frame::Dropped { id: envelope.id },
+            "hello" => Frame::Hello {
+                codec: envelope.text,
+                challenge: envelope.data,
+            },
+            "challenge" => Frame::Challenge {
+                challenge: envelope.data,
+            },
+            "proof" => Frame::Proof {
+                credentials: envelope.data,
+            },
+            "welcome" => Frame::Welcome,
+            "rejected" => Frame::Rejected {
+                reason: envelope.text,
+            },
+            "shard" => Frame::Shard {
+                message: message()?,
+                sharding: envelope.target,
+                entity: envelope.entity,
+            },
+            "gossip" => Frame::Gossip {
+                members: serde_json::from_slice(&envelope.data)?,
+            },
+            kind => {
+                let err = format!("unknown frame kind {}", kind);
+                return Err(io::Error::new(ErrorKind::InvalidData, err));
+            }
+        };
+        Ok(frame)
+    }
+}
+
+pub(crate) fn write_frame(
+    writer: &mut impl Write,
+    frame: &Frame,
+    codec: &dyn WireCodec,
+) -> io::Result<()> {
+    let frame = codec.encode(&frame.to_envelope()?)?;
+    if frame.len() > MAX_FRAME {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "The frame is too large.",
+        ));
+    }
+
+    writer.write_all(&(frame.len() as u32).to_be_bytes())?;
+    writer.write_all(&frame)?;
+    writer.flush()
+}
+
+// Reads the next frame, or returns `None` if the stream ended.
+pub(crate) fn read_frame(
+    reader: &mut impl Read,
+    codec: &dyn WireCodec,
+) -> io::Result<Option<Frame>> {
+    let mut len = [0; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => (),
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err),
+    }
+
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "The frame is too large.",
+        ));
+    }
+
+    let mut frame = vec![0; len];
+    reader.read_exact(&mut frame)?;
+    Frame::from_envelope(codec.decode(&frame)?).map(Some)
+}
+
+// Returns the configuration of the connections opened or accepted
+// from now on.
+fn config() -> Arc<RemoteConfig> {
+    // FIXME: panics
+    CONFIG.read().unwrap().clone()
+}
+
+pub(crate) fn configure(config: RemoteConfig) {
+    if config.authenticator.is_some() && !config.encrypted() {
+        warn!(
+            "Remote: The connections are authenticated but not encrypted: the messages \
+             can be read and altered on the network. Use RemoteConfig::with_tls to \
+             encrypt them."
+        );
+    }
+
+    // FIXME: panics
+    *CONFIG.write().unwrap() = Arc::new(config);
+}
+
+// Registers the node under the given name, connecting to it the
+// first time a frame is sent to it.
+pub(crate) fn register(name: &str, addr: SocketAddr) {
+    NODES.register(name, addr);
+}
+
+// Sends the frame to the node registered under the given name,
+// connecting to it if needed.
+pub(crate) fn send(name: &str, frame: &Frame) -> io::Result<()> {
+    with_connection(name, |connection| connection.send(frame))
+}
+
+// Asks the question to a recipient of the distributor of the node
+// registered under the given name, connecting to it if needed.
+pub(crate) fn ask(name: &str, distributor: &str, question: WireMessage) -> io::Result<Answer> {
+    with_connection(name, |connection| connection.ask(distributor, question))
+}
+
+// Returns the moving average of how long the questions asked to the
+// node registered under the given name took to be answered, if one
+// was since it was connected to.
+pub(crate) fn latency(name: &str) -> Option<Duration> {
+    NODES.get(name)?.latency()
+}
+
+fn with_connection<T>(name: &str, f: impl FnOnce(&Connection) -> io::Result<T>) -> io::Result<T> {
+    let node = NODES
+        .get(name)
+        .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "Unknown node."))?;
+    let connection = node.connection()?;
+    f(&connection).map_err(|err| {
+        connection.close();
+        err
+    })
+}
+
+// Connects to the node and registers it under the given name,
+// replacing the node previously registered under it if any.
+pub(crate) fn connect(name: String, addr: SocketAddr) -> io::Result<()> {
+    let node = Arc::new(Node::new(name, addr));
+    node.connection()?;
+    NODES.insert(node);
+    Ok(())
+}
+
+// Binds the listener and starts the children group accepting the
+// connections of the other nodes on it.
+pub(crate) fn listen(addr: SocketAddr) -> io::Result<ChildrenRef> {
+    let listener = TcpListener::bind(addr)?;
+    listener.set_nonblocking(true)?;
+    debug!("Remote: Listening on {}.", listener.local_addr()?);
+
+    // The listener is kept open across the restarts of the element.
+    let listener = Arc::new(listener);
+    Bastion::children(|children| {
+        children
+            .with_name("bastion-remote")
+            .with_exec(move |_ctx: BastionContext| {
+                let listener = listener.clone();
+                async move {
+                    loop {
+                        match listener.accept() {
+                            Ok((socket, peer)) => {
+                                debug!("Remote: Accepted connection from {}.", peer);
+                                // The handshakes don't delay the next
+                                // connections.
+                                executor::blocking(async move {
+                                    if let Err(err) = Connection::accept(socket, peer, &config()) {
+                                        warn!(
+                                            "Remote: Couldn't start connection with {}: {}",
+                                            peer, err
+                                        );
+                                    }
+                                });
+                            }
+                            Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                                executor::sleep(ACCEPT_INTERVAL).await;
+                            }
+                            Err(err) => {
+                                warn!("Remote: Couldn't accept connection: {}", err);
+                                return Err(());
+                            }
+                        }
+                    }
+                }
+            })
+    })
+    .map_err(|_| io::Error::new(ErrorKind::Other, "Couldn't create the children group."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::TokenAuthenticator;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_frames_round_trip() {
+        frames_round_trip(&JsonCodec);
+        frames_round_trip(&BincodeCodec);
+    }
+
+    fn frames_round_trip(codec: &dyn WireCodec) {
+        let mut buf = Vec::new();
+        let message = WireMessage::pack(&"ping".to_string()).unwrap();
+        write_frame(
+            &mut buf,
+            &Frame::Ask {
+                id: 7,
+                distributor: "workers".to_string(),
+                message: message.clone(),
+            },
+            codec,
+        )
+        .unwrap();
+        write_frame(&mut buf, &Frame::Dropped { id: 7 }, codec).unwrap();
+
+        let mut reader = Cursor::new(buf);
+        match read_frame(&mut reader, codec).unwrap() {
+            Some(Frame::Ask {
+                id,
+                distributor,
+                message: read,
+            }) => {
+                assert_eq!(id, 7);
+                assert_eq!(distributor, "workers");
+                assert_eq!(read, message);
+            }
+            frame => panic!("Unexpected frame: {:?}", frame),
+        }
+        assert!(matches!(
+            read_frame(&mut reader, codec).unwrap(),
+            Some(Frame::Dropped { id: 7 })
+        ));
+        assert!(read_frame(&mut reader, codec).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_oversized_frames_are_rejected() {
+        let mut reader = Cursor::new((MAX_FRAME as u32 + 1).to_be_bytes().to_vec());
+        let err = read_frame(&mut reader, &JsonCodec).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_untrusted_nodes_are_rejected() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepting = std::thread::spawn(move || {
+            let (socket, peer) = listener.accept().unwrap();
+            let config = RemoteConfig::new().with_authenticator(TokenAuthenticator::new("a"));
+            Connection::accept(socket, peer, &config).map(|_| ())
+        });
+
+        let config = RemoteConfig::new().with_authenticator(TokenAuthenticator::new("b"));
+        let opened = Connection::open(addr, &config, Arc::new(JsonCodec), Weak::new()).map(|_| ());
+        assert_eq!(opened.unwrap_err().kind(), ErrorKind::PermissionDenied);
+        let accepted = accepting.join().unwrap();
+        assert_eq!(accepted.unwrap_err().kind(), ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn test_handshakes_dont_send_the_token() {
+        // Reads a frame, returning it along with the bytes it was
+        // sent as.
+        fn read_sent(socket: &mut TcpStream) -> (Frame, Vec<u8>) {
+            let mut sent = vec![0; 4];
+            socket.read_exact(&mut sent).unwrap();
+            let len = u32::from_be_bytes([sent[0], sent[1], sent[2], sent[3]]) as usize;
+            sent.resize(4 + len, 0);
+            socket.read_exact(&mut sent[4..]).unwrap();
+            let frame = read_frame(&mut Cursor::new(&sent), &JsonCodec).unwrap();
+            (frame.unwrap(), sent)
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        // Plays the node accepting the connection, keeping what the
+        // other node sends.
+        let accepting = std::thread::spawn(move || {
+            let (mut socket, peer) = listener.accept().unwrap();
+            let authenticator = TokenAuthenticator::new("secret-token");
+            let (hello, sent_hello) = read_sent(&mut socket);
+            let challenge = match hello {
+                Frame::Hello { challenge, .. } => challenge,
+                frame => panic!("Unexpected frame: {:?}", frame),
+            };
+            let proof = Frame::Proof {
+                credentials: authenticator
+                    .credentials(&signed_challenge(&challenge, ACCEPTING_NODE)),
+            };
+            let nonce = b"nonce".to_vec();
+            let challenge = Frame::Challenge {
+                challenge: nonce.clone(),
+            };
+            write_frame(&mut socket, &challenge, &JsonCodec).unwrap();
+            write_frame(&mut socket, &proof, &JsonCodec).unwrap();
+
+            let (proof, sent_proof) = read_sent(&mut socket);
+            let credentials = match proof {
+                Frame::Proof { credentials } => credentials,
+                frame => panic!("Unexpected frame: {:?}", frame),
+            };
+            let peer = Peer::new(
+                peer,
+                Vec::new(),
+                signed_challenge(&nonce, OPENING_NODE),
+                credentials,
+            );
+            assert!(authenticator.authenticate(&peer).is_ok());
+            let rejected = Frame::Rejected {
+                reason: "done".to_string(),
+            };
+            write_frame(&mut socket, &rejected, &JsonCodec).unwrap();
+            vec![sent_hello, sent_proof]
+        });
+
+        let config =
+            RemoteConfig::new().with_authenticator(TokenAuthenticator::new("secret-token"));
+        let opened = Connection::open(addr, &config, Arc::new(JsonCodec), Weak::new()).map(|_| ());
+        assert_eq!(opened.unwrap_err().kind(), ErrorKind::PermissionDenied);
+        for sent in accepting.join().unwrap() {
+            assert!(!sent.windows(12).any(|window| window == b"secret-token"));
+        }
+    }
+
+    #[test]
+    fn test_unknown_codecs_are_rejected() {
+        #[derive(Debug)]
+        struct Unknown;
+
+        impl WireCodec for Unknown {
+            fn name(&self) -> &str {
+                "unknown"
+            }
+
+            fn encode(&self, envelope: &WireEnvelope) -> io::Result<Vec<u8>> {
+                JsonCodec.encode(envelope)
+            }
+
+            fn decode(&self, bytes: &[u8]) -> io::Result<WireEnvelope> {
+                JsonCodec.decode(bytes)
+            }
+        }
+
+        let config = RemoteConfig::new();
+        assert!(config.accepted_codec("bincode").is_some());
+        assert!(config.accepted_codec("unknown").is_none());
+        let config = config.with_node_codec("other", Unknown);
+        assert_eq!(config.codec_for("other").name(), "unknown");
+        assert_eq!(config.codec_for("another").name(), "json");
+        assert!(config.accepted_codec("unknown").is_some());
+    }
+
+    #[test]
+    fn test_reconnect_delays() {
+        assert_eq!(ReconnectPolicy::OnDemand.delay(0), None);
+        let policy = ReconnectPolicy::Backoff {
+            initial: Duration::from_millis(100),
+            multiplier: 2.0,
+            max: Duration::from_secs(1),
+        };
+        assert_eq!(policy.delay(0), Some(Duration::from_millis(100)));
+        assert_eq!(policy.delay(2), Some(Duration::from_millis(400)));
+        assert_eq!(policy.delay(10), Some(Duration::from_secs(1)));
+        assert_eq!(policy.delay(u32::MAX), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_reply_frames() {
+        let message = WireMessage::pack(&42_u32).unwrap();
+        assert!(matches!(
+            reply_frame(1, Msg::tell(message)),
+            Frame::Reply { id: 1, .. }
+        ));
+        assert!(matches!(
+            reply_frame(2, Msg::tell(ErrorReply("failed".to_string()))),
+            Frame::Error { id: 2, error } if error == "failed"
+        ));
+        assert!(matches!(
+            reply_frame(3, Msg::tell(42_u32)),
+            Frame::Error { id: 3, .. }
+        ));
+    }
+}
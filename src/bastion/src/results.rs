@@ -0,0 +1,94 @@
+//!
+//! The values the elements of a children group exited with, set
+//! using [`BastionContext::stop_with`] and awaited using
+//! [`ChildrenRef::join_all`].
+//!
+//! [`BastionContext::stop_with`]: crate::context::BastionContext::stop_with
+//! [`ChildrenRef::join_all`]: crate::children_ref::ChildrenRef::join_all
+use crate::context::BastionId;
+use fxhash::FxHashMap;
+use std::any::Any;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::{Arc, Mutex};
+use std::task::Waker;
+use tracing::trace;
+
+pub(crate) type ChildResult = Arc<dyn Any + Send + Sync>;
+
+#[derive(Default)]
+// The elements of a children group that completed, with the value
+// they exited with (if any).
+pub(crate) struct GroupResults {
+    completed: Mutex<FxHashMap<BastionId, Option<ChildResult>>>,
+    // The wakers of the futures waiting for elements to complete.
+    waiting: Mutex<Vec<Waker>>,
+}
+
+impl GroupResults {
+    pub(crate) fn complete(&self, id: BastionId, result: Option<ChildResult>) {
+        trace!("GroupResults: Element {} completed.", id);
+        self.completed.lock().unwrap().insert(id, result);
+
+        let waiting: Vec<_> = self.waiting.lock().unwrap().drain(..).collect();
+        for waker in waiting {
+            waker.wake();
+        }
+    }
+
+    // Returns the values the given elements exited with, or `None`
+    // if some of them didn't complete yet.
+    pub(crate) fn collect<R>(&self, ids: &[&BastionId]) -> Option<Vec<Option<R>>>
+    where
+        R: Clone + Send + Sync + 'static,
+    {
+        let completed = self.completed.lock().unwrap();
+        ids.iter()
+            .map(|id| {
+                completed.get(*id).map(|result| {
+                    result
+                        .as_ref()
+                        .and_then(|result| result.downcast_ref::<R>())
+                        .cloned()
+                })
+            })
+            .collect()
+    }
+
+    // Wakes the future up once an element completes.
+    pub(crate) fn park(&self, waker: &Waker) {
+        let mut waiting = self.waiting.lock().unwrap();
+        if !waiting.iter().any(|parked| parked.will_wake(waker)) {
+            waiting.push(waker.clone());
+        }
+    }
+}
+
+impl Debug for GroupResults {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("GroupResults")
+            .field("completed", &self.completed.lock().unwrap().len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_results_wait_for_every_element() {
+        let results = GroupResults::default();
+        let first = BastionId::new();
+        let second = BastionId::new();
+
+        results.complete(first.clone(), Some(Arc::new(42_u32)));
+        assert!(results.collect::<u32>(&[&first, &second]).is_none());
+
+        results.complete(second.clone(), None);
+        assert_eq!(
+            results.collect::<u32>(&[&first, &second]),
+            Some(vec![Some(42), None])
+        );
+        assert_eq!(results.collect::<String>(&[&first]), Some(vec![None]));
+    }
+}
@@ -0,0 +1,527 @@
+//!
+//! The authentication of the nodes connecting to each other using the
+//! remote transport, configured using [`RemoteConfig`].
+//!
+//! When a node opens a connection to another node, both nodes send
+//! a random challenge to each other, which the other node answers
+//! with the credentials returned by its [`Authenticator`]. Each node
+//! checks the answer it receives before any message is sent, starting
+//! with the node which accepted the connection. A node whose
+//! credentials are refused isn't allowed to send messages, and each
+//! message it sends to a distributor must then be authorized by the
+//! authenticator of the receiving node.
+//!
+//! The answers are bound to the challenge and to the side of the
+//! connection of the node answering it, so that an answer can't be
+//! replayed on another connection or sent back to the node which
+//! computed it. The secrets of the nodes (e.g. the token of a
+//! [`TokenAuthenticator`]) are never sent.
+//!
+//! With the `tls` feature, the connections can also be encrypted
+//! using mutual TLS (see [`TlsConfig`]), in which case each node
+//! must present a certificate signed by one of the trusted
+//! certificate authorities. Without it, the messages sent once the
+//! nodes are authenticated can still be read and altered on the
+//! network, which is why a warning is logged when an authenticator is
+//! configured without TLS.
+//!
+//! The connections and messages which are refused are published on
+//! the event bus of the system as [`SystemEvent::ConnectionRejected`]
+//! and [`SystemEvent::MessageRejected`] (see [`Bastion::events`]).
+//!
+//! This module is only available with the `remote` feature.
+//!
+//! [`RemoteConfig`]: crate::remote::RemoteConfig
+//! [`SystemEvent::ConnectionRejected`]: crate::events::SystemEvent::ConnectionRejected
+//! [`SystemEvent::MessageRejected`]: crate::events::SystemEvent::MessageRejected
+//! [`Bastion::events`]: crate::Bastion::events
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::fmt::{self, Debug, Formatter};
+#[cfg(feature = "tls")]
+use std::fs::File;
+#[cfg(feature = "tls")]
+use std::io::{self, BufReader, ErrorKind, Read, Write};
+use std::net::SocketAddr;
+#[cfg(feature = "tls")]
+use std::net::TcpStream;
+#[cfg(feature = "tls")]
+use std::path::Path;
+#[cfg(feature = "tls")]
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "tls")]
+// How many bytes are read from the socket of a connection at once.
+const TLS_BUFFER: usize = 16 * 1024;
+
+/// Authenticates the nodes this node connects to, or which connect
+/// to it, and authorizes the messages they send, when set using
+/// [`RemoteConfig::with_authenticator`].
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// #
+/// #[derive(Debug)]
+/// struct InternalNetwork;
+///
+/// impl Authenticator for InternalNetwork {
+///     fn authenticate(&self, peer: &Peer) -> Result<(), String> {
+///         match peer.addr().ip().to_string().starts_with("10.") {
+///             true => Ok(()),
+///             false => Err(format!("{} isn't internal", peer.addr())),
+///         }
+///     }
+///
+///     fn authorize(&self, _peer: &Peer, distributor: &str) -> bool {
+///         distributor != "admin"
+///     }
+/// }
+///
+/// let config = RemoteConfig::new().with_authenticator(InternalNetwork);
+/// ```
+///
+/// [`RemoteConfig::with_authenticator`]: crate::remote::RemoteConfig::with_authenticator
+pub trait Authenticator: Send + Sync + Debug + 'static {
+    /// Returns the credentials of this node answering the challenge
+    /// sent by a node it connects to or which connects to it.
+    /// Defaults to none.
+    ///
+    /// The credentials are sent over the connection, so they must
+    /// prove the identity of this node without revealing its
+    /// secrets.
+    ///
+    /// # Arguments
+    ///
+    /// * `challenge` - The challenge to answer.
+    fn credentials(&self, challenge: &[u8]) -> Vec<u8> {
+        let _ = challenge;
+        Vec::new()
+    }
+
+    /// Checks the credentials and certificates of a node, returning
+    /// why it isn't trusted if it isn't. The credentials answer the
+    /// challenge returned by [`Peer::challenge`].
+    ///
+    /// # Arguments
+    ///
+    /// * `peer` - The node to authenticate.
+    fn authenticate(&self, peer: &Peer) -> Result<(), String>;
+
+    /// Returns whether the authenticated node is allowed to send
+    /// messages to the distributor with the given name. Defaults to
+    /// allowing every distributor.
+    ///
+    /// # Arguments
+    ///
+    /// * `peer` - The node sending the message.
+    /// * `distributor` - The name of the distributor.
+    fn authorize(&self, peer: &Peer, distributor: &str) -> bool {
+        let _ = (peer, distributor);
+        true
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A node connected to this node, as given to an [`Authenticator`].
+pub struct Peer {
+    addr: SocketAddr,
+    certificates: Vec<Vec<u8>>,
+    challenge: Vec<u8>,
+    credentials: Vec<u8>,
+}
+
+#[derive(Clone)]
+/// An [`Authenticator`] trusting the nodes which share the same
+/// token with this node.
+///
+/// The nodes answer the challenges they receive with their HMAC-SHA256
+/// using the token as the key, so the token itself is never sent.
+pub struct TokenAuthenticator {
+    token: Vec<u8>,
+}
+
+#[cfg(feature = "tls")]
+#[derive(Clone)]
+/// The certificates used to encrypt the connections between the
+/// nodes using mutual TLS, set using [`RemoteConfig::with_tls`].
+///
+/// Each node presents its certificate to the nodes it connects to,
+/// or which connect to it, and only trusts the nodes presenting a
+/// certificate signed by one of the given certificate authorities.
+///
+/// This type is only available with the `tls` feature.
+///
+/// # Example
+///
+/// ```no_run
+/// # use bastion::prelude::*;
+/// #
+/// let tls = TlsConfig::from_pem_files("node.pem", "node.key", "ca.pem")
+///     .expect("Couldn't load the certificates.")
+///     .with_server_name("bastion.internal");
+/// let config = RemoteConfig::new().with_tls(tls);
+/// ```
+///
+/// [`RemoteConfig::with_tls`]: crate::remote::RemoteConfig::with_tls
+pub struct TlsConfig {
+    client: Arc<rustls::ClientConfig>,
+    server: Arc<rustls::ServerConfig>,
+    server_name: Option<String>,
+}
+
+#[cfg(feature = "tls")]
+// The halves of a connection encrypted using TLS, sharing its
+// session.
+pub(crate) struct TlsReader {
+    session: Arc<Mutex<rustls::Connection>>,
+    socket: TcpStream,
+    // The bytes read from the socket, and how many of them were
+    // given to the session.
+    received: Vec<u8>,
+    consumed: usize,
+}
+
+#[cfg(feature = "tls")]
+pub(crate) struct TlsWriter {
+    session: Arc<Mutex<rustls::Connection>>,
+    socket: TcpStream,
+}
+
+impl Peer {
+    pub(crate) fn new(
+        addr: SocketAddr,
+        certificates: Vec<Vec<u8>>,
+        challenge: Vec<u8>,
+        credentials: Vec<u8>,
+    ) -> Self {
+        Peer {
+            addr,
+            certificates,
+            challenge,
+            credentials,
+        }
+    }
+
+    /// Returns the address of the node.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns the chain of certificates presented by the node, in
+    /// DER format and starting with its own, if the connection is
+    /// encrypted using TLS.
+    pub fn certificates(&self) -> &[Vec<u8>] {
+        &self.certificates
+    }
+
+    /// Returns the challenge this node sent to the node, which its
+    /// credentials answer.
+    pub fn challenge(&self) -> &[u8] {
+        &self.challenge
+    }
+
+    /// Returns the credentials sent by the node, answering its
+    /// challenge (see [`Authenticator::credentials`]).
+    pub fn credentials(&self) -> &[u8] {
+        &self.credentials
+    }
+}
+
+impl TokenAuthenticator {
+    /// Creates an authenticator answering the challenges using the
+    /// token, and only trusting the nodes whose answers prove they
+    /// know the same one.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The token shared by the nodes.
+    pub fn new(token: impl Into<Vec<u8>>) -> Self {
+        TokenAuthenticator {
+            token: token.into(),
+        }
+    }
+}
+
+impl Authenticator for TokenAuthenticator {
+    fn credentials(&self, challenge: &[u8]) -> Vec<u8> {
+        self.mac(challenge).finalize().into_bytes().to_vec()
+    }
+
+    fn authenticate(&self, peer: &Peer) -> Result<(), String> {
+        // The comparison takes the same time however much of the
+        // answer is right.
+        self.mac(peer.challenge())
+            .verify_slice(peer.credentials())
+            .map_err(|_| "invalid token".to_string())
+    }
+}
+
+impl TokenAuthenticator {
+    fn mac(&self, challenge: &[u8]) -> Hmac<Sha256> {
+        // HMAC accepts keys of any length.
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.token).unwrap();
+        mac.update(challenge);
+        mac
+    }
+}
+
+impl Debug for TokenAuthenticator {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        // The token isn't logged.
+        fmt.debug_struct("TokenAuthenticator").finish()
+    }
+}
+
+#[cfg(feature = "tls")]
+impl TlsConfig {
+    /// Loads the certificates from PEM-encoded buffers.
+    ///
+    /// This method returns an error if the certificates or the key
+    /// couldn't be parsed, or if they don't match.
+    ///
+    /// # Arguments
+    ///
+    /// * `certificates` - The chain of certificates of this node,
+    ///     starting with its own.
+    /// * `key` - The private key of the certificate of this node.
+    /// * `ca` - The certificates of the trusted certificate
+    ///     authorities.
+    pub fn from_pem(certificates: &[u8], key: &[u8], ca: &[u8]) -> io::Result<Self> {
+        let mut roots = rustls::RootCertStore::empty();
+        for certificate in rustls_pemfile::certs(&mut &*ca)? {
+            roots
+                .add(&rustls::Certificate(certificate))
+                .map_err(invalid)?;
+        }
+
+        let certificates: Vec<_> = rustls_pemfile::certs(&mut &*certificates)?
+            .into_iter()
+            .map(rustls::Certificate)
+            .collect();
+        let key = rustls_pemfile::read_all(&mut &*key)?
+            .into_iter()
+            .find_map(|item| match item {
+                rustls_pemfile::Item::PKCS8Key(key)
+                | rustls_pemfile::Item::RSAKey(key)
+                | rustls_pemfile::Item::ECKey(key) => Some(rustls::PrivateKey(key)),
+                _ => None,
+            })
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "No private key found."))?;
+
+        let verifier = rustls::server::AllowAnyAuthenticatedClient::new(roots.clone());
+        let server = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_client_cert_verifier(verifier.boxed())
+            .with_single_cert(certificates.clone(), key.clone())
+            .map_err(invalid)?;
+        let client = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_client_auth_cert(certificates, key)
+            .map_err(invalid)?;
+
+        Ok(TlsConfig {
+            client: Arc::new(client),
+            server: Arc::new(server),
+            server_name: None,
+        })
+    }
+
+    /// Loads the certificates from PEM-encoded files (see
+    /// [`TlsConfig::from_pem`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `certificates` - The path of the chain of certificates of
+    ///     this node.
+    /// * `key` - The path of the private key of the certificate of
+    ///     this node.
+    /// * `ca` - The path of the certificates of the trusted
+    ///     certificate authorities.
+    pub fn from_pem_files(
+        certificates: impl AsRef<Path>,
+        key: impl AsRef<Path>,
+        ca: impl AsRef<Path>,
+    ) -> io::Result<Self> {
+        let read = |path: &Path| -> io::Result<Vec<u8>> {
+            let mut buf = Vec::new();
+            BufReader::new(File::open(path)?).read_to_end(&mut buf)?;
+            Ok(buf)
+        };
+        Self::from_pem(
+            &read(certificates.as_ref())?,
+            &read(key.as_ref())?,
+            &read(ca.as_ref())?,
+        )
+    }
+
+    /// Sets the name the certificates of the nodes this node
+    /// connects to must be valid for. By default, they must be
+    /// valid for the IP address the node was connected to at.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The DNS name of the certificates of the nodes.
+    pub fn with_server_name(mut self, name: impl Into<String>) -> Self {
+        self.server_name = Some(name.into());
+        self
+    }
+
+    // Performs the TLS handshake over the socket, returning the
+    // halves of the encrypted connection and the certificates
+    // presented by the node. `server` is the address of the node if
+    // this node opened the connection.
+    pub(crate) fn handshake(
+        &self,
+        socket: &TcpStream,
+        server: Option<SocketAddr>,
+    ) -> io::Result<(TlsReader, TlsWriter, Vec<Vec<u8>>)> {
+        let mut session: rustls::Connection = match server {
+            Some(addr) => {
+                let name = match &self.server_name {
+                    Some(name) => rustls::ServerName::try_from(name.as_str()).map_err(invalid)?,
+                    None => rustls::ServerName::IpAddress(addr.ip()),
+                };
+                rustls::ClientConnection::new(self.client.clone(), name)
+                    .map_err(invalid)?
+                    .into()
+            }
+            None => rustls::ServerConnection::new(self.server.clone())
+                .map_err(invalid)?
+                .into(),
+        };
+
+        let mut socket = socket.try_clone()?;
+        while session.is_handshaking() {
+            session.complete_io(&mut socket)?;
+        }
+
+        let certificates = session
+            .peer_certificates()
+            .map(|chain| chain.iter().map(|cert| cert.0.clone()).collect())
+            .unwrap_or_default();
+        let session = Arc::new(Mutex::new(session));
+        let reader = TlsReader {
+            session: session.clone(),
+            socket: socket.try_clone()?,
+            received: Vec::new(),
+            consumed: 0,
+        };
+        let writer = TlsWriter { session, socket };
+        Ok((reader, writer, certificates))
+    }
+}
+
+#[cfg(feature = "tls")]
+impl Debug for TlsConfig {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("TlsConfig")
+            .field("server_name", &self.server_name)
+            .finish()
+    }
+}
+
+#[cfg(feature = "tls")]
+impl Read for TlsReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            // FIXME: panics
+            let mut session = self.session.lock().unwrap();
+            match session.reader().read(buf) {
+                Ok(read) => return Ok(read),
+                Err(err) if err.kind() == ErrorKind::WouldBlock => (),
+                Err(err) => return Err(err),
+            }
+
+            if self.consumed < self.received.len() {
+                let mut received = &self.received[self.consumed..];
+                self.consumed += session.read_tls(&mut received)?;
+                session.process_new_packets().map_err(invalid)?;
+                // Sends what the session has to (e.g. an alert).
+                while session.wants_write() {
+                    session.write_tls(&mut self.socket)?;
+                }
+                continue;
+            }
+
+            // The socket is read without locking the session, which
+            // the writer needs meanwhile.
+            drop(session);
+            self.received.resize(TLS_BUFFER, 0);
+            let read = self.socket.read(&mut self.received)?;
+            self.received.truncate(read);
+            self.consumed = 0;
+            if read == 0 {
+                return Ok(0);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "tls")]
+impl Write for TlsWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // FIXME: panics
+        let mut session = self.session.lock().unwrap();
+        let written = session.writer().write(buf)?;
+        while session.wants_write() {
+            session.write_tls(&mut self.socket)?;
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // FIXME: panics
+        let mut session = self.session.lock().unwrap();
+        session.writer().flush()?;
+        while session.wants_write() {
+            session.write_tls(&mut self.socket)?;
+        }
+        self.socket.flush()
+    }
+}
+
+#[cfg(feature = "tls")]
+fn invalid(err: impl std::error::Error + Send + Sync + 'static) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_authenticator() {
+        let authenticator = TokenAuthenticator::new("secret");
+        let addr = "127.0.0.1:4222".parse().unwrap();
+        let peer = |challenge: &[u8], credentials: Vec<u8>| {
+            Peer::new(addr, Vec::new(), challenge.to_vec(), credentials)
+        };
+
+        let credentials = authenticator.credentials(b"challenge");
+        assert!(authenticator
+            .authenticate(&peer(b"challenge", credentials.clone()))
+            .is_ok());
+        assert!(authenticator.authorize(&peer(b"challenge", credentials.clone()), "workers"));
+        // The answer only holds for the challenge it answers.
+        assert_ne!(authenticator.credentials(b"other"), credentials);
+        assert!(authenticator
+            .authenticate(&peer(b"other", credentials.clone()))
+            .is_err());
+        // The token isn't sent.
+        assert!(!credentials.windows(6).any(|window| window == b"secret"));
+        assert!(authenticator
+            .authenticate(&peer(b"challenge", b"secret".to_vec()))
+            .is_err());
+        assert!(authenticator
+            .authenticate(&peer(b"challenge", Vec::new()))
+            .is_err());
+
+        let other = TokenAuthenticator::new("secreT");
+        assert!(authenticator
+            .authenticate(&peer(b"challenge", other.credentials(b"challenge")))
+            .is_err());
+    }
+}
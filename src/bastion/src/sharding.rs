@@ -0,0 +1,455 @@
+//!
+//! The sharding of entities across the members of the cluster.
+//!
+//! A [`Sharding`] splits the identifiers of its entities into a fixed
+//! number of shards, each owned by one of the reachable members of the
+//! cluster (see [`Cluster`]). Every member computes the same owner
+//! for a shard using rendezvous hashing over their names, so that no
+//! coordination is needed and only the shards of the members joining
+//! or leaving the cluster move.
+//!
+//! A message told to an entity is sent to the member owning its
+//! shard, where the entity is spawned as a children group the first
+//! time it receives a message. When the membership of the cluster
+//! changes, the entities whose shard is now owned by another member
+//! are stopped, and spawned again on that member by their next
+//! message.
+//!
+//! This module is only available with the `remote` feature.
+//!
+//! [`Cluster`]: crate::cluster::Cluster
+use crate::children_ref::ChildrenRef;
+use crate::cluster::{Cluster, MemberStatus};
+use crate::context::BastionContext;
+use crate::dead_letters::{self, DeadLetter, Destination};
+use crate::errors::SendError;
+use crate::executor;
+use crate::remote;
+use crate::wire::WireMessage;
+use crate::Bastion;
+use futures::future::BoxFuture;
+use futures::{Future, FutureExt, StreamExt};
+use fxhash::FxHashMap;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::{Arc, Mutex, RwLock};
+use tracing::{debug, trace};
+
+static SHARDINGS: Lazy<RwLock<FxHashMap<String, Arc<Region>>>> = Lazy::new(Default::default);
+
+type EntityInit =
+    Arc<dyn Fn(String, BastionContext) -> BoxFuture<'static, Result<(), ()>> + Send + Sync>;
+
+#[derive(Debug, Clone)]
+/// Entities identified by a string and sharded across the members
+/// of the cluster, which receive the messages told to them wherever
+/// they live.
+///
+/// Each member using the entities creates the sharding with the same
+/// name and number of shards, and the members which can host the
+/// entities set how to spawn them using [`Sharding::with_entity`].
+///
+/// The entities receive the messages as [`WireMessage`]s, which
+/// they can match using [`MessageHandler::on_wire`].
+///
+/// # Example
+///
+/// ```no_run
+/// # use bastion::prelude::*;
+/// # use serde::{Deserialize, Serialize};
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// #[derive(Debug, Serialize, Deserialize)]
+/// struct AddItem(String);
+///
+/// # fn run() {
+/// # Bastion::init();
+/// # Bastion::start();
+/// let carts = Sharding::new("carts", 64).with_entity(|cart: String, ctx: BastionContext| {
+///     async move {
+///         let mut items = Vec::new();
+///         loop {
+///             MessageHandler::new(ctx.recv().await?)
+///                 .on_wire(|AddItem(item), _| items.push(item))
+///                 .on_fallback(|_, _| ());
+///             println!("{}: {:?}", cart, items);
+///         }
+///     }
+/// });
+///
+/// carts
+///     .tell("cart-42", AddItem("book".to_string()))
+///     .expect("Couldn't send the message.");
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+///
+/// [`WireMessage`]: crate::wire::WireMessage
+/// [`MessageHandler::on_wire`]: crate::message::MessageHandler::on_wire
+pub struct Sharding {
+    region: Arc<Region>,
+}
+
+// The entities of a sharding hosted by this node.
+struct Region {
+    name: String,
+    shards: u32,
+    init: Mutex<Option<EntityInit>>,
+    entities: Mutex<FxHashMap<String, ChildrenRef>>,
+}
+
+impl Sharding {
+    /// Creates the sharding with the given name, splitting its
+    /// entities into the given number of shards, or returns the one
+    /// already created with this name.
+    ///
+    /// The number of shards should be the same on every member, and
+    /// much larger than the number of members so that the entities
+    /// are evenly spread.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the sharding, shared by the members.
+    /// * `shards` - The number of shards.
+    pub fn new(name: impl Into<String>, shards: u32) -> Self {
+        let name = name.into();
+        // FIXME: panics
+        let mut shardings = SHARDINGS.write().unwrap();
+        if let Some(region) = shardings.get(&name) {
+            return Sharding {
+                region: region.clone(),
+            };
+        }
+
+        let region = Arc::new(Region {
+            name: name.clone(),
+            shards: shards.max(1),
+            init: Mutex::new(None),
+            entities: Mutex::new(FxHashMap::default()),
+        });
+        shardings.insert(name, region.clone());
+
+        // The entities move when the membership of the cluster
+        // changes.
+        let mut events = Cluster::get().events();
+        let rebalanced = region.clone();
+        executor::spawn(async move {
+            while events.next().await.is_some() {
+                rebalanced.rebalance();
+            }
+        });
+
+        Sharding { region }
+    }
+
+    /// Sets how this node spawns the entities of the shards it owns:
+    /// each entity is an element of its own children group, running
+    /// the given closure with the entity's identifier.
+    ///
+    /// The members which don't call this method only send messages
+    /// to the entities hosted by the other members.
+    ///
+    /// # Arguments
+    ///
+    /// * `init` - The closure taking the entity's identifier and the
+    ///     [`BastionContext`] of the element, and returning the
+    ///     future run by the element.
+    ///
+    /// [`BastionContext`]: crate::context::BastionContext
+    pub fn with_entity<I, F>(self, init: I) -> Self
+    where
+        I: Fn(String, BastionContext) -> F + Send + Sync + 'static,
+        F: Future<Output = Result<(), ()>> + Send + 'static,
+    {
+        let init: EntityInit = Arc::new(move |entity, ctx| init(entity, ctx).boxed());
+        // FIXME: panics
+        *self.region.init.lock().unwrap() = Some(init);
+        self
+    }
+
+    /// Returns the name of the sharding.
+    pub fn name(&self) -> &str {
+        &self.region.name
+    }
+
+    /// Returns the number of shards of the sharding.
+    pub fn shards(&self) -> u32 {
+        self.region.shards
+    }
+
+    /// Returns the shard of the entity.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The identifier of the entity.
+    pub fn shard_of(&self, entity: &str) -> u32 {
+        self.region.shard_of(entity)
+    }
+
+    /// Returns the name of the member of the cluster owning the
+    /// shard of the entity, or `None` if it is this node.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The identifier of the entity.
+    pub fn owner_of(&self, entity: &str) -> Option<String> {
+        self.region.owner(self.shard_of(entity))
+    }
+
+    /// Returns the identifiers of the entities hosted by this node,
+    /// sorted.
+    pub fn local_entities(&self) -> Vec<String> {
+        // FIXME: panics
+        let mut entities: Vec<_> = self
+            .region
+            .entities
+            .lock()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        entities.sort();
+        entities
+    }
+
+    /// Sends the message to the entity, on the member owning its
+    /// shard, spawning the entity if it isn't running yet. The
+    /// entity receives it as a [`WireMessage`].
+    ///
+    /// This method returns an error if the message couldn't be
+    /// serialized, if the member couldn't be reached or if this
+    /// node owns the shard but doesn't host entities (see
+    /// [`Sharding::with_entity`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `entity` - The identifier of the entity.
+    /// * `message` - The message to send.
+    ///
+    /// [`WireMessage`]: crate::wire::WireMessage
+    pub fn tell<M: Serialize + 'static>(&self, entity: &str, message: M) -> Result<(), SendError> {
+        let message = WireMessage::pack(&message).map_err(|err| SendError::Other(err.into()))?;
+        match self.owner_of(entity) {
+            None => self.region.deliver(entity, message),
+            Some(owner) => {
+                // The entity was hosted here before the shard moved.
+                self.region.stop(entity);
+                trace!(
+                    "Sharding({}): Sending message for {} to {}.",
+                    self.region.name,
+                    entity,
+                    owner
+                );
+                let destination = Destination::Entity {
+                    sharding: self.region.name.clone(),
+                    entity: entity.to_string(),
+                };
+                remote::send(&owner, &destination.frame(message.clone())).map_err(|err| {
+                    dead_letters::record(DeadLetter::sent(owner, destination, message, &err));
+                    SendError::Other(err.into())
+                })
+            }
+        }
+    }
+}
+
+impl Region {
+    fn shard_of(&self, entity: &str) -> u32 {
+        (fxhash::hash64(entity) % u64::from(self.shards)) as u32
+    }
+
+    // Returns the reachable member with the highest score for the
+    // shard, or `None` if it is this node (or if it didn't join the
+    // cluster).
+    fn owner(&self, shard: u32) -> Option<String> {
+        let cluster = Cluster::get();
+        let local = cluster.node()?;
+        let owner = cluster
+            .members()
+            .into_iter()
+            .filter(|member| member.status() == MemberStatus::Up)
+            .map(|member| member.name().to_string())
+            .max_by_key(|member| score(member, shard))?;
+
+        if owner == local {
+            None
+        } else {
+            Some(owner)
+        }
+    }
+
+    // Sends the message to the entity hosted by this node, spawning
+    // it if needed.
+    fn deliver(&self, entity: &str, message: WireMessage) -> Result<(), SendError> {
+        // FIXME: panics
+        let mut entities = self.entities.lock().unwrap();
+        let message = match entities.get(entity) {
+            Some(children) => match children.broadcast(message) {
+                Ok(()) => return Ok(()),
+                // The entity stopped, and is spawned again.
+                Err(message) => message,
+            },
+            None => message,
+        };
+
+        let children = self.spawn(entity)?;
+        let sent = children.broadcast(message);
+        entities.insert(entity.to_string(), children);
+        sent.map_err(|_| SendError::Other(anyhow::anyhow!("The entity {} stopped.", entity)))
+    }
+
+    // Sends the message received from another member to the entity,
+    // unless its shard is owned by `owner` rather than this node. The
+    // message is then refused instead of being sent on, so that it
+    // isn't sent back and forth while the members disagree about the
+    // membership of the cluster.
+    fn receive(
+        &self,
+        owner: Option<String>,
+        entity: &str,
+        message: WireMessage,
+    ) -> Result<(), SendError> {
+        match owner {
+            None => self.deliver(entity, message),
+            Some(owner) => {
+                debug!(
+                    "Sharding({}): Refusing message for {}, owned by {}.",
+                    self.name, entity, owner
+                );
+                Err(SendError::Other(anyhow::anyhow!(
+                    "This node doesn't own the shard of {}, owned by {}.",
+                    entity,
+                    owner
+                )))
+            }
+        }
+    }
+
+    fn spawn(&self, entity: &str) -> Result<ChildrenRef, SendError> {
+        // FIXME: panics
+        let init = self.init.lock().unwrap().clone().ok_or_else(|| {
+            SendError::Other(anyhow::anyhow!(
+                "This node doesn't host the entities of {}.",
+                self.name
+            ))
+        })?;
+
+        debug!("Sharding({}): Spawning {}.", self.name, entity);
+        let id = entity.to_string();
+        Bastion::children(|children| {
+            children
+                .with_name(format!("{}/{}", self.name, entity))
+                .with_exec(move |ctx: BastionContext| init(id.clone(), ctx))
+        })
+        .map_err(|_| SendError::Other(anyhow::anyhow!("Couldn't spawn the entity {}.", entity)))
+    }
+
+    fn stop(&self, entity: &str) {
+        // FIXME: panics
+        if let Some(children) = self.entities.lock().unwrap().remove(entity) {
+            debug!("Sharding({}): Stopping {}.", self.name, entity);
+            children.stop().ok();
+        }
+    }
+
+    // Stops the entities whose shard is now owned by another member.
+    fn rebalance(&self) {
+        // FIXME: panics
+        let entities: Vec<_> = self.entities.lock().unwrap().keys().cloned().collect();
+        for entity in entities {
+            if self.owner(self.shard_of(&entity)).is_some() {
+                self.stop(&entity);
+            }
+        }
+    }
+}
+
+impl Debug for Region {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Region")
+            .field("name", &self.name)
+            .field("shards", &self.shards)
+            .finish()
+    }
+}
+
+// The rendezvous hashing score of the member for the shard.
+fn score(member: &str, shard: u32) -> u64 {
+    fxhash::hash64(&(member, shard))
+}
+
+// Sends the message received from another member to the entity,
+// if its shard is owned by this node. The message is kept in the
+// dead letters by the caller otherwise.
+pub(crate) fn deliver(sharding: &str, entity: &str, message: WireMessage) -> Result<(), SendError> {
+    // FIXME: panics
+    let region = SHARDINGS.read().unwrap().get(sharding).cloned();
+    match region {
+        Some(region) => {
+            let owner = region.owner(region.shard_of(entity));
+            region.receive(owner, entity, message)
+        }
+        None => Err(SendError::Other(anyhow::anyhow!("Unknown sharding."))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shards_move_only_with_their_owner() {
+        let members = ["a", "b", "c"];
+        let owner = |members: &[&str], shard| {
+            members
+                .iter()
+                .max_by_key(|member| score(member, shard))
+                .unwrap()
+                .to_string()
+        };
+
+        let mut owned = FxHashMap::<String, u32>::default();
+        for shard in 0..300 {
+            *owned.entry(owner(&members, shard)).or_default() += 1;
+            // Removing a member only moves the shards it owned.
+            let before = owner(&members, shard);
+            if before != "c" {
+                assert_eq!(owner(&members[..2], shard), before);
+            }
+        }
+        // The shards are spread evenly.
+        assert!(owned.values().all(|owned| (50..150).contains(owned)));
+    }
+
+    #[test]
+    fn test_messages_for_shards_owned_elsewhere_are_refused() {
+        let init: EntityInit = Arc::new(
+            |entity: String, _: BastionContext| -> BoxFuture<'static, Result<(), ()>> {
+                panic!("{} was spawned by a node not owning it.", entity)
+            },
+        );
+        let region = Region {
+            name: "refused".to_string(),
+            shards: 16,
+            init: Mutex::new(Some(init)),
+            entities: Mutex::new(FxHashMap::default()),
+        };
+
+        let message = WireMessage::pack(&"message".to_string()).unwrap();
+        let refused = region.receive(Some("b".to_string()), "entity", message);
+        assert!(matches!(refused, Err(SendError::Other(_))));
+        assert!(region.entities.lock().unwrap().is_empty());
+    }
+}
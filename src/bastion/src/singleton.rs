@@ -0,0 +1,304 @@
+//!
+//! Children groups running on a single member of the cluster.
+//!
+//! A [`ClusterSingleton`] is started by every member which can host
+//! it, but only runs on the member leading the group named after the
+//! singleton (see [`Cluster::leadership`]). When the membership of
+//! the cluster changes (e.g. when the member hosting the singleton
+//! becomes unreachable or leaves), the singleton is stopped on the
+//! members which don't lead the group anymore and started on its new
+//! leader.
+//!
+//! Because the membership is gossiped, the members may briefly
+//! disagree on who is elected while it changes: the singleton can
+//! then run on two members or on none for about a gossip interval.
+//! During a network partition, each side of it elects its own host.
+//!
+//! This module is only available with the `remote` feature.
+//!
+//! [`Cluster::leadership`]: crate::cluster::Cluster::leadership
+use crate::children_ref::ChildrenRef;
+use crate::cluster::{Cluster, Role};
+use crate::context::BastionContext;
+use crate::executor;
+use crate::Bastion;
+use futures::future::BoxFuture;
+use futures::{Future, FutureExt, StreamExt};
+use std::fmt::{self, Debug, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, warn};
+
+type SingletonInit =
+    Arc<dyn Fn(BastionContext) -> BoxFuture<'static, Result<(), ()>> + Send + Sync>;
+
+#[derive(Debug, Clone)]
+/// A children group of one element running on a single member of
+/// the cluster, which moves to another member when the one hosting
+/// it becomes unreachable or leaves the cluster.
+///
+/// Each member which can host the singleton starts it with the same
+/// name. When this node didn't join the cluster, the singleton runs
+/// on it.
+///
+/// # Example
+///
+/// ```no_run
+/// # use bastion::prelude::*;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// # Bastion::start();
+/// let config = ClusterConfig::new("node-1", "127.0.0.1:4221".parse().unwrap())
+///     .with_seed("127.0.0.1:4222".parse().unwrap());
+/// Bastion::cluster().join(config).expect("Couldn't join the cluster.");
+///
+/// let scheduler = ClusterSingleton::start("scheduler", |ctx: BastionContext| async move {
+///     loop {
+///         MessageHandler::new(ctx.recv().await?)
+///             .on_tell(|job: &'static str, _| println!("Running {}.", job))
+///             .on_fallback(|_, _| ());
+///     }
+/// });
+/// println!("The scheduler runs on {:?}.", scheduler.host());
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct ClusterSingleton {
+    singleton: Arc<Singleton>,
+}
+
+// The state of a singleton on this node.
+struct Singleton {
+    name: String,
+    // The cluster electing the member hosting the singleton.
+    cluster: Cluster,
+    init: SingletonInit,
+    // The children group running the singleton, if this node hosts
+    // it.
+    running: Mutex<Option<ChildrenRef>>,
+    stopped: AtomicBool,
+}
+
+impl ClusterSingleton {
+    /// Starts the singleton with the given name, which runs the
+    /// given closure on the elected member of the cluster, and is
+    /// started again on another member if this one goes away.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the singleton, shared by the members.
+    /// * `init` - The closure taking the [`BastionContext`] of the
+    ///     element and returning the future it runs.
+    ///
+    /// [`BastionContext`]: crate::context::BastionContext
+    pub fn start<I, F>(name: impl Into<String>, init: I) -> Self
+    where
+        I: Fn(BastionContext) -> F + Send + Sync + 'static,
+        F: Future<Output = Result<(), ()>> + Send + 'static,
+    {
+        let init: SingletonInit = Arc::new(move |ctx| init(ctx).boxed());
+        let singleton = Arc::new(Singleton {
+            name: name.into(),
+            cluster: Cluster::get(),
+            init,
+            running: Mutex::new(None),
+            stopped: AtomicBool::new(false),
+        });
+
+        // The singleton moves when the membership of the cluster
+        // changes.
+        let mut events = singleton.cluster.events();
+        let rebalanced = singleton.clone();
+        executor::spawn(async move {
+            while events.next().await.is_some() {
+                if rebalanced.stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                rebalanced.rebalance();
+            }
+        });
+
+        singleton.rebalance();
+        ClusterSingleton { singleton }
+    }
+
+    /// Returns the name of the singleton.
+    pub fn name(&self) -> &str {
+        &self.singleton.name
+    }
+
+    /// Returns the name of the member elected to host the singleton,
+    /// or `None` if this node didn't join the cluster.
+    pub fn host(&self) -> Option<String> {
+        self.singleton.cluster.leadership(self.name()).leader()
+    }
+
+    /// Returns whether the singleton is running on this node.
+    pub fn is_local(&self) -> bool {
+        // FIXME: panics
+        self.singleton.running.lock().unwrap().is_some()
+    }
+
+    /// Stops the singleton on this node, which won't host it
+    /// anymore. The other members keep electing this node until it
+    /// leaves the cluster.
+    pub fn stop(&self) {
+        self.singleton.stopped.store(true, Ordering::SeqCst);
+        self.singleton.stop();
+    }
+}
+
+impl Singleton {
+    // Starts the singleton if this node is elected to host it (or
+    // didn't join the cluster), and stops it otherwise.
+    fn rebalance(&self) {
+        if self.stopped.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let role = self.cluster.leadership(self.name.as_str()).role();
+        if role == Role::Leader {
+            self.spawn();
+        } else {
+            self.stop();
+        }
+    }
+
+    fn spawn(&self) {
+        // FIXME: panics
+        let mut running = self.running.lock().unwrap();
+        if running.is_some() {
+            return;
+        }
+
+        debug!("Singleton({}): Starting on this node.", self.name);
+        let init = self.init.clone();
+        let spawned = Bastion::children(|children| {
+            children
+                .with_name(format!("singleton/{}", self.name))
+                .with_exec(move |ctx: BastionContext| init(ctx))
+        });
+        match spawned {
+            Ok(children) => *running = Some(children),
+            Err(()) => warn!("Singleton({}): Couldn't start on this node.", self.name),
+        }
+    }
+
+    fn stop(&self) {
+        // FIXME: panics
+        if let Some(children) = self.running.lock().unwrap().take() {
+            debug!("Singleton({}): Stopping on this node.", self.name);
+            children.stop().ok();
+        }
+    }
+}
+
+impl Debug for Singleton {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("Singleton")
+            .field("name", &self.name)
+            .field("running", &self.running)
+            .field("stopped", &self.stopped)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[cfg(feature = "tokio-runtime")]
+    mod tokio_tests {
+        #[tokio::test]
+        async fn test_singleton_moves_when_its_host_leaves() {
+            super::test_singleton_moves_when_its_host_leaves()
+        }
+    }
+
+    #[cfg(not(feature = "tokio-runtime"))]
+    mod no_tokio_tests {
+        #[test]
+        fn test_singleton_moves_when_its_host_leaves() {
+            super::test_singleton_moves_when_its_host_leaves()
+        }
+    }
+
+    // Returns the name of a singleton hosted by the member.
+    fn hosted_by(cluster: Cluster, member: &str) -> String {
+        (0..)
+            .map(|i| format!("singleton-{}", i))
+            .find(|name| cluster.leadership(name.as_str()).leader().as_deref() == Some(member))
+            .unwrap()
+    }
+
+    async fn run(ctx: BastionContext, started: mpsc::Sender<()>) -> Result<(), ()> {
+        started.send(()).ok();
+        loop {
+            ctx.recv().await?;
+        }
+    }
+
+    // The singleton sends a message every time it starts.
+    fn singleton(cluster: Cluster, name: String, started: mpsc::Sender<()>) -> Singleton {
+        let started = Mutex::new(started);
+        let init: SingletonInit = Arc::new(move |ctx| {
+            let started = started.lock().unwrap().clone();
+            run(ctx, started).boxed()
+        });
+        Singleton {
+            name,
+            cluster,
+            init,
+            running: Mutex::new(None),
+            stopped: AtomicBool::new(false),
+        }
+    }
+
+    #[test]
+    fn test_singleton_only_runs_on_its_host() {
+        let cluster = Cluster::detached("local", &["a"]);
+        let (sender, started) = mpsc::channel();
+        let singleton = singleton(cluster, hosted_by(cluster, "a"), sender);
+
+        singleton.rebalance();
+        assert!(singleton.running.lock().unwrap().is_none());
+        assert!(started.try_recv().is_err());
+    }
+
+    fn test_singleton_moves_when_its_host_leaves() {
+        Bastion::init();
+        Bastion::start();
+
+        let cluster = Cluster::detached("local", &["a"]);
+        let (sender, started) = mpsc::channel();
+        let singleton = singleton(cluster, hosted_by(cluster, "a"), sender);
+        singleton.rebalance();
+        assert!(singleton.running.lock().unwrap().is_none());
+
+        // This node is the only member left, and hosts the singleton.
+        cluster.leave_as("a");
+        singleton.rebalance();
+        assert!(singleton.running.lock().unwrap().is_some());
+        started
+            .recv_timeout(Duration::from_secs(5))
+            .expect("The singleton didn't start.");
+
+        singleton.stop();
+        assert!(singleton.running.lock().unwrap().is_none());
+    }
+}
@@ -0,0 +1,107 @@
+//!
+//! The spans wrapping the handling of the messages received by the
+//! elements, when the `tracing` feature is enabled.
+//!
+//! Each message carries the span that was current when it was sent
+//! (e.g. the span of the message its sender was handling), which
+//! becomes the parent of the span of its handling, so that the
+//! traces follow the messages from an element to another.
+//!
+//! The span of a message is entered each time the future of the
+//! element is polled, from when the element receives it until it
+//! receives the next one.
+use crate::envelope::SignedMessage;
+use crate::path::BastionPath;
+use once_cell::sync::OnceCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::Span;
+
+#[derive(Debug, Default)]
+// The span of the message being handled by an element.
+pub(crate) struct MessageSpan {
+    // The path of the element, set the first time it is polled.
+    actor: OnceCell<Arc<BastionPath>>,
+    span: Mutex<Option<Span>>,
+    // Whether the future of the element is being polled, in which
+    // case the span is entered.
+    polling: AtomicBool,
+}
+
+// Returns the current span, which becomes the parent of the span of
+// the handling of a message sent now.
+pub(crate) fn sender_span() -> Option<Span> {
+    let span = Span::current();
+    if span.is_none() {
+        None
+    } else {
+        Some(span)
+    }
+}
+
+impl MessageSpan {
+    // Enters the span of the message being handled, before the
+    // future of the element at the given path is polled.
+    pub(crate) fn enter(&self, actor: &Arc<BastionPath>) {
+        self.actor.get_or_init(|| actor.clone());
+        self.polling.store(true, Ordering::SeqCst);
+        if let Some(span) = &*self.span.lock().unwrap() {
+            enter(span);
+        }
+    }
+
+    // Exits the span of the message being handled, once the future
+    // of the element was polled.
+    pub(crate) fn exit(&self) {
+        self.polling.store(false, Ordering::SeqCst);
+        if let Some(span) = &*self.span.lock().unwrap() {
+            exit(span);
+        }
+    }
+
+    // Replaces the span of the previous message by a new one for the
+    // message the element just received.
+    pub(crate) fn received(&self, msg: &SignedMessage) {
+        let actor = self
+            .actor
+            .get()
+            .cloned()
+            .unwrap_or_else(|| Arc::new(BastionPath::root()));
+        let message = msg.msg.type_name();
+        let correlation_id = msg.correlation_id();
+        let span = match msg.msg.span() {
+            Some(parent) => tracing::info_span!(
+                parent: parent,
+                "message",
+                actor = %actor,
+                message,
+                correlation_id = %correlation_id
+            ),
+            None => tracing::info_span!(
+                parent: None,
+                "message",
+                actor = %actor,
+                message,
+                correlation_id = %correlation_id
+            ),
+        };
+
+        let polling = self.polling.load(Ordering::SeqCst);
+        let mut current = self.span.lock().unwrap();
+        if let (Some(previous), true) = (&*current, polling) {
+            exit(previous);
+        }
+        if polling {
+            enter(&span);
+        }
+        *current = Some(span);
+    }
+}
+
+fn enter(span: &Span) {
+    span.with_subscriber(|(id, dispatch)| dispatch.enter(id));
+}
+
+fn exit(span: &Span) {
+    span.with_subscriber(|(id, dispatch)| dispatch.exit(id));
+}
@@ -0,0 +1,249 @@
+//!
+//! Declarative descriptions of children groups, allowing to
+//! describe a topology in data (e.g. loaded from a JSON or TOML
+//! file) and to instantiate it with [`Supervisor::with_specs`].
+//!
+//! [`Supervisor::with_specs`]: crate::supervisor::Supervisor::with_specs
+use crate::callbacks::Callbacks;
+use crate::child::Init;
+use crate::children::Children;
+use crate::context::BastionContext;
+use crate::distributor::Distributor;
+use crate::supervisor::{RestartPolicy, RestartStrategy};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use tracing::trace;
+
+#[derive(Debug, Serialize, Deserialize)]
+/// The description of a children group, instantiated by
+/// [`Supervisor::with_specs`].
+///
+/// Only the name, the redundancy, the distributors and the
+/// restart policy of the group are (de)serialized: the closure
+/// used by its elements (see [`with_exec`]) and its callbacks (see
+/// [`with_callbacks`]) have to be set once the spec is loaded (e.g.
+/// depending on its [`name`]). A group instantiated from a spec
+/// without a closure has elements that stop as soon as they are
+/// started.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// #
+/// let specs: Vec<ChildrenSpec> = serde_json::from_str(r#"[
+///     { "name": "parsers", "redundancy": 4, "distributors": ["parse"] },
+///     { "name": "writer", "restart_policy": { "Tries": 3 } }
+/// ]"#).expect("Couldn't parse the specs");
+///
+/// let specs: Vec<ChildrenSpec> = specs
+///     .into_iter()
+///     .map(|spec| match spec.name() {
+///         "parsers" => spec.with_exec(|ctx: BastionContext| async move {
+///             // Parse the received messages...
+///             Ok(())
+///         }),
+///         _ => spec.with_exec(|ctx: BastionContext| async move {
+///             // Write the parsed messages...
+///             Ok(())
+///         }),
+///     })
+///     .collect();
+/// ```
+///
+/// [`Supervisor::with_specs`]: crate::supervisor::Supervisor::with_specs
+/// [`with_exec`]: Self::with_exec
+/// [`with_callbacks`]: Self::with_callbacks
+/// [`name`]: Self::name
+pub struct ChildrenSpec {
+    name: String,
+    #[serde(default = "default_redundancy")]
+    redundancy: usize,
+    #[serde(default)]
+    distributors: Vec<String>,
+    #[serde(default)]
+    restart_policy: Option<RestartPolicy>,
+    #[serde(skip)]
+    callbacks: Option<Callbacks>,
+    #[serde(skip)]
+    exec: Option<Init>,
+}
+
+fn default_redundancy() -> usize {
+    1
+}
+
+impl ChildrenSpec {
+    /// Creates the spec of a children group with the given name,
+    /// containing one element, subscribed to no distributor and
+    /// using its supervisor's restart strategy.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the children group (see
+    ///     [`Children::with_name`]).
+    ///
+    /// [`Children::with_name`]: crate::children::Children::with_name
+    pub fn new(name: impl Into<String>) -> Self {
+        ChildrenSpec {
+            name: name.into(),
+            redundancy: default_redundancy(),
+            distributors: Vec::new(),
+            restart_policy: None,
+            callbacks: None,
+            exec: None,
+        }
+    }
+
+    /// Sets the number of elements of the children group (see
+    /// [`Children::with_redundancy`]).
+    ///
+    /// [`Children::with_redundancy`]: crate::children::Children::with_redundancy
+    pub fn with_redundancy(mut self, redundancy: usize) -> Self {
+        self.redundancy = redundancy;
+        self
+    }
+
+    /// Adds the name of a distributor the elements of the children
+    /// group subscribe to (see [`Children::with_distributor`]).
+    ///
+    /// [`Children::with_distributor`]: crate::children::Children::with_distributor
+    pub fn with_distributor(mut self, distributor: impl Into<String>) -> Self {
+        self.distributors.push(distributor.into());
+        self
+    }
+
+    /// Sets the restart policy applied to the elements of the
+    /// children group, instead of the one of its supervisor's
+    /// restart strategy (see [`Children::with_restart_strategy`]).
+    ///
+    /// [`Children::with_restart_strategy`]: crate::children::Children::with_restart_strategy
+    pub fn with_restart_policy(mut self, restart_policy: RestartPolicy) -> Self {
+        self.restart_policy = Some(restart_policy);
+        self
+    }
+
+    /// Sets the callbacks of the children group (see
+    /// [`Children::with_callbacks`]).
+    ///
+    /// [`Children::with_callbacks`]: crate::children::Children::with_callbacks
+    pub fn with_callbacks(mut self, callbacks: Callbacks) -> Self {
+        self.callbacks = Some(callbacks);
+        self
+    }
+
+    /// Sets the closure used by the elements of the children group
+    /// (see [`Children::with_exec`]).
+    ///
+    /// [`Children::with_exec`]: crate::children::Children::with_exec
+    pub fn with_exec<I, F>(mut self, init: I) -> Self
+    where
+        I: Fn(BastionContext) -> F + Send + 'static,
+        F: Future<Output = Result<(), ()>> + Send + 'static,
+    {
+        self.exec = Some(Init::new(init));
+        self
+    }
+
+    /// Returns the name of the children group.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the number of elements of the children group.
+    pub fn redundancy(&self) -> usize {
+        self.redundancy
+    }
+
+    /// Returns the names of the distributors the elements of the
+    /// children group subscribe to.
+    pub fn distributors(&self) -> &[String] {
+        &self.distributors
+    }
+
+    /// Returns the restart policy of the children group, if it
+    /// overrides its supervisor's.
+    pub fn restart_policy(&self) -> Option<&RestartPolicy> {
+        self.restart_policy.as_ref()
+    }
+
+    /// Returns whether the closure used by the elements of the
+    /// children group was set.
+    pub fn has_exec(&self) -> bool {
+        self.exec.is_some()
+    }
+
+    // Configures the given children group following this spec.
+    pub(crate) fn apply(self, mut children: Children) -> Children {
+        trace!(
+            "Children({}): Applying spec: {:?}",
+            children.id(),
+            self.name
+        );
+        children = children
+            .with_name(self.name)
+            .with_redundancy(self.redundancy);
+        for distributor in &self.distributors {
+            children = children.with_distributor(Distributor::named(distributor));
+        }
+        if let Some(restart_policy) = self.restart_policy {
+            let restart_strategy = RestartStrategy::default().with_restart_policy(restart_policy);
+            children = children.with_restart_strategy(restart_strategy);
+        }
+        if let Some(callbacks) = self.callbacks {
+            children = children.with_callbacks(callbacks);
+        }
+        if let Some(init) = self.exec {
+            children = children.with_init(init);
+        }
+
+        children
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_children_spec_deserializes_with_defaults() {
+        let specs: Vec<ChildrenSpec> = serde_json::from_str(
+            r#"[
+                { "name": "parsers", "redundancy": 4, "distributors": ["parse"] },
+                { "name": "writer", "restart_policy": { "Tries": 3 } }
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(specs[0].name(), "parsers");
+        assert_eq!(specs[0].redundancy(), 4);
+        assert_eq!(specs[0].distributors(), &["parse".to_string()]);
+        assert_eq!(specs[0].restart_policy(), None);
+
+        assert_eq!(specs[1].redundancy(), 1);
+        assert!(specs[1].distributors().is_empty());
+        assert_eq!(specs[1].restart_policy(), Some(&RestartPolicy::Tries(3)));
+        assert!(!specs[1].has_exec());
+    }
+
+    #[test]
+    fn test_children_spec_serializes_without_exec() {
+        let spec = ChildrenSpec::new("writer")
+            .with_redundancy(2)
+            .with_distributor("write")
+            .with_restart_policy(RestartPolicy::Never)
+            .with_exec(|_| async { Ok(()) });
+        assert!(spec.has_exec());
+
+        let json = serde_json::to_value(&spec).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "name": "writer",
+                "redundancy": 2,
+                "distributors": ["write"],
+                "restart_policy": "Never",
+            })
+        );
+    }
+}
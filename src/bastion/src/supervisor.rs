@@ -2,28 +2,43 @@
 //! Supervisors enable users to supervise a subtree of children
 //! or other supervisor trees under themselves.
 use crate::broadcast::{Broadcast, Parent, Sender};
-use crate::callbacks::Callbacks;
+use crate::callbacks::{Callbacks, RestartContext};
+use crate::child_ref::ChildRef;
 use crate::children::Children;
 use crate::children_ref::ChildrenRef;
 use crate::context::{BastionId, ContextState};
 use crate::envelope::Envelope;
+use crate::events::SystemEvent;
+use crate::executor;
+use crate::health::FailureKind;
 use crate::message::{BastionMessage, Deployment, Message};
+use crate::names::{self, NamedRef};
 use crate::path::{BastionPath, BastionPathElement};
+use crate::spec::ChildrenSpec;
+use crate::system::{self, STRING_INTERNER};
+use crate::time::{self, Instant};
+use crate::tree::{ChildNode, ChildrenNode, ElementState, SupervisorEntry};
 
-use bastion_executor::pool;
 use futures::prelude::*;
 use futures::stream::FuturesOrdered;
 use futures::{pending, poll};
-use futures_timer::Delay;
-use fxhash::FxHashMap;
+use fxhash::{FxHashMap, FxHashSet};
 use lightproc::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::cmp::{Eq, PartialEq};
+use std::collections::VecDeque;
+use std::fmt::{self, Debug, Formatter};
 use std::ops::Range;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::Poll;
 use std::time::Duration;
-use tracing::{debug, trace, warn};
+use tracing::{debug, error, trace, warn};
+use uuid::Uuid;
+
+// How many of the last restarts of a child are remembered, and given
+// to the supervision deciders in `ChildFailure::restarts`.
+const RESTARTS_HISTORY_LEN: usize = 32;
 
 #[derive(Debug)]
 /// A supervisor that can supervise both [`Children`] and other
@@ -96,13 +111,41 @@ pub struct Supervisor {
     killed: FxHashMap<BastionId, Supervised>,
     strategy: SupervisionStrategy,
     restart_strategy: RestartStrategy,
+    // The restart strategies overriding `restart_strategy` for
+    // specific children groups.
+    group_restart_strategies: FxHashMap<BastionId, RestartStrategy>,
+    // The maximum amount of restarts accepted within a time window
+    // before escalating the failure to the parent supervisor.
+    restart_limit: Option<RestartLimit>,
+    // The order in which the supervised elements are stopped.
+    stop_order: StopOrder,
+    // When the restarts within the restart limit's window happened.
+    restarts_history: VecDeque<Instant>,
+    // The supervised children groups and supervisors that are
+    // being restarted.
+    restarting: FxHashSet<BastionId>,
+    // The description of the supervised children groups, published
+    // in the supervision tree.
+    group_nodes: FxHashMap<BastionId, ChildrenNode>,
     // The callbacks called at the supervisor's different
     // lifecycle events.
     callbacks: Callbacks,
+    // The callback called when an element of a supervised
+    // children group fails.
+    on_child_failure: Option<ChildFailureCallback>,
+    // Whether the supervised elements were suspended, in which
+    // case newly deployed ones are suspended too.
+    suspended: bool,
     // Whether this supervisor was started by the system (in
     // which case, users shouldn't be able to get a reference
     // to it).
     is_system_supervisor: bool,
+    // The name of the supervisor.
+    name: Option<String>,
+    // The path under which the supervisor is registered to be
+    // looked up with `Bastion::supervisor_ref`, if it and all
+    // its supervisors are named.
+    named_path: Option<String>,
     // Messages that were received before the supervisor was
     // started. Those will be "replayed" once a start message
     // is received.
@@ -110,6 +153,8 @@ pub struct Supervisor {
     started: bool,
     // Stores amount of subtree restarts.
     subtree_restarts: usize,
+    // How many times the supervisor was reset by the system.
+    restarts: usize,
     // Store the maximum acceptable restarts for the supervisor.
     subtree_restarts_limit: usize,
 }
@@ -118,9 +163,15 @@ pub struct Supervisor {
 struct TrackedChildState {
     id: BastionId,
     state: Arc<Pin<Box<ContextState>>>,
-    restarts_counts: usize,
+    // How many times the child was restarted.
+    restarts: usize,
+    // When the child was last restarted (at most
+    // `RESTARTS_HISTORY_LEN` times), oldest first.
+    restarted_at: VecDeque<Instant>,
 }
 
+struct ChildFailureCallback(Arc<dyn Fn(&ChildRef, &ChildFailure) + Send + Sync>);
+
 #[derive(Debug)]
 enum RestartedElement {
     Supervisor(BastionId),
@@ -152,16 +203,31 @@ pub struct SupervisorRef {
 /// of its elements panicked or returned an error).
 ///
 /// The default strategy is `OneForOne`.
+///
+/// Those strategies follow the ones of Erlang/OTP supervisors
+/// (`one_for_one`, `one_for_all` and `rest_for_one`). Like in
+/// Erlang, the amount of restarts a supervisor accepts can be
+/// limited with [`Supervisor::with_restart_limit`], in which case
+/// a supervisor exceeding it escalates the failure to its own
+/// supervisor instead of restarting in a loop.
 pub enum SupervisionStrategy {
     /// When a children group dies (either because it got
     /// killed, it panicked or returned an error), only
     /// this group is restarted.
+    ///
+    /// Use it when the supervised elements are independent from
+    /// each other.
     OneForOne,
     /// When a children group dies (either because it got
     /// killed, it panicked or returned an error), all the
     /// children groups are restarted (even those which were
     /// stopped) in the same order they were added to the
     /// supervisor.
+    ///
+    /// Use it when the supervised elements depend on each other
+    /// and can't work if one of them is restarted with a fresh
+    /// state (e.g. a pool of workers and the group dispatching
+    /// work to them).
     OneForAll,
     /// When a children group dies (either because it got
     /// killed, it panicked or returned an error), this
@@ -169,7 +235,193 @@ pub enum SupervisionStrategy {
     /// supervisor after it are restarted (even those which
     /// were stopped) in the same order they were added to
     /// the supervisor.
+    ///
+    /// Use it when each supervised element depends on the ones
+    /// added before it (e.g. a connection and the groups using
+    /// it), so that restarting an element also restarts its
+    /// dependents.
     RestForOne,
+    /// When a children group or supervisor dies, the
+    /// [`SupervisionDecider`] decides what to do, given the
+    /// [`ChildFailure`] describing what failed and how.
+    ///
+    /// A decider can be given to [`Supervisor::with_strategy`]
+    /// directly since it converts into this variant.
+    Custom(Arc<dyn SupervisionDecider>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Why an element supervised by a supervisor failed.
+pub enum FailureReason {
+    /// An element of a supervised children group failed.
+    Child(FailureKind),
+    /// A supervised supervisor exceeded its [`RestartLimit`] and
+    /// escalated the failure, or was restarted too many times and
+    /// stopped (in which case the failure has no cause).
+    Escalated,
+}
+
+#[derive(Debug, Clone)]
+/// A failure of an element supervised by a supervisor, given to
+/// the [`SupervisionDecider`] of a supervisor using the
+/// [`SupervisionStrategy::Custom`] strategy and to the callback set
+/// with [`Supervisor::with_on_child_failure`].
+pub struct ChildFailure {
+    id: BastionId,
+    child: Option<ChildRef>,
+    reason: FailureReason,
+    message: Option<String>,
+    failed_at: Instant,
+    restart_count: usize,
+    restarts: Vec<Instant>,
+    cause: Option<Box<ChildFailure>>,
+}
+
+#[derive(Debug, Clone)]
+/// The report given to the handler set with [`Bastion::on_fatal`]
+/// when a top-level supervisor gives up (because it exceeded its
+/// [`RestartLimit`] or its [`SupervisionDecider`] escalated a
+/// failure), just before the system is stopped.
+///
+/// [`Bastion::on_fatal`]: crate::Bastion::on_fatal
+pub struct FatalReport {
+    failure: ChildFailure,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// What a supervisor should do after one of its supervised
+/// elements failed, as decided by a [`SupervisionDecider`].
+pub enum SupervisionDecision {
+    /// Restarts the failed element only (like
+    /// [`SupervisionStrategy::OneForOne`]).
+    Restart,
+    /// Stops the failed element without restarting it.
+    Stop,
+    /// Doesn't handle the failure and escalates it to the parent
+    /// supervisor, as if the supervisor itself had failed (a
+    /// supervisor without a parent supervisor is stopped instead).
+    Escalate,
+    /// Restarts all the supervised elements (like
+    /// [`SupervisionStrategy::OneForAll`]).
+    RestartAll,
+}
+
+/// Decides how a supervisor handles the failures of the elements
+/// it supervises, for the cases the built-in strategies don't
+/// cover (e.g. giving up on an element after a few restarts in a
+/// row, or restarting everything after a specific failure).
+///
+/// Deciders are set with [`Supervisor::with_strategy`] or
+/// [`SupervisorRef::strategy`].
+///
+/// Note that the supervisor's [`RestartLimit`] still applies to
+/// the restarts decided by a decider.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();    
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();    
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// #[derive(Debug)]
+/// struct GiveUpAfter(usize);
+///
+/// impl SupervisionDecider for GiveUpAfter {
+///     fn decide(&self, failure: &ChildFailure) -> SupervisionDecision {
+///         match failure.reason() {
+///             FailureReason::Escalated => SupervisionDecision::Escalate,
+///             FailureReason::Child(_) if failure.restart_count() >= self.0 => {
+///                 SupervisionDecision::Stop
+///             }
+///             FailureReason::Child(_) => SupervisionDecision::Restart,
+///         }
+///     }
+/// }
+///
+/// Bastion::supervisor(|sp| sp.with_strategy(GiveUpAfter(3)))
+///     .expect("Couldn't create the supervisor");
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub trait SupervisionDecider: Send + Sync + Debug {
+    /// Returns what the supervisor should do after the failure.
+    fn decide(&self, failure: &ChildFailure) -> SupervisionDecision;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// The maximum amount of restarts a supervisor accepts within a
+/// time window, set with [`Supervisor::with_restart_limit`] (this
+/// is the "restart intensity" of Erlang/OTP supervisors).
+///
+/// The limit is a budget shared by all the children groups and
+/// supervisors supervised by the supervisor: every restart counts
+/// against it, whichever element failed.
+///
+/// When a failure would make the supervisor exceed it, the
+/// supervisor doesn't restart anything and escalates the failure
+/// to its own supervisor, which handles it as if the supervisor had
+/// failed, using its own strategy (and restart limit): restarting
+/// the supervisor restarts all of its supervised elements. A
+/// top-level supervisor gives up and stops instead, calling the
+/// handler set with [`Bastion::on_fatal`].
+///
+/// A supervisor whose supervised elements were restarted this way
+/// three times gives up the next time too: it kills them and stops,
+/// and its own supervisor handles this last failure using its
+/// strategy like the previous ones.
+///
+/// [`Bastion::on_fatal`]: crate::Bastion::on_fatal
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use std::time::Duration;
+/// #
+/// // At most 5 restarts every 10 seconds.
+/// let limit = RestartLimit::new(5, Duration::from_secs(10));
+///
+/// assert_eq!(limit.max_restarts(), 5);
+/// assert_eq!(limit.within(), Duration::from_secs(10));
+/// ```
+pub struct RestartLimit {
+    max_restarts: usize,
+    within: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The order in which a supervisor stops the children groups and
+/// supervisors it supervises when it is stopped, set with
+/// [`Supervisor::with_stop_order`].
+///
+/// The default order is `Simultaneous`.
+pub enum StopOrder {
+    /// All the supervised elements are stopped at the same time.
+    Simultaneous,
+    /// The supervised elements are stopped one after the other, in
+    /// the order they were added to the supervisor.
+    StartOrder,
+    /// The supervised elements are stopped one after the other, in
+    /// the reverse of the order they were added to the supervisor
+    /// (like Erlang/OTP supervisors do), so that an element is
+    /// stopped before the elements it depends on.
+    ReverseStartOrder,
 }
 
 #[derive(Debug)]
@@ -178,7 +430,7 @@ enum Supervised {
     Children(Children),
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 /// The restart policy which is used during restoring failed
 /// actors by the supervisor.
 ///
@@ -198,12 +450,24 @@ pub enum RestartPolicy {
 /// restoring failed actors. It it fails after N attempts,
 /// the supervisor will remove an actor.
 ///
+/// The delays before restarts can be capped with
+/// [`with_max_delay`] and randomized with [`with_jitter`], so
+/// that failing actors don't restart in lockstep and hammer the
+/// services they depend on.
+///
 /// The default strategy used is [`ActorRestartStrategy::Immediate`]
 /// with the [`RestartPolicy::Always`] restart policy.
+///
+/// [`with_max_delay`]: Self::with_max_delay
+/// [`with_jitter`]: Self::with_jitter
 #[derive(Debug, Clone, PartialEq)]
 pub struct RestartStrategy {
     restart_policy: RestartPolicy,
     strategy: ActorRestartStrategy,
+    // The maximum delay before a restart.
+    max_delay: Option<Duration>,
+    // Whether the delays before restarts are randomized.
+    jitter: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -228,12 +492,30 @@ pub enum ActorRestartStrategy {
     /// When passed a multiplier that equals to 1, the strategy works as the
     /// linear back off strategy. Passing the multiplier that equals to 0 leads
     /// to constant restart delays which is equal to the given timeout.
+    ///
+    /// Despite its name, the timeout grows by `timeout * multiplier`
+    /// after each restart. Use [`CompoundBackOff`] for delays
+    /// multiplied after each restart.
+    ///
+    /// [`CompoundBackOff`]: ActorRestartStrategy::CompoundBackOff
     ExponentialBackOff {
         /// An initial delay before the restarting an actor.
         timeout: Duration,
         /// Defines a multiplier how fast the timeout will be increasing.
         multiplier: f64,
     },
+    /// Restart an actor after with the timeout. Each next timeout
+    /// is the previous one multiplied by the multiplier, so the
+    /// delay after n restarts is `timeout * multiplier^n`.
+    /// Multipliers lower than 1 are treated as 1, which leads to
+    /// constant restart delays.
+    CompoundBackOff {
+        /// An initial delay before the restarting an actor.
+        timeout: Duration,
+        /// Defines by how much the timeout is multiplied after
+        /// each restart.
+        multiplier: f64,
+    },
 }
 
 impl ActorRestartStrategy {
@@ -252,6 +534,15 @@ impl ActorRestartStrategy {
                 let delay = timeout.mul_f64(factor);
                 Some(timeout + delay)
             }
+            ActorRestartStrategy::CompoundBackOff {
+                timeout,
+                multiplier,
+            } => {
+                let factor = multiplier.max(1.0).powi(restarts_count.min(64) as i32);
+                // Saturates instead of overflowing.
+                let delay = timeout.as_secs_f64() * factor;
+                Some(Duration::from_secs_f64(delay.min(u32::MAX as f64)))
+            }
             _ => None,
         }
     }
@@ -268,11 +559,22 @@ impl Supervisor {
         let killed = FxHashMap::default();
         let strategy = SupervisionStrategy::default();
         let restart_strategy = RestartStrategy::default();
+        let group_restart_strategies = FxHashMap::default();
+        let restart_limit = None;
+        let stop_order = StopOrder::default();
+        let restarts_history = VecDeque::new();
+        let restarting = FxHashSet::default();
+        let group_nodes = FxHashMap::default();
         let callbacks = Callbacks::new();
+        let on_child_failure = None;
+        let suspended = false;
         let is_system_supervisor = false;
+        let name = None;
+        let named_path = None;
         let pre_start_msgs = Vec::new();
         let started = false;
         let subtree_restarts = 0;
+        let restarts = 0;
         let subtree_restarts_limit = 3;
 
         Supervisor {
@@ -285,11 +587,22 @@ impl Supervisor {
             killed,
             strategy,
             restart_strategy,
+            group_restart_strategies,
+            restart_limit,
+            stop_order,
+            restarts_history,
+            restarting,
+            group_nodes,
             callbacks,
+            on_child_failure,
+            suspended,
             is_system_supervisor,
+            name,
+            named_path,
             pre_start_msgs,
             started,
             subtree_restarts,
+            restarts,
             subtree_restarts_limit,
         }
     }
@@ -307,6 +620,10 @@ impl Supervisor {
         ProcStack::default()
     }
 
+    pub(crate) fn restarts(&self) -> usize {
+        self.restarts
+    }
+
     pub(crate) async fn reset(&mut self, bcast: Option<Broadcast>) {
         if let Some(bcast) = &bcast {
             debug!(
@@ -324,8 +641,11 @@ impl Supervisor {
 
         // TODO: stop or kill?
         self.kill(0..self.order.len()).await;
+        self.restarts += 1;
 
         if let Some(bcast) = bcast {
+            system::current().tree().remove(self.id());
+            self.unpublish_name();
             self.bcast = bcast;
         } else {
             self.bcast.clear_children();
@@ -340,7 +660,7 @@ impl Supervisor {
         self.pre_start_msgs.shrink_to_fit();
 
         let restarted_objects = self.search_restarted_objects(ActorSearchMethod::All);
-        self.restart(restarted_objects).await;
+        self.restart(restarted_objects, None).await;
 
         debug!(
             "Supervisor({}): Removing {} stopped elements.",
@@ -409,6 +729,14 @@ impl Supervisor {
         &self.callbacks
     }
 
+    pub(crate) fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub(crate) fn set_named_path(&mut self, named_path: String) {
+        self.named_path = Some(named_path);
+    }
+
     pub(crate) fn as_ref(&self) -> SupervisorRef {
         trace!(
             "Supervisor({}): Creating new SupervisorRef({}).",
@@ -760,6 +1088,133 @@ impl Supervisor {
         children_ref
     }
 
+    /// Creates a children group for each of the given specs, in
+    /// order, and then starts supervising them.
+    ///
+    /// This allows to describe the children groups supervised by
+    /// this supervisor in data (e.g. loaded from a configuration
+    /// file) instead of calling [`children`] for each of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `specs` - The specs of the children groups to create (see
+    ///     [`ChildrenSpec`]).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// let specs = vec![
+    ///     ChildrenSpec::new("parsers")
+    ///         .with_redundancy(4)
+    ///         .with_distributor("parse")
+    ///         .with_exec(|ctx: BastionContext| async move {
+    ///             // Parse the received messages...
+    ///             Ok(())
+    ///         }),
+    ///     ChildrenSpec::new("writer")
+    ///         .with_restart_policy(RestartPolicy::Tries(3))
+    ///         .with_exec(|ctx: BastionContext| async move {
+    ///             // Write the parsed messages...
+    ///             Ok(())
+    ///         }),
+    /// ];
+    ///
+    /// Bastion::supervisor(|sp| sp.with_name("ingest").with_specs(specs))
+    ///     .expect("Couldn't create the supervisor");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`children`]: Self::children
+    /// [`ChildrenSpec`]: crate::spec::ChildrenSpec
+    pub fn with_specs(self, specs: Vec<ChildrenSpec>) -> Self {
+        debug!(
+            "Supervisor({}): Creating {} children groups from specs.",
+            self.id(),
+            specs.len()
+        );
+        specs.into_iter().fold(self, |supervisor, spec| {
+            supervisor.children(|children| spec.apply(children))
+        })
+    }
+
+    /// Sets the name of this supervisor.
+    ///
+    /// A named supervisor supervised by the system (i.e. created
+    /// with [`Bastion::supervisor`]) or by another supervisor which
+    /// can be looked up by path can itself be looked up using
+    /// [`Bastion::supervisor_ref`], with its path being the names of
+    /// its supervisors and its own name, separated by `/`. The same
+    /// goes for the named children groups it supervises (see
+    /// [`Bastion::children_ref`]).
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the supervisor, which shouldn't
+    ///     contain `/`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::supervisor(|sp| {
+    ///     sp.with_name("ingest")
+    ///         .supervisor(|sp| sp.with_name("parsers"))
+    /// }).expect("Couldn't create the supervisor");
+    /// #
+    /// # Bastion::start();
+    /// // Once the supervisors are launched...
+    /// let parsers: Option<SupervisorRef> = Bastion::supervisor_ref("ingest/parsers");
+    /// #
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Bastion::supervisor`]: crate::Bastion::supervisor
+    /// [`Bastion::supervisor_ref`]: crate::Bastion::supervisor_ref
+    /// [`Bastion::children_ref`]: crate::Bastion::children_ref
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        trace!("Supervisor({}): Setting name: {}", self.id(), name);
+        self.name = Some(name);
+        self
+    }
+
     /// Sets the strategy the supervisor should use when one
     /// of its supervised children groups or supervisors dies
     /// (in the case of a children group, it could be because one
@@ -784,6 +1239,9 @@ impl Supervisor {
     ///         or supervisors that were added after them (even the
     ///         stopped ones), respecting the order in which they
     ///         were added.
+    ///     - [`SupervisionStrategy::Custom`] (or any
+    ///         [`SupervisionDecider`]) would let the decider choose
+    ///         what to do with each failure.
     ///
     /// # Example
     ///
@@ -814,7 +1272,8 @@ impl Supervisor {
     /// # Bastion::block_until_stopped();
     /// # }
     /// ```
-    pub fn with_strategy(mut self, strategy: SupervisionStrategy) -> Self {
+    pub fn with_strategy(mut self, strategy: impl Into<SupervisionStrategy>) -> Self {
+        let strategy = strategy.into();
         trace!(
             "Supervisor({}): Setting strategy: {:?}",
             self.id(),
@@ -878,6 +1337,116 @@ impl Supervisor {
         self
     }
 
+    /// Sets the maximum amount of restarts the supervisor accepts
+    /// within a time window (see [`RestartLimit`]).
+    ///
+    /// The restarts of all the supervised children groups and
+    /// supervisors count against the same limit. Once it is exceeded,
+    /// the supervisor stops restarting them and escalates the failure
+    /// to its parent supervisor, which decides whether to restart
+    /// the supervisor along with all of them. By default, there is no
+    /// limit.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// # use std::time::Duration;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::supervisor(|sp| {
+    ///     sp.with_strategy(SupervisionStrategy::OneForAll)
+    ///         // Escalate if more than 5 restarts happen within 10 seconds.
+    ///         .with_restart_limit(RestartLimit::new(5, Duration::from_secs(10)))
+    /// }).expect("Couldn't create the supervisor");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_restart_limit(mut self, restart_limit: RestartLimit) -> Self {
+        trace!(
+            "Supervisor({}): Setting restart limit: {:?}",
+            self.id(),
+            restart_limit
+        );
+        self.restart_limit = Some(restart_limit);
+        self
+    }
+
+    /// Sets the order in which the supervisor stops the children
+    /// groups and supervisors it supervises when it is stopped (e.g.
+    /// by [`Bastion::stop`]).
+    ///
+    /// Each children group gets its own stop timeout (see
+    /// [`Children::with_stop_timeout`]) to finish its in-flight work
+    /// before the next element is stopped.
+    ///
+    /// The default order is [`StopOrder::Simultaneous`].
+    ///
+    /// # Arguments
+    ///
+    /// * `stop_order` - The order in which the supervised elements
+    ///     are stopped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::supervisor(|sp| {
+    ///     sp.with_stop_order(StopOrder::ReverseStartOrder)
+    ///         // Stopped last...
+    ///         .children(|children| children)
+    ///         // Stopped first...
+    ///         .children(|children| children)
+    /// }).expect("Couldn't create the supervisor");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`Bastion::stop`]: crate::Bastion::stop
+    pub fn with_stop_order(mut self, stop_order: StopOrder) -> Self {
+        trace!(
+            "Supervisor({}): Setting stop order: {:?}",
+            self.id(),
+            stop_order
+        );
+        self.stop_order = stop_order;
+        self
+    }
+
     /// Sets the callbacks that will get called at this supervisor's
     /// different lifecycle events.
     ///
@@ -931,22 +1500,83 @@ impl Supervisor {
         self
     }
 
-    async fn restart(&mut self, objects: Vec<RestartedElement>) {
-        debug!(
-            "Supervisor({}): Restarting {:?} elements",
-            self.id(),
-            objects.len()
-        );
-        let mut restart_futures = FuturesOrdered::new();
-
-        for object in objects {
-            match object {
-                RestartedElement::Supervisor(supervisor_id) => {
-                    let msg = BastionMessage::restart_subtree();
-                    let env =
-                        Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
-                    self.bcast.send_child(&supervisor_id, env);
-                }
+    /// Sets a callback that will get called when an element of one
+    /// of the supervisor's children groups panics or returns an
+    /// error, before the supervisor handles the failure using its
+    /// strategy.
+    ///
+    /// The callback receives a reference to the failed child and
+    /// the [`ChildFailure`] describing the failure (its reason, the
+    /// panic's message, when it failed and when the child was
+    /// previously restarted), which makes it the place to log or
+    /// alert on the crashes.
+    ///
+    /// # Arguments
+    ///
+    /// * `on_child_failure` - The callback that will get called when
+    ///     a child fails.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// Bastion::supervisor(|sp| {
+    ///     sp.with_on_child_failure(|child_ref, failure| {
+    ///         println!(
+    ///             "Child({}) failed ({:?}) after {} restarts: {}",
+    ///             child_ref.id(),
+    ///             failure.reason(),
+    ///             failure.restart_count(),
+    ///             failure.message().unwrap_or("no message"),
+    ///         );
+    ///     })
+    /// }).expect("Couldn't create the supervisor.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn with_on_child_failure<F>(mut self, on_child_failure: F) -> Self
+    where
+        F: Fn(&ChildRef, &ChildFailure) + Send + Sync + 'static,
+    {
+        trace!("Supervisor({}): Setting child failure callback.", self.id());
+        self.on_child_failure = Some(ChildFailureCallback(Arc::new(on_child_failure)));
+        self
+    }
+
+    async fn restart(&mut self, objects: Vec<RestartedElement>, failure: Option<&ChildFailure>) {
+        debug!(
+            "Supervisor({}): Restarting {:?} elements",
+            self.id(),
+            objects.len()
+        );
+        let mut restart_futures = FuturesOrdered::new();
+
+        for object in objects {
+            match object {
+                RestartedElement::Supervisor(supervisor_id) => {
+                    let msg = BastionMessage::restart_subtree();
+                    let env =
+                        Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
+                    self.bcast.send_child(&supervisor_id, env);
+                }
                 RestartedElement::Child { id, parent_id } => {
                     let index = match self.tracked_groups_order.get(&id) {
                         Some(index) => *index,
@@ -961,30 +1591,40 @@ impl Supervisor {
                         None => continue,
                     };
                     let restarts_count = tracked_state.restarts_count();
+                    let restart_strategy = self
+                        .group_restart_strategies
+                        .get(&parent_id)
+                        .unwrap_or(&self.restart_strategy)
+                        .clone();
 
-                    let restart_required = match self.restart_strategy.restart_policy() {
+                    let restart_required = match restart_strategy.restart_policy() {
                         RestartPolicy::Always => true,
                         RestartPolicy::Never => false,
                         RestartPolicy::Tries(max_retries) => restarts_count < max_retries,
                     };
 
-                    let msg = match restart_required {
+                    let restored = match restart_required {
                         true => {
                             tracked_state.increase_restarts_counter();
-                            let state = tracked_state.state();
-                            BastionMessage::restore_child(id, state)
+                            Some((tracked_state.state(), tracked_state.restarts_count()))
                         }
                         false => {
                             self.remove_child(&id.clone(), &parent_id.clone());
-                            BastionMessage::drop_child(id)
+                            None
                         }
                     };
-                    let restart_strategy = self.restart_strategy.clone();
+                    let failure = failure.cloned();
 
                     restart_futures.push(async move {
-                        if restart_required {
-                            restart_strategy.apply_strategy(restarts_count).await;
-                        }
+                        let msg = match restored {
+                            Some((state, restart_count)) => {
+                                restart_strategy.apply_strategy(restarts_count).await;
+                                // The downtime includes the restart strategy's delay.
+                                let context = RestartContext::new(restart_count, failure);
+                                BastionMessage::restore_child(id, state, context)
+                            }
+                            None => BastionMessage::drop_child(id),
+                        };
 
                         (parent_id, msg)
                     });
@@ -1017,6 +1657,10 @@ impl Supervisor {
 
     async fn stop(&mut self, range: Range<usize>) {
         debug!("Supervisor({}): Stopping range: {:?}", self.id(), range);
+        if self.stop_order != StopOrder::Simultaneous {
+            return self.stop_in_order(range).await;
+        }
+
         if range.start == 0 {
             self.bcast.stop_children();
         } else {
@@ -1056,6 +1700,46 @@ impl Supervisor {
         }
     }
 
+    // Stops the supervised elements one after the other, following
+    // the stop order.
+    async fn stop_in_order(&mut self, range: Range<usize>) {
+        // FIXME: panics?
+        let mut ids = self.order.get(range).unwrap().to_vec();
+        if self.stop_order == StopOrder::ReverseStartOrder {
+            ids.reverse();
+        }
+
+        for id in ids {
+            trace!("Supervised({}): Stopping Supervised({}).", self.id(), id);
+            self.bcast.stop_child(&id);
+
+            // TODO: Err if None?
+            if let Some((_, launched)) = self.launched.remove(&id) {
+                let supervised = match launched.await {
+                    Some(supervised) => supervised,
+                    // The element panicked or was cancelled, so there
+                    // is nothing left to keep: the next ones are still
+                    // stopped in order.
+                    None => {
+                        error!(
+                            "Supervisor({}): Supervised({}) didn't stop properly.",
+                            self.id(),
+                            id
+                        );
+                        continue;
+                    }
+                };
+                trace!(
+                    "Supervisor({}): Supervised({}) stopped.",
+                    self.id(),
+                    supervised.id()
+                );
+                supervised.callbacks().after_stop();
+                self.stopped.insert(id, supervised);
+            }
+        }
+    }
+
     async fn kill(&mut self, range: Range<usize>) {
         debug!("Supervisor({}): Killing range: {:?}", self.id(), range);
         if range.start == 0 {
@@ -1105,38 +1789,167 @@ impl Supervisor {
         self.bcast.faulted();
     }
 
-    async fn recover(&mut self, id: BastionId, parent_id: BastionId) -> Result<(), ()> {
+    // Records a restart, returning whether it exceeds the restart limit.
+    fn restart_limit_exceeded(&mut self) -> bool {
+        let (max_restarts, within) = match &self.restart_limit {
+            Some(limit) => (limit.max_restarts, limit.within),
+            None => return false,
+        };
+
+        let now = time::now();
+        while let Some(restarted_at) = self.restarts_history.front() {
+            if now.duration_since(*restarted_at) <= within {
+                break;
+            }
+            self.restarts_history.pop_front();
+        }
+
+        if self.restarts_history.len() >= max_restarts {
+            return true;
+        }
+        self.restarts_history.push_back(now);
+        false
+    }
+
+    fn escalate(&mut self, cause: ChildFailure) -> Result<(), ()> {
+        self.restarts_history.clear();
+
+        let failure =
+            ChildFailure::new(self.id().clone(), None, FailureReason::Escalated).with_cause(cause);
+        system::current()
+            .events()
+            .publish(SystemEvent::SupervisorEscalated {
+                supervisor: self.id().clone(),
+                failure: failure.clone(),
+            });
+        let parent = match self.bcast.parent().clone().into_supervisor() {
+            Some(parent) => parent,
+            None => {
+                warn!(
+                    "Supervisor({}): Giving up without a parent supervisor.",
+                    self.id()
+                );
+                system::current().report_fatal(FatalReport::new(failure));
+                return Err(());
+            }
+        };
+
+        warn!(
+            "Supervisor({}): Escalating the failure to Supervisor({}).",
+            self.id(),
+            parent.id()
+        );
+        let msg = BastionMessage::restart_required(self.id().clone(), parent.id().clone(), failure);
+        let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
+        // TODO: handle errors
+        parent.send(env).ok();
+
+        // The supervisor keeps running until its parent supervisor
+        // handles the failure, which restarts its whole subtree (see
+        // `restart_subtree`, which gives up once the subtree was
+        // restarted too many times) or stops it.
+        Ok(())
+    }
+
+    async fn recover(
+        &mut self,
+        id: BastionId,
+        parent_id: BastionId,
+        failure: ChildFailure,
+    ) -> Result<(), ()> {
         debug!(
             "Supervisor({}): Recovering using strategy: {:?}",
             self.id(),
             self.strategy
         );
 
-        match self.strategy {
-            SupervisionStrategy::OneForOne => {
-                let search_method = ActorSearchMethod::OneActor { id, parent_id };
-                let objects = self.search_restarted_objects(search_method);
-                self.restart(objects).await;
-            }
-            SupervisionStrategy::OneForAll => {
-                let search_method = ActorSearchMethod::All;
-                let objects = self.search_restarted_objects(search_method);
-                self.restart(objects).await;
-
-                // TODO: should be empty
-                self.stopped.shrink_to_fit();
-                self.killed.shrink_to_fit();
-            }
-            SupervisionStrategy::RestForOne => {
-                let search_method = ActorSearchMethod::FromActor { id, parent_id };
-                let objects = self.search_restarted_objects(search_method);
-                self.restart(objects).await;
+        let search_method = match self.strategy.clone() {
+            SupervisionStrategy::OneForOne => ActorSearchMethod::OneActor { id, parent_id },
+            SupervisionStrategy::OneForAll => ActorSearchMethod::All,
+            SupervisionStrategy::RestForOne => ActorSearchMethod::FromActor { id, parent_id },
+            SupervisionStrategy::Custom(decider) => {
+                let decision = decider.decide(&failure);
+                debug!(
+                    "Supervisor({}): Decided to handle the failure of Supervised({}) with: {:?}",
+                    self.id(),
+                    id,
+                    decision
+                );
+                match decision {
+                    SupervisionDecision::Restart => ActorSearchMethod::OneActor { id, parent_id },
+                    SupervisionDecision::RestartAll => ActorSearchMethod::All,
+                    SupervisionDecision::Stop => {
+                        self.stop_failed(id, parent_id);
+                        return Ok(());
+                    }
+                    SupervisionDecision::Escalate => return self.escalate(failure),
+                }
             }
+        };
+
+        if self.restart_limit_exceeded() {
+            return self.escalate(failure);
+        }
+
+        let restarts_all = matches!(search_method, ActorSearchMethod::All);
+        let objects = self.search_restarted_objects(search_method);
+        for object in &objects {
+            let restarted_id = match object {
+                RestartedElement::Supervisor(id) => id,
+                RestartedElement::Child { parent_id, .. } => parent_id,
+            };
+            self.restarting.insert(restarted_id.clone());
+        }
+        self.publish_tree();
+
+        self.restart(objects, Some(&failure)).await;
+        self.restarting.clear();
+
+        if restarts_all {
+            // TODO: should be empty
+            self.stopped.shrink_to_fit();
+            self.killed.shrink_to_fit();
         }
 
         Ok(())
     }
 
+    // Returns how many times the given child was restarted and when
+    // it was last restarted, or nothing if it isn't a tracked child
+    // (e.g. a supervisor).
+    fn restarts_history_of(&self, id: &BastionId, parent_id: &BastionId) -> (usize, Vec<Instant>) {
+        let index = match self.tracked_groups_order.get(id) {
+            Some(index) => *index,
+            None => return (0, Vec::new()),
+        };
+
+        self.tracked_groups
+            .get(parent_id)
+            .and_then(|childs| childs.get(index))
+            .map(|tracked_state| {
+                let restarted_at = tracked_state.restarted_at().iter().copied().collect();
+                (tracked_state.restarts_count(), restarted_at)
+            })
+            .unwrap_or_default()
+    }
+
+    // Stops a failed child or supervisor without restarting it.
+    fn stop_failed(&mut self, id: BastionId, parent_id: BastionId) {
+        debug!(
+            "Supervisor({}): Stopping Supervised({}) without restarting it.",
+            self.id(),
+            id
+        );
+        if self.tracked_groups.contains_key(&parent_id) {
+            self.remove_child(&id, &parent_id);
+            let msg = BastionMessage::drop_child(id);
+            let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
+            self.bcast.send_child(&parent_id, env);
+        } else {
+            self.bcast.stop_child(&id);
+        }
+    }
+
     fn search_restarted_objects(&self, search_method: ActorSearchMethod) -> Vec<RestartedElement> {
         let mut objects = Vec::new();
 
@@ -1149,22 +1962,34 @@ impl Supervisor {
                 objects.push(element)
             }
             ActorSearchMethod::FromActor { id, parent_id } => {
-                let childs = self.tracked_groups.get(&parent_id).unwrap();
-                let start_index = *self.tracked_groups_order.get(&id).unwrap();
+                let rest_index = match self.tracked_groups.get(&parent_id) {
+                    Some(childs) => {
+                        let start_index = *self.tracked_groups_order.get(&id).unwrap();
+
+                        // Adding all elements in the group from the given actor
+                        childs.iter().skip(start_index).for_each(|tracked_state| {
+                            let id = tracked_state.id();
+                            let element = RestartedElement::Child {
+                                id,
+                                parent_id: parent_id.clone(),
+                            };
+                            objects.push(element)
+                        });
+
+                        let (rest_index, _) = self.launched.get(&parent_id).unwrap();
+                        *rest_index
+                    }
+                    // The failure was escalated by a supervisor.
+                    None => {
+                        objects.push(RestartedElement::Supervisor(id.clone()));
 
-                // Adding all elements in the group from the given actor
-                childs.iter().skip(start_index).for_each(|tracked_state| {
-                    let id = tracked_state.id();
-                    let element = RestartedElement::Child {
-                        id,
-                        parent_id: parent_id.clone(),
-                    };
-                    objects.push(element)
-                });
+                        let (rest_index, _) = self.launched.get(&id).unwrap();
+                        *rest_index
+                    }
+                };
 
                 // And then a rest after the failed group
-                let (rest_index, _) = self.launched.get(&parent_id).unwrap();
-                for index in *rest_index + 1..self.order.len() {
+                for index in rest_index + 1..self.order.len() {
                     let element_id = &self.order[index];
 
                     match self.tracked_groups.get(element_id) {
@@ -1178,7 +2003,8 @@ impl Supervisor {
                             }
                         }
                         None => {
-                            let restarted_element = RestartedElement::Supervisor(id.clone());
+                            let restarted_element =
+                                RestartedElement::Supervisor(element_id.clone());
                             objects.push(restarted_element);
                         }
                     }
@@ -1208,12 +2034,42 @@ impl Supervisor {
         objects
     }
 
-    async fn restart_subtree(&mut self) {
-        if self.subtree_restarts < self.subtree_restarts_limit {
-            self.subtree_restarts += 1;
-            let restarted_objects = self.search_restarted_objects(ActorSearchMethod::All);
-            self.restart(restarted_objects).await;
+    // Restarts all the supervised elements, or kills them if the
+    // parent supervisor already restarted them too many times.
+    async fn restart_subtree(&mut self) -> Result<(), ()> {
+        if self.subtree_restarts >= self.subtree_restarts_limit {
+            self.give_up().await;
+            return Err(());
+        }
+
+        self.subtree_restarts += 1;
+        let restarted_objects = self.search_restarted_objects(ActorSearchMethod::All);
+        self.restart(restarted_objects, None).await;
+        Ok(())
+    }
+
+    // Kills the supervised elements and lets the parent supervisor
+    // handle the failure of this supervisor, which can't be restarted
+    // anymore, using its own strategy.
+    async fn give_up(&mut self) {
+        warn!(
+            "Supervisor({}): Giving up after {} subtree restarts.",
+            self.id(),
+            self.subtree_restarts
+        );
+        self.kill(0..self.order.len()).await;
+
+        let mut failure = ChildFailure::new(self.id().clone(), None, FailureReason::Escalated);
+        failure.restart_count = self.subtree_restarts;
+        if let Some(parent) = self.bcast.parent().clone().into_supervisor() {
+            let msg =
+                BastionMessage::restart_required(self.id().clone(), parent.id().clone(), failure);
+            let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
+            // TODO: handle errors
+            parent.send(env).ok();
         }
+
+        self.faulted();
     }
 
     async fn deinit_with_stop(&mut self) {
@@ -1227,28 +2083,64 @@ impl Supervisor {
     }
 
     async fn deploy_supervised_object(&mut self, deployment: Box<Deployment>) {
+        // The system supervisor isn't part of the paths of the
+        // elements it supervises.
+        let named_prefix = if self.is_system_supervisor {
+            Some("")
+        } else {
+            self.named_path.as_deref()
+        };
         let supervised = match *deployment {
-            Deployment::Supervisor(supervisor) => {
+            Deployment::Supervisor(mut supervisor) => {
                 debug!(
                     "Supervisor({}): Deploying Supervisor({}).",
                     self.id(),
                     supervisor.id()
                 );
+                if let (Some(prefix), Some(name)) = (named_prefix, supervisor.name()) {
+                    let named_path = names::join(prefix, name);
+                    supervisor.set_named_path(named_path);
+                }
                 supervisor.callbacks().before_start();
                 Supervised::supervisor(supervisor)
             }
-            Deployment::Children(children) => {
+            Deployment::Children(mut children) => {
                 debug!(
                     "Supervisor({}): Deploying Children({}).",
                     self.id(),
                     children.id()
                 );
+                if let (Some(prefix), Some(name)) = (named_prefix, children.given_name()) {
+                    let named_path = names::join(prefix, name);
+                    children.set_named_path(named_path);
+                }
                 children.callbacks().before_start();
+                if let Some(restart_strategy) = children.restart_strategy() {
+                    self.group_restart_strategies
+                        .insert(children.id().clone(), restart_strategy.clone());
+                }
+                let distributors = children
+                    .distributors()
+                    .iter()
+                    .map(|distributor| STRING_INTERNER.resolve(distributor.interned()).to_string())
+                    .collect();
+                let group_node = ChildrenNode::new(
+                    children.id().clone(),
+                    children.name(),
+                    children.redundancy(),
+                    distributors,
+                );
+                self.group_nodes.insert(children.id().clone(), group_node);
                 Supervised::children(children)
             }
         };
 
         self.bcast.register(supervised.bcast());
+        if self.suspended {
+            let msg = BastionMessage::suspend();
+            let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
+            self.bcast.send_child(supervised.id(), env);
+        }
         if self.started {
             let msg = BastionMessage::start();
             let env = Envelope::new(msg, self.bcast.path().clone(), self.bcast.sender().clone());
@@ -1281,16 +2173,117 @@ impl Supervisor {
         }
     }
 
+    // Registers the supervisor under its path, if it has one, to
+    // be looked up with `Bastion::supervisor_ref`.
+    fn publish_name(&self) {
+        if let Some(named_path) = &self.named_path {
+            system::current()
+                .names()
+                .register(named_path, NamedRef::Supervisor(self.as_ref()));
+        }
+    }
+
+    fn unpublish_name(&self) {
+        if let Some(named_path) = &self.named_path {
+            system::current().names().remove(named_path, self.id());
+        }
+    }
+
+    // Publishes the state of the supervised elements in the
+    // supervision tree.
+    fn publish_tree(&self) {
+        let mut supervisors = Vec::new();
+        let mut children = Vec::new();
+        for id in &self.order {
+            let state = if self.restarting.contains(id) {
+                ElementState::Restarting
+            } else if self.launched.contains_key(id) {
+                ElementState::Running
+            } else {
+                ElementState::Stopped
+            };
+
+            match self.group_nodes.get(id) {
+                Some(group_node) => {
+                    let elements = self
+                        .tracked_groups
+                        .get(id)
+                        .map(|childs| {
+                            childs
+                                .iter()
+                                .map(|tracked_state| {
+                                    ChildNode::new(
+                                        tracked_state.id(),
+                                        tracked_state.restarts_count(),
+                                    )
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    let group_node = group_node.clone().with_state(state).with_elements(elements);
+                    children.push(group_node);
+                }
+                None => supervisors.push((id.clone(), state)),
+            }
+        }
+
+        let entry = SupervisorEntry::new(self.is_system_supervisor, supervisors, children);
+        system::current().tree().publish(self.id().clone(), entry);
+    }
+
+    async fn prune_supervised_object(&mut self, id: BastionId) {
+        debug!("Supervisor({}): Removing Supervised({}).", self.id(), id);
+        if let Some((_, launched)) = self.launched.remove(&id) {
+            self.bcast.stop_child(&id);
+            // TODO: add a "waiting" list an poll from it instead of awaiting
+            if let Some(supervised) = launched.await {
+                supervised.callbacks().after_stop();
+            }
+
+            self.bcast.unregister(&id);
+        }
+
+        // Forgets about the supervised object so that it doesn't
+        // get restarted with the others.
+        self.stopped.remove(&id);
+        self.killed.remove(&id);
+        self.group_restart_strategies.remove(&id);
+        self.group_nodes.remove(&id);
+        if let Some(childs) = self.tracked_groups.remove(&id) {
+            for tracked_state in childs {
+                self.tracked_groups_order.remove(&tracked_state.id);
+            }
+        }
+
+        if let Some(position) = self.order.iter().position(|order_id| order_id == &id) {
+            self.order.remove(position);
+            for (index, _) in self.launched.values_mut() {
+                if *index > position {
+                    *index -= 1;
+                }
+            }
+        }
+    }
+
     async fn recover_supervised_object(
         &mut self,
         id: BastionId,
         parent_id: BastionId,
+        failure: ChildFailure,
     ) -> Result<(), ()> {
         if self.launched.contains_key(&id) {
             warn!("Supervisor({}): Supervised({}) faulted.", self.id(), id);
         }
 
-        if self.recover(id, parent_id).await.is_err() {
+        let mut failure = failure;
+        let (restart_count, restarts) = self.restarts_history_of(&id, &parent_id);
+        failure.restart_count = restart_count;
+        failure.restarts = restarts;
+        if let (Some(callback), Some(child_ref)) = (&self.on_child_failure, failure.child()) {
+            (callback.0)(child_ref, &failure);
+        }
+
+        if self.recover(id, parent_id, failure).await.is_err() {
             // TODO: stop or kill?
             self.kill(0..self.order.len()).await;
             self.faulted();
@@ -1325,11 +2318,10 @@ impl Supervisor {
                 msg: BastionMessage::Deploy(deployment),
                 ..
             } => self.deploy_supervised_object(deployment).await,
-            // FIXME
             Envelope {
-                msg: BastionMessage::Prune { .. },
+                msg: BastionMessage::Prune { id },
                 ..
-            } => unimplemented!(),
+            } => self.prune_supervised_object(id).await,
             Envelope {
                 msg: BastionMessage::SuperviseWith(strategy),
                 ..
@@ -1378,10 +2370,19 @@ impl Supervisor {
                 self.bcast.send_children(env);
             }
             Envelope {
-                msg: BastionMessage::RestartRequired { id, parent_id },
+                msg:
+                    BastionMessage::RestartRequired {
+                        id,
+                        parent_id,
+                        failure,
+                    },
                 ..
             } => {
-                if self.recover_supervised_object(id, parent_id).await.is_err() {
+                if self
+                    .recover_supervised_object(id, parent_id, failure)
+                    .await
+                    .is_err()
+                {
                     return Err(());
                 }
             }
@@ -1392,7 +2393,11 @@ impl Supervisor {
             Envelope {
                 msg: BastionMessage::RestartSubtree,
                 ..
-            } => self.restart_subtree().await,
+            } => {
+                if self.restart_subtree().await.is_err() {
+                    return Err(());
+                }
+            }
             Envelope {
                 msg: BastionMessage::RestoreChild { .. },
                 ..
@@ -1417,6 +2422,42 @@ impl Supervisor {
                 msg: BastionMessage::Heartbeat,
                 ..
             } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::Suspend,
+                ..
+            } => {
+                debug!("Supervisor({}): Suspending.", self.id());
+                self.suspended = true;
+                self.bcast.send_children(env);
+            }
+            Envelope {
+                msg: BastionMessage::Resume,
+                ..
+            } => {
+                debug!("Supervisor({}): Resuming.", self.id());
+                self.suspended = false;
+                self.bcast.send_children(env);
+            }
+            Envelope {
+                msg: BastionMessage::RetireChild { .. },
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::RestartChild { .. },
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::KillWithReason { .. },
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::LinkFailed { .. },
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::ReplaceExec(_),
+                ..
+            } => unreachable!(),
         }
 
         Ok(())
@@ -1454,6 +2495,8 @@ impl Supervisor {
 
     async fn run(mut self) -> Self {
         debug!("Supervisor({}): Launched.", self.id());
+        self.publish_name();
+        self.publish_tree();
         loop {
             match poll!(&mut self.bcast.next()) {
                 // TODO: Err if started == true?
@@ -1462,8 +2505,11 @@ impl Supervisor {
                     ..
                 })) => {
                     if self.initialize().await.is_err() {
+                        system::current().tree().remove(self.id());
+                        self.unpublish_name();
                         return self;
                     }
+                    self.publish_tree();
                 }
                 Poll::Ready(Some(msg)) if !self.started => {
                     trace!(
@@ -1480,8 +2526,11 @@ impl Supervisor {
                         msg
                     );
                     if self.handle(msg).await.is_err() {
+                        system::current().tree().remove(self.id());
+                        self.unpublish_name();
                         return self;
                     }
+                    self.publish_tree();
                 }
                 // NOTE: because `Broadcast` always holds both a `Sender` and
                 //      `Receiver` of the same channel, this would only be
@@ -1495,7 +2544,7 @@ impl Supervisor {
     pub(crate) fn launch(self) -> RecoverableHandle<Self> {
         debug!("Supervisor({}): Launching.", self.id());
         let stack = self.stack();
-        pool::spawn(self.run(), stack)
+        executor::spawn_with(self.run(), stack)
     }
 }
 
@@ -1678,6 +2727,134 @@ impl SupervisorRef {
         self.children_with_id(BastionId::new(), init)
     }
 
+    /// Creates a new [`Children`], passes it through the specified
+    /// `init` closure and then sends it to the running supervisor
+    /// this `SupervisorRef` is referencing, which starts supervising
+    /// it without restarting any of the elements it already
+    /// supervises.
+    ///
+    /// This is the same as [`children`], and it is meant to be used
+    /// along with [`remove_children`] to grow and shrink the
+    /// supervision tree at runtime (e.g. to spawn a children group
+    /// per tenant on demand).
+    ///
+    /// This methods returns a [`ChildrenRef`] referencing the newly
+    /// created children group if it succeeded, or `Err(())`
+    /// otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `init` - The closure taking the new [`Children`] as an
+    ///     argument and returning it once configured.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let sp_ref = Bastion::supervisor(|sp| sp).unwrap();
+    /// # Bastion::start();
+    /// let tenant_ref = sp_ref
+    ///     .add_children(|children| {
+    ///         children.with_exec(|ctx: BastionContext| async move {
+    ///             // Handle the tenant's requests...
+    ///             Ok(())
+    ///         })
+    ///     })
+    ///     .expect("Couldn't add the children group.");
+    ///
+    /// // Once the tenant is gone...
+    /// sp_ref
+    ///     .remove_children(&tenant_ref)
+    ///     .expect("Couldn't remove the children group.");
+    /// #
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`children`]: Self::children
+    /// [`remove_children`]: Self::remove_children
+    pub fn add_children<C>(&self, init: C) -> Result<ChildrenRef, ()>
+    where
+        C: FnOnce(Children) -> Children,
+    {
+        self.children(init)
+    }
+
+    /// Sends a message to the supervisor this `SupervisorRef` is
+    /// referencing to tell it to stop the children group referenced
+    /// by `children_ref` and to stop supervising it, without
+    /// restarting any of the other elements it supervises.
+    ///
+    /// Unlike a children group stopped with [`ChildrenRef::stop`],
+    /// the removed group won't be restarted if the supervisor
+    /// restarts all of its supervised elements.
+    ///
+    /// This method returns `()` if it succeeded, or `Err(())`
+    /// otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `children_ref` - A reference to the children group to
+    ///     remove, which should be supervised by this supervisor.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let sp_ref = Bastion::supervisor(|sp| sp).unwrap();
+    /// let children_ref = sp_ref.add_children(|children| children).unwrap();
+    ///
+    /// sp_ref
+    ///     .remove_children(&children_ref)
+    ///     .expect("Couldn't remove the children group.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    pub fn remove_children(&self, children_ref: &ChildrenRef) -> Result<(), ()> {
+        debug!(
+            "SupervisorRef({}): Removing Children({}).",
+            self.id(),
+            children_ref.id()
+        );
+        let msg = BastionMessage::prune(children_ref.id().clone());
+        let env = Envelope::from_dead_letters(msg);
+        self.send(env).map_err(|_| ())
+    }
+
     pub(crate) fn children_with_id<C>(&self, id: BastionId, init: C) -> Result<ChildrenRef, ()>
     where
         C: FnOnce(Children) -> Children,
@@ -1739,6 +2916,9 @@ impl SupervisorRef {
     ///         or supervisors that were added after them (even the
     ///         stopped ones), respecting the order in which they
     ///         were added.
+    ///     - [`SupervisionStrategy::Custom`] (or any
+    ///         [`SupervisionDecider`]) would let the decider choose
+    ///         what to do with each failure.
     ///
     /// # Example
     ///
@@ -1768,7 +2948,8 @@ impl SupervisorRef {
     /// # Bastion::block_until_stopped();
     /// # }
     /// ```
-    pub fn strategy(&self, strategy: SupervisionStrategy) -> Result<(), ()> {
+    pub fn strategy(&self, strategy: impl Into<SupervisionStrategy>) -> Result<(), ()> {
+        let strategy = strategy.into();
         debug!(
             "SupervisorRef({}): Setting strategy: {:?}",
             self.id(),
@@ -1932,6 +3113,103 @@ impl SupervisorRef {
         self.send(env).map_err(|_| ())
     }
 
+    /// Sends a message to the supervisor this `SupervisorRef`
+    /// is referencing to tell it to suspend every element of the
+    /// children groups it is supervising, directly or through its
+    /// supervisors.
+    ///
+    /// The futures of suspended elements aren't polled anymore,
+    /// but they keep their state and the messages they receive
+    /// stay in their mailboxes until they are resumed (using
+    /// [`resume`]). Elements that are restarted or added while
+    /// the supervisor is suspended are suspended too. Stopping or
+    /// killing a suspended supervisor still works as usual.
+    ///
+    /// This method returns `()` if it succeeded, or `Err(())`
+    /// otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let sp_ref = Bastion::supervisor(|sp| sp).unwrap();
+    /// sp_ref.suspend().expect("Couldn't send the message.");
+    /// // ...maintenance...
+    /// sp_ref.resume().expect("Couldn't send the message.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`resume`]: Self::resume
+    pub fn suspend(&self) -> Result<(), ()> {
+        debug!("SupervisorRef({}): Suspending.", self.id());
+        let msg = BastionMessage::suspend();
+        let env = Envelope::from_dead_letters(msg);
+        self.send(env).map_err(|_| ())
+    }
+
+    /// Sends a message to the supervisor this `SupervisorRef`
+    /// is referencing to tell it to resume every element
+    /// suspended using [`suspend`], which will then handle the
+    /// messages they received in the meantime.
+    ///
+    /// This method returns `()` if it succeeded, or `Err(())`
+    /// otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use bastion::prelude::*;
+    /// #
+    /// # #[cfg(feature = "tokio-runtime")]
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # #[cfg(not(feature = "tokio-runtime"))]
+    /// # fn main() {
+    /// #    run();    
+    /// # }
+    /// #
+    /// # fn run() {
+    /// # Bastion::init();
+    /// #
+    /// # let sp_ref = Bastion::supervisor(|sp| sp).unwrap();
+    /// sp_ref.resume().expect("Couldn't send the message.");
+    /// #
+    /// # Bastion::start();
+    /// # Bastion::stop();
+    /// # Bastion::block_until_stopped();
+    /// # }
+    /// ```
+    ///
+    /// [`suspend`]: Self::suspend
+    pub fn resume(&self) -> Result<(), ()> {
+        debug!("SupervisorRef({}): Resuming.", self.id());
+        let msg = BastionMessage::resume();
+        let env = Envelope::from_dead_letters(msg);
+        self.send(env).map_err(|_| ())
+    }
+
     pub(crate) fn send(&self, env: Envelope) -> Result<(), Envelope> {
         trace!("SupervisorRef({}): Sending message: {:?}", self.id(), env);
         self.sender
@@ -1949,7 +3227,8 @@ impl TrackedChildState {
         TrackedChildState {
             id,
             state,
-            restarts_counts: 0,
+            restarts: 0,
+            restarted_at: VecDeque::new(),
         }
     }
 
@@ -1962,11 +3241,19 @@ impl TrackedChildState {
     }
 
     fn restarts_count(&self) -> usize {
-        self.restarts_counts
+        self.restarts
+    }
+
+    fn restarted_at(&self) -> &VecDeque<Instant> {
+        &self.restarted_at
     }
 
     fn increase_restarts_counter(&mut self) {
-        self.restarts_counts += 1;
+        self.restarts += 1;
+        if self.restarted_at.len() == RESTARTS_HISTORY_LEN {
+            self.restarted_at.pop_front();
+        }
+        self.restarted_at.push_back(time::now());
     }
 }
 
@@ -2011,7 +3298,7 @@ impl Supervised {
         let stack = self.stack();
         match self {
             Supervised::Supervisor(supervisor) => {
-                pool::spawn(
+                executor::spawn_with(
                     async {
                         // FIXME: panics?
                         let supervisor = supervisor.launch().await.unwrap();
@@ -2021,7 +3308,7 @@ impl Supervised {
                 )
             }
             Supervised::Children(children) => {
-                pool::spawn(
+                executor::spawn_with(
                     async {
                         // FIXME: panics?
                         let children = children.launch().await.unwrap();
@@ -2055,6 +3342,9 @@ impl RestartStrategy {
     ///         failed actor with the delay increasing linearly.
     ///     - [`ActorRestartStrategy::ExponentialBackOff`] would restart the
     ///         failed actor with the delay, multiplied by given coefficient.
+    ///     - [`ActorRestartStrategy::CompoundBackOff`] would restart the
+    ///         failed actor with the delay multiplied by the given
+    ///         coefficient after each restart.
     ///
     /// # Example
     ///
@@ -2072,9 +3362,36 @@ impl RestartStrategy {
         RestartStrategy {
             restart_policy,
             strategy,
+            max_delay: None,
+            jitter: false,
         }
     }
 
+    /// Creates a new instance of RestartStrategy always restarting
+    /// failed actors using the [`ActorRestartStrategy::CompoundBackOff`]
+    /// strategy, which doubles the delay after each restart when
+    /// given a multiplier of 2.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use std::time::Duration;
+    /// # use bastion::prelude::*;
+    /// #
+    /// let restart_strategy = RestartStrategy::exponential(Duration::from_millis(100), 2.0)
+    ///     .with_jitter()
+    ///     .with_max_delay(Duration::from_secs(30));
+    /// ```
+    pub fn exponential(timeout: Duration, multiplier: f64) -> Self {
+        RestartStrategy::new(
+            RestartPolicy::Always,
+            ActorRestartStrategy::CompoundBackOff {
+                timeout,
+                multiplier,
+            },
+        )
+    }
+
     /// Returns the acceptable count of retries for the failed actor.
     /// The `None` value means the amount of attempts is unlimited.
     pub fn restart_policy(&self) -> RestartPolicy {
@@ -2100,25 +3417,217 @@ impl RestartStrategy {
         self
     }
 
+    /// Sets the maximum delay before restarting a failed actor,
+    /// whatever the amount of restarts.
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = Some(max_delay);
+        self
+    }
+
+    /// Randomizes the delay before restarting a failed actor,
+    /// picking it between half of the delay calculated by the
+    /// actor restart strategy and that delay.
+    pub fn with_jitter(mut self) -> Self {
+        self.jitter = true;
+        self
+    }
+
+    /// Returns the maximum delay before restarting a failed actor.
+    pub fn max_delay(&self) -> Option<Duration> {
+        self.max_delay
+    }
+
+    /// Returns whether the delay before restarting a failed actor
+    /// is randomized.
+    pub fn has_jitter(&self) -> bool {
+        self.jitter
+    }
+
+    /// Calculate the delay before restarting an actor after n
+    /// restarts, taking the maximum delay and the jitter into
+    /// account.
+    pub fn calculate(&self, restarts_count: usize) -> Option<Duration> {
+        let mut delay = self.strategy.calculate(restarts_count)?;
+        if let Some(max_delay) = self.max_delay {
+            delay = delay.min(max_delay);
+        }
+        if self.jitter {
+            // The lower bits of a v4 UUID are random.
+            let bits = Uuid::new_v4().as_u128() as u64 & ((1 << 53) - 1);
+            let random = bits as f64 / (1u64 << 53) as f64;
+            delay = delay.mul_f64(0.5 + random / 2.0);
+        }
+
+        Some(delay)
+    }
+
     pub(crate) async fn apply_strategy(&self, restarts_count: usize) {
-        if let Some(dur) = self.strategy.calculate(restarts_count) {
-            Delay::new(dur).await;
+        if let Some(dur) = self.calculate(restarts_count) {
+            executor::sleep(dur).await;
         }
     }
 }
 
+impl RestartLimit {
+    /// Creates a new restart limit, accepting at most
+    /// `max_restarts` restarts within `within`.
+    pub fn new(max_restarts: usize, within: Duration) -> Self {
+        RestartLimit {
+            max_restarts,
+            within,
+        }
+    }
+
+    /// Returns the maximum amount of restarts accepted within
+    /// the time window.
+    pub fn max_restarts(&self) -> usize {
+        self.max_restarts
+    }
+
+    /// Returns the duration of the time window.
+    pub fn within(&self) -> Duration {
+        self.within
+    }
+}
+
+impl Default for StopOrder {
+    fn default() -> Self {
+        StopOrder::Simultaneous
+    }
+}
+
 impl Default for SupervisionStrategy {
     fn default() -> Self {
         SupervisionStrategy::OneForOne
     }
 }
 
+impl Debug for ChildFailureCallback {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("ChildFailureCallback").finish()
+    }
+}
+
+impl<D: SupervisionDecider + 'static> From<D> for SupervisionStrategy {
+    fn from(decider: D) -> Self {
+        SupervisionStrategy::Custom(Arc::new(decider))
+    }
+}
+
+impl ChildFailure {
+    pub(crate) fn new(id: BastionId, child: Option<ChildRef>, reason: FailureReason) -> Self {
+        ChildFailure {
+            id,
+            child,
+            reason,
+            message: None,
+            failed_at: time::now(),
+            restart_count: 0,
+            restarts: Vec::new(),
+            cause: None,
+        }
+    }
+
+    pub(crate) fn with_cause(mut self, cause: ChildFailure) -> Self {
+        self.cause = Some(Box::new(cause));
+        self
+    }
+
+    pub(crate) fn with_message(mut self, message: Option<String>) -> Self {
+        self.message = message;
+        self
+    }
+
+    /// Returns the identifier of the failed element (the
+    /// identifier of the child, or of the supervisor when the
+    /// failure was escalated).
+    pub fn id(&self) -> &BastionId {
+        &self.id
+    }
+
+    /// Returns a reference to the failed child, or `None` if the
+    /// failure was escalated by a supervisor.
+    pub fn child(&self) -> Option<&ChildRef> {
+        self.child.as_ref()
+    }
+
+    /// Returns why the element failed.
+    pub fn reason(&self) -> FailureReason {
+        self.reason
+    }
+
+    /// Returns the message of the panic that made the child fail,
+    /// if it panicked with a string message (like with `panic!`,
+    /// `unwrap` or `expect`).
+    ///
+    /// Children whose future returned an error don't have a
+    /// message, since their future returns `Err(())`.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// Returns when the element failed.
+    pub fn failed_at(&self) -> Instant {
+        self.failed_at
+    }
+
+    /// Returns how many times the failed child was previously
+    /// restarted by the supervisor.
+    pub fn restart_count(&self) -> usize {
+        self.restart_count
+    }
+
+    /// Returns when the failed child was last restarted by the
+    /// supervisor (at most its last 32 restarts), oldest first.
+    pub fn restarts(&self) -> &[Instant] {
+        &self.restarts
+    }
+
+    /// Returns the failure that made a supervisor escalate this
+    /// failure, or `None` if it wasn't escalated.
+    pub fn cause(&self) -> Option<&ChildFailure> {
+        self.cause.as_deref()
+    }
+}
+
+impl FatalReport {
+    pub(crate) fn new(failure: ChildFailure) -> Self {
+        FatalReport { failure }
+    }
+
+    /// Returns the identifier of the top-level supervisor that gave
+    /// up.
+    pub fn supervisor_id(&self) -> &BastionId {
+        self.failure.id()
+    }
+
+    /// Returns the escalation chain, from the failure of the
+    /// top-level supervisor to the failure that caused the
+    /// escalations.
+    pub fn chain(&self) -> Vec<&ChildFailure> {
+        let mut chain = Vec::new();
+        let mut failure = Some(&self.failure);
+        while let Some(current) = failure {
+            chain.push(current);
+            failure = current.cause();
+        }
+        chain
+    }
+
+    /// Returns the failure that caused the escalations (usually the
+    /// failure of a child).
+    pub fn root_cause(&self) -> &ChildFailure {
+        let mut failure = &self.failure;
+        while let Some(cause) = failure.cause() {
+            failure = cause;
+        }
+        failure
+    }
+}
+
 impl Default for RestartStrategy {
     fn default() -> Self {
-        RestartStrategy {
-            restart_policy: RestartPolicy::Always,
-            strategy: ActorRestartStrategy::default(),
-        }
+        RestartStrategy::new(RestartPolicy::Always, ActorRestartStrategy::default())
     }
 }
 
@@ -2135,3 +3644,21 @@ impl PartialEq for SupervisorRef {
 }
 
 impl Eq for SupervisorRef {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restarts_history_is_bounded() {
+        let state = Arc::new(Box::pin(ContextState::new()));
+        let mut tracked_state = TrackedChildState::new(BastionId::new(), state);
+
+        for _ in 0..RESTARTS_HISTORY_LEN * 2 {
+            tracked_state.increase_restarts_counter();
+        }
+
+        assert_eq!(tracked_state.restarts_count(), RESTARTS_HISTORY_LEN * 2);
+        assert_eq!(tracked_state.restarted_at().len(), RESTARTS_HISTORY_LEN);
+    }
+}
@@ -1,40 +1,151 @@
 use crate::broadcast::{Broadcast, Parent, Sender};
+use crate::callbacks::RestartContext;
 use crate::children_ref::ChildrenRef;
 use crate::context::{BastionContext, BastionId, NIL_ID};
 use crate::dispatcher::GlobalDispatcher;
 use crate::envelope::Envelope;
-use crate::message::{BastionMessage, Deployment};
+use crate::events::EventBus;
+use crate::executor;
+use crate::logging::{LogPolicy, MessageLog};
+use crate::message::{BastionMessage, CorrelationId, Deployment};
+use crate::metrics::{LatencyHistogram, LatencyRecorder};
+use crate::names::{self, NameRegistry};
 use crate::path::{BastionPath, BastionPathElement};
-use crate::supervisor::{Supervisor, SupervisorRef};
+use crate::supervisor::{FatalReport, Supervisor, SupervisorRef};
+use crate::time::{self, Instant};
+use crate::tree::TreeRegistry;
 use async_mutex::Mutex as AsyncMutex;
-use bastion_executor::pool;
 use futures::prelude::*;
 use futures::stream::FuturesUnordered;
 use futures::{pending, poll};
 use fxhash::{FxHashMap, FxHashSet};
 use lasso::ThreadedRodeo;
 use lightproc::prelude::*;
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
+use std::cell::RefCell;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Condvar, Mutex};
-use std::task::Poll;
+use std::task::{Context, Poll};
+use std::time::Duration;
 use tracing::{debug, error, info, trace, warn};
 
+// The names of the distributors, shared by all the systems of the
+// process. A `Distributor` is a `Copy` key into this interner that
+// can be created before and outside of any system (e.g. using
+// `Distributor::named`), so it must resolve to the same name in
+// every system. Sharing it is safe because it only maps names to
+// keys: the recipients of a distributor are kept by the dispatcher
+// of each system, so a name used by several systems doesn't route
+// messages from one to the other. The names are never removed, which
+// is fine as long as distributors aren't named after unbounded data.
 pub(crate) static STRING_INTERNER: Lazy<Arc<ThreadedRodeo>> =
     Lazy::new(|| Arc::new(Default::default()));
 
-pub(crate) static SYSTEM: Lazy<GlobalSystem> = Lazy::new(System::init);
+// The default system, used by the elements and tasks which weren't
+// started by a `BastionSystem`.
+pub(crate) static SYSTEM: Lazy<Arc<GlobalSystem>> = Lazy::new(System::init);
+
+thread_local! {
+    // The system of the task being polled by this thread, if it was
+    // spawned by an element or task of a system (see `Scoped`).
+    static CURRENT: RefCell<Option<Arc<GlobalSystem>>> = RefCell::new(None);
+}
 
 pub(crate) struct GlobalSystem {
     sender: Sender,
     supervisor: SupervisorRef,
-    dead_letters: ChildrenRef,
+    dead_letters: OnceCell<ChildrenRef>,
     path: Arc<BastionPath>,
     handle: Arc<AsyncMutex<Option<RecoverableHandle<()>>>>,
     running: Mutex<bool>,
     stopping_cvar: Condvar,
-    dispatcher: GlobalDispatcher,
+    dispatcher: Arc<GlobalDispatcher>,
+    names: NameRegistry,
+    tree: TreeRegistry,
+    fatal_handler: Mutex<Option<FatalHandler>>,
+    // The amount of messages received by the elements of the system,
+    // and the amount sampled by the last call to `messages_rate`.
+    messages: AtomicU64,
+    sampled: Mutex<(Instant, u64)>,
+    // How long the questions asked within the system waited for
+    // their answer.
+    ask_latency: LatencyRecorder,
+    events: EventBus,
+    message_log: MessageLog,
+}
+
+// A future polled with the system it was spawned from as the
+// current one, so that the elements and tasks it starts belong to
+// the same system.
+pub(crate) struct Scoped<F> {
+    system: Option<Arc<GlobalSystem>>,
+    future: Pin<Box<F>>,
+}
+
+// Restores the previous current system once dropped.
+struct Entered(Option<Arc<GlobalSystem>>);
+
+/// Returns the system of the task being polled by the current
+/// thread, or the default one.
+pub(crate) fn current() -> Arc<GlobalSystem> {
+    scope().unwrap_or_else(|| SYSTEM.clone())
+}
+
+/// Returns the system of the task being polled by the current
+/// thread, if it isn't running outside of any system.
+pub(crate) fn scope() -> Option<Arc<GlobalSystem>> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Counts a message received by an element of the current system.
+pub(crate) fn message_received() {
+    // Elements always run within their system, apart from in tests.
+    CURRENT.with(|current| {
+        if let Some(system) = &*current.borrow() {
+            system.messages.fetch_add(1, Ordering::Relaxed);
+        }
+    });
 }
 
+/// Logs an event of the lifecycle of a message (e.g. `"sent"`) if
+/// the message is sampled by the logging policy of the current
+/// system.
+pub(crate) fn log_message(
+    event: &'static str,
+    type_name: &'static str,
+    correlation_id: CorrelationId,
+) {
+    // Avoids cloning the current system for each message.
+    CURRENT.with(|current| match &*current.borrow() {
+        Some(system) => system.message_log.log(event, type_name, correlation_id),
+        None => SYSTEM.message_log.log(event, type_name, correlation_id),
+    });
+}
+
+/// Records how long a question asked within the current system
+/// waited for its answer.
+pub(crate) fn answer_received(latency: Duration) {
+    current().ask_latency.record(latency);
+    #[cfg(feature = "metrics")]
+    ::metrics::histogram!("bastion_ask_latency_seconds", latency.as_secs_f64());
+}
+
+/// Wraps the future so that it is polled with the current system,
+/// if any, as the current one.
+pub(crate) fn scoped<F: Future>(future: F) -> Scoped<F> {
+    Scoped::new(scope(), future)
+}
+
+/// Calls `f` with the given system as the current one.
+pub(crate) fn enter<R>(system: &Arc<GlobalSystem>, f: impl FnOnce() -> R) -> R {
+    let previous = CURRENT.with(|current| current.replace(Some(system.clone())));
+    let _entered = Entered(previous);
+    f()
+}
+
+pub(crate) type FatalHandler = Arc<dyn Fn(&FatalReport) + Send + Sync>;
+
 #[derive(Debug)]
 struct System {
     bcast: Broadcast,
@@ -48,18 +159,21 @@ struct System {
 
 #[allow(clippy::mutex_atomic)]
 impl GlobalSystem {
-    fn new(
-        sender: Sender,
-        supervisor: SupervisorRef,
-        dead_letters: ChildrenRef,
-        handle: RecoverableHandle<()>,
-    ) -> Self {
-        let handle = Some(handle);
-        let handle = Arc::new(AsyncMutex::new(handle));
+    fn new(sender: Sender, supervisor: SupervisorRef) -> Self {
+        let dead_letters = OnceCell::new();
+        let handle = Arc::new(AsyncMutex::new(None));
         let path = Arc::new(BastionPath::root());
         let running = Mutex::new(true);
         let stopping_cvar = Condvar::new();
-        let dispatcher = GlobalDispatcher::new();
+        let dispatcher = Arc::new(GlobalDispatcher::new());
+        let names = NameRegistry::default();
+        let tree = TreeRegistry::default();
+        let fatal_handler = Mutex::new(None);
+        let messages = AtomicU64::new(0);
+        let sampled = Mutex::new((time::now(), 0));
+        let ask_latency = LatencyRecorder::default();
+        let events = EventBus::default();
+        let message_log = MessageLog::default();
 
         GlobalSystem {
             sender,
@@ -70,9 +184,23 @@ impl GlobalSystem {
             running,
             stopping_cvar,
             dispatcher,
+            names,
+            tree,
+            fatal_handler,
+            messages,
+            sampled,
+            ask_latency,
+            events,
+            message_log,
         }
     }
 
+    /// Initializes and launches a new system, independent from the
+    /// default one.
+    pub(crate) fn init() -> Arc<Self> {
+        System::init()
+    }
+
     pub(crate) fn sender(&self) -> &Sender {
         &self.sender
     }
@@ -82,7 +210,11 @@ impl GlobalSystem {
     }
 
     pub(crate) fn dead_letters(&self) -> &ChildrenRef {
-        &self.dead_letters
+        // The dead letters are spawned while the system is
+        // initialized, before it is launched.
+        self.dead_letters
+            .get()
+            .expect("The dead letters weren't spawned.")
     }
 
     pub(crate) fn handle(&self) -> Arc<AsyncMutex<Option<RecoverableHandle<()>>>> {
@@ -93,8 +225,72 @@ impl GlobalSystem {
         &self.path
     }
 
-    pub(crate) fn dispatcher(&self) -> &GlobalDispatcher {
-        &self.dispatcher
+    pub(crate) fn dispatcher(&self) -> Arc<GlobalDispatcher> {
+        self.dispatcher.clone()
+    }
+
+    pub(crate) fn names(&self) -> &NameRegistry {
+        &self.names
+    }
+
+    pub(crate) fn tree(&self) -> &TreeRegistry {
+        &self.tree
+    }
+
+    pub(crate) fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    pub(crate) fn log_messages(&self, policy: LogPolicy) {
+        self.message_log.set_policy(policy);
+    }
+
+    // Returns the amount of messages received by the elements of the
+    // system, and how many were received per second since the
+    // previous call (or since the system was initialized).
+    pub(crate) fn messages_rate(&self) -> (u64, f64) {
+        let messages = self.messages.load(Ordering::Relaxed);
+        let now = time::now();
+        // FIXME: panics
+        let mut sampled = self.sampled.lock().unwrap();
+        let (at, previous) = *sampled;
+        *sampled = (now, messages);
+
+        let elapsed = now.duration_since(at).as_secs_f64();
+        if elapsed > 0.0 {
+            (messages, (messages - previous) as f64 / elapsed)
+        } else {
+            (messages, 0.0)
+        }
+    }
+
+    pub(crate) fn ask_latency(&self) -> LatencyHistogram {
+        self.ask_latency.snapshot()
+    }
+
+    pub(crate) fn set_fatal_handler(&self, handler: FatalHandler) {
+        // FIXME: panics
+        *self.fatal_handler.lock().unwrap() = Some(handler);
+    }
+
+    /// Logs the report, calls the fatal handler with it if one was
+    /// set, and stops the system.
+    pub(crate) fn report_fatal(&self, report: FatalReport) {
+        error!(
+            "System: Supervisor({}) gave up: {:?}",
+            report.supervisor_id(),
+            report
+        );
+        // FIXME: panics
+        let handler = self.fatal_handler.lock().unwrap().clone();
+        if let Some(handler) = handler {
+            handler(&report);
+        }
+
+        info!("System: Stopping after a fatal failure.");
+        let msg = BastionMessage::stop();
+        let env = Envelope::from_dead_letters(msg);
+        self.sender.unbounded_send(env).ok();
     }
 
     pub(crate) fn notify_stopped(&self) {
@@ -103,6 +299,11 @@ impl GlobalSystem {
         self.stopping_cvar.notify_all();
     }
 
+    pub(crate) fn is_running(&self) -> bool {
+        // FIXME: panics
+        *self.running.lock().unwrap()
+    }
+
     pub(crate) fn wait_until_stopped(&self) {
         // FIXME: panics
         let mut running = self.running.lock().unwrap();
@@ -110,10 +311,59 @@ impl GlobalSystem {
             running = self.stopping_cvar.wait(running).unwrap();
         }
     }
+
+    // Returns whether the system stopped before the timeout elapsed.
+    pub(crate) fn wait_until_stopped_timeout(&self, timeout: Duration) -> bool {
+        let deadline = time::now() + timeout;
+        // FIXME: panics
+        let mut running = self.running.lock().unwrap();
+        while *running {
+            let now = time::now();
+            if now >= deadline {
+                return false;
+            }
+
+            running = self
+                .stopping_cvar
+                .wait_timeout(running, deadline - now)
+                .unwrap()
+                .0;
+        }
+
+        true
+    }
+}
+
+impl<F: Future> Scoped<F> {
+    pub(crate) fn new(system: Option<Arc<GlobalSystem>>, future: F) -> Self {
+        Scoped {
+            system,
+            future: Box::pin(future),
+        }
+    }
+}
+
+impl<F: Future> Future for Scoped<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        match &this.system {
+            Some(system) => enter(system, || this.future.as_mut().poll(cx)),
+            None => this.future.as_mut().poll(cx),
+        }
+    }
+}
+
+impl Drop for Entered {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+    }
 }
 
 impl System {
-    fn init() -> GlobalSystem {
+    fn init() -> Arc<GlobalSystem> {
         info!("System: Initializing.");
         let parent = Parent::none();
         let bcast = Broadcast::new_root(parent);
@@ -149,14 +399,21 @@ impl System {
         );
         system.bcast.send_self(env);
 
-        debug!("System: Launching.");
-        let stack = system.stack();
-        let handle = pool::spawn(system.run(), stack);
-
-        let dead_letters_ref =
-            Self::spawn_dead_letters(&supervisor_ref).expect("Can't spawn dead letters");
+        let global = Arc::new(GlobalSystem::new(sender, supervisor_ref.clone()));
+        enter(&global, || {
+            let dead_letters_ref =
+                Self::spawn_dead_letters(&supervisor_ref).expect("Can't spawn dead letters");
+            global.dead_letters.set(dead_letters_ref).ok();
+
+            debug!("System: Launching.");
+            let stack = system.stack();
+            let handle = executor::spawn_with(system.run(), stack);
+            if let Some(mut launched) = global.handle.try_lock() {
+                *launched = Some(handle);
+            }
+        });
 
-        GlobalSystem::new(sender, supervisor_ref, dead_letters_ref, handle)
+        global
     }
 
     fn stack(&self) -> ProcStack {
@@ -191,7 +448,8 @@ impl System {
         };
 
         supervisor.reset(bcast).await;
-        supervisor.callbacks().after_restart();
+        let context = RestartContext::new(supervisor.restarts(), None);
+        supervisor.callbacks().after_restart(&context);
 
         self.bcast.register(supervisor.bcast());
 
@@ -253,8 +511,12 @@ impl System {
 
     async fn deploy(&mut self, deployment: Box<Deployment>) {
         match *deployment {
-            Deployment::Supervisor(supervisor) => {
+            Deployment::Supervisor(mut supervisor) => {
                 debug!("System: Deploying Supervisor({}).", supervisor.id());
+                if let Some(name) = supervisor.name() {
+                    let named_path = names::join("", name);
+                    supervisor.set_named_path(named_path);
+                }
                 supervisor.callbacks().before_start();
 
                 self.bcast.register(supervisor.bcast());
@@ -383,6 +645,34 @@ impl System {
                 msg: BastionMessage::Heartbeat,
                 ..
             } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::Suspend,
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::Resume,
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::RetireChild { .. },
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::RestartChild { .. },
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::KillWithReason { .. },
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::LinkFailed { .. },
+                ..
+            } => unreachable!(),
+            Envelope {
+                msg: BastionMessage::ReplaceExec(_),
+                ..
+            } => unreachable!(),
         }
 
         Ok(())
@@ -435,11 +725,12 @@ impl System {
                         trace!("System: Replaying message: {:?}", msg);
                         // FIXME: Err(Error)?
                         if self.handle(msg).await.is_err() {
-                            let handle = SYSTEM.handle();
+                            let global = current();
+                            let handle = global.handle();
                             let mut system = handle.lock().await;
                             *system = None;
 
-                            SYSTEM.notify_stopped();
+                            global.notify_stopped();
 
                             return;
                         }
@@ -452,11 +743,12 @@ impl System {
                 Poll::Ready(Some(msg)) => {
                     trace!("System: Received a new message (started=true): {:?}", msg);
                     if self.handle(msg).await.is_err() {
-                        let handle = SYSTEM.handle();
+                        let global = current();
+                        let handle = global.handle();
                         let mut system = handle.lock().await;
                         *system = None;
 
-                        SYSTEM.notify_stopped();
+                        global.notify_stopped();
 
                         return;
                     }
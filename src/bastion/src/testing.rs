@@ -0,0 +1,390 @@
+//!
+//! A deterministic, single-threaded runtime with virtual time,
+//! installed using [`Bastion::init_test`] to test the elements of
+//! the system without real sleeps nor races between threads.
+//!
+//! Every task of the system (the supervisors, children, `spawn!`ed
+//! and `blocking!` tasks) is queued and only run, one at a time and
+//! in the order it was woken up, by the thread driving the runtime:
+//! the thread calling [`TestRuntime::run_until_idle`],
+//! [`TestRuntime::advance`], [`run!`] or
+//! [`Bastion::block_until_stopped`].
+//!
+//! The timers (e.g. [`executor::sleep`], [`Answer::timeout`] or
+//! [`BastionContext::recv_timeout`]) use the virtual time of the
+//! runtime, which only moves forward when [`TestRuntime::advance`]
+//! is called, or when the thread blocked in [`run!`] or
+//! [`Bastion::block_until_stopped`] waits for a timer while every
+//! task is idle, in which case the time jumps to the next timer.
+//!
+//! The clock of the system moves forward with the virtual time too,
+//! so the durations the system measures (e.g. the time-to-live of
+//! the messages sent using [`Distributor::tell_one_with_ttl`], the
+//! window of a [`RestartLimit`] or of a [`HealthPolicy`]) elapse
+//! along with the timers.
+//!
+//! [`Bastion::init_test`]: crate::Bastion::init_test
+//! [`Bastion::block_until_stopped`]: crate::Bastion::block_until_stopped
+//! [`run!`]: crate::run
+//! [`executor::sleep`]: crate::executor::sleep
+//! [`Answer::timeout`]: crate::message::Answer::timeout
+//! [`BastionContext::recv_timeout`]: crate::context::BastionContext::recv_timeout
+//! [`Distributor::tell_one_with_ttl`]: crate::distributor::Distributor::tell_one_with_ttl
+//! [`RestartLimit`]: crate::supervisor::RestartLimit
+//! [`HealthPolicy`]: crate::health::HealthPolicy
+use crate::executor::{self, Executor};
+use futures::future::BoxFuture;
+use futures::task::{self, noop_waker_ref, ArcWake};
+use once_cell::sync::OnceCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+use tracing::{debug, trace};
+
+static RUNTIME: OnceCell<TestRuntime> = OnceCell::new();
+
+// How long the thread driving the runtime waits for a task to be
+// woken up by another thread, when there is neither a queued task
+// nor a timer.
+const IDLE_WAIT: Duration = Duration::from_millis(10);
+
+#[derive(Clone)]
+/// A handle to the deterministic runtime installed using
+/// [`Bastion::init_test`].
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use std::time::Duration;
+/// #
+/// let runtime = Bastion::init_test();
+/// Bastion::start();
+///
+/// let handle = spawn_handle!(async {
+///     bastion::executor::sleep(Duration::from_secs(60)).await;
+///     42
+/// });
+///
+/// // The task is waiting for the timer...
+/// runtime.advance(Duration::from_secs(59));
+/// // ...which fires one virtual minute later, instantly.
+/// runtime.advance(Duration::from_secs(1));
+/// assert_eq!(run!(handle), Ok(42));
+/// assert_eq!(runtime.elapsed(), Duration::from_secs(60));
+/// ```
+///
+/// [`Bastion::init_test`]: crate::Bastion::init_test
+pub struct TestRuntime {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    state: Mutex<State>,
+    // Notified when a task is queued.
+    queued: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    tasks: VecDeque<Arc<Task>>,
+    // The virtual time elapsed since the runtime was installed.
+    now: Duration,
+    // The wakers of the pending timers, by deadline and then by
+    // creation order.
+    timers: BTreeMap<(Duration, u64), Waker>,
+    next_timer: u64,
+}
+
+// A task of the runtime, queued again when it is woken up.
+struct Task {
+    runtime: TestRuntime,
+    // The future of the task, or `None` while it is polled or once
+    // it completed.
+    future: Mutex<Option<BoxFuture<'static, ()>>>,
+}
+
+// A timer of the runtime, returned by `Executor::sleep`.
+struct Sleep {
+    runtime: TestRuntime,
+    key: (Duration, u64),
+}
+
+impl TestRuntime {
+    // Installs the runtime as the executor of the system, or returns
+    // the one installed by a previous call.
+    pub(crate) fn install() -> Self {
+        RUNTIME
+            .get_or_init(|| {
+                debug!("TestRuntime: Installing.");
+                let runtime = TestRuntime {
+                    inner: Arc::default(),
+                };
+                if !executor::set_executor(Arc::new(runtime.clone())) {
+                    panic!("Bastion::init_test has to be called before another executor is set.");
+                }
+
+                runtime
+            })
+            .clone()
+    }
+
+    // Returns the runtime if it was installed.
+    pub(crate) fn installed() -> Option<&'static TestRuntime> {
+        RUNTIME.get()
+    }
+
+    /// Returns the virtual time elapsed since the runtime was
+    /// installed.
+    pub fn elapsed(&self) -> Duration {
+        self.inner.state.lock().unwrap().now
+    }
+
+    /// Runs the queued tasks, and the tasks they wake up, until
+    /// every task is waiting for a message, a timer or another
+    /// thread.
+    pub fn run_until_idle(&self) {
+        while self.run_next_task() {}
+    }
+
+    /// Moves the virtual time forward by the given duration, firing
+    /// the timers whose deadline elapsed in order, and running the
+    /// tasks until they are idle after each of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - How much the virtual time moves forward.
+    pub fn advance(&self, duration: Duration) {
+        let until = self.elapsed() + duration;
+        trace!("TestRuntime: Advancing to {:?}.", until);
+        loop {
+            self.run_until_idle();
+            if !self.fire_next_timer(Some(until)) {
+                break;
+            }
+        }
+
+        let mut state = self.inner.state.lock().unwrap();
+        state.now = state.now.max(until);
+    }
+
+    // Runs the tasks, moving the virtual time forward to the next
+    // timer when they are idle, until `done` returns `true`.
+    pub(crate) fn drive_until(&self, mut done: impl FnMut() -> bool) {
+        while !done() {
+            if self.run_next_task() || self.fire_next_timer(None) {
+                continue;
+            }
+
+            // Waits for a task to be woken up by another thread.
+            let state = self.inner.state.lock().unwrap();
+            if state.tasks.is_empty() {
+                let _ = self.inner.queued.wait_timeout(state, IDLE_WAIT).unwrap();
+            }
+        }
+    }
+
+    // Runs the tasks (see `drive_until`) until `done` returns `true`,
+    // or until the virtual time moved forward by `timeout`, returning
+    // whether `done` returned `true`.
+    pub(crate) fn drive_until_timeout(
+        &self,
+        timeout: Duration,
+        mut done: impl FnMut() -> bool,
+    ) -> bool {
+        // Polling the timer registers it, so that the time doesn't
+        // jump past its deadline.
+        let mut timer = self.sleep(timeout);
+        let mut finished = false;
+        self.drive_until(|| {
+            finished = done();
+            let mut cx = Context::from_waker(noop_waker_ref());
+            finished || timer.as_mut().poll(&mut cx).is_ready()
+        });
+
+        finished
+    }
+
+    // Polls `future` while running the tasks (see `drive_until`)
+    // until it completes.
+    pub(crate) fn block_on<F: Future>(&self, future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        let mut output = None;
+        self.drive_until(|| {
+            let mut cx = Context::from_waker(noop_waker_ref());
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(out) => output = Some(out),
+                Poll::Pending => (),
+            }
+
+            output.is_some()
+        });
+
+        output.unwrap()
+    }
+
+    fn run_next_task(&self) -> bool {
+        // The lock is released before the task runs, as it could
+        // queue other tasks.
+        let task = match self.inner.state.lock().unwrap().tasks.pop_front() {
+            Some(task) => task,
+            None => return false,
+        };
+
+        let future = task.future.lock().unwrap().take();
+        if let Some(mut future) = future {
+            let waker = task::waker(task.clone());
+            let mut cx = Context::from_waker(&waker);
+            if future.as_mut().poll(&mut cx).is_pending() {
+                *task.future.lock().unwrap() = Some(future);
+            }
+        }
+
+        true
+    }
+
+    // Wakes the task waiting for the next timer, if its deadline is
+    // before `until`, moving the virtual time forward to it.
+    fn fire_next_timer(&self, until: Option<Duration>) -> bool {
+        let mut state = self.inner.state.lock().unwrap();
+        let key = match state.timers.keys().next() {
+            Some(key) if until.map_or(true, |until| key.0 <= until) => *key,
+            _ => return false,
+        };
+
+        let waker = state.timers.remove(&key).unwrap();
+        state.now = state.now.max(key.0);
+        trace!("TestRuntime: Firing timer at {:?}.", key.0);
+        drop(state);
+
+        waker.wake();
+        true
+    }
+
+    fn queue(&self, task: Arc<Task>) {
+        self.inner.state.lock().unwrap().tasks.push_back(task);
+        self.inner.queued.notify_one();
+    }
+}
+
+impl ArcWake for Task {
+    fn wake_by_ref(task: &Arc<Self>) {
+        task.runtime.queue(task.clone());
+    }
+}
+
+impl Executor for TestRuntime {
+    fn spawn(&self, task: BoxFuture<'static, ()>) {
+        self.queue(Arc::new(Task {
+            runtime: self.clone(),
+            future: Mutex::new(Some(task)),
+        }));
+    }
+
+    fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>) {
+        self.spawn(Box::pin(async move { task() }));
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        let mut state = self.inner.state.lock().unwrap();
+        let key = (state.now + duration, state.next_timer);
+        state.next_timer += 1;
+
+        Box::pin(Sleep {
+            runtime: self.clone(),
+            key,
+        })
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let mut state = self.runtime.inner.state.lock().unwrap();
+        if state.now >= self.key.0 {
+            state.timers.remove(&self.key);
+            Poll::Ready(())
+        } else {
+            state.timers.insert(self.key, cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        self.runtime
+            .inner
+            .state
+            .lock()
+            .unwrap()
+            .timers
+            .remove(&self.key);
+    }
+}
+
+impl Debug for TestRuntime {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        let state = self.inner.state.lock().unwrap();
+        fmt.debug_struct("TestRuntime")
+            .field("now", &state.now)
+            .field("tasks", &state.tasks.len())
+            .field("timers", &state.timers.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_virtual_time() {
+        let runtime = TestRuntime {
+            inner: Arc::default(),
+        };
+        let fired = Arc::new(AtomicUsize::new(0));
+        for secs in &[3, 1, 2] {
+            let sleep = runtime.sleep(Duration::from_secs(*secs));
+            let fired = fired.clone();
+            runtime.spawn(Box::pin(async move {
+                sleep.await;
+                fired.fetch_add(1, Ordering::SeqCst);
+            }));
+        }
+
+        runtime.run_until_idle();
+        assert_eq!(fired.load(Ordering::SeqCst), 0);
+
+        runtime.advance(Duration::from_millis(1500));
+        assert_eq!(fired.load(Ordering::SeqCst), 1);
+        assert_eq!(runtime.elapsed(), Duration::from_millis(1500));
+
+        let sleep = runtime.sleep(Duration::from_secs(10));
+        runtime.block_on(sleep);
+        assert_eq!(fired.load(Ordering::SeqCst), 3);
+        assert_eq!(runtime.elapsed(), Duration::from_millis(11500));
+    }
+
+    #[test]
+    fn test_drive_until_timeout() {
+        let runtime = TestRuntime {
+            inner: Arc::default(),
+        };
+        let sleep = runtime.sleep(Duration::from_secs(60));
+        runtime.spawn(sleep);
+
+        assert!(!runtime.drive_until_timeout(Duration::from_secs(5), || false));
+        assert_eq!(runtime.elapsed(), Duration::from_secs(5));
+
+        assert!(runtime.drive_until_timeout(Duration::from_secs(5), || true));
+        assert_eq!(runtime.elapsed(), Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,32 @@
+//!
+//! The clock measuring the time of the system.
+//!
+//! `std::time::Instant::now` panics on `wasm32-unknown-unknown`,
+//! where the browser's clock (`performance.now()`) is used instead.
+//!
+//! Once the deterministic runtime is installed (see
+//! `Bastion::init_test`), the clock also moves forward with its
+//! virtual time, so that the expiration of the messages, the restart
+//! limits, the health windows and the other durations measured by
+//! the system follow `TestRuntime::advance`.
+use crate::testing::TestRuntime;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+pub(crate) use web_time::Instant;
+
+/// Returns the current instant of the system's clock.
+pub(crate) fn now() -> Instant {
+    let now = Instant::now();
+    match TestRuntime::installed() {
+        Some(runtime) => now + runtime.elapsed(),
+        None => now,
+    }
+}
+
+/// Returns the time elapsed since the given instant of the system's
+/// clock.
+pub(crate) fn since(earlier: Instant) -> Duration {
+    now().saturating_duration_since(earlier)
+}
@@ -0,0 +1,274 @@
+//!
+//! Topics delivering every message published on them to all their
+//! subscribers.
+//!
+//! Unlike a [`Distributor`], which sends each message to one of its
+//! recipients unless told otherwise, a [`Topic`] fans every message
+//! published on it out to all the elements subscribed to it (see
+//! [`BastionContext::subscribe`]).
+//!
+//! With the `remote` feature, the [`WireMessage`]s published on a
+//! topic are also delivered to its subscribers on the other members
+//! of the cluster (see [`Cluster::join`]).
+//!
+//! [`Distributor`]: crate::distributor::Distributor
+//! [`BastionContext::subscribe`]: crate::context::BastionContext::subscribe
+//! [`WireMessage`]: crate::wire::WireMessage
+//! [`Cluster::join`]: crate::cluster::Cluster::join
+use crate::distributor::Distributor;
+use crate::errors::SendError;
+use crate::message::Message;
+use tracing::trace;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// A named topic, whose subscribers all receive every message
+/// published on it.
+///
+/// The elements subscribe to a topic using
+/// [`BastionContext::subscribe`], and are unsubscribed from it when
+/// they stop.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// Bastion::children(|children| {
+///     children
+///         .with_redundancy(3)
+///         .with_exec(|ctx: BastionContext| async move {
+///             ctx.subscribe(&Topic::named("events")).expect("Couldn't subscribe.");
+///             loop {
+///                 MessageHandler::new(ctx.recv().await?)
+///                     .on_tell(|event: &'static str, _| println!("{}", event))
+///                     .on_fallback(|_, _| ());
+///             }
+///         })
+/// })
+/// .expect("Couldn't create the children group.");
+///
+/// # Bastion::start();
+/// // Received by the three elements.
+/// Topic::named("events")
+///     .publish("started")
+///     .expect("Couldn't publish the event.");
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+///
+/// [`BastionContext::subscribe`]: crate::context::BastionContext::subscribe
+pub struct Topic {
+    name: String,
+    distributor: Distributor,
+}
+
+impl Topic {
+    /// Returns the topic with the given name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the topic.
+    pub fn named(name: impl Into<String>) -> Self {
+        let name = name.into();
+        // The topics don't share the recipients of the distributors
+        // with the same name.
+        let distributor = Distributor::named(format!("topic/{}", name));
+        Topic { name, distributor }
+    }
+
+    /// Returns the name of the topic.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Sends the message to every subscriber of the topic, which
+    /// isn't an error if the topic has no subscriber.
+    ///
+    /// With the `remote` feature, [`WireMessage`]s are also sent to
+    /// the subscribers on the other members of the cluster.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to publish.
+    ///
+    /// [`WireMessage`]: crate::wire::WireMessage
+    pub fn publish(&self, message: impl Message + Clone) -> Result<(), SendError> {
+        trace!("Topic({}): Publishing {:?}.", self.name, message);
+        match self.distributor.tell_everyone(message) {
+            Ok(_) | Err(SendError::EmptyRecipient) | Err(SendError::NoDistributor(_)) => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub(crate) fn distributor(&self) -> Distributor {
+        self.distributor
+    }
+}
+
+#[cfg(test)]
+mod topic_tests {
+    use crate::prelude::*;
+    use std::sync::{mpsc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    #[cfg(feature = "tokio-runtime")]
+    mod tokio_tests {
+        #[tokio::test]
+        async fn test_topic() {
+            super::test_topic()
+        }
+    }
+
+    #[cfg(not(feature = "tokio-runtime"))]
+    mod no_tokio_tests {
+        #[test]
+        fn test_topic() {
+            super::test_topic()
+        }
+    }
+
+    fn test_topic() {
+        Bastion::init();
+        Bastion::start();
+
+        test_publish();
+        test_unsubscribe();
+        test_stopped_subscribers();
+    }
+
+    // Spawns the elements subscribing to the topic, which send the
+    // messages published on it along with their identifier, and
+    // unsubscribe from it when they are told to leave. Returns once
+    // all of them subscribed.
+    fn subscribers(
+        topic: &Topic,
+        redundancy: usize,
+        sender: mpsc::Sender<(&'static str, BastionId)>,
+        received: &mpsc::Receiver<(&'static str, BastionId)>,
+    ) -> ChildrenRef {
+        let topic = topic.clone();
+        let sender = Mutex::new(sender);
+        let children = Bastion::children(move |children| {
+            children
+                .with_redundancy(redundancy)
+                .with_exec(move |ctx: BastionContext| {
+                    let topic = topic.clone();
+                    let sender = sender.lock().unwrap().clone();
+                    async move {
+                        let id = ctx.current().id().clone();
+                        ctx.subscribe(&topic).unwrap();
+                        sender.send(("subscribed", id.clone())).ok();
+                        loop {
+                            msg! { ctx.recv().await?,
+                                msg: &'static str => {
+                                    if msg == "leave" {
+                                        ctx.unsubscribe(&topic).unwrap();
+                                    }
+                                    sender.send((msg, id.clone())).ok();
+                                };
+                                _: _ => ();
+                            }
+                        }
+                    }
+                })
+        })
+        .expect("Couldn't create the children group.");
+
+        for _ in 0..redundancy {
+            let (msg, _) = recv(received);
+            assert_eq!(msg, "subscribed");
+        }
+        children
+    }
+
+    fn recv(received: &mpsc::Receiver<(&'static str, BastionId)>) -> (&'static str, BastionId) {
+        received
+            .recv_timeout(Duration::from_secs(5))
+            .expect("The subscriber didn't receive the message.")
+    }
+
+    fn test_publish() {
+        let topic = Topic::named("test-publish");
+        let (sender, received) = mpsc::channel();
+        let children = subscribers(&topic, 3, sender, &received);
+
+        // Every subscriber receives the message once.
+        topic.publish("published").unwrap();
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            let (msg, id) = recv(&received);
+            assert_eq!(msg, "published");
+            assert!(!ids.contains(&id));
+            ids.push(id);
+        }
+        assert!(received.recv_timeout(Duration::from_millis(100)).is_err());
+
+        // Publishing on a topic without subscribers isn't an error.
+        assert!(Topic::named("test-no-subscriber").publish("lost").is_ok());
+
+        children.stop().unwrap();
+    }
+
+    fn test_unsubscribe() {
+        let topic = Topic::named("test-unsubscribe");
+        let (sender, received) = mpsc::channel();
+        let children = subscribers(&topic, 2, sender, &received);
+
+        let leaving = children.elems()[0].clone();
+        leaving.tell_anonymously("leave").unwrap();
+        assert_eq!(recv(&received), ("leave", leaving.id().clone()));
+
+        // Only the subscriber which didn't leave receives the message.
+        topic.publish("published").unwrap();
+        let (msg, id) = recv(&received);
+        assert_eq!(msg, "published");
+        assert_eq!(&id, children.elems()[1].id());
+        assert!(received.recv_timeout(Duration::from_millis(100)).is_err());
+
+        children.stop().unwrap();
+    }
+
+    fn test_stopped_subscribers() {
+        let topic = Topic::named("test-stopped-subscribers");
+        let (sender, received) = mpsc::channel();
+        let stopped = subscribers(&topic, 2, sender.clone(), &received);
+        let running = subscribers(&topic, 1, sender, &received);
+
+        // The stopped subscribers are removed from the topic.
+        stopped.stop().unwrap();
+        let mut remaining = 0;
+        for _ in 0..500 {
+            remaining = topic.distributor().tell_everyone(()).unwrap().len();
+            if remaining == 1 {
+                break;
+            }
+
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(remaining, 1);
+
+        topic.publish("published").unwrap();
+        assert_eq!(
+            recv(&received),
+            ("published", running.elems()[0].id().clone())
+        );
+        assert!(received.recv_timeout(Duration::from_millis(100)).is_err());
+
+        running.stop().unwrap();
+    }
+}
@@ -0,0 +1,339 @@
+//!
+//! Snapshots of the supervision tree, returned by [`Bastion::tree`].
+//!
+//! Every supervisor publishes the state of the elements it
+//! supervises (its children groups, along with their elements, and
+//! its supervisors) each time it handles a message, and
+//! [`Bastion::tree`] assembles the latest published states into a
+//! [`SupervisionTree`]. Since supervisors publish their state
+//! independently, a snapshot can be slightly out of date while the
+//! tree is changing.
+//!
+//! [`Bastion::tree`]: crate::Bastion::tree
+use crate::context::BastionId;
+use fxhash::FxHashMap;
+use std::sync::RwLock;
+use tracing::trace;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The state of a supervisor or children group in a
+/// [`SupervisionTree`].
+pub enum ElementState {
+    /// The element is running.
+    Running,
+    /// The element failed and its supervisor is restarting it.
+    Restarting,
+    /// The element was stopped or killed (it may still be
+    /// restarted if its supervisor restarts all of its elements).
+    Stopped,
+}
+
+#[derive(Debug, Clone, Default)]
+/// A snapshot of the supervision tree, returned by
+/// [`Bastion::tree`].
+///
+/// [`Bastion::tree`]: crate::Bastion::tree
+pub struct SupervisionTree {
+    roots: Vec<SupervisorNode>,
+}
+
+#[derive(Debug, Clone)]
+/// A supervisor in a [`SupervisionTree`].
+pub struct SupervisorNode {
+    id: BastionId,
+    state: ElementState,
+    supervisors: Vec<SupervisorNode>,
+    children: Vec<ChildrenNode>,
+}
+
+#[derive(Debug, Clone)]
+/// A children group in a [`SupervisionTree`].
+pub struct ChildrenNode {
+    id: BastionId,
+    name: String,
+    state: ElementState,
+    redundancy: usize,
+    distributors: Vec<String>,
+    elements: Vec<ChildNode>,
+}
+
+#[derive(Debug, Clone)]
+/// An element of a children group in a [`SupervisionTree`].
+pub struct ChildNode {
+    id: BastionId,
+    restarts: usize,
+}
+
+#[derive(Debug, Clone)]
+// The state of the elements supervised by a supervisor, as
+// published by the supervisor.
+pub(crate) struct SupervisorEntry {
+    is_root: bool,
+    supervisors: Vec<(BastionId, ElementState)>,
+    children: Vec<ChildrenNode>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct TreeRegistry {
+    supervisors: RwLock<FxHashMap<BastionId, SupervisorEntry>>,
+}
+
+impl SupervisionTree {
+    /// Returns the supervisors at the root of the tree (the
+    /// supervisor started by the system, which supervises the
+    /// supervisors and children groups created with [`Bastion`]).
+    ///
+    /// [`Bastion`]: crate::Bastion
+    pub fn roots(&self) -> &[SupervisorNode] {
+        &self.roots
+    }
+
+    /// Returns all the supervisors of the tree, depth-first.
+    pub fn supervisors(&self) -> Vec<&SupervisorNode> {
+        let mut supervisors = Vec::new();
+        for root in &self.roots {
+            root.collect_supervisors(&mut supervisors);
+        }
+        supervisors
+    }
+
+    /// Returns all the children groups of the tree, depth-first.
+    pub fn children_groups(&self) -> Vec<&ChildrenNode> {
+        self.supervisors()
+            .into_iter()
+            .flat_map(|supervisor| supervisor.children.iter())
+            .collect()
+    }
+}
+
+impl SupervisorNode {
+    /// Returns the identifier of the supervisor.
+    pub fn id(&self) -> &BastionId {
+        &self.id
+    }
+
+    /// Returns the state of the supervisor.
+    pub fn state(&self) -> ElementState {
+        self.state
+    }
+
+    /// Returns the supervisors supervised by this supervisor, in
+    /// the order they were added.
+    pub fn supervisors(&self) -> &[SupervisorNode] {
+        &self.supervisors
+    }
+
+    /// Returns the children groups supervised by this supervisor,
+    /// in the order they were added.
+    pub fn children(&self) -> &[ChildrenNode] {
+        &self.children
+    }
+
+    fn collect_supervisors<'a>(&'a self, supervisors: &mut Vec<&'a SupervisorNode>) {
+        supervisors.push(self);
+        for supervisor in &self.supervisors {
+            supervisor.collect_supervisors(supervisors);
+        }
+    }
+}
+
+impl ChildrenNode {
+    pub(crate) fn new(
+        id: BastionId,
+        name: String,
+        redundancy: usize,
+        distributors: Vec<String>,
+    ) -> Self {
+        ChildrenNode {
+            id,
+            name,
+            state: ElementState::Running,
+            redundancy,
+            distributors,
+            elements: Vec::new(),
+        }
+    }
+
+    pub(crate) fn with_state(mut self, state: ElementState) -> Self {
+        self.state = state;
+        self
+    }
+
+    pub(crate) fn with_elements(mut self, elements: Vec<ChildNode>) -> Self {
+        self.elements = elements;
+        self
+    }
+
+    /// Returns the identifier of the children group.
+    pub fn id(&self) -> &BastionId {
+        &self.id
+    }
+
+    /// Returns the name of the children group.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the state of the children group.
+    pub fn state(&self) -> ElementState {
+        self.state
+    }
+
+    /// Returns the amount of elements the children group was
+    /// created with.
+    pub fn redundancy(&self) -> usize {
+        self.redundancy
+    }
+
+    /// Returns the names of the distributors the elements of the
+    /// children group subscribe to.
+    pub fn distributors(&self) -> &[String] {
+        &self.distributors
+    }
+
+    /// Returns the elements of the children group.
+    pub fn elements(&self) -> &[ChildNode] {
+        &self.elements
+    }
+
+    /// Returns the total amount of restarts of the elements of
+    /// the children group.
+    pub fn restarts(&self) -> usize {
+        self.elements.iter().map(|element| element.restarts).sum()
+    }
+}
+
+impl ChildNode {
+    pub(crate) fn new(id: BastionId, restarts: usize) -> Self {
+        ChildNode { id, restarts }
+    }
+
+    /// Returns the identifier of the element.
+    pub fn id(&self) -> &BastionId {
+        &self.id
+    }
+
+    /// Returns how many times the element was restarted.
+    pub fn restarts(&self) -> usize {
+        self.restarts
+    }
+}
+
+impl SupervisorEntry {
+    pub(crate) fn new(
+        is_root: bool,
+        supervisors: Vec<(BastionId, ElementState)>,
+        children: Vec<ChildrenNode>,
+    ) -> Self {
+        SupervisorEntry {
+            is_root,
+            supervisors,
+            children,
+        }
+    }
+}
+
+impl TreeRegistry {
+    pub(crate) fn publish(&self, id: BastionId, entry: SupervisorEntry) {
+        trace!("TreeRegistry: Publishing Supervisor({}).", id);
+        self.supervisors.write().unwrap().insert(id, entry);
+    }
+
+    pub(crate) fn remove(&self, id: &BastionId) {
+        trace!("TreeRegistry: Removing Supervisor({}).", id);
+        self.supervisors.write().unwrap().remove(id);
+    }
+
+    pub(crate) fn snapshot(&self) -> SupervisionTree {
+        let supervisors = self.supervisors.read().unwrap();
+        let roots = supervisors
+            .iter()
+            .filter(|(_, entry)| entry.is_root)
+            .map(|(id, _)| Self::node(&supervisors, id, ElementState::Running))
+            .collect();
+
+        SupervisionTree { roots }
+    }
+
+    fn node(
+        supervisors: &FxHashMap<BastionId, SupervisorEntry>,
+        id: &BastionId,
+        state: ElementState,
+    ) -> SupervisorNode {
+        let (children, sub_supervisors) = match supervisors.get(id) {
+            Some(entry) => (
+                entry.children.clone(),
+                entry
+                    .supervisors
+                    .iter()
+                    .map(|(id, state)| Self::node(supervisors, id, *state))
+                    .collect(),
+            ),
+            // The supervisor didn't publish its state yet.
+            None => (Vec::new(), Vec::new()),
+        };
+
+        SupervisorNode {
+            id: id.clone(),
+            state,
+            supervisors: sub_supervisors,
+            children,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree_registry_builds_snapshot() {
+        let registry = TreeRegistry::default();
+        let root_id = BastionId::new();
+        let supervisor_id = BastionId::new();
+        let children_id = BastionId::new();
+
+        let children = ChildrenNode::new(
+            children_id.clone(),
+            "workers".to_string(),
+            2,
+            vec!["jobs".to_string()],
+        )
+        .with_elements(vec![
+            ChildNode::new(BastionId::new(), 1),
+            ChildNode::new(BastionId::new(), 2),
+        ]);
+        registry.publish(
+            root_id.clone(),
+            SupervisorEntry::new(
+                true,
+                vec![(supervisor_id.clone(), ElementState::Restarting)],
+                Vec::new(),
+            ),
+        );
+        registry.publish(
+            supervisor_id.clone(),
+            SupervisorEntry::new(false, Vec::new(), vec![children]),
+        );
+
+        let tree = registry.snapshot();
+        assert_eq!(tree.roots().len(), 1);
+        assert_eq!(tree.roots()[0].id(), &root_id);
+        assert_eq!(tree.supervisors().len(), 2);
+
+        let supervisor = &tree.roots()[0].supervisors()[0];
+        assert_eq!(supervisor.id(), &supervisor_id);
+        assert_eq!(supervisor.state(), ElementState::Restarting);
+
+        let groups = tree.children_groups();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].id(), &children_id);
+        assert_eq!(groups[0].name(), "workers");
+        assert_eq!(groups[0].distributors(), &["jobs".to_string()]);
+        assert_eq!(groups[0].restarts(), 3);
+
+        registry.remove(&supervisor_id);
+        let tree = registry.snapshot();
+        assert!(tree.roots()[0].supervisors()[0].children().is_empty());
+    }
+}
@@ -0,0 +1,287 @@
+//!
+//! Typed elements of children groups, which only receive messages
+//! of a single type (usually an enum), set using
+//! [`Children::with_typed_exec`], and the [`TypedChildRef`]s
+//! allowing to send them these messages.
+//!
+//! [`Children::with_typed_exec`]: crate::children::Children::with_typed_exec
+use crate::child_ref::ChildRef;
+use crate::context::{BastionContext, ContextState};
+use crate::envelope::SignedMessage;
+use crate::message::{Answer, AnswerSender, Message};
+use std::fmt::{self, Debug, Formatter};
+use std::marker::PhantomData;
+use std::sync::Mutex;
+use tracing::{debug, trace};
+
+/// The execution context of an element of a children group that
+/// only receives messages of type `M`, given to the closure set
+/// using [`Children::with_typed_exec`].
+///
+/// The messages of another type received by the element are routed
+/// to the dead letters. The untyped [`BastionContext`] of the element
+/// is available through [`context`].
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();    
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();    
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// #[derive(Debug, Clone)]
+/// enum Command {
+///     Increment(u64),
+///     Get,
+/// }
+///
+/// Bastion::children(|children| {
+///     children.with_typed_exec(|ctx: TypedContext<Command>| {
+///         async move {
+///             let mut counter = 0;
+///             loop {
+///                 match ctx.recv().await? {
+///                     Command::Increment(by) => counter += by,
+///                     Command::Get => {
+///                         ctx.answer(counter).ok();
+///                     }
+///                 }
+///             }
+///         }
+///     })
+/// }).expect("Couldn't create the children group.");
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+///
+/// [`Children::with_typed_exec`]: crate::children::Children::with_typed_exec
+/// [`context`]: Self::context
+pub struct TypedContext<M> {
+    ctx: BastionContext,
+    // The answer sender of the last received message, if it was
+    // asked.
+    sender: Mutex<Option<AnswerSender>>,
+    _msg: PhantomData<fn() -> M>,
+}
+
+/// A "reference" to an element of a children group that only
+/// receives messages of type `M` (see [`TypedContext`]), only
+/// allowing to send it messages of this type.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// #
+/// # #[cfg(feature = "tokio-runtime")]
+/// # #[tokio::main]
+/// # async fn main() {
+/// #    run();    
+/// # }
+/// #
+/// # #[cfg(not(feature = "tokio-runtime"))]
+/// # fn main() {
+/// #    run();    
+/// # }
+/// #
+/// # fn run() {
+/// # Bastion::init();
+/// #
+/// let children_ref = Bastion::children(|children| {
+///     children.with_typed_exec(|ctx: TypedContext<u64>| {
+///         async move {
+///             let value: u64 = ctx.recv().await?;
+///             Ok(())
+///         }
+///     })
+/// }).expect("Couldn't create the children group.");
+///
+/// let counter: TypedChildRef<u64> = TypedChildRef::new(children_ref.elems()[0].clone());
+/// counter.tell(42).expect("Couldn't send the message.");
+/// #
+/// # Bastion::start();
+/// # Bastion::stop();
+/// # Bastion::block_until_stopped();
+/// # }
+/// ```
+pub struct TypedChildRef<M> {
+    child_ref: ChildRef,
+    _msg: PhantomData<fn(M)>,
+}
+
+impl<M: Message + Clone> TypedContext<M> {
+    pub(crate) fn new(ctx: BastionContext) -> Self {
+        TypedContext {
+            ctx,
+            sender: Mutex::new(None),
+            _msg: PhantomData,
+        }
+    }
+
+    /// Retrieves asynchronously the next message of type `M`
+    /// received by the element this `TypedContext` is linked to,
+    /// and waits (always asynchronously) for one if none has been
+    /// received yet.
+    ///
+    /// This method returns the message if it succeeded, or
+    /// `Err(())` otherwise (see [`BastionContext::recv`]).
+    pub async fn recv(&self) -> Result<M, ()> {
+        loop {
+            let msg = self.ctx.recv().await?;
+            if let Some(msg) = self.typed(msg) {
+                return Ok(msg);
+            }
+        }
+    }
+
+    /// Tries to retrieve asynchronously a message of type `M`
+    /// received by the element this `TypedContext` is linked to,
+    /// returning `None` if none was available (see
+    /// [`BastionContext::try_recv`]).
+    pub async fn try_recv(&self) -> Option<M> {
+        loop {
+            let msg = self.ctx.try_recv().await?;
+            if let Some(msg) = self.typed(msg) {
+                return Some(msg);
+            }
+        }
+    }
+
+    /// Answers the last message received by the element this
+    /// `TypedContext` is linked to, if it was asked (e.g. using
+    /// [`TypedChildRef::ask`]).
+    ///
+    /// This method returns `()` if it succeeded, or `Err(reply)` if
+    /// the last message wasn't asked, was already answered or if
+    /// its sender stopped waiting for the answer.
+    ///
+    /// # Arguments
+    ///
+    /// * `reply` - The answer to send.
+    pub fn answer<R: Message>(&self, reply: R) -> Result<(), R> {
+        match self.sender.lock().unwrap().take() {
+            Some(sender) => sender.reply(reply),
+            None => Err(reply),
+        }
+    }
+
+    /// Returns a [`TypedChildRef`] referencing the element this
+    /// `TypedContext` is linked to.
+    pub fn current(&self) -> TypedChildRef<M> {
+        TypedChildRef::new(self.ctx.current().clone())
+    }
+
+    /// Returns the untyped [`BastionContext`] of the element this
+    /// `TypedContext` is linked to, e.g. to send messages to other
+    /// elements.
+    pub fn context(&self) -> &BastionContext {
+        &self.ctx
+    }
+
+    fn typed(&self, msg: SignedMessage) -> Option<M> {
+        let (mut msg, sign) = msg.split();
+        let sender = msg.take_sender();
+        let typed = match msg.try_unwrap::<M>() {
+            Ok(typed) => Some(typed),
+            // Broadcasts are shared with the other recipients.
+            Err(msg) => match msg.downcast_ref::<M>() {
+                Some(shared) => Some((*shared).clone()),
+                None => {
+                    debug!(
+                        "TypedContext({}): Received a message of another type: {:?}",
+                        self.ctx.current().id(),
+                        msg
+                    );
+                    ContextState::send_to_dead_letters(SignedMessage::new(msg, sign));
+                    None
+                }
+            },
+        };
+
+        // Only the last received message can be answered.
+        *self.sender.lock().unwrap() = sender.filter(|_| typed.is_some());
+        typed
+    }
+}
+
+impl<M: Message> TypedChildRef<M> {
+    /// Creates a `TypedChildRef` referencing the same element as the
+    /// given [`ChildRef`], which should be an element of a children
+    /// group set using [`Children::with_typed_exec`] with the same
+    /// type of messages.
+    ///
+    /// [`Children::with_typed_exec`]: crate::children::Children::with_typed_exec
+    pub fn new(child_ref: ChildRef) -> Self {
+        TypedChildRef {
+            child_ref,
+            _msg: PhantomData,
+        }
+    }
+
+    /// Sends a message to the element this `TypedChildRef` is
+    /// referencing, without waiting for an answer (see
+    /// [`ChildRef::tell_anonymously`]).
+    ///
+    /// This method returns `()` if it succeeded, or `Err(msg)`
+    /// otherwise.
+    pub fn tell(&self, msg: M) -> Result<(), M> {
+        trace!("TypedChildRef({}): Telling message.", self.child_ref.id());
+        self.child_ref.tell_anonymously(msg)
+    }
+
+    /// Sends a message to the element this `TypedChildRef` is
+    /// referencing, which can answer it using
+    /// [`TypedContext::answer`] (see [`ChildRef::ask_anonymously`]).
+    ///
+    /// This method returns [`Answer`] if it succeeded, or `Err(msg)`
+    /// otherwise.
+    pub fn ask(&self, msg: M) -> Result<Answer, M> {
+        trace!("TypedChildRef({}): Asking message.", self.child_ref.id());
+        self.child_ref.ask_anonymously(msg)
+    }
+
+    /// Returns the untyped [`ChildRef`] referencing the same element.
+    pub fn child_ref(&self) -> &ChildRef {
+        &self.child_ref
+    }
+}
+
+impl<M> Clone for TypedChildRef<M> {
+    fn clone(&self) -> Self {
+        TypedChildRef {
+            child_ref: self.child_ref.clone(),
+            _msg: PhantomData,
+        }
+    }
+}
+
+impl<M> Debug for TypedContext<M> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("TypedContext")
+            .field("ctx", &self.ctx)
+            .finish()
+    }
+}
+
+impl<M> Debug for TypedChildRef<M> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        fmt.debug_struct("TypedChildRef")
+            .field("child_ref", &self.child_ref)
+            .finish()
+    }
+}
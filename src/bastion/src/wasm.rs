@@ -0,0 +1,97 @@
+//!
+//! Running the system in the browser, on `wasm32-unknown-unknown`.
+//!
+//! The browser has no threads to run bastion's executor on, so the
+//! tasks of the system are run by the [`WasmExecutor`] on the
+//! browser's event loop instead (using `wasm-bindgen-futures`), and
+//! the timers (e.g. [`BastionContext::recv_timeout`] or the restart
+//! backoffs) and clock are the browser's. When compiled for `wasm32`
+//! (which requires this feature), the system uses it unless another
+//! executor was given to [`Bastion::init_with_executor`] before, and
+//! bastion's own executor isn't compiled.
+//!
+//! The children, supervisors, distributors and [`MessageHandler`]s
+//! then behave as on a native target, but the pieces relying on
+//! threads or on the operating system aren't available:
+//!
+//! - [`Bastion::stop_on_signal`] and the [`io`] module aren't
+//!   compiled.
+//! - Nothing can block the event loop: [`Bastion::block_until_stopped`],
+//!   [`run!`] and the tasks run using [`blocking!`] (which run on the
+//!   event loop, one after the other) must be avoided.
+//! - [`Children::with_exec_local`] and
+//!   [`Children::with_dedicated_executor`] fail to start their
+//!   threads.
+//! - The `remote` transport, and the features built on it, need TCP
+//!   sockets.
+//!
+//! Support for the browser is experimental: the `wasm32` build is
+//! only checked (using `cargo check --target wasm32-unknown-unknown
+//! --features wasm`), and isn't tested in a browser yet.
+//!
+//! This module is only available with the `wasm` feature.
+//!
+//! [`BastionContext::recv_timeout`]: crate::context::BastionContext::recv_timeout
+//! [`Bastion::init_with_executor`]: crate::Bastion::init_with_executor
+//! [`MessageHandler`]: crate::message::MessageHandler
+//! [`Bastion::stop_on_signal`]: crate::Bastion::stop_on_signal
+//! [`io`]: crate::io
+//! [`Bastion::block_until_stopped`]: crate::Bastion::block_until_stopped
+//! [`run!`]: crate::run
+//! [`blocking!`]: crate::blocking
+//! [`Children::with_exec_local`]: crate::children::Children::with_exec_local
+//! [`Children::with_dedicated_executor`]: crate::children::Children::with_dedicated_executor
+use crate::executor::Executor;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use futures_timer::Delay;
+use std::time::Duration;
+
+#[derive(Debug, Default, Clone, Copy)]
+/// The [`Executor`] running the tasks of the system on the event
+/// loop of the browser (see the [`wasm`] module).
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use bastion::prelude::*;
+/// use wasm_bindgen::prelude::*;
+///
+/// #[wasm_bindgen(start)]
+/// pub fn start() {
+///     Bastion::init_with_executor(WasmExecutor);
+///     Bastion::start();
+///
+///     Bastion::children(|children| {
+///         children.with_exec(|ctx: BastionContext| async move {
+///             loop {
+///                 MessageHandler::new(ctx.recv().await?)
+///                     .on_tell(|click: &'static str, _| web_sys::console::log_1(&click.into()))
+///                     .on_fallback(|_, _| ());
+///             }
+///         })
+///     })
+///     .expect("Couldn't create the children group.");
+/// }
+/// ```
+///
+/// [`wasm`]: crate::wasm
+pub struct WasmExecutor;
+
+impl Executor for WasmExecutor {
+    fn spawn(&self, task: BoxFuture<'static, ()>) {
+        wasm_bindgen_futures::spawn_local(task);
+    }
+
+    // There is no thread to block in the browser: the task runs on
+    // the event loop like the others.
+    fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>) {
+        wasm_bindgen_futures::spawn_local(async move { task() });
+    }
+
+    // `futures-timer` uses the timers of the browser when compiled
+    // for `wasm32` with its `wasm-bindgen` feature.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Delay::new(duration).boxed()
+    }
+}
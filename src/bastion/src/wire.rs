@@ -0,0 +1,367 @@
+//!
+//! Serializable envelope for the messages implementing
+//! [`Serialize`] and [`DeserializeOwned`], allowing them to be sent
+//! as bytes (e.g. to another process) and matched by type on the
+//! receiving side using [`MessageHandler::on_wire`].
+//!
+//! Message types can be registered in the [`MessageRegistry`] with a
+//! name and a version, which are then used to tag their envelopes,
+//! allowing elements running different versions of a message type
+//! to coexist (see [`MessageHandler::on_versioned`]).
+//!
+//! This module is only available with the `wire` feature.
+//!
+//! [`Serialize`]: serde::Serialize
+//! [`DeserializeOwned`]: serde::de::DeserializeOwned
+//! [`MessageHandler::on_wire`]: crate::message::MessageHandler::on_wire
+//! [`MessageHandler::on_versioned`]: crate::message::MessageHandler::on_versioned
+use crate::errors::RegistryError;
+use fxhash::FxHashMap;
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::any::{type_name, TypeId};
+use std::sync::RwLock;
+use tracing::{debug, trace};
+
+static REGISTRY: Lazy<MessageRegistry> = Lazy::new(MessageRegistry::default);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A serialized message along with a tag identifying its type, and
+/// the version of the type.
+///
+/// The tag and the version are the ones the type was registered
+/// with in the [`MessageRegistry`]. They otherwise default to the
+/// name of the type of the message (see [`std::any::type_name`]),
+/// which is only stable for a given build, and to `0`: processes
+/// built separately should either register the types they exchange
+/// or use [`with_tag`] and [`unpack_tagged`] with a tag they agree
+/// on.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use serde::{Deserialize, Serialize};
+/// #
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// struct Deposit {
+///     account: String,
+///     amount: u64,
+/// }
+///
+/// let deposit = Deposit { account: "account-1".to_string(), amount: 42 };
+/// let wire = WireMessage::pack(&deposit).unwrap();
+/// let bytes = serde_json::to_vec(&wire).unwrap();
+///
+/// // Later, maybe in another process...
+/// let wire: WireMessage = serde_json::from_slice(&bytes).unwrap();
+/// assert!(wire.is::<Deposit>());
+/// assert_eq!(wire.unpack::<Deposit>().unwrap(), deposit);
+/// ```
+///
+/// [`with_tag`]: Self::with_tag
+/// [`unpack_tagged`]: Self::unpack_tagged
+pub struct WireMessage {
+    tag: String,
+    #[serde(default)]
+    version: u32,
+    payload: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The name and version a message type was registered with in the
+/// [`MessageRegistry`].
+pub struct MessageSchema {
+    name: String,
+    version: u32,
+}
+
+#[derive(Debug, Default)]
+/// The registry of the names and versions of the message types,
+/// used to tag the [`WireMessage`]s they are packed in.
+///
+/// # Example
+///
+/// ```rust
+/// # use bastion::prelude::*;
+/// # use serde::{Deserialize, Serialize};
+/// #
+/// #[derive(Debug, Serialize, Deserialize)]
+/// struct Deposit {
+///     account: String,
+///     amount: u64,
+/// }
+///
+/// MessageRegistry::global()
+///     .register::<Deposit>("bank.deposit", 2)
+///     .expect("Couldn't register the message type.");
+///
+/// let deposit = Deposit { account: "account-1".to_string(), amount: 42 };
+/// let wire = WireMessage::pack(&deposit).unwrap();
+/// assert_eq!(wire.tag(), "bank.deposit");
+/// assert_eq!(wire.version(), 2);
+/// ```
+pub struct MessageRegistry {
+    schemas: RwLock<FxHashMap<TypeId, MessageSchema>>,
+}
+
+impl MessageSchema {
+    /// Returns the name the message type was registered with.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the version the message type was registered with.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+}
+
+impl MessageRegistry {
+    /// Returns the registry used by the system.
+    pub fn global() -> &'static MessageRegistry {
+        &REGISTRY
+    }
+
+    /// Registers the message type `T` with the given name and
+    /// version.
+    ///
+    /// Registering a type again with the same name and version does
+    /// nothing, but a type can't be registered with another name or
+    /// version, and two types can't share the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name identifying the type, which must be the
+    ///     same in every process exchanging it.
+    /// * `version` - The version of the type, which should be bumped
+    ///     each time its serialized form changes.
+    pub fn register<T: 'static>(
+        &self,
+        name: impl Into<String>,
+        version: u32,
+    ) -> Result<(), RegistryError> {
+        let schema = MessageSchema {
+            name: name.into(),
+            version,
+        };
+        let mut schemas = self.schemas.write().unwrap();
+        match schemas.get(&TypeId::of::<T>()) {
+            Some(registered) if *registered == schema => return Ok(()),
+            Some(registered) => {
+                return Err(RegistryError::AlreadyRegistered {
+                    type_name: type_name::<T>(),
+                    name: registered.name.clone(),
+                    version: registered.version,
+                })
+            }
+            None => (),
+        }
+        if schemas
+            .values()
+            .any(|registered| registered.name == schema.name)
+        {
+            return Err(RegistryError::NameTaken(schema.name));
+        }
+
+        debug!(
+            "MessageRegistry: Registering {} as {} v{}.",
+            type_name::<T>(),
+            schema.name,
+            schema.version
+        );
+        schemas.insert(TypeId::of::<T>(), schema);
+        Ok(())
+    }
+
+    /// Returns the name and version the message type `T` was
+    /// registered with, if it was.
+    pub fn schema_of<T: 'static>(&self) -> Option<MessageSchema> {
+        self.schemas
+            .read()
+            .unwrap()
+            .get(&TypeId::of::<T>())
+            .cloned()
+    }
+
+    // Returns the tag and version of the envelopes of the messages
+    // of type `T`.
+    fn tag_of<T: 'static>(&self) -> (String, u32) {
+        match self.schema_of::<T>() {
+            Some(MessageSchema { name, version }) => (name, version),
+            None => (type_name::<T>().to_string(), 0),
+        }
+    }
+}
+
+impl WireMessage {
+    /// Serializes the given message, tagged with the name and
+    /// version its type was registered with (or with the name of
+    /// its type if it wasn't).
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The message to serialize.
+    pub fn pack<T: Serialize + 'static>(msg: &T) -> serde_json::Result<Self> {
+        let (tag, version) = REGISTRY.tag_of::<T>();
+        Ok(Self::with_tag(tag, msg)?.with_version(version))
+    }
+
+    /// Serializes the given message with the given tag.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - The tag identifying the type of the message.
+    /// * `msg` - The message to serialize.
+    pub fn with_tag<T: Serialize>(tag: impl Into<String>, msg: &T) -> serde_json::Result<Self> {
+        let tag = tag.into();
+        trace!("WireMessage: Packing message tagged {}.", tag);
+        Ok(WireMessage {
+            tag,
+            version: 0,
+            payload: serde_json::to_vec(msg)?,
+        })
+    }
+
+    /// Creates a message from its tag, the version of its type and
+    /// its serialized payload, as decoded by a [`WireCodec`].
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - The tag identifying the type of the message.
+    /// * `version` - The version of the type of the message.
+    /// * `payload` - The message, serialized as JSON.
+    ///
+    /// [`WireCodec`]: crate::codec::WireCodec
+    pub fn from_parts(tag: impl Into<String>, version: u32, payload: Vec<u8>) -> Self {
+        WireMessage {
+            tag: tag.into(),
+            version,
+            payload,
+        }
+    }
+
+    /// Sets the version of the type of the message.
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - The version of the type of the message.
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Returns the tag identifying the type of the message.
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Returns the version of the type of the message.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Returns the serialized message.
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// Returns whether the message is tagged with the name of the
+    /// type `T` (see [`pack`]), whatever its version.
+    ///
+    /// [`pack`]: Self::pack
+    pub fn is<T: 'static>(&self) -> bool {
+        self.tag == REGISTRY.tag_of::<T>().0
+    }
+
+    /// Deserializes the message as a `T`, returning `None` if it
+    /// isn't tagged with the name of the type `T` (see [`pack`]).
+    ///
+    /// The version of the message isn't checked (see
+    /// [`MessageHandler::on_versioned`] to handle the older versions
+    /// of a type).
+    ///
+    /// [`pack`]: Self::pack
+    /// [`MessageHandler::on_versioned`]: crate::message::MessageHandler::on_versioned
+    pub fn unpack<T: DeserializeOwned + 'static>(&self) -> Option<serde_json::Result<T>> {
+        self.unpack_tagged(&REGISTRY.tag_of::<T>().0)
+    }
+
+    /// Deserializes the message as a `T`, returning `None` if it
+    /// isn't tagged with the given tag.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - The tag the message must have been packed with.
+    pub fn unpack_tagged<T: DeserializeOwned>(&self, tag: &str) -> Option<serde_json::Result<T>> {
+        if self.tag != tag {
+            return None;
+        }
+
+        trace!("WireMessage: Unpacking message tagged {}.", tag);
+        Some(serde_json::from_slice(&self.payload))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Deposit(u64);
+
+    #[test]
+    fn test_wire_message_round_trip() {
+        let wire = WireMessage::pack(&Deposit(42)).unwrap();
+        let bytes = serde_json::to_vec(&wire).unwrap();
+        let wire: WireMessage = serde_json::from_slice(&bytes).unwrap();
+
+        assert!(wire.is::<Deposit>());
+        assert_eq!(wire.unpack::<Deposit>().unwrap().unwrap(), Deposit(42));
+        assert!(wire.unpack::<String>().is_none());
+    }
+
+    #[test]
+    fn test_wire_message_custom_tag() {
+        let wire = WireMessage::with_tag("deposit", &Deposit(42)).unwrap();
+
+        assert!(!wire.is::<Deposit>());
+        assert!(wire.unpack::<Deposit>().is_none());
+        assert_eq!(
+            wire.unpack_tagged::<Deposit>("deposit").unwrap().unwrap(),
+            Deposit(42)
+        );
+    }
+
+    #[test]
+    fn test_registered_message_types() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Withdrawal(u64);
+
+        let registry = MessageRegistry::global();
+        registry
+            .register::<Withdrawal>("bank.withdrawal", 3)
+            .unwrap();
+        registry
+            .register::<Withdrawal>("bank.withdrawal", 3)
+            .unwrap();
+        assert!(matches!(
+            registry.register::<Withdrawal>("bank.withdrawal", 4),
+            Err(RegistryError::AlreadyRegistered { version: 3, .. })
+        ));
+        assert_eq!(
+            registry.register::<Deposit>("bank.withdrawal", 1),
+            Err(RegistryError::NameTaken("bank.withdrawal".to_string()))
+        );
+
+        let wire = WireMessage::pack(&Withdrawal(42)).unwrap();
+        assert_eq!(wire.tag(), "bank.withdrawal");
+        assert_eq!(wire.version(), 3);
+        assert!(wire.is::<Withdrawal>());
+        assert_eq!(
+            wire.unpack::<Withdrawal>().unwrap().unwrap(),
+            Withdrawal(42)
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use bastion::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_restarted_child_is_not_a_failure() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_restarted_child_is_not_a_failure() {
+        super::run()
+    }
+}
+
+fn run() {
+    Bastion::init();
+    Bastion::start();
+
+    let (sender, failures) = mpsc::channel();
+    let sender = Mutex::new(sender);
+    let supervisor = Bastion::supervisor(move |sp| {
+        sp.with_on_child_failure(move |_, failure| {
+            sender.lock().unwrap().send(failure.clone()).ok();
+        })
+    })
+    .expect("Couldn't create the supervisor.");
+
+    let runs = Arc::new(AtomicUsize::new(0));
+    let counted = runs.clone();
+    let children = supervisor
+        .children(move |children| {
+            children.with_exec(move |ctx: BastionContext| {
+                counted.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    loop {
+                        ctx.recv().await?;
+                    }
+                }
+            })
+        })
+        .expect("Couldn't create the children group.");
+
+    thread::sleep(Duration::from_millis(100));
+    let child_ref = children.elems()[0].clone();
+    child_ref.restart().expect("Couldn't send the message.");
+
+    for _ in 0..500 {
+        if runs.load(Ordering::SeqCst) >= 2 {
+            break;
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(runs.load(Ordering::SeqCst), 2);
+    // The supervisor isn't told about a failure.
+    assert!(failures.recv_timeout(Duration::from_millis(200)).is_err());
+
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
@@ -0,0 +1,77 @@
+use bastion::prelude::*;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_derive_message() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_derive_message() {
+        super::run()
+    }
+}
+
+#[derive(Debug, BastionMessage)]
+enum Counter {
+    Add(u64),
+    Sub { amount: u64 },
+    Get,
+}
+
+#[derive(Default)]
+struct Total(u64);
+
+impl CounterHandler for Total {
+    type Output = ();
+
+    fn on_add(&mut self, field0: u64, _origin: MessageOrigin) {
+        self.0 += field0;
+    }
+
+    fn on_sub(&mut self, amount: u64, _origin: MessageOrigin) {
+        self.0 -= amount;
+    }
+
+    fn on_get(&mut self, origin: MessageOrigin) {
+        assert!(origin.is_question());
+        origin.reply(self.0).expect("couldn't reply");
+    }
+}
+
+fn run() {
+    Bastion::init();
+    Bastion::start();
+
+    let children = Bastion::children(|children| {
+        children.with_exec(|ctx: BastionContext| async move {
+            let mut total = Total::default();
+            loop {
+                if let Err(msg) = Counter::handle(ctx.recv().await?, &mut total) {
+                    panic!("unexpected message: {:?}", msg);
+                }
+            }
+        })
+    })
+    .unwrap();
+
+    let child = &children.elems()[0];
+    child.tell_anonymously(Counter::Add(5)).unwrap();
+    child.tell_anonymously(Counter::Sub { amount: 2 }).unwrap();
+    let answer = child.ask_anonymously(Counter::Get).unwrap();
+
+    let total = run!(async {
+        msg! { answer.await.unwrap(),
+            total: u64 => total;
+            _: _ => panic!("didn't receive the total");
+        }
+    });
+    assert_eq!(total, 3);
+
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
@@ -0,0 +1,79 @@
+use bastion::prelude::*;
+use futures_timer::Delay;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_drain_handles_queued_messages() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_drain_handles_queued_messages() {
+        super::run()
+    }
+}
+
+const DISTRIBUTOR: &str = "drained";
+
+fn run() {
+    Bastion::init();
+    Bastion::start();
+
+    // The element is slow to handle its messages, which queue up in
+    // its mailbox.
+    let handled = Arc::new(AtomicUsize::new(0));
+    let counted = handled.clone();
+    let children = Bastion::children(move |children| {
+        children
+            .with_distributor(Distributor::named(DISTRIBUTOR))
+            .with_stop_timeout(Duration::from_secs(1))
+            .with_exec(move |ctx: BastionContext| {
+                let counted = counted.clone();
+                async move {
+                    while ctx.recv().await.is_ok() {
+                        Delay::new(Duration::from_millis(30)).await;
+                        counted.fetch_add(1, Ordering::SeqCst);
+                    }
+
+                    Ok(())
+                }
+            })
+    })
+    .expect("Couldn't create the children group.");
+
+    thread::sleep(Duration::from_millis(100));
+    for i in 0..5_usize {
+        children.broadcast(i).expect("Couldn't send the message.");
+    }
+    thread::sleep(Duration::from_millis(10));
+
+    let report = run!(children.drain(Duration::from_secs(5)));
+    assert!(report.is_complete());
+    assert_eq!(report.remaining(), 0);
+    // Only the message being handled when the mailbox was emptied
+    // can be left.
+    assert!(handled.load(Ordering::SeqCst) >= 4);
+    // The group doesn't receive new work anymore.
+    assert!(Distributor::named(DISTRIBUTOR).tell_one("late").is_err());
+
+    // The element finishes handling its last message before stopping.
+    for _ in 0..100 {
+        if handled.load(Ordering::SeqCst) == 5 {
+            break;
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(handled.load(Ordering::SeqCst), 5);
+
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
@@ -0,0 +1,89 @@
+use bastion::prelude::*;
+use futures::StreamExt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_system_events() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_system_events() {
+        super::run()
+    }
+}
+
+fn run() {
+    Bastion::init();
+    Bastion::start();
+
+    let mut events = Bastion::events();
+    let (sender, received) = mpsc::channel();
+    thread::spawn(move || {
+        futures::executor::block_on(async move {
+            while let Some(event) = events.next().await {
+                if sender.send(event).is_err() {
+                    break;
+                }
+            }
+        })
+    });
+
+    // The element fails the first time it runs.
+    let failed = Arc::new(AtomicBool::new(false));
+    let children = Bastion::children(|children| {
+        children
+            .with_name("flaky")
+            .with_exec(move |ctx: BastionContext| {
+                let failed = failed.clone();
+                async move {
+                    if !failed.swap(true, Ordering::SeqCst) {
+                        return Err(());
+                    }
+
+                    loop {
+                        ctx.recv().await?;
+                    }
+                }
+            })
+    })
+    .expect("Couldn't create the children group.");
+    let group = children.id().clone();
+
+    let mut started = false;
+    let mut failed = false;
+    let mut restarted = false;
+    while !(started && failed && restarted) {
+        let event = received
+            .recv_timeout(Duration::from_secs(5))
+            .expect("Missing events.");
+        match event {
+            SystemEvent::GroupStarted { group: id, name } if id == group => {
+                assert_eq!(name, "flaky");
+                started = true;
+            }
+            SystemEvent::ChildFailed {
+                group: id, reason, ..
+            } if id == group => {
+                assert_eq!(reason, FailureKind::Errored);
+                failed = true;
+            }
+            SystemEvent::ChildRestarted { group: id, .. } if id == group => {
+                assert!(failed);
+                restarted = true;
+            }
+            _ => (),
+        }
+    }
+
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
@@ -0,0 +1,55 @@
+use bastion::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_fatal_failures_stop_the_system_without_handler() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_fatal_failures_stop_the_system_without_handler() {
+        super::run()
+    }
+}
+
+fn run() {
+    Bastion::init();
+    Bastion::start();
+
+    // The element always fails, so the supervisor gives up after
+    // restarting it twice, without a fatal handler to notify.
+    let runs = Arc::new(AtomicUsize::new(0));
+    let counted = runs.clone();
+    Bastion::supervisor(move |sp| {
+        sp.with_restart_limit(RestartLimit::new(2, Duration::from_secs(60)))
+            .children(move |children| {
+                children.with_exec(move |_: BastionContext| {
+                    let counted = counted.clone();
+                    async move {
+                        counted.fetch_add(1, Ordering::SeqCst);
+                        Err(())
+                    }
+                })
+            })
+    })
+    .expect("Couldn't create the supervisor.");
+
+    // The system stops anyway.
+    let (sender, stopped) = mpsc::channel();
+    thread::spawn(move || {
+        Bastion::block_until_stopped();
+        sender.send(()).ok();
+    });
+    stopped
+        .recv_timeout(Duration::from_secs(5))
+        .expect("The system didn't stop.");
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+}
@@ -0,0 +1,92 @@
+use bastion::prelude::*;
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_system_health() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_system_health() {
+        super::run()
+    }
+}
+
+// Polls the health of the system until the condition holds.
+fn wait_for(condition: impl Fn(&SystemHealth) -> bool) -> SystemHealth {
+    let mut health = Bastion::health();
+    for _ in 0..200 {
+        if condition(&health) {
+            break;
+        }
+
+        thread::sleep(Duration::from_millis(10));
+        health = Bastion::health();
+    }
+
+    health
+}
+
+fn run() {
+    Bastion::init();
+    Bastion::start();
+
+    Bastion::children(|children| {
+        children.with_name("workers").with_redundancy(2).with_exec(
+            |ctx: BastionContext| async move {
+                loop {
+                    ctx.recv().await?;
+                }
+            },
+        )
+    })
+    .expect("Couldn't create the children group.");
+
+    let health = wait_for(|health| {
+        health
+            .groups()
+            .iter()
+            .any(|group| group.name() == "workers" && group.elements() == 2)
+    });
+    let workers = health
+        .groups()
+        .iter()
+        .find(|group| group.name() == "workers")
+        .expect("The group isn't running.");
+    assert_eq!(workers.redundancy(), 2);
+    assert!(!workers.report().is_failing());
+    assert!(health.is_live());
+
+    // The element fails each time it starts.
+    Bastion::children(|children| {
+        children
+            .with_name("flaky")
+            .with_exec(|_ctx: BastionContext| async move {
+                bastion::executor::sleep(Duration::from_millis(10)).await;
+                Err(())
+            })
+    })
+    .expect("Couldn't create the children group.");
+
+    let health = wait_for(|health| {
+        health
+            .crash_looping()
+            .iter()
+            .any(|group| group.name() == "flaky")
+    });
+    assert!(health
+        .crash_looping()
+        .iter()
+        .any(|group| group.name() == "flaky"));
+    assert!(!health.is_live());
+    assert!(!health.is_ready());
+
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
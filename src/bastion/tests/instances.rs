@@ -0,0 +1,105 @@
+use bastion::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_instances() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_instances() {
+        super::run()
+    }
+}
+
+fn spawn_counter(system: &BastionSystem, received: Arc<AtomicUsize>) {
+    system
+        .children(|children| {
+            children
+                .with_name("counter")
+                .with_distributor(Distributor::named("counter"))
+                .with_exec(move |ctx: BastionContext| {
+                    let received = received.clone();
+                    async move {
+                        loop {
+                            ctx.recv().await?;
+                            received.fetch_add(1, Ordering::SeqCst);
+                        }
+                    }
+                })
+        })
+        .expect("Couldn't create the children group.");
+}
+
+// Tells a message to the counter of the system, once it registered
+// itself as a recipient.
+fn tell_counter(system: &BastionSystem) {
+    for _ in 0..100 {
+        if system
+            .enter(|| Distributor::named("counter").tell_one("count"))
+            .is_ok()
+        {
+            return;
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    panic!("The counter didn't register itself.");
+}
+
+fn wait_for(received: &AtomicUsize, count: usize) {
+    for _ in 0..100 {
+        if received.load(Ordering::SeqCst) == count {
+            return;
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    assert_eq!(received.load(Ordering::SeqCst), count);
+}
+
+fn run() {
+    let first = BastionSystem::new(Config::new());
+    let second = BastionSystem::new(Config::new());
+
+    let first_received = Arc::new(AtomicUsize::new(0));
+    let second_received = Arc::new(AtomicUsize::new(0));
+    spawn_counter(&first, first_received.clone());
+    spawn_counter(&second, second_received.clone());
+
+    first.start();
+    second.start();
+
+    tell_counter(&first);
+    tell_counter(&first);
+    tell_counter(&second);
+    wait_for(&first_received, 2);
+    wait_for(&second_received, 1);
+
+    // Both systems have their own "counter" children group and
+    // distributor.
+    assert!(first.children_ref("counter").is_some());
+    assert!(second.children_ref("counter").is_some());
+    assert!(Bastion::children_ref("counter").is_none());
+
+    // Stopping a system doesn't affect the other one.
+    first.stop();
+    assert!(first.block_until_stopped_timeout(Duration::from_secs(5)));
+    assert!(second.is_running());
+
+    tell_counter(&second);
+    wait_for(&second_received, 2);
+
+    drop(second);
+    assert_eq!(first_received.load(Ordering::SeqCst), 2);
+}
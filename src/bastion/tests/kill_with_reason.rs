@@ -0,0 +1,62 @@
+use bastion::prelude::*;
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_kill_reason_reaches_the_supervisor() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_kill_reason_reaches_the_supervisor() {
+        super::run()
+    }
+}
+
+fn run() {
+    Bastion::init();
+    Bastion::start();
+
+    let (sender, received) = mpsc::channel();
+    let sender = Mutex::new(sender);
+    let supervisor = Bastion::supervisor(move |sp| {
+        sp.with_on_child_failure(move |child_ref, failure| {
+            let failure = (
+                child_ref.id().clone(),
+                failure.reason(),
+                failure.message().map(ToString::to_string),
+            );
+            sender.lock().unwrap().send(failure).ok();
+        })
+    })
+    .expect("Couldn't create the supervisor.");
+    let children = supervisor
+        .children(|children| {
+            children.with_exec(|ctx: BastionContext| async move {
+                loop {
+                    ctx.recv().await?;
+                }
+            })
+        })
+        .expect("Couldn't create the children group.");
+
+    let child_ref = children.elems()[0].clone();
+    child_ref
+        .kill_with_reason("leaking file descriptors")
+        .expect("Couldn't send the message.");
+
+    let (id, reason, message) = received
+        .recv_timeout(Duration::from_secs(5))
+        .expect("The supervisor wasn't told about the failure.");
+    assert_eq!(&id, child_ref.id());
+    assert!(matches!(reason, FailureReason::Child(FailureKind::Killed)));
+    assert_eq!(message.as_deref(), Some("leaking file descriptors"));
+
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
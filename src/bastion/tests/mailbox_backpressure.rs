@@ -0,0 +1,91 @@
+use bastion::prelude::*;
+use futures_timer::Delay;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_backpressure_keeps_mailboxes_within_capacity() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_backpressure_keeps_mailboxes_within_capacity() {
+        super::run()
+    }
+}
+
+fn run() {
+    Bastion::init();
+    Bastion::start();
+
+    // The element doesn't receive any message until it is released.
+    let released = Arc::new(AtomicBool::new(false));
+    let (sender, received) = mpsc::channel();
+    let sender = Mutex::new(sender);
+    let waiting = released.clone();
+    let children = Bastion::children(move |children| {
+        children
+            .with_mailbox_capacity(2)
+            .with_overflow_policy(OverflowPolicy::Backpressure)
+            .with_exec(move |ctx: BastionContext| {
+                let waiting = waiting.clone();
+                let sender = sender.lock().unwrap().clone();
+                async move {
+                    while !waiting.load(Ordering::SeqCst) {
+                        Delay::new(Duration::from_millis(10)).await;
+                    }
+
+                    loop {
+                        msg! { ctx.recv().await?,
+                            i: usize => {
+                                sender.send(i).ok();
+                            };
+                            _: _ => ();
+                        }
+                    }
+                }
+            })
+    })
+    .expect("Couldn't create the children group.");
+
+    // Only the messages the mailbox has room for are accepted, even
+    // before they are enqueued.
+    let child = children.elems()[0].clone();
+    let accepted: Vec<_> = (0..10_usize)
+        .filter(|i| child.tell_anonymously(*i).is_ok())
+        .collect();
+    assert_eq!(accepted, vec![0, 1]);
+    assert!(matches!(
+        child.try_tell_anonymously(10_usize),
+        Err(SendError::Full(_))
+    ));
+
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(child.mailbox_len(), 2);
+
+    released.store(true, Ordering::SeqCst);
+    for expected in 0..2_usize {
+        let i = received
+            .recv_timeout(Duration::from_secs(5))
+            .expect("The element didn't receive the message.");
+        assert_eq!(i, expected);
+    }
+
+    // There is room again once the messages were received.
+    run!(child.wait_for_capacity());
+    child
+        .tell_anonymously(2_usize)
+        .expect("Couldn't send the message.");
+    assert_eq!(received.recv_timeout(Duration::from_secs(5)).ok(), Some(2));
+    assert!(child.mailbox_len() <= 2);
+
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
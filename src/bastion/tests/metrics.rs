@@ -0,0 +1,69 @@
+use bastion::prelude::*;
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_metrics() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_metrics() {
+        super::run()
+    }
+}
+
+fn run() {
+    Bastion::init();
+    Bastion::start();
+
+    let children = Bastion::children(|children| {
+        children.with_name("workers").with_redundancy(2).with_exec(
+            |ctx: BastionContext| async move {
+                loop {
+                    ctx.recv().await?;
+                }
+            },
+        )
+    })
+    .expect("Couldn't create the children group.");
+
+    for _ in 0..10 {
+        children
+            .broadcast("work")
+            .expect("Couldn't send the message.");
+    }
+
+    let mut metrics = Bastion::metrics();
+    for _ in 0..100 {
+        let received = metrics
+            .groups()
+            .iter()
+            .any(|group| group.name() == "workers" && group.elements() == 2);
+        if received && metrics.messages() >= 20 {
+            break;
+        }
+
+        thread::sleep(Duration::from_millis(10));
+        metrics = Bastion::metrics();
+    }
+
+    let workers = metrics
+        .groups()
+        .iter()
+        .find(|group| group.name() == "workers")
+        .expect("The group isn't running.");
+    assert_eq!(workers.elements(), 2);
+    assert!(metrics.children() >= 2);
+    assert!(metrics.messages() >= 20);
+    assert!(metrics.tasks_live() > 0);
+    assert!(metrics.tasks_spawned() >= metrics.tasks_live());
+
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
@@ -0,0 +1,70 @@
+use bastion::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_on_child_failure_receives_panics() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_on_child_failure_receives_panics() {
+        super::run()
+    }
+}
+
+fn run() {
+    Bastion::init();
+    Bastion::start();
+
+    let (sender, received) = mpsc::channel();
+    let sender = Mutex::new(sender);
+    let runs = Arc::new(AtomicUsize::new(0));
+    Bastion::supervisor(move |sp| {
+        sp.with_on_child_failure(move |_, failure| {
+            let failure = (
+                failure.reason(),
+                failure.message().map(ToString::to_string),
+                failure.restart_count(),
+            );
+            sender.lock().unwrap().send(failure).ok();
+        })
+        .children(move |children| {
+            // The element panics the first two times it runs.
+            children.with_exec(move |ctx: BastionContext| {
+                let run = runs.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if run < 2 {
+                        panic!("run {} panicked", run);
+                    }
+
+                    loop {
+                        ctx.recv().await?;
+                    }
+                }
+            })
+        })
+    })
+    .expect("Couldn't create the supervisor.");
+
+    for run in 0..2_usize {
+        let (reason, message, restart_count) = received
+            .recv_timeout(Duration::from_secs(5))
+            .expect("The callback wasn't called.");
+        assert!(matches!(
+            reason,
+            FailureReason::Child(FailureKind::Panicked)
+        ));
+        assert_eq!(message, Some(format!("run {} panicked", run)));
+        assert_eq!(restart_count, run);
+    }
+
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
@@ -0,0 +1,85 @@
+#![cfg(feature = "remote")]
+use bastion::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_poison_messages_are_dead_letters() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_poison_messages_are_dead_letters() {
+        super::run()
+    }
+}
+
+fn run() {
+    Bastion::init();
+    Bastion::start();
+
+    // The element panics while handling the first message it
+    // receives.
+    let runs = Arc::new(AtomicUsize::new(0));
+    let counted = runs.clone();
+    let children = Bastion::children(move |children| {
+        children.with_exec(move |ctx: BastionContext| {
+            let run = counted.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if run == 0 {
+                    ctx.recv().await?;
+                    panic!("poisoned");
+                }
+
+                loop {
+                    ctx.recv().await?;
+                }
+            }
+        })
+    })
+    .expect("Couldn't create the children group.");
+
+    let child = children.elems()[0].clone();
+    let poison = WireMessage::pack(&"poison".to_string()).unwrap();
+    child
+        .tell_anonymously(poison.clone())
+        .expect("Couldn't send the message.");
+
+    // The message is kept in the dead letters before the element is
+    // restarted.
+    let mut dead_letters = Vec::new();
+    for _ in 0..500 {
+        dead_letters = Bastion::dead_letters().drain();
+        if !dead_letters.is_empty() {
+            break;
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(dead_letters.len(), 1);
+    assert_eq!(
+        dead_letters[0].destination(),
+        &Destination::Child(child.id().clone())
+    );
+    assert_eq!(dead_letters[0].message(), &poison);
+    assert!(dead_letters[0].node().is_none());
+
+    for _ in 0..500 {
+        if runs.load(Ordering::SeqCst) == 2 {
+            break;
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
@@ -0,0 +1,78 @@
+#![cfg(feature = "prometheus")]
+use bastion::prelude::*;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_serve_metrics() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_serve_metrics() {
+        super::run()
+    }
+}
+
+fn scrape(addr: &str, path: &str) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\n\r\n", path, addr).unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+fn run() {
+    Bastion::init();
+
+    // Finds a free port.
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let addr = format!("127.0.0.1:{}", port);
+    Bastion::serve_metrics(&*addr).expect("Couldn't serve the metrics.");
+    assert!(Bastion::serve_metrics(&*addr).is_err());
+
+    let echo = Bastion::children(|children| {
+        children
+            .with_name("echo")
+            .with_exec(|ctx: BastionContext| async move {
+                loop {
+                    MessageHandler::new(ctx.recv().await?).on_question(|msg: &str, sender| {
+                        sender.reply(msg).unwrap();
+                    });
+                }
+            })
+    })
+    .expect("Couldn't create the children group.");
+
+    Bastion::start();
+
+    let answer = echo.elems()[0].ask_anonymously("ping").unwrap();
+    run!(answer).unwrap();
+
+    let response = scrape(&addr, "/metrics");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.contains("# TYPE bastion_ask_latency_seconds histogram\n"));
+    assert!(response.contains("bastion_ask_latency_seconds_bucket{le=\"+Inf\"} "));
+    assert!(response.contains("bastion_group_elements{group=\"echo\","));
+    assert!(response.contains("bastion_group_restarts_total{group=\"bastion-prometheus\","));
+
+    let response = scrape(&addr, "/missing");
+    assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "remote")]
+use bastion::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::time::Duration;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_remote_distributor() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_remote_distributor() {
+        super::run()
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Square(u64);
+
+fn run() {
+    Bastion::init();
+
+    // Finds a free port.
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let addr = format!("127.0.0.1:{}", port);
+    Bastion::listen(&*addr).expect("Couldn't listen.");
+
+    let (sender, told) = mpsc::channel();
+    Bastion::children(|children| {
+        children
+            .with_distributor(Distributor::named("squares"))
+            .with_exec(move |ctx: BastionContext| {
+                let sender = sender.clone();
+                async move {
+                    loop {
+                        MessageHandler::new(ctx.recv().await?)
+                            .on_wire(|Square(n), _| {
+                                sender.send(n).unwrap();
+                            })
+                            .on_question(|question: WireMessage, answer| {
+                                let Square(n) = question.unpack().unwrap().unwrap();
+                                answer.reply(WireMessage::pack(&(n * n)).unwrap()).unwrap();
+                            })
+                            .on_fallback(|_, _| ());
+                    }
+                }
+            })
+    })
+    .expect("Couldn't create the children group.");
+
+    Bastion::start();
+
+    // The node connects to itself.
+    Bastion::connect("self", &*addr).expect("Couldn't connect.");
+    let squares = Distributor::remote("self", "squares");
+
+    squares
+        .tell_one(Square(3))
+        .expect("Couldn't send the message.");
+    assert_eq!(told.recv_timeout(Duration::from_secs(5)), Ok(3));
+
+    let answer = squares
+        .ask_one(Square(4))
+        .expect("Couldn't ask the question.");
+    let answer = run!(answer).expect("Couldn't receive the answer.");
+    let square: u64 = answer
+        .msg
+        .downcast::<WireMessage>()
+        .unwrap()
+        .unpack()
+        .unwrap()
+        .unwrap();
+    assert_eq!(square, 16);
+
+    assert!(matches!(
+        Distributor::remote("unknown", "squares").tell_one(Square(1)),
+        Err(SendError::UnknownNode(_))
+    ));
+
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
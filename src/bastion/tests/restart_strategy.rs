@@ -1,4 +1,7 @@
-use bastion::supervisor::{ActorRestartStrategy, RestartPolicy, RestartStrategy};
+use bastion::supervisor::{
+    ActorRestartStrategy, ChildFailure, RestartLimit, RestartPolicy, RestartStrategy,
+    SupervisionDecider, SupervisionDecision, SupervisionStrategy,
+};
 use std::time::Duration;
 
 #[test]
@@ -101,3 +104,101 @@ fn calculate_exp_strategy_with_multiplier_non_zero() {
         Some(Duration::from_millis(100 + 99 * 5 * 100))
     );
 }
+
+#[test]
+fn calculate_compound_strategy() {
+    let strategy = ActorRestartStrategy::CompoundBackOff {
+        timeout: Duration::from_millis(100),
+        multiplier: 2.0,
+    };
+
+    assert_eq!(strategy.calculate(0), Some(Duration::from_millis(100)));
+    assert_eq!(strategy.calculate(1), Some(Duration::from_millis(200)));
+    assert_eq!(strategy.calculate(2), Some(Duration::from_millis(400)));
+    assert_eq!(strategy.calculate(3), Some(Duration::from_millis(800)));
+}
+
+#[test]
+fn calculate_compound_strategy_saturates() {
+    let strategy = ActorRestartStrategy::CompoundBackOff {
+        timeout: Duration::from_secs(1),
+        multiplier: 10.0,
+    };
+
+    assert_eq!(
+        strategy.calculate(1000),
+        Some(Duration::from_secs(u32::MAX.into()))
+    );
+}
+
+#[test]
+fn restart_limit_values() {
+    let limit = RestartLimit::new(5, Duration::from_secs(10));
+
+    assert_eq!(limit.max_restarts(), 5);
+    assert_eq!(limit.within(), Duration::from_secs(10));
+}
+
+#[test]
+fn exponential_strategy_is_capped() {
+    let restart_strategy = RestartStrategy::exponential(Duration::from_millis(100), 2.0)
+        .with_max_delay(Duration::from_millis(300));
+
+    assert_eq!(restart_strategy.restart_policy(), RestartPolicy::Always);
+    assert_eq!(
+        restart_strategy.max_delay(),
+        Some(Duration::from_millis(300))
+    );
+    assert_eq!(
+        restart_strategy.calculate(0),
+        Some(Duration::from_millis(100))
+    );
+    assert_eq!(
+        restart_strategy.calculate(1),
+        Some(Duration::from_millis(200))
+    );
+    assert_eq!(
+        restart_strategy.calculate(2),
+        Some(Duration::from_millis(300))
+    );
+    assert_eq!(
+        restart_strategy.calculate(10),
+        Some(Duration::from_millis(300))
+    );
+}
+
+#[test]
+fn jitter_keeps_at_least_half_of_the_delay() {
+    let restart_strategy =
+        RestartStrategy::exponential(Duration::from_millis(100), 2.0).with_jitter();
+    assert!(restart_strategy.has_jitter());
+
+    for _ in 0..100 {
+        let delay = restart_strategy.calculate(1).unwrap();
+        assert!(delay >= Duration::from_millis(100));
+        assert!(delay <= Duration::from_millis(200));
+    }
+}
+
+#[test]
+fn immediate_strategy_has_no_delay_with_jitter() {
+    let restart_strategy = RestartStrategy::default().with_jitter();
+
+    assert_eq!(restart_strategy.calculate(3), None);
+}
+
+#[derive(Debug)]
+struct AlwaysStop;
+
+impl SupervisionDecider for AlwaysStop {
+    fn decide(&self, _: &ChildFailure) -> SupervisionDecision {
+        SupervisionDecision::Stop
+    }
+}
+
+#[test]
+fn decider_converts_into_custom_strategy() {
+    let strategy: SupervisionStrategy = AlwaysStop.into();
+
+    assert!(matches!(strategy, SupervisionStrategy::Custom(_)));
+}
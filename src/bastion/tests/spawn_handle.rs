@@ -0,0 +1,57 @@
+use bastion::prelude::*;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_spawn_handle() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_spawn_handle() {
+        super::run()
+    }
+}
+
+fn run() {
+    Bastion::init();
+    Bastion::start();
+
+    let panics = Arc::new(Mutex::new(Vec::new()));
+    let hook_panics = panics.clone();
+    Bastion::on_task_panic(move |message: Option<&str>| {
+        hook_panics
+            .lock()
+            .unwrap()
+            .push(message.map(str::to_string));
+    });
+
+    let handle = spawn_handle!(async { 40 + 2 });
+    assert_eq!(run!(handle), Ok(42));
+
+    let handle = spawn_handle! {
+        panic!("task failed");
+    };
+    assert_eq!(
+        run!(handle),
+        Err::<(), _>(TaskError::Panicked(Some("task failed".to_string())))
+    );
+    assert_eq!(
+        *panics.lock().unwrap(),
+        vec![Some("task failed".to_string())]
+    );
+
+    let handle = spawn_handle! {
+        bastion::executor::sleep(Duration::from_secs(60)).await;
+    };
+    handle.cancel();
+    assert_eq!(run!(handle), Err(TaskError::Cancelled));
+
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
@@ -0,0 +1,65 @@
+use bastion::prelude::*;
+use futures_timer::Delay;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_supervisor_stops_in_reverse_start_order() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_supervisor_stops_in_reverse_start_order() {
+        super::run()
+    }
+}
+
+// Sets up a children group which element takes `delay` to finish
+// its in-flight work once stopped, then records its name.
+fn group(
+    children: Children,
+    name: &'static str,
+    delay: Duration,
+    stopped: Arc<Mutex<Vec<&'static str>>>,
+) -> Children {
+    children
+        .with_stop_timeout(Duration::from_secs(5))
+        .with_exec(move |ctx: BastionContext| {
+            let stopped = stopped.clone();
+            async move {
+                while ctx.recv().await.is_ok() {}
+
+                Delay::new(delay).await;
+                stopped.lock().unwrap().push(name);
+                Ok(())
+            }
+        })
+}
+
+fn run() {
+    Bastion::init();
+    Bastion::start();
+
+    let stopped = Arc::new(Mutex::new(Vec::new()));
+    let first = stopped.clone();
+    let second = stopped.clone();
+    Bastion::supervisor(move |sp| {
+        sp.with_stop_order(StopOrder::ReverseStartOrder)
+            .children(move |children| group(children, "first", Duration::from_millis(0), first))
+            // Stopped first, even though it's slower to stop.
+            .children(move |children| group(children, "second", Duration::from_millis(200), second))
+    })
+    .expect("Couldn't create the supervisor.");
+
+    thread::sleep(Duration::from_millis(100));
+    Bastion::stop();
+    Bastion::block_until_stopped();
+
+    assert_eq!(*stopped.lock().unwrap(), vec!["second", "first"]);
+}
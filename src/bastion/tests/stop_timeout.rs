@@ -0,0 +1,59 @@
+use bastion::prelude::*;
+use futures_timer::Delay;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_element_ignoring_stop_is_killed() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_element_ignoring_stop_is_killed() {
+        super::run()
+    }
+}
+
+fn run() {
+    Bastion::init();
+    Bastion::start();
+
+    let stopping = Arc::new(AtomicBool::new(false));
+    let seen = stopping.clone();
+    Bastion::children(move |children| {
+        children
+            .with_stop_timeout(Duration::from_millis(200))
+            .with_exec(move |ctx: BastionContext| {
+                let seen = seen.clone();
+                async move {
+                    // The element never stops by itself.
+                    loop {
+                        if ctx.is_stopping() {
+                            seen.store(true, Ordering::SeqCst);
+                        }
+                        Delay::new(Duration::from_millis(10)).await;
+                    }
+                }
+            })
+    })
+    .expect("Couldn't create the children group.");
+
+    thread::sleep(Duration::from_millis(100));
+    let started_stopping = Instant::now();
+    Bastion::stop();
+    Bastion::block_until_stopped();
+
+    // The element was asked to stop, then killed once its stop
+    // timeout elapsed.
+    assert!(stopping.load(Ordering::SeqCst));
+    let elapsed = started_stopping.elapsed();
+    assert!(elapsed >= Duration::from_millis(200));
+    assert!(elapsed < Duration::from_secs(5));
+}
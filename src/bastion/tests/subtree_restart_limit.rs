@@ -0,0 +1,91 @@
+use bastion::prelude::*;
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_supervisors_restarted_too_often_are_stopped() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_supervisors_restarted_too_often_are_stopped() {
+        super::run()
+    }
+}
+
+#[derive(Debug)]
+// Restarts the failed elements, sending the failures it handles.
+struct Recorder(Mutex<mpsc::Sender<ChildFailure>>);
+
+impl SupervisionDecider for Recorder {
+    fn decide(&self, failure: &ChildFailure) -> SupervisionDecision {
+        self.0.lock().unwrap().send(failure.clone()).ok();
+        SupervisionDecision::Restart
+    }
+}
+
+fn run() {
+    Bastion::init();
+    Bastion::start();
+
+    let (sender, received) = mpsc::channel();
+    let outer = Bastion::supervisor(move |sp| sp.with_strategy(Recorder(Mutex::new(sender))))
+        .expect("Couldn't create the supervisor.");
+
+    // The element always fails, so the inner supervisor escalates
+    // every other failure.
+    let inner = outer
+        .supervisor(|sp| {
+            sp.with_restart_limit(RestartLimit::new(1, Duration::from_secs(60)))
+                .children(|children| children.with_exec(|_: BastionContext| async { Err(()) }))
+        })
+        .expect("Couldn't create the inner supervisor.");
+
+    // The outer supervisor restarts the subtree of the inner one
+    // three times, after which the inner supervisor gives up and
+    // the outer supervisor handles its failure one last time.
+    let mut escalated = 0;
+    let last = loop {
+        let failure = received
+            .recv_timeout(Duration::from_secs(5))
+            .expect("The inner supervisor didn't fail.");
+        assert_eq!(failure.id(), inner.id());
+        assert!(matches!(failure.reason(), FailureReason::Escalated));
+        if failure.cause().is_none() {
+            break failure;
+        }
+
+        escalated += 1;
+    };
+    assert_eq!(escalated, 4);
+    assert_eq!(last.restart_count(), 3);
+
+    // The inner supervisor and its children group are stopped.
+    let mut stopped = false;
+    for _ in 0..500 {
+        let tree = Bastion::tree();
+        stopped = tree
+            .supervisors()
+            .into_iter()
+            .find(|supervisor| supervisor.id() == inner.id())
+            .map_or(true, |supervisor| {
+                supervisor.state() == ElementState::Stopped
+            });
+        if stopped {
+            break;
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(stopped);
+    assert!(received.recv_timeout(Duration::from_millis(500)).is_err());
+
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
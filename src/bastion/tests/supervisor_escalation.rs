@@ -0,0 +1,89 @@
+use bastion::prelude::*;
+use futures::StreamExt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_escalated_subtree_is_restarted() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_escalated_subtree_is_restarted() {
+        super::run()
+    }
+}
+
+fn run() {
+    Bastion::init();
+    Bastion::start();
+
+    let mut events = Bastion::events();
+    let (sender, received) = mpsc::channel();
+    thread::spawn(move || {
+        futures::executor::block_on(async move {
+            while let Some(event) = events.next().await {
+                if sender.send(event).is_err() {
+                    break;
+                }
+            }
+        })
+    });
+
+    let outer = Bastion::supervisor(|sp| sp.with_strategy(SupervisionStrategy::OneForOne))
+        .expect("Couldn't create the supervisor.");
+
+    // The element fails the first two times it runs, which exceeds
+    // the restart limit of the inner supervisor.
+    let runs = Arc::new(AtomicUsize::new(0));
+    let counted = runs.clone();
+    let inner = outer
+        .supervisor(move |sp| {
+            sp.with_restart_limit(RestartLimit::new(1, Duration::from_secs(60)))
+                .children(move |children| {
+                    children.with_exec(move |ctx: BastionContext| {
+                        let counted = counted.clone();
+                        async move {
+                            if counted.fetch_add(1, Ordering::SeqCst) < 2 {
+                                return Err(());
+                            }
+
+                            loop {
+                                ctx.recv().await?;
+                            }
+                        }
+                    })
+                })
+        })
+        .expect("Couldn't create the inner supervisor.");
+
+    loop {
+        let event = received
+            .recv_timeout(Duration::from_secs(5))
+            .expect("The inner supervisor didn't escalate.");
+        if let SystemEvent::SupervisorEscalated { supervisor, .. } = event {
+            assert_eq!(&supervisor, inner.id());
+            break;
+        }
+    }
+
+    // The outer supervisor restarts the subtree of the inner one.
+    for _ in 0..500 {
+        if runs.load(Ordering::SeqCst) >= 3 {
+            break;
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
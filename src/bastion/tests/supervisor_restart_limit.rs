@@ -0,0 +1,94 @@
+use bastion::prelude::*;
+use futures::StreamExt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_restart_limit_is_fatal_for_top_level_supervisors() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_restart_limit_is_fatal_for_top_level_supervisors() {
+        super::run()
+    }
+}
+
+fn run() {
+    Bastion::init();
+
+    let (reports, reported) = mpsc::channel();
+    Bastion::on_fatal(move |report: &FatalReport| {
+        reports.send(report.clone()).ok();
+    });
+
+    Bastion::start();
+
+    let mut events = Bastion::events();
+    let (sender, received) = mpsc::channel();
+    thread::spawn(move || {
+        futures::executor::block_on(async move {
+            while let Some(event) = events.next().await {
+                if sender.send(event).is_err() {
+                    break;
+                }
+            }
+        })
+    });
+
+    // The element always fails, so the supervisor gives up after
+    // restarting it twice.
+    let runs = Arc::new(AtomicUsize::new(0));
+    let counted = runs.clone();
+    let supervisor = Bastion::supervisor(move |sp| {
+        sp.with_restart_limit(RestartLimit::new(2, Duration::from_secs(60)))
+            .children(move |children| {
+                children.with_exec(move |_: BastionContext| {
+                    let counted = counted.clone();
+                    async move {
+                        counted.fetch_add(1, Ordering::SeqCst);
+                        Err(())
+                    }
+                })
+            })
+    })
+    .expect("Couldn't create the supervisor.");
+
+    loop {
+        let event = received
+            .recv_timeout(Duration::from_secs(5))
+            .expect("The supervisor didn't escalate.");
+        if let SystemEvent::SupervisorEscalated {
+            supervisor: escalated,
+            failure,
+        } = event
+        {
+            assert_eq!(&escalated, supervisor.id());
+            assert!(matches!(failure.reason(), FailureReason::Escalated));
+            break;
+        }
+    }
+
+    // There is no parent supervisor to escalate to, so the fatal
+    // handler is called and the system stops.
+    let report = reported
+        .recv_timeout(Duration::from_secs(5))
+        .expect("The fatal handler wasn't called.");
+    assert_eq!(report.supervisor_id(), supervisor.id());
+    assert_eq!(report.chain().len(), 2);
+    assert!(matches!(
+        report.root_cause().reason(),
+        FailureReason::Child(_)
+    ));
+    assert_eq!(report.root_cause().restart_count(), 2);
+
+    Bastion::block_until_stopped();
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+}
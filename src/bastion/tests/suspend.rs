@@ -0,0 +1,81 @@
+use bastion::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_suspended_children_keep_their_messages() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_suspended_children_keep_their_messages() {
+        super::run()
+    }
+}
+
+fn run() {
+    Bastion::init();
+    Bastion::start();
+
+    let supervisor = Bastion::supervisor(|sp| sp).expect("Couldn't create the supervisor.");
+
+    // The element handles three messages, and would be considered
+    // failed if it took more than a second of running to do so.
+    let runs = Arc::new(AtomicUsize::new(0));
+    let handled = Arc::new(AtomicUsize::new(0));
+    let (counted_runs, counted_handled) = (runs.clone(), handled.clone());
+    let children = supervisor
+        .children(move |children| {
+            children
+                .with_exec_timeout(Duration::from_secs(1))
+                .with_exec(move |ctx: BastionContext| {
+                    counted_runs.fetch_add(1, Ordering::SeqCst);
+                    let counted_handled = counted_handled.clone();
+                    async move {
+                        for _ in 0..3 {
+                            ctx.recv().await?;
+                            counted_handled.fetch_add(1, Ordering::SeqCst);
+                        }
+
+                        Ok(())
+                    }
+                })
+        })
+        .expect("Couldn't create the children group.");
+
+    supervisor
+        .suspend()
+        .expect("Couldn't suspend the supervisor.");
+    thread::sleep(Duration::from_millis(100));
+    for i in 0..3_usize {
+        children.broadcast(i).expect("Couldn't send the message.");
+    }
+
+    // The messages stay in the mailbox, and the exec timeout doesn't
+    // elapse while the element is suspended.
+    thread::sleep(Duration::from_millis(1500));
+    assert_eq!(handled.load(Ordering::SeqCst), 0);
+
+    supervisor
+        .resume()
+        .expect("Couldn't resume the supervisor.");
+    for _ in 0..100 {
+        if handled.load(Ordering::SeqCst) == 3 {
+            break;
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(handled.load(Ordering::SeqCst), 3);
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
@@ -0,0 +1,99 @@
+#![cfg(feature = "tracing")]
+use bastion::prelude::*;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tracing::span::{Attributes, Id};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_message_spans() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_message_spans() {
+        super::run()
+    }
+}
+
+#[derive(Clone, Default)]
+// Records the name of the parent of each span named "message".
+struct Parents(Arc<Mutex<Vec<Option<String>>>>);
+
+impl<S> Layer<S> for Parents
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn new_span(&self, attrs: &Attributes, id: &Id, ctx: Context<S>) {
+        if attrs.metadata().name() != "message" {
+            return;
+        }
+
+        let parent = ctx
+            .span(id)
+            .and_then(|span| span.parent().map(|parent| parent.name().to_string()));
+        self.0.lock().unwrap().push(parent);
+    }
+}
+
+fn run() {
+    let parents = Parents::default();
+    let subscriber = tracing_subscriber::registry().with(parents.clone());
+    tracing::subscriber::set_global_default(subscriber).unwrap();
+
+    Bastion::init();
+    Bastion::start();
+
+    let second = Bastion::children(|children| {
+        children.with_exec(|ctx: BastionContext| async move {
+            loop {
+                ctx.recv().await?;
+            }
+        })
+    })
+    .expect("Couldn't create the children group.");
+
+    let first = Bastion::children(move |children| {
+        let second = second.clone();
+        children.with_exec(move |ctx: BastionContext| {
+            let second = second.clone();
+            async move {
+                loop {
+                    ctx.recv().await?;
+                    second.broadcast("pong").unwrap();
+                }
+            }
+        })
+    })
+    .expect("Couldn't create the children group.");
+
+    tracing::info_span!("request").in_scope(|| {
+        first.broadcast("ping").expect("Couldn't send the message.");
+    });
+
+    for _ in 0..100 {
+        if parents.0.lock().unwrap().len() >= 2 {
+            break;
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    // The span of the handling of "ping" is a child of the span it was
+    // sent in, and the span of "pong" a child of the one of "ping".
+    let parents = parents.0.lock().unwrap().clone();
+    assert_eq!(parents.len(), 2);
+    assert_eq!(parents[0].as_deref(), Some("request"));
+    assert_eq!(parents[1].as_deref(), Some("message"));
+
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}
@@ -0,0 +1,64 @@
+use bastion::prelude::*;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[cfg(feature = "tokio-runtime")]
+mod tokio_tests {
+    #[tokio::test]
+    async fn test_message_ttl_follows_virtual_time() {
+        super::run()
+    }
+}
+
+#[cfg(not(feature = "tokio-runtime"))]
+mod not_tokio_tests {
+    #[test]
+    fn test_message_ttl_follows_virtual_time() {
+        super::run()
+    }
+}
+
+fn run() {
+    let runtime = Bastion::init_test();
+    Bastion::start();
+
+    // The element only receives its messages once a virtual minute
+    // elapsed.
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let handled = received.clone();
+    Bastion::children(move |children| {
+        children
+            .with_distributor(Distributor::named("virtual-clock"))
+            .with_exec(move |ctx: BastionContext| {
+                let handled = handled.clone();
+                async move {
+                    bastion::executor::sleep(Duration::from_secs(60)).await;
+                    loop {
+                        msg! { ctx.recv().await?,
+                            msg: &'static str => {
+                                handled.lock().unwrap().push(msg);
+                            };
+                            _: _ => ();
+                        }
+                    }
+                }
+            })
+    })
+    .expect("Couldn't create the children group.");
+    runtime.run_until_idle();
+
+    let distributor = Distributor::named("virtual-clock");
+    distributor
+        .tell_one_with_ttl("expired", Duration::from_secs(30))
+        .expect("Couldn't send the message.");
+    distributor
+        .tell_one_with_ttl("kept", Duration::from_secs(120))
+        .expect("Couldn't send the message.");
+
+    // The first message expires while the element is sleeping.
+    runtime.advance(Duration::from_secs(60));
+    assert_eq!(*received.lock().unwrap(), vec!["kept"]);
+
+    Bastion::stop();
+    Bastion::block_until_stopped();
+}